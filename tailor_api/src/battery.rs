@@ -0,0 +1,191 @@
+/// The steps of a battery calibration. Tailord charges the battery to full,
+/// discharges it to a low charge and charges it to full again,
+/// so the firmware can learn the actual capacity.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum CalibrationPhase {
+    /// No calibration was started since tailord started.
+    #[default]
+    Idle,
+    /// Charging to full, the charger has to be plugged in.
+    Charging,
+    /// Discharging to the target charge, the charger has to be unplugged.
+    Discharging,
+    /// Charging to full again, the charger has to be plugged in.
+    Recharging,
+    Finished,
+    Cancelled,
+    /// The battery couldn't be read.
+    Failed,
+}
+
+impl CalibrationPhase {
+    pub fn is_running(self) -> bool {
+        matches!(self, Self::Charging | Self::Discharging | Self::Recharging)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct CalibrationStatus {
+    pub phase: CalibrationPhase,
+    /// The charge of the battery in percent.
+    pub capacity: u8,
+    pub plugged_in: bool,
+    /// The charge in percent at which the discharge phase ends.
+    pub discharge_to: u8,
+}
+
+impl CalibrationStatus {
+    /// The progress of the whole calibration from `0.0` to `1.0`.
+    /// Each of the three phases takes a third.
+    pub fn progress(&self) -> f64 {
+        let capacity = f64::from(self.capacity) / 100.0;
+        let discharge_range = (100 - self.discharge_to.min(99)) as f64 / 100.0;
+        let phase_progress = match self.phase {
+            CalibrationPhase::Idle | CalibrationPhase::Cancelled | CalibrationPhase::Failed => {
+                return 0.0
+            }
+            CalibrationPhase::Finished => return 1.0,
+            CalibrationPhase::Charging => capacity,
+            CalibrationPhase::Discharging => 1.0 + ((1.0 - capacity) / discharge_range).min(1.0),
+            CalibrationPhase::Recharging => 2.0 + capacity,
+        };
+        phase_progress / 3.0
+    }
+
+    /// Whether the user has to plug in or unplug the charger to continue.
+    pub fn needs_charger_change(&self) -> bool {
+        match self.phase {
+            CalibrationPhase::Charging | CalibrationPhase::Recharging => !self.plugged_in,
+            CalibrationPhase::Discharging => self.plugged_in,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum BatteryStatus {
+    Charging,
+    Discharging,
+    /// Plugged in, but the firmware doesn't charge, e.g. because of a charge limit.
+    NotCharging,
+    Full,
+    #[default]
+    Unknown,
+}
+
+impl BatteryStatus {
+    /// Whether the charger is plugged in.
+    pub fn is_plugged_in(self) -> bool {
+        !matches!(self, Self::Discharging | Self::Unknown)
+    }
+}
+
+/// The current charge of the battery.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct BatteryState {
+    /// The charge in percent.
+    pub capacity: u8,
+    pub status: BatteryStatus,
+    /// The charging or discharging rate in mW, `None` if the battery doesn't report it.
+    pub power_mw: Option<u32>,
+    /// The charge in percent below which charging starts.
+    pub start_threshold: Option<u8>,
+    /// The charge in percent at which charging stops.
+    pub end_threshold: Option<u8>,
+}
+
+/// The remaining capacity of the battery compared to when it was new.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct BatteryHealth {
+    /// The capacity of a new battery.
+    pub design_capacity: u32,
+    /// The capacity of the last full charge.
+    pub full_capacity: u32,
+    pub unit: CapacityUnit,
+    /// `None` if the battery doesn't count its charge cycles.
+    pub cycle_count: Option<u32>,
+}
+
+impl BatteryHealth {
+    /// The full charge capacity in percent of the design capacity.
+    /// New batteries can exceed 100%.
+    pub fn percentage(&self) -> f64 {
+        if self.design_capacity == 0 {
+            return 0.0;
+        }
+        f64::from(self.full_capacity) / f64::from(self.design_capacity) * 100.0
+    }
+}
+
+/// Most batteries report their energy, some only their charge.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum CapacityUnit {
+    #[default]
+    MilliWattHours,
+    MilliAmpHours,
+}
+
+impl std::fmt::Display for CapacityUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MilliWattHours => write!(f, "mWh"),
+            Self::MilliAmpHours => write!(f, "mAh"),
+        }
+    }
+}
+
+/// An entry of the battery health history of tailord.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct BatteryHealthSample {
+    /// Seconds since the unix epoch.
+    pub timestamp: u64,
+    pub health: BatteryHealth,
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BatteryHealth, CalibrationPhase, CalibrationStatus};
+
+    #[test]
+    fn calibration_progress() {
+        let status = |phase, capacity, plugged_in| CalibrationStatus {
+            phase,
+            capacity,
+            plugged_in,
+            discharge_to: 5,
+        };
+        assert_eq!(
+            status(CalibrationPhase::Charging, 100, true).progress(),
+            1.0 / 3.0
+        );
+        assert_eq!(
+            status(CalibrationPhase::Discharging, 5, false).progress(),
+            2.0 / 3.0
+        );
+        assert_eq!(
+            status(CalibrationPhase::Recharging, 100, true).progress(),
+            1.0
+        );
+        assert_eq!(
+            status(CalibrationPhase::Cancelled, 50, true).progress(),
+            0.0
+        );
+
+        assert!(status(CalibrationPhase::Discharging, 80, true).needs_charger_change());
+        assert!(!status(CalibrationPhase::Recharging, 10, true).needs_charger_change());
+    }
+
+    #[test]
+    fn health_percentage() {
+        let health = BatteryHealth {
+            design_capacity: 50000,
+            full_capacity: 45000,
+            ..Default::default()
+        };
+        assert_eq!(health.percentage(), 90.0);
+        assert_eq!(BatteryHealth::default().percentage(), 0.0);
+    }
+}
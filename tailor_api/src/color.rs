@@ -1,4 +1,7 @@
-use crate::led::LedControllerMode;
+use crate::{
+    led::LedControllerMode,
+    version::{check_version, SCHEMA_VERSION},
+};
 use atoi::FromRadix16;
 use std::{fmt::Display, io, str::FromStr};
 
@@ -16,14 +19,118 @@ pub enum ColorTransition {
     Linear,
 }
 
+/// Keys light up when they are pressed and fade back to the idle color.
+/// On keyboards with a single LED, every key lights up the whole keyboard.
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TypingEffect {
+    pub idle: Color,
+    pub pressed: Color,
+    /// Time in ms until a pressed key is back at the idle color.
+    pub fade_time: u32,
+}
+
+/// A part of the keyboard with its own colors, e.g. static WASD keys.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ColorRegion {
+    /// The LEDs of the region, numbered like the `key_leds` of the
+    /// daemon configuration. Empty for all LEDs.
+    pub leds: Vec<u32>,
+    /// Must not contain regions itself.
+    pub profile: ColorProfile,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "ColorProfileRepr", into = "ColorProfileRepr")]
 pub enum ColorProfile {
     None,
     Single(Color),
     Multiple(Vec<ColorPoint>),
+    Typing(TypingEffect),
+    /// Each LED shows the first region that contains it.
+    /// Keys of no region are off.
+    Regions(Vec<ColorRegion>),
+}
+
+/// Serialized form of [`ColorProfile`] along with the schema version.
+/// Older versions stored the profile without version.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum ColorProfileRepr {
+    Versioned {
+        version: u32,
+        profile: ColorProfileData,
+    },
+    Legacy(ColorProfileData),
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename = "ColorProfile")]
+enum ColorProfileData {
+    None,
+    Single(Color),
+    Multiple(Vec<ColorPoint>),
+    Typing(TypingEffect),
+    Regions(Vec<ColorRegion>),
+}
+
+impl TryFrom<ColorProfileRepr> for ColorProfile {
+    type Error = String;
+
+    fn try_from(repr: ColorProfileRepr) -> Result<Self, Self::Error> {
+        let profile = match repr {
+            ColorProfileRepr::Versioned { version, profile } => {
+                check_version(version)?;
+                profile
+            }
+            ColorProfileRepr::Legacy(profile) => profile,
+        };
+        Ok(match profile {
+            ColorProfileData::None => Self::None,
+            ColorProfileData::Single(color) => Self::Single(color),
+            ColorProfileData::Multiple(points) => Self::Multiple(points),
+            ColorProfileData::Typing(effect) => Self::Typing(effect),
+            ColorProfileData::Regions(regions) => {
+                if regions
+                    .iter()
+                    .any(|region| matches!(region.profile, ColorProfile::Regions(_)))
+                {
+                    return Err("Regions can't contain regions".to_owned());
+                }
+                Self::Regions(regions)
+            }
+        })
+    }
+}
+
+impl From<ColorProfile> for ColorProfileRepr {
+    fn from(profile: ColorProfile) -> Self {
+        let profile = match profile {
+            ColorProfile::None => ColorProfileData::None,
+            ColorProfile::Single(color) => ColorProfileData::Single(color),
+            ColorProfile::Multiple(points) => ColorProfileData::Multiple(points),
+            ColorProfile::Typing(effect) => ColorProfileData::Typing(effect),
+            ColorProfile::Regions(regions) => ColorProfileData::Regions(regions),
+        };
+        Self::Versioned {
+            version: SCHEMA_VERSION,
+            profile,
+        }
+    }
 }
 
 impl ColorProfile {
+    /// The profile of the first region that contains an LED. Returns
+    /// `None` if no region contains it and `self` if there are no regions.
+    pub fn region(&self, led: u32) -> Option<&ColorProfile> {
+        match self {
+            Self::Regions(regions) => regions
+                .iter()
+                .find(|region| region.leds.is_empty() || region.leds.contains(&led))
+                .map(|region| &region.profile),
+            profile => Some(profile),
+        }
+    }
+
     pub fn default(mode: LedControllerMode) -> Self {
         match mode {
             LedControllerMode::Monochrome => Self::None,
@@ -56,6 +163,35 @@ pub struct Color {
 }
 
 impl Color {
+    /// Scale the color by a brightness value in percent from 0 to 100.
+    /// Values above 100 will be clamped to 100.
+    pub fn with_brightness(&self, brightness: u8) -> Self {
+        let brightness = brightness.min(100) as f32 / 100.0;
+        let scale = |value: u8| -> u8 { (value as f32 * brightness).round() as u8 };
+        Self {
+            r: scale(self.r),
+            g: scale(self.g),
+            b: scale(self.b),
+        }
+    }
+
+    /// The gray that a brightness-only LED shows for this color. The
+    /// brightest channel is used, so saturated colors stay bright.
+    pub fn to_monochrome(&self) -> Self {
+        let value = self.r.max(self.g).max(self.b);
+        Self {
+            r: value,
+            g: value,
+            b: value,
+        }
+    }
+
+    /// The brightness in percent of the gray from [`Color::to_monochrome`].
+    pub fn brightness(&self) -> u8 {
+        let value = self.r.max(self.g).max(self.b);
+        (value as f32 / 255.0 * 100.0).round() as u8
+    }
+
     pub fn sysfs_rgb_string(&self, max_brightness: u32) -> String {
         let Color { r, g, b } = *self;
         if max_brightness == 255 {
@@ -119,9 +255,9 @@ impl FromStr for Color {
                 "Incorrect length for 3x8-bit hexadecimal value",
             ))
         } else {
-            let r = u8::from_radix_16(s[0..2].as_bytes());
-            let g = u8::from_radix_16(s[2..4].as_bytes());
-            let b = u8::from_radix_16(s[4..6].as_bytes());
+            let r = u8::from_radix_16(&s.as_bytes()[0..2]);
+            let g = u8::from_radix_16(&s.as_bytes()[2..4]);
+            let b = u8::from_radix_16(&s.as_bytes()[4..6]);
 
             if r.1 == 2 && g.1 == 2 && b.1 == 2 {
                 Ok(Self {
@@ -141,7 +277,7 @@ impl FromStr for Color {
 
 #[cfg(test)]
 mod test {
-    use crate::color::Color;
+    use crate::color::{Color, ColorProfile, ColorRegion};
     use std::str::FromStr;
 
     #[test]
@@ -162,4 +298,60 @@ mod test {
         Color::from_str("F").unwrap_err();
         Color::from_str("INVLD!").unwrap_err();
     }
+
+    #[test]
+    fn color_with_brightness() {
+        let color = Color {
+            r: 255,
+            g: 100,
+            b: 0,
+        };
+        assert_eq!(color.with_brightness(100), color);
+        assert_eq!(color.with_brightness(200), color);
+        assert_eq!(
+            color.with_brightness(50),
+            Color {
+                r: 128,
+                g: 50,
+                b: 0,
+            }
+        );
+        assert_eq!(color.with_brightness(0), Color { r: 0, g: 0, b: 0 });
+    }
+
+    #[test]
+    fn nested_regions() {
+        let region = |leds: Vec<u32>, profile| ColorRegion { leds, profile };
+        let wasd = ColorProfile::Single(Color { r: 255, g: 0, b: 0 });
+        let profile = ColorProfile::Regions(vec![
+            region(vec![1, 2], wasd.clone()),
+            region(Vec::new(), ColorProfile::None),
+        ]);
+        assert_eq!(profile.region(2), Some(&wasd));
+        assert_eq!(profile.region(3), Some(&ColorProfile::None));
+
+        let json = serde_json::to_string(&profile).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ColorProfile>(&json).unwrap(),
+            profile
+        );
+        let nested = ColorProfile::Regions(vec![region(Vec::new(), profile)]);
+        let json = serde_json::to_string(&nested).unwrap();
+        serde_json::from_str::<ColorProfile>(&json).unwrap_err();
+    }
+
+    #[test]
+    fn color_to_monochrome() {
+        let color = Color { r: 255, g: 0, b: 0 };
+        assert_eq!(
+            color.to_monochrome(),
+            Color {
+                r: 255,
+                g: 255,
+                b: 255,
+            }
+        );
+        assert_eq!(color.brightness(), 100);
+        assert_eq!(Color { r: 0, g: 64, b: 0 }.brightness(), 25);
+    }
 }
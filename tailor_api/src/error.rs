@@ -0,0 +1,121 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// Errors of tailord that clients can react to.
+///
+/// They are sent as JSON in the message of a D-Bus error,
+/// so clients that don't know them still get a readable message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum TailorError {
+    /// The hardware or the daemon doesn't support the feature.
+    NotSupported { message: String },
+    /// A profile doesn't exist, has an invalid name or invalid content.
+    InvalidProfile { message: String },
+    /// Accessing the hardware failed. `errno` is set
+    /// if the kernel reported an error code.
+    HardwareError { errno: Option<i32>, message: String },
+    /// The caller isn't allowed to access a resource.
+    PermissionDenied { message: String },
+    /// A profile with the new name of a profile already exists.
+    AlreadyExists { message: String },
+    /// A part of a profile couldn't be applied. `applied` lists the
+    /// parts that were applied before. If `rolled_back` is set, the
+    /// previous profile was restored, otherwise the hardware might
+    /// use a mix of both profiles.
+    PartiallyApplied {
+        applied: Vec<String>,
+        failed: String,
+        rolled_back: bool,
+        message: String,
+    },
+}
+
+impl TailorError {
+    pub fn not_supported(message: impl Into<String>) -> Self {
+        Self::NotSupported {
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid_profile(message: impl Into<String>) -> Self {
+        Self::InvalidProfile {
+            message: message.into(),
+        }
+    }
+
+    pub fn hardware_error(errno: Option<i32>, message: impl Into<String>) -> Self {
+        Self::HardwareError {
+            errno,
+            message: message.into(),
+        }
+    }
+
+    pub fn permission_denied(message: impl Into<String>) -> Self {
+        Self::PermissionDenied {
+            message: message.into(),
+        }
+    }
+
+    pub fn already_exists(message: impl Into<String>) -> Self {
+        Self::AlreadyExists {
+            message: message.into(),
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Self::NotSupported { message }
+            | Self::InvalidProfile { message }
+            | Self::HardwareError { message, .. }
+            | Self::PermissionDenied { message }
+            | Self::AlreadyExists { message }
+            | Self::PartiallyApplied { message, .. } => message,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    /// Returns `None` if the message of an error
+    /// doesn't contain a serialized [`TailorError`].
+    pub fn from_json(message: &str) -> Option<Self> {
+        serde_json::from_str(message).ok()
+    }
+}
+
+impl Display for TailorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::HardwareError {
+                errno: Some(errno),
+                message,
+            } => write!(f, "{message} (errno {errno})"),
+            _ => f.write_str(self.message()),
+        }
+    }
+}
+
+impl std::error::Error for TailorError {}
+
+#[cfg(test)]
+mod test {
+    use super::TailorError;
+
+    #[test]
+    fn round_trip() {
+        let err = TailorError::hardware_error(Some(19), "No such device");
+        assert_eq!(TailorError::from_json(&err.to_json()), Some(err));
+        assert_eq!(TailorError::from_json("Some other error"), None);
+
+        let err = TailorError::PartiallyApplied {
+            applied: vec!["fans".to_owned()],
+            failed: "performance profile".to_owned(),
+            rolled_back: true,
+            message: "Unsupported".to_owned(),
+        };
+        assert_eq!(TailorError::from_json(&err.to_json()), Some(err));
+    }
+}
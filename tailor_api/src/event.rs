@@ -0,0 +1,79 @@
+/// An entry of the event log of tailord.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Event {
+    /// Seconds since the unix epoch.
+    pub timestamp: u64,
+    pub kind: EventKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[non_exhaustive]
+pub enum EventKind {
+    /// A global profile was activated, by a user if `user` is set.
+    ProfileSwitch {
+        profile: String,
+        #[serde(default)]
+        user: Option<String>,
+    },
+    /// A fan was pinned at a fixed speed or released (`speed` is `None`).
+    FanSpeedOverride { fan_idx: u8, speed: Option<u8> },
+    /// The colors of LED devices were overridden or released (`color` is `None`).
+    LedColorOverride {
+        device: Option<String>,
+        color: Option<crate::Color>,
+    },
+    /// The temperature exceeded the critical limit.
+    CriticalTemperature { temp: u8, limit: u8 },
+    /// The temperature dropped below the critical limit again.
+    CriticalTemperatureRecovered { temp: u8 },
+    /// A fan couldn't be read or controlled.
+    FanFault { fan_idx: u8, error: String },
+    /// The CPU started or stopped throttling while all fans run at 100%.
+    ThermalThrottling { active: bool },
+}
+
+impl std::fmt::Display for EventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ProfileSwitch {
+                profile,
+                user: Some(user),
+            } => write!(f, "Activated profile `{profile}` by {user}"),
+            Self::ProfileSwitch {
+                profile,
+                user: None,
+            } => {
+                write!(f, "Activated profile `{profile}`")
+            }
+            Self::FanSpeedOverride {
+                fan_idx,
+                speed: Some(speed),
+            } => write!(f, "Pinned fan {fan_idx} at {speed}%"),
+            Self::FanSpeedOverride {
+                fan_idx,
+                speed: None,
+            } => write!(f, "Released fan {fan_idx}"),
+            Self::LedColorOverride { device, color } => {
+                let device = device.as_deref().unwrap_or("all LED devices");
+                match color {
+                    Some(color) => write!(f, "Set color of {device} to {color}"),
+                    None => write!(f, "Released color of {device}"),
+                }
+            }
+            Self::CriticalTemperature { temp, limit } => {
+                write!(
+                    f,
+                    "Temperature {temp}°C exceeded the critical limit of {limit}°C"
+                )
+            }
+            Self::CriticalTemperatureRecovered { temp } => {
+                write!(f, "Temperature {temp}°C is below the critical limit again")
+            }
+            Self::FanFault { fan_idx, error } => write!(f, "Fan {fan_idx} failed: {error}"),
+            Self::ThermalThrottling { active: true } => {
+                write!(f, "The CPU is throttling although all fans run at 100%")
+            }
+            Self::ThermalThrottling { active: false } => write!(f, "The CPU stopped throttling"),
+        }
+    }
+}
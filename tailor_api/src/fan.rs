@@ -1,5 +1,298 @@
+use crate::version::{check_version, SCHEMA_VERSION};
+
 #[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
 pub struct FanProfilePoint {
     pub temp: u8,
     pub fan: u8,
 }
+
+/// The fan speed in percent that a curve of `points`, sorted by
+/// temperature, requests at `temp`. Speeds between two points are
+/// interpolated linearly. Below the first point, the fan runs at its
+/// speed and above the last point at full speed.
+pub fn fan_speed_at(points: &[FanProfilePoint], temp: u8) -> u8 {
+    // Find the first point that has a greater or equal temperature.
+    match points.iter().position(|point| point.temp >= temp) {
+        Some(0) => points[0].fan,
+        Some(idx) => {
+            let (prev, next) = (&points[idx - 1], &points[idx]);
+            // Use i32 so falling curves and the multiplication don't overflow,
+            // unsanitized curves can have speeds above 100%.
+            let temp_diff = i32::from(next.temp.saturating_sub(prev.temp).max(1));
+            let fan_diff = i32::from(next.fan) - i32::from(prev.fan);
+            let curr_temp_diff = i32::from(temp.saturating_sub(prev.temp));
+            (i32::from(prev.fan) + fan_diff * curr_temp_diff / temp_diff).clamp(0, 100) as u8
+        }
+        // The temperature is higher than anything in the list.
+        None => 100,
+    }
+}
+
+/// A fan profile along with its settings.
+///
+/// Profiles that were stored as plain list of [`FanProfilePoint`]s
+/// by older versions can still be loaded.
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "FanProfileRepr", into = "FanProfileRepr")]
+pub struct FanProfileInfo {
+    pub points: Vec<FanProfilePoint>,
+    pub smoothing: TemperatureSmoothing,
+    /// Drive all fans with this flag from the highest of their
+    /// temperatures, so they always run at the same speed.
+    pub synchronized: bool,
+    /// Hold a target temperature instead of following the points.
+    pub pid: Option<PidControl>,
+}
+
+/// A PID controller that holds the temperature at `target_temp`. The
+/// fan speed in percent is the sum of the gains multiplied with the
+/// temperature above the target, its integral over time and its rate
+/// of change, clamped to the speeds that the hardware supports.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct PidControl {
+    pub target_temp: u8,
+    /// Percent per °C above the target.
+    pub kp: f32,
+    /// Percent per °C above the target and second.
+    pub ki: f32,
+    /// Percent per °C per second that the temperature rises.
+    pub kd: f32,
+}
+
+impl From<Vec<FanProfilePoint>> for FanProfileInfo {
+    fn from(points: Vec<FanProfilePoint>) -> Self {
+        Self {
+            points,
+            smoothing: TemperatureSmoothing::default(),
+            synchronized: false,
+            pid: None,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+#[serde(untagged)]
+enum FanProfileRepr {
+    Points(Vec<FanProfilePoint>),
+    Full(FanProfileFields),
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct FanProfileFields {
+    #[serde(default)]
+    version: u32,
+    /// Only required without `pid`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    points: Vec<FanProfilePoint>,
+    #[serde(default)]
+    smoothing: TemperatureSmoothing,
+    #[serde(default)]
+    synchronized: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pid: Option<PidControl>,
+}
+
+/// The variant is chosen by the kind of the data instead of trying each
+/// like `#[serde(untagged)]` does, which would hide the location of errors.
+impl<'de> serde::Deserialize<'de> for FanProfileRepr {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ReprVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for ReprVisitor {
+            type Value = FanProfileRepr;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("a fan profile or a list of points")
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                seq: A,
+            ) -> Result<Self::Value, A::Error> {
+                serde::Deserialize::deserialize(serde::de::value::SeqAccessDeserializer::new(seq))
+                    .map(FanProfileRepr::Points)
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                map: A,
+            ) -> Result<Self::Value, A::Error> {
+                serde::Deserialize::deserialize(serde::de::value::MapAccessDeserializer::new(map))
+                    .map(FanProfileRepr::Full)
+            }
+        }
+
+        deserializer.deserialize_any(ReprVisitor)
+    }
+}
+
+impl TryFrom<FanProfileRepr> for FanProfileInfo {
+    type Error = String;
+
+    fn try_from(repr: FanProfileRepr) -> Result<Self, Self::Error> {
+        match repr {
+            FanProfileRepr::Points(points) => Ok(points.into()),
+            FanProfileRepr::Full(FanProfileFields {
+                version,
+                points,
+                smoothing,
+                synchronized,
+                pid,
+            }) => {
+                check_version(version)?;
+                Ok(Self {
+                    points,
+                    smoothing,
+                    synchronized,
+                    pid,
+                })
+            }
+        }
+    }
+}
+
+impl From<FanProfileInfo> for FanProfileRepr {
+    fn from(info: FanProfileInfo) -> Self {
+        let FanProfileInfo {
+            points,
+            smoothing,
+            synchronized,
+            pid,
+        } = info;
+        Self::Full(FanProfileFields {
+            version: SCHEMA_VERSION,
+            points,
+            smoothing,
+            synchronized,
+            pid,
+        })
+    }
+}
+
+/// Smoothing that is applied to the temperature before the
+/// fan profile is evaluated. Stronger smoothing reduces fan speed
+/// changes caused by short temperature spikes but reacts slower.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum TemperatureSmoothing {
+    /// Use the latest temperature.
+    #[default]
+    None,
+    /// Average over the given number of the latest temperature samples.
+    MovingAverage { window: u8 },
+    /// Exponential moving average. `factor` is the weight in
+    /// percent (1 to 100) of each new temperature sample.
+    Exponential { factor: u8 },
+}
+
+/// The current state of a fan controlled by tailord.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct FanStatus {
+    pub fan_idx: u8,
+    /// Temperature in °C as reported by the sensor.
+    pub raw_temp: u8,
+    /// Temperature in °C after applying the calibration offset and smoothing.
+    /// This value is used to evaluate the fan profile.
+    pub temp: u8,
+    /// Fan speed in percent that tailord commanded.
+    pub fan_speed: u8,
+    /// Fan speed in percent that the hardware reports, if available.
+    /// It differs from `fan_speed` if the fan doesn't follow the commanded duty.
+    #[serde(default)]
+    pub reported_fan_speed: Option<u8>,
+    /// Fan speed in percent that the profile requests for `temp`.
+    pub target_fan_speed: u8,
+    /// Fan speed in percent that replaces the profile, if any.
+    pub speed_override: Option<u8>,
+    /// The fan runs in sync with the other fans of a synchronized profile.
+    #[serde(default)]
+    pub synchronized: bool,
+}
+
+/// Thermal throttling state of the CPU.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct ThrottleStatus {
+    /// The CPU throttled since the last check.
+    pub throttling: bool,
+    /// All fans run at 100%.
+    pub fans_at_max: bool,
+    /// Total number of throttle events reported by the kernel since boot.
+    pub throttle_count: u64,
+}
+
+impl ThrottleStatus {
+    /// The CPU is throttling although the fans can't spin any faster.
+    pub fn is_critical(&self) -> bool {
+        self.throttling && self.fans_at_max
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{fan_speed_at, FanProfileInfo, FanProfilePoint, PidControl, TemperatureSmoothing};
+
+    #[test]
+    fn interpolation() {
+        let points = [
+            FanProfilePoint { temp: 30, fan: 20 },
+            FanProfilePoint { temp: 50, fan: 60 },
+            FanProfilePoint { temp: 70, fan: 40 },
+        ];
+        assert_eq!(fan_speed_at(&points, 0), 20);
+        assert_eq!(fan_speed_at(&points, 30), 20);
+        assert_eq!(fan_speed_at(&points, 40), 40);
+        assert_eq!(fan_speed_at(&points, 50), 60);
+        assert_eq!(fan_speed_at(&points, 60), 50);
+        assert_eq!(fan_speed_at(&points, 71), 100);
+        assert_eq!(fan_speed_at(&[], 40), 100);
+
+        // Unsanitized curves neither overflow nor exceed 100%.
+        let points = [
+            FanProfilePoint { temp: 0, fan: 0 },
+            FanProfilePoint {
+                temp: 200,
+                fan: 255,
+            },
+        ];
+        assert_eq!(fan_speed_at(&points, 40), 51);
+        assert_eq!(fan_speed_at(&points, 199), 100);
+    }
+
+    #[test]
+    fn fan_profile_info_compatibility() {
+        let points = vec![FanProfilePoint { temp: 30, fan: 20 }];
+
+        let legacy = r#"[{"temp":30,"fan":20}]"#;
+        let info: FanProfileInfo = serde_json::from_str(legacy).unwrap();
+        assert_eq!(info, FanProfileInfo::from(points.clone()));
+        assert_eq!(
+            serde_json::to_string(&info).unwrap(),
+            r#"{"version":1,"points":[{"temp":30,"fan":20}],"smoothing":"None","synchronized":false}"#
+        );
+
+        let newer = r#"{"version":1000,"points":[{"temp":30,"fan":20}]}"#;
+        serde_json::from_str::<FanProfileInfo>(newer).unwrap_err();
+
+        let info = FanProfileInfo {
+            points,
+            smoothing: TemperatureSmoothing::MovingAverage { window: 5 },
+            synchronized: true,
+            pid: Some(PidControl {
+                target_temp: 70,
+                kp: 4.0,
+                ki: 0.1,
+                kd: 2.0,
+            }),
+        };
+        let data = serde_json::to_string(&info).unwrap();
+        assert_eq!(serde_json::from_str::<FanProfileInfo>(&data).unwrap(), info);
+
+        // PID profiles don't need points.
+        let pid = r#"{"version":1,"pid":{"target_temp":70,"kp":4.0,"ki":0.1,"kd":2.0}}"#;
+        let info: FanProfileInfo = serde_json::from_str(pid).unwrap();
+        assert!(info.points.is_empty());
+        assert_eq!(
+            serde_json::to_string(&info).unwrap(),
+            r#"{"version":1,"smoothing":"None","synchronized":false,"pid":{"target_temp":70,"kp":4.0,"ki":0.1,"kd":2.0}}"#
+        );
+    }
+}
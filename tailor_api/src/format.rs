@@ -0,0 +1,346 @@
+use std::{fmt::Display, path::Path};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// File formats in which profiles can be stored.
+/// The format of a file is detected by its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+}
+
+/// An error of reading or writing data. Errors of reading tell
+/// which field is invalid and where it is, if the format reports it.
+#[derive(Debug)]
+pub struct FormatError {
+    message: String,
+    /// Line and column, starting at 1.
+    position: Option<(usize, usize)>,
+    path: Option<String>,
+}
+
+impl FormatError {
+    fn new(message: impl Display) -> Self {
+        Self {
+            message: message.to_string(),
+            position: None,
+            path: None,
+        }
+    }
+
+    /// The description of the problem without its location.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn line(&self) -> Option<usize> {
+        self.position.map(|(line, _)| line)
+    }
+
+    pub fn column(&self) -> Option<usize> {
+        self.position.map(|(_, column)| column)
+    }
+
+    /// The invalid field, e.g. `points[2].temp`.
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+}
+
+impl Display for FormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)?;
+        if let Some(path) = &self.path {
+            write!(f, " in `{path}`")?;
+        }
+        if let Some((line, column)) = self.position {
+            write!(f, " at line {line} column {column}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+impl ConfigFormat {
+    /// All formats, in the order in which they are preferred
+    /// if a profile exists in multiple formats.
+    pub const ALL: [Self; 2] = [Self::Json, Self::Toml];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Toml => "toml",
+        }
+    }
+
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.extension() == extension)
+    }
+
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        Self::from_extension(path.as_ref().extension()?.to_str()?)
+    }
+
+    pub fn serialize<T: Serialize>(&self, data: &T) -> Result<String, FormatError> {
+        match self {
+            Self::Json => serde_json::to_string_pretty(data).map_err(FormatError::new),
+            Self::Toml => toml::to_string_pretty(data).map_err(FormatError::new),
+        }
+    }
+
+    pub fn deserialize<T: DeserializeOwned>(&self, data: &str) -> Result<T, FormatError> {
+        match self {
+            Self::Json => serde_json::from_str(data).map_err(|err| json_error(data, err)),
+            Self::Toml => toml::from_str(data).map_err(|err| toml_error(data, err)),
+        }
+    }
+
+    /// Convert data from this format to another format
+    /// without knowing the type of the data.
+    pub fn convert(&self, data: &str, to: Self) -> Result<String, FormatError> {
+        let mut value: serde_json::Value = self.deserialize(data)?;
+        if to == Self::Toml {
+            // TOML has no null values, missing values are equivalent.
+            remove_nulls(&mut value);
+        }
+        to.serialize(&value)
+    }
+}
+
+fn json_error(data: &str, err: serde_json::Error) -> FormatError {
+    let (line, column) = (err.line(), err.column());
+    let message = err.to_string();
+    // The message ends with the position, which is shown separately.
+    let message = message
+        .strip_suffix(&format!(" at line {line} column {column}"))
+        .unwrap_or(&message)
+        .to_owned();
+    if line == 0 {
+        return FormatError::new(message);
+    }
+    let offset = data
+        .split_inclusive('\n')
+        .take(line - 1)
+        .map(str::len)
+        .sum::<usize>()
+        + column;
+    // The column counts bytes and can point into a multibyte character.
+    let mut offset = offset.min(data.len());
+    while !data.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    FormatError {
+        message,
+        position: Some((line, column)),
+        path: json_path(&data[..offset]),
+    }
+}
+
+fn toml_error(data: &str, err: toml::de::Error) -> FormatError {
+    let Some(offset) = err.span().map(|span| span.start.min(data.len())) else {
+        return FormatError::new(err.message());
+    };
+    let before = &data[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |idx| idx + 1) + 1;
+    // The line of the error has its table header or key.
+    let line_end = data[offset..]
+        .find('\n')
+        .map_or(data.len(), |idx| offset + idx);
+    FormatError {
+        message: err.message().to_owned(),
+        position: Some((line, column)),
+        path: toml_path(&data[..line_end]),
+    }
+}
+
+/// The path of the value that is being read at the end of a JSON document.
+fn json_path(data: &str) -> Option<String> {
+    enum Segment {
+        Key(Option<String>),
+        Index(usize),
+    }
+
+    let mut segments = Vec::new();
+    let mut last_string = None;
+    let mut chars = data.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                let mut string = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => string.extend(chars.next()),
+                        c => string.push(c),
+                    }
+                }
+                last_string = Some(string);
+            }
+            ':' => {
+                if let Some(Segment::Key(key)) = segments.last_mut() {
+                    *key = last_string.take();
+                }
+            }
+            ',' => match segments.last_mut() {
+                Some(Segment::Key(key)) => *key = None,
+                Some(Segment::Index(idx)) => *idx += 1,
+                None => {}
+            },
+            '{' => segments.push(Segment::Key(None)),
+            '[' => segments.push(Segment::Index(0)),
+            '}' | ']' => {
+                segments.pop();
+            }
+            _ => {}
+        }
+    }
+
+    let mut path = String::new();
+    for segment in segments {
+        match segment {
+            Segment::Key(Some(key)) if path.is_empty() => path.push_str(&key),
+            Segment::Key(Some(key)) => {
+                path.push('.');
+                path.push_str(&key);
+            }
+            Segment::Key(None) => {}
+            Segment::Index(idx) => path.push_str(&format!("[{idx}]")),
+        }
+    }
+    (!path.is_empty()).then_some(path)
+}
+
+/// The path of the value that is being read at the end of a TOML document,
+/// from the last table header and key. Inline tables aren't resolved.
+fn toml_path(data: &str) -> Option<String> {
+    let mut table: Option<String> = None;
+    let mut array_lengths: std::collections::HashMap<String, usize> = Default::default();
+    let mut key = None;
+    for line in data.lines() {
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix("[[").and_then(|l| l.split("]]").next()) {
+            let name = name.trim().to_owned();
+            let idx = array_lengths.entry(name.clone()).or_default();
+            table = Some(format!("{name}[{idx}]"));
+            *idx += 1;
+            key = None;
+        } else if let Some(name) = line.strip_prefix('[').and_then(|l| l.split(']').next()) {
+            table = Some(name.trim().to_owned());
+            key = None;
+        } else if let Some((name, _)) = line.split_once('=') {
+            let name = name.trim();
+            if !name.is_empty() && !name.starts_with(['{', '#']) {
+                key = Some(name.trim_matches('"').to_owned());
+            }
+        }
+    }
+    match (table, key) {
+        (Some(table), Some(key)) => Some(format!("{table}.{key}")),
+        (table, key) => table.or(key),
+    }
+}
+
+fn remove_nulls(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.retain(|_, value| !value.is_null());
+            map.values_mut().for_each(remove_nulls);
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(remove_nulls),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ColorProfile, FanProfileInfo, FanProfilePoint, ProfileInfo};
+
+    use super::ConfigFormat;
+
+    #[test]
+    fn toml_round_trip() {
+        let profile = ProfileInfo::default();
+        let data = ConfigFormat::Toml.serialize(&profile).unwrap();
+        assert_eq!(
+            ConfigFormat::Toml
+                .deserialize::<ProfileInfo>(&data)
+                .unwrap(),
+            profile
+        );
+
+        let fan: FanProfileInfo = vec![FanProfilePoint { temp: 30, fan: 20 }].into();
+        let data = ConfigFormat::Toml.serialize(&fan).unwrap();
+        assert_eq!(
+            ConfigFormat::Toml
+                .deserialize::<FanProfileInfo>(&data)
+                .unwrap(),
+            fan
+        );
+
+        let color = ColorProfile::default(crate::LedControllerMode::Rgb);
+        let json = ConfigFormat::Json.serialize(&color).unwrap();
+        let data = ConfigFormat::Json
+            .convert(&json, ConfigFormat::Toml)
+            .unwrap();
+        assert_eq!(
+            ConfigFormat::Toml
+                .deserialize::<ColorProfile>(&data)
+                .unwrap(),
+            color
+        );
+
+        let json = serde_json::to_string(&profile).unwrap();
+        let data = ConfigFormat::Json
+            .convert(&json, ConfigFormat::Toml)
+            .unwrap();
+        let json = ConfigFormat::Toml
+            .convert(&data, ConfigFormat::Json)
+            .unwrap();
+        assert_eq!(
+            ConfigFormat::Json
+                .deserialize::<ProfileInfo>(&json)
+                .unwrap(),
+            profile
+        );
+    }
+
+    #[test]
+    fn error_location() {
+        let json = "{\n  \"points\": [\n    { \"temp\": 40, \"fan\": 20 },\n    { \"temp\": \"hot\", \"fan\": 50 }\n  ]\n}";
+        let err = ConfigFormat::Json
+            .deserialize::<FanProfileInfo>(json)
+            .unwrap_err();
+        assert_eq!(err.path(), Some("points[1].temp"));
+        assert_eq!(err.line(), Some(4));
+        assert!(err
+            .to_string()
+            .ends_with("in `points[1].temp` at line 4 column 19"));
+
+        let led = "device_name = \"rgb\"\nfunction = \"kbd_backlight\"\n";
+        let toml = format!(
+            "fans = [\"default\"]\n\n[[leds]]\n{led}profile = \"default\"\n\n[[leds]]\n{led}profile = 5\n"
+        );
+        let err = ConfigFormat::Toml
+            .deserialize::<ProfileInfo>(&toml)
+            .unwrap_err();
+        assert_eq!(err.path(), Some("leds[1].profile"));
+        assert_eq!(err.line(), Some(11));
+        // The column of serde_json can point into a multibyte character.
+        for json in [
+            "[1,ü]",
+            "{\"a\": ü}",
+            "ü",
+            "{\"points\": [{\"temp\": \"ü\"x}]}",
+        ] {
+            let err = ConfigFormat::Json
+                .deserialize::<FanProfileInfo>(json)
+                .unwrap_err();
+            assert_eq!(err.line(), Some(1));
+        }
+    }
+}
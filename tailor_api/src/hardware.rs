@@ -0,0 +1,140 @@
+use std::fmt::Display;
+
+use crate::LedDeviceInfo;
+
+/// The features of the hardware that tailord controls.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct HardwareCapabilities {
+    pub num_of_fans: u8,
+    /// The lowest fan speed in percent that the firmware allows.
+    pub fans_min_speed: u8,
+    /// Whether the fans can be turned off completely.
+    pub fans_off_available: bool,
+    /// The ODM performance profiles of the firmware.
+    pub performance_profiles: Vec<String>,
+    /// The names of the configurable power limits, e.g. `pl1`.
+    pub tdp_descriptors: Vec<String>,
+    pub webcam: bool,
+    /// Whether the firmware can turn the touchpad on and off.
+    pub touchpad: bool,
+    /// Whether the driver exposes the Fn-lock of the keyboard.
+    pub fn_lock: bool,
+    /// Whether the firmware can limit the charge to extend the lifespan of the battery.
+    pub battery_conservation: bool,
+    /// Whether there's an internal battery that can be calibrated.
+    pub battery_calibration: bool,
+    /// Whether profiles can change the PCIe ASPM policy. The kernel refuses
+    /// changes if ASPM is disabled by the firmware or with `pcie_aspm=off`.
+    pub pcie_aspm: bool,
+    pub led_devices: Vec<LedDeviceInfo>,
+}
+
+impl HardwareCapabilities {
+    pub fn supports_fan_control(&self) -> bool {
+        self.num_of_fans > 0
+    }
+
+    /// Whether a fan speed of 0% actually stops the fans.
+    pub fn supports_fans_off(&self) -> bool {
+        self.supports_fan_control() && self.fans_off_available
+    }
+
+    pub fn supports_performance_profiles(&self) -> bool {
+        !self.performance_profiles.is_empty()
+    }
+
+    /// The number of configurable power limits.
+    pub fn tdp_slots(&self) -> usize {
+        self.tdp_descriptors.len()
+    }
+}
+
+/// Identifiers and versions of the device and its drivers,
+/// e.g. for support requests and bug reports.
+/// Values that the firmware or the drivers don't provide are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct DeviceInfo {
+    pub vendor: Option<String>,
+    /// The model name, e.g. `InfinityBook Pro 14 Gen9`.
+    pub product_name: Option<String>,
+    pub board_name: Option<String>,
+    pub bios_version: Option<String>,
+    pub bios_date: Option<String>,
+    /// The firmware of the embedded controller.
+    pub ec_version: Option<String>,
+    /// How tailord controls the hardware, e.g. `ioctl` or `Nb05`.
+    /// `None` if no interface is available.
+    pub interface: Option<String>,
+    /// The interface of the ODM, e.g. `clevo_acpi` or `uniwill`.
+    pub odm_interface: Option<String>,
+    /// The version of the tuxedo_io module.
+    pub io_module_version: Option<String>,
+    pub model_id: Option<String>,
+    pub kernel_version: Option<String>,
+    /// The loaded modules of the tuxedo drivers.
+    pub drivers: Vec<DriverInfo>,
+    pub tailord_version: String,
+    /// The names of the features that tailord detected.
+    pub capabilities: Vec<String>,
+}
+
+/// A value that activating a profile would change, listed by
+/// a dry run. Sysfs attributes are identified by the directory of
+/// the device and the path of the attribute within it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct HardwareWrite {
+    /// E.g. `/sys/devices/system/cpu/cpufreq/policy0` or `Fan 1`.
+    pub device: String,
+    /// E.g. `scaling_max_freq` or `fan curve`.
+    pub attribute: String,
+    /// `None` if the current value can't be read.
+    pub old: Option<String>,
+    pub new: String,
+}
+
+impl Display for HardwareWrite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let old = self.old.as_deref().unwrap_or("unknown");
+        write!(
+            f,
+            "{} {}: {old} → {}",
+            self.device, self.attribute, self.new
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct DriverInfo {
+    pub name: String,
+    /// `None` if the module doesn't declare a version.
+    pub version: Option<String>,
+}
+
+impl Display for DeviceInfo {
+    /// One `key: value` line per property, to paste into bug reports.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let unknown = |value: &Option<String>| value.clone().unwrap_or_else(|| "unknown".into());
+        writeln!(f, "Vendor: {}", unknown(&self.vendor))?;
+        writeln!(f, "Model: {}", unknown(&self.product_name))?;
+        writeln!(f, "Board: {}", unknown(&self.board_name))?;
+        writeln!(f, "Model id: {}", unknown(&self.model_id))?;
+        writeln!(
+            f,
+            "BIOS: {} ({})",
+            unknown(&self.bios_version),
+            unknown(&self.bios_date)
+        )?;
+        writeln!(f, "EC: {}", unknown(&self.ec_version))?;
+        writeln!(f, "Interface: {}", unknown(&self.interface))?;
+        writeln!(f, "ODM interface: {}", unknown(&self.odm_interface))?;
+        writeln!(f, "tuxedo_io: {}", unknown(&self.io_module_version))?;
+        for driver in &self.drivers {
+            writeln!(f, "{}: {}", driver.name, unknown(&driver.version))?;
+        }
+        writeln!(f, "Kernel: {}", unknown(&self.kernel_version))?;
+        writeln!(f, "tailord: {}", self.tailord_version)?;
+        write!(f, "Capabilities: {}", self.capabilities.join(", "))
+    }
+}
@@ -1,3 +1,5 @@
+use crate::Color;
+
 #[derive(
     Default, Debug, Copy, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash,
 )]
@@ -5,9 +7,20 @@
 pub enum LedControllerMode {
     #[default]
     Rgb,
+    /// Brightness only, e.g. the white backlight of most InfinityBooks.
+    /// Colors are shown with the brightness of their brightest channel.
     Monochrome,
 }
 
+impl LedControllerMode {
+    pub fn supports_color(self) -> bool {
+        match self {
+            Self::Rgb => true,
+            Self::Monochrome => false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct LedDeviceInfo {
     pub device_name: String,
@@ -25,3 +38,18 @@ impl LedDeviceInfo {
         format!("{device_name}::{function}")
     }
 }
+
+/// The current state of an LED device driven by tailord.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LedDeviceState {
+    pub info: LedDeviceInfo,
+    /// The color that was last written to the device.
+    /// `None` if nothing has been written yet.
+    pub color: Option<Color>,
+    /// The color override that replaces the active profile, if any.
+    pub color_override: Option<Color>,
+    /// The brightness of the device in percent, which the brightness keys
+    /// of the keyboard change. `None` if it hasn't been read yet.
+    #[serde(default)]
+    pub brightness: Option<u8>,
+}
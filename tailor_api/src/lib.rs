@@ -1,9 +1,38 @@
+mod battery;
 mod color;
+mod error;
+mod event;
 mod fan;
+#[cfg(feature = "config-format")]
+mod format;
+mod hardware;
 mod led;
+mod log;
+mod preset;
 mod profile;
+mod tcc;
+mod version;
 
-pub use color::{Color, ColorPoint, ColorProfile, ColorTransition};
-pub use fan::FanProfilePoint;
-pub use led::{LedControllerMode, LedDeviceInfo};
-pub use profile::{LedProfile, ProfileInfo};
+pub use battery::{
+    BatteryHealth, BatteryHealthSample, BatteryState, BatteryStatus, CalibrationPhase,
+    CalibrationStatus, CapacityUnit,
+};
+pub use color::{Color, ColorPoint, ColorProfile, ColorRegion, ColorTransition, TypingEffect};
+pub use error::TailorError;
+pub use event::{Event, EventKind};
+pub use fan::{
+    fan_speed_at, FanProfileInfo, FanProfilePoint, FanStatus, PidControl, TemperatureSmoothing,
+    ThrottleStatus,
+};
+#[cfg(feature = "config-format")]
+pub use format::{ConfigFormat, FormatError};
+pub use hardware::{DeviceInfo, DriverInfo, HardwareCapabilities, HardwareWrite};
+pub use led::{LedControllerMode, LedDeviceInfo, LedDeviceState};
+pub use log::{LogLevel, LogRecord};
+pub use preset::Preset;
+pub use profile::{
+    AspmPolicy, LedProfile, PowerTuning, ProfileInfo, ProfileOrder, ProfileOwner, ProfileScope,
+    SataLinkPower, UsbAutosuspend,
+};
+pub use tcc::{TccImport, TccProfile};
+pub use version::SCHEMA_VERSION;
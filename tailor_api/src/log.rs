@@ -0,0 +1,41 @@
+/// A message of the log of tailord.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LogRecord {
+    /// Seconds since the unix epoch.
+    pub timestamp: u64,
+    pub level: LogLevel,
+    /// The module that logged the message, e.g. `tailord::fancontrol`.
+    pub target: String,
+    pub message: String,
+}
+
+/// The severity of a log message, ordered from the most to the least severe.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+}
+
+impl LogLevel {
+    pub const ALL: [Self; 3] = [Self::Error, Self::Warn, Self::Info];
+
+    /// Whether a message of this level is shown if messages
+    /// of `minimum` and more severe levels are shown.
+    pub fn is_at_least(self, minimum: Self) -> bool {
+        self <= minimum
+    }
+}
+
+impl std::fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Error => "Error",
+            Self::Warn => "Warning",
+            Self::Info => "Info",
+        })
+    }
+}
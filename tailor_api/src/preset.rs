@@ -0,0 +1,125 @@
+use crate::{
+    Color, ColorProfile, FanProfileInfo, FanProfilePoint, LedControllerMode, PowerTuning,
+    SataLinkPower,
+};
+
+/// The profiles that tailord creates on the first start.
+/// Each preset consists of a global, a fan and a keyboard
+/// profile that are all named after the preset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Preset {
+    Silent,
+    Balanced,
+    Performance,
+    BatteryMax,
+}
+
+impl Preset {
+    pub const ALL: [Self; 4] = [
+        Self::Silent,
+        Self::Balanced,
+        Self::Performance,
+        Self::BatteryMax,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Silent => "silent",
+            Self::Balanced => "balanced",
+            Self::Performance => "performance",
+            Self::BatteryMax => "battery-max",
+        }
+    }
+
+    pub fn fan_profile(&self) -> FanProfileInfo {
+        let points: &[(u8, u8)] = match self {
+            Self::Silent => &[(40, 0), (50, 10), (60, 20), (70, 35), (80, 60), (90, 100)],
+            Self::Balanced => &[
+                (25, 0),
+                (30, 10),
+                (40, 22),
+                (50, 35),
+                (60, 45),
+                (70, 62),
+                (80, 75),
+                (90, 100),
+            ],
+            Self::Performance => &[(25, 20), (40, 35), (50, 50), (60, 65), (70, 80), (80, 100)],
+            Self::BatteryMax => &[(45, 0), (55, 15), (65, 30), (75, 50), (85, 75), (90, 100)],
+        };
+        points
+            .iter()
+            .map(|&(temp, fan)| FanProfilePoint { temp, fan })
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    /// The keyboard profile for devices of the given mode.
+    pub fn keyboard_profile(&self, mode: LedControllerMode) -> ColorProfile {
+        let white = Color {
+            r: 255,
+            g: 255,
+            b: 255,
+        };
+        match self {
+            Self::Silent => ColorProfile::Single(white.with_brightness(30)),
+            Self::Balanced => ColorProfile::default(mode),
+            Self::Performance => ColorProfile::Single(white),
+            Self::BatteryMax => ColorProfile::None,
+        }
+    }
+
+    /// The names of the ODM performance profiles that fit the preset,
+    /// in the order of preference. The names depend on the interface,
+    /// so the first one that the hardware supports is used.
+    pub fn odm_performance_profiles(&self) -> &'static [&'static str] {
+        match self {
+            Self::Silent => &["quiet", "low-power", "power_save"],
+            Self::Balanced => &["balanced", "power_save", "entertainment"],
+            Self::Performance => &["performance", "overboost"],
+            Self::BatteryMax => &["low-power", "power_saving", "quiet", "power_save"],
+        }
+    }
+
+    /// Power saving of devices for the battery preset, the same settings that
+    /// most distributions use on battery. Other presets leave the devices as is.
+    pub fn power_tuning(&self) -> Option<PowerTuning> {
+        match self {
+            Self::BatteryMax => Some(PowerTuning {
+                nvme_max_latency_us: None,
+                sata_link_power: Some(SataLinkPower::MedPowerWithDipm),
+                audio_power_save_secs: Some(1),
+            }),
+            _ => None,
+        }
+    }
+
+    /// The preferred ODM performance profile among the `available` ones.
+    pub fn odm_performance_profile(&self, available: &[String]) -> Option<String> {
+        self.odm_performance_profiles()
+            .iter()
+            .find(|name| available.iter().any(|profile| profile == *name))
+            .map(|name| (*name).to_owned())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Preset;
+
+    #[test]
+    fn performance_profiles() {
+        let uniwill = ["power_save", "enthusiast", "overboost"].map(String::from);
+        assert_eq!(
+            Preset::Silent.odm_performance_profile(&uniwill).as_deref(),
+            Some("power_save")
+        );
+        assert_eq!(
+            Preset::Performance
+                .odm_performance_profile(&uniwill)
+                .as_deref(),
+            Some("overboost")
+        );
+        assert_eq!(Preset::Performance.odm_performance_profile(&[]), None);
+    }
+}
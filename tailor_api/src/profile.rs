@@ -1,10 +1,22 @@
-use crate::LedControllerMode;
+use crate::{
+    version::{check_version, SCHEMA_VERSION},
+    LedControllerMode,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "ProfileInfoRepr", into = "ProfileInfoRepr")]
 pub struct ProfileInfo {
     pub fans: Vec<String>,
     pub leds: Vec<LedProfile>,
     pub performance_profile: Option<String>,
+    /// Upper limit of the CPU frequency in MHz.
+    pub max_freq_mhz: Option<u32>,
+    /// Runtime power management of USB devices, left as is if `None`.
+    pub usb_autosuspend: Option<UsbAutosuspend>,
+    /// The PCIe ASPM policy of the kernel, left as is if `None`.
+    pub pcie_aspm: Option<AspmPolicy>,
+    /// Power saving of storage and audio devices, left as is if `None`.
+    pub power_tuning: Option<PowerTuning>,
 }
 
 impl Default for ProfileInfo {
@@ -13,10 +25,237 @@ impl Default for ProfileInfo {
             fans: vec!["default".to_owned()],
             leds: Default::default(),
             performance_profile: Default::default(),
+            max_freq_mhz: Default::default(),
+            usb_autosuspend: Default::default(),
+            pcie_aspm: Default::default(),
+            power_tuning: Default::default(),
         }
     }
 }
 
+/// Serialized form of [`ProfileInfo`] along with the schema version.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ProfileInfoRepr {
+    #[serde(default)]
+    version: u32,
+    fans: Vec<String>,
+    leds: Vec<LedProfile>,
+    performance_profile: Option<String>,
+    #[serde(default)]
+    max_freq_mhz: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    usb_autosuspend: Option<UsbAutosuspend>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pcie_aspm: Option<AspmPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    power_tuning: Option<PowerTuning>,
+}
+
+impl TryFrom<ProfileInfoRepr> for ProfileInfo {
+    type Error = String;
+
+    fn try_from(repr: ProfileInfoRepr) -> Result<Self, Self::Error> {
+        let ProfileInfoRepr {
+            version,
+            fans,
+            leds,
+            performance_profile,
+            max_freq_mhz,
+            usb_autosuspend,
+            pcie_aspm,
+            power_tuning,
+        } = repr;
+        check_version(version)?;
+        Ok(Self {
+            fans,
+            leds,
+            performance_profile,
+            max_freq_mhz,
+            usb_autosuspend,
+            pcie_aspm,
+            power_tuning,
+        })
+    }
+}
+
+impl From<ProfileInfo> for ProfileInfoRepr {
+    fn from(info: ProfileInfo) -> Self {
+        let ProfileInfo {
+            fans,
+            leds,
+            performance_profile,
+            max_freq_mhz,
+            usb_autosuspend,
+            pcie_aspm,
+            power_tuning,
+        } = info;
+        Self {
+            version: SCHEMA_VERSION,
+            fans,
+            leds,
+            performance_profile,
+            max_freq_mhz,
+            usb_autosuspend,
+            pcie_aspm,
+            power_tuning,
+        }
+    }
+}
+
+/// Lets the kernel suspend idle USB devices, which saves power on battery.
+/// Without `usb_autosuspend` in a profile, the
+/// devices keep the settings of the kernel and udev rules.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct UsbAutosuspend {
+    /// Suspend idle devices. If `false`, no device is suspended.
+    pub enabled: bool,
+    /// How long a device has to be idle before it's suspended,
+    /// the default of the kernel is 2 seconds.
+    pub delay_ms: Option<u32>,
+    /// Devices that are never suspended as `vendor:product` ID,
+    /// e.g. `046d:c52b` for a receiver that drops input after waking up.
+    pub exceptions: Vec<String>,
+}
+
+impl Default for UsbAutosuspend {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            delay_ms: None,
+            exceptions: Vec::new(),
+        }
+    }
+}
+
+impl UsbAutosuspend {
+    /// Whether a device may be suspended. IDs are compared
+    /// case-insensitively, like `lsusb` prints them.
+    pub fn allows(&self, vendor: &str, product: &str) -> bool {
+        self.enabled
+            && !self.exceptions.iter().any(|id| {
+                id.split_once(':')
+                    .is_some_and(|(exception_vendor, exception_product)| {
+                        exception_vendor.eq_ignore_ascii_case(vendor)
+                            && exception_product.eq_ignore_ascii_case(product)
+                    })
+            })
+    }
+}
+
+/// How aggressively the kernel lets idle PCIe links enter power saving states
+/// (Active State Power Management).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AspmPolicy {
+    /// The settings of the firmware.
+    Default,
+    /// Disables ASPM.
+    Performance,
+    Powersave,
+    /// Like `Powersave`, but also enables the deepest link states,
+    /// which some devices don't handle well.
+    Powersupersave,
+}
+
+impl AspmPolicy {
+    pub const ALL: [Self; 4] = [
+        Self::Default,
+        Self::Performance,
+        Self::Powersave,
+        Self::Powersupersave,
+    ];
+
+    /// The name the kernel uses for the policy.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::Performance => "performance",
+            Self::Powersave => "powersave",
+            Self::Powersupersave => "powersupersave",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|policy| policy.name() == name)
+    }
+}
+
+impl std::fmt::Display for AspmPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Runtime power management of devices that keep the system awake.
+/// Settings that are `None` are left as is.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct PowerTuning {
+    /// The highest latency in µs that NVMe drives may add by entering a power state
+    /// (Autonomous Power State Transition). `0` disables APST.
+    pub nvme_max_latency_us: Option<u32>,
+    /// The link power management policy of SATA drives.
+    pub sata_link_power: Option<SataLinkPower>,
+    /// Seconds of silence after which the audio codec is powered down, `0` disables it.
+    pub audio_power_save_secs: Option<u32>,
+}
+
+impl PowerTuning {
+    /// Each setting of `self`, or of `fallback` where `self` has none.
+    pub fn or(&self, fallback: &Self) -> Self {
+        Self {
+            nvme_max_latency_us: self.nvme_max_latency_us.or(fallback.nvme_max_latency_us),
+            sata_link_power: self.sata_link_power.or(fallback.sata_link_power),
+            audio_power_save_secs: self
+                .audio_power_save_secs
+                .or(fallback.audio_power_save_secs),
+        }
+    }
+}
+
+/// The `link_power_management_policy` of SATA hosts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SataLinkPower {
+    MaxPerformance,
+    MediumPower,
+    /// Medium power with Device Initiated Power Management,
+    /// what most distributions use on battery.
+    MedPowerWithDipm,
+    /// The lowest power usage, which can lose data on some drives.
+    MinPower,
+}
+
+impl SataLinkPower {
+    pub const ALL: [Self; 4] = [
+        Self::MaxPerformance,
+        Self::MediumPower,
+        Self::MedPowerWithDipm,
+        Self::MinPower,
+    ];
+
+    /// The name the kernel uses for the policy.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::MaxPerformance => "max_performance",
+            Self::MediumPower => "medium_power",
+            Self::MedPowerWithDipm => "med_power_with_dipm",
+            Self::MinPower => "min_power",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|policy| policy.name() == name)
+    }
+}
+
+impl std::fmt::Display for SataLinkPower {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct LedProfile {
     pub device_name: String,
@@ -25,3 +264,98 @@ pub struct LedProfile {
     #[serde(default)]
     pub mode: LedControllerMode,
 }
+
+/// Where a profile is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum ProfileScope {
+    /// The configuration directory of tailord, e.g. `/etc/tailord`.
+    System,
+    /// The configuration directory of a user, e.g. `~/.config/tailor`.
+    User,
+}
+
+impl std::fmt::Display for ProfileScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::System => f.write_str("system"),
+            Self::User => f.write_str("user"),
+        }
+    }
+}
+
+/// The user that activated the global profile.
+/// If several users activate profiles, the last one wins.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct ProfileOwner {
+    pub uid: u32,
+    pub user_name: String,
+}
+
+impl std::fmt::Display for ProfileOwner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.user_name)
+    }
+}
+
+/// The order in which profiles are listed. Favorites are pinned to the top.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ProfileOrder {
+    /// Names of profiles in the order of the list.
+    pub order: Vec<String>,
+    pub favorites: Vec<String>,
+}
+
+impl ProfileOrder {
+    pub fn is_favorite(&self, name: &str) -> bool {
+        self.favorites.iter().any(|favorite| favorite == name)
+    }
+
+    /// Sort names by this order, favorites first.
+    /// Names that aren't part of the order keep their order after the others.
+    pub fn sort(&self, names: &mut [String]) {
+        names.sort_by_key(|name| {
+            let position = self.order.iter().position(|ordered| ordered == name);
+            (!self.is_favorite(name), position.unwrap_or(usize::MAX))
+        });
+    }
+
+    /// Keep the position and the favorite state of a renamed profile.
+    /// Returns whether the order changed.
+    pub fn rename(&mut self, from: &str, to: &str) -> bool {
+        let mut changed = false;
+        for name in self.order.iter_mut().chain(&mut self.favorites) {
+            if name == from {
+                *name = to.to_owned();
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Returns whether the order changed.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let len = self.order.len() + self.favorites.len();
+        self.order.retain(|ordered| ordered != name);
+        self.favorites.retain(|favorite| favorite != name);
+        len != self.order.len() + self.favorites.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ProfileOrder;
+
+    #[test]
+    fn profile_order() {
+        let order = ProfileOrder {
+            order: vec!["quiet".into(), "default".into(), "gaming".into()],
+            favorites: vec!["gaming".into()],
+        };
+        let mut names: Vec<String> = ["new", "default", "other", "gaming", "quiet"]
+            .map(Into::into)
+            .into();
+        order.sort(&mut names);
+        assert_eq!(names, ["gaming", "quiet", "default", "new", "other"]);
+    }
+}
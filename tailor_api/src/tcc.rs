@@ -0,0 +1,380 @@
+//! Conversion of the profiles of the TUXEDO Control Center (TCC).
+//!
+//! TCC exports its profiles as a JSON list. The settings file of TCC
+//! (`/etc/tcc/settings`) holds the keyboard backlight, which is shared
+//! by all of its profiles.
+
+use serde::Deserialize;
+
+use crate::{
+    Color, ColorProfile, FanProfilePoint, HardwareCapabilities, LedProfile, Preset, ProfileInfo,
+};
+
+/// The function of the LED devices of keyboards, which TCC controls.
+const KEYBOARD_FUNCTION: &str = "kbd_backlight";
+
+/// The profiles and the keyboard backlight of a TCC export.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TccImport {
+    pub profiles: Vec<TccProfile>,
+    /// `None` if the file has no keyboard backlight settings.
+    pub keyboard: Option<ColorProfile>,
+    /// Settings that tailor doesn't support and that are skipped.
+    pub warnings: Vec<String>,
+}
+
+/// A TCC profile, converted to a fan curve and the settings of a global profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TccProfile {
+    /// The name in TCC, with the characters that tailord doesn't allow replaced.
+    pub name: String,
+    pub fan: Vec<FanProfilePoint>,
+    /// The ODM performance profile, e.g. `performance`.
+    pub performance_profile: Option<String>,
+    /// Upper limit of the CPU frequency in MHz.
+    pub max_freq_mhz: Option<u32>,
+}
+
+impl TccImport {
+    /// Read a list of TCC profiles, a single profile or the settings of TCC.
+    pub fn parse(data: &str) -> Result<Self, String> {
+        let value: serde_json::Value = serde_json::from_str(data).map_err(|err| err.to_string())?;
+        let mut import = Self {
+            profiles: Vec::new(),
+            keyboard: None,
+            warnings: Vec::new(),
+        };
+        match value {
+            serde_json::Value::Array(profiles) => {
+                for profile in profiles {
+                    import.add_profile(profile)?;
+                }
+            }
+            serde_json::Value::Object(object) if object.contains_key("keyboardBacklightStates") => {
+                let settings: TccSettings = serde_json::from_value(object.into())
+                    .map_err(|err| format!("Invalid TCC settings: {err}"))?;
+                import.keyboard = settings
+                    .keyboard_backlight_states
+                    .first()
+                    .map(TccKeyboardState::color_profile);
+            }
+            value @ serde_json::Value::Object(_) => import.add_profile(value)?,
+            _ => return Err("Expected TCC profiles or TCC settings".to_owned()),
+        }
+        if import.profiles.is_empty() && import.keyboard.is_none() {
+            return Err("The file contains no TCC profiles".to_owned());
+        }
+        Ok(import)
+    }
+
+    fn add_profile(&mut self, value: serde_json::Value) -> Result<(), String> {
+        let profile: TccProfileRepr =
+            serde_json::from_value(value).map_err(|err| format!("Invalid TCC profile: {err}"))?;
+        let name = profile.name.replace(['/', '.'], "-");
+
+        let unsupported = [
+            (profile.display.use_brightness, "display brightness"),
+            (profile.display.use_ref_rate, "display refresh rate"),
+            (profile.webcam.use_status, "webcam"),
+            (profile.cpu.no_turbo, "disabled turbo boost"),
+            (
+                !profile.odm_power_limits.tdp_values.is_empty(),
+                "power limits",
+            ),
+        ];
+        for (_, setting) in unsupported.iter().filter(|(used, _)| *used) {
+            self.warnings
+                .push(format!("`{name}`: The {setting} setting is skipped"));
+        }
+
+        let fan = profile.fan.points().unwrap_or_else(|| {
+            self.warnings.push(format!(
+                "`{name}`: The fan curve `{}` is replaced by a similar curve",
+                profile.fan.fan_profile
+            ));
+            builtin_fan_curve(&profile.fan.fan_profile)
+                .fan_profile()
+                .points
+        });
+
+        self.profiles.push(TccProfile {
+            name,
+            fan,
+            performance_profile: profile.odm_profile.name,
+            // TCC uses kHz.
+            max_freq_mhz: profile.cpu.scaling_max_frequency.map(|freq| freq / 1000),
+        });
+        Ok(())
+    }
+}
+
+impl TccProfile {
+    /// A global profile for the given hardware that uses the fan profile
+    /// named after `self.name` and the `keyboard` profile for the keyboards.
+    /// Performance profiles that the hardware doesn't support are dropped.
+    pub fn global_profile(
+        &self,
+        hardware: &HardwareCapabilities,
+        keyboard: Option<&str>,
+    ) -> ProfileInfo {
+        let leds = keyboard.map_or_else(Vec::new, |keyboard| {
+            hardware
+                .led_devices
+                .iter()
+                .filter(|info| info.function.contains(KEYBOARD_FUNCTION))
+                .map(|info| LedProfile {
+                    device_name: info.device_name.clone(),
+                    function: info.function.clone(),
+                    profile: keyboard.to_owned(),
+                    mode: info.mode,
+                })
+                .collect()
+        });
+        ProfileInfo {
+            fans: vec![self.name.clone(); hardware.num_of_fans.max(1) as usize],
+            leds,
+            performance_profile: self
+                .performance_profile
+                .clone()
+                .filter(|name| hardware.performance_profiles.contains(name)),
+            max_freq_mhz: self.max_freq_mhz,
+            ..Default::default()
+        }
+    }
+}
+
+/// The preset that is closest to a built-in fan curve of TCC.
+fn builtin_fan_curve(name: &str) -> Preset {
+    match name.to_lowercase().as_str() {
+        "silent" | "quiet" => Preset::Silent,
+        "cool" | "freezy" => Preset::Performance,
+        _ => Preset::Balanced,
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TccProfileRepr {
+    name: String,
+    #[serde(default)]
+    display: TccDisplay,
+    #[serde(default)]
+    cpu: TccCpu,
+    #[serde(default)]
+    webcam: TccWebcam,
+    #[serde(default)]
+    fan: TccFan,
+    #[serde(default)]
+    odm_profile: TccOdmProfile,
+    #[serde(default)]
+    odm_power_limits: TccPowerLimits,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct TccDisplay {
+    use_brightness: bool,
+    use_ref_rate: bool,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct TccCpu {
+    scaling_max_frequency: Option<u32>,
+    no_turbo: bool,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct TccWebcam {
+    use_status: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct TccFan {
+    use_control: bool,
+    fan_profile: String,
+    minimum_fanspeed: u8,
+    maximum_fanspeed: u8,
+    offset_fanspeed: i16,
+    custom_fan_curve: Option<TccFanCurve>,
+}
+
+impl Default for TccFan {
+    fn default() -> Self {
+        Self {
+            use_control: true,
+            fan_profile: "Balanced".to_owned(),
+            minimum_fanspeed: 0,
+            maximum_fanspeed: 100,
+            offset_fanspeed: 0,
+            custom_fan_curve: None,
+        }
+    }
+}
+
+impl TccFan {
+    /// The points of the custom curve of the CPU with the limits
+    /// and the offset of the profile applied.
+    /// `None` if the profile uses a built-in curve.
+    fn points(&self) -> Option<Vec<FanProfilePoint>> {
+        let curve = self.custom_fan_curve.as_ref()?;
+        if !self.use_control || curve.table_cpu.is_empty() {
+            return None;
+        }
+        let min = self.minimum_fanspeed.min(100);
+        let max = self.maximum_fanspeed.clamp(min, 100);
+        let mut table = curve.table_cpu.clone();
+        table.sort_by_key(|entry| entry.temp);
+        table.dedup_by_key(|entry| entry.temp);
+
+        let speeds: Vec<u8> = table
+            .iter()
+            .map(|entry| {
+                i16::from(entry.speed)
+                    .saturating_add(self.offset_fanspeed)
+                    .clamp(min as i16, max as i16) as u8
+            })
+            .collect();
+        // TCC has a point every few degrees, points within flat sections are redundant.
+        let points = table
+            .iter()
+            .zip(&speeds)
+            .enumerate()
+            .filter(|(idx, (_, speed))| {
+                let previous = idx.checked_sub(1).map(|idx| speeds[idx]);
+                let next = speeds.get(idx + 1).copied();
+                previous != Some(**speed) || next != Some(**speed)
+            })
+            .map(|(_, (entry, speed))| FanProfilePoint {
+                temp: entry.temp,
+                fan: *speed,
+            })
+            .collect();
+        Some(points)
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct TccFanCurve {
+    #[serde(rename = "tableCPU")]
+    table_cpu: Vec<TccFanTableEntry>,
+}
+
+#[derive(Clone, Deserialize)]
+struct TccFanTableEntry {
+    temp: u8,
+    speed: u8,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct TccOdmProfile {
+    name: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct TccPowerLimits {
+    tdp_values: Vec<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TccSettings {
+    keyboard_backlight_states: Vec<TccKeyboardState>,
+}
+
+#[derive(Deserialize)]
+struct TccKeyboardState {
+    /// From 0 to 255 on most devices.
+    brightness: u8,
+    #[serde(default)]
+    red: u8,
+    #[serde(default)]
+    green: u8,
+    #[serde(default)]
+    blue: u8,
+}
+
+impl TccKeyboardState {
+    fn color_profile(&self) -> ColorProfile {
+        if self.brightness == 0 {
+            return ColorProfile::None;
+        }
+        let color = Color {
+            r: self.red,
+            g: self.green,
+            b: self.blue,
+        };
+        let percent = (self.brightness as u16 * 100 / u8::MAX as u16) as u8;
+        ColorProfile::Single(color.with_brightness(percent))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ColorProfile, FanProfilePoint, HardwareCapabilities};
+
+    use super::TccImport;
+
+    #[test]
+    fn tcc_import() {
+        let export = r#"[{
+            "id": "0123",
+            "name": "Work/Office",
+            "display": { "brightness": 80, "useBrightness": true, "useRefRate": false },
+            "cpu": { "scalingMaxFrequency": 3200000, "noTurbo": false },
+            "fan": {
+                "useControl": true,
+                "fanProfile": "Custom",
+                "minimumFanspeed": 10,
+                "offsetFanspeed": 0,
+                "customFanCurve": {
+                    "tableCPU": [
+                        { "temp": 30, "speed": 0 },
+                        { "temp": 40, "speed": 0 },
+                        { "temp": 50, "speed": 0 },
+                        { "temp": 60, "speed": 40 },
+                        { "temp": 70, "speed": 100 }
+                    ]
+                }
+            },
+            "odmProfile": { "name": "performance" }
+        }, {
+            "name": "Quiet",
+            "fan": { "fanProfile": "Silent" }
+        }]"#;
+        let import = TccImport::parse(export).unwrap();
+        assert_eq!(import.profiles.len(), 2);
+        assert_eq!(import.warnings.len(), 2);
+
+        let profile = &import.profiles[0];
+        assert_eq!(profile.name, "Work-Office");
+        assert_eq!(profile.max_freq_mhz, Some(3200));
+        assert_eq!(
+            profile.fan,
+            [(30, 10), (50, 10), (60, 40), (70, 100)]
+                .map(|(temp, fan)| FanProfilePoint { temp, fan })
+        );
+
+        let global = profile.global_profile(&HardwareCapabilities::default(), None);
+        assert_eq!(global.fans, ["Work-Office"]);
+        assert_eq!(global.performance_profile, None);
+
+        // Offsets far beyond the speeds don't overflow.
+        let offset = export.replace(r#""offsetFanspeed": 0"#, r#""offsetFanspeed": 32767"#);
+        let import = TccImport::parse(&offset).unwrap();
+        assert_eq!(
+            import.profiles[0].fan,
+            [(30, 100), (70, 100)].map(|(temp, fan)| FanProfilePoint { temp, fan })
+        );
+
+        let settings = r#"{ "keyboardBacklightStates": [{ "mode": 0, "brightness": 0 }] }"#;
+        let import = TccImport::parse(settings).unwrap();
+        assert!(import.profiles.is_empty());
+        assert_eq!(import.keyboard, Some(ColorProfile::None));
+    }
+}
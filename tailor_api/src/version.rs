@@ -0,0 +1,16 @@
+/// Version of the schema of the profiles that are stored by tailord.
+///
+/// Bump this for breaking changes of the stored format
+/// and add a migration from the previous version to tailord.
+/// Data without version is treated as version 0.
+pub const SCHEMA_VERSION: u32 = 1;
+
+pub(crate) fn check_version(version: u32) -> Result<(), String> {
+    if version > SCHEMA_VERSION {
+        Err(format!(
+            "Unsupported schema version {version}, the latest supported version is {SCHEMA_VERSION}"
+        ))
+    } else {
+        Ok(())
+    }
+}
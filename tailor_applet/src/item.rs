@@ -0,0 +1,108 @@
+//! The `org.kde.StatusNotifierItem` interface, which system trays
+//! like the one of waybar use to show the icon and the tooltip.
+
+use tokio::sync::mpsc::UnboundedSender;
+use zbus::{dbus_interface, dbus_proxy, zvariant::OwnedObjectPath, SignalContext};
+
+use crate::{menu::MENU_PATH, status::Status, Action};
+
+pub const ITEM_PATH: &str = "/StatusNotifierItem";
+
+/// The symbolic icon of Tailor GUI.
+const ICON_NAME: &str = "com.github.aaronerhardt.Tailor-symbolic";
+
+#[dbus_proxy(
+    interface = "org.kde.StatusNotifierWatcher",
+    default_service = "org.kde.StatusNotifierWatcher",
+    default_path = "/StatusNotifierWatcher"
+)]
+trait StatusNotifierWatcher {
+    fn register_status_notifier_item(&self, service: &str) -> zbus::Result<()>;
+}
+
+/// Icon name, pixmaps, title and description.
+type ToolTip = (String, Vec<(i32, i32, Vec<u8>)>, String, String);
+
+pub struct Item {
+    pub status: Status,
+    pub actions: UnboundedSender<Action>,
+}
+
+#[dbus_interface(name = "org.kde.StatusNotifierItem")]
+impl Item {
+    #[dbus_interface(property)]
+    fn category(&self) -> &str {
+        "Hardware"
+    }
+
+    #[dbus_interface(property)]
+    fn id(&self) -> &str {
+        "tailor"
+    }
+
+    #[dbus_interface(property)]
+    fn title(&self) -> String {
+        self.status.title()
+    }
+
+    #[dbus_interface(property)]
+    fn status(&self) -> &str {
+        if self.status.connected {
+            "Active"
+        } else {
+            "Passive"
+        }
+    }
+
+    #[dbus_interface(property)]
+    fn icon_name(&self) -> &str {
+        ICON_NAME
+    }
+
+    #[dbus_interface(property)]
+    fn tool_tip(&self) -> ToolTip {
+        (
+            ICON_NAME.to_owned(),
+            Vec::new(),
+            self.status.title(),
+            self.status.description(),
+        )
+    }
+
+    /// Clicks open the menu instead of calling `Activate`.
+    #[dbus_interface(property)]
+    fn item_is_menu(&self) -> bool {
+        true
+    }
+
+    #[dbus_interface(property)]
+    fn menu(&self) -> OwnedObjectPath {
+        OwnedObjectPath::try_from(MENU_PATH).unwrap()
+    }
+
+    fn activate(&self, _x: i32, _y: i32) {
+        self.actions.send(Action::OpenGui).ok();
+    }
+
+    fn secondary_activate(&self, _x: i32, _y: i32) {
+        self.actions.send(Action::OpenGui).ok();
+    }
+
+    fn context_menu(&self, _x: i32, _y: i32) {}
+
+    /// Scrolling over the icon switches to the previous or next profile.
+    fn scroll(&self, delta: i32, orientation: &str) {
+        if orientation.eq_ignore_ascii_case("vertical") && delta != 0 {
+            self.actions.send(Action::CycleProfile(delta.signum())).ok();
+        }
+    }
+
+    #[dbus_interface(signal)]
+    pub async fn new_title(ctxt: &SignalContext<'_>) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    pub async fn new_tool_tip(ctxt: &SignalContext<'_>) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    pub async fn new_status(ctxt: &SignalContext<'_>, status: &str) -> zbus::Result<()>;
+}
@@ -0,0 +1,194 @@
+//! A status applet for system trays, e.g. of waybar, that shows the
+//! active profile and the temperatures and switches between profiles,
+//! for desktops where the full GUI is overkill.
+//!
+//! It implements the StatusNotifierItem and dbusmenu D-Bus interfaces,
+//! so the tray draws the icon and the menu.
+
+mod item;
+mod menu;
+mod status;
+
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use tailor_client::TailorConnection;
+use tokio::sync::mpsc;
+use zbus::{Connection, ConnectionBuilder};
+
+use crate::{
+    item::{Item, StatusNotifierWatcherProxy, ITEM_PATH},
+    menu::{Menu, MENU_PATH},
+    status::Status,
+};
+
+/// How often the temperatures are refreshed.
+const UPDATE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Requests of the tray, handled by the main loop.
+#[derive(Debug)]
+pub enum Action {
+    ActivateProfile(String),
+    /// Switch to the next (`1`) or the previous (`-1`) profile.
+    CycleProfile(i32),
+    OpenGui,
+    Refresh,
+    Quit,
+}
+
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    if std::env::var_os("RUST_LOG").is_none() {
+        std::env::set_var("RUST_LOG", "info");
+    }
+
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .without_time()
+        .init();
+
+    let (sender, mut actions) = mpsc::unbounded_channel();
+    let service = format!("org.kde.StatusNotifierItem-{}-1", std::process::id());
+    let session = ConnectionBuilder::session()?
+        .name(service.as_str())?
+        .serve_at(
+            ITEM_PATH,
+            Item {
+                status: Status::default(),
+                actions: sender.clone(),
+            },
+        )?
+        .serve_at(
+            MENU_PATH,
+            Menu {
+                status: Status::default(),
+                revision: 0,
+                actions: sender,
+            },
+        )?
+        .build()
+        .await?;
+
+    // Register again whenever the tray restarts.
+    let watcher = StatusNotifierWatcherProxy::new(&session).await?;
+    let mut watcher_changes = watcher.receive_owner_changed().await?;
+    register(&watcher, &service).await;
+
+    let mut tailor = None;
+    let mut status = Status::default();
+    let mut interval = tokio::time::interval(UPDATE_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            Some(owner) = watcher_changes.next() => {
+                if owner.is_some() {
+                    register(&watcher, &service).await;
+                }
+                continue;
+            }
+            Some(action) = actions.recv() => {
+                match action {
+                    Action::Quit => return Ok(()),
+                    Action::OpenGui => open_gui(),
+                    Action::Refresh => {}
+                    action => {
+                        if let Some(connection) = &tailor {
+                            if let Err(err) = handle(connection, &status, action).await {
+                                tracing::warn!("Failed to switch the profile: `{err}`");
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        if tailor.is_none() {
+            tailor = TailorConnection::new().await.ok();
+        }
+        let new_status = match &tailor {
+            Some(connection) => match Status::fetch(connection).await {
+                Ok(status) => status,
+                Err(err) => {
+                    tracing::debug!("Lost the connection to tailord: `{err}`");
+                    tailor = None;
+                    Status::default()
+                }
+            },
+            None => Status::default(),
+        };
+        if new_status != status {
+            status = new_status;
+            publish(&session, &status).await?;
+        }
+    }
+}
+
+async fn register(watcher: &StatusNotifierWatcherProxy<'_>, service: &str) {
+    match watcher.register_status_notifier_item(service).await {
+        Ok(()) => tracing::info!("Registered with the system tray"),
+        Err(err) => tracing::warn!("No system tray available yet: `{err}`"),
+    }
+}
+
+async fn handle(
+    connection: &TailorConnection<'static>,
+    status: &Status,
+    action: Action,
+) -> eyre::Result<()> {
+    let name = match action {
+        Action::ActivateProfile(name) => name,
+        Action::CycleProfile(step) => {
+            let count = status.profiles.len() as i32;
+            let Some(active) = status
+                .profiles
+                .iter()
+                .position(|name| name == &status.active_profile)
+            else {
+                return Ok(());
+            };
+            let next = (active as i32 + step).rem_euclid(count);
+            status.profiles[next as usize].clone()
+        }
+        _ => return Ok(()),
+    };
+    connection.set_active_global_profile_name(&name).await?;
+    connection.reload().await?;
+    Ok(())
+}
+
+/// Show the status in the tray.
+async fn publish(session: &Connection, status: &Status) -> zbus::Result<()> {
+    let server = session.object_server();
+
+    let item = server.interface::<_, Item>(ITEM_PATH).await?;
+    item.get_mut().await.status = status.clone();
+    let ctxt = item.signal_context();
+    Item::new_title(ctxt).await?;
+    Item::new_tool_tip(ctxt).await?;
+    Item::new_status(
+        ctxt,
+        if status.connected {
+            "Active"
+        } else {
+            "Passive"
+        },
+    )
+    .await?;
+
+    let menu = server.interface::<_, Menu>(MENU_PATH).await?;
+    let revision = {
+        let mut menu = menu.get_mut().await;
+        menu.status = status.clone();
+        menu.revision += 1;
+        menu.revision
+    };
+    Menu::layout_updated(menu.signal_context(), revision, 0).await
+}
+
+/// Start Tailor GUI, or show its window if it's already running.
+fn open_gui() {
+    if let Err(err) = std::process::Command::new("tailor_gui").spawn() {
+        tracing::warn!("Failed to start Tailor GUI: `{err}`");
+    }
+}
@@ -0,0 +1,205 @@
+//! The `com.canonical.dbusmenu` interface, the menu that trays show
+//! when the icon is clicked. It lists the temperatures and switches
+//! between the profiles.
+
+use std::collections::HashMap;
+
+use tokio::sync::mpsc::UnboundedSender;
+use zbus::{
+    dbus_interface, fdo,
+    zvariant::{OwnedValue, StructureBuilder, Value},
+    SignalContext,
+};
+
+use crate::{status::Status, Action};
+
+pub const MENU_PATH: &str = "/MenuBar";
+
+const ROOT: i32 = 0;
+const OPEN_GUI: i32 = 1;
+const QUIT: i32 = 2;
+const SENSOR_SEPARATOR: i32 = 3;
+const PROFILE_SEPARATOR: i32 = 4;
+/// Ids of the lines with the temperatures, one per fan.
+const FIRST_SENSOR: i32 = 100;
+/// Ids of the profiles, in the order of the profile list.
+const FIRST_PROFILE: i32 = 1000;
+
+type Properties = HashMap<String, OwnedValue>;
+/// An entry with its id, properties and children.
+type Layout = (i32, Properties, Vec<OwnedValue>);
+
+pub struct Menu {
+    pub status: Status,
+    /// Incremented whenever the entries change.
+    pub revision: u32,
+    pub actions: UnboundedSender<Action>,
+}
+
+impl Menu {
+    fn entries(&self) -> Vec<(i32, Properties)> {
+        let mut entries = Vec::new();
+        if !self.status.connected {
+            entries.push((FIRST_SENSOR, label("tailord isn't running", false)));
+        }
+        for (idx, line) in self.status.fan_lines().into_iter().enumerate() {
+            entries.push((FIRST_SENSOR + idx as i32, label(&line, false)));
+        }
+        entries.push((SENSOR_SEPARATOR, separator()));
+
+        for (idx, name) in self.status.profiles.iter().enumerate() {
+            let mut properties = label(name, true);
+            properties.insert("toggle-type".to_owned(), Value::from("radio").into());
+            let active = name == &self.status.active_profile;
+            properties.insert("toggle-state".to_owned(), Value::from(active as i32).into());
+            entries.push((FIRST_PROFILE + idx as i32, properties));
+        }
+        if !self.status.profiles.is_empty() {
+            entries.push((PROFILE_SEPARATOR, separator()));
+        }
+
+        entries.push((OPEN_GUI, label("Open Tailor", true)));
+        entries.push((QUIT, label("Quit", true)));
+        entries
+    }
+
+    fn properties(&self, id: i32) -> Option<Properties> {
+        if id == ROOT {
+            let children = Value::from("submenu").into();
+            return Some(HashMap::from([("children-display".to_owned(), children)]));
+        }
+        self.entries()
+            .into_iter()
+            .find(|(entry, _)| *entry == id)
+            .map(|(_, properties)| properties)
+    }
+
+    fn clicked(&self, id: i32) -> bool {
+        let action = match id {
+            OPEN_GUI => Action::OpenGui,
+            QUIT => Action::Quit,
+            id if id >= FIRST_PROFILE => {
+                match self.status.profiles.get((id - FIRST_PROFILE) as usize) {
+                    Some(name) => Action::ActivateProfile(name.clone()),
+                    None => return false,
+                }
+            }
+            _ => return self.properties(id).is_some(),
+        };
+        self.actions.send(action).ok();
+        true
+    }
+}
+
+#[dbus_interface(name = "com.canonical.dbusmenu")]
+impl Menu {
+    /// All entries are children of the root, so the depth doesn't matter.
+    fn get_layout(
+        &self,
+        parent_id: i32,
+        _recursion_depth: i32,
+        _property_names: Vec<String>,
+    ) -> fdo::Result<(u32, Layout)> {
+        let properties = self
+            .properties(parent_id)
+            .ok_or_else(|| fdo::Error::InvalidArgs(format!("No menu entry with id {parent_id}")))?;
+        let children = if parent_id == ROOT {
+            self.entries()
+                .into_iter()
+                .map(|(id, properties)| {
+                    let layout = StructureBuilder::new()
+                        .add_field(id)
+                        .add_field(properties)
+                        .add_field(Vec::<OwnedValue>::new())
+                        .build();
+                    Value::from(layout).into()
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        Ok((self.revision, (parent_id, properties, children)))
+    }
+
+    fn get_group_properties(
+        &self,
+        ids: Vec<i32>,
+        _property_names: Vec<String>,
+    ) -> Vec<(i32, Properties)> {
+        ids.into_iter()
+            .filter_map(|id| Some((id, self.properties(id)?)))
+            .collect()
+    }
+
+    fn get_property(&self, id: i32, name: String) -> fdo::Result<OwnedValue> {
+        self.properties(id)
+            .and_then(|mut properties| properties.remove(&name))
+            .ok_or_else(|| fdo::Error::InvalidArgs(format!("No property `{name}` for id {id}")))
+    }
+
+    fn event(&self, id: i32, event_id: String, _data: OwnedValue, _timestamp: u32) {
+        if event_id == "clicked" {
+            self.clicked(id);
+        }
+    }
+
+    /// Returns the ids that weren't found.
+    fn event_group(&self, events: Vec<(i32, String, OwnedValue, u32)>) -> Vec<i32> {
+        events
+            .into_iter()
+            .filter(|(id, event_id, _, _)| event_id == "clicked" && !self.clicked(*id))
+            .map(|(id, _, _, _)| id)
+            .collect()
+    }
+
+    /// Refresh the temperatures when the menu opens, the new layout follows with a signal.
+    fn about_to_show(&self, _id: i32) -> bool {
+        self.actions.send(Action::Refresh).ok();
+        false
+    }
+
+    fn about_to_show_group(&self, _ids: Vec<i32>) -> (Vec<i32>, Vec<i32>) {
+        self.actions.send(Action::Refresh).ok();
+        (Vec::new(), Vec::new())
+    }
+
+    #[dbus_interface(property)]
+    fn version(&self) -> u32 {
+        3
+    }
+
+    #[dbus_interface(property)]
+    fn text_direction(&self) -> &str {
+        "ltr"
+    }
+
+    #[dbus_interface(property)]
+    fn status(&self) -> &str {
+        "normal"
+    }
+
+    #[dbus_interface(property)]
+    fn icon_theme_path(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[dbus_interface(signal)]
+    pub async fn layout_updated(
+        ctxt: &SignalContext<'_>,
+        revision: u32,
+        parent: i32,
+    ) -> zbus::Result<()>;
+}
+
+fn label(text: &str, enabled: bool) -> Properties {
+    // Underscores mark mnemonics, profile names are shown as they are.
+    let text = text.replace('_', "__");
+    HashMap::from([
+        ("label".to_owned(), Value::from(text).into()),
+        ("enabled".to_owned(), Value::from(enabled).into()),
+    ])
+}
+
+fn separator() -> Properties {
+    HashMap::from([("type".to_owned(), Value::from("separator").into())])
+}
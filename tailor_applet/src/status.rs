@@ -0,0 +1,60 @@
+use tailor_api::FanStatus;
+use tailor_client::{ClientResult, TailorConnection};
+
+/// What the applet shows, refreshed periodically.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Status {
+    /// `false` while tailord isn't running.
+    pub connected: bool,
+    pub active_profile: String,
+    /// All global profiles, in the order of the profile list.
+    pub profiles: Vec<String>,
+    pub fans: Vec<FanStatus>,
+}
+
+impl Status {
+    pub async fn fetch(connection: &TailorConnection<'_>) -> ClientResult<Self> {
+        Ok(Self {
+            connected: true,
+            active_profile: connection.get_active_global_profile_name().await?,
+            profiles: connection.list_global_profiles().await?,
+            fans: connection.get_fan_status().await?,
+        })
+    }
+
+    /// The hottest sensor, in °C.
+    pub fn max_temp(&self) -> Option<u8> {
+        self.fans.iter().map(|fan| fan.temp).max()
+    }
+
+    /// A short line for panels that show the title next to the icon.
+    pub fn title(&self) -> String {
+        if !self.connected {
+            return "Tailor".to_owned();
+        }
+        match self.max_temp() {
+            Some(temp) => format!("{} · {temp}°C", self.active_profile),
+            None => self.active_profile.clone(),
+        }
+    }
+
+    /// One line per fan, e.g. `Fan 1: 54°C, 40%`.
+    pub fn fan_lines(&self) -> Vec<String> {
+        self.fans
+            .iter()
+            .map(|fan| {
+                let speed = fan.reported_fan_speed.unwrap_or(fan.fan_speed);
+                format!("Fan {}: {}°C, {speed}%", fan.fan_idx + 1, fan.temp)
+            })
+            .collect()
+    }
+
+    pub fn description(&self) -> String {
+        if !self.connected {
+            return "tailord isn't running".to_owned();
+        }
+        let mut lines = vec![format!("Profile: {}", self.active_profile)];
+        lines.extend(self.fan_lines());
+        lines.join("\n")
+    }
+}
@@ -4,7 +4,7 @@ use clap_mangen::Man;
 
 use std::{
     env,
-    fs::{create_dir_all, File},
+    fs::{self, create_dir_all, File},
     path::Path,
 };
 
@@ -12,6 +12,7 @@ include!("src/cli.rs");
 
 fn main() {
     println!("cargo:rerun-if-env-changed=GEN_ARTIFACTS");
+    println!("cargo:rerun-if-changed=completions");
 
     if let Some(dir) = env::var_os("GEN_ARTIFACTS") {
         let out = &Path::new(&dir);
@@ -23,7 +24,41 @@ fn main() {
             .unwrap();
 
         for shell in Shell::value_variants() {
-            generate_to(*shell, cmd, "tailor", out).unwrap();
+            let path = generate_to(*shell, cmd, "tailor", out).unwrap();
+            add_live_completions(*shell, &path);
         }
     }
 }
+
+/// Let the generated completions query fan indices and LED devices
+/// from tailord via `tailor complete`, other shells only get static values.
+fn add_live_completions(shell: Shell, path: &Path) {
+    let script = fs::read_to_string(path).unwrap();
+    let script = match shell {
+        Shell::Bash => format!("{}{script}", include_str!("completions/live.bash"))
+            .replace("complete -F _tailor ", "complete -F _tailor_live "),
+        Shell::Fish => format!("{script}{}", include_str!("completions/live.fish")),
+        Shell::Zsh => {
+            let script = script
+                .replace(":FAN: '", ":FAN:_tailor_live fans'")
+                .replace(":DEVICE: '", ":DEVICE:_tailor_live leds'")
+                .lines()
+                .map(|line| match line.strip_suffix(":' \\") {
+                    Some(spec) if line.starts_with("':fan -- ") => {
+                        format!("{spec}:_tailor_live fans' \\\n")
+                    }
+                    _ => format!("{line}\n"),
+                })
+                .collect::<String>();
+            script.replace(
+                "if [ \"$funcstack[1]\" = \"_tailor\" ]",
+                &format!(
+                    "{}if [ \"$funcstack[1]\" = \"_tailor\" ]",
+                    include_str!("completions/live.zsh")
+                ),
+            )
+        }
+        _ => return,
+    };
+    fs::write(path, script).unwrap();
+}
@@ -0,0 +1,25 @@
+# Complete fan indices and LED devices with the values of tailord.
+_tailor_live() {
+    local cur="${COMP_WORDS[COMP_CWORD]}" prev="${COMP_WORDS[COMP_CWORD-1]}"
+    local words=() word values=""
+    for word in "${COMP_WORDS[@]:1:COMP_CWORD-1}"; do
+        [[ ${word} != -* ]] && words+=("${word}")
+    done
+    case "${words[0]} ${words[1]}" in
+        "fan override"|"fan release")
+            [[ ${cur} != -* && ${#words[@]} -eq 2 && ${prev} != --for ]] && values=fans
+            ;;
+        "fan show")
+            [[ ${prev} == --fan ]] && values=fans
+            ;;
+        "led set"|"led reset")
+            [[ ${prev} == -d || ${prev} == --device ]] && values=leds
+            ;;
+    esac
+    if [[ -n ${values} ]]; then
+        COMPREPLY=( $(compgen -W "$(tailor complete ${values} 2>/dev/null)" -- "${cur}") )
+    else
+        _tailor "$@"
+    fi
+}
+
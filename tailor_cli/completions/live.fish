@@ -0,0 +1,8 @@
+
+# Complete fan indices and LED devices with the values of tailord.
+function __fish_tailor_positional_count
+    count (string match -v -- '-*' (commandline -opc))
+end
+complete -c tailor -n "__fish_seen_subcommand_from fan; and __fish_seen_subcommand_from override release; and test (__fish_tailor_positional_count) -eq 3" -f -a "(tailor complete fans 2>/dev/null)"
+complete -c tailor -n "__fish_seen_subcommand_from fan; and __fish_seen_subcommand_from show" -l fan -x -a "(tailor complete fans 2>/dev/null)"
+complete -c tailor -n "__fish_seen_subcommand_from led; and __fish_seen_subcommand_from set reset" -s d -l device -x -a "(tailor complete leds 2>/dev/null)"
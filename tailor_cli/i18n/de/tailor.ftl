@@ -0,0 +1,193 @@
+# Meldungen der tailor CLI.
+# Die Hilfetexte der Befehle werden von clap erzeugt und bleiben englisch.
+
+## Fehler
+
+error = Fehler: { $message }
+error-cause = Ursache: { $message }
+error-not-supported = Von diesem Gerät nicht unterstützt: { $message }
+error-invalid-profile = Ungültiges Profil: { $message }
+error-hardware = Hardwarefehler: { $message }
+error-hardware-errno = Hardwarefehler: { $message } (errno { $errno })
+error-permission-denied = Zugriff verweigert: { $message }
+error-already-exists = Existiert bereits: { $message }
+error-profile-rolled-back = Das Profil konnte nicht angewendet werden, das vorherige Profil wurde wiederhergestellt: { $message }
+error-profile-partially-applied = Das Profil wurde nur teilweise angewendet: { $message }
+
+## Akku
+
+battery-conservation-on = Der Akku-Schonmodus ist an
+battery-conservation-off = Der Akku-Schonmodus ist aus
+battery-conservation-unsupported = Die Firmware hat keinen Akku-Schonmodus
+battery-charge = Ladung: { $capacity }% ({ $status })
+battery-status-charging = lädt
+battery-status-discharging = entlädt
+battery-status-not-charging = lädt nicht
+battery-status-full = voll
+battery-status-unknown = unbekannter Zustand
+battery-power = Leistung: { $power } W
+battery-threshold-start = Laden beginnt unter { $threshold }%
+battery-threshold-end = Laden endet bei { $threshold }%
+battery-health = Zustand: { $health }%
+battery-health-capacity = Kapazität: { $full } { $unit } von { $design } { $unit } im Neuzustand
+battery-health-cycles = Ladezyklen: { $cycles }
+battery-health-change = Veränderung seit { $date }: { $change } Prozentpunkte
+battery-column-date = Datum
+battery-column-health = Zustand
+battery-column-capacity = Kapazität
+battery-column-cycles = Zyklen
+battery-calibration-unsupported = Es gibt keinen Akku zum Kalibrieren
+battery-calibration-idle = Es wurde keine Akku-Kalibrierung gestartet
+battery-calibration-charging = Laden bis voll ({ $capacity }%)
+battery-calibration-discharging = Entladen bis { $target }% ({ $capacity }%)
+battery-calibration-recharging = Erneutes Laden bis voll ({ $capacity }%)
+battery-calibration-finished = Die Akku-Kalibrierung ist abgeschlossen
+battery-calibration-cancelled = Die Akku-Kalibrierung wurde abgebrochen
+battery-calibration-failed = Die Akku-Kalibrierung ist fehlgeschlagen, siehe das Log von tailord
+battery-calibration-plug-in = Schließe das Ladegerät an, um fortzufahren
+battery-calibration-unplug = Trenne das Ladegerät, um fortzufahren
+battery-calibration-progress = Fortschritt: { $progress }%
+
+## Webcam
+
+webcam-on = Die Webcam ist an
+webcam-off = Die Webcam ist in der Hardware ausgeschaltet
+webcam-unsupported = Das Gerät kann die Webcam nicht ein- und ausschalten
+
+## Eingabe
+
+touchpad-on = Das Touchpad ist an
+touchpad-off = Das Touchpad ist in der Firmware ausgeschaltet
+touchpad-unsupported = Das Gerät kann das Touchpad nicht ein- und ausschalten
+fn-lock-on = Die Fn-Sperre ist an, die Funktionstasten senden F1-F12
+fn-lock-off = Die Fn-Sperre ist aus
+fn-lock-unsupported = Der Treiber stellt die Fn-Sperre nicht bereit
+
+## Lüfter
+
+fan-title = Lüfter { $fan }
+fan-not-found = Lüfter { $fan } existiert nicht
+fan-temperature-raw = { $temp }°C (roh: { $raw }°C)
+fan-speed-override = { $speed }% (überschrieben)
+fan-column-fan = Lüfter
+fan-column-temperature = Temperatur
+fan-column-speed = Drehzahl
+fan-column-target = Ziel
+fan-column-reported = Gemeldet
+fan-column-synchronized = Synchronisiert
+fan-synchronized-yes = ja
+fan-synchronized-no = nein
+fan-inhibited = Die Firmware steuert die Lüfter, bis tailor beendet wird (Strg+C)
+fan-profile-synchronized = synchronisiert: alle Lüfter folgen der höchsten Temperatur
+tune-started = Die Lüfterdrehzahlen werden gemessen, während `{ $command }` läuft, das dauert eine Weile
+tune-measuring = Messung bei { $speed }%…
+tune-too-hot = Die Lüfter haben bei { $speed }% { $temp }°C erreicht, die niedrigeren Drehzahlen werden übersprungen
+tune-load-exited = Der Lastbefehl wurde vor dem Ende der Messungen beendet ({ $status })
+tune-no-fans = Das Gerät hat keine Lüfter, die tailord steuert
+tune-column-speed = Drehzahl
+tune-column-temperature = Temperatur
+tune-column-reported = Gemeldet
+tune-column-steady = Eingependelt
+tune-steady-yes = ja
+tune-steady-no = nein (Zeitüberschreitung)
+tune-proposal = Vorgeschlagene Lüfterkurve für { $target }°C unter Last:
+tune-save-prompt = Als Lüfterprofil speichern (leer lassen zum Verwerfen):
+tune-saved = Das Lüfterprofil `{ $name }` wurde gespeichert
+tune-discarded = Die Lüfterkurve wurde nicht gespeichert, mit --save <name> wird sie gespeichert
+throttle-critical = Die CPU drosselt wegen Überhitzung, obwohl alle Lüfter mit 100% laufen
+throttle = Die CPU drosselt wegen Überhitzung
+
+## LEDs
+
+led-mode-rgb = RGB
+led-mode-monochrome = einfarbig
+led-unknown = unbekannt
+led-color-override = { $color } (überschrieben)
+led-column-device = Gerät
+led-column-mode = Modus
+led-column-color = Farbe
+led-column-brightness = Helligkeit
+led-column-parameter = Parameter
+led-column-value = Wert
+led-no-parameters = Das geladene tuxedo_keyboard hat keine Parameter für die Beleuchtung
+led-invalid-color = Ungültige Farbe `{ $color }`
+
+## Profile
+
+profile-active = aktiv
+profile-user = Benutzer
+profile-favorite = Favorit
+profile-set-by = gesetzt von { $owner }
+profile-current = Aktuelles Profil: { $profile }
+profile-updated = Profil geändert
+profile-show-performance = Leistungsprofil: { $profile }
+profile-show-performance-default = Standard
+profile-show-max-freq = CPU-Frequenzgrenze: { $freq } MHz
+profile-show-usb-autosuspend = USB-Autosuspend: { $settings }
+profile-show-pcie-aspm = PCIe-ASPM-Richtlinie: { $policy }
+profile-show-power-tuning = Energiesparen der Geräte: { $settings }
+profile-show-fan = Lüfter { $fan }: { $profile }
+profile-show-fan-synchronized = Lüfter { $fan }: { $profile } (synchronisiert)
+profile-show-led = { $device }: { $profile }
+profile-show-led-off = aus
+profile-show-led-typing = { $idle }, gedrückte Tasten { $pressed }
+profile-show-led-regions = { $count } Tastaturbereiche
+profile-diff-field = Feld
+profile-diff-active = { $profile } (aktiv)
+profile-diff-none = Die Profile sind identisch
+profile-diff-fan-override = Lüfter { $fan } bleibt bei { $speed }%, bis das Überschreiben aufgehoben wird
+profile-diff-led-override = { $device } behält die Farbe { $color }, bis das Überschreiben zurückgesetzt wird
+profile-preview-none = Das Aktivieren des Profils würde nichts ändern
+profile-preview-device = Gerät
+profile-preview-attribute = Attribut
+profile-preview-old = Aktuell
+profile-preview-new = Neu
+profile-preview-unknown = unbekannt
+profile-defaults-restored = Die Standardprofile wurden wiederhergestellt
+profile-unknown-format = Unbekanntes Format von { $path }, erwartet wird eine .json- oder .toml-Datei
+profile-same-file = Eingabe- und Ausgabedatei sind identisch
+profile-converted = { $input } wurde nach { $output } konvertiert
+profile-valid = { $path } ist gültig
+profile-invalid = { $path }: { $error }
+profile-invalid-files = { $count } ungültige Profildatei(en)
+
+## Interaktive Oberfläche
+
+tui-help = ↑/↓ Profil wählen · Enter aktivieren · ←/→ Lüfter wählen · r neu laden · q beenden
+tui-profiles = Profile
+tui-profile-active = { $profile } (aktiv)
+tui-profile-activated = Profil `{ $profile }` aktiviert
+tui-profile-activation-failed = Profil `{ $profile }` konnte nicht aktiviert werden: { $error }
+tui-profiles-failed = Profile konnten nicht geladen werden: { $error }
+tui-sensors = Sensoren
+tui-sensors-throttling = Sensoren - CPU drosselt
+tui-sensors-critical = Sensoren - CPU drosselt trotz voller Lüfterdrehzahl
+tui-sensors-failed = Sensoren konnten nicht gelesen werden: { $error }
+tui-temperature = Temp.
+tui-speed = Drehzahl
+tui-target = Ziel
+tui-fan-curve = Lüfterkurve
+tui-fan-curve-of = Lüfter { $fan } Kurve: { $profile }
+tui-no-fan-profile = Kein Lüfterprofil
+tui-current = aktuell
+
+## Ereignisse
+
+event-profile-switch = Profil `{ $profile }` aktiviert
+event-profile-switch-by = Profil `{ $profile }` von { $user } aktiviert
+event-fan-pinned = Lüfter { $fan } auf { $speed }% festgesetzt
+event-fan-released = Lüfter { $fan } freigegeben
+event-led-all-devices = alle LED-Geräte
+event-led-color = Farbe von { $device } auf { $color } gesetzt
+event-led-released = Farbe von { $device } freigegeben
+event-critical-temperature = Die Temperatur von { $temp }°C hat den kritischen Grenzwert von { $limit }°C überschritten
+event-critical-temperature-recovered = Die Temperatur von { $temp }°C liegt wieder unter dem kritischen Grenzwert
+event-fan-fault = Lüfter { $fan } ist ausgefallen: { $error }
+event-throttling = Die CPU drosselt, obwohl alle Lüfter mit 100% laufen
+event-throttling-stopped = Die CPU drosselt nicht mehr
+
+## Fehlersuche
+
+debug-ioctl-attribute = Attribut
+debug-ioctl-value = Wert
+debug-ioctl-written = { $value } wurde nach { $name } geschrieben
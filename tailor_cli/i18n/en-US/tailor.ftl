@@ -0,0 +1,193 @@
+# Messages of the tailor CLI.
+# The help texts of the commands are generated by clap and stay in English.
+
+## Errors
+
+error = Error: { $message }
+error-cause = Caused by: { $message }
+error-not-supported = Not supported by this device: { $message }
+error-invalid-profile = Invalid profile: { $message }
+error-hardware = Hardware error: { $message }
+error-hardware-errno = Hardware error: { $message } (errno { $errno })
+error-permission-denied = Permission denied: { $message }
+error-already-exists = Already exists: { $message }
+error-profile-rolled-back = The profile couldn't be applied, the previous profile was restored: { $message }
+error-profile-partially-applied = The profile was only applied partly: { $message }
+
+## Battery
+
+battery-conservation-on = Battery conservation mode is on
+battery-conservation-off = Battery conservation mode is off
+battery-conservation-unsupported = The firmware has no battery conservation mode
+battery-charge = Charge: { $capacity }% ({ $status })
+battery-status-charging = charging
+battery-status-discharging = discharging
+battery-status-not-charging = not charging
+battery-status-full = full
+battery-status-unknown = unknown state
+battery-power = Rate: { $power } W
+battery-threshold-start = Charging starts below { $threshold }%
+battery-threshold-end = Charging stops at { $threshold }%
+battery-health = Health: { $health }%
+battery-health-capacity = Capacity: { $full } { $unit } of { $design } { $unit } when new
+battery-health-cycles = Charge cycles: { $cycles }
+battery-health-change = Change since { $date }: { $change } percentage points
+battery-column-date = Date
+battery-column-health = Health
+battery-column-capacity = Capacity
+battery-column-cycles = Cycles
+battery-calibration-unsupported = There's no battery to calibrate
+battery-calibration-idle = No battery calibration was started
+battery-calibration-charging = Charging to full ({ $capacity }%)
+battery-calibration-discharging = Discharging to { $target }% ({ $capacity }%)
+battery-calibration-recharging = Charging to full again ({ $capacity }%)
+battery-calibration-finished = The battery calibration is finished
+battery-calibration-cancelled = The battery calibration was cancelled
+battery-calibration-failed = The battery calibration failed, see the log of tailord
+battery-calibration-plug-in = Plug in the charger to continue
+battery-calibration-unplug = Unplug the charger to continue
+battery-calibration-progress = Progress: { $progress }%
+
+## Webcam
+
+webcam-on = The webcam is on
+webcam-off = The webcam is turned off in the hardware
+webcam-unsupported = The device can't turn the webcam on and off
+
+## Input
+
+touchpad-on = The touchpad is on
+touchpad-off = The touchpad is turned off in the firmware
+touchpad-unsupported = The device can't turn the touchpad on and off
+fn-lock-on = The Fn-lock is on, the function keys send F1-F12
+fn-lock-off = The Fn-lock is off
+fn-lock-unsupported = The driver doesn't expose the Fn-lock
+
+## Fans
+
+fan-title = Fan { $fan }
+fan-not-found = Fan { $fan } doesn't exist
+fan-temperature-raw = { $temp }°C (raw: { $raw }°C)
+fan-speed-override = { $speed }% (override)
+fan-column-fan = Fan
+fan-column-temperature = Temperature
+fan-column-speed = Speed
+fan-column-target = Target
+fan-column-reported = Reported
+fan-column-synchronized = Synchronized
+fan-synchronized-yes = yes
+fan-synchronized-no = no
+fan-inhibited = The fans are controlled by the firmware until tailor is stopped (Ctrl+C)
+fan-profile-synchronized = synchronized: all fans follow the highest temperature
+tune-started = Measuring the fan speeds while `{ $command }` runs, this takes a while
+tune-measuring = Measuring at { $speed }%…
+tune-too-hot = The fans reached { $temp }°C at { $speed }%, skipping the lower speeds
+tune-load-exited = The load command exited ({ $status }) before the measurements were done
+tune-no-fans = The device has no fans that tailord controls
+tune-column-speed = Speed
+tune-column-temperature = Temperature
+tune-column-reported = Reported
+tune-column-steady = Settled
+tune-steady-yes = yes
+tune-steady-no = no (timeout)
+tune-proposal = Proposed fan curve for { $target }°C under the load:
+tune-save-prompt = Save as fan profile (leave empty to discard):
+tune-saved = Saved the fan profile `{ $name }`
+tune-discarded = The fan curve wasn't saved, pass --save <name> to save it
+throttle-critical = The CPU is thermally throttling although all fans run at 100%
+throttle = The CPU is thermally throttling
+
+## LEDs
+
+led-mode-rgb = RGB
+led-mode-monochrome = monochrome
+led-unknown = unknown
+led-color-override = { $color } (override)
+led-column-device = Device
+led-column-mode = Mode
+led-column-color = Color
+led-column-brightness = Brightness
+led-column-parameter = Parameter
+led-column-value = Value
+led-no-parameters = The loaded tuxedo_keyboard has no backlight parameters
+led-invalid-color = Invalid color `{ $color }`
+
+## Profiles
+
+profile-active = active
+profile-user = user
+profile-favorite = favorite
+profile-set-by = set by { $owner }
+profile-current = Current profile: { $profile }
+profile-updated = Profile updated
+profile-show-performance = performance profile: { $profile }
+profile-show-performance-default = default
+profile-show-max-freq = CPU frequency limit: { $freq } MHz
+profile-show-usb-autosuspend = USB autosuspend: { $settings }
+profile-show-pcie-aspm = PCIe ASPM policy: { $policy }
+profile-show-power-tuning = Device power tuning: { $settings }
+profile-show-fan = fan { $fan }: { $profile }
+profile-show-fan-synchronized = fan { $fan }: { $profile } (synchronized)
+profile-show-led = { $device }: { $profile }
+profile-show-led-off = off
+profile-show-led-typing = { $idle }, pressed keys { $pressed }
+profile-show-led-regions = { $count } keyboard regions
+profile-diff-field = Field
+profile-diff-active = { $profile } (active)
+profile-diff-none = The profiles are the same
+profile-diff-fan-override = Fan { $fan } stays at { $speed }% until its override is released
+profile-diff-led-override = { $device } keeps the color { $color } until its override is reset
+profile-preview-none = Activating the profile wouldn't change anything
+profile-preview-device = Device
+profile-preview-attribute = Attribute
+profile-preview-old = Current
+profile-preview-new = New
+profile-preview-unknown = unknown
+profile-defaults-restored = Restored the default profiles
+profile-unknown-format = Unknown format of { $path }, expected a .json or .toml file
+profile-same-file = The input and output files are the same
+profile-converted = Converted { $input } to { $output }
+profile-valid = { $path } is valid
+profile-invalid = { $path }: { $error }
+profile-invalid-files = { $count } invalid profile file(s)
+
+## Interactive interface
+
+tui-help = ↑/↓ select profile · enter activate · ←/→ select fan · r refresh · q quit
+tui-profiles = Profiles
+tui-profile-active = { $profile } (active)
+tui-profile-activated = Activated profile `{ $profile }`
+tui-profile-activation-failed = Failed to activate profile `{ $profile }`: { $error }
+tui-profiles-failed = Failed to load profiles: { $error }
+tui-sensors = Sensors
+tui-sensors-throttling = Sensors - throttling
+tui-sensors-critical = Sensors - throttling at full fan speed
+tui-sensors-failed = Failed to read sensors: { $error }
+tui-temperature = Temp
+tui-speed = Speed
+tui-target = Target
+tui-fan-curve = Fan curve
+tui-fan-curve-of = Fan { $fan } curve: { $profile }
+tui-no-fan-profile = No fan profile
+tui-current = current
+
+## Events
+
+event-profile-switch = Activated profile `{ $profile }`
+event-profile-switch-by = Activated profile `{ $profile }` by { $user }
+event-fan-pinned = Pinned fan { $fan } at { $speed }%
+event-fan-released = Released fan { $fan }
+event-led-all-devices = all LED devices
+event-led-color = Set color of { $device } to { $color }
+event-led-released = Released color of { $device }
+event-critical-temperature = Temperature { $temp }°C exceeded the critical limit of { $limit }°C
+event-critical-temperature-recovered = Temperature { $temp }°C is below the critical limit again
+event-fan-fault = Fan { $fan } failed: { $error }
+event-throttling = The CPU is throttling although all fans run at 100%
+event-throttling-stopped = The CPU stopped throttling
+
+## Debugging
+
+debug-ioctl-attribute = Attribute
+debug-ioctl-value = Value
+debug-ioctl-written = Wrote { $value } to { $name }
@@ -0,0 +1,208 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use eyre::Result;
+use tailor_api::{
+    BatteryHealth, BatteryHealthSample, BatteryState, BatteryStatus, CalibrationPhase,
+    CalibrationStatus,
+};
+use tailor_client::TailorConnection;
+
+use crate::{
+    cli::{BatteryCommand, CalibrationAction, Toggle},
+    exit::{CliError, ErrorKind},
+    i18n::tr,
+    output::Table,
+};
+
+/// Handle battery commands
+pub(crate) async fn handle(cmd: BatteryCommand) -> Result<()> {
+    let connection = TailorConnection::new().await?;
+    match cmd {
+        BatteryCommand::Status { history } => {
+            print_state(&connection.get_battery_state().await?);
+            if connection
+                .get_hardware_capabilities()
+                .await?
+                .battery_conservation
+            {
+                print_conservation(connection.get_battery_conservation().await?);
+            }
+            // Not all batteries report their design capacity.
+            if let Ok(health) = connection.get_battery_health().await {
+                let samples = connection.get_battery_health_history().await?;
+                print_health(&health, &samples);
+                if history && !samples.is_empty() {
+                    println!();
+                    print_history(&samples);
+                }
+            }
+        }
+        BatteryCommand::Conservation { state } => {
+            if !connection
+                .get_hardware_capabilities()
+                .await?
+                .battery_conservation
+            {
+                eyre::bail!(CliError::new(
+                    ErrorKind::NotSupported,
+                    tr!("battery-conservation-unsupported")
+                ));
+            }
+            let enabled = match state {
+                Some(state) => {
+                    let enabled = state == Toggle::On;
+                    connection.set_battery_conservation(enabled).await?;
+                    enabled
+                }
+                None => connection.get_battery_conservation().await?,
+            };
+            print_conservation(enabled);
+        }
+        BatteryCommand::Calibration { action } => {
+            if !connection
+                .get_hardware_capabilities()
+                .await?
+                .battery_calibration
+            {
+                eyre::bail!(CliError::new(
+                    ErrorKind::NotSupported,
+                    tr!("battery-calibration-unsupported")
+                ));
+            }
+            match action {
+                Some(CalibrationAction::Start) => connection.start_battery_calibration().await?,
+                Some(CalibrationAction::Cancel) => connection.cancel_battery_calibration().await?,
+                None => {}
+            }
+            print_calibration(&connection.get_battery_calibration_status().await?);
+        }
+    }
+    Ok(())
+}
+
+fn print_state(state: &BatteryState) {
+    let status = match state.status {
+        BatteryStatus::Charging => tr!("battery-status-charging"),
+        BatteryStatus::Discharging => tr!("battery-status-discharging"),
+        BatteryStatus::NotCharging => tr!("battery-status-not-charging"),
+        BatteryStatus::Full => tr!("battery-status-full"),
+        BatteryStatus::Unknown => tr!("battery-status-unknown"),
+    };
+    println!(
+        "{}",
+        tr!("battery-charge", capacity = state.capacity, status = status)
+    );
+    if let Some(power) = state.power_mw.filter(|power| *power > 0) {
+        let power = format!("{:.1}", f64::from(power) / 1000.0);
+        println!("{}", tr!("battery-power", power = power));
+    }
+    if let Some(threshold) = state.start_threshold {
+        println!("{}", tr!("battery-threshold-start", threshold = threshold));
+    }
+    if let Some(threshold) = state.end_threshold {
+        println!("{}", tr!("battery-threshold-end", threshold = threshold));
+    }
+}
+
+fn print_conservation(enabled: bool) {
+    if enabled {
+        println!("{}", tr!("battery-conservation-on"));
+    } else {
+        println!("{}", tr!("battery-conservation-off"));
+    }
+}
+
+fn print_health(health: &BatteryHealth, samples: &[BatteryHealthSample]) {
+    println!("{}", tr!("battery-health", health = percentage(health)));
+    println!(
+        "{}",
+        tr!(
+            "battery-health-capacity",
+            full = health.full_capacity,
+            design = health.design_capacity,
+            unit = health.unit.to_string()
+        )
+    );
+    if let Some(cycles) = health.cycle_count {
+        println!("{}", tr!("battery-health-cycles", cycles = cycles));
+    }
+    if let Some(first) = samples.first() {
+        let change = health.percentage() - first.health.percentage();
+        println!(
+            "{}",
+            tr!(
+                "battery-health-change",
+                date = date(first.timestamp),
+                change = format!("{change:+.1}")
+            )
+        );
+    }
+}
+
+fn print_history(samples: &[BatteryHealthSample]) {
+    let mut table = Table::new([
+        tr!("battery-column-date"),
+        tr!("battery-column-health"),
+        tr!("battery-column-capacity"),
+        tr!("battery-column-cycles"),
+    ]);
+    for BatteryHealthSample { timestamp, health } in samples {
+        table.row([
+            date(*timestamp),
+            format!("{}%", percentage(health)),
+            format!("{} {}", health.full_capacity, health.unit),
+            health
+                .cycle_count
+                .map(|cycles| cycles.to_string())
+                .unwrap_or_else(|| "-".to_owned()),
+        ]);
+    }
+    table.print();
+}
+
+fn percentage(health: &BatteryHealth) -> String {
+    format!("{:.0}", health.percentage())
+}
+
+/// The day of a timestamp in UTC, e.g. `2024-03-01`.
+fn date(timestamp: u64) -> String {
+    let time = UNIX_EPOCH + Duration::from_secs(timestamp);
+    let mut date = humantime::format_rfc3339_seconds(time).to_string();
+    date.truncate(10);
+    date
+}
+
+fn print_calibration(status: &CalibrationStatus) {
+    let capacity = status.capacity;
+    let phase = match status.phase {
+        CalibrationPhase::Idle => tr!("battery-calibration-idle"),
+        CalibrationPhase::Charging => tr!("battery-calibration-charging", capacity = capacity),
+        CalibrationPhase::Discharging => tr!(
+            "battery-calibration-discharging",
+            target = status.discharge_to,
+            capacity = capacity
+        ),
+        CalibrationPhase::Recharging => {
+            tr!("battery-calibration-recharging", capacity = capacity)
+        }
+        CalibrationPhase::Finished => tr!("battery-calibration-finished"),
+        CalibrationPhase::Cancelled => tr!("battery-calibration-cancelled"),
+        CalibrationPhase::Failed => tr!("battery-calibration-failed"),
+    };
+    println!("{phase}");
+    if !status.phase.is_running() {
+        return;
+    }
+    let progress = (status.progress() * 100.0).round() as u8;
+    println!(
+        "{}",
+        tr!("battery-calibration-progress", progress = progress)
+    );
+    if status.needs_charger_change() {
+        if status.plugged_in {
+            println!("{}", tr!("battery-calibration-unplug"));
+        } else {
+            println!("{}", tr!("battery-calibration-plug-in"));
+        }
+    }
+}
@@ -4,6 +4,14 @@ use clap::{Parser, Subcommand};
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 pub(crate) struct Opts {
+    /// Don't use colors, even if the output is a terminal.
+    /// Colors are also disabled if `NO_COLOR` is set.
+    #[arg(long, global = true)]
+    pub no_color: bool,
+    /// Print errors as JSON to stderr. The exit code tells the kind
+    /// of the error in both cases, see the README.
+    #[arg(long, global = true)]
+    pub json_errors: bool,
     #[command(subcommand)]
     pub command: Option<Command>,
 }
@@ -15,18 +23,155 @@ pub(crate) enum Command {
         #[command(subcommand)]
         profile_cmd: ProfileCommand,
     },
+    /// LED commands
+    Led {
+        #[command(subcommand)]
+        led_cmd: LedCommand,
+    },
+    /// Fan commands
+    Fan {
+        #[command(subcommand)]
+        fan_cmd: FanCommand,
+    },
+    /// Battery commands
+    Battery {
+        #[command(subcommand)]
+        battery_cmd: BatteryCommand,
+    },
+    /// Show whether the webcam is on, or turn it on or off
+    Webcam {
+        /// Turn the webcam on or off
+        #[arg()]
+        state: Option<Toggle>,
+    },
+    /// Control input devices like the touchpad and the Fn-lock
+    Input {
+        #[command(subcommand)]
+        input_cmd: InputCommand,
+    },
+    /// Show the latest events like profile switches, overrides and thermal emergencies
+    Events {
+        /// The maximum number of events to show
+        #[arg(long, short, default_value_t = 50)]
+        limit: u32,
+    },
+    /// Interactive interface with live sensors, fan curves and profile switching
+    Tui,
+    /// Commands for developers, require root
+    Debug {
+        #[command(subcommand)]
+        debug_cmd: DebugCommand,
+    },
+    /// Print the values of an argument for shell completions
+    #[command(hide = true)]
+    Complete {
+        #[arg()]
+        values: CompletionValues,
+    },
+}
+
+/// Arguments whose values are queried from tailord when completing them.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CompletionValues {
+    /// Fan indices
+    Fans,
+    /// LED device ids
+    Leds,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum BatteryCommand {
+    /// Show the charge, charging rate, charge thresholds and health of the battery
+    Status {
+        /// Show the daily records of the health
+        #[arg(long)]
+        history: bool,
+    },
+    /// Show or set the conservation mode, which limits the charge
+    /// to extend the lifespan of batteries that are mostly plugged in
+    Conservation {
+        /// Turn the conservation mode on or off
+        #[arg()]
+        state: Option<Toggle>,
+    },
+    /// Show the progress of the battery calibration, or start or cancel it.
+    /// The calibration charges the battery to full, discharges it
+    /// and charges it to full again, so the firmware learns its capacity
+    Calibration {
+        #[arg()]
+        action: Option<CalibrationAction>,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum InputCommand {
+    /// Show whether the touchpad is on, or turn it on or off in the firmware
+    Touchpad {
+        /// Turn the touchpad on or off
+        #[arg()]
+        state: Option<Toggle>,
+    },
+    /// Show or set the Fn-lock, which makes the function keys send F1-F12 without holding Fn
+    FnLock {
+        /// Turn the Fn-lock on or off
+        #[arg()]
+        state: Option<Toggle>,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum DebugCommand {
+    /// Read raw attributes of the tuxedo_io driver, e.g. to report driver bugs.
+    /// Requires tailord to be built with the `debug-ioctl` feature.
+    Ioctl {
+        /// The attribute, e.g. `uw.fan_temp_0`. Reads all attributes if omitted
+        #[arg()]
+        name: Option<String>,
+        /// Write a raw value instead, bypassing all limits of tailord.
+        /// Requires tailord to be built with the `debug-ioctl-writes` feature
+        #[arg(long, requires = "name", allow_negative_numbers = true)]
+        write: Option<i32>,
+    },
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CalibrationAction {
+    Start,
+    Cancel,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Toggle {
+    On,
+    Off,
 }
 
 #[derive(Subcommand, Debug, Clone)]
 pub(crate) enum ProfileCommand {
     /// List profile names
-    List,
+    List {
+        /// Also show the fan curves, LED settings and performance profile of each profile
+        #[arg(long, short)]
+        detailed: bool,
+    },
+
+    /// Show the fan curves, LED settings and performance profile of a profile
+    Show {
+        /// The name of the profile (see: list)
+        #[arg()]
+        name: String,
+    },
 
     /// Set the active profile
+    #[command(visible_alias = "apply")]
     Set {
         /// The name of the profile to set (see: list)
         #[arg()]
         name: String,
+
+        /// Only show the values that tailord would change
+        #[arg(long)]
+        dry_run: bool,
     },
 
     /// Cycle profiles
@@ -39,4 +184,213 @@ pub(crate) enum ProfileCommand {
         #[arg(long, short)]
         notify: bool,
     },
+
+    /// Show what switching to a profile changes
+    Diff {
+        /// The profile to switch to
+        #[arg()]
+        to: String,
+
+        /// Compare with this profile instead of the active profile
+        #[arg(long, short)]
+        from: Option<String>,
+    },
+
+    /// Restore the default profile and the presets (silent, balanced, performance
+    /// and battery-max), discarding changes to them
+    ResetDefaults,
+
+    /// Convert a profile file between JSON and TOML
+    Convert {
+        /// The profile file, e.g. /etc/tailord/fan/default.json
+        #[arg()]
+        input: std::path::PathBuf,
+
+        /// The converted file. Defaults to the input with the other extension
+        #[arg(long, short)]
+        output: Option<std::path::PathBuf>,
+
+        /// Keep the input file. tailord prefers JSON if a profile exists in both formats
+        #[arg(long, short)]
+        keep: bool,
+    },
+
+    /// Check profile files for errors and show which field is invalid.
+    /// Files in a `fan` or `keyboard` directory are checked as fan or keyboard profiles
+    Validate {
+        /// The profile files, e.g. /etc/tailord/profiles/*.json
+        #[arg(required = true)]
+        files: Vec<std::path::PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum LedCommand {
+    /// List all LED devices and their current state
+    List,
+
+    /// Set the color of LED devices until the active profile is reloaded
+    Set {
+        /// Only change the LED device with this id (see: list)
+        #[arg(long, short)]
+        device: Option<String>,
+
+        /// The color as hexadecimal RGB value, e.g. ff0000
+        #[arg(long, short, default_value = "ffffff")]
+        color: String,
+
+        /// The brightness in percent
+        #[arg(long, short, default_value_t = 100, value_parser = clap::value_parser!(u8).range(0..=100))]
+        brightness: u8,
+    },
+
+    /// Discard a color set via `set` and continue with the active profile
+    Reset {
+        /// Only reset the LED device with this id (see: list)
+        #[arg(long, short)]
+        device: Option<String>,
+    },
+
+    /// Show or set the parameters of tuxedo_keyboard, for keyboards that
+    /// aren't LED devices. Tailord restores them when it starts
+    Param {
+        /// The parameter, e.g. color_left, mode, brightness or state
+        name: Option<String>,
+
+        /// The new value, e.g. ff0000 for colors or on and off for the state
+        #[arg(requires = "name")]
+        value: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum FanCommand {
+    /// Show the temperatures and speeds of all fans
+    Status,
+
+    /// Pin a fan at a fixed speed instead of following the active profile
+    Override {
+        /// The index of the fan, starting at 0
+        #[arg()]
+        fan: u8,
+
+        /// The fan speed in percent
+        #[arg(value_parser = clap::value_parser!(u8).range(0..=100))]
+        percent: u8,
+
+        /// Return to the active profile after this duration, e.g. 30s, 10m or 1h
+        #[arg(long = "for", value_parser = parse_duration)]
+        duration: Option<std::time::Duration>,
+    },
+
+    /// Release a fan speed override and return to the active profile
+    Release {
+        /// The index of the fan, starting at 0
+        #[arg()]
+        fan: u8,
+    },
+
+    /// Leave the fans to the firmware while a command runs, e.g. a firmware update.
+    /// Without a command, the fans stay with the firmware until tailor is stopped.
+    Inhibit {
+        /// Why the fans are taken over
+        #[arg(long, default_value = "Exclusive access to the embedded controller")]
+        reason: String,
+
+        /// The command to run and its arguments
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+
+    /// Show the points of a fan profile
+    Show {
+        /// The name of the fan profile
+        #[arg()]
+        name: String,
+
+        /// Render the fan curve as a chart
+        #[arg(long)]
+        plot: bool,
+
+        /// Mark the current temperature and speed of this fan in the chart
+        #[arg(long, requires = "plot")]
+        fan: Option<u8>,
+    },
+
+    /// Measure the temperatures that fixed fan speeds hold while a command
+    /// puts load on the device, and propose a fan curve from them
+    Tune {
+        /// The fan speeds to measure in percent
+        #[arg(
+            long,
+            value_delimiter = ',',
+            default_values_t = [100u8, 80, 60, 40, 20],
+            value_parser = clap::value_parser!(u8).range(0..=100),
+        )]
+        levels: Vec<u8>,
+
+        /// The temperature in °C that the proposed curve holds under the load
+        #[arg(long, default_value_t = 80, value_parser = clap::value_parser!(u8).range(40..=90))]
+        target: u8,
+
+        /// Skip the lower speeds once a fan reaches this temperature in °C
+        #[arg(long, default_value_t = 90, value_parser = clap::value_parser!(u8).range(50..=100))]
+        max_temp: u8,
+
+        /// The longest time to wait for the temperature to settle at each speed
+        #[arg(long, default_value = "5m", value_parser = parse_duration)]
+        settle: std::time::Duration,
+
+        /// Save the proposed curve as fan profile with this name instead of asking
+        #[arg(long)]
+        save: Option<String>,
+
+        /// The load command and its arguments, e.g. `stress-ng --cpu 0`
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
+        command: Vec<String>,
+    },
+}
+
+/// Parse a duration with an optional unit (s, m or h). Defaults to seconds.
+fn parse_duration(value: &str) -> Result<std::time::Duration, String> {
+    let value = value.trim();
+    let (number, factor) = match value.char_indices().last() {
+        Some((idx, 's')) => (&value[..idx], 1),
+        Some((idx, 'm')) => (&value[..idx], 60),
+        Some((idx, 'h')) => (&value[..idx], 60 * 60),
+        _ => (value, 1),
+    };
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("Invalid duration `{value}`"))?;
+    if number == 0 {
+        Err("The duration can't be zero".to_owned())
+    } else {
+        Ok(std::time::Duration::from_secs(
+            number.saturating_mul(factor),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::parse_duration;
+
+    #[test]
+    fn duration() {
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("10m"), Ok(Duration::from_secs(600)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(7200)));
+        // Seconds without a unit.
+        assert_eq!(parse_duration(" 45 "), Ok(Duration::from_secs(45)));
+
+        assert!(parse_duration("0m").is_err());
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("1d").is_err());
+        assert!(parse_duration("-5s").is_err());
+        assert!(parse_duration("1.5h").is_err());
+    }
 }
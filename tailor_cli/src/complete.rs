@@ -0,0 +1,27 @@
+use tailor_client::TailorConnection;
+
+use crate::cli::CompletionValues;
+
+/// Print the current values of an argument, one per line.
+/// Errors are ignored, shells then fall back to no suggestions.
+pub(crate) async fn handle(values: CompletionValues) {
+    let Ok(connection) = TailorConnection::new().await else {
+        return;
+    };
+    match values {
+        CompletionValues::Fans => {
+            if let Ok(fans) = connection.get_number_of_fans().await {
+                for fan in 0..fans {
+                    println!("{fan}");
+                }
+            }
+        }
+        CompletionValues::Leds => {
+            if let Ok(devices) = connection.get_led_devices().await {
+                for device in devices {
+                    println!("{}", device.device_id());
+                }
+            }
+        }
+    }
+}
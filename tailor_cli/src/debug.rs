@@ -0,0 +1,40 @@
+use eyre::Result;
+use tailor_client::TailorConnection;
+
+use crate::{
+    cli::DebugCommand,
+    i18n::tr,
+    output::{Cell, Style, Table},
+};
+
+/// Handle debug commands
+pub(crate) async fn handle(cmd: DebugCommand) -> Result<()> {
+    let connection = TailorConnection::new().await?;
+    match cmd {
+        DebugCommand::Ioctl {
+            name: Some(name),
+            write: Some(value),
+        } => {
+            connection.write_ioctl(&name, value).await?;
+            println!("{}", tr!("debug-ioctl-written", name = name, value = value));
+        }
+        DebugCommand::Ioctl {
+            name: Some(name),
+            write: None,
+        } => println!("{}", connection.read_ioctl(&name).await?),
+        DebugCommand::Ioctl { name: None, .. } => {
+            let (readable, _) = connection.list_ioctl_attributes().await?;
+            let mut table = Table::new([tr!("debug-ioctl-attribute"), tr!("debug-ioctl-value")]);
+            for name in readable {
+                // Attributes of the other interface fail on every device.
+                let value = match connection.read_ioctl(&name).await {
+                    Ok(value) => Cell::new(value),
+                    Err(err) => Cell::new(err.to_string()).style(Style::Dimmed),
+                };
+                table.row([Cell::new(name), value]);
+            }
+            table.print();
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,262 @@
+//! Field by field comparison of profiles.
+
+use std::collections::BTreeMap;
+
+use tailor_api::{
+    FanProfileInfo, FanProfilePoint, PidControl, PowerTuning, ProfileInfo, TemperatureSmoothing,
+    UsbAutosuspend,
+};
+
+/// A profile along with its fan profiles.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ResolvedProfile {
+    pub info: ProfileInfo,
+    /// The fan profile of each fan, in the order of `info.fans`.
+    pub fan_profiles: Vec<FanProfileInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Change {
+    /// The name of the field in the profile file.
+    pub field: String,
+    pub from: String,
+    pub to: String,
+}
+
+const UNSET: &str = "-";
+
+/// The fields that differ between the profiles.
+pub(crate) fn profiles(from: &ResolvedProfile, to: &ResolvedProfile) -> Vec<Change> {
+    let mut changes = Vec::new();
+    let mut push = |field: String, from: Option<String>, to: Option<String>| {
+        if from != to {
+            changes.push(Change {
+                field,
+                from: from.unwrap_or_else(|| UNSET.to_owned()),
+                to: to.unwrap_or_else(|| UNSET.to_owned()),
+            });
+        }
+    };
+
+    let fans = from.info.fans.len().max(to.info.fans.len());
+    for idx in 0..fans {
+        push(
+            format!("fans[{idx}]"),
+            from.info.fans.get(idx).cloned(),
+            to.info.fans.get(idx).cloned(),
+        );
+        // Fan profiles with different names can still have the same settings.
+        let (from_fan, to_fan) = (from.fan_profiles.get(idx), to.fan_profiles.get(idx));
+        push(
+            format!("fans[{idx}].points"),
+            from_fan.map(|fan| curve(&fan.points)),
+            to_fan.map(|fan| curve(&fan.points)),
+        );
+        push(
+            format!("fans[{idx}].smoothing"),
+            from_fan.map(|fan| smoothing(fan.smoothing)),
+            to_fan.map(|fan| smoothing(fan.smoothing)),
+        );
+        push(
+            format!("fans[{idx}].synchronized"),
+            from_fan.map(|fan| fan.synchronized.to_string()),
+            to_fan.map(|fan| fan.synchronized.to_string()),
+        );
+        push(
+            format!("fans[{idx}].pid"),
+            from_fan.and_then(|fan| fan.pid).map(pid),
+            to_fan.and_then(|fan| fan.pid).map(pid),
+        );
+    }
+
+    let leds = |info: &ProfileInfo| -> BTreeMap<String, String> {
+        info.leds
+            .iter()
+            .map(|led| {
+                (
+                    format!("{}::{}", led.device_name, led.function),
+                    led.profile.clone(),
+                )
+            })
+            .collect()
+    };
+    let (from_leds, to_leds) = (leds(&from.info), leds(&to.info));
+    let mut devices: Vec<&String> = from_leds.keys().chain(to_leds.keys()).collect();
+    devices.sort();
+    devices.dedup();
+    for device in devices {
+        push(
+            format!("leds[{device}]"),
+            from_leds.get(device).cloned(),
+            to_leds.get(device).cloned(),
+        );
+    }
+
+    push(
+        "performance_profile".to_owned(),
+        from.info.performance_profile.clone(),
+        to.info.performance_profile.clone(),
+    );
+    push(
+        "max_freq_mhz".to_owned(),
+        from.info.max_freq_mhz.map(|freq| freq.to_string()),
+        to.info.max_freq_mhz.map(|freq| freq.to_string()),
+    );
+    push(
+        "usb_autosuspend".to_owned(),
+        from.info.usb_autosuspend.as_ref().map(usb_autosuspend),
+        to.info.usb_autosuspend.as_ref().map(usb_autosuspend),
+    );
+    push(
+        "pcie_aspm".to_owned(),
+        from.info.pcie_aspm.map(|policy| policy.to_string()),
+        to.info.pcie_aspm.map(|policy| policy.to_string()),
+    );
+    push(
+        "power_tuning".to_owned(),
+        from.info.power_tuning.as_ref().map(power_tuning),
+        to.info.power_tuning.as_ref().map(power_tuning),
+    );
+    changes
+}
+
+/// The USB autosuspend settings on a single line, with the field names of the profile file.
+pub(crate) fn usb_autosuspend(settings: &UsbAutosuspend) -> String {
+    let UsbAutosuspend {
+        enabled,
+        delay_ms,
+        exceptions,
+    } = settings;
+    let mut fields = vec![format!("enabled={enabled}")];
+    if let Some(delay_ms) = delay_ms {
+        fields.push(format!("delay_ms={delay_ms}"));
+    }
+    if !exceptions.is_empty() {
+        fields.push(format!("exceptions={}", exceptions.join(",")));
+    }
+    fields.join(" ")
+}
+
+/// The device power tuning on a single line, with the field names of the profile file.
+pub(crate) fn power_tuning(tuning: &PowerTuning) -> String {
+    let PowerTuning {
+        nvme_max_latency_us,
+        sata_link_power,
+        audio_power_save_secs,
+    } = tuning;
+    let mut fields = Vec::new();
+    if let Some(latency_us) = nvme_max_latency_us {
+        fields.push(format!("nvme_max_latency_us={latency_us}"));
+    }
+    if let Some(policy) = sata_link_power {
+        fields.push(format!("sata_link_power={policy}"));
+    }
+    if let Some(secs) = audio_power_save_secs {
+        fields.push(format!("audio_power_save_secs={secs}"));
+    }
+    fields.join(" ")
+}
+
+/// The temperature smoothing on a single line, with the names of the profile file.
+fn smoothing(smoothing: TemperatureSmoothing) -> String {
+    match smoothing {
+        TemperatureSmoothing::None => "None".to_owned(),
+        TemperatureSmoothing::MovingAverage { window } => format!("MovingAverage window={window}"),
+        TemperatureSmoothing::Exponential { factor } => format!("Exponential factor={factor}"),
+    }
+}
+
+/// The PID controller on a single line, with the field names of the profile file.
+fn pid(pid: PidControl) -> String {
+    let PidControl {
+        target_temp,
+        kp,
+        ki,
+        kd,
+    } = pid;
+    format!("target_temp={target_temp} kp={kp} ki={ki} kd={kd}")
+}
+
+/// A fan curve on a single line.
+pub(crate) fn curve(points: &[FanProfilePoint]) -> String {
+    points
+        .iter()
+        .map(|FanProfilePoint { temp, fan }| format!("{temp}°C:{fan}%"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod test {
+    use tailor_api::{
+        FanProfileInfo, FanProfilePoint, LedProfile, ProfileInfo, TemperatureSmoothing,
+    };
+
+    use super::{profiles, Change, ResolvedProfile};
+
+    fn led(device_name: &str, profile: &str) -> LedProfile {
+        LedProfile {
+            device_name: device_name.to_owned(),
+            function: "kbd_backlight".to_owned(),
+            profile: profile.to_owned(),
+            mode: Default::default(),
+        }
+    }
+
+    #[test]
+    fn changed_fields() {
+        let fan = FanProfileInfo::from(vec![FanProfilePoint { temp: 50, fan: 40 }]);
+        let from = ResolvedProfile {
+            info: ProfileInfo {
+                fans: vec!["silent".to_owned(), "silent".to_owned()],
+                leds: vec![led("a", "off"), led("b", "blue")],
+                performance_profile: None,
+                max_freq_mhz: Some(2000),
+                usb_autosuspend: None,
+                pcie_aspm: None,
+                power_tuning: None,
+            },
+            fan_profiles: vec![fan.clone(), fan.clone()],
+        };
+        let to = ResolvedProfile {
+            info: ProfileInfo {
+                fans: vec!["silent".to_owned(), "loud".to_owned()],
+                leds: vec![led("b", "red")],
+                performance_profile: Some("performance".to_owned()),
+                max_freq_mhz: Some(2000),
+                usb_autosuspend: None,
+                pcie_aspm: None,
+                power_tuning: None,
+            },
+            fan_profiles: vec![fan.clone(), fan.clone()],
+        };
+
+        let change = |field: &str, from: &str, to: &str| Change {
+            field: field.to_owned(),
+            from: from.to_owned(),
+            to: to.to_owned(),
+        };
+        assert_eq!(
+            profiles(&from, &to),
+            [
+                change("fans[1]", "silent", "loud"),
+                change("leds[a::kbd_backlight]", "off", "-"),
+                change("leds[b::kbd_backlight]", "blue", "red"),
+                change("performance_profile", "-", "performance"),
+            ]
+        );
+        assert!(profiles(&to, &to).is_empty());
+
+        // Fan profiles that only differ in their settings.
+        let mut changed = to.clone();
+        changed.fan_profiles[0].smoothing = TemperatureSmoothing::Exponential { factor: 20 };
+        changed.fan_profiles[1].synchronized = true;
+        assert_eq!(
+            profiles(&to, &changed),
+            [
+                change("fans[0].smoothing", "None", "Exponential factor=20"),
+                change("fans[1].synchronized", "false", "true"),
+            ]
+        );
+    }
+}
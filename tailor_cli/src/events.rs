@@ -0,0 +1,64 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use colored::Colorize;
+use eyre::Result;
+use tailor_api::EventKind;
+use tailor_client::TailorConnection;
+
+use crate::i18n::tr;
+
+/// Print the latest events
+pub(crate) async fn handle(limit: u32) -> Result<()> {
+    let connection = TailorConnection::new().await?;
+    for event in connection.get_events(limit).await? {
+        let time = UNIX_EPOCH + Duration::from_secs(event.timestamp);
+        let time = humantime::format_rfc3339_seconds(time).to_string();
+        println!("{} {}", time.dimmed(), message(event.kind));
+    }
+    Ok(())
+}
+
+fn message(kind: EventKind) -> String {
+    match kind {
+        EventKind::ProfileSwitch {
+            profile,
+            user: Some(user),
+        } => tr!("event-profile-switch-by", profile = profile, user = user),
+        EventKind::ProfileSwitch {
+            profile,
+            user: None,
+        } => tr!("event-profile-switch", profile = profile),
+        EventKind::FanSpeedOverride {
+            fan_idx,
+            speed: Some(speed),
+        } => tr!("event-fan-pinned", fan = fan_idx, speed = speed),
+        EventKind::FanSpeedOverride {
+            fan_idx,
+            speed: None,
+        } => tr!("event-fan-released", fan = fan_idx),
+        EventKind::LedColorOverride { device, color } => {
+            let device = device.unwrap_or_else(|| tr!("event-led-all-devices"));
+            match color {
+                Some(color) => tr!(
+                    "event-led-color",
+                    device = device,
+                    color = color.to_string()
+                ),
+                None => tr!("event-led-released", device = device),
+            }
+        }
+        EventKind::CriticalTemperature { temp, limit } => {
+            tr!("event-critical-temperature", temp = temp, limit = limit)
+        }
+        EventKind::CriticalTemperatureRecovered { temp } => {
+            tr!("event-critical-temperature-recovered", temp = temp)
+        }
+        EventKind::FanFault { fan_idx, error } => {
+            tr!("event-fan-fault", fan = fan_idx, error = error)
+        }
+        EventKind::ThermalThrottling { active: true } => tr!("event-throttling"),
+        EventKind::ThermalThrottling { active: false } => tr!("event-throttling-stopped"),
+        // Events of newer daemons aren't translated yet.
+        kind => kind.to_string(),
+    }
+}
@@ -0,0 +1,229 @@
+//! Exit codes and machine-readable errors, so scripts can tell failures apart.
+//! The codes are part of the interface and don't change between versions.
+//!
+//! | Code | Meaning                                      |
+//! |------|----------------------------------------------|
+//! | 0    | Success                                      |
+//! | 1    | Any other error                              |
+//! | 2    | Invalid arguments                            |
+//! | 3    | tailord or the system bus can't be reached   |
+//! | 4    | Permission denied                            |
+//! | 5    | A profile is missing or invalid              |
+//! | 6    | Not supported by the hardware or by tailord  |
+//! | 7    | Accessing the hardware failed                |
+
+use std::fmt::Display;
+
+use tailor_api::TailorError;
+use tailor_client::ClientError;
+use zbus::fdo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ErrorKind {
+    Other,
+    InvalidArguments,
+    DaemonUnreachable,
+    PermissionDenied,
+    InvalidProfile,
+    NotSupported,
+    Hardware,
+}
+
+impl ErrorKind {
+    pub(crate) fn exit_code(self) -> i32 {
+        match self {
+            Self::Other => 1,
+            // The same code as clap uses for invalid arguments.
+            Self::InvalidArguments => 2,
+            Self::DaemonUnreachable => 3,
+            Self::PermissionDenied => 4,
+            Self::InvalidProfile => 5,
+            Self::NotSupported => 6,
+            Self::Hardware => 7,
+        }
+    }
+
+    /// The kind of the first error in the chain that has one.
+    pub(crate) fn of(report: &eyre::Report) -> Self {
+        report.chain().find_map(kind_of).unwrap_or(Self::Other)
+    }
+}
+
+fn kind_of(err: &(dyn std::error::Error + 'static)) -> Option<ErrorKind> {
+    if let Some(err) = err.downcast_ref::<CliError>() {
+        Some(err.kind)
+    } else if let Some(err) = err.downcast_ref::<ClientError>() {
+        match (err.tailor_error(), err.bus_error()) {
+            (Some(err), _) => Some(tailor_error_kind(err)),
+            (None, Some(err)) => bus_error_kind(err),
+            (None, None) => None,
+        }
+    } else if let Some(err) = err.downcast_ref::<fdo::Error>() {
+        bus_error_kind(err)
+    } else if err.downcast_ref::<zbus::Error>().is_some() {
+        // Only connecting to the bus returns plain zbus errors.
+        Some(ErrorKind::DaemonUnreachable)
+    } else {
+        None
+    }
+}
+
+fn tailor_error_kind(err: &TailorError) -> ErrorKind {
+    match err {
+        TailorError::NotSupported { .. } => ErrorKind::NotSupported,
+        // A name that is taken is an invalid name for a new profile.
+        TailorError::InvalidProfile { .. } | TailorError::AlreadyExists { .. } => {
+            ErrorKind::InvalidProfile
+        }
+        TailorError::HardwareError { .. } | TailorError::PartiallyApplied { .. } => {
+            ErrorKind::Hardware
+        }
+        TailorError::PermissionDenied { .. } => ErrorKind::PermissionDenied,
+    }
+}
+
+fn bus_error_kind(err: &fdo::Error) -> Option<ErrorKind> {
+    match err {
+        fdo::Error::ServiceUnknown(_)
+        | fdo::Error::NameHasNoOwner(_)
+        | fdo::Error::NoServer(_)
+        | fdo::Error::NoReply(_)
+        | fdo::Error::Disconnected(_)
+        | fdo::Error::Timeout(_)
+        | fdo::Error::TimedOut(_)
+        | fdo::Error::ZBus(_) => Some(ErrorKind::DaemonUnreachable),
+        fdo::Error::AccessDenied(_)
+        | fdo::Error::AuthFailed(_)
+        | fdo::Error::InteractiveAuthorizationRequired(_) => Some(ErrorKind::PermissionDenied),
+        // Older versions of tailord reject invalid profiles without a typed error.
+        fdo::Error::InvalidArgs(_) | fdo::Error::FileNotFound(_) | fdo::Error::FileExists(_) => {
+            Some(ErrorKind::InvalidProfile)
+        }
+        fdo::Error::NotSupported(_) => Some(ErrorKind::NotSupported),
+        // Older versions of tailord lack newer methods.
+        fdo::Error::UnknownMethod(_)
+        | fdo::Error::UnknownInterface(_)
+        | fdo::Error::UnknownObject(_)
+        | fdo::Error::UnknownProperty(_) => Some(ErrorKind::NotSupported),
+        _ => None,
+    }
+}
+
+/// An error of the CLI itself, e.g. of a local file.
+#[derive(Debug)]
+pub(crate) struct CliError {
+    kind: ErrorKind,
+    message: String,
+}
+
+impl CliError {
+    pub(crate) fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// Print an error as a single line of JSON, e.g.
+/// `{"kind":"daemon-unreachable","exit_code":3,"message":"...","causes":[]}`.
+/// The messages of tailord aren't translated.
+pub(crate) fn print_json(report: &eyre::Report) {
+    #[derive(serde::Serialize)]
+    struct JsonError<'a> {
+        kind: ErrorKind,
+        exit_code: i32,
+        message: String,
+        causes: Vec<String>,
+        /// The error of tailord with its details, e.g. the errno.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        tailor_error: Option<&'a TailorError>,
+    }
+
+    let kind = ErrorKind::of(report);
+    let mut messages = report.chain().map(ToString::to_string);
+    let error = JsonError {
+        kind,
+        exit_code: kind.exit_code(),
+        message: messages.next().unwrap_or_default(),
+        causes: messages.collect(),
+        tailor_error: report
+            .chain()
+            .filter_map(|err| err.downcast_ref::<ClientError>())
+            .find_map(ClientError::tailor_error),
+    };
+    eprintln!("{}", serde_json::to_string(&error).unwrap());
+}
+
+#[cfg(test)]
+mod test {
+    use clap::Parser;
+    use tailor_api::TailorError;
+    use tailor_client::ClientError;
+    use zbus::fdo;
+
+    use super::{CliError, ErrorKind};
+    use crate::cli::Opts;
+
+    fn exit_code(err: impl Into<eyre::Report>) -> i32 {
+        ErrorKind::of(&err.into()).exit_code()
+    }
+
+    fn tailor_error(err: TailorError) -> ClientError {
+        fdo::Error::Failed(err.to_json()).into()
+    }
+
+    #[test]
+    fn exit_codes() {
+        assert_eq!(exit_code(eyre::eyre!("Something else")), 1);
+        let err = Opts::try_parse_from(["tailor", "--unknown"]).unwrap_err();
+        assert_eq!(err.exit_code(), 2);
+        let cli_error = CliError::new(ErrorKind::InvalidArguments, "Same file");
+        assert_eq!(exit_code(eyre::Report::new(cli_error)), 2);
+
+        assert_eq!(exit_code(zbus::Error::Unsupported), 3);
+        let unreachable = fdo::Error::ServiceUnknown("com.tux.Tailor".to_owned());
+        assert_eq!(exit_code(ClientError::from(unreachable)), 3);
+
+        let denied = TailorError::permission_denied("Not the active session");
+        assert_eq!(exit_code(tailor_error(denied)), 4);
+        let denied = fdo::Error::AccessDenied("Denied".to_owned());
+        assert_eq!(exit_code(ClientError::from(denied)), 4);
+
+        let invalid = TailorError::invalid_profile("Missing field `fans`");
+        assert_eq!(exit_code(tailor_error(invalid)), 5);
+        let exists = TailorError::already_exists("Profile `quiet` already exists");
+        assert_eq!(exit_code(tailor_error(exists)), 5);
+        // Plain errors of tailord, e.g. of older versions.
+        let invalid = fdo::Error::InvalidArgs("Missing field `fans`".to_owned());
+        assert_eq!(exit_code(ClientError::from(invalid)), 5);
+        let cli_error = CliError::new(ErrorKind::InvalidProfile, "Invalid file");
+        assert_eq!(exit_code(eyre::Report::new(cli_error)), 5);
+
+        let unsupported = TailorError::not_supported("No fan control");
+        assert_eq!(exit_code(tailor_error(unsupported)), 6);
+        let cli_error = CliError::new(ErrorKind::NotSupported, "No webcam control");
+        assert_eq!(exit_code(eyre::Report::new(cli_error)), 6);
+        let unknown = fdo::Error::UnknownMethod("GetFanStatus".to_owned());
+        assert_eq!(exit_code(ClientError::from(unknown)), 6);
+
+        let hardware = TailorError::hardware_error(Some(5), "Writing the fan speed failed");
+        assert_eq!(exit_code(tailor_error(hardware)), 7);
+
+        // The kind of a cause counts if the error itself has none.
+        let report = eyre::Report::new(ClientError::from(fdo::Error::InvalidArgs(
+            "Invalid".to_owned(),
+        )))
+        .wrap_err("Failed to add the profile");
+        assert_eq!(exit_code(report), 5);
+    }
+}
@@ -0,0 +1,161 @@
+use colored::Colorize;
+use eyre::Result;
+use tailor_api::{FanProfilePoint, FanStatus};
+use tailor_client::TailorConnection;
+
+use crate::{
+    cli::FanCommand,
+    exit::{CliError, ErrorKind},
+    i18n::tr,
+    output::{Cell, Style, Table},
+    plot, tune,
+};
+
+/// Handle fan commands
+pub(crate) async fn handle(cmd: FanCommand) -> Result<()> {
+    let connection = TailorConnection::new().await?;
+    match cmd {
+        FanCommand::Status => {
+            let mut table = Table::new([
+                tr!("fan-column-fan"),
+                tr!("fan-column-temperature"),
+                tr!("fan-column-speed"),
+                tr!("fan-column-target"),
+                tr!("fan-column-reported"),
+                tr!("fan-column-synchronized"),
+            ]);
+            for status in connection.get_fan_status().await? {
+                let FanStatus {
+                    fan_idx,
+                    raw_temp,
+                    temp,
+                    fan_speed,
+                    reported_fan_speed,
+                    target_fan_speed,
+                    speed_override,
+                    synchronized,
+                } = status;
+                let temp = if raw_temp == temp {
+                    format!("{temp}°C")
+                } else {
+                    tr!("fan-temperature-raw", temp = temp, raw = raw_temp)
+                };
+                let speed = match speed_override {
+                    Some(speed) => {
+                        Cell::new(tr!("fan-speed-override", speed = speed)).style(Style::Warning)
+                    }
+                    None => Cell::new(format!("{fan_speed}%")),
+                };
+                let reported = match reported_fan_speed {
+                    Some(reported) if reported != fan_speed => {
+                        Cell::new(format!("{reported}%")).style(Style::Warning)
+                    }
+                    Some(reported) => Cell::new(format!("{reported}%")),
+                    None => Cell::new("-").style(Style::Dimmed),
+                };
+                let synchronized = if synchronized {
+                    tr!("fan-synchronized-yes")
+                } else {
+                    tr!("fan-synchronized-no")
+                };
+                table.row([
+                    Cell::new(fan_idx.to_string()).style(Style::Bold),
+                    Cell::new(temp),
+                    speed,
+                    Cell::new(format!("{target_fan_speed}%")),
+                    reported,
+                    Cell::new(synchronized),
+                ]);
+            }
+            table.print();
+
+            let throttle_status = connection.get_throttle_status().await?;
+            if throttle_status.is_critical() {
+                println!("{}", tr!("throttle-critical").red());
+            } else if throttle_status.throttling {
+                println!("{}", tr!("throttle").yellow());
+            }
+        }
+        FanCommand::Override {
+            fan,
+            percent,
+            duration,
+        } => {
+            connection
+                .set_fan_speed_override(fan, percent, duration)
+                .await?;
+        }
+        FanCommand::Release { fan } => {
+            connection.clear_fan_speed_override(fan).await?;
+        }
+        FanCommand::Inhibit { reason, command } => {
+            let who = match command.first() {
+                Some(program) => format!("tailor ({program})"),
+                None => "tailor".to_owned(),
+            };
+            // Fan control resumes once the file descriptor is closed,
+            // which also happens if tailor is killed.
+            let _inhibitor = connection.inhibit_fan_control(&who, &reason).await?;
+            let Some((program, args)) = command.split_first() else {
+                println!("{}", tr!("fan-inhibited"));
+                return std::future::pending().await;
+            };
+            let mut command = std::process::Command::new(program);
+            command.args(args);
+            let status = tokio::task::spawn_blocking(move || command.status()).await??;
+            if !status.success() {
+                std::process::exit(status.code().unwrap_or(1));
+            }
+        }
+        FanCommand::Show { name, plot, fan } => {
+            let info = connection.get_fan_profile_info(&name).await?;
+            if plot {
+                let current = match fan {
+                    Some(fan) => {
+                        let status = connection
+                            .get_fan_status()
+                            .await?
+                            .into_iter()
+                            .find(|status| status.fan_idx == fan)
+                            .ok_or_else(|| {
+                                CliError::new(
+                                    ErrorKind::InvalidArguments,
+                                    tr!("fan-not-found", fan = fan),
+                                )
+                            })?;
+                        Some((status.temp, status.fan_speed))
+                    }
+                    None => None,
+                };
+                print!("{}", plot::fan_curve(&info.points, current));
+            } else {
+                println!("{}", name.bold());
+                if info.synchronized {
+                    println!("  {}", tr!("fan-profile-synchronized"));
+                }
+                for FanProfilePoint { temp, fan } in &info.points {
+                    println!("  {temp:>3}°C  {fan:>3}%");
+                }
+            }
+        }
+        FanCommand::Tune {
+            levels,
+            target,
+            max_temp,
+            settle,
+            save,
+            command,
+        } => {
+            let options = tune::Options {
+                levels,
+                target,
+                max_temp,
+                settle,
+                save,
+                command,
+            };
+            tune::run(&connection, options).await?;
+        }
+    }
+    Ok(())
+}
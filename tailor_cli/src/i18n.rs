@@ -0,0 +1,137 @@
+//! Translations of the messages of the CLI.
+//! The messages are stored in `i18n/<locale>/tailor.ftl`.
+
+use std::sync::OnceLock;
+
+use fluent_bundle::{concurrent::FluentBundle, FluentArgs, FluentResource};
+use unic_langid::LanguageIdentifier;
+
+type Bundle = FluentBundle<FluentResource>;
+
+/// The available translations. The first one is used
+/// for locales and messages that aren't translated.
+const TRANSLATIONS: [(&str, &str); 2] = [
+    ("en-US", include_str!("../i18n/en-US/tailor.ftl")),
+    ("de", include_str!("../i18n/de/tailor.ftl")),
+];
+
+static BUNDLES: OnceLock<Bundles> = OnceLock::new();
+
+/// Translate a message, optionally with arguments:
+/// `tr!("fan-title", fan = 0)`.
+macro_rules! tr {
+    ($id:literal) => {
+        $crate::i18n::message($id, None)
+    };
+    ($id:literal, $($name:ident = $value:expr),+ $(,)?) => {{
+        let mut args = fluent_bundle::FluentArgs::new();
+        $(args.set(stringify!($name), $value);)+
+        $crate::i18n::message($id, Some(&args))
+    }};
+}
+pub(crate) use tr;
+
+struct Bundles {
+    /// `None` if the locale of the user is the fallback.
+    translation: Option<Bundle>,
+    fallback: Bundle,
+}
+
+impl Bundles {
+    fn from_env() -> Self {
+        let (fallback_locale, fallback_source) = TRANSLATIONS[0];
+        let translation = env_locale().and_then(|locale| {
+            TRANSLATIONS[1..]
+                .iter()
+                .find(|(id, _)| {
+                    id.parse::<LanguageIdentifier>().unwrap().language == locale.language
+                })
+                .map(|(id, source)| bundle(id, source))
+        });
+        Self {
+            translation,
+            fallback: bundle(fallback_locale, fallback_source),
+        }
+    }
+}
+
+fn bundle(locale: &str, source: &str) -> Bundle {
+    let locale: LanguageIdentifier = locale.parse().unwrap();
+    let resource = FluentResource::try_new(source.to_owned()).expect("Invalid translation");
+    let mut bundle = FluentBundle::new_concurrent(vec![locale]);
+    // Terminals would show the unicode isolation marks.
+    bundle.set_use_isolating(false);
+    bundle
+        .add_resource(resource)
+        .expect("Duplicate message in translation");
+    bundle
+}
+
+/// The locale of the user, in the same order of precedence as gettext.
+fn env_locale() -> Option<LanguageIdentifier> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .and_then(|value| parse_locale(&value))
+}
+
+/// Parse POSIX locales such as `de_DE.UTF-8@euro`.
+fn parse_locale(value: &str) -> Option<LanguageIdentifier> {
+    let locale = value.split(['.', '@']).next()?;
+    if matches!(locale, "C" | "POSIX") {
+        return None;
+    }
+    locale.replace('_', "-").parse().ok()
+}
+
+/// Use [`tr`] instead of calling this directly.
+pub(crate) fn message(id: &str, args: Option<&FluentArgs<'_>>) -> String {
+    let bundles = BUNDLES.get_or_init(Bundles::from_env);
+    bundles
+        .translation
+        .iter()
+        .chain([&bundles.fallback])
+        .find_map(|bundle| {
+            let pattern = bundle.get_message(id)?.value()?;
+            let mut errors = Vec::new();
+            Some(
+                bundle
+                    .format_pattern(pattern, args, &mut errors)
+                    .into_owned(),
+            )
+        })
+        .unwrap_or_else(|| id.to_owned())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{bundle, parse_locale, TRANSLATIONS};
+
+    #[test]
+    fn locales() {
+        let locale = parse_locale("de_DE.UTF-8@euro").unwrap();
+        assert_eq!(locale.language.as_str(), "de");
+        assert_eq!(locale.region.unwrap().as_str(), "DE");
+        assert!(parse_locale("C").is_none());
+        assert!(parse_locale("POSIX.UTF-8").is_none());
+    }
+
+    #[test]
+    fn complete_translations() {
+        let (_, fallback) = TRANSLATIONS[0];
+        let ids: Vec<&str> = fallback
+            .lines()
+            .filter(|line| line.starts_with(|c: char| c.is_ascii_lowercase()))
+            .filter_map(|line| line.split_once(" =").map(|(id, _)| id))
+            .collect();
+        assert!(!ids.is_empty());
+
+        for (locale, source) in TRANSLATIONS {
+            let bundle = bundle(locale, source);
+            for id in &ids {
+                assert!(bundle.has_message(id), "`{id}` is missing in {locale}");
+            }
+        }
+    }
+}
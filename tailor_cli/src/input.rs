@@ -0,0 +1,54 @@
+use eyre::Result;
+use tailor_client::TailorConnection;
+
+use crate::{
+    cli::{InputCommand, Toggle},
+    exit::{CliError, ErrorKind},
+    i18n::tr,
+};
+
+/// Handle input device commands
+pub(crate) async fn handle(cmd: InputCommand) -> Result<()> {
+    let connection = TailorConnection::new().await?;
+    match cmd {
+        InputCommand::Touchpad { state } => touchpad(&connection, state).await,
+        InputCommand::FnLock { state } => fn_lock(&connection, state).await,
+    }
+}
+
+async fn touchpad(connection: &TailorConnection<'_>, state: Option<Toggle>) -> Result<()> {
+    if !connection.get_hardware_capabilities().await?.touchpad {
+        eyre::bail!(CliError::new(
+            ErrorKind::NotSupported,
+            tr!("touchpad-unsupported")
+        ));
+    }
+    if let Some(state) = state {
+        connection.set_touchpad_enabled(state == Toggle::On).await?;
+    }
+    // Read the state back, the hotkey might have changed it in the meantime.
+    if connection.get_touchpad_enabled().await? {
+        println!("{}", tr!("touchpad-on"));
+    } else {
+        println!("{}", tr!("touchpad-off"));
+    }
+    Ok(())
+}
+
+async fn fn_lock(connection: &TailorConnection<'_>, state: Option<Toggle>) -> Result<()> {
+    if !connection.get_hardware_capabilities().await?.fn_lock {
+        eyre::bail!(CliError::new(
+            ErrorKind::NotSupported,
+            tr!("fn-lock-unsupported")
+        ));
+    }
+    if let Some(state) = state {
+        connection.set_fn_lock(state == Toggle::On).await?;
+    }
+    if connection.get_fn_lock().await? {
+        println!("{}", tr!("fn-lock-on"));
+    } else {
+        println!("{}", tr!("fn-lock-off"));
+    }
+    Ok(())
+}
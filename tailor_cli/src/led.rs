@@ -0,0 +1,93 @@
+use std::str::FromStr;
+
+use eyre::{Result, WrapErr};
+use tailor_api::{Color, LedControllerMode, LedDeviceState};
+use tailor_client::TailorConnection;
+
+use crate::{
+    cli::LedCommand,
+    i18n::tr,
+    output::{Cell, Style, Table},
+};
+
+/// Handle LED commands
+pub(crate) async fn handle(cmd: LedCommand) -> Result<()> {
+    let connection = TailorConnection::new().await?;
+    match cmd {
+        LedCommand::List => {
+            let mut table = Table::new([
+                tr!("led-column-device"),
+                tr!("led-column-mode"),
+                tr!("led-column-color"),
+                tr!("led-column-brightness"),
+            ]);
+            for device in connection.list_led_devices().await? {
+                let LedDeviceState {
+                    info,
+                    color,
+                    color_override,
+                    brightness,
+                } = device;
+                let mode = match info.mode {
+                    LedControllerMode::Rgb => tr!("led-mode-rgb"),
+                    LedControllerMode::Monochrome => tr!("led-mode-monochrome"),
+                    _ => tr!("led-unknown"),
+                };
+                let color = match (color, color_override) {
+                    (_, Some(color)) => {
+                        Cell::new(tr!("led-color-override", color = color.to_string()))
+                            .style(Style::Warning)
+                    }
+                    (Some(color), None) => Cell::new(color.to_string()),
+                    (None, None) => Cell::new(tr!("led-unknown")).style(Style::Dimmed),
+                };
+                let brightness = match brightness {
+                    Some(brightness) => Cell::new(format!("{brightness}%")),
+                    None => Cell::new(tr!("led-unknown")).style(Style::Dimmed),
+                };
+                table.row([
+                    Cell::new(info.device_id()).style(Style::Bold),
+                    Cell::new(mode),
+                    color,
+                    brightness,
+                ]);
+            }
+            table.print();
+        }
+        LedCommand::Set {
+            device,
+            color,
+            brightness,
+        } => {
+            let color = Color::from_str(color.trim_start_matches('#'))
+                .wrap_err_with(|| tr!("led-invalid-color", color = color.as_str()))?;
+            connection
+                .set_led_color_override(device.as_deref(), &color.with_brightness(brightness))
+                .await?;
+        }
+        LedCommand::Param { name, value } => {
+            if let (Some(name), Some(value)) = (&name, &value) {
+                connection.set_keyboard_parameter(name, value).await?;
+            }
+            let parameters = connection.list_keyboard_parameters().await?;
+            if parameters.is_empty() {
+                println!("{}", tr!("led-no-parameters"));
+                return Ok(());
+            }
+            let mut table = Table::new([tr!("led-column-parameter"), tr!("led-column-value")]);
+            for (parameter, value) in parameters {
+                if name.as_ref().is_some_and(|name| *name != parameter) {
+                    continue;
+                }
+                table.row([Cell::new(parameter).style(Style::Bold), Cell::new(value)]);
+            }
+            table.print();
+        }
+        LedCommand::Reset { device } => {
+            connection
+                .clear_led_color_override(device.as_deref())
+                .await?;
+        }
+    }
+    Ok(())
+}
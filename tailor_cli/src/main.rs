@@ -1,16 +1,117 @@
+mod battery;
 mod cli;
+mod complete;
+mod debug;
+mod diff;
+mod events;
+mod exit;
+mod fan;
+mod i18n;
+mod input;
+mod led;
+mod output;
+mod plot;
 mod profile;
+mod tui;
+mod tune;
+mod webcam;
 
 use clap::Parser;
 use eyre::Result;
+use tailor_api::TailorError;
+use tailor_client::ClientError;
 
-use crate::cli::{Command, Opts};
+use crate::{
+    cli::{Command, Opts},
+    i18n::tr,
+};
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() {
     let args = Opts::parse();
-    if let Some(Command::Profile { profile_cmd }) = args.command {
-        profile::handle(profile_cmd).await?;
+    let json_errors = args.json_errors;
+    if let Err(err) = run(args).await {
+        if json_errors {
+            exit::print_json(&err);
+        } else {
+            print_error(&err);
+        }
+        std::process::exit(exit::ErrorKind::of(&err).exit_code());
+    }
+}
+
+async fn run(args: Opts) -> Result<()> {
+    output::init(args.no_color);
+    match args.command {
+        Some(Command::Profile { profile_cmd }) => profile::handle(profile_cmd).await?,
+        Some(Command::Led { led_cmd }) => led::handle(led_cmd).await?,
+        Some(Command::Fan { fan_cmd }) => fan::handle(fan_cmd).await?,
+        Some(Command::Battery { battery_cmd }) => battery::handle(battery_cmd).await?,
+        Some(Command::Webcam { state }) => webcam::handle(state).await?,
+        Some(Command::Input { input_cmd }) => input::handle(input_cmd).await?,
+        Some(Command::Events { limit }) => events::handle(limit).await?,
+        Some(Command::Tui) => tui::handle().await?,
+        Some(Command::Debug { debug_cmd }) => debug::handle(debug_cmd).await?,
+        Some(Command::Complete { values }) => complete::handle(values).await,
+        None => {}
     }
     Ok(())
 }
+
+fn print_error(err: &eyre::Report) {
+    let mut chain = err.chain().map(describe);
+    if let Some(message) = chain.next() {
+        eprintln!("{}", tr!("error", message = message));
+    }
+    for message in chain {
+        eprintln!("  {}", tr!("error-cause", message = message));
+    }
+}
+
+/// Errors of tailord are translated by their kind,
+/// the detailed message is only available in English.
+fn describe(err: &(dyn std::error::Error + 'static)) -> String {
+    let tailor_error = err
+        .downcast_ref::<ClientError>()
+        .and_then(ClientError::tailor_error);
+    match tailor_error {
+        Some(TailorError::NotSupported { message }) => {
+            tr!("error-not-supported", message = message.as_str())
+        }
+        Some(TailorError::InvalidProfile { message }) => {
+            tr!("error-invalid-profile", message = message.as_str())
+        }
+        Some(TailorError::HardwareError {
+            errno: Some(errno),
+            message,
+        }) => tr!(
+            "error-hardware-errno",
+            message = message.as_str(),
+            errno = *errno
+        ),
+        Some(TailorError::HardwareError {
+            errno: None,
+            message,
+        }) => tr!("error-hardware", message = message.as_str()),
+        Some(TailorError::PermissionDenied { message }) => {
+            tr!("error-permission-denied", message = message.as_str())
+        }
+        Some(TailorError::AlreadyExists { message }) => {
+            tr!("error-already-exists", message = message.as_str())
+        }
+        Some(TailorError::PartiallyApplied {
+            rolled_back: true,
+            message,
+            ..
+        }) => tr!("error-profile-rolled-back", message = message.as_str()),
+        Some(TailorError::PartiallyApplied {
+            rolled_back: false,
+            message,
+            ..
+        }) => tr!(
+            "error-profile-partially-applied",
+            message = message.as_str()
+        ),
+        None => err.to_string(),
+    }
+}
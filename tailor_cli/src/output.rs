@@ -0,0 +1,157 @@
+//! Formatting of the human readable output.
+
+use std::io::IsTerminal;
+
+use colored::Colorize;
+
+/// Colors are only used if stdout is a terminal,
+/// unless they are disabled by `--no-color` or `NO_COLOR`.
+pub(crate) fn init(no_color: bool) {
+    let no_color_env = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let enabled = !no_color && !no_color_env && std::io::stdout().is_terminal();
+    colored::control::set_override(enabled);
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum Style {
+    #[default]
+    Plain,
+    Bold,
+    Dimmed,
+    /// Values that differ from what the user expects, like overrides.
+    Warning,
+}
+
+impl Style {
+    pub(crate) fn apply(self, text: &str) -> String {
+        match self {
+            Self::Plain => text.to_owned(),
+            Self::Bold => text.bold().to_string(),
+            Self::Dimmed => text.dimmed().to_string(),
+            Self::Warning => text.yellow().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Cell {
+    text: String,
+    style: Style,
+}
+
+impl Cell {
+    pub(crate) fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            style: Style::Plain,
+        }
+    }
+
+    pub(crate) fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+}
+
+impl<T: Into<String>> From<T> for Cell {
+    fn from(text: T) -> Self {
+        Self::new(text)
+    }
+}
+
+/// A table with a bold header whose columns are aligned to the widest cell.
+#[derive(Debug, Default)]
+pub(crate) struct Table {
+    header: Vec<String>,
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Table {
+    pub(crate) fn new<I, S>(header: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            header: header.into_iter().map(Into::into).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub(crate) fn row<I, C>(&mut self, cells: I)
+    where
+        I: IntoIterator<Item = C>,
+        C: Into<Cell>,
+    {
+        self.rows.push(cells.into_iter().map(Into::into).collect());
+    }
+
+    pub(crate) fn print(&self) {
+        print!("{self}");
+    }
+
+    fn widths(&self) -> Vec<usize> {
+        let mut widths: Vec<usize> = self.header.iter().map(|text| text_width(text)).collect();
+        for row in &self.rows {
+            for (idx, cell) in row.iter().enumerate() {
+                let width = text_width(&cell.text);
+                match widths.get_mut(idx) {
+                    Some(column_width) => *column_width = (*column_width).max(width),
+                    None => widths.push(width),
+                }
+            }
+        }
+        widths
+    }
+}
+
+impl std::fmt::Display for Table {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let widths = self.widths();
+        let header = self
+            .header
+            .iter()
+            .map(|text| Cell::new(text.as_str()).style(Style::Bold));
+        write_line(f, &widths, header)?;
+        for row in &self.rows {
+            write_line(f, &widths, row.iter().cloned())?;
+        }
+        Ok(())
+    }
+}
+
+/// Pad before styling, the escape codes of colors don't take up space.
+fn write_line(
+    f: &mut std::fmt::Formatter<'_>,
+    widths: &[usize],
+    cells: impl Iterator<Item = Cell>,
+) -> std::fmt::Result {
+    let mut line = String::new();
+    for (cell, column_width) in cells.zip(widths) {
+        let padding = column_width - text_width(&cell.text);
+        line.push_str(&cell.style.apply(&cell.text));
+        line.push_str(&" ".repeat(padding + 2));
+    }
+    writeln!(f, "{}", line.trim_end())
+}
+
+fn text_width(text: &str) -> usize {
+    text.chars().count()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Cell, Style, Table};
+
+    #[test]
+    fn alignment() {
+        colored::control::set_override(false);
+        let mut table = Table::new(["Fan", "Temperature"]);
+        table.row(["0", "45°C"]);
+        table.row([Cell::new("10").style(Style::Bold), Cell::new("100°C")]);
+        assert_eq!(
+            table.to_string(),
+            "Fan  Temperature\n0    45°C\n10   100°C\n"
+        );
+    }
+}
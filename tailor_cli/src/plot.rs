@@ -0,0 +1,84 @@
+//! Plain text charts of fan curves for terminals without a GUI.
+
+use tailor_api::{fan_speed_at, FanProfilePoint};
+
+/// Degrees per column.
+const TEMP_STEP: u8 = 2;
+/// Percent per row.
+const FAN_STEP: u8 = 5;
+const MAX: u8 = 100;
+
+const CURVE: char = '.';
+const POINT: char = '*';
+const CURRENT: char = 'X';
+
+/// Render the fan curve from 0 to 100°C and, if given, the
+/// current operating point as `(temp, fan speed)`.
+pub(crate) fn fan_curve(points: &[FanProfilePoint], current: Option<(u8, u8)>) -> String {
+    let columns = (MAX / TEMP_STEP) as usize + 1;
+    let rows = (MAX / FAN_STEP) as usize + 1;
+    let mut grid = vec![vec![' '; columns]; rows];
+
+    let row = |fan: u8| rows - 1 - ((fan.min(MAX) + FAN_STEP / 2) / FAN_STEP) as usize;
+    let column = |temp: u8| ((temp.min(MAX) + TEMP_STEP / 2) / TEMP_STEP) as usize;
+
+    if !points.is_empty() {
+        for (idx, temp) in (0..=MAX).step_by(TEMP_STEP as usize).enumerate() {
+            grid[row(fan_speed_at(points, temp))][idx] = CURVE;
+        }
+        for point in points {
+            grid[row(point.fan)][column(point.temp)] = POINT;
+        }
+    }
+    if let Some((temp, fan)) = current {
+        grid[row(fan)][column(temp)] = CURRENT;
+    }
+
+    let mut output = String::new();
+    for (idx, line) in grid.iter().enumerate() {
+        let fan = MAX - idx as u8 * FAN_STEP;
+        let label = if fan % 25 == 0 {
+            format!("{fan:>3}%")
+        } else {
+            String::new()
+        };
+        let line: String = line.iter().collect();
+        output.push_str(&format!("{label:>4} |{}\n", line.trim_end()));
+    }
+    output.push_str(&format!("     +{}\n", "-".repeat(columns)));
+    output.push_str(&format!(
+        "      0{:>width$}{:>width$}°C\n",
+        "50",
+        "100",
+        width = columns / 2
+    ));
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use tailor_api::FanProfilePoint;
+
+    use super::fan_curve;
+
+    #[test]
+    fn chart() {
+        let points = [
+            FanProfilePoint { temp: 0, fan: 0 },
+            FanProfilePoint {
+                temp: 100,
+                fan: 100,
+            },
+        ];
+        let chart = fan_curve(&points, Some((50, 50)));
+        let lines: Vec<&str> = chart.lines().collect();
+        // One row per 5% and the axis with its labels.
+        assert_eq!(lines.len(), 23);
+        assert_eq!(
+            lines[0],
+            "100% |                                                 .*"
+        );
+        assert_eq!(lines[10].trim_end(), " 50% |                        .X.");
+        assert_eq!(lines[20].trim_end(), "  0% |*.");
+    }
+}
@@ -1,26 +1,115 @@
+use std::path::Path;
+
 use colored::Colorize;
 use eyre::Result;
+use tailor_api::{ColorProfile, ConfigFormat, FanProfileInfo, ProfileInfo, ProfileScope};
 use tailor_client::TailorConnection;
 
-use crate::cli::ProfileCommand;
+use crate::{
+    cli::ProfileCommand,
+    diff::{self, ResolvedProfile},
+    exit::{CliError, ErrorKind},
+    i18n::tr,
+    output::{Cell, Style, Table},
+};
 use notify_rust::Notification;
 
 /// Handle profile commands
 pub(crate) async fn handle(cmd: ProfileCommand) -> Result<()> {
+    // Conversions don't need the daemon.
+    if let ProfileCommand::Convert {
+        input,
+        output,
+        keep,
+    } = cmd
+    {
+        return convert(&input, output.as_deref(), keep);
+    }
+    if let ProfileCommand::Validate { files } = cmd {
+        return validate(&files);
+    }
+
     let connection = TailorConnection::new().await?;
     match cmd {
-        ProfileCommand::List => {
+        ProfileCommand::List { detailed } => {
             let active_profile = connection.get_active_global_profile_name().await?;
-            let inactive_profiles: Vec<String> = connection
-                .list_global_profiles()
-                .await?
-                .into_iter()
-                .filter(|name| name != &active_profile)
-                .collect();
-            let active_profile_str = format!("{} (active)", active_profile).bold().green();
-            println!("{}\n{}", active_profile_str, inactive_profiles.join("\n"));
-        }
-        ProfileCommand::Set { name } => {
+            // The profiles are listed in the order set in Tailor GUI, favorites first.
+            let profiles = connection.list_global_profiles().await?;
+            // Older versions of tailord have no order.
+            let order = connection
+                .get_global_profile_order()
+                .await
+                .unwrap_or_default();
+            let mut active_labels = vec![tr!("profile-active")];
+            if connection.get_active_global_profile_scope().await? == ProfileScope::User {
+                active_labels.push(tr!("profile-user"));
+            }
+            if let Some(owner) = connection.get_active_global_profile_owner().await? {
+                active_labels.push(tr!("profile-set-by", owner = owner.to_string()));
+            }
+            for (idx, name) in profiles.iter().enumerate() {
+                let active = name == &active_profile;
+                let mut labels = if active {
+                    active_labels.clone()
+                } else {
+                    Vec::new()
+                };
+                if order.is_favorite(name) {
+                    labels.push(tr!("profile-favorite"));
+                }
+                let line = if labels.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{name} ({})", labels.join(", "))
+                };
+                let line = match (active, detailed) {
+                    (true, _) => line.bold().green(),
+                    (false, true) => line.bold(),
+                    (false, false) => line.normal(),
+                };
+                if detailed && idx > 0 {
+                    println!();
+                }
+                println!("{line}");
+                if detailed {
+                    print_details(&connection, name).await?;
+                }
+            }
+        }
+        ProfileCommand::Show { name } => {
+            println!("{}", name.bold());
+            print_details(&connection, &name).await?;
+        }
+        ProfileCommand::Set {
+            name,
+            dry_run: true,
+        } => {
+            let writes = connection.preview_global_profile(&name).await?;
+            if writes.is_empty() {
+                println!("{}", tr!("profile-preview-none"));
+            } else {
+                let mut table = Table::new([
+                    tr!("profile-preview-device"),
+                    tr!("profile-preview-attribute"),
+                    tr!("profile-preview-old"),
+                    tr!("profile-preview-new"),
+                ]);
+                for write in writes {
+                    let old = write.old.unwrap_or_else(|| tr!("profile-preview-unknown"));
+                    table.row([
+                        Cell::new(write.device),
+                        Cell::new(write.attribute),
+                        Cell::new(old).style(Style::Dimmed),
+                        Cell::new(write.new).style(Style::Bold),
+                    ]);
+                }
+                table.print();
+            }
+        }
+        ProfileCommand::Set {
+            name,
+            dry_run: false,
+        } => {
             connection.set_active_global_profile_name(&name).await?;
             connection.reload().await?;
         }
@@ -35,7 +124,8 @@ pub(crate) async fn handle(cmd: ProfileCommand) -> Result<()> {
                 next_profile_name = Some(profile_name)
             }
             if let Some(next_profile_name) = next_profile_name {
-                let profile_updated_msg = format!("Current profile: {}", next_profile_name);
+                let profile_updated_msg =
+                    tr!("profile-current", profile = next_profile_name.as_str());
                 connection
                     .set_active_global_profile_name(next_profile_name)
                     .await?;
@@ -45,7 +135,7 @@ pub(crate) async fn handle(cmd: ProfileCommand) -> Result<()> {
                 }
                 if notify {
                     Notification::new()
-                        .summary("Profile updated")
+                        .summary(&tr!("profile-updated"))
                         .body(&profile_updated_msg)
                         .appname("tailor")
                         .show_async()
@@ -53,6 +143,250 @@ pub(crate) async fn handle(cmd: ProfileCommand) -> Result<()> {
                 }
             }
         }
+        ProfileCommand::Diff { to, from } => {
+            let (from_label, from_profile) = match &from {
+                Some(name) => (name.clone(), resolve(&connection, name).await?),
+                None => {
+                    let active = connection.get_active_global_profile_name().await?;
+                    (
+                        tr!("profile-diff-active", profile = active.as_str()),
+                        resolve(&connection, &active).await?,
+                    )
+                }
+            };
+            let to_profile = resolve(&connection, &to).await?;
+
+            let changes = diff::profiles(&from_profile, &to_profile);
+            if changes.is_empty() {
+                println!("{}", tr!("profile-diff-none"));
+            } else {
+                let mut table = Table::new([tr!("profile-diff-field"), from_label, to]);
+                for change in changes {
+                    table.row([
+                        Cell::new(change.field),
+                        Cell::new(change.from).style(Style::Dimmed),
+                        Cell::new(change.to).style(Style::Bold),
+                    ]);
+                }
+                table.print();
+            }
+
+            // Overrides survive profile switches.
+            if from.is_none() {
+                print_overrides(&connection).await?;
+            }
+        }
+        ProfileCommand::ResetDefaults => {
+            connection.reset_default_profiles().await?;
+            println!("{}", tr!("profile-defaults-restored"));
+        }
+        ProfileCommand::Convert { .. } | ProfileCommand::Validate { .. } => unreachable!(),
     }
     Ok(())
 }
+
+/// Print the settings of a profile with the fan curves and colors it refers to.
+async fn print_details(connection: &TailorConnection<'_>, name: &str) -> Result<()> {
+    let info = connection.get_global_profile(name).await?;
+
+    let performance_profile = info
+        .performance_profile
+        .clone()
+        .unwrap_or_else(|| tr!("profile-show-performance-default"));
+    println!(
+        "  {}",
+        tr!("profile-show-performance", profile = performance_profile)
+    );
+    if let Some(freq) = info.max_freq_mhz {
+        println!("  {}", tr!("profile-show-max-freq", freq = freq));
+    }
+    if let Some(settings) = &info.usb_autosuspend {
+        let settings = diff::usb_autosuspend(settings);
+        println!(
+            "  {}",
+            tr!("profile-show-usb-autosuspend", settings = settings)
+        );
+    }
+    if let Some(policy) = info.pcie_aspm {
+        let policy = policy.to_string();
+        println!("  {}", tr!("profile-show-pcie-aspm", policy = policy));
+    }
+    if let Some(tuning) = &info.power_tuning {
+        let settings = diff::power_tuning(tuning);
+        println!(
+            "  {}",
+            tr!("profile-show-power-tuning", settings = settings)
+        );
+    }
+
+    for (idx, fan) in info.fans.iter().enumerate() {
+        let fan_info = connection.get_fan_profile_info(fan).await?;
+        let line = if fan_info.synchronized {
+            tr!(
+                "profile-show-fan-synchronized",
+                fan = idx,
+                profile = fan.as_str()
+            )
+        } else {
+            tr!("profile-show-fan", fan = idx, profile = fan.as_str())
+        };
+        println!("  {line}");
+        println!("    {}", diff::curve(&fan_info.points).dimmed());
+    }
+
+    for led in &info.leds {
+        let device = format!("{}::{}", led.device_name, led.function);
+        let line = tr!(
+            "profile-show-led",
+            device = device,
+            profile = led.profile.as_str()
+        );
+        let colors = match connection.get_led_profile(&led.profile).await? {
+            ColorProfile::None => tr!("profile-show-led-off"),
+            ColorProfile::Single(color) => color.to_string(),
+            ColorProfile::Multiple(points) => points
+                .iter()
+                .map(|point| point.color.to_string())
+                .collect::<Vec<_>>()
+                .join(" → "),
+            ColorProfile::Typing(effect) => tr!(
+                "profile-show-led-typing",
+                idle = effect.idle.to_string(),
+                pressed = effect.pressed.to_string()
+            ),
+            ColorProfile::Regions(regions) => {
+                tr!("profile-show-led-regions", count = regions.len())
+            }
+        };
+        println!("  {line}");
+        println!("    {}", colors.dimmed());
+    }
+    Ok(())
+}
+
+/// Load a profile along with its fan curves.
+async fn resolve(connection: &TailorConnection<'_>, name: &str) -> Result<ResolvedProfile> {
+    let info = connection.get_global_profile(name).await?;
+    let mut fan_profiles = Vec::new();
+    for fan in &info.fans {
+        fan_profiles.push(connection.get_fan_profile_info(fan).await?);
+    }
+    Ok(ResolvedProfile { info, fan_profiles })
+}
+
+async fn print_overrides(connection: &TailorConnection<'_>) -> Result<()> {
+    for status in connection.get_fan_status().await? {
+        if let Some(speed) = status.speed_override {
+            let note = tr!(
+                "profile-diff-fan-override",
+                fan = status.fan_idx,
+                speed = speed
+            );
+            println!("{}", Style::Warning.apply(&note));
+        }
+    }
+    for device in connection.list_led_devices().await? {
+        if let Some(color) = device.color_override {
+            let note = tr!(
+                "profile-diff-led-override",
+                device = device.info.device_id(),
+                color = color.to_string()
+            );
+            println!("{}", Style::Warning.apply(&note));
+        }
+    }
+    Ok(())
+}
+
+/// Convert a profile file to the format of the output file.
+fn convert(input: &Path, output: Option<&Path>, keep: bool) -> Result<()> {
+    let input_format = ConfigFormat::from_path(input).ok_or_else(|| {
+        CliError::new(
+            ErrorKind::InvalidProfile,
+            tr!("profile-unknown-format", path = format!("{input:?}")),
+        )
+    })?;
+    let output = match output {
+        Some(output) => output.to_owned(),
+        None => {
+            let format = match input_format {
+                ConfigFormat::Json => ConfigFormat::Toml,
+                ConfigFormat::Toml => ConfigFormat::Json,
+            };
+            input.with_extension(format.extension())
+        }
+    };
+    let output_format = ConfigFormat::from_path(&output).ok_or_else(|| {
+        CliError::new(
+            ErrorKind::InvalidProfile,
+            tr!("profile-unknown-format", path = format!("{output:?}")),
+        )
+    })?;
+    if output == input {
+        eyre::bail!(CliError::new(
+            ErrorKind::InvalidArguments,
+            tr!("profile-same-file")
+        ));
+    }
+
+    let data = std::fs::read_to_string(input)?;
+    let data = input_format.convert(&data, output_format)?;
+    std::fs::write(&output, data)?;
+    if !keep {
+        std::fs::remove_file(input)?;
+    }
+    println!(
+        "{}",
+        tr!(
+            "profile-converted",
+            input = format!("{input:?}"),
+            output = format!("{output:?}")
+        )
+    );
+    Ok(())
+}
+
+/// Parse profile files as the kind of profile their directory contains.
+fn validate(files: &[std::path::PathBuf]) -> Result<()> {
+    let mut invalid = 0;
+    for file in files {
+        let path = format!("{file:?}");
+        let format = ConfigFormat::from_path(file).ok_or_else(|| {
+            CliError::new(
+                ErrorKind::InvalidProfile,
+                tr!("profile-unknown-format", path = path.as_str()),
+            )
+        })?;
+        let data = std::fs::read_to_string(file)?;
+        let kind = file
+            .parent()
+            .and_then(Path::file_name)
+            .and_then(|dir| dir.to_str());
+        let result = match kind {
+            Some("fan") => format.deserialize::<FanProfileInfo>(&data).map(drop),
+            Some("keyboard") => format.deserialize::<ColorProfile>(&data).map(drop),
+            _ => format.deserialize::<ProfileInfo>(&data).map(drop),
+        };
+        match result {
+            Ok(()) => println!("{}", tr!("profile-valid", path = path.as_str())),
+            Err(err) => {
+                invalid += 1;
+                let message = tr!(
+                    "profile-invalid",
+                    path = path.as_str(),
+                    error = err.to_string()
+                );
+                eprintln!("{}", Style::Warning.apply(&message));
+            }
+        }
+    }
+    if invalid == 0 {
+        Ok(())
+    } else {
+        Err(CliError::new(
+            ErrorKind::InvalidProfile,
+            tr!("profile-invalid-files", count = invalid),
+        )
+        .into())
+    }
+}
@@ -0,0 +1,401 @@
+//! Interactive terminal interface with live sensors,
+//! fan curves and profile switching.
+
+use std::{io::Stdout, time::Duration};
+
+use crossterm::{
+    cursor::Show,
+    event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use eyre::Result;
+use futures_util::StreamExt;
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    symbols::Marker,
+    text::{Line, Span},
+    widgets::{
+        Axis, Block, Borders, Chart, Dataset, GraphType, List, ListItem, ListState, Paragraph, Row,
+        Table,
+    },
+    Frame, Terminal,
+};
+use tailor_api::{FanProfilePoint, FanStatus, ThrottleStatus};
+use tailor_client::{ClientResult, TailorConnection};
+
+use crate::i18n::tr;
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+type TuiTerminal = Terminal<CrosstermBackend<Stdout>>;
+
+struct Tui<'a> {
+    connection: TailorConnection<'a>,
+    profiles: Vec<String>,
+    active_profile: String,
+    profile_list: ListState,
+    /// The fan profile and its curve of each fan of the active profile.
+    fan_curves: Vec<(String, Vec<FanProfilePoint>)>,
+    selected_fan: usize,
+    fans: Vec<FanStatus>,
+    throttle: ThrottleStatus,
+    /// The result of the last action or an error.
+    message: Option<String>,
+}
+
+/// Show the interface until the user quits.
+pub(crate) async fn handle() -> Result<()> {
+    let connection = TailorConnection::new().await?;
+    let mut tui = Tui::new(connection);
+    tui.reload_profiles().await?;
+    tui.refresh_sensors().await?;
+
+    restore_terminal_on_panic();
+    let mut terminal = enter_terminal()?;
+    let result = tui.run(&mut terminal).await;
+    leave_terminal(&mut terminal)?;
+    result
+}
+
+/// Leave raw mode and the alternate screen before the panic message
+/// is printed, so the message is readable and the shell keeps working.
+fn restore_terminal_on_panic() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        disable_raw_mode().ok();
+        execute!(std::io::stdout(), LeaveAlternateScreen, Show).ok();
+        default_hook(info);
+    }));
+}
+
+fn enter_terminal() -> Result<TuiTerminal> {
+    enable_raw_mode()?;
+    let mut stdout = std::io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    Ok(Terminal::new(CrosstermBackend::new(stdout))?)
+}
+
+fn leave_terminal(terminal: &mut TuiTerminal) -> Result<()> {
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    Ok(())
+}
+
+impl<'a> Tui<'a> {
+    fn new(connection: TailorConnection<'a>) -> Self {
+        Self {
+            connection,
+            profiles: Vec::new(),
+            active_profile: String::new(),
+            profile_list: ListState::default(),
+            fan_curves: Vec::new(),
+            selected_fan: 0,
+            fans: Vec::new(),
+            throttle: ThrottleStatus::default(),
+            message: None,
+        }
+    }
+
+    async fn run(&mut self, terminal: &mut TuiTerminal) -> Result<()> {
+        let mut events = EventStream::new();
+        let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            tokio::select! {
+                _ = interval.tick() => {
+                    if let Err(err) = self.refresh_sensors().await {
+                        self.message = Some(tr!("tui-sensors-failed", error = err.to_string()));
+                    }
+                }
+                event = events.next() => match event {
+                    Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => {
+                        if !self.handle_key(key).await {
+                            return Ok(());
+                        }
+                    }
+                    Some(Ok(_)) => {}
+                    Some(Err(err)) => return Err(err.into()),
+                    None => return Ok(()),
+                },
+            }
+        }
+    }
+
+    /// Returns `false` if the user wants to quit.
+    async fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Up | KeyCode::Char('k') => self.select_profile(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.select_profile(1),
+            KeyCode::Left | KeyCode::Char('h') => {
+                self.selected_fan = self.selected_fan.saturating_sub(1);
+            }
+            KeyCode::Right | KeyCode::Char('l')
+                if self.selected_fan + 1 < self.fan_curves.len() =>
+            {
+                self.selected_fan += 1;
+            }
+            KeyCode::Enter => {
+                if let Some(name) = self.selected_profile().map(ToOwned::to_owned) {
+                    self.message = Some(match self.activate_profile(&name).await {
+                        Ok(()) => tr!("tui-profile-activated", profile = name.as_str()),
+                        Err(err) => tr!(
+                            "tui-profile-activation-failed",
+                            profile = name.as_str(),
+                            error = err.to_string()
+                        ),
+                    });
+                }
+            }
+            KeyCode::Char('r') => {
+                self.message = self
+                    .reload_profiles()
+                    .await
+                    .err()
+                    .map(|err| tr!("tui-profiles-failed", error = err.to_string()));
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn select_profile(&mut self, offset: isize) {
+        if self.profiles.is_empty() {
+            return;
+        }
+        let selected = self.profile_list.selected().unwrap_or_default() as isize + offset;
+        let selected = selected.clamp(0, self.profiles.len() as isize - 1) as usize;
+        self.profile_list.select(Some(selected));
+    }
+
+    fn selected_profile(&self) -> Option<&str> {
+        self.profile_list
+            .selected()
+            .and_then(|idx| self.profiles.get(idx))
+            .map(String::as_str)
+    }
+
+    async fn activate_profile(&mut self, name: &str) -> ClientResult<()> {
+        self.connection.set_active_global_profile_name(name).await?;
+        self.connection.reload().await?;
+        self.reload_profiles().await
+    }
+
+    /// Load the profiles and the fan curves of the active profile.
+    async fn reload_profiles(&mut self) -> ClientResult<()> {
+        self.profiles = self.connection.list_global_profiles().await?;
+        self.active_profile = self.connection.get_active_global_profile_name().await?;
+
+        if self.profile_list.selected().is_none() {
+            let active_idx = self
+                .profiles
+                .iter()
+                .position(|name| *name == self.active_profile);
+            self.profile_list.select(active_idx.or(Some(0)));
+        }
+
+        let info = self
+            .connection
+            .get_global_profile(&self.active_profile)
+            .await?;
+        self.fan_curves.clear();
+        for name in info.fans {
+            let points = self.connection.get_fan_profile(&name).await?;
+            self.fan_curves.push((name, points));
+        }
+        self.selected_fan = self
+            .selected_fan
+            .min(self.fan_curves.len().saturating_sub(1));
+        Ok(())
+    }
+
+    async fn refresh_sensors(&mut self) -> ClientResult<()> {
+        self.fans = self.connection.get_fan_status().await?;
+        self.throttle = self.connection.get_throttle_status().await?;
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame<'_>) {
+        let [main, status] = split(
+            frame.size(),
+            Direction::Vertical,
+            [Constraint::Min(0), Constraint::Length(1)],
+        );
+        let [profiles, details] = split(
+            main,
+            Direction::Horizontal,
+            [Constraint::Percentage(30), Constraint::Percentage(70)],
+        );
+        let sensors_height = self.fans.len() as u16 + 4;
+        let [sensors, curve] = split(
+            details,
+            Direction::Vertical,
+            [Constraint::Length(sensors_height), Constraint::Min(0)],
+        );
+
+        self.draw_profiles(frame, profiles);
+        self.draw_sensors(frame, sensors);
+        self.draw_curve(frame, curve);
+
+        let status_line = match &self.message {
+            Some(message) => Line::from(message.as_str()),
+            None => Line::styled(
+                tr!("tui-help"),
+                Style::default().add_modifier(Modifier::DIM),
+            ),
+        };
+        frame.render_widget(Paragraph::new(status_line), status);
+    }
+
+    fn draw_profiles(&mut self, frame: &mut Frame<'_>, area: Rect) {
+        let items: Vec<ListItem<'_>> = self
+            .profiles
+            .iter()
+            .map(|name| {
+                if *name == self.active_profile {
+                    ListItem::new(tr!("tui-profile-active", profile = name.as_str())).style(
+                        Style::default()
+                            .fg(Color::Green)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                } else {
+                    ListItem::new(name.as_str())
+                }
+            })
+            .collect();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(tr!("tui-profiles")),
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+        frame.render_stateful_widget(list, area, &mut self.profile_list);
+    }
+
+    fn draw_sensors(&self, frame: &mut Frame<'_>, area: Rect) {
+        let rows = self.fans.iter().map(|status| {
+            let speed = match status.speed_override {
+                Some(speed) => tr!("fan-speed-override", speed = speed),
+                None => format!("{}%", status.fan_speed),
+            };
+            Row::new(vec![
+                tr!("fan-title", fan = status.fan_idx),
+                format!("{}°C", status.temp),
+                speed,
+                format!("{}%", status.target_fan_speed),
+            ])
+        });
+
+        let (title, style) = if self.throttle.is_critical() {
+            (tr!("tui-sensors-critical"), Style::default().fg(Color::Red))
+        } else if self.throttle.throttling {
+            (
+                tr!("tui-sensors-throttling"),
+                Style::default().fg(Color::Yellow),
+            )
+        } else {
+            (tr!("tui-sensors"), Style::default())
+        };
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(8),
+                Constraint::Length(8),
+                Constraint::Length(16),
+                Constraint::Length(8),
+            ],
+        )
+        .header(
+            Row::new(vec![
+                String::new(),
+                tr!("tui-temperature"),
+                tr!("tui-speed"),
+                tr!("tui-target"),
+            ])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Span::styled(title, style)),
+        );
+        frame.render_widget(table, area);
+    }
+
+    fn draw_curve(&self, frame: &mut Frame<'_>, area: Rect) {
+        let Some((name, points)) = self.fan_curves.get(self.selected_fan) else {
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .title(tr!("tui-fan-curve"));
+            frame.render_widget(Paragraph::new(tr!("tui-no-fan-profile")).block(block), area);
+            return;
+        };
+
+        let curve: Vec<(f64, f64)> = points
+            .iter()
+            .map(|point| (point.temp as f64, point.fan as f64))
+            .collect();
+        let current: Vec<(f64, f64)> = self
+            .fans
+            .iter()
+            .filter(|status| status.fan_idx as usize == self.selected_fan)
+            .map(|status| (status.temp as f64, status.fan_speed as f64))
+            .collect();
+
+        let datasets = vec![
+            Dataset::default()
+                .name(name.as_str())
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::Cyan))
+                .data(&curve),
+            Dataset::default()
+                .name(tr!("tui-current"))
+                .marker(Marker::Block)
+                .graph_type(GraphType::Scatter)
+                .style(Style::default().fg(Color::Yellow))
+                .data(&current),
+        ];
+        let labels = || vec![Span::raw("0"), Span::raw("50"), Span::raw("100")];
+        let chart = Chart::new(datasets)
+            .block(Block::default().borders(Borders::ALL).title(tr!(
+                "tui-fan-curve-of",
+                fan = self.selected_fan,
+                profile = name.as_str()
+            )))
+            .x_axis(
+                Axis::default()
+                    .title("°C")
+                    .bounds([0.0, 100.0])
+                    .labels(labels()),
+            )
+            .y_axis(
+                Axis::default()
+                    .title("%")
+                    .bounds([0.0, 100.0])
+                    .labels(labels()),
+            );
+        frame.render_widget(chart, area);
+    }
+}
+
+fn split<const N: usize>(
+    area: Rect,
+    direction: Direction,
+    constraints: [Constraint; N],
+) -> [Rect; N] {
+    let chunks = Layout::default()
+        .direction(direction)
+        .constraints(constraints)
+        .split(area);
+    std::array::from_fn(|idx| chunks[idx])
+}
@@ -0,0 +1,284 @@
+//! `tailor fan tune` pins the fans at fixed speeds while a load command
+//! runs, measures the temperature that each speed holds and proposes a
+//! fan curve from the measurements.
+
+use std::{
+    collections::VecDeque,
+    io::{BufRead, IsTerminal, Write},
+    process::{Child, Command},
+    time::{Duration, Instant},
+};
+
+use eyre::Result;
+use tailor_api::FanProfilePoint;
+use tailor_client::TailorConnection;
+
+use crate::{
+    exit::{CliError, ErrorKind},
+    i18n::tr,
+    output::{Cell, Style, Table},
+    plot,
+};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+/// The temperature has settled once it stayed within
+/// [`STEADY_TOLERANCE`] °C for this many samples.
+const STEADY_SAMPLES: usize = 15;
+const STEADY_TOLERANCE: u8 = 1;
+/// The overrides expire shortly after a speed should have been measured,
+/// so the fans return to the active profile even if tailor is killed.
+const OVERRIDE_MARGIN: Duration = Duration::from_secs(30);
+/// The proposed curve rises from half the speed that holds the target
+/// temperature to full speed within this many degrees of the target.
+const CURVE_STEP: u8 = 10;
+/// How far below the target temperature the proposed curve starts.
+const CURVE_START_BELOW_TARGET: u8 = 25;
+
+pub(crate) struct Options {
+    pub(crate) levels: Vec<u8>,
+    pub(crate) target: u8,
+    pub(crate) max_temp: u8,
+    pub(crate) settle: Duration,
+    pub(crate) save: Option<String>,
+    pub(crate) command: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Measurement {
+    /// The speed that all fans were pinned at.
+    level: u8,
+    /// The highest temperature of all fans.
+    temp: u8,
+    /// The highest speed that the firmware reports, a proxy for the noise.
+    reported: Option<u8>,
+    /// Whether the temperature settled before the timeout.
+    steady: bool,
+}
+
+pub(crate) async fn run(connection: &TailorConnection<'_>, options: Options) -> Result<()> {
+    let Options {
+        mut levels,
+        target,
+        max_temp,
+        settle,
+        save,
+        command,
+    } = options;
+    let fans = connection.get_number_of_fans().await?;
+    if fans == 0 {
+        eyre::bail!(CliError::new(ErrorKind::NotSupported, tr!("tune-no-fans")));
+    }
+    // Starting at full speed keeps the device cool while the lower
+    // speeds are measured, and hot runs can stop early.
+    levels.sort_unstable_by(|a, b| b.cmp(a));
+    levels.dedup();
+
+    let (program, args) = command.split_first().expect("clap requires a load command");
+    let mut load = Command::new(program).args(args).spawn()?;
+    println!("{}", tr!("tune-started", command = program.as_str()));
+    let result = measure(connection, fans, &levels, max_temp, settle, &mut load).await;
+    load.kill().ok();
+    load.wait().ok();
+    for fan in 0..fans {
+        connection.clear_fan_speed_override(fan).await.ok();
+    }
+    let measurements = result?;
+
+    let mut table = Table::new([
+        tr!("tune-column-speed"),
+        tr!("tune-column-temperature"),
+        tr!("tune-column-reported"),
+        tr!("tune-column-steady"),
+    ]);
+    for measurement in &measurements {
+        let reported = match measurement.reported {
+            Some(reported) => Cell::new(format!("{reported}%")),
+            None => Cell::new("-").style(Style::Dimmed),
+        };
+        let steady = if measurement.steady {
+            Cell::new(tr!("tune-steady-yes"))
+        } else {
+            Cell::new(tr!("tune-steady-no")).style(Style::Warning)
+        };
+        table.row([
+            Cell::new(format!("{}%", measurement.level)).style(Style::Bold),
+            Cell::new(format!("{}°C", measurement.temp)),
+            reported,
+            steady,
+        ]);
+    }
+    table.print();
+
+    let points = propose_curve(&measurements, target);
+    println!();
+    println!("{}", tr!("tune-proposal", target = target));
+    for FanProfilePoint { temp, fan } in &points {
+        println!("  {temp:>3}°C  {fan:>3}%");
+    }
+    print!("{}", plot::fan_curve(&points, None));
+
+    let name = match save {
+        Some(name) => Some(name),
+        None if std::io::stdin().is_terminal() => prompt_name()?,
+        None => None,
+    };
+    match name {
+        Some(name) => {
+            connection.add_fan_profile(&name, &points).await?;
+            println!("{}", tr!("tune-saved", name = name.as_str()));
+        }
+        None => println!("{}", tr!("tune-discarded")),
+    }
+    Ok(())
+}
+
+async fn measure(
+    connection: &TailorConnection<'_>,
+    fans: u8,
+    levels: &[u8],
+    max_temp: u8,
+    settle: Duration,
+    load: &mut Child,
+) -> Result<Vec<Measurement>> {
+    let mut measurements = Vec::new();
+    for &level in levels {
+        for fan in 0..fans {
+            connection
+                .set_fan_speed_override(fan, level, Some(settle + OVERRIDE_MARGIN))
+                .await?;
+        }
+        println!("{}", tr!("tune-measuring", speed = level));
+
+        let started = Instant::now();
+        let mut temps = VecDeque::with_capacity(STEADY_SAMPLES + 1);
+        let measurement = loop {
+            tokio::time::sleep(SAMPLE_INTERVAL).await;
+            if let Some(status) = load.try_wait()? {
+                eyre::bail!(tr!("tune-load-exited", status = status.to_string()));
+            }
+            let statuses = connection.get_fan_status().await?;
+            let temp = statuses
+                .iter()
+                .map(|status| status.temp)
+                .max()
+                .unwrap_or_default();
+            let reported = statuses
+                .iter()
+                .filter_map(|status| status.reported_fan_speed)
+                .max();
+
+            temps.push_back(temp);
+            if temps.len() > STEADY_SAMPLES {
+                temps.pop_front();
+            }
+            let steady = temps.len() == STEADY_SAMPLES && {
+                let min = temps.iter().min().copied().unwrap_or_default();
+                let max = temps.iter().max().copied().unwrap_or_default();
+                max - min <= STEADY_TOLERANCE
+            };
+            if steady || temp >= max_temp || started.elapsed() >= settle {
+                break Measurement {
+                    level,
+                    temp,
+                    reported,
+                    steady,
+                };
+            }
+        };
+        measurements.push(measurement);
+        if measurement.temp >= max_temp {
+            println!("{}", tr!("tune-too-hot", speed = level, temp = max_temp));
+            break;
+        }
+    }
+    Ok(measurements)
+}
+
+fn prompt_name() -> Result<Option<String>> {
+    print!("{} ", tr!("tune-save-prompt"));
+    std::io::stdout().flush()?;
+    let mut name = String::new();
+    std::io::stdin().lock().read_line(&mut name)?;
+    let name = name.trim();
+    Ok((!name.is_empty()).then(|| name.to_owned()))
+}
+
+/// The proposed curve runs the fans at the lowest speed that held the
+/// target temperature under the load and reaches full speed above the
+/// target. Below the target, it falls off to the lowest measured speed.
+/// If no speed held the target, the fans run at full speed at the target.
+fn propose_curve(measurements: &[Measurement], target: u8) -> Vec<FanProfilePoint> {
+    let holding = measurements
+        .iter()
+        .filter(|measurement| measurement.temp <= target)
+        .map(|measurement| measurement.level)
+        .min()
+        .unwrap_or(100);
+    let lowest = measurements
+        .iter()
+        .map(|measurement| measurement.level)
+        .min()
+        .unwrap_or(holding)
+        .min(holding);
+    vec![
+        FanProfilePoint {
+            temp: target.saturating_sub(CURVE_START_BELOW_TARGET),
+            fan: lowest,
+        },
+        FanProfilePoint {
+            temp: target.saturating_sub(CURVE_STEP),
+            fan: (holding / 2).max(lowest),
+        },
+        FanProfilePoint {
+            temp: target,
+            fan: holding,
+        },
+        FanProfilePoint {
+            temp: target.saturating_add(CURVE_STEP).min(100),
+            fan: 100,
+        },
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use tailor_api::FanProfilePoint;
+
+    use super::{propose_curve, Measurement};
+
+    fn measurement(level: u8, temp: u8) -> Measurement {
+        Measurement {
+            level,
+            temp,
+            reported: Some(level),
+            steady: true,
+        }
+    }
+
+    #[test]
+    fn proposed_curve() {
+        let point = |temp, fan| FanProfilePoint { temp, fan };
+        let measurements = [
+            measurement(100, 62),
+            measurement(80, 70),
+            measurement(60, 78),
+            measurement(40, 86),
+        ];
+        assert_eq!(
+            propose_curve(&measurements, 80),
+            [point(55, 40), point(70, 40), point(80, 60), point(90, 100)]
+        );
+
+        // Measuring stopped after full speed got too hot.
+        let measurements = [measurement(100, 91)];
+        assert_eq!(
+            propose_curve(&measurements, 80),
+            [
+                point(55, 100),
+                point(70, 100),
+                point(80, 100),
+                point(90, 100)
+            ]
+        );
+    }
+}
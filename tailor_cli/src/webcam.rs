@@ -0,0 +1,29 @@
+use eyre::Result;
+use tailor_client::TailorConnection;
+
+use crate::{
+    cli::Toggle,
+    exit::{CliError, ErrorKind},
+    i18n::tr,
+};
+
+/// Show whether the webcam is on, or turn it on or off
+pub(crate) async fn handle(state: Option<Toggle>) -> Result<()> {
+    let connection = TailorConnection::new().await?;
+    if !connection.get_hardware_capabilities().await?.webcam {
+        eyre::bail!(CliError::new(
+            ErrorKind::NotSupported,
+            tr!("webcam-unsupported")
+        ));
+    }
+    if let Some(state) = state {
+        connection.set_webcam_enabled(state == Toggle::On).await?;
+    }
+    // Read the state back, the hotkey might have changed it in the meantime.
+    if connection.get_webcam_enabled().await? {
+        println!("{}", tr!("webcam-on"));
+    } else {
+        println!("{}", tr!("webcam-off"));
+    }
+    Ok(())
+}
@@ -0,0 +1,40 @@
+//! Calls without an async runtime, e.g. for shell prompts and small applets.
+//! The `blocking` feature needs the default features to be disabled:
+//!
+//! ```toml
+//! tailor_client = { version = "0.2", default-features = false, features = ["blocking"] }
+//! ```
+//!
+//! ```no_run
+//! let connection = tailor_client::blocking::connect()?;
+//! let profile = tailor_client::blocking::block_on(connection.get_active_global_profile_name())?;
+//! println!("{profile}");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::future::Future;
+
+use crate::TailorConnection;
+
+/// Connect to tailord on the system bus, see [`TailorConnection::new`].
+pub fn connect() -> Result<TailorConnection<'static>, zbus::Error> {
+    block_on(TailorConnection::new())
+}
+
+/// Wait for a call of a [`TailorConnection`] to finish.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    zbus::block_on(future)
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn connect_without_runtime() {
+        // Connecting outside of an async runtime must not panic,
+        // whether a system bus is available or not.
+        if let Ok(connection) = super::connect() {
+            drop(connection);
+        }
+        assert_eq!(super::block_on(async { 1 }), 1);
+    }
+}
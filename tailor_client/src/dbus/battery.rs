@@ -0,0 +1,29 @@
+use zbus::{dbus_proxy, fdo};
+
+#[dbus_proxy(
+    interface = "com.tux.Tailor.Battery",
+    default_service = "com.tux.Tailor",
+    default_path = "/com/tux/Tailor"
+)]
+trait Battery {
+    #[dbus_proxy(property)]
+    fn conservation_mode(&self) -> fdo::Result<bool>;
+
+    #[dbus_proxy(property)]
+    fn set_conservation_mode(&self, enabled: bool) -> fdo::Result<()>;
+
+    async fn get_state(&self) -> fdo::Result<String>;
+
+    async fn get_health(&self) -> fdo::Result<String>;
+
+    async fn get_health_history(&self) -> fdo::Result<String>;
+
+    async fn start_calibration(&self) -> fdo::Result<()>;
+
+    async fn cancel_calibration(&self) -> fdo::Result<()>;
+
+    async fn get_calibration_status(&self) -> fdo::Result<String>;
+
+    #[dbus_proxy(signal)]
+    fn calibration_changed(&self, status: String) -> fdo::Result<()>;
+}
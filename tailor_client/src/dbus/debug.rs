@@ -0,0 +1,14 @@
+use zbus::{dbus_proxy, fdo};
+
+#[dbus_proxy(
+    interface = "com.tux.Tailor.Debug",
+    default_service = "com.tux.Tailor",
+    default_path = "/com/tux/Tailor"
+)]
+trait Debug {
+    async fn list_ioctl_attributes(&self) -> fdo::Result<(Vec<String>, Vec<String>)>;
+
+    async fn read_ioctl(&self, name: &str) -> fdo::Result<String>;
+
+    async fn write_ioctl(&self, name: &str, value: i32) -> fdo::Result<()>;
+}
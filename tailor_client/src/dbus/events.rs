@@ -0,0 +1,17 @@
+use zbus::{dbus_proxy, fdo};
+
+#[dbus_proxy(
+    interface = "com.tux.Tailor.Events",
+    default_service = "com.tux.Tailor",
+    default_path = "/com/tux/Tailor"
+)]
+trait Events {
+    async fn get_events(&self, limit: u32) -> fdo::Result<String>;
+
+    async fn get_log(&self, limit: u32) -> fdo::Result<String>;
+
+    async fn subscribe_log(&self) -> fdo::Result<()>;
+
+    #[dbus_proxy(signal)]
+    fn log_recorded(&self, record: &str) -> zbus::Result<()>;
+}
@@ -1,4 +1,4 @@
-use zbus::{dbus_proxy, fdo};
+use zbus::{dbus_proxy, fdo, zvariant::OwnedFd};
 
 #[dbus_proxy(
     interface = "com.tux.Tailor.Fan",
@@ -17,4 +17,25 @@ trait Fan {
     async fn rename_profile(&self, from: &str, to: &str) -> fdo::Result<Vec<String>>;
 
     async fn override_speed(&self, fan_idx: u8, speed: u8) -> fdo::Result<()>;
+
+    async fn set_speed_override(
+        &self,
+        fan_idx: u8,
+        speed: u8,
+        duration_secs: u64,
+    ) -> fdo::Result<()>;
+
+    async fn clear_speed_override(&self, fan_idx: u8) -> fdo::Result<()>;
+
+    async fn get_status(&self) -> fdo::Result<String>;
+
+    async fn inhibit(&self, who: &str, reason: &str) -> fdo::Result<OwnedFd>;
+
+    async fn get_throttle_status(&self) -> fdo::Result<String>;
+
+    #[dbus_proxy(signal)]
+    fn critical_temperature(&self, temp: u8) -> fdo::Result<()>;
+
+    #[dbus_proxy(signal)]
+    fn thermal_throttling(&self, active: bool) -> fdo::Result<()>;
 }
@@ -0,0 +1,19 @@
+use zbus::{dbus_proxy, fdo};
+
+#[dbus_proxy(
+    interface = "com.tux.Tailor.Input",
+    default_service = "com.tux.Tailor",
+    default_path = "/com/tux/Tailor"
+)]
+trait Input {
+    async fn get_touchpad_enabled(&self) -> fdo::Result<bool>;
+
+    async fn set_touchpad_enabled(&self, enabled: bool) -> fdo::Result<()>;
+
+    // Not cached, the hotkey changes the state without a signal.
+    #[dbus_proxy(property(emits_changed_signal = "false"))]
+    fn fn_lock(&self) -> fdo::Result<bool>;
+
+    #[dbus_proxy(property)]
+    fn set_fn_lock(&self, enabled: bool) -> fdo::Result<()>;
+}
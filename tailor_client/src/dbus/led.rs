@@ -17,4 +17,17 @@ trait Led {
     async fn rename_profile(&self, from: &str, to: &str) -> fdo::Result<Vec<String>>;
 
     async fn override_color(&self, color: &str) -> fdo::Result<()>;
+
+    async fn list_devices(&self) -> fdo::Result<String>;
+
+    async fn set_color_override(&self, device: &str, color: &str) -> fdo::Result<()>;
+
+    async fn clear_color_override(&self, device: &str) -> fdo::Result<()>;
+
+    async fn list_keyboard_parameters(&self) -> fdo::Result<Vec<(String, String)>>;
+
+    async fn set_keyboard_parameter(&self, name: &str, value: &str) -> fdo::Result<()>;
+
+    #[dbus_proxy(signal)]
+    fn brightness_changed(&self, device: &str, brightness: u8) -> fdo::Result<()>;
 }
@@ -1,9 +1,19 @@
+mod battery;
+mod debug;
+mod events;
 mod fan;
+mod input;
 mod led;
 mod performance;
 mod profiles;
+mod webcam;
 
+pub(crate) use battery::BatteryProxy;
+pub(crate) use debug::DebugProxy;
+pub(crate) use events::EventsProxy;
 pub(crate) use fan::FanProxy;
+pub(crate) use input::InputProxy;
 pub(crate) use led::LedProxy;
 pub(crate) use performance::PerformanceProxy;
 pub(crate) use profiles::ProfilesProxy;
+pub(crate) use webcam::WebcamProxy;
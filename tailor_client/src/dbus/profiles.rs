@@ -16,13 +16,32 @@ trait Profiles {
 
     async fn rename_profile(&self, from: &str, to: &str) -> fdo::Result<Vec<String>>;
 
+    async fn get_profile_order(&self) -> fdo::Result<String>;
+
+    async fn set_profile_order(&self, value: &str) -> fdo::Result<()>;
+
     async fn set_active_profile_name(&self, name: &str) -> fdo::Result<()>;
 
     async fn get_active_profile_name(&self) -> fdo::Result<String>;
 
+    async fn preview_profile(&self, name: &str) -> fdo::Result<String>;
+
+    async fn get_active_profile_scope(&self) -> fdo::Result<String>;
+
+    async fn get_active_profile_owner(&self) -> fdo::Result<String>;
+
     async fn get_number_of_fans(&self) -> fdo::Result<u8>;
 
     async fn get_led_devices(&self) -> fdo::Result<String>;
 
+    async fn get_hardware_capabilities(&self) -> fdo::Result<String>;
+
+    async fn get_device_info(&self) -> fdo::Result<String>;
+
+    async fn reset_default_profiles(&self) -> fdo::Result<()>;
+
     async fn reload(&self) -> fdo::Result<()>;
+
+    #[dbus_proxy(signal)]
+    fn active_profile_changed(&self, name: &str, owner: &str) -> fdo::Result<()>;
 }
@@ -0,0 +1,12 @@
+use zbus::{dbus_proxy, fdo};
+
+#[dbus_proxy(
+    interface = "com.tux.Tailor.Webcam",
+    default_service = "com.tux.Tailor",
+    default_path = "/com/tux/Tailor"
+)]
+trait Webcam {
+    async fn get_enabled(&self) -> fdo::Result<bool>;
+
+    async fn set_enabled(&self, enabled: bool) -> fdo::Result<()>;
+}
@@ -1,10 +1,42 @@
+use tailor_api::TailorError;
 use thiserror::Error;
-use zbus::fdo;
+use zbus::{fdo, DBusError};
 
 #[derive(Error, Debug)]
 pub enum ClientError {
     #[error("Bus response error: `{0}`")]
-    BusError(#[from] fdo::Error),
+    BusError(fdo::Error),
+    /// An error that tailord reported with a known [`TailorError`] kind.
+    #[error("{0}")]
+    Tailor(TailorError),
     #[error("Serialization error: `{0}`")]
     Serialization(#[from] serde_json::Error),
 }
+
+impl ClientError {
+    /// The typed error of tailord, if it sent one.
+    pub fn tailor_error(&self) -> Option<&TailorError> {
+        match self {
+            Self::Tailor(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// The D-Bus error, if tailord or the bus replied with one
+    /// that isn't a [`TailorError`].
+    pub fn bus_error(&self) -> Option<&fdo::Error> {
+        match self {
+            Self::BusError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<fdo::Error> for ClientError {
+    fn from(err: fdo::Error) -> Self {
+        match err.description().and_then(TailorError::from_json) {
+            Some(err) => Self::Tailor(err),
+            None => Self::BusError(err),
+        }
+    }
+}
@@ -1,38 +1,101 @@
 #![deny(unreachable_pub, rust_2018_idioms)]
 
+#[cfg(not(any(feature = "tokio", feature = "blocking")))]
+compile_error!("tailor_client needs either the `tokio` or the `blocking` feature");
+
+// zbus drives all connections with tokio if its `tokio` feature is on,
+// so blocking calls would panic outside of a tokio runtime.
+#[cfg(all(feature = "tokio", feature = "blocking"))]
+compile_error!(
+    "the `blocking` feature of tailor_client requires `default-features = false`, \
+     because the `tokio` feature makes connections depend on a tokio runtime"
+);
+
+#[cfg(feature = "blocking")]
+pub mod blocking;
 mod dbus;
 mod error;
 
+use std::{
+    os::fd::{FromRawFd, IntoRawFd, OwnedFd},
+    sync::OnceLock,
+    time::Duration,
+};
+
 pub use error::ClientError;
-use tailor_api::{Color, ColorProfile, FanProfilePoint, LedDeviceInfo, ProfileInfo};
-use zbus::Connection;
+use futures_util::{future, Stream, StreamExt};
+use tailor_api::{
+    BatteryHealth, BatteryHealthSample, BatteryState, CalibrationStatus, Color, ColorProfile,
+    DeviceInfo, Event, FanProfileInfo, FanProfilePoint, FanStatus, HardwareCapabilities,
+    HardwareWrite, LedDeviceInfo, LedDeviceState, LogRecord, ProfileInfo, ProfileOrder,
+    ProfileOwner, ProfileScope, ThrottleStatus,
+};
+use zbus::{fdo, Connection};
 
 pub type ClientResult<T> = Result<T, ClientError>;
 
+/// A connection to tailord. Clones share the bus connection and the proxies.
 #[derive(Debug, Clone)]
 pub struct TailorConnection<'a> {
     profiles: dbus::ProfilesProxy<'a>,
     led: dbus::LedProxy<'a>,
     fan: dbus::FanProxy<'a>,
     performance: dbus::PerformanceProxy<'a>,
+    events: dbus::EventsProxy<'a>,
+    battery: dbus::BatteryProxy<'a>,
+    debug: dbus::DebugProxy<'a>,
+    webcam: dbus::WebcamProxy<'a>,
+    input: dbus::InputProxy<'a>,
 }
 
+/// The connection to tailord that is shared by the whole process.
+static SHARED: OnceLock<TailorConnection<'static>> = OnceLock::new();
+
 impl<'a> TailorConnection<'a> {
+    /// Connect to tailord on the system bus. The bus connection and the
+    /// proxies are only set up once per process, further calls return a
+    /// clone of them. With the `tokio` feature, the bus connection belongs
+    /// to the runtime that connected first, applications that start several
+    /// runtimes one after another use [`TailorConnection::with_connection`].
     pub async fn new() -> Result<TailorConnection<'a>, zbus::Error> {
-        let connection = Connection::system().await?;
+        if let Some(shared) = SHARED.get() {
+            return Ok(shared.clone());
+        }
+        let connection = TailorConnection::with_connection(&Connection::system().await?).await?;
+        // Keep the first connection if another task connected in the meantime.
+        Ok(SHARED.get_or_init(|| connection).clone())
+    }
 
-        let profiles = dbus::ProfilesProxy::new(&connection).await?;
-        let keyboard = dbus::LedProxy::new(&connection).await?;
-        let fan = dbus::FanProxy::new(&connection).await?;
-        let performance = dbus::PerformanceProxy::new(&connection).await?;
+    /// Talk to tailord over an existing bus connection, e.g. one that
+    /// also serves the interfaces of the application.
+    pub async fn with_connection(connection: &Connection) -> Result<Self, zbus::Error> {
+        let profiles = dbus::ProfilesProxy::new(connection).await?;
+        let keyboard = dbus::LedProxy::new(connection).await?;
+        let fan = dbus::FanProxy::new(connection).await?;
+        let performance = dbus::PerformanceProxy::new(connection).await?;
+        let events = dbus::EventsProxy::new(connection).await?;
+        let battery = dbus::BatteryProxy::new(connection).await?;
+        let debug = dbus::DebugProxy::new(connection).await?;
+        let webcam = dbus::WebcamProxy::new(connection).await?;
+        let input = dbus::InputProxy::new(connection).await?;
 
         Ok(Self {
             profiles,
             led: keyboard,
             fan,
             performance,
+            events,
+            battery,
+            debug,
+            webcam,
+            input,
         })
     }
+
+    /// The underlying bus connection.
+    pub fn connection(&self) -> &Connection {
+        self.profiles.connection()
+    }
 }
 
 impl<'a> TailorConnection<'a> {
@@ -67,6 +130,67 @@ impl<'a> TailorConnection<'a> {
         let value = serde_json::to_string(color)?;
         Ok(self.led.override_color(&value).await?)
     }
+
+    /// List all LED devices along with their current state.
+    pub async fn list_led_devices(&self) -> ClientResult<Vec<LedDeviceState>> {
+        let data = self.led.list_devices().await?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// Set the color of a LED device (or all devices if `device` is `None`)
+    /// until the override is cleared or the active profile is reloaded.
+    /// Devices are identified by [`tailor_api::LedDeviceInfo::device_id`].
+    pub async fn set_led_color_override(
+        &self,
+        device: Option<&str>,
+        color: &Color,
+    ) -> ClientResult<()> {
+        let value = serde_json::to_string(color)?;
+        Ok(self
+            .led
+            .set_color_override(device.unwrap_or_default(), &value)
+            .await?)
+    }
+
+    /// Clear a color override of a LED device (or all devices if `device` is `None`)
+    /// and continue with the active profile.
+    pub async fn clear_led_color_override(&self, device: Option<&str>) -> ClientResult<()> {
+        Ok(self
+            .led
+            .clear_color_override(device.unwrap_or_default())
+            .await?)
+    }
+
+    /// List the parameters of tuxedo_keyboard with their values,
+    /// e.g. `color_left` with `ff0000`.
+    pub async fn list_keyboard_parameters(&self) -> ClientResult<Vec<(String, String)>> {
+        Ok(self.led.list_keyboard_parameters().await?)
+    }
+
+    /// Set a parameter of tuxedo_keyboard. Tailord restores it when it starts.
+    pub async fn set_keyboard_parameter(&self, name: &str, value: &str) -> ClientResult<()> {
+        Ok(self.led.set_keyboard_parameter(name, value).await?)
+    }
+
+    /// Receive the device id and the new brightness in percent when the
+    /// brightness of a LED device was changed with the keys of the keyboard.
+    pub async fn receive_led_brightness_changed(
+        &self,
+    ) -> ClientResult<impl Stream<Item = (String, u8)> + 'a> {
+        let stream = self
+            .led
+            .receive_brightness_changed()
+            .await
+            .map_err(fdo::Error::from)?;
+        Ok(stream.filter_map(|signal| {
+            future::ready(
+                signal
+                    .args()
+                    .ok()
+                    .map(|args| (args.device.to_owned(), args.brightness)),
+            )
+        }))
+    }
 }
 
 impl<'a> TailorConnection<'a> {
@@ -80,6 +204,21 @@ impl<'a> TailorConnection<'a> {
     }
 
     pub async fn get_fan_profile(&self, name: &str) -> ClientResult<Vec<FanProfilePoint>> {
+        Ok(self.get_fan_profile_info(name).await?.points)
+    }
+
+    /// Add a fan profile along with its settings.
+    pub async fn add_fan_profile_info(
+        &self,
+        name: &str,
+        profile: &FanProfileInfo,
+    ) -> ClientResult<()> {
+        let value = serde_json::to_string(profile)?;
+        Ok(self.fan.add_profile(name, &value).await?)
+    }
+
+    /// Get a fan profile along with its settings.
+    pub async fn get_fan_profile_info(&self, name: &str) -> ClientResult<FanProfileInfo> {
         let profile_data = self.fan.get_profile(name).await?;
         Ok(serde_json::from_str(&profile_data)?)
     }
@@ -89,8 +228,8 @@ impl<'a> TailorConnection<'a> {
     }
 
     pub async fn copy_fan_profile(&self, from: &str, to: &str) -> ClientResult<()> {
-        let profile = self.get_fan_profile(from).await?;
-        self.add_fan_profile(to, &profile).await
+        let profile = self.get_fan_profile_info(from).await?;
+        self.add_fan_profile_info(to, &profile).await
     }
 
     pub async fn rename_fan_profile(&self, from: &str, to: &str) -> ClientResult<Vec<String>> {
@@ -104,6 +243,70 @@ impl<'a> TailorConnection<'a> {
     pub async fn override_fan_speed(&self, fan_idx: u8, speed: u8) -> ClientResult<()> {
         Ok(self.fan.override_speed(fan_idx, speed).await?)
     }
+
+    /// Pin a fan at a fixed speed until the override is released
+    /// or the optional duration has passed.
+    /// The duration is rounded down to whole seconds, but is at least one second.
+    pub async fn set_fan_speed_override(
+        &self,
+        fan_idx: u8,
+        speed: u8,
+        duration: Option<Duration>,
+    ) -> ClientResult<()> {
+        let duration_secs = duration.map_or(0, |duration| duration.as_secs().max(1));
+        Ok(self
+            .fan
+            .set_speed_override(fan_idx, speed, duration_secs)
+            .await?)
+    }
+
+    /// Release a fan speed override and return to the active profile.
+    pub async fn clear_fan_speed_override(&self, fan_idx: u8) -> ClientResult<()> {
+        Ok(self.fan.clear_speed_override(fan_idx).await?)
+    }
+
+    /// Leave the fans to the firmware and stop all writes of tailord to the EC
+    /// until the returned file descriptor is dropped, e.g. while a firmware
+    /// update needs exclusive access to the EC. Requires root or an active session.
+    pub async fn inhibit_fan_control(&self, who: &str, reason: &str) -> ClientResult<OwnedFd> {
+        let fd = self.fan.inhibit(who, reason).await?;
+        // SAFETY: The file descriptor was owned by the D-Bus message.
+        Ok(unsafe { OwnedFd::from_raw_fd(fd.into_raw_fd()) })
+    }
+
+    /// Get the current status of all fans.
+    pub async fn get_fan_status(&self) -> ClientResult<Vec<FanStatus>> {
+        let data = self.fan.get_status().await?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// Get the thermal throttling state of the CPU.
+    pub async fn get_throttle_status(&self) -> ClientResult<ThrottleStatus> {
+        let data = self.fan.get_throttle_status().await?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// Receive the temperatures in °C that exceeded
+    /// the critical limit of the daemon configuration.
+    pub async fn receive_critical_temperature(&self) -> ClientResult<impl Stream<Item = u8> + 'a> {
+        let stream = self
+            .fan
+            .receive_critical_temperature()
+            .await
+            .map_err(fdo::Error::from)?;
+        Ok(stream.filter_map(|signal| future::ready(signal.args().ok().map(|args| args.temp))))
+    }
+
+    /// Receive whether the CPU started or stopped
+    /// thermal throttling while all fans run at 100%.
+    pub async fn receive_thermal_throttling(&self) -> ClientResult<impl Stream<Item = bool> + 'a> {
+        let stream = self
+            .fan
+            .receive_thermal_throttling()
+            .await
+            .map_err(fdo::Error::from)?;
+        Ok(stream.filter_map(|signal| future::ready(signal.args().ok().map(|args| args.active))))
+    }
 }
 
 impl<'a> TailorConnection<'a> {
@@ -134,14 +337,65 @@ impl<'a> TailorConnection<'a> {
         Ok(self.profiles.remove_profile(name).await?)
     }
 
+    /// The order in which profiles are listed, favorites first.
+    /// [`Self::list_global_profiles`] already returns the profiles in this order.
+    pub async fn get_global_profile_order(&self) -> ClientResult<ProfileOrder> {
+        let data = self.profiles.get_profile_order().await?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    pub async fn set_global_profile_order(&self, order: &ProfileOrder) -> ClientResult<()> {
+        let value = serde_json::to_string(order)?;
+        Ok(self.profiles.set_profile_order(&value).await?)
+    }
+
     pub async fn get_active_global_profile_name(&self) -> ClientResult<String> {
         Ok(self.profiles.get_active_profile_name().await?)
     }
 
+    /// Get whether the active profile is a system profile or a profile of a user.
+    pub async fn get_active_global_profile_scope(&self) -> ClientResult<ProfileScope> {
+        let data = self.profiles.get_active_profile_scope().await?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// Activate a global profile. If multiple users activate
+    /// profiles, the last one wins and becomes the owner.
     pub async fn set_active_global_profile_name(&self, name: &str) -> ClientResult<()> {
         Ok(self.profiles.set_active_profile_name(name).await?)
     }
 
+    /// The values that activating a global profile would change, without changing them.
+    pub async fn preview_global_profile(&self, name: &str) -> ClientResult<Vec<HardwareWrite>> {
+        let data = self.profiles.preview_profile(name).await?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// Get the user that activated the active profile,
+    /// `None` if it was activated by the daemon.
+    pub async fn get_active_global_profile_owner(&self) -> ClientResult<Option<ProfileOwner>> {
+        let data = self.profiles.get_active_profile_owner().await?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// Receive the name and the owner of the profile
+    /// whenever another global profile is activated.
+    pub async fn receive_active_global_profile_changed(
+        &self,
+    ) -> ClientResult<impl Stream<Item = (String, Option<ProfileOwner>)> + 'a> {
+        let stream = self
+            .profiles
+            .receive_active_profile_changed()
+            .await
+            .map_err(fdo::Error::from)?;
+        Ok(stream.filter_map(|signal| {
+            future::ready(signal.args().ok().and_then(|args| {
+                let owner = serde_json::from_str(args.owner).ok()?;
+                Some((args.name.to_owned(), owner))
+            }))
+        }))
+    }
+
     pub async fn get_number_of_fans(&self) -> ClientResult<u8> {
         Ok(self.profiles.get_number_of_fans().await?)
     }
@@ -151,6 +405,24 @@ impl<'a> TailorConnection<'a> {
         Ok(serde_json::from_str(&data)?)
     }
 
+    /// Get the features of the hardware, so clients
+    /// don't have to detect them one by one.
+    pub async fn get_hardware_capabilities(&self) -> ClientResult<HardwareCapabilities> {
+        let data = self.profiles.get_hardware_capabilities().await?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// Get the model, firmware and driver versions of the device.
+    pub async fn get_device_info(&self) -> ClientResult<DeviceInfo> {
+        let data = self.profiles.get_device_info().await?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// Restore the default profile and the presets, discarding changes to them.
+    pub async fn reset_default_profiles(&self) -> ClientResult<()> {
+        Ok(self.profiles.reset_default_profiles().await?)
+    }
+
     pub async fn reload(&self) -> ClientResult<()> {
         Ok(self.profiles.reload().await?)
     }
@@ -173,3 +445,155 @@ impl<'a> TailorConnection<'a> {
         Ok(self.performance.list_profiles().await?)
     }
 }
+
+impl<'a> TailorConnection<'a> {
+    /// Whether the firmware limits the charge to extend the lifespan of the battery.
+    /// See [`HardwareCapabilities::battery_conservation`] for whether it's supported.
+    pub async fn get_battery_conservation(&self) -> ClientResult<bool> {
+        Ok(self.battery.conservation_mode().await?)
+    }
+
+    pub async fn set_battery_conservation(&self, enabled: bool) -> ClientResult<()> {
+        Ok(self.battery.set_conservation_mode(enabled).await?)
+    }
+
+    /// Whether the webcam is turned on in the hardware, e.g. not turned off
+    /// with the hotkey. See [`HardwareCapabilities::webcam`] for whether it's supported.
+    pub async fn get_webcam_enabled(&self) -> ClientResult<bool> {
+        Ok(self.webcam.get_enabled().await?)
+    }
+
+    /// Turn the webcam on or off. Does nothing if it's already in that state.
+    pub async fn set_webcam_enabled(&self, enabled: bool) -> ClientResult<()> {
+        Ok(self.webcam.set_enabled(enabled).await?)
+    }
+
+    /// Whether the touchpad is turned on in the firmware.
+    /// See [`HardwareCapabilities::touchpad`] for whether it's supported.
+    pub async fn get_touchpad_enabled(&self) -> ClientResult<bool> {
+        Ok(self.input.get_touchpad_enabled().await?)
+    }
+
+    /// Turn the touchpad on or off. Does nothing if it's already in that state.
+    pub async fn set_touchpad_enabled(&self, enabled: bool) -> ClientResult<()> {
+        Ok(self.input.set_touchpad_enabled(enabled).await?)
+    }
+
+    /// Whether the function keys send F1-F12 without holding Fn.
+    /// See [`HardwareCapabilities::fn_lock`] for whether it's supported.
+    pub async fn get_fn_lock(&self) -> ClientResult<bool> {
+        Ok(self.input.fn_lock().await?)
+    }
+
+    /// Set the Fn-lock. Tailord restores it after restarts and resume.
+    pub async fn set_fn_lock(&self, enabled: bool) -> ClientResult<()> {
+        Ok(self.input.set_fn_lock(enabled).await?)
+    }
+
+    /// Get the charge, the charging rate and the charge thresholds of the battery.
+    pub async fn get_battery_state(&self) -> ClientResult<BatteryState> {
+        let data = self.battery.get_state().await?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// Get the capacity of the battery compared to when it was new.
+    pub async fn get_battery_health(&self) -> ClientResult<BatteryHealth> {
+        let data = self.battery.get_health().await?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// Get the daily records of the battery health, oldest first.
+    pub async fn get_battery_health_history(&self) -> ClientResult<Vec<BatteryHealthSample>> {
+        let data = self.battery.get_health_history().await?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// Start the guided calibration of the battery.
+    /// See [`HardwareCapabilities::battery_calibration`] for whether it's supported.
+    pub async fn start_battery_calibration(&self) -> ClientResult<()> {
+        Ok(self.battery.start_calibration().await?)
+    }
+
+    pub async fn cancel_battery_calibration(&self) -> ClientResult<()> {
+        Ok(self.battery.cancel_calibration().await?)
+    }
+
+    /// Get the progress of the current or last battery calibration.
+    pub async fn get_battery_calibration_status(&self) -> ClientResult<CalibrationStatus> {
+        let data = self.battery.get_calibration_status().await?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// Receive the progress of the battery calibration whenever it changes.
+    pub async fn receive_battery_calibration(
+        &self,
+    ) -> ClientResult<impl Stream<Item = CalibrationStatus> + 'a> {
+        let stream = self
+            .battery
+            .receive_calibration_changed()
+            .await
+            .map_err(fdo::Error::from)?;
+        Ok(stream.filter_map(|signal| {
+            future::ready(
+                signal
+                    .args()
+                    .ok()
+                    .and_then(|args| serde_json::from_str(&args.status).ok()),
+            )
+        }))
+    }
+}
+
+impl<'a> TailorConnection<'a> {
+    /// Get up to `limit` of the latest events of the event log, oldest first.
+    pub async fn get_events(&self, limit: u32) -> ClientResult<Vec<Event>> {
+        let data = self.events.get_events(limit).await?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// Get up to `limit` of the latest messages of the log of tailord, oldest first.
+    /// Only root and users of an active local session may read the log.
+    pub async fn get_log(&self, limit: u32) -> ClientResult<Vec<LogRecord>> {
+        let data = self.events.get_log(limit).await?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// Receive new messages of the log of tailord. Only root and users
+    /// of an active local session may read the log.
+    pub async fn receive_log(&self) -> ClientResult<impl Stream<Item = LogRecord> + 'a> {
+        let stream = self
+            .events
+            .receive_log_recorded()
+            .await
+            .map_err(fdo::Error::from)?;
+        // tailord only sends the messages to clients that subscribed.
+        self.events.subscribe_log().await?;
+        Ok(stream.filter_map(|signal| {
+            future::ready(
+                signal
+                    .args()
+                    .ok()
+                    .and_then(|args| serde_json::from_str(args.record).ok()),
+            )
+        }))
+    }
+}
+
+/// Raw access to the tuxedo_io driver for triaging driver bugs. Only
+/// available for root and if tailord was built with the `debug-ioctl` feature.
+impl<'a> TailorConnection<'a> {
+    /// The attributes that can be read and the ones that can be written.
+    pub async fn list_ioctl_attributes(&self) -> ClientResult<(Vec<String>, Vec<String>)> {
+        Ok(self.debug.list_ioctl_attributes().await?)
+    }
+
+    pub async fn read_ioctl(&self, name: &str) -> ClientResult<String> {
+        Ok(self.debug.read_ioctl(name).await?)
+    }
+
+    /// Write a raw value, bypassing all limits of tailord.
+    /// Requires tailord to be built with the `debug-ioctl-writes` feature.
+    pub async fn write_ioctl(&self, name: &str, value: i32) -> ClientResult<()> {
+        Ok(self.debug.write_ioctl(name, value).await?)
+    }
+}
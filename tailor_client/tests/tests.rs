@@ -1,9 +1,18 @@
 use tailor_api::{Color, ColorPoint, ColorProfile, ColorTransition, FanProfilePoint};
 use tailor_client::TailorConnection;
 
+/// Every test runs on its own runtime, so they can't share
+/// the connection of [`TailorConnection::new`].
+async fn connect() -> TailorConnection<'static> {
+    let connection = zbus::Connection::system().await.unwrap();
+    TailorConnection::with_connection(&connection)
+        .await
+        .unwrap()
+}
+
 #[tokio::test]
 async fn test_profiles() {
-    let connection = TailorConnection::new().await.unwrap();
+    let connection = connect().await;
     let name = "__test_global_profile";
     let second_name = "__test_global_profile2";
 
@@ -112,7 +121,7 @@ async fn test_profiles() {
 
 #[tokio::test]
 async fn test_fan() {
-    let connection = TailorConnection::new().await.unwrap();
+    let connection = connect().await;
     let name = "__test_fan_profile";
     let second_name = "__test_fan_profile2";
 
@@ -178,7 +187,7 @@ async fn test_fan() {
 
 #[tokio::test]
 async fn test_keyboard() {
-    let connection = TailorConnection::new().await.unwrap();
+    let connection = connect().await;
     let name = "__test_keyboard_profile";
     let second_name = "__test_keyboard_profile2";
 
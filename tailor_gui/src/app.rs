@@ -1,9 +1,11 @@
+use std::cell::Cell;
 use std::time::Duration;
 
 use gtk::prelude::{
-    ApplicationExt, ApplicationWindowExt, GtkWindowExt, ObjectExt, SettingsExt, WidgetExt,
+    AccessibleExt, ApplicationExt, ApplicationWindowExt, ButtonExt, GtkWindowExt, ObjectExt,
+    SettingsExt, WidgetExt,
 };
-use gtk::{gio, glib};
+use gtk::{gio, glib, glib::ToVariant};
 use relm4::actions::{RelmAction, RelmActionGroup};
 use relm4::gtk::prelude::{BoxExt, OrientableExt};
 use relm4::{
@@ -13,13 +15,25 @@ use relm4::{
 use relm4_icons::icon_names;
 use tailor_api::ProfileInfo;
 
+use crate::background;
+use crate::components::about_device::AboutDevice;
+use crate::components::battery::BatteryPage;
+use crate::components::battery_calibration::BatteryCalibration;
 use crate::components::fan_list::FanList;
-use crate::components::hardware_info::HardwareInfo;
 use crate::components::led_list::LedList;
+use crate::components::log::LogPage;
 use crate::components::profiles::Profiles;
+use crate::components::search::{SearchOutput, SettingsSearch};
 use crate::config::{APP_ID, PROFILE};
 use crate::modals::about::AboutDialog;
-use crate::state::{initialize_tailor_state, TailorStateInner, STATE};
+use crate::modals::advanced_mode;
+use crate::modals::import_tcc;
+use crate::modals::preview_profile;
+use crate::state::{
+    hardware_capabilities, initialize_tailor_state, tailor_connection, TailorStateInner,
+    TailorStateMsg, STATE,
+};
+use crate::AppActionGroup;
 
 const CONNECT_ERROR_MSG: &str = r#"Please make sure <a href="https://github.com/AaronErhardt/tuxedo-rs#tailord">tailord</a> is running correctly on your system. Tailor will connect automatically once tailord becomes available."#;
 
@@ -43,19 +57,50 @@ pub struct FullProfileInfo {
 
 pub(super) struct App {
     about_dialog: Controller<AboutDialog>,
+    search: Controller<SettingsSearch>,
     connection_state: ConnectionState,
     error: Option<adw::Toast>,
+    /// Whether Tailor starts with the session and keeps
+    /// running in the background when the window is closed.
+    autostart: bool,
+    autostart_action: gio::SimpleAction,
+    /// Whether the controls that can harm the device are shown.
+    advanced_mode_action: gio::SimpleAction,
+    /// Only enabled in the advanced mode.
+    battery_calibration_action: gio::SimpleAction,
+    /// Only enabled once the state of the battery conservation mode is known.
+    battery_conservation_action: gio::SimpleAction,
+    /// Only enabled once the state of the webcam is known.
+    webcam_action: gio::SimpleAction,
+    /// Only enabled once the state of the Fn-lock is known.
+    fn_lock_action: gio::SimpleAction,
+    /// Page that is shown with the next update of the view.
+    requested_page: Cell<Option<Page>>,
+    /// Profile that is activated once the connection is ready.
+    pending_profile: Option<String>,
 }
 
 #[derive(Debug)]
 pub(super) enum Command {
     SetInitializedState { error: Option<String> },
+    BatteryConservationChanged(Result<bool, String>),
+    WebcamChanged(Result<bool, String>),
+    FnLockChanged(Result<bool, String>),
 }
 
 #[derive(Debug)]
 pub(super) enum AppMsg {
     AddError(String),
-    Quit,
+    CloseWindow,
+    ShowPage(Page),
+    ActivateProfile(String),
+    SetAutostart(bool),
+    AutostartChanged(Result<bool, String>),
+    SetAdvancedMode(bool),
+    AdvancedModeChanged(bool),
+    SetBatteryConservation(bool),
+    SetWebcam(bool),
+    SetFnLock(bool),
 }
 
 relm4::new_action_group!(pub(super) WindowActionGroup, "win");
@@ -63,11 +108,108 @@ relm4::new_stateless_action!(PreferencesAction, WindowActionGroup, "preferences"
 relm4::new_stateless_action!(pub(super) ShortcutsAction, WindowActionGroup, "show-help-overlay");
 relm4::new_stateless_action!(AboutAction, WindowActionGroup, "about");
 relm4::new_stateless_action!(HardwareInfoAction, WindowActionGroup, "hw-info");
+relm4::new_stateless_action!(ImportTccAction, WindowActionGroup, "import-tcc");
+relm4::new_stateless_action!(
+    BatteryCalibrationAction,
+    WindowActionGroup,
+    "battery-calibration"
+);
+relm4::new_stateful_action!(AutostartAction, WindowActionGroup, "autostart", (), bool);
+relm4::new_stateful_action!(
+    AdvancedModeAction,
+    WindowActionGroup,
+    "advanced-mode",
+    (),
+    bool
+);
+relm4::new_stateful_action!(
+    PreviewProfileAction,
+    WindowActionGroup,
+    "preview-profile-changes",
+    (),
+    bool
+);
+relm4::new_stateful_action!(
+    BatteryConservationAction,
+    WindowActionGroup,
+    "battery-conservation",
+    (),
+    bool
+);
+relm4::new_stateful_action!(WebcamAction, WindowActionGroup, "webcam", (), bool);
+relm4::new_stateful_action!(FnLockAction, WindowActionGroup, "fn-lock", (), bool);
+
+// Application actions that other programs can activate via D-Bus,
+// e.g. `gapplication action com.github.aaronerhardt.Tailor show-page "'fan-curves'"`.
+relm4::new_stateful_action!(ShowPageAction, AppActionGroup, "show-page", String, ());
+relm4::new_stateful_action!(
+    ActivateProfileAction,
+    AppActionGroup,
+    "activate-profile",
+    String,
+    ()
+);
+
+/// Pages of the main window that can be opened directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub(super) enum Page {
+    Profiles,
+    Led,
+    FanCurves,
+    Battery,
+    Log,
+}
+
+impl Page {
+    pub(super) fn name(self) -> &'static str {
+        match self {
+            Self::Profiles => "profiles",
+            Self::Led => "led",
+            Self::FanCurves => "fan-curves",
+            Self::Battery => "battery",
+            Self::Log => "log",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [
+            Self::Profiles,
+            Self::Led,
+            Self::FanCurves,
+            Self::Battery,
+            Self::Log,
+        ]
+        .into_iter()
+        .find(|page| page.name() == name)
+    }
+
+    pub(super) fn title(self) -> &'static str {
+        match self {
+            Self::Profiles => "Profiles",
+            Self::Led => "LED",
+            Self::FanCurves => "Fan control",
+            Self::Battery => "Battery",
+            Self::Log => "Log",
+        }
+    }
+
+    /// Name of the page in the view stack.
+    fn stack_name(self) -> &'static str {
+        match self {
+            Self::Profiles => "profiles",
+            Self::Led => "led",
+            Self::FanCurves => "fan",
+            Self::Battery => "battery",
+            Self::Log => "log",
+        }
+    }
+}
 
 #[relm4::component(pub)]
 impl Component for App {
     type CommandOutput = Command;
-    type Init = ();
+    /// Start without showing the main window.
+    type Init = bool;
     type Input = AppMsg;
     type Output = ();
 
@@ -76,7 +218,15 @@ impl Component for App {
             section! {
                 "_Preferences" => PreferencesAction,
                 "_Keyboard Shortcuts" => ShortcutsAction,
-                "_Hardware information" => HardwareInfoAction,
+                "_About this device" => HardwareInfoAction,
+                "_Import from TUXEDO Control Center" => ImportTccAction,
+                "_Start with the session" => AutostartAction,
+                "A_dvanced mode" => AdvancedModeAction,
+                "_Confirm profile changes" => PreviewProfileAction,
+                "_Battery conservation mode" => BatteryConservationAction,
+                "Battery _calibration" => BatteryCalibrationAction,
+                "_Webcam" => WebcamAction,
+                "_Fn-lock" => FnLockAction,
                 "_About Tailor" => AboutAction,
             }
         }
@@ -84,9 +234,9 @@ impl Component for App {
 
     view! {
         main_window = adw::ApplicationWindow::new(&main_application()) {
-            set_visible: true,
+            set_visible: !hidden,
             connect_close_request[sender] => move |_| {
-                sender.input(AppMsg::Quit);
+                sender.input(AppMsg::CloseWindow);
                 gtk::glib::Propagation::Stop
             },
 
@@ -130,11 +280,26 @@ impl Component for App {
                             }
                         },
 
+                        #[name = "search_button"]
+                        pack_start = &gtk::ToggleButton {
+                            set_icon_name: "system-search-symbolic",
+                            set_tooltip_text: Some("Search settings"),
+                            update_property: &[gtk::accessible::Property::Label("Search settings")],
+                            #[watch]
+                            set_visible: model.connection_state.is_ok(),
+                        },
+
                         pack_end = &gtk::MenuButton {
                             set_icon_name: icon_names::MENU_LARGE,
+                            set_tooltip_text: Some("Main menu"),
+                            update_property: &[gtk::accessible::Property::Label("Main menu")],
                             set_menu_model: Some(&primary_menu),
                         }
                     },
+                    #[local_ref]
+                    search_bar -> gtk::SearchBar {
+                        set_key_capture_widget: Some(&main_window),
+                    },
                     #[transition(SlideDown)]
                     match &model.connection_state {
                         ConnectionState::Ok => {
@@ -160,6 +325,17 @@ impl Component for App {
                                     add_titled[Some("fan"), "Fan control"] = fan_list -> gtk::ScrolledWindow {} -> {
                                         set_icon_name: Some(icon_names::DATA_BAR_VERTICAL_ASCENDING_FILLED),
                                     },
+                                    #[local_ref]
+                                    add_titled[Some("battery"), "Battery"] = battery_widget -> gtk::ScrolledWindow {} -> {
+                                        set_icon_name: Some("battery-full-symbolic"),
+                                        #[watch]
+                                        set_visible: hardware_capabilities()
+                                            .is_some_and(|capabilities| capabilities.battery_calibration),
+                                    },
+                                    #[local_ref]
+                                    add_titled[Some("log"), "Log"] = log_widget -> gtk::ScrolledWindow {} -> {
+                                        set_icon_name: Some("text-x-generic-symbolic"),
+                                    },
                                 },
                                 #[name = "view_bar"]
                                 adw::ViewSwitcherBar {
@@ -223,10 +399,14 @@ impl Component for App {
 
         let err = matches!(&model.connection_state, ConnectionState::Error);
         err_spinner.set_spinning(err);
+
+        if let Some(page) = model.requested_page.take() {
+            view_stack.set_visible_child_name(page.stack_name());
+        }
     }
 
     fn init(
-        _init: Self::Init,
+        hidden: Self::Init,
         root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
@@ -253,14 +433,108 @@ impl Component for App {
         fan_list.detach_runtime();
         let fan_list = &**fan_list.widget();
 
+        let mut battery = BatteryPage::builder().launch(()).detach();
+        battery.detach_runtime();
+        let battery_widget = &**battery.widget();
+
+        let mut log = LogPage::builder().launch(()).detach();
+        log.detach_runtime();
+        let log_widget = &**log.widget();
+
         let mut profiles = Profiles::builder().launch(()).detach();
         profiles.detach_runtime();
         let profile_widget = &**profiles.widget();
 
+        let search = SettingsSearch::builder()
+            .launch(())
+            .forward(sender.input_sender(), |msg| match msg {
+                SearchOutput::ShowPage(page) => AppMsg::ShowPage(page),
+            });
+        let search_bar = &search.widget().clone();
+
+        let autostart = gio::Settings::new(APP_ID).boolean("autostart");
+        let autostart_action = {
+            let sender = sender.clone();
+            RelmAction::<AutostartAction>::new_stateful(&autostart, move |_, state| {
+                // The state is updated once the portal responded.
+                sender.input(AppMsg::SetAutostart(!*state));
+            })
+        };
+
+        let advanced_mode = advanced_mode::is_enabled();
+        let advanced_mode_action = {
+            let sender = sender.clone();
+            RelmAction::<AdvancedModeAction>::new_stateful(&advanced_mode, move |_, state| {
+                // The state is updated once the user confirmed the warning.
+                sender.input(AppMsg::SetAdvancedMode(!*state));
+            })
+        };
+
+        let preview_profile_action = RelmAction::<PreviewProfileAction>::new_stateful(
+            &preview_profile::is_enabled(),
+            |_, state| {
+                *state = !*state;
+                let settings = gio::Settings::new(APP_ID);
+                if let Err(err) = settings.set_boolean(preview_profile::SETTING, *state) {
+                    tracing::warn!("Failed to store the profile preview setting: `{err}`");
+                }
+            },
+        );
+
+        let battery_conservation_action = {
+            let sender = sender.clone();
+            RelmAction::<BatteryConservationAction>::new_stateful(&false, move |_, state| {
+                // The state is updated once tailord applied it.
+                sender.input(AppMsg::SetBatteryConservation(!*state));
+            })
+        };
+        battery_conservation_action.gio_action().set_enabled(false);
+
+        let webcam_action = {
+            let sender = sender.clone();
+            RelmAction::<WebcamAction>::new_stateful(&false, move |_, state| {
+                // The state is updated once the driver reports it.
+                sender.input(AppMsg::SetWebcam(!*state));
+            })
+        };
+        webcam_action.gio_action().set_enabled(false);
+
+        let fn_lock_action = {
+            let sender = sender.clone();
+            RelmAction::<FnLockAction>::new_stateful(&false, move |_, state| {
+                // The state is updated once tailord applied it.
+                sender.input(AppMsg::SetFnLock(!*state));
+            })
+        };
+        fn_lock_action.gio_action().set_enabled(false);
+
+        let battery_calibration_action = {
+            let window = root.clone();
+            RelmAction::<BatteryCalibrationAction>::new_stateless(move |_| {
+                BatteryCalibration::builder()
+                    .transient_for(&window)
+                    .launch(())
+                    .detach();
+            })
+        };
+        battery_calibration_action
+            .gio_action()
+            .set_enabled(advanced_mode);
+
         let model = Self {
             about_dialog,
+            search,
             connection_state: ConnectionState::Connecting,
             error: None,
+            autostart,
+            autostart_action: autostart_action.gio_action().clone(),
+            advanced_mode_action: advanced_mode_action.gio_action().clone(),
+            battery_calibration_action: battery_calibration_action.gio_action().clone(),
+            battery_conservation_action: battery_conservation_action.gio_action().clone(),
+            webcam_action: webcam_action.gio_action().clone(),
+            fn_lock_action: fn_lock_action.gio_action().clone(),
+            requested_page: Cell::new(None),
+            pending_profile: None,
         };
 
         let widgets = view_output!();
@@ -270,6 +544,13 @@ impl Component for App {
             .bind_property("title-visible", &widgets.view_bar, "reveal")
             .build();
 
+        widgets
+            .search_button
+            .bind_property("active", search_bar, "search-mode-enabled")
+            .bidirectional()
+            .sync_create()
+            .build();
+
         let shortcuts_action = {
             let shortcuts = widgets.shortcuts.clone();
             RelmAction::<ShortcutsAction>::new_stateless(move |_| {
@@ -280,13 +561,16 @@ impl Component for App {
         let hardware_action = {
             let window = widgets.main_window.clone();
             RelmAction::<HardwareInfoAction>::new_stateless(move |_| {
-                HardwareInfo::builder()
+                AboutDevice::builder()
                     .transient_for(&window)
                     .launch(())
                     .detach();
             })
         };
 
+        let import_tcc_action =
+            RelmAction::<ImportTccAction>::new_stateless(|_| import_tcc::import());
+
         let about_action = {
             let sender = model.about_dialog.sender().clone();
             RelmAction::<AboutAction>::new_stateless(move |_| {
@@ -298,8 +582,44 @@ impl Component for App {
         actions.add_action(shortcuts_action);
         actions.add_action(about_action);
         actions.add_action(hardware_action);
+        actions.add_action(import_tcc_action);
+        actions.add_action(autostart_action);
+        actions.add_action(advanced_mode_action);
+        actions.add_action(preview_profile_action);
+        actions.add_action(battery_conservation_action);
+        actions.add_action(battery_calibration_action);
+        actions.add_action(webcam_action);
+        actions.add_action(fn_lock_action);
         actions.register_for_widget(&widgets.main_window);
 
+        // Show the window when Tailor is launched again while it runs in the background.
+        {
+            let window = widgets.main_window.clone();
+            main_application().connect_activate(move |_| window.present());
+        }
+
+        let show_page_action = {
+            let sender = sender.clone();
+            RelmAction::<ShowPageAction>::new_with_target_value(move |_, name: String| {
+                match Page::from_name(&name) {
+                    Some(page) => sender.input(AppMsg::ShowPage(page)),
+                    None => tracing::warn!("Unknown page `{name}`"),
+                }
+            })
+        };
+
+        let activate_profile_action = {
+            let sender = sender.clone();
+            RelmAction::<ActivateProfileAction>::new_with_target_value(move |_, name: String| {
+                sender.input(AppMsg::ActivateProfile(name));
+            })
+        };
+
+        let mut app_actions = RelmActionGroup::<AppActionGroup>::new();
+        app_actions.add_action(show_page_action);
+        app_actions.add_action(activate_profile_action);
+        app_actions.register_for_main_application();
+
         widgets.load_window_size();
 
         Self::initialize_connection(&sender, None);
@@ -307,12 +627,104 @@ impl Component for App {
         ComponentParts { model, widgets }
     }
 
-    fn update(&mut self, message: Self::Input, _sender: ComponentSender<Self>, _root: &Self::Root) {
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>, root: &Self::Root) {
         match message {
             AppMsg::AddError(error) => {
                 self.error = Some(adw::Toast::new(&error));
             }
-            AppMsg::Quit => main_application().quit(),
+            AppMsg::CloseWindow => {
+                if self.autostart {
+                    // Keep running in the background.
+                    root.set_visible(false);
+                } else {
+                    main_application().quit();
+                }
+            }
+            AppMsg::ShowPage(page) => {
+                self.requested_page.set(Some(page));
+                root.present();
+            }
+            AppMsg::ActivateProfile(name) => {
+                if self.connection_state.is_ok() {
+                    STATE.emit(TailorStateMsg::SetActiveProfile(name));
+                } else {
+                    self.pending_profile = Some(name);
+                }
+            }
+            AppMsg::SetAutostart(enable) => {
+                let window = AsRef::<gtk::Window>::as_ref(root).clone();
+                relm4::spawn_local(async move {
+                    let result = background::request_autostart(&window, enable)
+                        .await
+                        .map_err(|err| err.to_string());
+                    sender.input(AppMsg::AutostartChanged(result));
+                });
+            }
+            AppMsg::AutostartChanged(Ok(autostart)) => {
+                self.autostart = autostart;
+                self.autostart_action.set_state(&autostart.to_variant());
+                if let Err(err) = gio::Settings::new(APP_ID).set_boolean("autostart", autostart) {
+                    tracing::warn!("Failed to store the autostart setting: `{err}`");
+                }
+            }
+            AppMsg::SetAdvancedMode(true) => {
+                relm4::spawn_local(async move {
+                    if advanced_mode::confirm().await {
+                        sender.input(AppMsg::AdvancedModeChanged(true));
+                    }
+                });
+            }
+            AppMsg::SetAdvancedMode(false) => {
+                sender.input(AppMsg::AdvancedModeChanged(false));
+            }
+            AppMsg::AdvancedModeChanged(enabled) => {
+                self.advanced_mode_action.set_state(&enabled.to_variant());
+                self.battery_calibration_action.set_enabled(enabled);
+                if let Err(err) = gio::Settings::new(APP_ID).set_boolean("advanced-mode", enabled) {
+                    tracing::warn!("Failed to store the advanced mode setting: `{err}`");
+                }
+            }
+            AppMsg::SetBatteryConservation(enable) => {
+                if let Some(connection) = tailor_connection() {
+                    sender.oneshot_command(async move {
+                        let result = connection
+                            .set_battery_conservation(enable)
+                            .await
+                            .map(|()| enable)
+                            .map_err(|err| err.to_string());
+                        Command::BatteryConservationChanged(result)
+                    });
+                }
+            }
+            AppMsg::SetWebcam(enable) => {
+                if let Some(connection) = tailor_connection() {
+                    sender.oneshot_command(async move {
+                        // Read the state back, the driver may ignore the request.
+                        let result = match connection.set_webcam_enabled(enable).await {
+                            Ok(()) => connection.get_webcam_enabled().await,
+                            Err(err) => Err(err),
+                        };
+                        Command::WebcamChanged(result.map_err(|err| err.to_string()))
+                    });
+                }
+            }
+            AppMsg::SetFnLock(enable) => {
+                if let Some(connection) = tailor_connection() {
+                    sender.oneshot_command(async move {
+                        let result = connection
+                            .set_fn_lock(enable)
+                            .await
+                            .map(|()| enable)
+                            .map_err(|err| err.to_string());
+                        Command::FnLockChanged(result)
+                    });
+                }
+            }
+            AppMsg::AutostartChanged(Err(err)) => {
+                self.error = Some(adw::Toast::new(&format!(
+                    "Failed to change the autostart setting: {err}"
+                )));
+            }
         }
     }
 
@@ -330,8 +742,72 @@ impl Component for App {
                     Self::initialize_connection(&sender, Some(Duration::from_secs(5)));
                 } else {
                     self.connection_state = ConnectionState::Ok;
+                    if let Some(name) = self.pending_profile.take() {
+                        STATE.emit(TailorStateMsg::SetActiveProfile(name));
+                    }
+                    let battery_conservation = hardware_capabilities()
+                        .is_some_and(|capabilities| capabilities.battery_conservation);
+                    if let (true, Some(connection)) = (battery_conservation, tailor_connection()) {
+                        sender.oneshot_command(async move {
+                            let result = connection
+                                .get_battery_conservation()
+                                .await
+                                .map_err(|err| err.to_string());
+                            Command::BatteryConservationChanged(result)
+                        });
+                    }
+                    let webcam =
+                        hardware_capabilities().is_some_and(|capabilities| capabilities.webcam);
+                    if let (true, Some(connection)) = (webcam, tailor_connection()) {
+                        sender.oneshot_command(async move {
+                            let result = connection
+                                .get_webcam_enabled()
+                                .await
+                                .map_err(|err| err.to_string());
+                            Command::WebcamChanged(result)
+                        });
+                    }
+                    let fn_lock =
+                        hardware_capabilities().is_some_and(|capabilities| capabilities.fn_lock);
+                    if let (true, Some(connection)) = (fn_lock, tailor_connection()) {
+                        sender.oneshot_command(async move {
+                            let result = connection
+                                .get_fn_lock()
+                                .await
+                                .map_err(|err| err.to_string());
+                            Command::FnLockChanged(result)
+                        });
+                    }
                 }
             }
+            Command::BatteryConservationChanged(Ok(enabled)) => {
+                self.battery_conservation_action
+                    .set_state(&enabled.to_variant());
+                self.battery_conservation_action.set_enabled(true);
+            }
+            Command::BatteryConservationChanged(Err(err)) => {
+                self.error = Some(adw::Toast::new(&format!(
+                    "Failed to change the battery conservation mode: {err}"
+                )));
+            }
+            Command::WebcamChanged(Ok(enabled)) => {
+                self.webcam_action.set_state(&enabled.to_variant());
+                self.webcam_action.set_enabled(true);
+            }
+            Command::WebcamChanged(Err(err)) => {
+                self.error = Some(adw::Toast::new(&format!(
+                    "Failed to change the webcam state: {err}"
+                )));
+            }
+            Command::FnLockChanged(Ok(enabled)) => {
+                self.fn_lock_action.set_state(&enabled.to_variant());
+                self.fn_lock_action.set_enabled(true);
+            }
+            Command::FnLockChanged(Err(err)) => {
+                self.error = Some(adw::Toast::new(&format!(
+                    "Failed to change the Fn-lock: {err}"
+                )));
+            }
         }
     }
 
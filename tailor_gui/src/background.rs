@@ -0,0 +1,33 @@
+//! Autostart via the background portal, so Tailor can start
+//! with the session without showing the main window.
+//! The portal also works inside of the Flatpak sandbox.
+
+use ashpd::desktop::background::Background;
+use ashpd::WindowIdentifier;
+use relm4::gtk;
+
+/// Command that the session runs on login.
+const AUTOSTART_COMMAND: [&str; 2] = ["tailor_gui", "--hidden"];
+
+/// Ask the portal to enable or disable autostart and running in the background.
+/// The portal might ask the user for permission, so the request is attached to
+/// the window. Returns whether autostart is enabled afterwards.
+pub async fn request_autostart(window: &gtk::Window, enable: bool) -> ashpd::Result<bool> {
+    let identifier = WindowIdentifier::from_native(window).await;
+    let response = Background::request()
+        .identifier(identifier)
+        .reason("Start Tailor with the session to monitor your hardware in the background")
+        .auto_start(enable)
+        .command(AUTOSTART_COMMAND)
+        .dbus_activatable(false)
+        .send()
+        .await?
+        .response()?;
+
+    tracing::debug!(
+        "Background portal response: autostart {}, background {}",
+        response.auto_start(),
+        response.run_in_background()
+    );
+    Ok(response.auto_start())
+}
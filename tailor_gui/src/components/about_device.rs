@@ -0,0 +1,359 @@
+use std::fmt::Write;
+
+use adw::prelude::{ActionRowExt, PreferencesGroupExt, PreferencesRowExt};
+use relm4::gtk::prelude::{ButtonExt, GtkWindowExt, OrientableExt, WidgetExt};
+use relm4::{adw, gtk, Component, ComponentParts, ComponentSender, RelmWidgetExt};
+use tailor_api::{DeviceInfo, HardwareCapabilities};
+
+use crate::state::{hardware_capabilities, tailor_connection};
+use crate::templates;
+
+/// The model, firmware, drivers and features of the device,
+/// which can be copied into bug reports.
+pub struct AboutDevice {
+    hardware: HardwareCapabilities,
+    device: Option<DeviceInfo>,
+    error: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum AboutDeviceInput {
+    Copy,
+}
+
+#[derive(Debug)]
+pub enum AboutDeviceCommand {
+    Loaded(DeviceInfo),
+    Error(String),
+}
+
+impl AboutDevice {
+    fn value(&self, value: impl Fn(&DeviceInfo) -> &Option<String>) -> String {
+        match (&self.device, &self.error) {
+            (Some(device), _) => value(device)
+                .clone()
+                .unwrap_or_else(|| "Unknown".to_owned()),
+            (None, Some(err)) => err.clone(),
+            (None, None) => "Loading…".to_owned(),
+        }
+    }
+
+    fn bios(&self) -> String {
+        let version = self.value(|device| &device.bios_version);
+        match self
+            .device
+            .as_ref()
+            .and_then(|device| device.bios_date.as_ref())
+        {
+            Some(date) => format!("{version} ({date})"),
+            None => version,
+        }
+    }
+
+    fn tailord_version(&self) -> String {
+        self.device
+            .as_ref()
+            .map(|device| device.tailord_version.clone())
+            .unwrap_or_default()
+    }
+
+    fn capabilities(&self) -> String {
+        self.device
+            .as_ref()
+            .map(|device| comma_list(device.capabilities.iter().map(String::as_str)))
+            .unwrap_or_default()
+    }
+
+    /// Everything on the page as text.
+    fn report(&self) -> String {
+        let mut report = match &self.device {
+            Some(device) => device.to_string(),
+            None => String::new(),
+        };
+        let info = &self.hardware;
+        write!(
+            report,
+            "\nFans: {}\nLED devices: {}\nPerformance profiles: {}\nFans can be turned off: {}\nTDP slots: {}",
+            info.num_of_fans,
+            led_info(info),
+            performance_info(info),
+            fans_off_info(info),
+            info.tdp_slots(),
+        )
+        .unwrap();
+        report
+    }
+}
+
+#[relm4::component(pub)]
+impl Component for AboutDevice {
+    type CommandOutput = AboutDeviceCommand;
+    type Init = ();
+    type Input = AboutDeviceInput;
+    type Output = ();
+
+    view! {
+        #[template]
+        #[name = "window"]
+        templates::DialogWindow {
+            set_visible: true,
+            set_default_size: (500, 600),
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+
+                gtk::WindowHandle {
+                    gtk::CenterBox {
+                        #[wrap(Some)]
+                        set_center_widget = &gtk::Label {
+                            add_css_class: "title-4",
+                            set_margin_all: 12,
+                            set_label: "About this device"
+                        },
+                    },
+                },
+
+                gtk::ScrolledWindow {
+                    set_vexpand: true,
+                    set_hscrollbar_policy: gtk::PolicyType::Never,
+
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                        set_margin_all: 18,
+                        set_spacing: 24,
+
+                        adw::PreferencesGroup {
+                            set_title: "Device",
+
+                            adw::ActionRow {
+                                set_title: "Vendor",
+                                #[watch]
+                                set_subtitle: &model.value(|device| &device.vendor),
+                            },
+                            adw::ActionRow {
+                                set_title: "Model",
+                                #[watch]
+                                set_subtitle: &model.value(|device| &device.product_name),
+                            },
+                            adw::ActionRow {
+                                set_title: "Board",
+                                #[watch]
+                                set_subtitle: &model.value(|device| &device.board_name),
+                            },
+                            adw::ActionRow {
+                                set_title: "BIOS",
+                                #[watch]
+                                set_subtitle: &model.bios(),
+                            },
+                            adw::ActionRow {
+                                set_title: "Embedded controller",
+                                #[watch]
+                                set_subtitle: &model.value(|device| &device.ec_version),
+                            },
+                        },
+
+                        #[name = "drivers_group"]
+                        adw::PreferencesGroup {
+                            set_title: "Drivers",
+
+                            adw::ActionRow {
+                                set_title: "Interface",
+                                #[watch]
+                                set_subtitle: &model.value(|device| &device.interface),
+                            },
+                            adw::ActionRow {
+                                set_title: "ODM interface",
+                                #[watch]
+                                set_subtitle: &model.value(|device| &device.odm_interface),
+                            },
+                            adw::ActionRow {
+                                set_title: "tuxedo_io",
+                                #[watch]
+                                set_subtitle: &model.value(|device| &device.io_module_version),
+                                #[watch]
+                                set_visible: model.device.as_ref()
+                                    .is_some_and(|device| device.io_module_version.is_some()),
+                            },
+                            adw::ActionRow {
+                                set_title: "Kernel",
+                                #[watch]
+                                set_subtitle: &model.value(|device| &device.kernel_version),
+                            },
+                            adw::ActionRow {
+                                set_title: "tailord",
+                                #[watch]
+                                set_subtitle: &model.tailord_version(),
+                                #[watch]
+                                set_visible: model.device.is_some(),
+                            },
+                        },
+
+                        adw::PreferencesGroup {
+                            set_title: "Features",
+
+                            adw::ActionRow {
+                                set_title: "Detected capabilities",
+                                #[watch]
+                                set_subtitle: &model.capabilities(),
+                                #[watch]
+                                set_visible: model.device.is_some(),
+                            },
+                            adw::ActionRow {
+                                set_title: "Fans",
+                                set_subtitle: &model.hardware.num_of_fans.to_string(),
+                            },
+                            adw::ActionRow {
+                                set_title: "LED devices",
+                                set_subtitle: &led_info(&model.hardware),
+                            },
+                            adw::ActionRow {
+                                set_title: "Performance profiles",
+                                set_subtitle: &performance_info(&model.hardware),
+                            },
+                            adw::ActionRow {
+                                set_title: "Fans can be turned off",
+                                set_subtitle: fans_off_info(&model.hardware),
+                            },
+                            adw::ActionRow {
+                                set_title: "TDP slots",
+                                set_subtitle: &model.hardware.tdp_slots().to_string(),
+                            },
+                        },
+                    },
+                },
+
+                gtk::Separator,
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    add_css_class: "response-area",
+
+                    gtk::Button {
+                        set_label: "Copy",
+                        set_hexpand: true,
+                        set_tooltip_text: Some("Copy the information to the clipboard, e.g. for a bug report"),
+                        add_css_class: "flat",
+                        connect_clicked => AboutDeviceInput::Copy,
+                    },
+                    gtk::Separator,
+                    gtk::Button {
+                        set_label: "Close",
+                        set_hexpand: true,
+                        #[iterate]
+                        add_css_class: &["flat", "suggested"],
+                        connect_clicked: move |btn| {
+                            let window = btn.toplevel_window().unwrap();
+                            window.destroy();
+                        },
+                    },
+                }
+            }
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = AboutDevice {
+            hardware: hardware_capabilities().cloned().unwrap_or_default(),
+            device: None,
+            error: None,
+        };
+
+        sender.oneshot_command(async move {
+            let Some(connection) = tailor_connection() else {
+                return AboutDeviceCommand::Error("Not connected to tailord".to_owned());
+            };
+            match connection.get_device_info().await {
+                Ok(device) => AboutDeviceCommand::Loaded(device),
+                Err(err) => AboutDeviceCommand::Error(err.to_string()),
+            }
+        });
+
+        let widgets = view_output!();
+        ComponentParts { model, widgets }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        input: Self::Input,
+        _sender: ComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        let AboutDeviceInput::Copy = input;
+        widgets.drivers_group.clipboard().set_text(&self.report());
+    }
+
+    fn update_cmd_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        command: Self::CommandOutput,
+        sender: ComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        match command {
+            AboutDeviceCommand::Loaded(device) => {
+                for driver in &device.drivers {
+                    let row = adw::ActionRow::builder()
+                        .title(&driver.name)
+                        .subtitle(driver.version.as_deref().unwrap_or("Unknown"))
+                        .use_markup(false)
+                        .build();
+                    widgets.drivers_group.add(&row);
+                }
+                self.device = Some(device);
+            }
+            AboutDeviceCommand::Error(err) => {
+                tracing::error!("Failed to load the device information: {err}");
+                self.error = Some(err);
+            }
+        }
+        self.update_view(widgets, sender);
+    }
+}
+
+fn led_info(info: &HardwareCapabilities) -> String {
+    comma_list(info.led_devices.iter().map(|d| d.device_id()))
+}
+
+fn performance_info(info: &HardwareCapabilities) -> String {
+    comma_list_optional(
+        info.supports_performance_profiles()
+            .then_some(info.performance_profiles.iter()),
+    )
+}
+
+fn fans_off_info(info: &HardwareCapabilities) -> &'static str {
+    if info.supports_fans_off() {
+        "Yes"
+    } else {
+        "No"
+    }
+}
+
+fn comma_list<I, S>(iter: I) -> String
+where
+    I: Iterator<Item = S>,
+    S: Into<String>,
+{
+    let value: String = iter.fold(String::new(), |mut out, string| {
+        write!(&mut out, ", {}", string.into()).unwrap();
+        out
+    });
+    value.trim_start_matches(", ").to_owned()
+}
+
+fn comma_list_optional<I, S>(iter: Option<I>) -> String
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    if let Some(iter) = iter {
+        comma_list(iter.into_iter())
+    } else {
+        "Device not available".into()
+    }
+}
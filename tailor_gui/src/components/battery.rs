@@ -0,0 +1,201 @@
+use adw::prelude::{ActionRowExt, PreferencesGroupExt, PreferencesRowExt};
+use gtk::prelude::{OrientableExt, WidgetExt};
+use relm4::{adw, component, gtk, Component, ComponentParts, ComponentSender};
+use tailor_api::{BatteryHealth, BatteryHealthSample};
+
+use crate::state::{tailor_connection, TailorStateInner, STATE};
+use crate::templates;
+
+/// The health of the battery and how it changed over time.
+pub struct BatteryPage {
+    health: Option<BatteryHealth>,
+    history: Vec<BatteryHealthSample>,
+    /// The rows of `history` in the history group.
+    history_rows: Vec<adw::ActionRow>,
+    error: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum BatteryPageInput {
+    Reload,
+}
+
+#[derive(Debug)]
+pub enum BatteryPageCommand {
+    Loaded(BatteryHealth, Vec<BatteryHealthSample>),
+    Error(String),
+}
+
+impl BatteryPage {
+    fn health_label(&self) -> String {
+        match (&self.health, &self.error) {
+            (Some(health), _) => format!("{:.0}%", health.percentage()),
+            (None, Some(err)) => err.clone(),
+            (None, None) => "Loading…".to_owned(),
+        }
+    }
+
+    fn capacity_label(&self) -> String {
+        self.health
+            .as_ref()
+            .map(|health| {
+                format!(
+                    "{} {unit} of {} {unit}",
+                    health.full_capacity,
+                    health.design_capacity,
+                    unit = health.unit
+                )
+            })
+            .unwrap_or_default()
+    }
+
+    fn cycles_label(&self) -> String {
+        self.health
+            .as_ref()
+            .and_then(|health| health.cycle_count)
+            .map(|cycles| cycles.to_string())
+            .unwrap_or_else(|| "Unknown".to_owned())
+    }
+}
+
+#[component(pub)]
+impl Component for BatteryPage {
+    type CommandOutput = BatteryPageCommand;
+    type Init = ();
+    type Input = BatteryPageInput;
+    type Output = ();
+
+    view! {
+        #[template]
+        templates::CustomClamp {
+            #[template_child]
+            clamp {
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_spacing: 24,
+
+                    adw::PreferencesGroup {
+                        set_title: "Battery health",
+                        set_description: Some("The capacity of the last full charge compared to when the battery was new"),
+
+                        adw::ActionRow {
+                            set_title: "Health",
+                            #[watch]
+                            set_subtitle: &model.health_label(),
+                        },
+                        adw::ActionRow {
+                            set_title: "Capacity",
+                            #[watch]
+                            set_subtitle: &model.capacity_label(),
+                            #[watch]
+                            set_visible: model.health.is_some(),
+                        },
+                        adw::ActionRow {
+                            set_title: "Charge cycles",
+                            #[watch]
+                            set_subtitle: &model.cycles_label(),
+                            #[watch]
+                            set_visible: model.health.is_some(),
+                        },
+                    },
+
+                    #[name = "history_group"]
+                    adw::PreferencesGroup {
+                        set_title: "History",
+                        set_description: Some("Tailord records the health once a day"),
+                        #[watch]
+                        set_visible: !model.history.is_empty(),
+                    },
+                }
+            }
+        }
+    }
+
+    fn init(
+        _: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        // Load the health once connected.
+        STATE.subscribe_optional(sender.input_sender(), |state| {
+            state
+                .get()
+                .filter(|state| state.changed(TailorStateInner::connection()))
+                .map(|_| BatteryPageInput::Reload)
+        });
+
+        let model = Self {
+            health: None,
+            history: Vec::new(),
+            history_rows: Vec::new(),
+            error: None,
+        };
+        let widgets = view_output!();
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, input: Self::Input, sender: ComponentSender<Self>, _root: &Self::Root) {
+        match input {
+            BatteryPageInput::Reload => sender.oneshot_command(async move {
+                let Some(connection) = tailor_connection() else {
+                    return BatteryPageCommand::Error("Not connected to tailord".to_owned());
+                };
+                let result = async {
+                    let health = connection.get_battery_health().await?;
+                    let history = connection.get_battery_health_history().await?;
+                    Ok::<_, tailor_client::ClientError>((health, history))
+                }
+                .await;
+                match result {
+                    Ok((health, history)) => BatteryPageCommand::Loaded(health, history),
+                    Err(err) => BatteryPageCommand::Error(err.to_string()),
+                }
+            }),
+        }
+    }
+
+    fn update_cmd_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        command: Self::CommandOutput,
+        sender: ComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        match command {
+            BatteryPageCommand::Loaded(health, history) => {
+                self.health = Some(health);
+                self.history = history;
+                self.error = None;
+            }
+            BatteryPageCommand::Error(err) => {
+                self.health = None;
+                self.history = Vec::new();
+                self.error = Some(err);
+            }
+        }
+
+        // Newest first, the rows are rebuilt because the history only grows once a day.
+        while let Some(row) = self.history_rows.pop() {
+            widgets.history_group.remove(&row);
+        }
+        for sample in self.history.iter().rev() {
+            let row = adw::ActionRow::builder()
+                .title(date(sample.timestamp))
+                .subtitle(format!("{:.0}%", sample.health.percentage()))
+                .use_markup(false)
+                .build();
+            widgets.history_group.add(&row);
+            self.history_rows.push(row);
+        }
+
+        self.update_view(widgets, sender);
+    }
+}
+
+/// The day of a timestamp in UTC, e.g. `2024-03-01`.
+fn date(timestamp: u64) -> String {
+    gtk::glib::DateTime::from_unix_utc(timestamp as i64)
+        .and_then(|date| date.format("%F"))
+        .map(|date| date.to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,229 @@
+use futures::StreamExt;
+use relm4::gtk::prelude::{ButtonExt, GtkWindowExt, OrientableExt, WidgetExt};
+use relm4::{gtk, Component, ComponentParts, ComponentSender, RelmWidgetExt};
+use tailor_api::{CalibrationPhase, CalibrationStatus};
+
+use crate::state::tailor_connection;
+use crate::templates;
+
+/// Shows the progress of the battery calibration of tailord.
+/// The calibration continues when the dialog is closed.
+pub struct BatteryCalibration {
+    status: CalibrationStatus,
+    error: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum BatteryCalibrationInput {
+    /// Start the calibration, or cancel it if it's running.
+    Toggle,
+}
+
+#[derive(Debug)]
+pub enum BatteryCalibrationCommand {
+    Status(CalibrationStatus),
+    Error(String),
+}
+
+impl BatteryCalibration {
+    fn description(&self) -> String {
+        if let Some(err) = &self.error {
+            return err.clone();
+        }
+        let capacity = self.status.capacity;
+        match self.status.phase {
+            CalibrationPhase::Idle => {
+                "Charges the battery to full, discharges it and charges it to full again, \
+                so the firmware learns the actual capacity. This takes several hours."
+                    .to_owned()
+            }
+            CalibrationPhase::Charging => format!("Charging to full ({capacity}%)"),
+            CalibrationPhase::Discharging => {
+                format!("Discharging to {}% ({capacity}%)", self.status.discharge_to)
+            }
+            CalibrationPhase::Recharging => format!("Charging to full again ({capacity}%)"),
+            CalibrationPhase::Finished => "The calibration is finished".to_owned(),
+            CalibrationPhase::Cancelled => "The calibration was cancelled".to_owned(),
+            CalibrationPhase::Failed => "The battery couldn't be read".to_owned(),
+        }
+    }
+
+    fn instruction(&self) -> &'static str {
+        if !self.status.needs_charger_change() {
+            ""
+        } else if self.status.plugged_in {
+            "Unplug the charger to continue"
+        } else {
+            "Plug in the charger to continue"
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        self.status.phase.is_running()
+    }
+}
+
+#[relm4::component(pub)]
+impl Component for BatteryCalibration {
+    type CommandOutput = BatteryCalibrationCommand;
+    type Init = ();
+    type Input = BatteryCalibrationInput;
+    type Output = ();
+
+    view! {
+        #[template]
+        #[name = "window"]
+        templates::DialogWindow {
+            set_visible: true,
+            set_default_size: (400, 0),
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+
+                gtk::WindowHandle {
+                    gtk::CenterBox {
+                        #[wrap(Some)]
+                        set_center_widget = &gtk::Label {
+                            add_css_class: "title-4",
+                            set_margin_all: 12,
+                            set_label: "Battery calibration"
+                        },
+                    },
+                },
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_margin_all: 18,
+                    set_spacing: 12,
+                    set_vexpand: true,
+
+                    gtk::Label {
+                        set_wrap: true,
+                        #[watch]
+                        set_label: &model.description(),
+                    },
+                    gtk::ProgressBar {
+                        #[watch]
+                        set_fraction: model.status.progress(),
+                        #[watch]
+                        set_visible: model.is_running(),
+                    },
+                    gtk::Label {
+                        add_css_class: "warning",
+                        #[watch]
+                        set_label: model.instruction(),
+                        #[watch]
+                        set_visible: !model.instruction().is_empty(),
+                    },
+                },
+
+                gtk::Separator,
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    add_css_class: "response-area",
+
+                    gtk::Button {
+                        set_label: "Close",
+                        set_hexpand: true,
+                        add_css_class: "flat",
+                        connect_clicked: move |btn| {
+                            let window = btn.toplevel_window().unwrap();
+                            window.destroy();
+                        },
+                    },
+                    gtk::Separator,
+                    gtk::Button {
+                        set_hexpand: true,
+                        #[watch]
+                        set_label: if model.is_running() { "Cancel" } else { "Start" },
+                        #[watch]
+                        set_css_classes: if model.is_running() {
+                            &["flat", "destructive"]
+                        } else {
+                            &["flat", "suggested"]
+                        },
+                        connect_clicked => BatteryCalibrationInput::Toggle,
+                    },
+                }
+            }
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = BatteryCalibration {
+            status: CalibrationStatus::default(),
+            error: None,
+        };
+
+        sender.command(|out, shutdown| {
+            shutdown
+                .register(async move {
+                    let Some(connection) = tailor_connection() else {
+                        return;
+                    };
+                    // Subscribe first, so no change is missed.
+                    let stream = connection.receive_battery_calibration().await;
+                    let status = connection.get_battery_calibration_status().await;
+                    match status {
+                        Ok(status) => out.emit(BatteryCalibrationCommand::Status(status)),
+                        Err(err) => out.emit(BatteryCalibrationCommand::Error(err.to_string())),
+                    }
+                    let Ok(mut stream) = stream else {
+                        return;
+                    };
+                    while let Some(status) = stream.next().await {
+                        out.emit(BatteryCalibrationCommand::Status(status));
+                    }
+                })
+                .drop_on_shutdown()
+        });
+
+        let widgets = view_output!();
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, input: Self::Input, sender: ComponentSender<Self>, _root: &Self::Root) {
+        let BatteryCalibrationInput::Toggle = input;
+        let running = self.is_running();
+        sender.oneshot_command(async move {
+            let Some(connection) = tailor_connection() else {
+                return BatteryCalibrationCommand::Error("Not connected to tailord".to_owned());
+            };
+            let result = if running {
+                connection.cancel_battery_calibration().await
+            } else {
+                connection.start_battery_calibration().await
+            };
+            match result {
+                Ok(()) => match connection.get_battery_calibration_status().await {
+                    Ok(status) => BatteryCalibrationCommand::Status(status),
+                    Err(err) => BatteryCalibrationCommand::Error(err.to_string()),
+                },
+                Err(err) => BatteryCalibrationCommand::Error(err.to_string()),
+            }
+        });
+    }
+
+    fn update_cmd(
+        &mut self,
+        command: Self::CommandOutput,
+        _sender: ComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        match command {
+            BatteryCalibrationCommand::Status(status) => {
+                self.status = status;
+                self.error = None;
+            }
+            BatteryCalibrationCommand::Error(err) => {
+                tracing::error!("Battery calibration: {err}");
+                self.error = Some(err);
+            }
+        }
+    }
+}
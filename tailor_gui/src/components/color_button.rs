@@ -1,16 +1,28 @@
 use gtk::gdk::RGBA;
 use gtk::gdk_pixbuf::Pixbuf;
 use gtk::prelude::{
-    ButtonExt, Cast, ColorChooserExt, DialogExt, DialogExtManual, GridExt, GtkWindowExt, ObjectExt,
-    WidgetExt,
+    AccessibleExt, ButtonExt, Cast, ColorChooserExt, DialogExt, DialogExtManual, GridExt,
+    GtkWindowExt, ObjectExt, PopoverExt, RangeExt, WidgetExt,
 };
 use gtk::ResponseType;
 use relm4::{gtk, Component, ComponentParts, ComponentSender, RelmWidgetExt};
 use tailor_api::Color;
 
-use crate::state::{TailorStateMsg, STATE};
+use crate::state::{hardware_capabilities, TailorStateMsg, STATE};
 use crate::util;
 
+/// Whether all LED devices only support a brightness, e.g.
+/// white backlights. A slider replaces the color picker then.
+fn brightness_only() -> bool {
+    hardware_capabilities().map_or(false, |capabilities| {
+        !capabilities.led_devices.is_empty()
+            && capabilities
+                .led_devices
+                .iter()
+                .all(|device| !device.mode.supports_color())
+    })
+}
+
 pub struct ColorButton {
     pub color: Color,
     pixbuf: Pixbuf,
@@ -22,6 +34,38 @@ pub enum ColorButtonInput {
     UpdateColor(Color),
 }
 
+impl ColorButton {
+    /// The label for screen readers, which can't see the color.
+    fn description(&self) -> String {
+        if brightness_only() {
+            return format!("Change brightness, currently {}%", self.color.brightness());
+        }
+        let Color { r, g, b } = self.color;
+        format!("Change color, currently #{r:02X}{g:02X}{b:02X}")
+    }
+
+    fn open_brightness_popover(&self, sender: ComponentSender<Self>, root: &gtk::Button) {
+        let scale = gtk::Scale::with_range(gtk::Orientation::Horizontal, 0.0, 100.0, 1.0);
+        scale.set_width_request(200);
+        scale.set_value(self.color.brightness() as f64);
+        scale.connect_value_changed(move |scale| {
+            let color = Color {
+                r: 255,
+                g: 255,
+                b: 255,
+            }
+            .with_brightness(scale.value().round() as u8);
+            STATE.emit(TailorStateMsg::OverwriteColor(color.clone()));
+            sender.input(ColorButtonInput::UpdateColor(color));
+        });
+
+        let popover = gtk::Popover::builder().child(&scale).build();
+        popover.set_parent(root);
+        popover.connect_closed(|popover| popover.unparent());
+        popover.popup();
+    }
+}
+
 #[relm4::component(pub)]
 impl Component for ColorButton {
     type CommandOutput = ();
@@ -33,6 +77,10 @@ impl Component for ColorButton {
         button = gtk::Button {
             add_css_class: "color",
             set_width_request: 52,
+            #[watch]
+            set_tooltip_text: Some(&model.description()),
+            #[watch]
+            update_property: &[gtk::accessible::Property::Label(&model.description())],
             connect_clicked => ColorButtonInput::OpenDialog,
 
             #[name = "image"]
@@ -60,6 +108,9 @@ impl Component for ColorButton {
 
     fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>, root: &Self::Root) {
         match message {
+            ColorButtonInput::OpenDialog if brightness_only() => {
+                self.open_brightness_popover(sender, root);
+            }
             ColorButtonInput::OpenDialog => {
                 let parent_window = root.toplevel_window().unwrap();
                 let dialog = gtk::ColorChooserDialog::builder()
@@ -1,4 +1,4 @@
-use gtk::prelude::{OrientableExt, WidgetExt};
+use gtk::prelude::{AccessibleExt, OrientableExt, WidgetExt};
 use relm4::factory::{DynamicIndex, FactoryComponent, FactorySender, FactoryView};
 use relm4::gtk::prelude::{BoxExt, ButtonExt};
 use relm4::{factory, gtk, Component, ComponentController, Controller};
@@ -69,18 +69,24 @@ impl FactoryComponent for ColorRow {
 
                     gtk::Button {
                         set_icon_name: icon_names::UP,
+                        set_tooltip_text: Some("Move up"),
+                        update_property: &[gtk::accessible::Property::Label("Move up")],
                         connect_clicked[sender, index] => move |_| {
                             sender.output(ColorOutput::Up(index.clone())).unwrap();
                         }
                     },
                     gtk::Button {
                         set_icon_name: icon_names::DOWN,
+                        set_tooltip_text: Some("Move down"),
+                        update_property: &[gtk::accessible::Property::Label("Move down")],
                         connect_clicked[sender, index] => move |_| {
                             sender.output(ColorOutput::Down(index.clone())).unwrap();
                         }
                     },
                     gtk::Button {
                         set_icon_name: icon_names::CROSS_FILLED,
+                        set_tooltip_text: Some("Remove"),
+                        update_property: &[gtk::accessible::Property::Label("Remove")],
                         add_css_class: "destructive-action",
                         connect_clicked[sender, index] => move |_| {
                             sender.output(ColorOutput::Remove(index.clone())).unwrap();
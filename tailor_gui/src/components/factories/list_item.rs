@@ -2,7 +2,9 @@ use std::marker::PhantomData;
 
 use adw::prelude::{MessageDialogExt, MessageDialogExtManual};
 use gtk::glib;
-use gtk::prelude::{BoxExt, ButtonExt, EditableExt, ObjectExt, OrientableExt, WidgetExt};
+use gtk::prelude::{
+    AccessibleExt, BoxExt, ButtonExt, EditableExt, ObjectExt, OrientableExt, WidgetExt,
+};
 use relm4::factory::{DynamicIndex, FactoryComponent, FactorySender};
 use relm4::{adw, factory, gtk, RelmWidgetExt};
 use relm4_icons::icon_names;
@@ -60,6 +62,8 @@ where
 
                 gtk::Button {
                     set_icon_name: icon_names::CROSS_FILLED,
+                    set_tooltip_text: Some("Delete"),
+                    update_property: &[gtk::accessible::Property::Label("Delete")],
                     connect_clicked[sender, index, name = self.name.clone()] => move |btn| {
                         let window = btn.toplevel_window().unwrap();
                         let dialog = adw::MessageDialog::builder()
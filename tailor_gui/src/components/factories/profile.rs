@@ -1,5 +1,8 @@
 use adw::prelude::{ExpanderRowExt, PreferencesRowExt};
-use gtk::prelude::{BoxExt, ButtonExt, CheckButtonExt, ObjectExt, WidgetExt};
+use gtk::prelude::{
+    AccessibleExt, BoxExt, ButtonExt, CheckButtonExt, ObjectExt, ToValue, WidgetExt,
+};
+use gtk::{gdk, glib};
 use once_cell::unsync::Lazy;
 use relm4::factory::{DynamicIndex, FactoryComponent, FactorySender, FactoryVecDeque};
 use relm4::{adw, factory, gtk, Component, ComponentController, Controller};
@@ -10,6 +13,7 @@ use tailor_api::{LedDeviceInfo, LedProfile, ProfileInfo};
 use super::profile_item_fan::{ProfileItemFan, ProfileItemFanInit};
 use super::profile_item_led::{ProfileItemLed, ProfileItemLedInit};
 use crate::components::profiles::ProfilesInput;
+use crate::modals::{keep_changes, preview_profile};
 use crate::state::{hardware_capabilities, TailorStateMsg, STATE};
 use crate::templates;
 
@@ -22,6 +26,8 @@ pub struct Profile {
     pub name: String,
     pub info: ProfileInfo,
     pub active: bool,
+    /// Favorites are pinned to the top of the list.
+    pub favorite: bool,
     pub leds: FactoryVecDeque<ProfileItemLed>,
     pub fans: FactoryVecDeque<ProfileItemFan>,
     pub performance: Option<Controller<SimpleComboBox<String>>>,
@@ -32,13 +38,14 @@ pub struct ProfileInit {
     pub name: String,
     pub info: ProfileInfo,
     pub active: bool,
+    pub favorite: bool,
     pub led_profiles: Vec<String>,
     pub fan_profiles: Vec<String>,
 }
 
 #[derive(Debug)]
 pub enum ProfileInput {
-    Enabled,
+    Enabled(DynamicIndex),
     UpdateProfile,
 }
 
@@ -66,23 +73,60 @@ impl FactoryComponent for Profile {
                     set_active: self.active,
 
                     set_group: Some(&RADIO_GROUP.with(|g| (**g).clone())),
+                    update_property: &[gtk::accessible::Property::Label(&format!("Activate {}", self.name))],
 
                     connect_toggled[sender, index] => move |btn| {
                         if btn.is_active() {
-                            sender.input(ProfileInput::Enabled);
-                            sender.output(ProfilesInput::Enabled(index.clone())).unwrap();
+                            sender.input(ProfileInput::Enabled(index.clone()));
                        }
                     },
                 },
             },
 
+            // Profiles are reordered by dropping them onto another profile.
+            add_controller = gtk::DragSource {
+                set_actions: gdk::DragAction::MOVE,
+                connect_prepare[name = self.name.clone()] => move |_, _, _| {
+                    Some(gdk::ContentProvider::for_value(&name.to_value()))
+                },
+            },
+            add_controller = gtk::DropTarget::new(glib::Type::STRING, gdk::DragAction::MOVE) {
+                connect_drop[name = self.name.clone()] => move |_, value, _, _| {
+                    match value.get::<String>() {
+                        Ok(from) if from != name => {
+                            STATE.emit(TailorStateMsg::MoveProfile { from, to: name.clone() });
+                            true
+                        }
+                        _ => false,
+                    }
+                },
+            },
+
             add_action = &gtk::Box {
                 set_valign: gtk::Align::Center,
                 set_margin_end: 2,
+                set_spacing: 6,
+
+                gtk::Button {
+                    set_icon_name: if self.favorite { icon_names::STAR_FILLED } else { icon_names::STAR },
+                    set_tooltip_text: Some(if self.favorite { "Unpin from the top" } else { "Pin to the top" }),
+                    update_property: &[gtk::accessible::Property::Label(
+                        if self.favorite { "Unpin from the top" } else { "Pin to the top" },
+                    )],
+                    add_css_class: "flat",
+                    connect_clicked[name = self.name.clone(), favorite = self.favorite] => move |_| {
+                        STATE.emit(TailorStateMsg::SetFavoriteProfile {
+                            name: name.clone(),
+                            favorite: !favorite,
+                        });
+                    }
+                },
 
                 #[name = "delete_button"]
                 gtk::Button {
                     set_icon_name: icon_names::CROSS_FILLED,
+                    set_tooltip_text: Some("Delete"),
+                    update_property: &[gtk::accessible::Property::Label("Delete")],
                     add_css_class: "destructive-action",
                     set_visible: false,
                     #[watch]
@@ -120,6 +164,7 @@ impl FactoryComponent for Profile {
             name,
             mut info,
             active,
+            favorite,
             led_profiles,
             fan_profiles,
         } = init;
@@ -190,8 +235,8 @@ impl FactoryComponent for Profile {
         }
 
         let performance = capabilities
-            .performance_profiles
-            .as_ref()
+            .supports_performance_profiles()
+            .then_some(&capabilities.performance_profiles)
             .map(|cap_perf_prof| {
                 let active_index = info
                     .performance_profile
@@ -209,6 +254,7 @@ impl FactoryComponent for Profile {
             name,
             info,
             active,
+            favorite,
             leds,
             fans,
             performance,
@@ -219,10 +265,16 @@ impl FactoryComponent for Profile {
         let name = self.name.clone();
 
         match message {
-            ProfileInput::Enabled => {
+            ProfileInput::Enabled(index) => {
                 if !self.active {
-                    sender.oneshot_command(async move {
-                        STATE.emit(TailorStateMsg::SetActiveProfile(name));
+                    relm4::spawn_local(async move {
+                        if preview_profile::confirm(&name).await {
+                            sender.output(ProfilesInput::Enabled(index)).unwrap();
+                            STATE.emit(TailorStateMsg::SetActiveProfile(name));
+                        } else {
+                            // Select the active profile again.
+                            sender.output(ProfilesInput::ResetActive).unwrap();
+                        }
                     });
                 }
             }
@@ -236,17 +288,41 @@ impl FactoryComponent for Profile {
                     .as_ref()
                     .and_then(|perf| perf.state().get().model.get_active_elem().cloned());
 
+                let previous = self.info.clone();
                 self.info = ProfileInfo {
                     leds,
                     fans,
                     performance_profile,
+                    max_freq_mhz: self.info.max_freq_mhz,
+                    usb_autosuspend: self.info.usb_autosuspend.clone(),
+                    pcie_aspm: self.info.pcie_aspm,
+                    power_tuning: self.info.power_tuning.clone(),
                 };
 
                 let profile = self.info.clone();
-
-                sender.oneshot_command(async move {
-                    STATE.emit(TailorStateMsg::AddProfile { name, profile });
-                });
+                // Fans and the performance profile of the active profile take effect
+                // immediately and can make the device unusable, so they are reverted
+                // unless the user keeps them.
+                let risky = self.active
+                    && (profile.fans != previous.fans
+                        || profile.performance_profile != previous.performance_profile);
+                if risky {
+                    STATE.emit(TailorStateMsg::AddProfile {
+                        name: name.clone(),
+                        profile,
+                    });
+                    keep_changes::confirm(
+                        "Keep the new profile settings?",
+                        TailorStateMsg::RevertProfile {
+                            name,
+                            profile: previous,
+                        },
+                    );
+                } else {
+                    sender.oneshot_command(async move {
+                        STATE.emit(TailorStateMsg::AddProfile { name, profile });
+                    });
+                }
             }
         }
     }
@@ -1,11 +1,15 @@
 use std::f64::consts::PI;
 use std::time::Duration;
 
+use adw::prelude::{MessageDialogExt, MessageDialogExtManual};
 use gtk::cairo::Operator;
 use gtk::gdk;
 use gtk::gdk::RGBA;
-use gtk::glib::{timeout_add_local_once, MainContext, SourceId};
-use gtk::prelude::{BoxExt, ButtonExt, DrawingAreaExt, GestureDragExt, OrientableExt, WidgetExt};
+use gtk::glib::{timeout_add_local_once, MainContext, Propagation, SourceId};
+use gtk::prelude::{
+    AccessibleExt, BoxExt, ButtonExt, DrawingAreaExt, EventControllerExt, GestureDragExt,
+    OrientableExt, WidgetExt,
+};
 use relm4::abstractions::DrawHandler;
 use relm4::{
     adw, component, gtk, Component, ComponentController, ComponentParts, ComponentSender,
@@ -14,9 +18,18 @@ use relm4::{
 use relm4_components::simple_combo_box::{SimpleComboBox, SimpleComboBoxMsg};
 use tailor_api::FanProfilePoint;
 
+use crate::modals::{advanced_mode, keep_changes};
 use crate::state::{hardware_capabilities, tailor_connection, TailorStateMsg, STATE};
 use crate::templates;
 
+/// From this temperature on, low fan speeds are considered unsafe.
+const WARNING_TEMP: u8 = 70;
+/// Fan speeds in percent below this are unsafe from [`WARNING_TEMP`] on.
+const WARNING_FAN_SPEED: u8 = 40;
+/// How the curve is edited without a pointer device, read by screen readers.
+const KEYBOARD_HELP: &str = "Left and right select a point, up and down change its fan speed, \
+    shift with left and right changes its temperature. Insert adds a point, delete removes it.";
+
 struct Colors {
     stroke: RGBA,
     warn: RGBA,
@@ -39,6 +52,8 @@ impl Colors {
 pub struct FanEdit {
     profile_name: Option<String>,
     profile: Vec<FanProfilePoint>,
+    /// The profile as it was loaded, to revert to if the changes aren't kept.
+    saved_profile: Vec<FanProfilePoint>,
     drawing_handler: DrawHandler,
     drawn_points: Vec<(f64, f64)>,
     colors: Colors,
@@ -48,6 +63,11 @@ pub struct FanEdit {
     visible: bool,
     last_override_event: Option<SourceId>,
     preview_fan: Controller<SimpleComboBox<String>>,
+    /// Why the current curve is unsafe.
+    warning: Option<String>,
+    /// Unsafe curves can only be applied and the fan speed is
+    /// only previewed in the advanced mode.
+    advanced: bool,
 }
 
 #[derive(Debug)]
@@ -64,6 +84,47 @@ pub enum FanEditInput {
     UpdateColors,
     #[doc(hidden)]
     Apply,
+    /// Apply the profile after the user confirmed the warning.
+    #[doc(hidden)]
+    ForceApply,
+    Keyboard(KeyboardEdit),
+    /// Set the temperature of the selected point.
+    SetTemp(u8),
+    /// Set the fan speed of the selected point.
+    SetFan(u8),
+}
+
+/// Edits of the curve with the keyboard, relative to the selected point.
+#[derive(Debug, Clone, Copy)]
+pub enum KeyboardEdit {
+    Select(isize),
+    SelectFirst,
+    SelectLast,
+    Temp(i8),
+    Fan(i8),
+    Add,
+    Remove,
+}
+
+impl KeyboardEdit {
+    fn from_key(key: gdk::Key, modifiers: gdk::ModifierType) -> Option<Self> {
+        let shift = modifiers.contains(gdk::ModifierType::SHIFT_MASK);
+        Some(match key {
+            gdk::Key::Left if shift => Self::Temp(-1),
+            gdk::Key::Right if shift => Self::Temp(1),
+            gdk::Key::Left => Self::Select(-1),
+            gdk::Key::Right => Self::Select(1),
+            gdk::Key::Home => Self::SelectFirst,
+            gdk::Key::End => Self::SelectLast,
+            gdk::Key::Up => Self::Fan(1),
+            gdk::Key::Down => Self::Fan(-1),
+            gdk::Key::Page_Up => Self::Fan(10),
+            gdk::Key::Page_Down => Self::Fan(-10),
+            gdk::Key::Insert | gdk::Key::plus | gdk::Key::KP_Add => Self::Add,
+            gdk::Key::Delete | gdk::Key::minus | gdk::Key::KP_Subtract => Self::Remove,
+            _ => return None,
+        })
+    }
 }
 
 #[component(pub)]
@@ -89,6 +150,9 @@ impl Component for FanEdit {
                         set_start_widget = fan_selection -> gtk::ComboBoxText {
                             set_margin_all: 6,
                             set_tooltip: "Select a fan for instant feedback",
+                            #[watch]
+                            set_visible: model.advanced && hardware_capabilities()
+                                .is_some_and(|capabilities| capabilities.num_of_fans > 1),
                         },
 
                         #[wrap(Some)]
@@ -101,6 +165,13 @@ impl Component for FanEdit {
                     },
                 },
 
+                adw::Banner {
+                    #[watch]
+                    set_revealed: model.warning.is_some(),
+                    #[watch]
+                    set_title: &model.banner_title(),
+                },
+
                 gtk::Overlay {
                     #[local_ref]
                     drawing_area -> gtk::DrawingArea {
@@ -114,8 +185,11 @@ impl Component for FanEdit {
                             }.as_ref(),
                         set_vexpand: true,
                         set_hexpand: true,
+                        set_focusable: true,
+                        update_property: &[gtk::accessible::Property::Description(KEYBOARD_HELP)],
                         add_controller = gtk::GestureDrag {
-                            connect_drag_begin[sender] => move |_, x, y| {
+                            connect_drag_begin[sender] => move |gesture, x, y| {
+                                gesture.widget().grab_focus();
                                 sender.input(FanEditInput::DragStart((x, y)));
                             },
                             connect_drag_update[sender] => move |_, x, y| {
@@ -125,6 +199,16 @@ impl Component for FanEdit {
                                 sender.input(FanEditInput::DragEnd((x, y)));
                             },
                         },
+                        add_controller = gtk::EventControllerKey {
+                            connect_key_pressed[sender] => move |_, key, _, modifiers| {
+                                if let Some(edit) = KeyboardEdit::from_key(key, modifiers) {
+                                    sender.input(FanEditInput::Keyboard(edit));
+                                    Propagation::Stop
+                                } else {
+                                    Propagation::Proceed
+                                }
+                            },
+                        },
                         connect_resize[sender] => move |_, _, _| {
                             sender.input(FanEditInput::Update);
                         },
@@ -157,6 +241,47 @@ impl Component for FanEdit {
                         }
                     }
                 },
+
+                // The selected point, for precise values and screen readers.
+                gtk::Box {
+                    set_halign: gtk::Align::Center,
+                    set_spacing: 6,
+                    set_margin_bottom: 12,
+                    #[watch]
+                    set_sensitive: model.selected().is_some(),
+
+                    gtk::Label {
+                        set_label: "Temperature",
+                    },
+                    gtk::SpinButton::with_range(20.0, 100.0, 1.0) {
+                        update_property: &[gtk::accessible::Property::Label("Temperature in °C")],
+                        #[watch]
+                        #[block_signal(temp_handler)]
+                        set_value: model.selected_point().map_or(0.0, |point| point.temp as f64),
+                        connect_value_changed[sender] => move |spin| {
+                            sender.input(FanEditInput::SetTemp(spin.value() as u8));
+                        } @temp_handler,
+                    },
+                    gtk::Label {
+                        set_label: "°C",
+                        set_margin_end: 12,
+                    },
+                    gtk::Label {
+                        set_label: "Fan speed",
+                    },
+                    gtk::SpinButton::with_range(0.0, 100.0, 1.0) {
+                        update_property: &[gtk::accessible::Property::Label("Fan speed in percent")],
+                        #[watch]
+                        #[block_signal(fan_handler)]
+                        set_value: model.selected_point().map_or(0.0, |point| point.fan as f64),
+                        connect_value_changed[sender] => move |spin| {
+                            sender.input(FanEditInput::SetFan(spin.value() as u8));
+                        } @fan_handler,
+                    },
+                    gtk::Label {
+                        set_label: "%",
+                    },
+                },
                 gtk::Separator {},
 
                 #[template]
@@ -167,6 +292,8 @@ impl Component for FanEdit {
                     },
                     #[template_child]
                     save_button -> gtk::Button {
+                        #[watch]
+                        set_sensitive: model.advanced || model.warning.is_none(),
                         connect_clicked => FanEditInput::Apply,
                     },
                 }
@@ -191,6 +318,7 @@ impl Component for FanEdit {
         let model = Self {
             profile_name: None,
             profile: Vec::new(),
+            saved_profile: Vec::new(),
             drawing_handler: DrawHandler::new(),
             active_drag_info: None,
             colors,
@@ -200,6 +328,8 @@ impl Component for FanEdit {
             visible: false,
             last_override_event: None,
             preview_fan,
+            warning: None,
+            advanced: false,
         };
 
         let drawing_area = model.drawing_handler.drawing_area();
@@ -218,12 +348,11 @@ impl Component for FanEdit {
         match input {
             FanEditInput::Load(name) => {
                 self.profile_name = Some(name.clone());
+                self.advanced = advanced_mode::is_enabled();
 
+                // If we just have one fan, there's nothing to select
                 let capabilities = hardware_capabilities().unwrap();
-                if capabilities.num_of_fans <= 1 {
-                    // If we just have one fan, there's nothing to select
-                    self.preview_fan.widget().set_visible(false);
-                } else {
+                if capabilities.num_of_fans > 1 {
                     self.preview_fan
                         .emit(SimpleComboBoxMsg::UpdateData(SimpleComboBox {
                             variants: (0..capabilities.num_of_fans)
@@ -244,12 +373,15 @@ impl Component for FanEdit {
                 });
             }
             FanEditInput::Apply => {
-                self.visible = false;
-                if let Some(name) = self.profile_name.clone() {
-                    let profile = self.profile.drain(..).collect();
-                    STATE.emit(TailorStateMsg::AddFanProfile { name, profile });
+                if self.warning.is_none() {
+                    self.apply();
+                } else if self.advanced {
+                    confirm_unsafe_profile(root.as_ref(), sender);
                 }
             }
+            FanEditInput::ForceApply => {
+                self.apply();
+            }
             FanEditInput::Cancel => {
                 self.visible = false;
             }
@@ -284,7 +416,28 @@ impl Component for FanEdit {
                 self.drag_into_danger_zone = false;
                 self.active_drag_info = None;
             }
+            FanEditInput::Keyboard(edit) => {
+                self.drag_into_danger_zone = false;
+                self.edit(edit);
+                self.update_drawn_points();
+            }
+            FanEditInput::SetTemp(temp) => {
+                if let Some(idx) = self.selected() {
+                    self.drag_into_danger_zone = false;
+                    self.set_point(idx, temp, self.profile[idx].fan);
+                    self.update_drawn_points();
+                }
+            }
+            FanEditInput::SetFan(fan) => {
+                if let Some(idx) = self.selected() {
+                    self.drag_into_danger_zone = false;
+                    self.set_point(idx, self.profile[idx].temp, fan);
+                    self.update_drawn_points();
+                }
+            }
         }
+        self.warning = unsafe_curve_warning(&self.profile);
+        self.update_accessible_label();
         self.draw();
     }
 
@@ -295,14 +448,128 @@ impl Component for FanEdit {
         _root: &Self::Root,
     ) {
         self.profile = profile.unwrap_or_default();
+        self.saved_profile = self.profile.clone();
+        self.selection = (!self.profile.is_empty()).then_some(0);
+        self.warning = unsafe_curve_warning(&self.profile);
         self.visible = true;
 
+        self.update_accessible_label();
         self.update_drawn_points();
         self.draw();
     }
 }
 
 impl FanEdit {
+    fn banner_title(&self) -> String {
+        match &self.warning {
+            Some(warning) if !self.advanced => {
+                format!("{warning} Enable the advanced mode to apply it anyway.")
+            }
+            Some(warning) => warning.clone(),
+            None => String::new(),
+        }
+    }
+
+    /// Apply the profile provisionally, it's reverted unless the user keeps it.
+    fn apply(&mut self) {
+        self.visible = false;
+        if let Some(name) = self.profile_name.clone() {
+            let profile: Vec<_> = self.profile.drain(..).collect();
+            let saved_profile = std::mem::take(&mut self.saved_profile);
+            let changed = profile != saved_profile;
+            STATE.emit(TailorStateMsg::AddFanProfile {
+                name: name.clone(),
+                profile,
+            });
+            if changed && !saved_profile.is_empty() {
+                keep_changes::confirm(
+                    "Keep the new fan curve?",
+                    TailorStateMsg::AddFanProfile {
+                        name,
+                        profile: saved_profile,
+                    },
+                );
+            }
+        }
+    }
+
+    /// The index of the selected point, if it still exists.
+    fn selected(&self) -> Option<usize> {
+        self.selection.filter(|idx| *idx < self.profile.len())
+    }
+
+    fn selected_point(&self) -> Option<&FanProfilePoint> {
+        self.selected().map(|idx| &self.profile[idx])
+    }
+
+    /// Describe the selected point, so screen readers announce changes of the curve.
+    fn update_accessible_label(&self) {
+        let label = match self.selected_point() {
+            Some(point) => format!(
+                "Fan curve, point {} of {}: {}°C, {}%",
+                self.selection.unwrap_or_default() + 1,
+                self.profile.len(),
+                point.temp,
+                point.fan
+            ),
+            None => "Fan curve".to_owned(),
+        };
+        self.drawing_handler
+            .drawing_area()
+            .update_property(&[gtk::accessible::Property::Label(&label)]);
+    }
+
+    fn edit(&mut self, edit: KeyboardEdit) {
+        let Some(last) = self.profile.len().checked_sub(1) else {
+            return;
+        };
+        let idx = self.selected().unwrap_or_default();
+        let point = self.profile[idx].clone();
+        match edit {
+            KeyboardEdit::Select(step) => {
+                self.selection = Some(idx.saturating_add_signed(step).min(last));
+            }
+            KeyboardEdit::SelectFirst => self.selection = Some(0),
+            KeyboardEdit::SelectLast => self.selection = Some(last),
+            KeyboardEdit::Temp(step) => {
+                self.set_point(idx, point.temp.saturating_add_signed(step), point.fan);
+            }
+            KeyboardEdit::Fan(step) => {
+                self.set_point(idx, point.temp, point.fan.saturating_add_signed(step));
+            }
+            KeyboardEdit::Add => self.selection = Some(self.insert_point(idx)),
+            KeyboardEdit::Remove => {
+                // A curve needs a start and an end.
+                if self.profile.len() > 2 {
+                    self.profile.remove(idx);
+                    self.selection = Some(idx.min(last - 1));
+                }
+            }
+        }
+    }
+
+    /// Insert a point halfway between the point at `idx` and its neighbour
+    /// and return the index of the new point, or `idx` if there's no room.
+    fn insert_point(&mut self, idx: usize) -> usize {
+        let next = if idx + 1 < self.profile.len() {
+            idx + 1
+        } else if idx > 0 {
+            idx
+        } else {
+            return idx;
+        };
+        let (before, after) = (&self.profile[next - 1], &self.profile[next]);
+        if after.temp.saturating_sub(before.temp) < 2 {
+            return idx;
+        }
+        let point = FanProfilePoint {
+            temp: before.temp + (after.temp - before.temp) / 2,
+            fan: before.fan + after.fan.saturating_sub(before.fan) / 2,
+        };
+        self.profile.insert(next, point);
+        next
+    }
+
     fn dimensions(&self) -> (f64, f64) {
         let width = self.drawing_handler.width() as f64;
         let height = (self.drawing_handler.height() - 5) as f64;
@@ -447,7 +714,7 @@ impl FanEdit {
             ctx.arc(*x, *y, 5.0, 0.0, PI * 2.0);
             ctx.fill().unwrap();
 
-            if Some(idx) == self.active_drag_info.map(|(idx, _, _)| idx) {
+            if Some(idx) == self.selected() {
                 if self
                     .profile
                     .iter()
@@ -477,87 +744,109 @@ impl FanEdit {
             let temp = x.clamp(20.0, 100.0) as u8;
             let fan = y.clamp(0.0, 100.0) as u8;
 
-            let mut fan = {
-                let min_fan = if idx == 0 {
-                    0
-                } else {
-                    self.profile[idx - 1].fan
-                };
-
-                let max_fan = if idx == self.profile.len() - 1 {
-                    100
-                } else {
-                    self.profile[idx + 1].fan
-                }
-                .max(min_fan);
+            self.set_point(idx, temp, fan);
+        }
+    }
 
-                fan.clamp(min_fan, max_fan)
-            };
+    /// Move the point at `idx`, limited by its neighbours and the safety margin.
+    fn set_point(&mut self, idx: usize, temp: u8, fan: u8) {
+        let previous = idx.checked_sub(1).map(|idx| self.profile[idx].clone());
+        let next = self.profile.get(idx + 1).cloned();
 
-            let mut temp = {
-                let min_temp = if idx == 0 {
-                    20
-                } else {
-                    self.profile[idx - 1].temp
-                };
-                let max_temp = if idx == self.profile.len() - 1 {
-                    100
-                } else {
-                    self.profile[idx + 1].temp
-                };
+        let mut fan = {
+            let min_fan = previous.as_ref().map_or(0, |point| point.fan);
+            let max_fan = next.as_ref().map_or(100, |point| point.fan).max(min_fan);
+            fan.clamp(min_fan, max_fan)
+        };
 
-                temp.clamp(min_temp, max_temp)
-            };
+        let mut temp = {
+            let min_temp = previous.as_ref().map_or(20, |point| point.temp);
+            let max_temp = next.as_ref().map_or(100, |point| point.temp).max(min_temp);
+            temp.clamp(min_temp, max_temp)
+        };
 
-            if let Some(profile) = self.profile.get(idx - 1) {
-                if profile.fan != fan && profile.temp == temp {
-                    temp += 1;
-                }
+        if let Some(profile) = &previous {
+            if profile.fan != fan && profile.temp == temp {
+                temp += 1;
             }
-            if let Some(profile) = self.profile.get(idx + 1) {
-                if profile.fan != fan && profile.temp == temp {
-                    temp -= 1;
-                }
+        }
+        if let Some(profile) = &next {
+            if profile.fan != fan && profile.temp == temp {
+                temp -= 1;
             }
+        }
 
-            let safety_fan_speed = temp.saturating_sub(75).saturating_mul(5);
-            if fan < safety_fan_speed {
-                self.drag_into_danger_zone = true;
-                fan = safety_fan_speed;
-            }
+        let safety_fan_speed = temp.saturating_sub(75).saturating_mul(5);
+        if fan < safety_fan_speed {
+            self.drag_into_danger_zone = true;
+            fan = safety_fan_speed;
+        }
 
-            self.profile[idx].temp = temp;
-            self.profile[idx].fan = fan;
+        self.profile[idx].temp = temp;
+        self.profile[idx].fan = fan;
 
-            // Cancel the previous timeout if a new value has arrived.
-            if let Some(source_id) = self.last_override_event.take() {
-                let main_context = MainContext::default();
-                if main_context.find_source_by_id(&source_id).is_some() {
-                    source_id.remove();
-                }
-            }
+        if !self.advanced {
+            return;
+        }
 
-            // Don't override the value immediately, but wait a bit for other events to arrive.
-            let fan_idx = self
-                .preview_fan
-                .state()
-                .get()
-                .model
-                .active_index
-                .unwrap_or_default() as u8;
-            self.last_override_event = Some(timeout_add_local_once(
-                Duration::from_millis(60),
-                move || {
-                    STATE.emit(TailorStateMsg::OverwriteFanSpeed {
-                        speed: fan,
-                        fan_idx,
-                    });
-                },
-            ));
+        // Cancel the previous timeout if a new value has arrived.
+        if let Some(source_id) = self.last_override_event.take() {
+            let main_context = MainContext::default();
+            if main_context.find_source_by_id(&source_id).is_some() {
+                source_id.remove();
+            }
         }
+
+        // Don't override the value immediately, but wait a bit for other events to arrive.
+        let fan_idx = self
+            .preview_fan
+            .state()
+            .get()
+            .model
+            .active_index
+            .unwrap_or_default() as u8;
+        self.last_override_event = Some(timeout_add_local_once(
+            Duration::from_millis(60),
+            move || {
+                STATE.emit(TailorStateMsg::OverwriteFanSpeed {
+                    speed: fan,
+                    fan_idx,
+                });
+            },
+        ));
     }
 }
 
+/// Explain why a curve is unsafe, if it keeps the fans slow at high temperatures.
+fn unsafe_curve_warning(profile: &[FanProfilePoint]) -> Option<String> {
+    let point = profile
+        .iter()
+        .find(|point| point.temp >= WARNING_TEMP && point.fan < WARNING_FAN_SPEED)?;
+    Some(format!(
+        "The fans only run at {}% at {}°C. This can overheat the device and slow it down.",
+        point.fan, point.temp
+    ))
+}
+
+fn confirm_unsafe_profile(window: &gtk::Window, sender: ComponentSender<FanEdit>) {
+    let dialog = adw::MessageDialog::builder()
+        .modal(true)
+        .transient_for(window)
+        .heading("Apply unsafe fan profile?")
+        .body("The fans stay slow at high temperatures. Only continue if you know that your device stays cool enough.")
+        .default_response("cancel")
+        .close_response("cancel")
+        .build();
+    dialog.add_responses(&[("cancel", "Cancel"), ("apply", "Apply anyway")]);
+    dialog.set_response_appearance("apply", adw::ResponseAppearance::Destructive);
+
+    relm4::spawn_local(async move {
+        if dialog.choose_future().await == "apply" {
+            sender.input(FanEditInput::ForceApply);
+        }
+    });
+}
+
 fn set_source_rgb(ctx: &gtk::cairo::Context, color: &RGBA) {
     ctx.set_source_rgb(
         color.red() as f64,
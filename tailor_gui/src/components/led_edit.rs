@@ -1,4 +1,4 @@
-use gtk::prelude::{ButtonExt, WidgetExt};
+use gtk::prelude::{AccessibleExt, ButtonExt, WidgetExt};
 use relm4::factory::FactoryVecDeque;
 use relm4::gtk::prelude::OrientableExt;
 use relm4::prelude::DynamicIndex;
@@ -8,7 +8,7 @@ use relm4::{
 };
 use relm4_components::simple_combo_box::{SimpleComboBox, SimpleComboBoxMsg};
 use relm4_icons::icon_names;
-use tailor_api::{Color, ColorPoint, ColorProfile, ColorTransition};
+use tailor_api::{Color, ColorPoint, ColorProfile, ColorRegion, ColorTransition, TypingEffect};
 
 use super::color_button::{ColorButton, ColorButtonInput};
 use super::factories::color::ColorRow;
@@ -22,8 +22,14 @@ pub enum ColorProfileType {
     None,
     Single,
     Multiple,
+    Typing,
+    /// Only kept, regions are edited in the profile file.
+    Regions,
 }
 
+/// Fade time of new typing effects in ms.
+const DEFAULT_FADE_TIME: u32 = 500;
+
 impl std::fmt::Display for ColorProfileType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(match self {
@@ -31,6 +37,8 @@ impl std::fmt::Display for ColorProfileType {
             Self::None => "None",
             Self::Single => "Single",
             Self::Multiple => "Multiple",
+            Self::Typing => "Typing",
+            Self::Regions => "Regions",
         })
     }
 }
@@ -40,6 +48,12 @@ pub struct LedEdit {
     color_profile_type: ColorProfileType,
     colors: FactoryVecDeque<ColorRow>,
     color_button: Controller<ColorButton>,
+    idle_button: Controller<ColorButton>,
+    pressed_button: Controller<ColorButton>,
+    /// Kept from the loaded typing effect.
+    fade_time: u32,
+    /// Kept from the loaded profile.
+    regions: Vec<ColorRegion>,
     type_selector: Controller<SimpleComboBox<ColorProfileType>>,
     visible: bool,
 }
@@ -133,6 +147,8 @@ impl Component for LedEdit {
                                         },
                                         gtk::Button {
                                             set_icon_name: icon_names::PLUS,
+                                            set_tooltip_text: Some("Add a color"),
+                                            update_property: &[gtk::accessible::Property::Label("Add a color")],
                                             set_halign: gtk::Align::End,
                                             connect_clicked => LedEditInput::Add,
                                         }
@@ -144,6 +160,30 @@ impl Component for LedEdit {
                                         add_css_class: "boxed-list",
                                     }
                                 }
+                            },
+                            ColorProfileType::Typing => {
+                                gtk::Box {
+                                    set_halign: gtk::Align::Center,
+                                    set_valign: gtk::Align::Center,
+                                    set_spacing: 12,
+
+                                    gtk::Label {
+                                        set_label: "Idle",
+                                    },
+                                    #[local_ref]
+                                    idle_button -> gtk::Button,
+                                    gtk::Label {
+                                        set_label: "Pressed keys",
+                                    },
+                                    #[local_ref]
+                                    pressed_button -> gtk::Button,
+                                }
+                            },
+                            ColorProfileType::Regions => {
+                                gtk::Label {
+                                    set_label: "The keyboard regions of this profile can only be edited in its file",
+                                    set_wrap: true,
+                                }
                             }
                         }
                     }
@@ -194,6 +234,16 @@ impl Component for LedEdit {
                 b: 255,
             })
             .detach();
+        let idle_button = ColorButton::builder()
+            .launch(Color { r: 0, g: 0, b: 255 })
+            .detach();
+        let pressed_button = ColorButton::builder()
+            .launch(Color {
+                r: 255,
+                g: 255,
+                b: 255,
+            })
+            .detach();
 
         let type_selector = SimpleComboBox::builder()
             .launch(SimpleComboBox {
@@ -202,13 +252,15 @@ impl Component for LedEdit {
                     ColorProfileType::None,
                     ColorProfileType::Single,
                     ColorProfileType::Multiple,
+                    ColorProfileType::Typing,
                 ],
             })
             .forward(sender.input_sender(), |idx| {
                 LedEditInput::SetType(match idx {
                     0 => ColorProfileType::None,
                     1 => ColorProfileType::Single,
-                    _ => ColorProfileType::Multiple,
+                    2 => ColorProfileType::Multiple,
+                    _ => ColorProfileType::Typing,
                 })
             });
 
@@ -217,12 +269,18 @@ impl Component for LedEdit {
             color_profile_type: ColorProfileType::Loading,
             colors,
             color_button,
+            idle_button,
+            pressed_button,
+            fade_time: DEFAULT_FADE_TIME,
+            regions: Vec::new(),
             type_selector,
             visible: false,
         };
 
         let type_selector_widget = model.type_selector.widget();
         let color_button = model.color_button.widget();
+        let idle_button = model.idle_button.widget();
+        let pressed_button = model.pressed_button.widget();
         let widgets = view_output!();
 
         ComponentParts { model, widgets }
@@ -253,6 +311,18 @@ impl Component for LedEdit {
                     guard.push_back(color_point);
                 }
             }
+            ColorProfile::Typing(effect) => {
+                self.type_selector.emit(SimpleComboBoxMsg::SetActiveIdx(3));
+                self.idle_button
+                    .emit(ColorButtonInput::UpdateColor(effect.idle));
+                self.pressed_button
+                    .emit(ColorButtonInput::UpdateColor(effect.pressed));
+                self.fade_time = effect.fade_time;
+            }
+            ColorProfile::Regions(regions) => {
+                self.color_profile_type = ColorProfileType::Regions;
+                self.regions = regions;
+            }
         }
     }
 
@@ -333,6 +403,12 @@ impl LedEdit {
             ColorProfileType::Multiple => {
                 ColorProfile::Multiple(self.colors.iter().map(|row| row.inner.clone()).collect())
             }
+            ColorProfileType::Typing => ColorProfile::Typing(TypingEffect {
+                idle: self.idle_button.model().color.clone(),
+                pressed: self.pressed_button.model().color.clone(),
+                fade_time: self.fade_time,
+            }),
+            ColorProfileType::Regions => ColorProfile::Regions(self.regions.clone()),
         }
     }
 }
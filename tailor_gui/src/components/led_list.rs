@@ -1,5 +1,5 @@
 use futures::StreamExt;
-use gtk::prelude::{BoxExt, ButtonExt, ListBoxRowExt, OrientableExt, WidgetExt};
+use gtk::prelude::{AccessibleExt, BoxExt, ButtonExt, ListBoxRowExt, OrientableExt, WidgetExt};
 use relm4::factory::FactoryVecDeque;
 use relm4::prelude::DynamicIndex;
 use relm4::{
@@ -79,6 +79,8 @@ impl Component for LedList {
                             },
                             gtk::Button {
                                 set_icon_name: icon_names::PLUS,
+                                set_tooltip_text: Some("Add an LED profile"),
+                                update_property: &[gtk::accessible::Property::Label("Add an LED profile")],
                                 connect_clicked => LedListInput::Add,
                             }
                         },
@@ -0,0 +1,229 @@
+use adw::prelude::PreferencesGroupExt;
+use futures::StreamExt;
+use gtk::prelude::{OrientableExt, WidgetExt};
+use relm4::{adw, component, gtk, Component, ComponentParts, ComponentSender};
+use tailor_api::{LogLevel, LogRecord};
+
+use crate::state::{tailor_connection, TailorStateInner, STATE};
+use crate::templates;
+
+/// How many of the latest messages are loaded and shown.
+const MAX_RECORDS: usize = 500;
+
+/// The latest messages of tailord, newest first,
+/// e.g. to find out why a profile didn't apply.
+pub struct LogPage {
+    /// The least severe level that is shown.
+    minimum_level: LogLevel,
+    /// Rows of the messages in the log list, oldest first.
+    rows: Vec<(LogLevel, adw::ActionRow)>,
+    error: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum LogPageInput {
+    Reload,
+    SetMinimumLevel(LogLevel),
+}
+
+#[derive(Debug)]
+pub enum LogPageCommand {
+    Loaded(Vec<LogRecord>),
+    Recorded(LogRecord),
+    Error(String),
+}
+
+impl LogPage {
+    fn description(&self) -> String {
+        match &self.error {
+            Some(err) => err.clone(),
+            None if self.visible_rows() == 0 => "No messages".to_owned(),
+            None => String::new(),
+        }
+    }
+
+    fn visible_rows(&self) -> usize {
+        self.rows
+            .iter()
+            .filter(|(level, _)| level.is_at_least(self.minimum_level))
+            .count()
+    }
+
+    fn clear(&mut self, list: &gtk::ListBox) {
+        for (_, row) in self.rows.drain(..) {
+            list.remove(&row);
+        }
+    }
+
+    fn add(&mut self, list: &gtk::ListBox, record: LogRecord) {
+        if self.rows.len() == MAX_RECORDS {
+            let (_, row) = self.rows.remove(0);
+            list.remove(&row);
+        }
+        let row = adw::ActionRow::builder()
+            .title(&record.message)
+            .subtitle(format!(
+                "{} · {} · {}",
+                time(record.timestamp),
+                record.level,
+                record.target
+            ))
+            .title_lines(3)
+            .use_markup(false)
+            .visible(record.level.is_at_least(self.minimum_level))
+            .build();
+        match record.level {
+            LogLevel::Error => row.add_css_class("error"),
+            LogLevel::Warn => row.add_css_class("warning"),
+            LogLevel::Info => {}
+        }
+        list.prepend(&row);
+        self.rows.push((record.level, row));
+    }
+}
+
+#[component(pub)]
+impl Component for LogPage {
+    type CommandOutput = LogPageCommand;
+    type Init = ();
+    type Input = LogPageInput;
+    type Output = ();
+
+    view! {
+        #[template]
+        templates::CustomClamp {
+            #[template_child]
+            clamp {
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_spacing: 24,
+
+                    adw::PreferencesGroup {
+                        set_title: "Log",
+                        #[watch]
+                        set_description: Some(&model.description()),
+
+                        #[wrap(Some)]
+                        set_header_suffix = &gtk::DropDown::from_strings(&[
+                            "Errors",
+                            "Warnings and errors",
+                            "Everything",
+                        ]) {
+                            set_valign: gtk::Align::Center,
+                            set_selected: 2,
+                            connect_selected_notify[sender] => move |dropdown| {
+                                let level = LogLevel::ALL
+                                    .get(dropdown.selected() as usize)
+                                    .copied()
+                                    .unwrap_or(LogLevel::Info);
+                                sender.input(LogPageInput::SetMinimumLevel(level));
+                            },
+                        },
+
+                        #[name = "log_list"]
+                        gtk::ListBox {
+                            add_css_class: "boxed-list",
+                            set_selection_mode: gtk::SelectionMode::None,
+                            #[watch]
+                            set_visible: model.visible_rows() > 0,
+                        },
+                    },
+                }
+            }
+        }
+    }
+
+    fn init(
+        _: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        // Load the log once connected.
+        STATE.subscribe_optional(sender.input_sender(), |state| {
+            state
+                .get()
+                .filter(|state| state.changed(TailorStateInner::connection()))
+                .map(|_| LogPageInput::Reload)
+        });
+
+        let model = Self {
+            minimum_level: LogLevel::Info,
+            rows: Vec::new(),
+            error: None,
+        };
+        let widgets = view_output!();
+        ComponentParts { model, widgets }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        input: Self::Input,
+        sender: ComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        match input {
+            LogPageInput::Reload => sender.command(|out, shutdown| {
+                shutdown
+                    .register(async move {
+                        let Some(connection) = tailor_connection() else {
+                            out.emit(LogPageCommand::Error("Not connected to tailord".to_owned()));
+                            return;
+                        };
+                        // Subscribe first, so no message is missed.
+                        let stream = connection.receive_log().await;
+                        match connection.get_log(MAX_RECORDS as u32).await {
+                            Ok(records) => out.emit(LogPageCommand::Loaded(records)),
+                            Err(err) => out.emit(LogPageCommand::Error(err.to_string())),
+                        }
+                        let Ok(mut stream) = stream else {
+                            return;
+                        };
+                        while let Some(record) = stream.next().await {
+                            out.emit(LogPageCommand::Recorded(record));
+                        }
+                    })
+                    .drop_on_shutdown()
+            }),
+            LogPageInput::SetMinimumLevel(level) => {
+                self.minimum_level = level;
+                for (level, row) in &self.rows {
+                    row.set_visible(level.is_at_least(self.minimum_level));
+                }
+            }
+        }
+        self.update_view(widgets, sender);
+    }
+
+    fn update_cmd_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        command: Self::CommandOutput,
+        sender: ComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        match command {
+            LogPageCommand::Loaded(records) => {
+                self.clear(&widgets.log_list);
+                for record in records {
+                    self.add(&widgets.log_list, record);
+                }
+                self.error = None;
+            }
+            LogPageCommand::Recorded(record) => self.add(&widgets.log_list, record),
+            LogPageCommand::Error(err) => {
+                self.clear(&widgets.log_list);
+                self.error = Some(err);
+            }
+        }
+        self.update_view(widgets, sender);
+    }
+}
+
+/// The local time of a timestamp, e.g. `2024-03-01 14:05:09`.
+fn time(timestamp: u64) -> String {
+    gtk::glib::DateTime::from_unix_local(timestamp as i64)
+        .and_then(|time| time.format("%F %T"))
+        .map(|time| time.to_string())
+        .unwrap_or_default()
+}
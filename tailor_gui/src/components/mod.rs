@@ -1,9 +1,13 @@
+pub mod about_device;
+pub mod battery;
+pub mod battery_calibration;
 pub mod color_button;
 pub mod factories;
 pub mod fan_edit;
 pub mod fan_list;
-pub mod hardware_info;
 pub mod led_edit;
 pub mod led_list;
+pub mod log;
 pub mod new_entry;
 pub mod profiles;
+pub mod search;
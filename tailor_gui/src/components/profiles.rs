@@ -1,6 +1,6 @@
 use adw::prelude::PreferencesGroupExt;
 use futures::StreamExt;
-use gtk::prelude::ButtonExt;
+use gtk::prelude::{AccessibleExt, ButtonExt, WidgetExt};
 use relm4::factory::FactoryVecDeque;
 use relm4::prelude::DynamicIndex;
 use relm4::{adw, component, gtk, Component, ComponentParts, ComponentSender, WidgetRef};
@@ -23,10 +23,14 @@ pub enum ProfilesInput {
     UpdateProfiles {
         profiles: Vec<FullProfileInfo>,
         active_profile: String,
+        favorites: Vec<String>,
         fan_profiles: Vec<String>,
         led_profiles: Vec<String>,
     },
     Enabled(DynamicIndex),
+    /// Show the active profile as selected again, e.g. if
+    /// the user didn't confirm the activation of another one.
+    ResetActive,
     Remove(DynamicIndex),
     Add,
 }
@@ -49,6 +53,8 @@ impl Component for Profiles {
                     #[wrap(Some)]
                     set_header_suffix = &gtk::Button {
                         set_icon_name: icon_names::PLUS,
+                        set_tooltip_text: Some("Add a profile"),
+                        update_property: &[gtk::accessible::Property::Label("Add a profile")],
                         connect_clicked => ProfilesInput::Add,
                     }
                 },
@@ -66,6 +72,7 @@ impl Component for Profiles {
             ProfilesInput::UpdateProfiles {
                 profiles: state.profiles.clone(),
                 active_profile: state.active_profile_name.clone(),
+                favorites: state.profile_order.favorites.clone(),
                 fan_profiles: state.fan_profiles.clone(),
                 led_profiles: state.led_profiles.clone(),
             }
@@ -92,6 +99,7 @@ impl Component for Profiles {
             ProfilesInput::UpdateProfiles {
                 profiles,
                 active_profile,
+                favorites,
                 led_profiles,
                 fan_profiles,
             } => {
@@ -100,12 +108,14 @@ impl Component for Profiles {
                 guard.clear();
                 for profile in profiles {
                     let active = active_profile == profile.name;
+                    let favorite = favorites.contains(&profile.name);
                     guard.push_back(ProfileInit {
                         name: profile.name,
                         info: profile.data,
                         led_profiles: led_profiles.clone(),
                         fan_profiles: fan_profiles.clone(),
                         active,
+                        favorite,
                     });
                 }
                 self.led = led_profiles;
@@ -117,6 +127,10 @@ impl Component for Profiles {
                     profile.active = idx == index;
                 }
             }
+            ProfilesInput::ResetActive => {
+                // Accessing the profiles mutably updates their widgets.
+                for _ in self.profiles.guard().iter_mut() {}
+            }
             ProfilesInput::Remove(index) => {
                 let index = index.current_index();
                 if let Some(profile) = self.profiles.get(index) {
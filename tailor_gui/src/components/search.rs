@@ -0,0 +1,220 @@
+use gtk::prelude::{BoxExt, EditableExt, ListBoxRowExt, OrientableExt, WidgetExt};
+use relm4::{adw, component, gtk, Component, ComponentParts, ComponentSender};
+
+use crate::app::Page;
+use crate::state::STATE;
+
+const MAX_RESULTS: usize = 8;
+
+/// Settings that can be found regardless of the
+/// profiles, along with lowercase keywords.
+const SETTINGS: &[(&str, &str, Page)] = &[
+    ("Profiles", "global active default", Page::Profiles),
+    ("Performance profile", "power tdp cpu odm", Page::Profiles),
+    (
+        "Keyboard backlight",
+        "led color brightness light",
+        Page::Led,
+    ),
+    ("LED profiles", "color rgb light", Page::Led),
+    (
+        "Fan curves",
+        "fan speed temperature cooling noise",
+        Page::FanCurves,
+    ),
+    (
+        "Battery health",
+        "capacity wear cycles history",
+        Page::Battery,
+    ),
+    ("Log", "journal messages errors warnings debug", Page::Log),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SearchItem {
+    title: String,
+    keywords: &'static str,
+    page: Page,
+}
+
+impl SearchItem {
+    fn matches(&self, query: &str) -> bool {
+        self.title.to_lowercase().contains(query) || self.keywords.contains(query)
+    }
+}
+
+/// Search entry that filters the settings
+/// and profiles of all pages.
+pub struct SettingsSearch {
+    items: Vec<SearchItem>,
+    results: Vec<SearchItem>,
+    query: String,
+}
+
+#[derive(Debug)]
+pub enum SearchInput {
+    UpdateItems {
+        profiles: Vec<String>,
+        led_profiles: Vec<String>,
+        fan_profiles: Vec<String>,
+    },
+    Search(String),
+    Activate(usize),
+}
+
+#[derive(Debug)]
+pub enum SearchOutput {
+    ShowPage(Page),
+}
+
+#[component(pub)]
+impl Component for SettingsSearch {
+    type CommandOutput = ();
+    type Init = ();
+    type Input = SearchInput;
+    type Output = SearchOutput;
+
+    view! {
+        gtk::SearchBar {
+            #[wrap(Some)]
+            set_child = &adw::Clamp {
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_spacing: 6,
+
+                    #[name = "entry"]
+                    gtk::SearchEntry {
+                        set_placeholder_text: Some("Search settings"),
+                        set_hexpand: true,
+                        connect_search_changed[sender] => move |entry| {
+                            sender.input(SearchInput::Search(entry.text().to_string()));
+                        },
+                        connect_activate => SearchInput::Activate(0),
+                    },
+
+                    #[name = "results"]
+                    gtk::ListBox {
+                        add_css_class: "boxed-list",
+                        set_selection_mode: gtk::SelectionMode::None,
+                        #[watch]
+                        set_visible: !model.results.is_empty(),
+                        connect_row_activated[sender] => move |_, row| {
+                            sender.input(SearchInput::Activate(row.index() as usize));
+                        },
+                    },
+                }
+            }
+        }
+    }
+
+    fn init(
+        _: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        STATE.subscribe(sender.input_sender(), |state| {
+            let state = state.unwrap();
+            SearchInput::UpdateItems {
+                profiles: state
+                    .profiles
+                    .iter()
+                    .map(|profile| profile.name.clone())
+                    .collect(),
+                led_profiles: state.led_profiles.clone(),
+                fan_profiles: state.fan_profiles.clone(),
+            }
+        });
+
+        let model = Self {
+            items: Self::items(Vec::new(), Vec::new(), Vec::new()),
+            results: Vec::new(),
+            query: String::new(),
+        };
+
+        let widgets = view_output!();
+        root.connect_entry(&widgets.entry);
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        message: Self::Input,
+        sender: ComponentSender<Self>,
+        root: &Self::Root,
+    ) {
+        match message {
+            SearchInput::UpdateItems {
+                profiles,
+                led_profiles,
+                fan_profiles,
+            } => {
+                self.items = Self::items(profiles, led_profiles, fan_profiles);
+            }
+            SearchInput::Search(query) => {
+                self.query = query.trim().to_lowercase();
+            }
+            SearchInput::Activate(idx) => {
+                if let Some(item) = self.results.get(idx) {
+                    sender.output(SearchOutput::ShowPage(item.page)).ok();
+                    widgets.entry.set_text("");
+                    root.set_search_mode(false);
+                }
+                return;
+            }
+        }
+
+        self.results = if self.query.is_empty() {
+            Vec::new()
+        } else {
+            self.items
+                .iter()
+                .filter(|item| item.matches(&self.query))
+                .take(MAX_RESULTS)
+                .cloned()
+                .collect()
+        };
+
+        widgets.results.remove_all();
+        for item in &self.results {
+            let row = adw::ActionRow::builder()
+                .title(&item.title)
+                .subtitle(item.page.title())
+                .use_markup(false)
+                .activatable(true)
+                .build();
+            widgets.results.append(&row);
+        }
+
+        self.update_view(widgets, sender);
+    }
+}
+
+impl SettingsSearch {
+    fn items(
+        profiles: Vec<String>,
+        led_profiles: Vec<String>,
+        fan_profiles: Vec<String>,
+    ) -> Vec<SearchItem> {
+        let settings = SETTINGS.iter().map(|&(title, keywords, page)| SearchItem {
+            title: title.to_owned(),
+            keywords,
+            page,
+        });
+        let profiles = [
+            (profiles, "profile", Page::Profiles),
+            (led_profiles, "led profile", Page::Led),
+            (fan_profiles, "fan profile", Page::FanCurves),
+        ]
+        .into_iter()
+        .flat_map(|(names, keywords, page)| {
+            names.into_iter().map(move |title| SearchItem {
+                title,
+                keywords,
+                page,
+            })
+        });
+        settings.chain(profiles).collect()
+    }
+}
@@ -1,6 +1,7 @@
 #![allow(deprecated)]
 
 mod app;
+mod background;
 pub mod components;
 mod config;
 mod modals;
@@ -9,9 +10,10 @@ pub mod state;
 pub mod templates;
 pub mod util;
 
-use app::App;
+use app::{App, Page};
 use clap::Parser;
-use gtk::prelude::ApplicationExt;
+use gtk::gio;
+use gtk::prelude::{ActionGroupExt, ApplicationCommandLineExt, ApplicationExt, ToVariant};
 use relm4::actions::{AccelsPlus, RelmAction, RelmActionGroup};
 use relm4::{gtk, main_application, RelmApp};
 use setup::setup;
@@ -24,14 +26,45 @@ relm4::new_stateless_action!(QuitAction, AppActionGroup, "quit");
 /// Tailord GUI (part of tuxedo-rs)
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
-struct CliArgs {}
+struct CliArgs {
+    /// Start in the background without showing the main window
+    #[arg(long)]
+    hidden: bool,
+    /// Open a page of the main window
+    #[arg(long, value_enum)]
+    page: Option<Page>,
+    /// Activate a global profile without showing the main window
+    #[arg(long, value_name = "PROFILE")]
+    activate_profile: Option<String>,
+}
+
+impl CliArgs {
+    fn show_window(&self) -> bool {
+        !self.hidden && (self.page.is_some() || self.activate_profile.is_none())
+    }
+
+    /// Forward the arguments to the actions of the application.
+    /// If Tailor is already running, this happens in the running instance.
+    fn activate(&self, app: &gtk::Application) {
+        if self.show_window() {
+            app.activate();
+        }
+        if let Some(page) = self.page {
+            app.activate_action("show-page", Some(&page.name().to_variant()));
+        }
+        if let Some(profile) = &self.activate_profile {
+            app.activate_action("activate-profile", Some(&profile.to_variant()));
+        }
+    }
+}
 
 fn main() {
-    let _ = CliArgs::parse();
-    run_app()
+    // Print the help or errors before starting GTK.
+    let args = CliArgs::parse();
+    run_app(!args.show_window())
 }
 
-fn run_app() {
+fn run_app(hidden: bool) {
     // Enable logging
     tracing_subscriber::fmt()
         .with_span_events(tracing_subscriber::fmt::format::FmtSpan::FULL)
@@ -43,6 +76,21 @@ fn run_app() {
     let app = main_application();
     app.set_application_id(Some(APP_ID));
     app.set_resource_base_path(Some("/com/github/aaronerhardt/Tailor/"));
+    app.set_flags(gio::ApplicationFlags::HANDLES_COMMAND_LINE);
+
+    // Called in the running instance with the arguments of each launch.
+    app.connect_command_line(|app, command_line| {
+        match CliArgs::try_parse_from(command_line.arguments()) {
+            Ok(args) => {
+                args.activate(app);
+                0
+            }
+            Err(err) => {
+                command_line.printerr_literal(&err.to_string());
+                1
+            }
+        }
+    });
 
     let quit_action = {
         let app = app.clone();
@@ -59,6 +107,9 @@ fn run_app() {
 
     relm4_icons::initialize_icons();
 
-    let app = RelmApp::from_app(app).visible_on_activate(false);
-    app.run::<App>(());
+    // Pass the arguments to GTK, so they reach the running instance.
+    let app = RelmApp::from_app(app)
+        .visible_on_activate(false)
+        .with_args(std::env::args().collect());
+    app.run::<App>(hidden);
 }
@@ -0,0 +1,34 @@
+use adw::prelude::{MessageDialogExt, MessageDialogExtManual};
+use gtk::gio;
+use gtk::prelude::{GtkApplicationExt, GtkWindowExt, SettingsExt};
+use relm4::{adw, gtk, main_application};
+
+use crate::config::APP_ID;
+
+/// Whether the controls that can harm the device are shown,
+/// e.g. unsafe fan curves or the fan speed preview.
+pub fn is_enabled() -> bool {
+    gio::Settings::new(APP_ID).boolean("advanced-mode")
+}
+
+/// Explain the risks of the advanced mode and
+/// return whether the user still wants to enable it.
+pub async fn confirm() -> bool {
+    let dialog = adw::MessageDialog::builder()
+        .modal(true)
+        .heading("Enable advanced mode?")
+        .body(
+            "The advanced mode allows fan curves that keep the fans slow at high temperatures, \
+            sets the fan speed directly while editing a fan curve and enables the battery \
+            calibration, which overrides the charging settings of the firmware.\n\n\
+            Wrong settings can overheat the device or wear out the battery. \
+            Only enable it if you know what these settings do.",
+        )
+        .default_response("cancel")
+        .close_response("cancel")
+        .build();
+    dialog.set_transient_for(main_application().active_window().as_ref());
+    dialog.add_responses(&[("cancel", "Cancel"), ("enable", "Enable")]);
+    dialog.set_response_appearance("enable", adw::ResponseAppearance::Destructive);
+    dialog.choose_future().await == "enable"
+}
@@ -0,0 +1,127 @@
+use std::fmt::Write;
+
+use adw::prelude::{MessageDialogExt, MessageDialogExtManual};
+use gtk::prelude::{FileExtManual, GtkApplicationExt, GtkWindowExt};
+use relm4::{adw, gtk, main_application};
+use tailor_api::TccImport;
+
+use crate::state::{hardware_capabilities, tailor_connection, TailorStateMsg, STATE};
+
+/// The name of the keyboard profile that is imported from the TCC settings.
+const KEYBOARD_PROFILE_NAME: &str = "tcc";
+
+/// Let the user pick an export or the settings of the TUXEDO Control Center
+/// and import its profiles as global and fan profiles.
+pub fn import() {
+    relm4::spawn_local(async move {
+        let window = main_application().active_window();
+        let dialog = gtk::FileDialog::builder()
+            .title("Import from TUXEDO Control Center")
+            .modal(true)
+            .build();
+        let Ok(file) = dialog.open_future(window.as_ref()).await else {
+            // Cancelled.
+            return;
+        };
+
+        let result = match file.load_contents_future().await {
+            Ok((data, _)) => TccImport::parse(&String::from_utf8_lossy(&data)),
+            Err(err) => Err(err.to_string()),
+        };
+        match result {
+            Ok(import) => confirm(import).await,
+            Err(err) => STATE.emit(TailorStateMsg::Error(format!(
+                "Failed to import the TCC profiles: {err}"
+            ))),
+        }
+    });
+}
+
+/// Show what's going to be imported and import it if the user agrees.
+async fn confirm(import: TccImport) {
+    let dialog = adw::MessageDialog::builder()
+        .modal(true)
+        .heading("Import from TUXEDO Control Center")
+        .body(summary(&import))
+        .default_response("import")
+        .close_response("cancel")
+        .build();
+    dialog.set_transient_for(main_application().active_window().as_ref());
+    dialog.add_responses(&[("cancel", "Cancel"), ("import", "Import")]);
+    dialog.set_response_appearance("import", adw::ResponseAppearance::Suggested);
+    if dialog.choose_future().await != "import" {
+        return;
+    }
+
+    let Some(connection) = tailor_connection() else {
+        return;
+    };
+    // Existing profiles are kept, the imported ones get another name instead.
+    let mut names = connection.list_global_profiles().await.unwrap_or_default();
+    names.extend(connection.list_fan_profiles().await.unwrap_or_default());
+    let led_profiles = connection.list_led_profiles().await.unwrap_or_default();
+
+    let keyboard = import.keyboard.map(|profile| {
+        let name = unique_name(KEYBOARD_PROFILE_NAME, &led_profiles);
+        STATE.emit(TailorStateMsg::AddLedProfile {
+            name: name.clone(),
+            profile,
+        });
+        name
+    });
+    let hardware = hardware_capabilities().cloned().unwrap_or_default();
+    for mut profile in import.profiles {
+        profile.name = unique_name(&profile.name, &names);
+        names.push(profile.name.clone());
+        STATE.emit(TailorStateMsg::AddFanProfile {
+            name: profile.name.clone(),
+            profile: profile.fan.clone(),
+        });
+        STATE.emit(TailorStateMsg::AddProfile {
+            name: profile.name.clone(),
+            profile: profile.global_profile(&hardware, keyboard.as_deref()),
+        });
+    }
+}
+
+fn summary(import: &TccImport) -> String {
+    let mut summary = String::new();
+    if !import.profiles.is_empty() {
+        let names: Vec<&str> = import
+            .profiles
+            .iter()
+            .map(|profile| profile.name.as_str())
+            .collect();
+        writeln!(
+            summary,
+            "Each profile is imported as a global profile with a fan curve of the same name: {}.",
+            names.join(", ")
+        )
+        .unwrap();
+    }
+    if import.keyboard.is_some() {
+        writeln!(
+            summary,
+            "The keyboard backlight is imported as LED profile \"{KEYBOARD_PROFILE_NAME}\"."
+        )
+        .unwrap();
+    }
+    if !import.warnings.is_empty() {
+        writeln!(summary, "\nNot supported by Tailor:").unwrap();
+        for warning in &import.warnings {
+            writeln!(summary, "• {warning}").unwrap();
+        }
+    }
+    summary.trim_end().to_owned()
+}
+
+/// `name`, or `name-2`, `name-3` and so on if it's already taken.
+fn unique_name(name: &str, existing: &[String]) -> String {
+    let mut candidate = name.to_owned();
+    let mut counter = 1;
+    while existing.contains(&candidate) {
+        counter += 1;
+        candidate = format!("{name}-{counter}");
+    }
+    candidate
+}
@@ -0,0 +1,60 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use adw::prelude::{MessageDialogExt, MessageDialogExtManual};
+use gtk::glib;
+use gtk::prelude::{GtkApplicationExt, GtkWindowExt};
+use relm4::{adw, gtk, main_application};
+
+use crate::state::{TailorStateMsg, STATE};
+
+/// Seconds until changes are reverted without confirmation.
+const KEEP_TIMEOUT: u32 = 15;
+
+/// Ask whether to keep changes that were just applied, like the display settings do.
+/// `revert` is emitted unless the user keeps the changes in time, so a fan curve
+/// or performance profile that makes the device unusable doesn't stay active.
+pub fn confirm(heading: &str, revert: TailorStateMsg) {
+    let dialog = adw::MessageDialog::builder()
+        .modal(true)
+        .heading(heading)
+        .body(body(KEEP_TIMEOUT))
+        .default_response("keep")
+        .close_response("revert")
+        .build();
+    dialog.set_transient_for(main_application().active_window().as_ref());
+    dialog.add_responses(&[("revert", "Revert"), ("keep", "Keep changes")]);
+    dialog.set_response_appearance("keep", adw::ResponseAppearance::Suggested);
+
+    let remaining = Rc::new(Cell::new(KEEP_TIMEOUT));
+    let countdown = {
+        let dialog = dialog.clone();
+        let remaining = remaining.clone();
+        glib::timeout_add_seconds_local(1, move || {
+            let seconds = remaining.get() - 1;
+            remaining.set(seconds);
+            if seconds == 0 {
+                dialog.response("revert");
+                glib::ControlFlow::Break
+            } else {
+                dialog.set_body(&body(seconds));
+                glib::ControlFlow::Continue
+            }
+        })
+    };
+
+    relm4::spawn_local(async move {
+        let response = dialog.choose_future().await;
+        // The countdown removed itself if it ran out.
+        if remaining.get() > 0 {
+            countdown.remove();
+        }
+        if response != "keep" {
+            STATE.emit(revert);
+        }
+    });
+}
+
+fn body(seconds: u32) -> String {
+    format!("The previous settings are restored in {seconds} seconds.")
+}
@@ -1,2 +1,6 @@
 pub mod about;
 pub mod add_profile;
+pub mod advanced_mode;
+pub mod import_tcc;
+pub mod keep_changes;
+pub mod preview_profile;
@@ -0,0 +1,61 @@
+use std::fmt::Write;
+
+use adw::prelude::{MessageDialogExt, MessageDialogExtManual};
+use gtk::gio;
+use gtk::prelude::{GtkApplicationExt, GtkWindowExt, SettingsExt};
+use relm4::{adw, gtk, main_application};
+use tailor_api::HardwareWrite;
+
+use crate::config::APP_ID;
+use crate::state::tailor_connection;
+
+pub const SETTING: &str = "preview-profile-changes";
+
+/// Whether profiles are activated only after
+/// the user saw the values they change.
+pub fn is_enabled() -> bool {
+    gio::Settings::new(APP_ID).boolean(SETTING)
+}
+
+/// Show the values that activating a profile changes and return
+/// whether the user wants to activate it. Returns `true` right
+/// away if the confirmation is disabled or nothing changes.
+pub async fn confirm(name: &str) -> bool {
+    if !is_enabled() {
+        return true;
+    }
+    let Some(connection) = tailor_connection() else {
+        return true;
+    };
+    let writes = match connection.preview_global_profile(name).await {
+        Ok(writes) => writes,
+        Err(err) => {
+            // Older versions of tailord can't preview profiles.
+            tracing::warn!("Failed to preview profile `{name}`: `{err}`");
+            return true;
+        }
+    };
+    if writes.is_empty() {
+        return true;
+    }
+
+    let dialog = adw::MessageDialog::builder()
+        .modal(true)
+        .heading(format!("Activate {name}?"))
+        .body(summary(&writes))
+        .default_response("activate")
+        .close_response("cancel")
+        .build();
+    dialog.set_transient_for(main_application().active_window().as_ref());
+    dialog.add_responses(&[("cancel", "Cancel"), ("activate", "Activate")]);
+    dialog.set_response_appearance("activate", adw::ResponseAppearance::Suggested);
+    dialog.choose_future().await == "activate"
+}
+
+fn summary(writes: &[HardwareWrite]) -> String {
+    let mut summary = String::from("Tailor is going to change these settings:\n");
+    for write in writes {
+        write!(summary, "\n• {write}").unwrap();
+    }
+    summary
+}
@@ -2,7 +2,9 @@ use std::time::Duration;
 
 use relm4::tokio::sync::OnceCell;
 use relm4::{Reducer, Reducible};
-use tailor_api::{Color, ColorProfile, FanProfilePoint, LedDeviceInfo, ProfileInfo};
+use tailor_api::{
+    Color, ColorProfile, FanProfilePoint, HardwareCapabilities, ProfileInfo, ProfileOrder,
+};
 use tailor_client::{ClientError, TailorConnection};
 
 use crate::app::FullProfileInfo;
@@ -19,13 +21,6 @@ pub fn hardware_capabilities() -> Option<&'static HardwareCapabilities> {
     HARDWARE_CAPABILITIES.get()
 }
 
-#[derive(Clone)]
-pub struct HardwareCapabilities {
-    pub num_of_fans: u8,
-    pub led_devices: Vec<LedDeviceInfo>,
-    pub performance_profiles: Option<Vec<String>>,
-}
-
 pub enum TailorState {
     Uninitialized,
     Initialized(TailorStateInner),
@@ -63,6 +58,8 @@ pub struct TailorStateInner {
     pub connection: TailorConnection<'static>,
     pub active_profile_name: String,
     pub profiles: Vec<FullProfileInfo>,
+    /// The order of [`TailorStateInner::profiles`], which is shared with the CLI.
+    pub profile_order: ProfileOrder,
     pub led_profiles: Vec<String>,
     pub fan_profiles: Vec<String>,
     pub error: Option<String>,
@@ -76,6 +73,13 @@ pub enum TailorStateMsg {
         name: String,
         profile: ProfileInfo,
     },
+    /// Restore a profile after provisionally applied changes were declined.
+    /// Unlike [`TailorStateMsg::AddProfile`], this also resets the widgets
+    /// of the profile.
+    RevertProfile {
+        name: String,
+        profile: ProfileInfo,
+    },
     AddFanProfile {
         name: String,
         profile: Vec<FanProfilePoint>,
@@ -108,6 +112,15 @@ pub enum TailorStateMsg {
         from: String,
         to: String,
     },
+    /// Move a profile to the position of another profile.
+    MoveProfile {
+        from: String,
+        to: String,
+    },
+    SetFavoriteProfile {
+        name: String,
+        favorite: bool,
+    },
     DeleteProfile(String),
     DeleteFanProfile(String),
     DeleteLedProfile(String),
@@ -179,6 +192,9 @@ impl Reducible for TailorState {
                     }
                 }
             }
+            TailorStateMsg::RevertProfile { name, profile } => {
+                self.reduce(TailorStateMsg::AddProfile { name, profile });
+            }
             TailorStateMsg::AddFanProfile { name, profile } => {
                 if let Some(state) = self.get_mut() {
                     {
@@ -260,12 +276,14 @@ impl Reducible for TailorState {
                     let profiles = state.get_mut_profiles();
                     if let Some(profile) = profiles.iter_mut().find(|p| p.name == from) {
                         {
+                            let from = from.clone();
                             let to = to.clone();
                             relm4::spawn(async move {
                                 handle_result(connection.rename_global_profile(&from, &to).await);
                             });
                         }
-                        profile.name = to;
+                        profile.name = to.clone();
+                        state.get_mut_profile_order().rename(&from, &to);
                     }
                 }
             }
@@ -299,6 +317,31 @@ impl Reducible for TailorState {
                     }
                 }
             }
+            TailorStateMsg::MoveProfile { from, to } => {
+                if let Some(state) = self.get_mut() {
+                    let mut names: Vec<String> =
+                        state.profiles.iter().map(|p| p.name.clone()).collect();
+                    let (Some(from_idx), Some(to_idx)) = (
+                        names.iter().position(|name| name == &from),
+                        names.iter().position(|name| name == &to),
+                    ) else {
+                        return false;
+                    };
+                    let name = names.remove(from_idx);
+                    names.insert(to_idx, name);
+                    state.update_profile_order(|order| order.order = names);
+                }
+            }
+            TailorStateMsg::SetFavoriteProfile { name, favorite } => {
+                if let Some(state) = self.get_mut() {
+                    state.update_profile_order(|order| {
+                        order.favorites.retain(|n| n != &name);
+                        if favorite {
+                            order.favorites.push(name);
+                        }
+                    });
+                }
+            }
             TailorStateMsg::DeleteProfile(name) => {
                 if let Some(state) = self.get_mut() {
                     let connection = state.connection.clone();
@@ -311,6 +354,7 @@ impl Reducible for TailorState {
                             });
                         }
                         profiles.remove(pos);
+                        state.get_mut_profile_order().remove(&name);
                     }
                 }
             }
@@ -372,27 +416,32 @@ impl Reducible for TailorState {
     }
 }
 
+impl TailorStateInner {
+    /// Change the order of the profiles, sort them accordingly and store the order in tailord.
+    fn update_profile_order(&mut self, change: impl FnOnce(&mut ProfileOrder)) {
+        change(self.get_mut_profile_order());
+        let mut names: Vec<String> = self.profiles.iter().map(|p| p.name.clone()).collect();
+        self.profile_order.sort(&mut names);
+        self.get_mut_profiles()
+            .sort_by_key(|profile| names.iter().position(|name| name == &profile.name));
+
+        let order = self.profile_order.clone();
+        let connection = self.connection.clone();
+        relm4::spawn(async move {
+            handle_result(connection.set_global_profile_order(&order).await);
+        });
+    }
+}
+
 pub async fn initialize_tailor_state() -> Result<(), String> {
     let connection = TailorConnection::new().await.map_err(|e| e.to_string())?;
-    let num_of_fans = connection
-        .get_number_of_fans()
+    let capabilities = connection
+        .get_hardware_capabilities()
         .await
         .map_err(|err| err.to_string())?;
-    let led_devices = connection
-        .get_led_devices()
-        .await
-        .map_err(|err| err.to_string())?;
-    let performance_profiles = connection
-        .list_performance_profiles()
-        .await
-        .map_err(|err| tracing::info!("No performance handler available: {err}"))
-        .ok();
-
-    let capabilities = HardwareCapabilities {
-        num_of_fans,
-        led_devices,
-        performance_profiles,
-    };
+    if !capabilities.supports_performance_profiles() {
+        tracing::info!("No performance handler available");
+    }
 
     let active_profile_name = connection
         .get_active_global_profile_name()
@@ -409,6 +458,12 @@ pub async fn initialize_tailor_state() -> Result<(), String> {
         .await
         .map_err(|e| e.to_string())?;
 
+    // Older versions of tailord have no order.
+    let profile_order = connection
+        .get_global_profile_order()
+        .await
+        .unwrap_or_default();
+
     let profiles = futures::future::join_all(
         connection
             .list_global_profiles()
@@ -436,6 +491,7 @@ pub async fn initialize_tailor_state() -> Result<(), String> {
         connection,
         active_profile_name,
         profiles,
+        profile_order,
         led_profiles,
         fan_profiles,
         tracker: 0,
@@ -1,4 +1,13 @@
-use std::fmt::Debug;
+mod probe;
+mod report;
+
+use std::{
+    fmt::Debug,
+    io::{BufRead, IsTerminal, Write},
+};
+
+use probe::ProbeReport;
+use report::{FanReport, PerformanceReport, Probe, Report, TdpReport};
 
 fn print_value<T: Debug>(property: &str, value: &T) {
     println!("[OK]    {property}: {value:?}");
@@ -8,15 +17,15 @@ fn print_info(property: &str) {
     println!("[INFO]  {property}");
 }
 
-fn print_err<T: Debug>(property: &str, value: &T) {
-    println!("[ERR]   {property}: {value:?}");
+fn print_err(property: &str, err: &str) {
+    println!("[ERR]   {property}: {err}");
 }
 
-fn print_fatal<T: Debug>(property: &str, value: &T) {
-    println!("[FATAL] {property}: {value:?}");
+fn print_fatal(property: &str, err: &str) {
+    println!("[FATAL] {property}: {err}");
 }
 
-fn print_result<T: Debug, E: Debug>(property: &str, value: &Result<T, E>) {
+fn print_result<T: Debug>(property: &str, value: &Probe<T>) {
     match value {
         Ok(ok) => print_value(property, ok),
         Err(err) => print_err(property, err),
@@ -24,87 +33,169 @@ fn print_result<T: Debug, E: Debug>(property: &str, value: &Result<T, E>) {
 }
 
 fn main() {
+    let mut json = false;
+    let mut probe = false;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--probe" => probe = true,
+            _ => {
+                eprintln!("Usage: tailor_hwcaps [--json] [--probe]");
+                std::process::exit(2);
+            }
+        }
+    }
+
     sudo::escalate_if_needed().unwrap();
 
-    ioctl();
+    if probe {
+        eprintln!(
+            "Probing sets the fans to their current speed and switches through all performance \
+            profiles. A running tailord is inhibited meanwhile and restores its settings afterwards"
+        );
+        // Scripts probe everything.
+        let interactive = std::io::stdin().is_terminal();
+        let report = ProbeReport::collect(|capability| !interactive || confirm(capability));
+        if json {
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        } else {
+            print_probe(&report);
+        }
+        return;
+    }
+
+    let report = Report::collect();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    } else {
+        print_report(&report);
+    }
+}
 
-    tokio_uring::start(sysfs());
+/// Ask on stderr whether the setters of a capability should be probed.
+fn confirm(capability: &str) -> bool {
+    eprint!("Probe the setters of the {capability}? [y/N] ");
+    std::io::stderr().flush().ok();
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer).ok();
+    matches!(answer.trim(), "y" | "Y" | "yes")
 }
 
-fn ioctl() {
-    let io = tuxedo_ioctl::hal::IoInterface::new();
-    let io = match io {
-        Ok(io) => io,
-        Err(err) => {
-            print_fatal("Connecting to ioctl interface failed", &err);
-            return;
+fn print_probe(report: &ProbeReport) {
+    if let Some(err) = &report.interface_error {
+        print_fatal("Connecting to the hardware interface failed", err);
+    }
+    for call in &report.calls {
+        match &call.result {
+            Ok(value) => println!("[OK]    {}: {value}", call.call),
+            Err(err) => print_err(&call.call, err),
         }
-    };
+    }
+    for capability in &report.skipped {
+        print_info(&format!("Skipped the {capability}"));
+    }
+}
 
-    print_value("Module version", &io.module_version);
+fn print_report(report: &Report) {
+    print_result("Kernel version", &report.kernel_version);
+    match &report.modules {
+        Ok(modules) if modules.is_empty() => print_info("No tuxedo kernel modules are loaded"),
+        Ok(modules) => {
+            for module in modules {
+                let version = module.version.as_deref().unwrap_or("unknown version");
+                print_value(&format!("Loaded module {}", module.name), &version);
+            }
+        }
+        Err(err) => print_err("Reading the loaded kernel modules failed", err),
+    }
 
-    print_result("Device interface ID", &io.device.device_interface_id_str());
-    print_result("Model ID", &io.device.device_model_id_str());
+    if let Some(err) = &report.ioctl_error {
+        print_err("Connecting to ioctl interface failed", err);
+        match &report.interface {
+            Ok(interface) => print_value("Platform interface", interface),
+            Err(err) => print_fatal("Connecting to platform interface failed", err),
+        }
+    }
+    if let Some(module_version) = &report.module_version {
+        print_value("Module version", module_version);
+    }
+
+    if let Some(device) = &report.device {
+        print_result("Device interface ID", &device.interface_id);
+        print_result("Model ID", &device.model_id);
+        print_value("Capabilities", &report.capabilities);
 
+        match &report.performance_profiles {
+            Some(profiles) => print_performance_profiles(profiles),
+            None => print_info("Performance profiles are not available"),
+        }
+        match &report.fans {
+            Some(fans) => print_fans(fans),
+            None => print_info("Fan control is not available"),
+        }
+        match &report.webcam {
+            Some(webcam) => print_result("Webcam enabled", webcam),
+            None => print_info("Webcam control is not available"),
+        }
+        match &report.touchpad {
+            Some(touchpad) => print_result("Touchpad enabled", touchpad),
+            None => print_info("Touchpad control is not available"),
+        }
+        match &report.tdp {
+            Some(tdp) => print_tdp(tdp),
+            None => print_info("TDP control is not available"),
+        }
+    }
+
+    match &report.leds {
+        Ok(leds) => {
+            print_value("Number of LED devices", &leds.len());
+            for (idx, led) in leds.iter().enumerate() {
+                print_value("LED device number", &idx);
+                print_value("LED device name", &led.device_name);
+                print_value("LED device function", &led.function);
+                print_value("LED mode", &led.mode);
+                print_result("LED device color", &led.color);
+            }
+        }
+        Err(err) => print_err("Detecting LED devices failed", err),
+    }
+
+    match &report.pcie_aspm {
+        Some(aspm) => {
+            print_result("PCIe ASPM policy", &aspm.policy);
+            print_value("PCIe ASPM policy writable", &aspm.writable);
+        }
+        None => print_info("PCIe ASPM is not available"),
+    }
+}
+
+fn print_performance_profiles(profiles: &PerformanceReport) {
     print_result(
         "Available ODM performance profiles",
-        &io.device.get_available_odm_performance_profiles(),
+        &profiles.available_odm_performance_profiles,
     );
     print_result(
         "Default ODM performance profile",
-        &io.device.get_default_odm_performance_profile(),
+        &profiles.default_odm_performance_profile,
     );
-
-    print_value("Number of fans", &io.device.get_number_fans());
-    let fan_temperatures = (0..io.device.get_number_fans())
-        .map(|fan| io.device.get_fan_temperature(fan))
-        .collect::<Result<Vec<_>, _>>();
-    let fan_speeds = (0..io.device.get_number_fans())
-        .map(|fan| io.device.get_fan_speed_percent(fan))
-        .collect::<Result<Vec<_>, _>>();
-    print_result("Fan temperatures [°C]", &fan_temperatures);
-    print_result("Fan speeds [%]", &fan_speeds);
-    print_result("Fan min speed [%]", &io.device.get_fans_min_speed());
-
-    if let Some(webcam) = &io.webcam {
-        print_result("Webcam enabled", &webcam.get_webcam());
-    } else {
-        print_info("Webcam control is not available");
-    }
-
-    if let Some(tdp) = &io.tdp {
-        let number_of_tdp_devices = tdp.get_number_tdps();
-        print_result("number_of_tdp_devices", &number_of_tdp_devices);
-        print_result("tdp_descriptors", &tdp.get_tdp_descriptors());
-
-        let number_of_tdp_devices = number_of_tdp_devices.unwrap_or_default();
-        let tdps = (0..number_of_tdp_devices)
-            .map(|tdp_index| tdp.get_tdp(tdp_index))
-            .collect::<Result<Vec<_>, _>>();
-        let max_tdps = (0..number_of_tdp_devices)
-            .map(|tdp_index| tdp.get_tdp_max(tdp_index))
-            .collect::<Result<Vec<_>, _>>();
-        let min_tdps = (0..number_of_tdp_devices)
-            .map(|tdp_index| tdp.get_tdp_min(tdp_index))
-            .collect::<Result<Vec<_>, _>>();
-
-        print_result("tdps", &tdps);
-        print_result("max_tdps", &max_tdps);
-        print_result("min_tdps", &min_tdps);
-    } else {
-        print_info("TDP control is not available");
-    }
 }
 
-async fn sysfs() {
-    let led_collection = tuxedo_sysfs::led::Collection::new().await.unwrap();
-    print_value("Number of LED devices", &led_collection.len());
+fn print_fans(fans: &FanReport) {
+    print_value("Number of fans", &fans.fan_count);
+    print_result("Fan temperatures [°C]", &fans.fan_temperatures);
+    print_result("Fan speeds [%]", &fans.fan_speeds);
+    print_result(
+        &format!("Fan speeds [0-{}]", fans.fan_speed_raw_max),
+        &fans.fan_speeds_raw,
+    );
+    print_result("Fan min speed [%]", &fans.fans_min_speed);
+}
 
-    for (idx, mut controller) in led_collection.into_inner().into_iter().enumerate() {
-        print_value("LED device number", &idx);
-        print_value("LED device name", &controller.device_name());
-        print_value("LED device function", &controller.function());
-        print_value("LED mode", &controller.mode());
-        print_value("LED device color", &controller.get_color().await);
-    }
+fn print_tdp(tdp: &TdpReport) {
+    print_result("number_of_tdp_devices", &tdp.count);
+    print_result("tdp_descriptors", &tdp.descriptors);
+    print_result("tdps", &tdp.values);
+    print_result("max_tdps", &tdp.max_values);
+    print_result("min_tdps", &tdp.min_values);
 }
@@ -0,0 +1,242 @@
+use std::{os::fd::OwnedFd, sync::Arc};
+
+use serde::Serialize;
+use tailor_client::TailorConnection;
+use tuxedo_ioctl::hal::{
+    traits::{FanControl, PerformanceProfiles, TdpControl, TouchpadControl, WebcamControl},
+    Capabilities, IoctlResult,
+};
+
+/// Uniwill devices have at most three TDP slots. All of them
+/// are read, even if the firmware reports fewer.
+const TDP_SLOTS: u8 = 3;
+
+/// Which calls of the hardware interface succeed on a device. Unlike
+/// the [`crate::report::Report`], probing also calls setters, but
+/// only with values that keep the hardware in its current state.
+/// A running tailord is inhibited meanwhile, so it doesn't write
+/// to the EC at the same time.
+#[derive(Debug, Default, Serialize)]
+pub struct ProbeReport {
+    /// Why no hardware interface is available or tailord couldn't be inhibited.
+    pub interface_error: Option<String>,
+    pub calls: Vec<ProbeCall>,
+    /// The capabilities whose setters weren't confirmed.
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProbeCall {
+    /// The call with its arguments, e.g. `get_tdp(2)`.
+    pub call: String,
+    /// The returned value or the error.
+    pub result: Result<String, String>,
+}
+
+/// Keeps tailord away from the EC until it's dropped.
+struct Inhibitor {
+    _fd: OwnedFd,
+    /// The performance profile that tailord applied.
+    performance_profile: Option<String>,
+}
+
+impl Inhibitor {
+    /// `None` if tailord isn't running.
+    fn take() -> Result<Option<Self>, String> {
+        tokio_uring::start(async {
+            let Ok(connection) = TailorConnection::new().await else {
+                return Ok(None);
+            };
+            let performance_profile = connection.get_performance_profile("").await.ok();
+            let fd = connection
+                .inhibit_fan_control("tailor_hwcaps", "Probing the hardware interface")
+                .await
+                .map_err(|err| format!("Inhibiting tailord failed: {err}"))?;
+            Ok(Some(Self {
+                _fd: fd,
+                performance_profile,
+            }))
+        })
+    }
+}
+
+impl ProbeReport {
+    /// Probe the capabilities whose setters are confirmed by `confirm`,
+    /// which receives the name of the capability.
+    pub fn collect(confirm: impl Fn(&str) -> bool) -> Self {
+        let inhibitor = match Inhibitor::take() {
+            Ok(inhibitor) => inhibitor,
+            Err(err) => {
+                return Self {
+                    interface_error: Some(err),
+                    ..Default::default()
+                }
+            }
+        };
+        let capabilities = match tuxedo_ioctl::hal::IoInterface::new() {
+            Ok(io) => io.capabilities,
+            Err(err) => match tuxedo_sysfs::platform::PlatformHardware::new() {
+                Ok(platform) => Arc::new(platform).capabilities(),
+                Err(platform_err) => {
+                    return Self {
+                        interface_error: Some(format!("{err:?}, {platform_err:?}")),
+                        ..Default::default()
+                    }
+                }
+            },
+        };
+
+        let performance_profile = inhibitor
+            .as_ref()
+            .and_then(|inhibitor| inhibitor.performance_profile.clone());
+        let mut report = Self::default();
+        report.probe_capabilities(&capabilities, performance_profile, confirm);
+        // tailord applies its settings again once the inhibitor is dropped.
+        drop(inhibitor);
+        report
+    }
+
+    fn probe_capabilities(
+        &mut self,
+        capabilities: &Capabilities,
+        performance_profile: Option<String>,
+        confirm: impl Fn(&str) -> bool,
+    ) {
+        if let Some(fans) = &capabilities.fans {
+            if self.confirm(&confirm, "fans") {
+                self.probe_fans(fans.as_ref());
+            }
+        }
+        if let Some(profiles) = &capabilities.performance_profiles {
+            if self.confirm(&confirm, "performance profiles") {
+                self.probe_performance_profiles(profiles.as_ref(), performance_profile);
+            }
+        }
+        if let Some(webcam) = &capabilities.webcam {
+            if self.confirm(&confirm, "webcam") {
+                self.probe_webcam(webcam.as_ref());
+            }
+        }
+        if let Some(touchpad) = &capabilities.touchpad {
+            if self.confirm(&confirm, "touchpad") {
+                self.probe_touchpad(touchpad.as_ref());
+            }
+        }
+        // Only reads.
+        if let Some(tdp) = &capabilities.tdp {
+            self.probe_tdp(tdp.as_ref());
+        }
+    }
+
+    /// Whether the setters of a capability are probed.
+    fn confirm(&mut self, confirm: impl Fn(&str) -> bool, capability: &str) -> bool {
+        let confirmed = confirm(capability);
+        if !confirmed {
+            self.skipped.push(capability.to_owned());
+        }
+        confirmed
+    }
+
+    fn call<T: std::fmt::Debug>(&mut self, call: String, result: IoctlResult<T>) -> Option<T> {
+        let (result, value) = match result {
+            Ok(value) => (Ok(format!("{value:?}")), Some(value)),
+            Err(err) => (Err(format!("{err:?}")), None),
+        };
+        self.calls.push(ProbeCall { call, result });
+        value
+    }
+
+    /// Set each fan to the speed it already runs at, then return the
+    /// control to the firmware and restore the duties that were set before.
+    fn probe_fans(&mut self, fans: &dyn FanControl) {
+        let mut duties = Vec::new();
+        for fan in 0..fans.get_number_fans() {
+            self.call(
+                format!("get_fan_temperature({fan})"),
+                fans.get_fan_temperature(fan),
+            );
+            let duty = fans.get_fan_duty_raw(fan);
+            self.call(format!("get_fan_duty_raw({fan})"), Ok(duty));
+            duties.push((fan, duty));
+            if let Some(speed) = self.call(
+                format!("get_fan_speed_raw({fan})"),
+                fans.get_fan_speed_raw(fan),
+            ) {
+                self.call(
+                    format!("set_fan_speed_raw({fan}, {speed})"),
+                    fans.set_fan_speed_raw(fan, speed),
+                );
+            }
+        }
+        self.call("get_fans_min_speed()".to_owned(), fans.get_fans_min_speed());
+        self.call(
+            "get_fans_off_available()".to_owned(),
+            fans.get_fans_off_available(),
+        );
+        self.call("set_fans_auto()".to_owned(), fans.set_fans_auto());
+        for (fan, duty) in duties {
+            if let Some(duty) = duty {
+                self.call(
+                    format!("set_fan_speed_raw({fan}, {duty})"),
+                    fans.set_fan_speed_raw(fan, duty),
+                );
+            }
+        }
+    }
+
+    /// Set each available profile, then the profile that tailord applied.
+    /// Without tailord, the default profile is set last, since the active
+    /// profile can't be read from the firmware.
+    fn probe_performance_profiles(
+        &mut self,
+        profiles: &dyn PerformanceProfiles,
+        active: Option<String>,
+    ) {
+        let available = self.call(
+            "get_available_odm_performance_profiles()".to_owned(),
+            profiles.get_available_odm_performance_profiles(),
+        );
+        let default = self.call(
+            "get_default_odm_performance_profile()".to_owned(),
+            profiles.get_default_odm_performance_profile(),
+        );
+        for profile in available
+            .iter()
+            .flatten()
+            .chain(active.as_ref().or(default.as_ref()))
+        {
+            self.call(
+                format!("set_odm_performance_profile({profile:?})"),
+                profiles.set_odm_performance_profile(profile),
+            );
+        }
+    }
+
+    fn probe_webcam(&mut self, webcam: &dyn WebcamControl) {
+        if let Some(enabled) = self.call("get_webcam()".to_owned(), webcam.get_webcam()) {
+            self.call(format!("set_webcam({enabled})"), webcam.set_webcam(enabled));
+        }
+    }
+
+    fn probe_touchpad(&mut self, touchpad: &dyn TouchpadControl) {
+        if let Some(enabled) = self.call("get_touchpad()".to_owned(), touchpad.get_touchpad()) {
+            self.call(
+                format!("set_touchpad({enabled})"),
+                touchpad.set_touchpad(enabled),
+            );
+        }
+    }
+
+    fn probe_tdp(&mut self, tdp: &dyn TdpControl) {
+        self.call("get_number_tdps()".to_owned(), tdp.get_number_tdps());
+        self.call(
+            "get_tdp_descriptors()".to_owned(),
+            tdp.get_tdp_descriptors(),
+        );
+        for slot in 0..TDP_SLOTS {
+            self.call(format!("get_tdp({slot})"), tdp.get_tdp(slot));
+            self.call(format!("get_tdp_min({slot})"), tdp.get_tdp_min(slot));
+            self.call(format!("get_tdp_max({slot})"), tdp.get_tdp_max(slot));
+        }
+    }
+}
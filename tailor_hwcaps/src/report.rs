@@ -0,0 +1,305 @@
+use std::fmt::Debug;
+
+use serde::Serialize;
+use tailor_api::{AspmPolicy, Color, LedControllerMode};
+use tuxedo_ioctl::hal::{
+    traits::{FanControl, HardwareDevice, PerformanceProfiles, TdpControl},
+    Capabilities,
+};
+
+/// Errors are stored as text, so the report can be serialized.
+pub type Probe<T> = Result<T, String>;
+
+fn probe<T, E: Debug>(result: Result<T, E>) -> Probe<T> {
+    result.map_err(|err| format!("{err:?}"))
+}
+
+/// Kernel modules that belong to the tuxedo drivers.
+const MODULE_PREFIXES: [&str; 3] = ["tuxedo", "clevo", "uniwill"];
+const SYSFS_MODULE_PATH: &str = "/sys/module";
+const KERNEL_VERSION_PATH: &str = "/proc/sys/kernel/osrelease";
+
+/// The hardware capabilities of a device.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub kernel_version: Probe<String>,
+    /// The loaded tuxedo driver modules.
+    pub modules: Probe<Vec<ModuleReport>>,
+    /// Why the ioctl interface is unavailable.
+    pub ioctl_error: Option<String>,
+    /// The version that the tuxedo_io module reports via ioctl.
+    pub module_version: Option<String>,
+    /// Either `ioctl` or the platform interface.
+    pub interface: Probe<String>,
+    pub device: Option<DeviceReport>,
+    /// The names of the supported capabilities.
+    pub capabilities: Vec<String>,
+    /// `None` if fan control is not available.
+    pub fans: Option<FanReport>,
+    /// `None` if performance profiles are not available.
+    pub performance_profiles: Option<PerformanceReport>,
+    /// `None` if webcam control is not available.
+    pub webcam: Option<Probe<bool>>,
+    /// `None` if touchpad control is not available.
+    pub touchpad: Option<Probe<bool>>,
+    /// `None` if TDP control is not available.
+    pub tdp: Option<TdpReport>,
+    pub leds: Probe<Vec<LedReport>>,
+    /// `None` if the kernel was built without PCIe ASPM support.
+    pub pcie_aspm: Option<AspmReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModuleReport {
+    pub name: String,
+    /// `None` if the module doesn't declare a version.
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeviceReport {
+    pub interface_id: Probe<String>,
+    pub model_id: Probe<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PerformanceReport {
+    pub available_odm_performance_profiles: Probe<Vec<String>>,
+    pub default_odm_performance_profile: Probe<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FanReport {
+    pub fan_count: u8,
+    /// In °C.
+    pub fan_temperatures: Probe<Vec<u8>>,
+    /// In percent.
+    pub fan_speeds: Probe<Vec<u8>>,
+    /// In hardware units from 0 to `fan_speed_raw_max`.
+    pub fan_speeds_raw: Probe<Vec<u8>>,
+    pub fan_speed_raw_max: u8,
+    /// In percent.
+    pub fans_min_speed: Probe<u8>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TdpReport {
+    pub count: Probe<u8>,
+    pub descriptors: Probe<Vec<String>>,
+    pub values: Probe<Vec<i32>>,
+    pub min_values: Probe<Vec<i32>>,
+    pub max_values: Probe<Vec<i32>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AspmReport {
+    pub policy: Probe<AspmPolicy>,
+    /// Whether profiles can change the policy.
+    pub writable: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LedReport {
+    pub device_name: String,
+    pub function: String,
+    pub mode: LedControllerMode,
+    pub color: Probe<Color>,
+}
+
+impl Report {
+    pub fn collect() -> Self {
+        let kernel_version = kernel_version();
+        let modules = ModuleReport::collect();
+        let mut report = match tuxedo_ioctl::hal::IoInterface::new() {
+            Ok(io) => Self::new(
+                kernel_version,
+                modules,
+                None,
+                Some(io.module_version.clone()),
+                Ok("ioctl".to_owned()),
+                Some((io.device.as_ref(), &io.capabilities)),
+            ),
+            Err(err) => match tuxedo_sysfs::platform::PlatformHardware::new() {
+                Ok(platform) => {
+                    let platform = std::sync::Arc::new(platform);
+                    let capabilities = platform.capabilities();
+                    Self::new(
+                        kernel_version,
+                        modules,
+                        Some(format!("{err:?}")),
+                        None,
+                        Ok(format!("{:?}", platform.interface())),
+                        Some((platform.as_ref(), &capabilities)),
+                    )
+                }
+                Err(platform_err) => Self::new(
+                    kernel_version,
+                    modules,
+                    Some(format!("{err:?}")),
+                    None,
+                    Err(format!("{platform_err:?}")),
+                    None,
+                ),
+            },
+        };
+
+        report.leds = tokio_uring::start(LedReport::collect());
+        report
+    }
+
+    fn new(
+        kernel_version: Probe<String>,
+        modules: Probe<Vec<ModuleReport>>,
+        ioctl_error: Option<String>,
+        module_version: Option<String>,
+        interface: Probe<String>,
+        device: Option<(&dyn HardwareDevice, &Capabilities)>,
+    ) -> Self {
+        let capabilities = device.map(|(_, capabilities)| capabilities);
+        Self {
+            kernel_version,
+            modules,
+            ioctl_error,
+            module_version,
+            interface,
+            device: device.map(|(device, _)| DeviceReport::collect(device)),
+            capabilities: capabilities
+                .map(|capabilities| {
+                    capabilities
+                        .list()
+                        .into_iter()
+                        .map(|capability| format!("{capability:?}"))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            fans: capabilities
+                .and_then(|capabilities| capabilities.fans.as_deref())
+                .map(FanReport::collect),
+            performance_profiles: capabilities
+                .and_then(|capabilities| capabilities.performance_profiles.as_deref())
+                .map(PerformanceReport::collect),
+            webcam: capabilities
+                .and_then(|capabilities| capabilities.webcam.as_deref())
+                .map(|webcam| probe(webcam.get_webcam())),
+            touchpad: capabilities
+                .and_then(|capabilities| capabilities.touchpad.as_deref())
+                .map(|touchpad| probe(touchpad.get_touchpad())),
+            tdp: capabilities
+                .and_then(|capabilities| capabilities.tdp.as_deref())
+                .map(TdpReport::collect),
+            leds: Ok(Vec::new()),
+            pcie_aspm: AspmReport::collect(),
+        }
+    }
+}
+
+fn kernel_version() -> Probe<String> {
+    probe(std::fs::read_to_string(KERNEL_VERSION_PATH)).map(|version| version.trim().to_owned())
+}
+
+impl ModuleReport {
+    fn collect() -> Probe<Vec<Self>> {
+        let mut modules = Vec::new();
+        for entry in probe(std::fs::read_dir(SYSFS_MODULE_PATH))? {
+            let entry = probe(entry)?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if MODULE_PREFIXES
+                .iter()
+                .any(|prefix| name.starts_with(prefix))
+            {
+                let version = std::fs::read_to_string(entry.path().join("version"))
+                    .ok()
+                    .map(|version| version.trim().to_owned());
+                modules.push(Self { name, version });
+            }
+        }
+        modules.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(modules)
+    }
+}
+
+impl DeviceReport {
+    fn collect(device: &dyn HardwareDevice) -> Self {
+        Self {
+            interface_id: probe(device.device_interface_id_str()),
+            model_id: probe(device.device_model_id_str()),
+        }
+    }
+}
+
+impl PerformanceReport {
+    fn collect(profiles: &dyn PerformanceProfiles) -> Self {
+        Self {
+            available_odm_performance_profiles: probe(
+                profiles.get_available_odm_performance_profiles(),
+            ),
+            default_odm_performance_profile: probe(profiles.get_default_odm_performance_profile()),
+        }
+    }
+}
+
+impl FanReport {
+    fn collect(fans: &dyn FanControl) -> Self {
+        let fan_count = fans.get_number_fans();
+        Self {
+            fan_count,
+            fan_temperatures: probe(
+                (0..fan_count)
+                    .map(|fan| fans.get_fan_temperature(fan))
+                    .collect(),
+            ),
+            fan_speeds: probe(
+                (0..fan_count)
+                    .map(|fan| fans.get_fan_speed_percent(fan))
+                    .collect(),
+            ),
+            fan_speeds_raw: probe(
+                (0..fan_count)
+                    .map(|fan| fans.get_fan_speed_raw(fan))
+                    .collect(),
+            ),
+            fan_speed_raw_max: fans.get_fan_speed_raw_max(),
+            fans_min_speed: probe(fans.get_fans_min_speed()),
+        }
+    }
+}
+
+impl TdpReport {
+    fn collect(tdp: &dyn TdpControl) -> Self {
+        let count = probe(tdp.get_number_tdps());
+        let indices = 0..count.clone().unwrap_or_default();
+        Self {
+            count,
+            descriptors: probe(tdp.get_tdp_descriptors()),
+            values: probe(indices.clone().map(|idx| tdp.get_tdp(idx)).collect()),
+            min_values: probe(indices.clone().map(|idx| tdp.get_tdp_min(idx)).collect()),
+            max_values: probe(indices.map(|idx| tdp.get_tdp_max(idx)).collect()),
+        }
+    }
+}
+
+impl AspmReport {
+    fn collect() -> Option<Self> {
+        let aspm = tuxedo_sysfs::pcie_aspm::PcieAspm::new()?;
+        Some(Self {
+            policy: probe(aspm.get()),
+            writable: aspm.is_writable(),
+        })
+    }
+}
+
+impl LedReport {
+    async fn collect() -> Probe<Vec<Self>> {
+        let collection = probe(tuxedo_sysfs::led::Collection::new().await)?;
+        let mut leds = Vec::new();
+        for mut controller in collection.into_inner() {
+            leds.push(Self {
+                device_name: controller.device_name().to_owned(),
+                function: controller.function().to_owned(),
+                mode: controller.mode(),
+                color: probe(controller.get_color().await),
+            });
+        }
+        Ok(leds)
+    }
+}
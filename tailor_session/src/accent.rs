@@ -0,0 +1,120 @@
+//! Keyboard lighting that follows the accent color of the desktop,
+//! read from the settings portal of xdg-desktop-portal.
+
+use futures_util::StreamExt;
+use tailor_api::Color;
+use tailor_client::TailorConnection;
+use zbus::{
+    dbus_proxy,
+    zvariant::{OwnedValue, Value},
+    Connection,
+};
+
+const APPEARANCE_NAMESPACE: &str = "org.freedesktop.appearance";
+const ACCENT_COLOR_KEY: &str = "accent-color";
+
+#[dbus_proxy(
+    interface = "org.freedesktop.portal.Settings",
+    default_service = "org.freedesktop.portal.Desktop",
+    default_path = "/org/freedesktop/portal/desktop"
+)]
+trait Settings {
+    fn read_one(&self, namespace: &str, key: &str) -> zbus::Result<OwnedValue>;
+
+    /// Deprecated in favor of `ReadOne`, but older portals only have this.
+    fn read(&self, namespace: &str, key: &str) -> zbus::Result<OwnedValue>;
+
+    #[dbus_proxy(signal)]
+    fn setting_changed(&self, namespace: &str, key: &str, value: Value<'_>) -> zbus::Result<()>;
+}
+
+/// Set the LEDs to the accent color and update them when it changes.
+pub async fn run(connection: TailorConnection<'static>) -> eyre::Result<()> {
+    let session = Connection::session().await?;
+    let settings = SettingsProxy::new(&session).await?;
+    let mut setting_changed = settings.receive_setting_changed().await?;
+
+    let initial = match settings
+        .read_one(APPEARANCE_NAMESPACE, ACCENT_COLOR_KEY)
+        .await
+    {
+        Ok(value) => Ok(value),
+        Err(_) => settings.read(APPEARANCE_NAMESPACE, ACCENT_COLOR_KEY).await,
+    };
+    match initial {
+        Ok(value) => update(&connection, accent_color(&value)).await,
+        Err(err) => tracing::info!("The desktop doesn't provide an accent color yet: `{err}`"),
+    }
+
+    while let Some(signal) = setting_changed.next().await {
+        let args = signal.args()?;
+        if args.namespace == APPEARANCE_NAMESPACE && args.key == ACCENT_COLOR_KEY {
+            update(&connection, accent_color(&args.value)).await;
+        }
+    }
+
+    connection.clear_led_color_override(None).await.ok();
+    Ok(())
+}
+
+async fn update(connection: &TailorConnection<'static>, color: Option<Color>) {
+    tracing::debug!("Accent color: {color:?}");
+    let result = match color {
+        Some(color) => connection.set_led_color_override(None, &color).await,
+        // The user doesn't have an accent color.
+        None => connection.clear_led_color_override(None).await,
+    };
+    if let Err(err) = result {
+        tracing::warn!("Failed to update the LEDs: `{err}`");
+    }
+}
+
+/// The portal stores the accent color as `(ddd)` with sRGB values from 0 to 1.
+/// Values out of that range mean that no accent color is set.
+fn accent_color(value: &Value<'_>) -> Option<Color> {
+    match value {
+        // `Read` wraps the value in another variant.
+        Value::Value(value) => accent_color(value),
+        Value::Structure(structure) => {
+            let channel = |idx: usize| match structure.fields().get(idx)? {
+                Value::F64(value) if (0.0..=1.0).contains(value) => {
+                    Some((value * 255.0).round() as u8)
+                }
+                _ => None,
+            };
+            Some(Color {
+                r: channel(0)?,
+                g: channel(1)?,
+                b: channel(2)?,
+            })
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tailor_api::Color;
+    use zbus::zvariant::Value;
+
+    use super::accent_color;
+
+    #[test]
+    fn accent_colors() {
+        let value = Value::from((1.0, 0.5, 0.0));
+        assert_eq!(
+            accent_color(&value),
+            Some(Color {
+                r: 255,
+                g: 128,
+                b: 0
+            })
+        );
+        let wrapped = Value::Value(Box::new(value));
+        assert!(accent_color(&wrapped).is_some());
+
+        let unset = Value::from((-1.0, -1.0, -1.0));
+        assert_eq!(accent_color(&unset), None);
+        assert_eq!(accent_color(&Value::from("blue")), None);
+    }
+}
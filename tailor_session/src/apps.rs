@@ -0,0 +1,131 @@
+use std::{collections::HashSet, os::unix::fs::MetadataExt, path::Path, time::Duration};
+
+use tailor_client::TailorConnection;
+
+use crate::config::AppRule;
+
+const PROC_PATH: &str = "/proc";
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Activate the profile of the first rule whose application is running
+/// and restore the previous profile once none of them is running anymore.
+pub async fn run(connection: TailorConnection<'static>, rules: Vec<AppRule>) -> eyre::Result<()> {
+    let uid = std::fs::metadata(Path::new(PROC_PATH).join("self"))?.uid();
+    // The profile that was active before a rule matched.
+    let mut previous_profile: Option<String> = None;
+    let mut applied_rule: Option<&AppRule> = None;
+
+    loop {
+        let processes = tokio::task::spawn_blocking(move || running_processes(uid)).await?;
+        let rule = matching_rule(&rules, &processes);
+
+        if rule != applied_rule {
+            let profile = match rule {
+                Some(rule) => {
+                    if previous_profile.is_none() {
+                        previous_profile = Some(connection.get_active_global_profile_name().await?);
+                    }
+                    tracing::info!("`{}` is running", rule.process);
+                    Some(rule.profile.clone())
+                }
+                None => previous_profile.take(),
+            };
+
+            if let Some(profile) = profile {
+                match activate_profile(&connection, &profile).await {
+                    Ok(()) => tracing::info!("Activated profile `{profile}`"),
+                    Err(err) => tracing::warn!("Failed to activate profile `{profile}`: `{err}`"),
+                }
+            }
+            applied_rule = rule;
+        }
+
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+async fn activate_profile(
+    connection: &TailorConnection<'_>,
+    name: &str,
+) -> tailor_client::ClientResult<()> {
+    connection.set_active_global_profile_name(name).await?;
+    connection.reload().await
+}
+
+/// The first rule with a running process.
+fn matching_rule<'a>(rules: &'a [AppRule], processes: &HashSet<String>) -> Option<&'a AppRule> {
+    rules.iter().find(|rule| processes.contains(&rule.process))
+}
+
+/// Executable names of all processes of the user.
+fn running_processes(uid: u32) -> HashSet<String> {
+    let Ok(entries) = std::fs::read_dir(PROC_PATH) else {
+        return HashSet::new();
+    };
+
+    entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if !path
+                .file_name()?
+                .to_str()?
+                .bytes()
+                .all(|byte| byte.is_ascii_digit())
+            {
+                return None;
+            }
+            if path.metadata().ok()?.uid() != uid {
+                return None;
+            }
+            let cmdline = std::fs::read(path.join("cmdline")).ok()?;
+            process_name(&cmdline)
+        })
+        .collect()
+}
+
+/// The file name of the executable in `/proc/<pid>/cmdline`.
+fn process_name(cmdline: &[u8]) -> Option<String> {
+    let program = cmdline.split(|byte| *byte == 0).next()?;
+    let program = std::str::from_utf8(program).ok()?;
+    let name = Path::new(program).file_name()?.to_str()?;
+    Some(name.to_owned())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::config::AppRule;
+
+    use super::{matching_rule, process_name};
+
+    #[test]
+    fn app_rules() {
+        assert_eq!(
+            process_name(b"/usr/bin/steam\0-silent\0").as_deref(),
+            Some("steam")
+        );
+        assert_eq!(process_name(b"blender\0").as_deref(), Some("blender"));
+        assert_eq!(process_name(b""), None);
+
+        let rules = vec![
+            AppRule {
+                process: "blender".to_owned(),
+                profile: "performance".to_owned(),
+            },
+            AppRule {
+                process: "steam".to_owned(),
+                profile: "gaming".to_owned(),
+            },
+        ];
+        let processes: HashSet<String> = ["steam", "blender", "bash"]
+            .into_iter()
+            .map(ToOwned::to_owned)
+            .collect();
+        assert_eq!(
+            matching_rule(&rules, &processes).map(|rule| rule.profile.as_str()),
+            Some("performance")
+        );
+        assert_eq!(matching_rule(&rules, &HashSet::new()), None);
+    }
+}
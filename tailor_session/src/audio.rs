@@ -0,0 +1,107 @@
+//! Keyboard lighting that follows the audio output of the session.
+//! The audio is recorded by an external command, PipeWire's
+//! `pw-record` by default, so no audio library has to be linked.
+
+use std::process::Stdio;
+
+use tailor_client::TailorConnection;
+use tokio::{io::AsyncReadExt, process::Command};
+
+use crate::config::AudioLighting;
+
+/// The sample rate of the default command.
+const SAMPLE_RATE: usize = 8000;
+/// Number of color updates per second.
+const UPDATES_PER_SEC: usize = 20;
+/// Each update uses the samples since the last one, 2 bytes each.
+const CHUNK_SIZE: usize = SAMPLE_RATE / UPDATES_PER_SEC * 2;
+/// How much of the previous level is kept per update,
+/// so the LEDs fade out instead of flickering.
+const DECAY: f32 = 0.8;
+/// Levels are amplified because music rarely gets close to full scale.
+const GAIN: f32 = 4.0;
+
+/// Set the LEDs to the configured color with a brightness
+/// that follows the audio output until the recording stops.
+pub async fn run(connection: TailorConnection<'static>, config: AudioLighting) -> eyre::Result<()> {
+    let (program, args) = config
+        .command
+        .split_first()
+        .ok_or_else(|| eyre::eyre!("The audio command is empty"))?;
+    let mut child = Command::new(program)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()?;
+    let mut stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| eyre::eyre!("The audio command has no output"))?;
+    tracing::info!("Recording the audio output with `{program}`");
+
+    let mut chunk = [0; CHUNK_SIZE];
+    let mut level = 0.0;
+    let mut last_brightness = None;
+    let result = loop {
+        if let Err(err) = stdout.read_exact(&mut chunk).await {
+            break Err(err.into());
+        }
+        level = chunk_level(&chunk).max(level * DECAY);
+
+        let brightness = brightness(level, config.min_brightness);
+        if last_brightness != Some(brightness) {
+            let color = config.color.with_brightness(brightness);
+            if let Err(err) = connection.set_led_color_override(None, &color).await {
+                break Err(err.into());
+            }
+            last_brightness = Some(brightness);
+        }
+    };
+
+    connection.clear_led_color_override(None).await.ok();
+    result
+}
+
+/// The RMS level of signed 16-bit little-endian samples from 0 to 1.
+fn chunk_level(chunk: &[u8]) -> f32 {
+    let samples = chunk.chunks_exact(2);
+    let count = samples.len();
+    if count == 0 {
+        return 0.0;
+    }
+    let sum: f32 = samples
+        .map(|sample| {
+            let sample = f32::from(i16::from_le_bytes([sample[0], sample[1]])) / 32768.0;
+            sample * sample
+        })
+        .sum();
+    (sum / count as f32).sqrt()
+}
+
+/// Brightness in percent for a level from 0 to 1.
+fn brightness(level: f32, min_brightness: u8) -> u8 {
+    let min = f32::from(min_brightness.min(100));
+    let level = (level * GAIN).min(1.0);
+    (min + (100.0 - min) * level).round() as u8
+}
+
+#[cfg(test)]
+mod test {
+    use super::{brightness, chunk_level};
+
+    #[test]
+    fn audio_levels() {
+        let silence = [0; 8];
+        assert_eq!(chunk_level(&silence), 0.0);
+        let full_scale: Vec<u8> = [i16::MIN, i16::MIN]
+            .into_iter()
+            .flat_map(i16::to_le_bytes)
+            .collect();
+        assert_eq!(chunk_level(&full_scale), 1.0);
+
+        assert_eq!(brightness(0.0, 20), 20);
+        assert_eq!(brightness(0.125, 0), 50);
+        assert_eq!(brightness(1.0, 20), 100);
+    }
+}
@@ -0,0 +1,117 @@
+use std::path::PathBuf;
+
+use tailor_api::{Color, ConfigFormat};
+
+/// Directory of the per-user configuration, relative to the home directory.
+/// tailord reads user profiles from the same directory.
+const USER_CONFIG_DIR: &str = ".config/tailor";
+/// File name of the configuration without extension.
+const CONFIG_NAME: &str = "session";
+
+/// Settings of the session service, stored in
+/// `~/.config/tailor/session.toml` or `~/.config/tailor/session.json`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    /// Show desktop notifications for critical
+    /// temperatures and thermal throttling.
+    pub notifications: bool,
+    /// Turn off the LEDs while the screen saver is active.
+    pub idle_dimming: bool,
+    /// Activate profiles while certain applications are running.
+    pub app_rules: Vec<AppRule>,
+    /// Let the LEDs follow the audio output.
+    pub audio_lighting: Option<AudioLighting>,
+    /// Set the LEDs to the accent color of the desktop.
+    pub accent_lighting: bool,
+    /// Register shortcuts to cycle profiles and to run the fans at full speed.
+    pub global_shortcuts: bool,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            notifications: true,
+            idle_dimming: true,
+            app_rules: Vec::new(),
+            audio_lighting: None,
+            accent_lighting: false,
+            global_shortcuts: false,
+        }
+    }
+}
+
+/// Activates a global profile while a process is running.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AppRule {
+    /// Executable name of the process, e.g. `steam`.
+    pub process: String,
+    /// Name of the global profile.
+    pub profile: String,
+}
+
+/// Modulates the brightness of the LEDs with the audio level, like a visualizer.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AudioLighting {
+    /// The color at full volume.
+    pub color: Color,
+    /// Brightness in percent while it's silent.
+    #[serde(default)]
+    pub min_brightness: u8,
+    /// Command that writes the audio output to stdout
+    /// as mono, signed 16-bit little-endian samples.
+    #[serde(default = "AudioLighting::default_command")]
+    pub command: Vec<String>,
+}
+
+impl AudioLighting {
+    /// Records the monitor of the default output with PipeWire.
+    fn default_command() -> Vec<String> {
+        [
+            "pw-record",
+            "--rate",
+            "8000",
+            "--channels",
+            "1",
+            "--format",
+            "s16",
+            "-P",
+            "{ stream.capture.sink = true }",
+            "-",
+        ]
+        .into_iter()
+        .map(ToOwned::to_owned)
+        .collect()
+    }
+}
+
+impl SessionConfig {
+    /// Load the configuration and fall back to the
+    /// defaults if it's missing or invalid.
+    pub fn load() -> Self {
+        let Some(dir) =
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(USER_CONFIG_DIR))
+        else {
+            tracing::warn!("HOME isn't set, using the default session config");
+            return Self::default();
+        };
+
+        for format in ConfigFormat::ALL {
+            let path = dir.join(format!("{CONFIG_NAME}.{}", format.extension()));
+            match std::fs::read_to_string(&path) {
+                Ok(data) => {
+                    return format.deserialize(&data).unwrap_or_else(|err| {
+                        tracing::warn!("Failed to parse session config at {path:?}: `{err}`");
+                        Self::default()
+                    })
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => {
+                    tracing::warn!("Failed to read session config at {path:?}: `{err}`");
+                    return Self::default();
+                }
+            }
+        }
+        Self::default()
+    }
+}
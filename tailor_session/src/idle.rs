@@ -0,0 +1,37 @@
+use futures_util::StreamExt;
+use tailor_api::Color;
+use tailor_client::TailorConnection;
+use zbus::{dbus_proxy, Connection};
+
+#[dbus_proxy(
+    interface = "org.freedesktop.ScreenSaver",
+    default_service = "org.freedesktop.ScreenSaver",
+    default_path = "/org/freedesktop/ScreenSaver"
+)]
+trait ScreenSaver {
+    #[dbus_proxy(signal)]
+    fn active_changed(&self, active: bool) -> zbus::Result<()>;
+}
+
+/// Turn off all LEDs while the screen saver is active.
+pub async fn run(connection: TailorConnection<'static>) -> eyre::Result<()> {
+    let session = Connection::session().await?;
+    let screen_saver = ScreenSaverProxy::new(&session).await?;
+    let mut active_changed = screen_saver.receive_active_changed().await?;
+
+    while let Some(signal) = active_changed.next().await {
+        let active = signal.args()?.active;
+        tracing::debug!("Screen saver active: {active}");
+        let result = if active {
+            connection
+                .set_led_color_override(None, &Color { r: 0, g: 0, b: 0 })
+                .await
+        } else {
+            connection.clear_led_color_override(None).await
+        };
+        if let Err(err) = result {
+            tracing::warn!("Failed to update the LEDs: `{err}`");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,90 @@
+//! Companion service that runs in the user session and talks to tailord.
+//!
+//! Features that need to see into the session, like the screen saver
+//! state or the running applications of a user, live here so the
+//! system daemon never has to access user sessions.
+
+mod accent;
+mod apps;
+mod audio;
+mod config;
+mod idle;
+mod notifications;
+mod shortcuts;
+
+use std::future::Future;
+
+use tailor_client::TailorConnection;
+
+use crate::config::SessionConfig;
+
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    if std::env::var_os("RUST_LOG").is_none() {
+        std::env::set_var("RUST_LOG", "info");
+    }
+
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .without_time()
+        .init();
+
+    let config = SessionConfig::load();
+    let connection = TailorConnection::new().await?;
+
+    let mut tasks = Vec::new();
+    if config.notifications {
+        tasks.push(spawn(
+            "notifications",
+            notifications::run(connection.clone()),
+        ));
+    }
+    if config.idle_dimming {
+        tasks.push(spawn("idle dimming", idle::run(connection.clone())));
+    }
+    if !config.app_rules.is_empty() {
+        tasks.push(spawn(
+            "application rules",
+            apps::run(connection.clone(), config.app_rules),
+        ));
+    }
+
+    if let Some(audio_lighting) = config.audio_lighting {
+        tasks.push(spawn(
+            "audio lighting",
+            audio::run(connection.clone(), audio_lighting),
+        ));
+    }
+
+    if config.accent_lighting {
+        tasks.push(spawn("accent lighting", accent::run(connection.clone())));
+    }
+
+    if config.global_shortcuts {
+        tasks.push(spawn(
+            "global shortcuts",
+            shortcuts::run(connection.clone()),
+        ));
+    }
+
+    if tasks.is_empty() {
+        tracing::info!("All features are disabled");
+    }
+    for task in tasks {
+        task.await?;
+    }
+    Ok(())
+}
+
+/// Run a feature in the background and log why it stopped.
+fn spawn(
+    name: &'static str,
+    feature: impl Future<Output = eyre::Result<()>> + Send + 'static,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        if let Err(err) = feature.await {
+            tracing::error!("Stopped {name}: `{err}`");
+        }
+    })
+}
@@ -0,0 +1,65 @@
+use std::{os::unix::fs::MetadataExt, pin::pin};
+
+use futures_util::StreamExt;
+use notify_rust::{Notification, Urgency};
+use tailor_client::TailorConnection;
+
+/// Show desktop notifications for the warnings of the daemon
+/// and for profiles that were activated by other users.
+pub async fn run(connection: TailorConnection<'static>) -> eyre::Result<()> {
+    let uid = std::fs::metadata("/proc/self")?.uid();
+    let mut critical_temperature = pin!(connection.receive_critical_temperature().await?);
+    let mut thermal_throttling = pin!(connection.receive_thermal_throttling().await?);
+    let mut profile_changed = pin!(connection.receive_active_global_profile_changed().await?);
+
+    loop {
+        tokio::select! {
+            Some(temp) = critical_temperature.next() => {
+                notify(
+                    "Critical temperature",
+                    &format!("The temperature reached {temp}°C"),
+                    Urgency::Critical,
+                )
+                .await;
+            }
+            Some(active) = thermal_throttling.next() => {
+                if active {
+                    notify(
+                        "Thermal throttling",
+                        "The CPU is throttling although all fans run at full speed",
+                        Urgency::Normal,
+                    )
+                    .await;
+                }
+            }
+            Some((name, owner)) = profile_changed.next() => {
+                if let Some(owner) = owner.filter(|owner| owner.uid != uid) {
+                    notify(
+                        "Profile changed",
+                        &format!("{owner} activated the profile `{name}`"),
+                        Urgency::Low,
+                    )
+                    .await;
+                }
+            }
+            else => return Ok(()),
+        }
+    }
+}
+
+pub async fn notify(summary: &str, body: &str, urgency: Urgency) {
+    let result = Notification::new()
+        .summary(summary)
+        .body(body)
+        .appname("tailor")
+        .icon(match urgency {
+            Urgency::Low => "dialog-information",
+            Urgency::Normal | Urgency::Critical => "dialog-warning",
+        })
+        .urgency(urgency)
+        .show_async()
+        .await;
+    if let Err(err) = result {
+        tracing::warn!("Failed to show notification: `{err}`");
+    }
+}
@@ -0,0 +1,212 @@
+//! Global keyboard shortcuts, registered with the GlobalShortcuts portal
+//! of xdg-desktop-portal. The desktop lets the user pick the keys,
+//! the preferred triggers are only a suggestion.
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use eyre::{bail, eyre};
+use futures_util::StreamExt;
+use notify_rust::Urgency;
+use tailor_client::TailorConnection;
+use zbus::{
+    dbus_proxy,
+    zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value},
+    Connection,
+};
+
+use crate::notifications::notify;
+
+const CYCLE_PROFILE: &str = "cycle-profile";
+const TOGGLE_MAX_FANS: &str = "toggle-max-fans";
+
+/// Ids, descriptions and preferred triggers of the shortcuts.
+const SHORTCUTS: &[(&str, &str, &str)] = &[
+    (CYCLE_PROFILE, "Switch to the next profile", "CTRL+ALT+p"),
+    (TOGGLE_MAX_FANS, "Run all fans at full speed", "CTRL+ALT+f"),
+];
+
+#[dbus_proxy(
+    interface = "org.freedesktop.portal.GlobalShortcuts",
+    default_service = "org.freedesktop.portal.Desktop",
+    default_path = "/org/freedesktop/portal/desktop"
+)]
+trait GlobalShortcuts {
+    fn create_session(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<OwnedObjectPath>;
+
+    fn bind_shortcuts(
+        &self,
+        session_handle: &ObjectPath<'_>,
+        shortcuts: &[(&str, HashMap<&str, Value<'_>>)],
+        parent_window: &str,
+        options: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<OwnedObjectPath>;
+
+    #[dbus_proxy(signal)]
+    fn activated(
+        &self,
+        session_handle: ObjectPath<'_>,
+        shortcut_id: &str,
+        timestamp: u64,
+        options: HashMap<String, OwnedValue>,
+    ) -> zbus::Result<()>;
+}
+
+#[dbus_proxy(
+    interface = "org.freedesktop.portal.Request",
+    default_service = "org.freedesktop.portal.Desktop"
+)]
+trait Request {
+    #[dbus_proxy(signal)]
+    fn response(&self, response: u32, results: HashMap<String, OwnedValue>) -> zbus::Result<()>;
+}
+
+/// Register the shortcuts and handle them until the portal goes away.
+pub async fn run(connection: TailorConnection<'static>) -> eyre::Result<()> {
+    let session = Connection::session().await?;
+    let portal = GlobalShortcutsProxy::new(&session).await?;
+
+    let token = handle_token();
+    let results = request(&session, &token, async {
+        let options = HashMap::from([
+            ("handle_token", Value::from(token.as_str())),
+            ("session_handle_token", Value::from(handle_token())),
+        ]);
+        portal.create_session(options).await
+    })
+    .await?;
+    let session_handle = results
+        .get("session_handle")
+        .and_then(|value| match &**value {
+            Value::Str(path) => ObjectPath::try_from(path.as_str()).ok(),
+            Value::ObjectPath(path) => Some(path.clone()),
+            _ => None,
+        })
+        .map(OwnedObjectPath::from)
+        .ok_or_else(|| eyre!("The portal didn't return a session"))?;
+
+    let mut activated = portal.receive_activated().await?;
+
+    let shortcuts: Vec<_> = SHORTCUTS
+        .iter()
+        .map(|(id, description, trigger)| {
+            let options = HashMap::from([
+                ("description", Value::from(*description)),
+                ("preferred_trigger", Value::from(*trigger)),
+            ]);
+            (*id, options)
+        })
+        .collect();
+    let token = handle_token();
+    request(&session, &token, async {
+        let options = HashMap::from([("handle_token", Value::from(token.as_str()))]);
+        portal
+            .bind_shortcuts(&session_handle, &shortcuts, "", options)
+            .await
+    })
+    .await?;
+    tracing::info!("Registered global shortcuts");
+
+    while let Some(signal) = activated.next().await {
+        let args = signal.args()?;
+        if args.session_handle != *session_handle {
+            continue;
+        }
+        let result = match args.shortcut_id {
+            CYCLE_PROFILE => cycle_profile(&connection).await,
+            TOGGLE_MAX_FANS => toggle_max_fans(&connection).await,
+            id => {
+                tracing::debug!("Unknown shortcut `{id}`");
+                Ok(())
+            }
+        };
+        if let Err(err) = result {
+            tracing::warn!("Failed to handle shortcut `{}`: `{err}`", args.shortcut_id);
+        }
+    }
+    Ok(())
+}
+
+/// Activate the profile after the active one, in the order of the profile list.
+async fn cycle_profile(connection: &TailorConnection<'static>) -> eyre::Result<()> {
+    let active = connection.get_active_global_profile_name().await?;
+    let profiles = connection.list_global_profiles().await?;
+    let next = profiles
+        .iter()
+        .position(|name| name == &active)
+        .map_or(0, |idx| (idx + 1) % profiles.len());
+    let Some(next) = profiles.get(next) else {
+        return Ok(());
+    };
+    connection.set_active_global_profile_name(next).await?;
+    connection.reload().await?;
+    notify(
+        "Profile changed",
+        &format!("Activated `{next}`"),
+        Urgency::Low,
+    )
+    .await;
+    Ok(())
+}
+
+/// Pin all fans at full speed, or return them to the profile
+/// if they are already pinned at full speed.
+async fn toggle_max_fans(connection: &TailorConnection<'static>) -> eyre::Result<()> {
+    let fans = connection.get_fan_status().await?;
+    if fans.iter().all(|fan| fan.speed_override == Some(100)) {
+        for fan in &fans {
+            connection.clear_fan_speed_override(fan.fan_idx).await?;
+        }
+        notify("Fans", "The fans follow the profile again", Urgency::Low).await;
+    } else {
+        for fan in &fans {
+            connection
+                .set_fan_speed_override(fan.fan_idx, 100, None)
+                .await?;
+        }
+        notify("Fans", "All fans run at full speed", Urgency::Low).await;
+    }
+    Ok(())
+}
+
+/// Call a portal method and wait for the response of its request.
+/// The request is subscribed before the call, so the response can't be missed.
+async fn request(
+    session: &Connection,
+    token: &str,
+    call: impl Future<Output = zbus::Result<OwnedObjectPath>>,
+) -> eyre::Result<HashMap<String, OwnedValue>> {
+    let sender = session
+        .unique_name()
+        .ok_or_else(|| eyre!("The session bus didn't assign a name"))?
+        .trim_start_matches(':')
+        .replace('.', "_");
+    let request = RequestProxy::builder(session)
+        .path(format!(
+            "/org/freedesktop/portal/desktop/request/{sender}/{token}"
+        ))?
+        .build()
+        .await?;
+    let mut responses = request.receive_response().await?;
+    call.await?;
+
+    let response = responses
+        .next()
+        .await
+        .ok_or_else(|| eyre!("The portal closed the request"))?;
+    let args = response.args()?;
+    match args.response {
+        0 => Ok(args.results),
+        1 => bail!("The request was cancelled"),
+        _ => bail!("The request failed"),
+    }
+}
+
+/// A token that is unique within this process.
+fn handle_token() -> String {
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+    format!("tailor_{}", COUNTER.fetch_add(1, Ordering::Relaxed))
+}
@@ -0,0 +1,56 @@
+//! The PCIe ASPM policy of the active profile.
+
+use std::{io, sync::Mutex};
+
+use tailor_api::{AspmPolicy, HardwareWrite};
+use tuxedo_sysfs::pcie_aspm::PcieAspm;
+
+/// The policy before a profile changed it, so it can be
+/// restored when switching to a profile without policy.
+static ORIGINAL: Mutex<Option<AspmPolicy>> = Mutex::new(None);
+
+/// Set the ASPM policy of the kernel. `None` restores the previous policy.
+pub fn apply_policy(policy: Option<AspmPolicy>) -> Result<(), io::Error> {
+    let mut original = ORIGINAL.lock().unwrap();
+    let Some(aspm) = PcieAspm::new() else {
+        if policy.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "The kernel doesn't support PCIe ASPM",
+            ));
+        }
+        return Ok(());
+    };
+
+    match policy {
+        Some(policy) => {
+            if original.is_none() {
+                *original = Some(aspm.get()?);
+            }
+            aspm.set(policy)?;
+            tracing::info!("Set PCIe ASPM policy to `{policy}`");
+        }
+        None => {
+            if let Some(policy) = original.take() {
+                aspm.set(policy)?;
+                tracing::info!("Restored PCIe ASPM policy `{policy}`");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The write of [`apply_policy`] if it changes the policy, without applying it.
+pub fn preview_policy(policy: Option<AspmPolicy>) -> Result<Option<HardwareWrite>, io::Error> {
+    let original = ORIGINAL.lock().unwrap();
+    let Some(aspm) = PcieAspm::new() else {
+        if policy.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "The kernel doesn't support PCIe ASPM",
+            ));
+        }
+        return Ok(None);
+    };
+    Ok(policy.or(*original).and_then(|policy| aspm.preview(policy)))
+}
@@ -0,0 +1,141 @@
+//! The audit log records every value that tailord writes to the hardware
+//! together with the reason, so thermal anomalies can be traced back to
+//! what the daemon did. It's disabled unless `audit_log` is set in the
+//! daemon configuration.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use once_cell::sync::OnceCell;
+use tailor_api::HardwareWrite;
+use tokio::sync::mpsc;
+
+use crate::{config, events};
+
+const AUDIT_LOG: &str = "audit.jsonl";
+/// The previous log file is kept after rotation.
+const ROTATED_AUDIT_LOG: &str = "audit.jsonl.1";
+
+static AUDIT_SENDER: OnceCell<mpsc::UnboundedSender<Entry>> = OnceCell::new();
+
+/// Why tailord wrote a value.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Cause {
+    /// Applying the active profile.
+    Profile {
+        name: String,
+        /// The rule that activated the profile, if it wasn't a request over D-Bus.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        rule: Option<&'static str>,
+    },
+    /// Restoring the previous profile after a profile couldn't be applied.
+    Rollback { name: String },
+    /// The fan curve reacting to the temperature.
+    FanCurve { temp: u8 },
+    /// A request over D-Bus that bypasses the profile.
+    Override,
+    /// An automatic action, e.g. of the critical temperature protection.
+    Rule(&'static str),
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Entry {
+    timestamp: u64,
+    #[serde(flatten)]
+    write: HardwareWrite,
+    cause: Cause,
+}
+
+/// Whether writes are recorded. Lets callers skip
+/// collecting the old values if they aren't needed.
+pub fn enabled() -> bool {
+    AUDIT_SENDER.get().is_some()
+}
+
+/// Add a write to the audit log.
+/// Writes are dropped if the audit log is disabled.
+pub fn record(write: HardwareWrite, cause: Cause) {
+    if let Some(sender) = AUDIT_SENDER.get() {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        sender
+            .send(Entry {
+                timestamp,
+                write,
+                cause,
+            })
+            .ok();
+    }
+}
+
+pub fn record_fan_speed(fan_idx: u8, old: Option<u8>, new: u8, cause: Cause) {
+    record(
+        HardwareWrite {
+            device: format!("Fan {}", fan_idx + 1),
+            attribute: "speed".to_owned(),
+            old: old.map(|old| format!("{old}%")),
+            new: format!("{new}%"),
+        },
+        cause,
+    );
+}
+
+/// Writes recorded entries to the audit log.
+pub struct AuditLogWriter {
+    receiver: mpsc::UnboundedReceiver<Entry>,
+}
+
+impl AuditLogWriter {
+    /// Enable the audit log. Writes that are recorded
+    /// before this is called will be dropped.
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        if AUDIT_SENDER.set(sender).is_err() {
+            tracing::warn!("The audit log was already set up");
+        }
+        Self { receiver }
+    }
+
+    pub async fn run(mut self) {
+        while let Some(entry) = self.receiver.recv().await {
+            if let Err(err) = events::append_line(AUDIT_LOG, ROTATED_AUDIT_LOG, &entry).await {
+                tracing::warn!(
+                    "Failed to write the audit log to `{:?}`: `{err}`",
+                    config::state_dir()
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tailor_api::HardwareWrite;
+
+    use super::{Cause, Entry};
+
+    #[test]
+    fn entry_format() {
+        let entry = Entry {
+            timestamp: 3,
+            write: HardwareWrite {
+                device: "Fan 1".to_owned(),
+                attribute: "speed".to_owned(),
+                old: None,
+                new: "50%".to_owned(),
+            },
+            cause: Cause::Profile {
+                name: "silent".to_owned(),
+                rule: Some("idle"),
+            },
+        };
+        assert_eq!(
+            serde_json::to_string(&entry).unwrap(),
+            r#"{"timestamp":3,"device":"Fan 1","attribute":"speed","old":null,"new":"50%","cause":{"profile":{"name":"silent","rule":"idle"}}}"#
+        );
+        let override_cause = serde_json::to_string(&Cause::Override).unwrap();
+        assert_eq!(override_cause, r#""override""#);
+    }
+}
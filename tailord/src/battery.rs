@@ -0,0 +1,100 @@
+//! Keeps the battery conservation mode across restarts,
+//! in case the firmware resets the charging profile,
+//! and records the health of the battery over time.
+
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use tailor_api::BatteryHealthSample;
+use tuxedo_sysfs::{charging::BatteryConservation, power_supply::Battery};
+
+use crate::config;
+
+const CONSERVATION_STATE: &str = "battery-conservation";
+const HEALTH_HISTORY: &str = "battery-health.jsonl";
+/// The health is recorded once a day, checking hourly
+/// catches up on days on which the device was suspended.
+const HEALTH_SAMPLE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+const HEALTH_POLL_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+fn state_path() -> PathBuf {
+    config::state_dir().join(CONSERVATION_STATE)
+}
+
+fn health_history_path() -> PathBuf {
+    config::state_dir().join(HEALTH_HISTORY)
+}
+
+/// Apply the mode that was set last, if any.
+pub fn restore(conservation: &BatteryConservation) {
+    let Ok(state) = std::fs::read_to_string(state_path()) else {
+        return;
+    };
+    match state.trim().parse::<bool>() {
+        Ok(enabled) => {
+            if let Err(err) = conservation.set(enabled) {
+                tracing::error!("Failed to restore the battery conservation mode: `{err}`");
+            }
+        }
+        Err(err) => tracing::warn!("Invalid battery conservation state `{state}`: `{err}`"),
+    }
+}
+
+pub fn store(enabled: bool) {
+    let result = std::fs::create_dir_all(config::state_dir())
+        .and_then(|()| std::fs::write(state_path(), enabled.to_string()));
+    if let Err(err) = result {
+        tracing::warn!("Failed to store the battery conservation mode: `{err}`");
+    }
+}
+
+pub async fn record_health(battery: Battery) {
+    loop {
+        if let Err(err) = record_health_sample(&battery) {
+            tracing::warn!("Failed to record the battery health: `{err}`");
+        }
+        tokio::time::sleep(HEALTH_POLL_INTERVAL).await;
+    }
+}
+
+fn record_health_sample(battery: &Battery) -> io::Result<()> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let recent = health_history()?.last().is_some_and(|sample| {
+        timestamp.saturating_sub(sample.timestamp) < HEALTH_SAMPLE_INTERVAL.as_secs()
+    });
+    if recent {
+        return Ok(());
+    }
+
+    let sample = BatteryHealthSample {
+        timestamp,
+        health: battery.health()?,
+    };
+    let mut line = serde_json::to_string(&sample)?;
+    line.push('\n');
+    std::fs::create_dir_all(config::state_dir())?;
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(health_history_path())?
+        .write_all(line.as_bytes())
+}
+
+/// The recorded health of the battery, oldest first.
+pub fn health_history() -> io::Result<Vec<BatteryHealthSample>> {
+    let history = match std::fs::read_to_string(health_history_path()) {
+        Ok(history) => history,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    Ok(history
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
@@ -0,0 +1,296 @@
+//! Guides through a full charge cycle, so the firmware can learn
+//! the actual capacity of the battery: charge to full, discharge
+//! to a low charge and charge to full again.
+//!
+//! Neither tuxedo_io nor tuxedo_keyboard offer a calibration command,
+//! so tailord runs the cycle itself. Charge thresholds and the conservation
+//! mode are lifted for the cycle, so the battery can charge to full. If the
+//! firmware can discharge while the charger is plugged in (the
+//! `charge_behaviour` of the kernel), tailord uses it for the discharge phase.
+//! Otherwise the user has to plug in and unplug the charger, each phase waits
+//! until the battery reports the matching state.
+
+use std::time::Duration;
+
+use tailor_api::{BatteryStatus, CalibrationPhase, CalibrationStatus};
+use tokio::sync::{mpsc, watch};
+use tuxedo_sysfs::{
+    charging::BatteryConservation,
+    power_supply::{Battery, ChargeBehaviour, ChargeThresholds},
+};
+use zbus::Connection;
+
+use crate::{
+    config::BatteryCalibrationConfig,
+    dbus::BatteryInterface,
+    profile_switch::{self, PreviousProfile},
+    DBUS_PATH,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+/// The reason of profile switches in the audit log.
+const RULE: &str = "battery calibration";
+/// Thresholds that let the battery charge to full. The start
+/// threshold has to stay below the end threshold.
+const LIFTED_THRESHOLDS: ChargeThresholds = ChargeThresholds {
+    start: Some(99),
+    end: Some(100),
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationCommand {
+    Start,
+    Cancel,
+}
+
+pub struct CalibrationHandle {
+    pub command_sender: mpsc::Sender<CalibrationCommand>,
+    pub status_receiver: watch::Receiver<CalibrationStatus>,
+}
+
+pub struct CalibrationRuntime {
+    battery: Battery,
+    conservation: Option<BatteryConservation>,
+    config: BatteryCalibrationConfig,
+    command_receiver: mpsc::Receiver<CalibrationCommand>,
+    status_sender: watch::Sender<CalibrationStatus>,
+}
+
+impl CalibrationRuntime {
+    pub fn new(
+        battery: Battery,
+        conservation: Option<BatteryConservation>,
+        config: BatteryCalibrationConfig,
+    ) -> (Self, CalibrationHandle) {
+        let (command_sender, command_receiver) = mpsc::channel(1);
+        let (status_sender, status_receiver) = watch::channel(CalibrationStatus {
+            discharge_to: config.discharge_to,
+            ..Default::default()
+        });
+        (
+            Self {
+                battery,
+                conservation,
+                config,
+                command_receiver,
+                status_sender,
+            },
+            CalibrationHandle {
+                command_sender,
+                status_receiver,
+            },
+        )
+    }
+
+    pub async fn run(mut self, connection: Connection) {
+        loop {
+            match self.command_receiver.recv().await {
+                Some(CalibrationCommand::Start) => self.calibrate(&connection).await,
+                Some(CalibrationCommand::Cancel) => {}
+                None => return,
+            }
+        }
+    }
+
+    async fn calibrate(&mut self, connection: &Connection) {
+        tracing::info!("Starting battery calibration");
+        // The conservation mode would stop charging before the battery is full.
+        let conservation = self
+            .conservation
+            .as_ref()
+            .filter(|conservation| conservation.get().unwrap_or_default());
+        if let Some(conservation) = conservation {
+            if let Err(err) = conservation.set(false) {
+                tracing::error!("Failed to disable the battery conservation mode: `{err}`");
+            }
+        }
+        // So do charge thresholds.
+        let thresholds = self.battery.charge_thresholds();
+        let lift_thresholds = thresholds.start.is_some_and(|start| start < 99)
+            || thresholds.end.is_some_and(|end| end < 100);
+        if lift_thresholds {
+            let lifted = ChargeThresholds {
+                start: thresholds.start.and(LIFTED_THRESHOLDS.start),
+                end: thresholds.end.and(LIFTED_THRESHOLDS.end),
+            };
+            if let Err(err) = self.battery.set_charge_thresholds(lifted) {
+                tracing::error!("Failed to lift the charge thresholds: `{err}`");
+            }
+        }
+        let force_discharge = self
+            .battery
+            .supports_charge_behaviour(ChargeBehaviour::ForceDischarge);
+
+        let mut phase = CalibrationPhase::Charging;
+        let mut previous_profile = None;
+        loop {
+            let status = match self.read_status(phase) {
+                Ok(status) => status,
+                Err(err) => {
+                    tracing::error!("Failed to read the battery: `{err}`");
+                    phase = CalibrationPhase::Failed;
+                    self.publish(connection, self.status(phase)).await;
+                    break;
+                }
+            };
+            phase = status.phase;
+            self.publish(connection, status).await;
+
+            if phase == CalibrationPhase::Discharging && previous_profile.is_none() {
+                if force_discharge {
+                    self.set_charge_behaviour(ChargeBehaviour::ForceDischarge);
+                }
+                previous_profile = self.activate_profile(connection).await;
+            } else if phase != CalibrationPhase::Discharging {
+                if force_discharge && phase == CalibrationPhase::Recharging {
+                    self.set_charge_behaviour(ChargeBehaviour::Auto);
+                }
+                self.restore_profile(connection, previous_profile.take())
+                    .await;
+            }
+            if phase == CalibrationPhase::Finished {
+                break;
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                command = self.command_receiver.recv() => {
+                    if command != Some(CalibrationCommand::Start) {
+                        phase = CalibrationPhase::Cancelled;
+                        self.publish(connection, self.status(phase)).await;
+                        break;
+                    }
+                }
+            }
+        }
+
+        self.restore_profile(connection, previous_profile.take())
+            .await;
+        if force_discharge {
+            self.set_charge_behaviour(ChargeBehaviour::Auto);
+        }
+        if lift_thresholds {
+            if let Err(err) = self.battery.set_charge_thresholds(thresholds) {
+                tracing::error!("Failed to restore the charge thresholds: `{err}`");
+            }
+        }
+        if let Some(conservation) = conservation {
+            if let Err(err) = conservation.set(true) {
+                tracing::error!("Failed to restore the battery conservation mode: `{err}`");
+            }
+        }
+        tracing::info!("Battery calibration stopped: {phase:?}");
+    }
+
+    fn set_charge_behaviour(&self, behaviour: ChargeBehaviour) {
+        if let Err(err) = self.battery.set_charge_behaviour(behaviour) {
+            tracing::error!("Failed to set the charge behaviour to {behaviour:?}: `{err}`");
+        }
+    }
+
+    /// The current state of the battery, advanced to the next phase if necessary.
+    fn read_status(&self, phase: CalibrationPhase) -> std::io::Result<CalibrationStatus> {
+        let capacity = self.battery.capacity()?;
+        let status = self.battery.status()?;
+        Ok(CalibrationStatus {
+            phase: next_phase(phase, capacity, status, self.config.discharge_to),
+            capacity,
+            plugged_in: status.is_plugged_in(),
+            discharge_to: self.config.discharge_to,
+        })
+    }
+
+    /// The last status with another phase.
+    fn status(&self, phase: CalibrationPhase) -> CalibrationStatus {
+        CalibrationStatus {
+            phase,
+            ..self.status_sender.borrow().clone()
+        }
+    }
+
+    async fn publish(&self, connection: &Connection, status: CalibrationStatus) {
+        let changed = self.status_sender.send_if_modified(|current| {
+            let changed = *current != status;
+            *current = status.clone();
+            changed
+        });
+        if !changed {
+            return;
+        }
+        let result = async {
+            let data = serde_json::to_string(&status).map_err(|err| err.to_string())?;
+            let iface = connection
+                .object_server()
+                .interface::<_, BatteryInterface>(DBUS_PATH)
+                .await
+                .map_err(|err| err.to_string())?;
+            BatteryInterface::calibration_changed(iface.signal_context(), &data)
+                .await
+                .map_err(|err| err.to_string())
+        }
+        .await;
+        if let Err(err) = result {
+            tracing::warn!("Failed to report the calibration progress: `{err}`");
+        }
+    }
+
+    async fn activate_profile(&self, connection: &Connection) -> Option<PreviousProfile> {
+        let profile = self.config.profile.as_ref()?;
+        match profile_switch::activate(connection, profile, RULE).await {
+            Ok(previous) => previous,
+            Err(err) => {
+                tracing::error!("Failed to activate the calibration profile: `{err}`");
+                None
+            }
+        }
+    }
+
+    async fn restore_profile(&self, connection: &Connection, previous: Option<PreviousProfile>) {
+        let Some(profile) = &self.config.profile else {
+            return;
+        };
+        if let Err(err) = profile_switch::restore(connection, profile, previous, RULE).await {
+            tracing::error!("Failed to restore the profile after discharging: `{err}`");
+        }
+    }
+}
+
+/// Some batteries report 100% long before the firmware stops charging them.
+fn next_phase(
+    phase: CalibrationPhase,
+    capacity: u8,
+    status: BatteryStatus,
+    discharge_to: u8,
+) -> CalibrationPhase {
+    let full =
+        status == BatteryStatus::Full || (capacity >= 100 && status == BatteryStatus::NotCharging);
+    match phase {
+        CalibrationPhase::Charging if full => CalibrationPhase::Discharging,
+        CalibrationPhase::Discharging
+            if status == BatteryStatus::Discharging && capacity <= discharge_to =>
+        {
+            CalibrationPhase::Recharging
+        }
+        CalibrationPhase::Recharging if full => CalibrationPhase::Finished,
+        phase => phase,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tailor_api::{BatteryStatus, CalibrationPhase};
+
+    use super::next_phase;
+
+    #[test]
+    fn phases() {
+        let next = |phase, capacity, status| next_phase(phase, capacity, status, 5);
+        use CalibrationPhase::*;
+
+        assert_eq!(next(Charging, 100, BatteryStatus::Charging), Charging);
+        assert_eq!(next(Charging, 98, BatteryStatus::Full), Discharging);
+        assert_eq!(next(Discharging, 4, BatteryStatus::Charging), Discharging);
+        assert_eq!(next(Discharging, 5, BatteryStatus::Discharging), Recharging);
+        assert_eq!(next(Recharging, 100, BatteryStatus::NotCharging), Finished);
+    }
+}
@@ -0,0 +1,245 @@
+//! Verifies the configuration without starting the daemon,
+//! for example in post-install scripts of packages.
+//!
+//! Usage: `tailord [--config-dir <dir>] [--state-dir <dir>] --check`
+//!
+//! Every problem is printed and the exit status is `1` if there was any.
+
+use std::path::Path;
+
+use tailor_api::{ColorProfile, ConfigFormat, ProfileInfo, TailorError};
+use zbus::{fdo, DBusError};
+
+use crate::{
+    config::DaemonConfig,
+    fancontrol::profile::FanProfile,
+    instance,
+    profiles::{ACTIVE_PROFILE_PATH, FAN_DIR, KEYBOARD_DIR, PROFILE_DIR},
+    util,
+};
+
+const CHECK_FLAG: &str = "--check";
+
+/// The directories and files that are checked.
+struct ProfilePaths<'a> {
+    fans: &'a str,
+    keyboards: &'a str,
+    profiles: &'a str,
+    active_profile: &'a str,
+}
+
+/// Check the configuration if the daemon was started with `--check`.
+/// Returns the exit status, or `None` if the daemon should start normally.
+pub fn run_from_args(args: &[String]) -> Option<i32> {
+    if !args.iter().any(|arg| arg == CHECK_FLAG) {
+        return None;
+    }
+
+    let problems = check();
+    if problems.is_empty() {
+        println!("The configuration is valid");
+        print_running();
+        Some(0)
+    } else {
+        for problem in &problems {
+            eprintln!("{problem}");
+        }
+        eprintln!("Found {} problem(s) in the configuration", problems.len());
+        Some(1)
+    }
+}
+
+/// A running instance needs a restart to use the configuration.
+fn print_running() {
+    if let Some(pid) = instance::running() {
+        println!("tailord is already running with pid {pid}, restart it to apply changes");
+    }
+}
+
+/// All problems of the system configuration and profiles.
+fn check() -> Vec<String> {
+    let mut problems = Vec::new();
+
+    if let Err(err) = DaemonConfig::try_load() {
+        problems.push(err);
+    }
+    check_profiles(
+        &ProfilePaths {
+            fans: &FAN_DIR,
+            keyboards: &KEYBOARD_DIR,
+            profiles: &PROFILE_DIR,
+            active_profile: &ACTIVE_PROFILE_PATH,
+        },
+        &mut problems,
+    );
+    problems
+}
+
+fn check_profiles(paths: &ProfilePaths, problems: &mut Vec<String>) {
+    for (name, path) in profile_files(paths.fans, problems) {
+        if let Err(err) = FanProfile::load_config(&path) {
+            problems.push(format!("Invalid fan profile `{name}`: {}", describe(&err)));
+        }
+    }
+    for (name, path) in profile_files(paths.keyboards, problems) {
+        if let Err(err) = util::read_profile_file::<ColorProfile>(&path) {
+            problems.push(format!(
+                "Invalid keyboard profile `{name}`: {}",
+                describe(&err)
+            ));
+        }
+    }
+    for (name, path) in profile_files(paths.profiles, problems) {
+        match util::read_profile_file::<ProfileInfo>(&path) {
+            Ok(info) => check_references(paths, &name, &info, problems),
+            Err(err) => problems.push(format!("Invalid profile `{name}`: {}", describe(&err))),
+        }
+    }
+
+    // The active profile is only created on the first start.
+    let active_profile = Path::new(paths.active_profile);
+    if active_profile.is_symlink() && !active_profile.exists() {
+        problems.push(format!(
+            "The active profile at `{}` points to a missing profile",
+            paths.active_profile
+        ));
+    }
+}
+
+/// Make sure that all fan and keyboard profiles of a profile exist.
+fn check_references(
+    paths: &ProfilePaths,
+    name: &str,
+    info: &ProfileInfo,
+    problems: &mut Vec<String>,
+) {
+    let fans = info.fans.iter().map(|fan| (paths.fans, "fan", fan));
+    let leds = info
+        .leds
+        .iter()
+        .map(|led| (paths.keyboards, "keyboard", &led.profile));
+    for (dir, kind, reference) in fans.chain(leds) {
+        let exists =
+            util::find_profile(dir, reference).is_ok_and(|(path, _)| Path::new(&path).exists());
+        if !exists {
+            problems.push(format!(
+                "Profile `{name}` uses the missing {kind} profile `{reference}`"
+            ));
+        }
+    }
+}
+
+/// The message of an error without the D-Bus details.
+fn describe(err: &fdo::Error) -> String {
+    let description = err.description().unwrap_or_default();
+    TailorError::from_json(description)
+        .map(|err| err.message().to_owned())
+        .unwrap_or_else(|| description.to_owned())
+}
+
+/// The names and paths of the profiles in a directory.
+fn profile_files(dir: &str, problems: &mut Vec<String>) -> Vec<(String, String)> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        // The directories are only created on the first start.
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(err) => {
+            problems.push(format!("Failed to read `{dir}`: `{err}`"));
+            return Vec::new();
+        }
+    };
+
+    let mut files: Vec<_> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && ConfigFormat::from_path(path).is_some())
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.to_owned();
+            Some((name, path.to_string_lossy().into_owned()))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod test {
+    use tailor_api::{ColorProfile, FanProfilePoint, LedProfile, ProfileInfo};
+
+    use super::{check_profiles, ProfilePaths};
+
+    #[test]
+    fn profiles() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let path = |name: &str| dir.join(name).to_str().unwrap().to_owned();
+        let (fans, keyboards, profiles) = (path("fan"), path("keyboard"), path("profiles"));
+        let active_profile = path("active_profile");
+        let paths = ProfilePaths {
+            fans: &fans,
+            keyboards: &keyboards,
+            profiles: &profiles,
+            active_profile: &active_profile,
+        };
+        let write = |dir: &str, name: &str, data: String| {
+            std::fs::create_dir_all(dir).unwrap();
+            std::fs::write(format!("{dir}/{name}"), data).unwrap();
+        };
+
+        // Missing directories aren't problems.
+        let mut problems = Vec::new();
+        check_profiles(&paths, &mut problems);
+        assert!(problems.is_empty(), "{problems:?}");
+
+        let points = vec![
+            FanProfilePoint { temp: 30, fan: 20 },
+            FanProfilePoint { temp: 90, fan: 100 },
+        ];
+        write(&fans, "quiet.json", serde_json::to_string(&points).unwrap());
+        write(
+            &keyboards,
+            "off.json",
+            serde_json::to_string(&ColorProfile::None).unwrap(),
+        );
+        let info = ProfileInfo {
+            fans: vec!["quiet".to_owned()],
+            leds: vec![LedProfile {
+                device_name: "white:kbd".to_owned(),
+                function: "kbd_backlight".to_owned(),
+                profile: "off".to_owned(),
+                mode: Default::default(),
+            }],
+            ..Default::default()
+        };
+        write(
+            &profiles,
+            "default.json",
+            serde_json::to_string(&info).unwrap(),
+        );
+        std::os::unix::fs::symlink(format!("{profiles}/default.json"), &active_profile).unwrap();
+        let mut problems = Vec::new();
+        check_profiles(&paths, &mut problems);
+        assert!(problems.is_empty(), "{problems:?}");
+
+        write(&fans, "broken.json", "[{\"temp\": 30}]".to_owned());
+        let missing = ProfileInfo {
+            fans: vec!["loud".to_owned()],
+            ..Default::default()
+        };
+        write(
+            &profiles,
+            "missing.json",
+            serde_json::to_string(&missing).unwrap(),
+        );
+        std::fs::remove_file(format!("{profiles}/default.json")).unwrap();
+        let mut problems = Vec::new();
+        check_profiles(&paths, &mut problems);
+        assert_eq!(problems.len(), 3, "{problems:?}");
+        assert!(problems[0].starts_with("Invalid fan profile `broken`"));
+        assert_eq!(
+            problems[1],
+            "Profile `missing` uses the missing fan profile `loud`"
+        );
+        assert!(problems[2].contains("points to a missing profile"));
+    }
+}
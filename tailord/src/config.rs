@@ -0,0 +1,556 @@
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use once_cell::sync::{Lazy, OnceCell};
+use tailor_api::{Color, ConfigFormat, LedDeviceInfo};
+
+pub const DEFAULT_CONFIG_DIR: &str = "/etc/tailord";
+/// Environment variable that overrides the configuration directory.
+pub const CONFIG_DIR_ENV: &str = "TAILORD_CONFIG_DIR";
+const CONFIG_DIR_FLAG: &str = "--config-dir";
+
+pub const DEFAULT_STATE_DIR: &str = "/var/lib/tailord";
+/// Environment variable that overrides the state directory.
+pub const STATE_DIR_ENV: &str = "TAILORD_STATE_DIR";
+const STATE_DIR_FLAG: &str = "--state-dir";
+
+static CONFIG_DIR: OnceCell<PathBuf> = OnceCell::new();
+static STATE_DIR: OnceCell<PathBuf> = OnceCell::new();
+
+/// Path of the configuration without extension.
+/// It can be stored in any of the supported formats.
+pub static CONFIG_PATH: Lazy<String> = Lazy::new(|| config_path("config"));
+
+/// The values of `--config-dir` and `--state-dir` and the other arguments.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct DirArgs {
+    config_dir: Option<String>,
+    state_dir: Option<String>,
+    remaining: Vec<String>,
+}
+
+fn parse_dir_args(args: impl IntoIterator<Item = String>) -> Result<DirArgs, String> {
+    let mut args = args.into_iter();
+    let mut parsed = DirArgs::default();
+    while let Some(arg) = args.next() {
+        let (flag, value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag, Some(value.to_owned())),
+            None => (arg.as_str(), None),
+        };
+        let dir = match flag {
+            CONFIG_DIR_FLAG => &mut parsed.config_dir,
+            STATE_DIR_FLAG => &mut parsed.state_dir,
+            _ => {
+                parsed.remaining.push(arg);
+                continue;
+            }
+        };
+        // A following flag is most likely not meant as directory.
+        let value = value
+            .or_else(|| args.next().filter(|value| !value.starts_with("--")))
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| format!("`{flag}` requires a directory"))?;
+        *dir = Some(value);
+    }
+    Ok(parsed)
+}
+
+/// Set the configuration and state directories from the `--config-dir`
+/// and `--state-dir` flags and return the remaining arguments.
+/// This must be called before any configuration or state path is used.
+pub fn init_dirs(args: impl IntoIterator<Item = String>) -> Result<Vec<String>, String> {
+    let parsed = parse_dir_args(args)?;
+    if let Some(dir) = parsed.config_dir {
+        CONFIG_DIR.set(PathBuf::from(dir)).ok();
+    }
+    if let Some(dir) = parsed.state_dir {
+        STATE_DIR.set(PathBuf::from(dir)).ok();
+    }
+    tracing::debug!("Using configuration directory {:?}", config_dir());
+    tracing::debug!("Using state directory {:?}", state_dir());
+    Ok(parsed.remaining)
+}
+
+/// The directory that contains the configuration and all profiles.
+/// Set by `--config-dir` or the `TAILORD_CONFIG_DIR`
+/// environment variable and `/etc/tailord` by default.
+pub fn config_dir() -> &'static Path {
+    CONFIG_DIR.get_or_init(|| dir_from_env(CONFIG_DIR_ENV, DEFAULT_CONFIG_DIR))
+}
+
+/// The directory of the event log, the lock file and the state
+/// that tailord restores on startup. Set by `--state-dir` or the
+/// `TAILORD_STATE_DIR` environment variable and `/var/lib/tailord` by default.
+pub fn state_dir() -> &'static Path {
+    STATE_DIR.get_or_init(|| dir_from_env(STATE_DIR_ENV, DEFAULT_STATE_DIR))
+}
+
+fn dir_from_env(env: &str, default: &str) -> PathBuf {
+    std::env::var_os(env)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(default))
+}
+
+/// A path relative to the configuration directory.
+pub fn config_path(path: &str) -> String {
+    config_dir().join(path).to_string_lossy().into_owned()
+}
+
+/// General settings of the daemon that don't belong to a profile.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct DaemonConfig {
+    pub fan_control: FanControlConfig,
+    pub calibration: CalibrationConfig,
+    /// Actions for temperatures above a hard limit.
+    pub critical_temp: Option<CriticalTempConfig>,
+    pub throttle: ThrottleConfig,
+    pub lighting: LightingConfig,
+    pub idle: IdleConfig,
+    pub battery_calibration: BatteryCalibrationConfig,
+    /// Serve the LED devices over the OpenRGB SDK protocol.
+    /// Requires the `openrgb` feature.
+    pub openrgb: Option<OpenRgbConfig>,
+    /// Restrict the daemon to the system calls it needs once it's started.
+    /// Only supported on x86_64 and aarch64, and ignored if a critical
+    /// temperature action runs a command.
+    pub seccomp: bool,
+    /// Restrict file access to the configuration, the state, sysfs and the
+    /// devices tailord manages, if the kernel supports Landlock.
+    pub landlock: bool,
+    /// Exit instead of starting if another program like the
+    /// TUXEDO Control Center daemon controls the fans.
+    pub refuse_on_conflict: bool,
+    /// Record every value written to the hardware and why
+    /// in `/var/lib/tailord/audit.jsonl`.
+    pub audit_log: bool,
+}
+
+impl DaemonConfig {
+    /// Whether a critical temperature action runs a command.
+    pub fn runs_commands(&self) -> bool {
+        self.critical_temp.as_ref().is_some_and(|critical| {
+            critical
+                .actions
+                .iter()
+                .any(|action| matches!(action, CriticalTempAction::Command(_)))
+        })
+    }
+
+    /// Load the configuration and fall back to the
+    /// defaults if it's missing or invalid.
+    pub fn load() -> Self {
+        Self::try_load().unwrap_or_else(|err| {
+            tracing::warn!("{err}");
+            Self::default()
+        })
+    }
+
+    /// Load the configuration, using the defaults if it's missing.
+    pub fn try_load() -> Result<Self, String> {
+        for format in ConfigFormat::ALL {
+            let path = format!("{}.{}", *CONFIG_PATH, format.extension());
+            match std::fs::read_to_string(&path) {
+                Ok(data) => {
+                    return format.deserialize(&data).map_err(|err| {
+                        format!("Failed to parse daemon config at `{path}`: `{err}`")
+                    })
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => {
+                    return Err(format!("Failed to read daemon config at `{path}`: `{err}`"))
+                }
+            }
+        }
+        Ok(Self::default())
+    }
+}
+
+/// Corrections for sensors that read consistently high or low
+/// and LEDs that show colors with a different hue.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct CalibrationConfig {
+    /// Offsets in °C that are added to the temperature of each fan
+    /// before the fan profile is evaluated, ordered by fan index.
+    pub temp_offsets: Vec<i8>,
+    pub leds: Vec<ColorCorrection>,
+}
+
+impl CalibrationConfig {
+    pub fn temp_offset(&self, fan_idx: u8) -> i8 {
+        self.temp_offsets
+            .get(fan_idx as usize)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn color_correction(&self, info: &LedDeviceInfo) -> Option<ColorCorrection> {
+        let device = info.device_id();
+        self.leds
+            .iter()
+            .find(|correction| correction.device == device)
+            .cloned()
+    }
+}
+
+/// Gamma and white point of an LED device, which are
+/// applied to every color before it's written.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ColorCorrection {
+    /// The device as shown by `tailor led list`, e.g. `rgb::kbd_backlight`.
+    pub device: String,
+    /// Each channel is raised to this power, values above 1.0
+    /// darken the colors in between. 1.0 by default.
+    #[serde(default = "ColorCorrection::default_gamma")]
+    pub gamma: f32,
+    /// The factors of the red, green and blue channel from 0.0 to 1.0,
+    /// e.g. `[1.0, 0.8, 0.7]` for LEDs that show white as blueish.
+    #[serde(default = "ColorCorrection::default_white_point")]
+    pub white_point: [f32; 3],
+}
+
+impl ColorCorrection {
+    fn default_gamma() -> f32 {
+        1.0
+    }
+
+    fn default_white_point() -> [f32; 3] {
+        [1.0; 3]
+    }
+
+    pub fn apply(&self, color: &Color) -> Color {
+        let [r, g, b] = self.white_point;
+        let channel = |value: u8, factor: f32| {
+            let value = (value as f32 / 255.0).powf(self.gamma) * factor.clamp(0.0, 1.0);
+            (value * 255.0).round().clamp(0.0, 255.0) as u8
+        };
+        Color {
+            r: channel(color.r, r),
+            g: channel(color.g, g),
+            b: channel(color.b, b),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct FanControlConfig {
+    /// Delay between two fan speed updates in ms while the temperatures
+    /// are stable. The delay gets shorter as temperatures change.
+    pub tick_ms: u64,
+    /// Upper limit of a random delay in ms that is added to each tick
+    /// so multiple fans don't wake up in lockstep.
+    pub jitter_ms: u64,
+    /// Time in ms a reading of the fan sensors is reused, so several
+    /// readers within that time don't query the EC again. `0` disables it.
+    pub sensor_cache_ms: u64,
+}
+
+impl FanControlConfig {
+    const MIN_TICK_MS: u64 = 100;
+
+    pub fn tick(&self) -> Duration {
+        Duration::from_millis(self.tick_ms.max(Self::MIN_TICK_MS))
+    }
+
+    /// A random delay between zero and the configured jitter.
+    pub fn jitter(&self) -> Duration {
+        if self.jitter_ms == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_millis(fastrand::u64(0..=self.jitter_ms))
+        }
+    }
+
+    pub fn sensor_cache(&self) -> Option<Duration> {
+        (self.sensor_cache_ms > 0).then(|| Duration::from_millis(self.sensor_cache_ms))
+    }
+}
+
+impl Default for FanControlConfig {
+    fn default() -> Self {
+        Self {
+            tick_ms: 2000,
+            jitter_ms: 0,
+            sensor_cache_ms: 0,
+        }
+    }
+}
+
+/// Actions that are triggered once a temperature stays above
+/// a hard limit, regardless of the active fan profile.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CriticalTempConfig {
+    /// Temperature limit in °C.
+    pub limit: u8,
+    /// Number of seconds the limit has to be exceeded
+    /// before the actions are triggered.
+    #[serde(default)]
+    pub duration_secs: u64,
+    pub actions: Vec<CriticalTempAction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum CriticalTempAction {
+    /// Emit the `CriticalTemperature` D-Bus signal.
+    Notify,
+    /// Run all fans at 100% until the temperature drops again.
+    MaxFans,
+    /// Activate the global profile with the given name.
+    SwitchProfile(String),
+    /// Run a command, e.g. `["systemctl", "suspend"]`.
+    Command(Vec<String>),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ThrottleConfig {
+    /// Emit the `ThermalThrottling` D-Bus signal when the CPU starts
+    /// or stops throttling while all fans run at 100%.
+    pub notify: bool,
+}
+
+/// Turns all LEDs off at certain times, regardless of the active profile.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct LightingConfig {
+    /// A daily period in which the LEDs are off.
+    pub off_hours: Option<OffHours>,
+    /// Turn the LEDs off while all graphical sessions are locked.
+    pub off_when_locked: bool,
+    /// Turn the LEDs off while all sessions are idle, see [`IdleConfig`].
+    pub off_when_idle: bool,
+    /// The LED of each key for the typing effect of LED profiles.
+    /// If it's empty, every key lights up all LEDs.
+    pub key_leds: Vec<KeyLed>,
+    pub night_shift: Option<NightShiftConfig>,
+}
+
+/// Gradually warms the colors of RGB LEDs at night, like redshift does
+/// for screens. The night is set by `hours` or the sunset and sunrise
+/// at `location`.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct NightShiftConfig {
+    /// The color temperature at night in Kelvin, from 1000 to 6500.
+    /// 6500 K keeps the colors.
+    #[serde(default = "NightShiftConfig::default_temperature")]
+    pub temperature: u32,
+    pub hours: Option<OffHours>,
+    pub location: Option<Location>,
+    /// How long the colors take to change at the start
+    /// and at the end of the night.
+    #[serde(default = "NightShiftConfig::default_transition_mins")]
+    pub transition_mins: u32,
+}
+
+impl NightShiftConfig {
+    fn default_temperature() -> u32 {
+        3400
+    }
+
+    fn default_transition_mins() -> u32 {
+        60
+    }
+}
+
+/// A location in degrees, north and east are positive.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Location {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// Assigns a key to a LED of a per-key keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct KeyLed {
+    /// The Linux input event code of the key, e.g. `30` for `A`.
+    pub key: u16,
+    /// The number at the end of the LED device, e.g. `12` for `rgb:kbd_backlight_12`.
+    /// Keys of ITE 8291 controllers are numbered row by row from the bottom left.
+    pub led: u32,
+}
+
+impl LightingConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.off_hours.is_some() || self.off_when_locked || self.off_when_idle
+    }
+}
+
+/// Actions while logind reports all sessions as idle. Desktops
+/// mark their sessions as idle after their own idle delay,
+/// which also works on Wayland.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct IdleConfig {
+    /// Number of seconds the sessions have to be idle before the actions apply.
+    pub delay_secs: u64,
+    /// Activate this profile while idle and restore the previous one afterwards.
+    pub profile: Option<String>,
+}
+
+/// Settings for the guided calibration of the battery.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct BatteryCalibrationConfig {
+    /// The charge in percent at which the discharge phase ends.
+    pub discharge_to: u8,
+    /// Activate this profile while discharging, so the battery drains at
+    /// a steady rate, and restore the previous one afterwards.
+    pub profile: Option<String>,
+}
+
+impl Default for BatteryCalibrationConfig {
+    fn default() -> Self {
+        Self {
+            discharge_to: 5,
+            profile: None,
+        }
+    }
+}
+
+/// The OpenRGB SDK server has no authentication,
+/// so it only listens on localhost by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct OpenRgbConfig {
+    #[serde(default = "OpenRgbConfig::default_address")]
+    pub address: SocketAddr,
+}
+
+impl OpenRgbConfig {
+    /// The default port of OpenRGB.
+    fn default_address() -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], 6742))
+    }
+}
+
+/// Starts at `from` and ends at `to`, e.g. from `23:00` to `07:00`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct OffHours {
+    pub from: TimeOfDay,
+    pub to: TimeOfDay,
+}
+
+impl OffHours {
+    /// Periods that end before they start extend over midnight.
+    pub fn contains(&self, time: TimeOfDay) -> bool {
+        if self.from <= self.to {
+            self.from <= time && time < self.to
+        } else {
+            self.from <= time || time < self.to
+        }
+    }
+}
+
+/// A time in the format `HH:MM`.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeOfDay {
+    pub hour: u8,
+    pub minute: u8,
+}
+
+impl TryFrom<String> for TimeOfDay {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let invalid = || format!("Invalid time `{value}`, expected `HH:MM`");
+        let (hour, minute) = value.split_once(':').ok_or_else(invalid)?;
+        let hour: u8 = hour.parse().map_err(|_| invalid())?;
+        let minute: u8 = minute.parse().map_err(|_| invalid())?;
+        if hour < 24 && minute < 60 {
+            Ok(Self { hour, minute })
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
+impl From<TimeOfDay> for String {
+    fn from(time: TimeOfDay) -> Self {
+        format!("{:02}:{:02}", time.hour, time.minute)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tailor_api::Color;
+
+    use super::{parse_dir_args, ColorCorrection, DirArgs, OffHours, TimeOfDay};
+
+    fn time(value: &str) -> TimeOfDay {
+        TimeOfDay::try_from(value.to_owned()).unwrap()
+    }
+
+    #[test]
+    fn off_hours() {
+        let night = OffHours {
+            from: time("23:00"),
+            to: time("07:00"),
+        };
+        assert!(night.contains(time("23:30")));
+        assert!(night.contains(time("06:59")));
+        assert!(!night.contains(time("07:00")));
+        assert!(!night.contains(time("12:00")));
+
+        let afternoon = OffHours {
+            from: time("12:00"),
+            to: time("14:30"),
+        };
+        assert!(afternoon.contains(time("13:00")));
+        assert!(!afternoon.contains(time("23:30")));
+
+        assert!(TimeOfDay::try_from("24:00".to_owned()).is_err());
+        assert_eq!(String::from(time("7:05")), "07:05");
+    }
+
+    #[test]
+    fn color_correction() {
+        let correction = ColorCorrection {
+            device: "rgb::kbd_backlight".to_owned(),
+            gamma: 2.0,
+            white_point: [1.0, 0.5, 1.0],
+        };
+        let color = Color {
+            r: 255,
+            g: 255,
+            b: 128,
+        };
+        assert_eq!(
+            correction.apply(&color),
+            Color {
+                r: 255,
+                g: 128,
+                b: 64,
+            }
+        );
+    }
+
+    #[test]
+    fn dir_args() {
+        let args = |args: &[&str]| parse_dir_args(args.iter().map(|arg| arg.to_string()));
+        assert_eq!(
+            args(&[
+                "--config-dir",
+                "/tmp/config",
+                "--check",
+                "--state-dir=/tmp/state"
+            ]),
+            Ok(DirArgs {
+                config_dir: Some("/tmp/config".to_owned()),
+                state_dir: Some("/tmp/state".to_owned()),
+                remaining: vec!["--check".to_owned()],
+            })
+        );
+        assert_eq!(args(&["--safe-mode"]).unwrap().remaining, ["--safe-mode"]);
+        assert!(args(&["--check", "--config-dir"]).is_err());
+        assert!(args(&["--config-dir", "--check"]).is_err());
+        assert!(args(&["--state-dir="]).is_err());
+    }
+}
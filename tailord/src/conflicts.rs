@@ -0,0 +1,146 @@
+//! Detects other programs that control the fans. If two daemons
+//! write to the embedded controller, the fans jump between the
+//! speeds both of them set and neither fan curve is followed.
+
+use std::path::Path;
+
+/// Processes that write the fan speed, with a hint how to stop them.
+const FAN_DAEMONS: &[(&str, &str)] = &[
+    (
+        "tccd",
+        "the daemon of TUXEDO Control Center, stop it with \
+        `systemctl disable --now tccd.service tccd-sleep.service`",
+    ),
+    (
+        "tailord",
+        "another instance of tailord, stop it before starting a new one",
+    ),
+    (
+        "fancontrol",
+        "the fancontrol script of lm-sensors, stop it with `systemctl disable --now fancontrol`",
+    ),
+    (
+        "nbfc_service",
+        "NoteBook FanControl, stop it with `systemctl disable --now nbfc_service`",
+    ),
+];
+
+/// thermald only controls fans if its configuration defines them.
+const THERMALD: &str = "thermald";
+const THERMALD_CONFIGS: &[&str] = &[
+    "/etc/thermald/thermal-conf.xml",
+    "/etc/thermald/thermal-cpu-cdev-order.xml",
+];
+const THERMALD_HINT: &str = "thermald with fan cooling devices in `/etc/thermald`, \
+    remove them from its configuration or stop it with `systemctl disable --now thermald`";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub name: &'static str,
+    pub pid: u32,
+    pub hint: &'static str,
+}
+
+/// Find the running programs that would fight with tailord over the fans.
+pub fn detect() -> Vec<Conflict> {
+    let own_pid = std::process::id();
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        tracing::warn!("Failed to list the processes, can't check for other fan daemons");
+        return Vec::new();
+    };
+
+    let mut thermald_controls_fans = None;
+    let mut conflicts = Vec::new();
+    for entry in entries.flatten() {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        if pid == own_pid {
+            continue;
+        }
+
+        let path = entry.path();
+        let comm = std::fs::read_to_string(path.join("comm")).unwrap_or_default();
+        let cmdline = std::fs::read(path.join("cmdline")).unwrap_or_default();
+        let names = process_names(&comm, &cmdline);
+
+        if let Some((name, hint)) = FAN_DAEMONS
+            .iter()
+            .find(|(daemon, _)| names.iter().any(|name| name == daemon))
+        {
+            conflicts.push(Conflict { name, pid, hint });
+        } else if names.iter().any(|name| name == THERMALD)
+            && *thermald_controls_fans.get_or_insert_with(thermald_controls_fans_config)
+        {
+            conflicts.push(Conflict {
+                name: THERMALD,
+                pid,
+                hint: THERMALD_HINT,
+            });
+        }
+    }
+    conflicts.sort_by_key(|conflict| conflict.pid);
+    conflicts
+}
+
+/// Log the conflicts and return whether there were any.
+pub fn warn(conflicts: &[Conflict]) -> bool {
+    for Conflict { name, pid, hint } in conflicts {
+        tracing::warn!(
+            "`{name}` (pid {pid}) also controls the fans, which makes them behave unpredictably. \
+            This is {hint}"
+        );
+    }
+    !conflicts.is_empty()
+}
+
+/// The kernel name of a process and the file names of the program
+/// and its first argument, since daemons written in scripting
+/// languages run as e.g. `node tccd`.
+fn process_names(comm: &str, cmdline: &[u8]) -> Vec<String> {
+    let mut names = vec![comm.trim().to_owned()];
+    names.extend(cmdline.split(|byte| *byte == 0).take(2).filter_map(|arg| {
+        let arg = std::str::from_utf8(arg).ok()?;
+        Path::new(arg).file_name()?.to_str().map(str::to_owned)
+    }));
+    names
+}
+
+fn thermald_controls_fans_config() -> bool {
+    THERMALD_CONFIGS.iter().any(|path| {
+        std::fs::read_to_string(path)
+            .map(|config| defines_fan(&config))
+            .unwrap_or_default()
+    })
+}
+
+/// Whether a thermald configuration uses a fan as cooling device.
+fn defines_fan(config: &str) -> bool {
+    config.lines().any(|line| {
+        let line = line.to_ascii_lowercase();
+        line.contains("<type>") && line.contains("fan")
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{defines_fan, process_names};
+
+    #[test]
+    fn names_of_scripts() {
+        let names = process_names("node\n", b"/usr/bin/node\0/opt/tcc/tccd\0--start\0");
+        assert_eq!(names, ["node", "node", "tccd"]);
+        assert_eq!(process_names("fancontrol\n", b""), ["fancontrol"]);
+    }
+
+    #[test]
+    fn thermald_fans() {
+        let config = "<CoolingDevice>\n  <Type>_Fan</Type>\n</CoolingDevice>";
+        assert!(defines_fan(config));
+        assert!(!defines_fan("<CoolingDevice><Type>rapl_controller</Type>"));
+    }
+}
@@ -0,0 +1,115 @@
+//! Limits of the CPU frequency via the cpufreq sysfs interface.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use tailor_api::HardwareWrite;
+use tuxedo_sysfs::attribute::planned_write;
+
+const CPUFREQ_PATH: &str = "/sys/devices/system/cpu/cpufreq";
+const KHZ_PER_MHZ: u32 = 1000;
+
+/// Whether a profile capped the frequency, so it can
+/// be restored when switching to a profile without cap.
+static CAPPED: AtomicBool = AtomicBool::new(false);
+
+/// The frequencies in MHz at which a cap can be set, from the highest
+/// minimum to the highest maximum of all CPUs. CPUs with a lower maximum,
+/// e.g. the efficiency cores of hybrid CPUs, stay at their own maximum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrequencyLimits {
+    pub min_mhz: u32,
+    pub max_mhz: u32,
+}
+
+impl FrequencyLimits {
+    pub fn contains(&self, freq_mhz: u32) -> bool {
+        (self.min_mhz..=self.max_mhz).contains(&freq_mhz)
+    }
+}
+
+/// Get the range of frequencies in which a cap can be set,
+/// or `None` if cpufreq isn't available.
+pub fn frequency_limits() -> Result<Option<FrequencyLimits>, io::Error> {
+    let mut limits: Option<FrequencyLimits> = None;
+    for policy in policies()? {
+        let min_mhz = read_khz(&policy.join("cpuinfo_min_freq"))? / KHZ_PER_MHZ;
+        let max_mhz = read_khz(&policy.join("cpuinfo_max_freq"))? / KHZ_PER_MHZ;
+        let limits = limits.get_or_insert(FrequencyLimits { min_mhz, max_mhz });
+        limits.min_mhz = limits.min_mhz.max(min_mhz);
+        limits.max_mhz = limits.max_mhz.max(max_mhz);
+    }
+    Ok(limits)
+}
+
+/// Cap the frequency of all CPUs at `max_freq_mhz`.
+/// Each CPU is limited to its own supported range.
+/// `None` removes a previously applied cap.
+pub fn apply_max_freq(max_freq_mhz: Option<u32>) -> Result<(), io::Error> {
+    if max_freq_mhz.is_none() && !CAPPED.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+
+    for policy in policies()? {
+        let freq_khz = max_freq_khz(&policy, max_freq_mhz)?;
+        fs::write(policy.join("scaling_max_freq"), freq_khz.to_string())?;
+    }
+
+    CAPPED.store(max_freq_mhz.is_some(), Ordering::Relaxed);
+    match max_freq_mhz {
+        Some(freq_mhz) => tracing::info!("Capped CPU frequency at {freq_mhz} MHz"),
+        None => tracing::info!("Removed CPU frequency cap"),
+    }
+    Ok(())
+}
+
+/// The writes of [`apply_max_freq`] that change a value, without applying them.
+pub fn preview_max_freq(max_freq_mhz: Option<u32>) -> Result<Vec<HardwareWrite>, io::Error> {
+    if max_freq_mhz.is_none() && !CAPPED.load(Ordering::Relaxed) {
+        return Ok(Vec::new());
+    }
+
+    let mut writes = Vec::new();
+    for policy in policies()? {
+        let freq_khz = max_freq_khz(&policy, max_freq_mhz)?;
+        writes.extend(planned_write(&policy, "scaling_max_freq", freq_khz));
+    }
+    Ok(writes)
+}
+
+/// The cap of a CPU within its supported range.
+fn max_freq_khz(policy: &Path, max_freq_mhz: Option<u32>) -> Result<u32, io::Error> {
+    let min_khz = read_khz(&policy.join("cpuinfo_min_freq"))?;
+    let max_khz = read_khz(&policy.join("cpuinfo_max_freq"))?;
+    Ok(match max_freq_mhz {
+        Some(freq_mhz) => freq_mhz.saturating_mul(KHZ_PER_MHZ).clamp(min_khz, max_khz),
+        None => max_khz,
+    })
+}
+
+fn policies() -> Result<Vec<PathBuf>, io::Error> {
+    let dirs = match fs::read_dir(CPUFREQ_PATH) {
+        Ok(dirs) => dirs,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+
+    let mut policies = Vec::new();
+    for dir in dirs {
+        let dir = dir?;
+        if dir.file_name().to_string_lossy().starts_with("policy") {
+            policies.push(dir.path());
+        }
+    }
+    Ok(policies)
+}
+
+fn read_khz(path: &Path) -> Result<u32, io::Error> {
+    fs::read_to_string(path)?
+        .trim()
+        .parse()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
@@ -0,0 +1,264 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use tailor_api::EventKind;
+use tuxedo_ioctl::hal::traits::FanControl;
+use zbus::Connection;
+
+use crate::{
+    audit::Cause,
+    config::{CalibrationConfig, CriticalTempAction, CriticalTempConfig},
+    dbus::{FanInterface, ProfileInterface},
+    events,
+    fancontrol::{FanRuntimeHandle, FanSpeedOverride},
+    profiles::Profile,
+    DBUS_PATH,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// The reason of the actions in the audit log.
+const RULE: &str = "critical temperature";
+/// The temperature must drop this many °C below the
+/// limit before the actions can be triggered again.
+const HYSTERESIS: u8 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transition {
+    /// The temperature stayed above the limit for the configured duration.
+    Triggered,
+    /// The temperature dropped below the limit minus the [`HYSTERESIS`].
+    Recovered,
+}
+
+/// Tracks how long the temperature has been above the limit.
+#[derive(Debug)]
+struct CriticalState {
+    limit: u8,
+    duration: Duration,
+    exceeded_since: Option<Instant>,
+    triggered: bool,
+}
+
+impl CriticalState {
+    fn new(limit: u8, duration: Duration) -> Self {
+        Self {
+            limit,
+            duration,
+            exceeded_since: None,
+            triggered: false,
+        }
+    }
+
+    fn update(&mut self, temp: u8, now: Instant) -> Option<Transition> {
+        if temp > self.limit {
+            let since = *self.exceeded_since.get_or_insert(now);
+            if !self.triggered && now.duration_since(since) >= self.duration {
+                self.triggered = true;
+                return Some(Transition::Triggered);
+            }
+        } else if temp < self.limit.saturating_sub(HYSTERESIS) {
+            self.exceeded_since = None;
+            if self.triggered {
+                self.triggered = false;
+                return Some(Transition::Recovered);
+            }
+        }
+        None
+    }
+}
+
+/// Watches the temperatures independently of the fan runtimes
+/// and triggers the configured actions if they stay too high.
+pub struct CriticalTempWatcher {
+    config: CriticalTempConfig,
+    calibration: CalibrationConfig,
+    io: Arc<dyn FanControl>,
+    fan_handles: Vec<FanRuntimeHandle>,
+    connection: Connection,
+}
+
+impl CriticalTempWatcher {
+    pub fn new(
+        config: CriticalTempConfig,
+        calibration: CalibrationConfig,
+        io: Arc<dyn FanControl>,
+        fan_handles: Vec<FanRuntimeHandle>,
+        connection: Connection,
+    ) -> Self {
+        Self {
+            config,
+            calibration,
+            io,
+            fan_handles,
+            connection,
+        }
+    }
+
+    pub async fn run(self) {
+        let limit = self.config.limit;
+        let mut state = CriticalState::new(limit, Duration::from_secs(self.config.duration_secs));
+
+        loop {
+            if let Some(temp) = self.max_temp() {
+                match state.update(temp, Instant::now()) {
+                    Some(Transition::Triggered) => {
+                        tracing::warn!(
+                            "Temperature {temp}°C exceeded the critical limit of {limit}°C"
+                        );
+                        events::record(EventKind::CriticalTemperature { temp, limit });
+                        self.trigger(temp).await;
+                    }
+                    Some(Transition::Recovered) => {
+                        tracing::info!("Temperature {temp}°C is below the critical limit again");
+                        events::record(EventKind::CriticalTemperatureRecovered { temp });
+                        self.recover().await;
+                    }
+                    None => {}
+                }
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Highest calibrated temperature of all fans, read in one pass.
+    fn max_temp(&self) -> Option<u8> {
+        let sensors = match self.io.read_sensors() {
+            Ok(sensors) => sensors,
+            Err(err) => {
+                tracing::error!("Failed reading the fan temperatures: `{err}`");
+                return None;
+            }
+        };
+        sensors
+            .iter()
+            .enumerate()
+            .map(|(fan_idx, sensors)| {
+                let offset = self.calibration.temp_offset(fan_idx as u8);
+                sensors.temp.saturating_add_signed(offset)
+            })
+            .max()
+    }
+
+    async fn trigger(&self, temp: u8) {
+        for action in &self.config.actions {
+            if let Err(err) = self.run_action(action, temp).await {
+                tracing::error!("Failed to run critical temperature action {action:?}: `{err}`");
+            }
+        }
+    }
+
+    async fn run_action(&self, action: &CriticalTempAction, temp: u8) -> Result<(), String> {
+        match action {
+            CriticalTempAction::Notify => {
+                let iface = self
+                    .connection
+                    .object_server()
+                    .interface::<_, FanInterface>(DBUS_PATH)
+                    .await
+                    .map_err(|err| err.to_string())?;
+                FanInterface::critical_temperature(iface.signal_context(), temp)
+                    .await
+                    .map_err(|err| err.to_string())
+            }
+            CriticalTempAction::MaxFans => {
+                self.send_speed_override(Some(FanSpeedOverride {
+                    speed: 100,
+                    duration: None,
+                    cause: Cause::Rule(RULE),
+                }))
+                .await
+            }
+            CriticalTempAction::SwitchProfile(name) => {
+                Profile::set_active_profile_name(name, None, None)
+                    .await
+                    .map_err(|err| err.to_string())?;
+                let iface = self
+                    .connection
+                    .object_server()
+                    .interface::<_, ProfileInterface>(DBUS_PATH)
+                    .await
+                    .map_err(|err| err.to_string())?;
+                let result = iface.get().await.reload_active_profile(Some(RULE)).await;
+                result.map_err(|err| err.to_string())?;
+                ProfileInterface::emit_active_profile_changed(iface.signal_context())
+                    .await
+                    .map_err(|err| err.to_string())
+            }
+            CriticalTempAction::Command(command) => {
+                let (program, args) = command
+                    .split_first()
+                    .ok_or_else(|| "Empty command".to_owned())?;
+                let mut command = std::process::Command::new(program);
+                command
+                    .args(args)
+                    .env("TAILORD_TEMPERATURE", temp.to_string());
+                let status = tokio::task::spawn_blocking(move || command.status())
+                    .await
+                    .map_err(|err| err.to_string())?
+                    .map_err(|err| err.to_string())?;
+                if status.success() {
+                    Ok(())
+                } else {
+                    Err(format!("Command exited with {status}"))
+                }
+            }
+        }
+    }
+
+    /// Undo actions that only apply while the temperature is too high.
+    async fn recover(&self) {
+        if self.config.actions.contains(&CriticalTempAction::MaxFans) {
+            if let Err(err) = self.send_speed_override(None).await {
+                tracing::error!("Failed to release fans: `{err}`");
+            }
+        }
+    }
+
+    async fn send_speed_override(
+        &self,
+        speed_override: Option<FanSpeedOverride>,
+    ) -> Result<(), String> {
+        for handle in &self.fan_handles {
+            handle
+                .speed_override_sender
+                .send(speed_override.clone())
+                .await
+                .map_err(|err| err.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::{CriticalState, Transition};
+
+    #[test]
+    fn critical_state() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut state = CriticalState::new(90, Duration::from_secs(10));
+
+        assert_eq!(state.update(95, at(0)), None);
+        assert_eq!(state.update(95, at(9)), None);
+        assert_eq!(state.update(92, at(10)), Some(Transition::Triggered));
+        // Only triggered once while the temperature stays high.
+        assert_eq!(state.update(96, at(20)), None);
+
+        // Within the hysteresis, nothing changes.
+        assert_eq!(state.update(86, at(21)), None);
+        assert_eq!(state.update(84, at(22)), Some(Transition::Recovered));
+        assert_eq!(state.update(80, at(23)), None);
+
+        // Dropping below the hysteresis resets the duration.
+        assert_eq!(state.update(91, at(30)), None);
+        assert_eq!(state.update(80, at(35)), None);
+        assert_eq!(state.update(91, at(36)), None);
+        assert_eq!(state.update(91, at(45)), None);
+        assert_eq!(state.update(91, at(46)), Some(Transition::Triggered));
+    }
+}
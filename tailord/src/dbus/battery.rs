@@ -0,0 +1,138 @@
+use tailor_api::TailorError;
+use tuxedo_ioctl::hal::IoctlError;
+use tuxedo_sysfs::{charging::BatteryConservation, power_supply::Battery};
+use zbus::{dbus_interface, fdo, SignalContext};
+
+use crate::{
+    battery,
+    calibration::{CalibrationCommand, CalibrationHandle},
+    inhibit, util,
+};
+
+pub struct BatteryInterface {
+    /// `None` if the firmware doesn't support it.
+    pub conservation: Option<BatteryConservation>,
+    /// `None` if there's no internal battery.
+    pub battery: Option<Battery>,
+    /// `None` if there's no internal battery.
+    pub calibration: Option<CalibrationHandle>,
+}
+
+impl BatteryInterface {
+    fn conservation(&self) -> fdo::Result<&BatteryConservation> {
+        self.conservation.as_ref().ok_or_else(|| {
+            util::tailor_error(TailorError::not_supported(
+                "The firmware has no battery conservation mode",
+            ))
+        })
+    }
+
+    fn battery(&self) -> fdo::Result<&Battery> {
+        self.battery.as_ref().ok_or_else(|| {
+            util::tailor_error(TailorError::not_supported("There's no internal battery"))
+        })
+    }
+
+    fn calibration(&self) -> fdo::Result<&CalibrationHandle> {
+        self.calibration.as_ref().ok_or_else(|| {
+            util::tailor_error(TailorError::not_supported(
+                "There's no battery to calibrate",
+            ))
+        })
+    }
+
+    async fn send_calibration_command(&self, command: CalibrationCommand) -> fdo::Result<()> {
+        self.calibration()?
+            .command_sender
+            .send(command)
+            .await
+            .map_err(|err| fdo::Error::Failed(format!("Internal error: `{err}`")))
+    }
+}
+
+#[dbus_interface(name = "com.tux.Tailor.Battery")]
+impl BatteryInterface {
+    /// Whether the firmware limits the charge to extend the lifespan
+    /// of the battery, independently of charge thresholds.
+    #[dbus_interface(property)]
+    async fn conservation_mode(&self) -> fdo::Result<bool> {
+        self.conservation()?.get().map_err(|err| {
+            util::hardware_error(
+                "Failed to read the battery conservation mode",
+                IoctlError::IO(err),
+            )
+        })
+    }
+
+    #[dbus_interface(property)]
+    async fn set_conservation_mode(&mut self, enabled: bool) -> fdo::Result<()> {
+        inhibit::check_write("set the battery conservation mode")?;
+        self.conservation()?.set(enabled).map_err(|err| {
+            util::hardware_error(
+                "Failed to set the battery conservation mode",
+                IoctlError::IO(err),
+            )
+        })?;
+        tracing::info!("Battery conservation mode: {enabled}");
+        battery::store(enabled);
+        Ok(())
+    }
+
+    /// Get the charge, the charging rate and the charge thresholds of the battery.
+    async fn get_state(&self) -> fdo::Result<String> {
+        let state = self.battery()?.state().map_err(|err| {
+            util::hardware_error("Failed to read the battery state", IoctlError::IO(err))
+        })?;
+        serde_json::to_string(&state).map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Get the capacity of the battery compared to when it was new.
+    async fn get_health(&self) -> fdo::Result<String> {
+        let health = self.battery()?.health().map_err(|err| {
+            util::hardware_error("Failed to read the battery health", IoctlError::IO(err))
+        })?;
+        serde_json::to_string(&health).map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Get the daily records of the battery health, oldest first.
+    async fn get_health_history(&self) -> fdo::Result<String> {
+        self.battery()?;
+        let history = battery::health_history()
+            .map_err(|err| fdo::Error::IOError(format!("Failed to read the history: `{err}`")))?;
+        serde_json::to_string(&history).map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Start a calibration of the battery. The progress is
+    /// reported by the `CalibrationChanged` signal.
+    async fn start_calibration(&self) -> fdo::Result<()> {
+        if self
+            .calibration()?
+            .status_receiver
+            .borrow()
+            .phase
+            .is_running()
+        {
+            return Err(fdo::Error::Failed(
+                "The battery calibration is already running".to_owned(),
+            ));
+        }
+        inhibit::check_write("start the battery calibration")?;
+        self.send_calibration_command(CalibrationCommand::Start)
+            .await
+    }
+
+    async fn cancel_calibration(&self) -> fdo::Result<()> {
+        self.send_calibration_command(CalibrationCommand::Cancel)
+            .await
+    }
+
+    /// Get the progress of the current or last battery calibration.
+    async fn get_calibration_status(&self) -> fdo::Result<String> {
+        let status = self.calibration()?.status_receiver.borrow().clone();
+        serde_json::to_string(&status).map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Emitted when the phase of the calibration or the battery changes.
+    #[dbus_interface(signal)]
+    pub async fn calibration_changed(ctxt: &SignalContext<'_>, status: &str) -> zbus::Result<()>;
+}
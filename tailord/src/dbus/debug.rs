@@ -0,0 +1,96 @@
+use tailor_api::TailorError;
+use tuxedo_ioctl::debug;
+use zbus::{dbus_interface, fdo, Connection, MessageHeader};
+
+use crate::{scope, util};
+
+/// Raw access to the tuxedo_io driver for triaging driver bugs.
+/// Only available with the `debug-ioctl` feature and only for root.
+pub struct DebugInterface;
+
+#[dbus_interface(name = "com.tux.Tailor.Debug")]
+impl DebugInterface {
+    /// List the attributes that can be read and written.
+    async fn list_ioctl_attributes(
+        &self,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(header)] header: MessageHeader<'_>,
+    ) -> fdo::Result<(Vec<String>, Vec<String>)> {
+        require_root(connection, &header).await?;
+        let names = |names: Vec<&str>| names.into_iter().map(str::to_owned).collect();
+        Ok((
+            names(debug::readable_attributes()),
+            names(debug::writable_attributes()),
+        ))
+    }
+
+    /// Read an attribute of the driver, e.g. `uw.fan_temp_0`.
+    async fn read_ioctl(
+        &self,
+        name: &str,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(header)] header: MessageHeader<'_>,
+    ) -> fdo::Result<String> {
+        require_root(connection, &header).await?;
+        debug::read_attribute(name)
+            .ok_or_else(|| unknown_attribute(name))?
+            .map_err(|err| util::hardware_error(&format!("unable to read `{name}`"), err))
+    }
+
+    /// Write a raw value to an attribute of the driver, bypassing all limits
+    /// of tailord. Requires the `debug-ioctl-writes` feature.
+    async fn write_ioctl(
+        &self,
+        name: &str,
+        value: i32,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(header)] header: MessageHeader<'_>,
+    ) -> fdo::Result<()> {
+        require_root(connection, &header).await?;
+        write(name, value)
+    }
+}
+
+#[cfg(feature = "debug-ioctl-writes")]
+fn write(name: &str, value: i32) -> fdo::Result<()> {
+    use tailor_api::HardwareWrite;
+
+    use crate::audit::{self, Cause};
+
+    crate::inhibit::check_write("write to the driver")?;
+    tracing::warn!("Writing raw value {value} to `{name}`");
+    debug::write_attribute(name, value)
+        .ok_or_else(|| unknown_attribute(name))?
+        .map_err(|err| util::hardware_error(&format!("unable to write `{name}`"), err))?;
+    audit::record(
+        HardwareWrite {
+            device: "tuxedo_io".to_owned(),
+            attribute: name.to_owned(),
+            old: None,
+            new: value.to_string(),
+        },
+        Cause::Override,
+    );
+    Ok(())
+}
+
+#[cfg(not(feature = "debug-ioctl-writes"))]
+fn write(_name: &str, _value: i32) -> fdo::Result<()> {
+    Err(util::tailor_error(TailorError::not_supported(
+        "Raw writes require tailord to be built with the `debug-ioctl-writes` feature",
+    )))
+}
+
+async fn require_root(connection: &Connection, header: &MessageHeader<'_>) -> fdo::Result<()> {
+    if scope::caller_uid(connection, header).await? == Some(0) {
+        Ok(())
+    } else {
+        Err(util::tailor_error(TailorError::permission_denied(
+            "Only root can access the driver directly",
+        )))
+    }
+}
+
+fn unknown_attribute(name: &str) -> fdo::Error {
+    fdo::Error::InvalidArgs(format!("Unknown attribute `{name}`"))
+}
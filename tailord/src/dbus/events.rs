@@ -0,0 +1,50 @@
+use zbus::{dbus_interface, fdo, Connection, MessageHeader, SignalContext};
+
+use crate::{events, logs, scope};
+
+pub struct EventsInterface;
+
+#[dbus_interface(name = "com.tux.Tailor.Events")]
+impl EventsInterface {
+    /// Get up to `limit` of the latest events, oldest first.
+    async fn get_events(&self, limit: u32) -> fdo::Result<String> {
+        let events = events::read_events(limit as usize)
+            .await
+            .map_err(|err| fdo::Error::IOError(err.to_string()))?;
+        serde_json::to_string(&events).map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Get up to `limit` of the latest messages of the log of tailord, oldest first.
+    /// Only for root and users of an active local session.
+    async fn get_log(
+        &self,
+        limit: u32,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(header)] header: MessageHeader<'_>,
+    ) -> fdo::Result<String> {
+        scope::require_active_user(connection, &header, "read the log").await?;
+        serde_json::to_string(&logs::records(limit as usize))
+            .map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Receive the `LogRecorded` signal until the caller disconnects.
+    /// Only for root and users of an active local session.
+    async fn subscribe_log(
+        &self,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(header)] header: MessageHeader<'_>,
+    ) -> fdo::Result<()> {
+        scope::require_active_user(connection, &header, "read the log").await?;
+        let sender = header
+            .sender()
+            .ok()
+            .flatten()
+            .ok_or_else(|| fdo::Error::Failed("Message without sender".to_owned()))?;
+        logs::subscribe(sender.to_owned().into());
+        Ok(())
+    }
+
+    /// Emitted for each new message of the log, only to the subscribers.
+    #[dbus_interface(signal)]
+    pub async fn log_recorded(ctxt: &SignalContext<'_>, record: &str) -> zbus::Result<()>;
+}
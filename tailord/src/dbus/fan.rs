@@ -1,23 +1,44 @@
-use tailor_api::{FanProfilePoint, ProfileInfo};
-use zbus::{dbus_interface, fdo};
+use std::{
+    os::fd::{FromRawFd, IntoRawFd},
+    path::Path,
+    time::Duration,
+};
+
+use tailor_api::{FanProfileInfo, FanStatus, ProfileInfo, TailorError, ThrottleStatus};
+use tokio::sync::watch;
+use zbus::{dbus_interface, fdo, zvariant::OwnedFd, Connection, MessageHeader, SignalContext};
 
 use crate::{
-    fancontrol::FanRuntimeHandle,
-    profiles::{Profile, FAN_DIR, PROFILE_DIR},
+    audit::Cause,
+    fancontrol::{
+        profile::{FanLimits, FanProfile},
+        FanRuntimeHandle, FanSpeedOverride,
+    },
+    inhibit,
+    profiles::{Profile, FAN_DIR, FAN_SUBDIR, PROFILE_DIR},
+    scope::{self, UserScope},
     util,
 };
 
 pub struct FanInterface {
     pub handles: Vec<FanRuntimeHandle>,
+    pub throttle_status: watch::Receiver<ThrottleStatus>,
+    /// `None` if fan control isn't available.
+    pub limits: Option<FanLimits>,
 }
 
 #[dbus_interface(name = "com.tux.Tailor.Fan")]
 impl FanInterface {
     async fn add_profile(&self, name: &str, value: &str) -> fdo::Result<()> {
-        // Verify correctness of the file.
-        serde_json::from_str::<Vec<FanProfilePoint>>(value)
-            .map_err(|err| fdo::Error::InvalidArgs(err.to_string()))?;
-        util::write_file(FAN_DIR, name, value.as_bytes()).await?;
+        // Verify correctness of the file, the daemon must be able to load it again.
+        let info = util::parse_json::<FanProfileInfo>(name, value)?;
+        let profile = FanProfile::from_config(info, Path::new(name))?;
+        if let Some(limits) = self.limits {
+            profile
+                .validate_limits(limits)
+                .map_err(util::tailor_error)?;
+        }
+        util::write_file(&FAN_DIR, name, value.as_bytes()).await?;
 
         // Reload if the fan profile is part of the active global profile
         let info = Profile::get_active_profile_info()?;
@@ -25,8 +46,7 @@ impl FanInterface {
             let info = Profile::load();
             for (idx, handle) in self.handles.iter().enumerate() {
                 handle
-                    .profile_sender
-                    .send(info.fans.get(idx).cloned().unwrap_or_default())
+                    .set_profile(info.fans.get(idx).cloned().unwrap_or_default())
                     .await
                     .unwrap();
             }
@@ -34,33 +54,50 @@ impl FanInterface {
         Ok(())
     }
 
-    async fn get_profile(&self, name: &str) -> fdo::Result<String> {
-        util::read_file(FAN_DIR, name).await
+    /// Get a profile, preferring the profiles of the calling user.
+    async fn get_profile(
+        &self,
+        name: &str,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(header)] header: MessageHeader<'_>,
+    ) -> fdo::Result<String> {
+        let user = UserScope::of_caller(connection, &header).await?;
+        scope::read_profile_json(user.as_ref(), &FAN_DIR, FAN_SUBDIR, name).await
     }
 
-    async fn list_profiles(&self) -> fdo::Result<Vec<String>> {
-        util::get_profiles(FAN_DIR).await
+    /// List the system profiles along with the profiles of the calling user.
+    async fn list_profiles(
+        &self,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(header)] header: MessageHeader<'_>,
+    ) -> fdo::Result<Vec<String>> {
+        let user = UserScope::of_caller(connection, &header).await?;
+        scope::list_profiles(user.as_ref(), &FAN_DIR, FAN_SUBDIR).await
     }
 
     async fn remove_profile(&self, name: &str) -> fdo::Result<()> {
-        util::remove_file(FAN_DIR, name).await
+        util::remove_file(&FAN_DIR, name).await
     }
 
     async fn rename_profile(&self, from: &str, to: &str) -> fdo::Result<Vec<String>> {
-        if self.list_profiles().await?.contains(&to.to_string()) {
-            Err(fdo::Error::InvalidArgs(format!(
-                "File `{to}` already exists"
-            )))
+        if util::get_profiles(&FAN_DIR)
+            .await?
+            .contains(&to.to_string())
+        {
+            Err(util::tailor_error(TailorError::already_exists(format!(
+                "Profile `{to}` already exists"
+            ))))
         } else {
-            let profiles = util::get_profiles(PROFILE_DIR).await?;
+            let profiles = util::get_profiles(&PROFILE_DIR).await?;
 
             for profile in profiles {
-                let mut data =
-                    if let Ok(data) = util::read_json::<ProfileInfo>(PROFILE_DIR, &profile).await {
-                        data
-                    } else {
-                        continue;
-                    };
+                let mut data = if let Ok(data) =
+                    util::read_profile::<ProfileInfo>(&PROFILE_DIR, &profile).await
+                {
+                    data
+                } else {
+                    continue;
+                };
                 let mut changed = false;
 
                 for fan in &mut data.fans {
@@ -71,13 +108,13 @@ impl FanInterface {
                 }
 
                 if changed {
-                    util::write_json(PROFILE_DIR, &profile, &data).await?;
+                    util::write_profile(&PROFILE_DIR, &profile, &data).await?;
                 }
             }
 
-            util::move_file(FAN_DIR, from, to).await?;
+            util::move_file(&FAN_DIR, from, to).await?;
 
-            self.list_profiles().await
+            util::get_profiles(&FAN_DIR).await
         }
     }
 
@@ -94,4 +131,97 @@ impl FanInterface {
             ))
         }
     }
+
+    /// Emitted when a temperature exceeds the configured critical limit.
+    #[dbus_interface(signal)]
+    pub async fn critical_temperature(ctxt: &SignalContext<'_>, temp: u8) -> zbus::Result<()>;
+
+    /// Emitted when the CPU starts or stops throttling while all fans run at 100%.
+    #[dbus_interface(signal)]
+    pub async fn thermal_throttling(ctxt: &SignalContext<'_>, active: bool) -> zbus::Result<()>;
+
+    /// Leave the fans to the firmware and stop writing to the EC until the
+    /// returned file descriptor is closed, e.g. while a firmware update needs
+    /// exclusive access to the EC. Only for root and the active session.
+    async fn inhibit(
+        &self,
+        who: &str,
+        reason: &str,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(header)] header: MessageHeader<'_>,
+    ) -> fdo::Result<OwnedFd> {
+        scope::require_active_user(connection, &header, "take over the embedded controller")
+            .await?;
+        let stream = inhibit::inhibit(who.to_owned(), reason.to_owned())
+            .map_err(|err| fdo::Error::IOError(err.to_string()))?;
+        // SAFETY: The file descriptor was owned by the stream.
+        Ok(unsafe { OwnedFd::from_raw_fd(stream.into_raw_fd()) })
+    }
+
+    /// Get the thermal throttling state of the CPU.
+    async fn get_throttle_status(&self) -> fdo::Result<String> {
+        let status = self.throttle_status.borrow().clone();
+        serde_json::to_string(&status).map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Get the current status of all fans.
+    async fn get_status(&self) -> fdo::Result<String> {
+        let status: Vec<FanStatus> = self
+            .handles
+            .iter()
+            .map(|handle| handle.status_receiver.borrow().clone())
+            .collect();
+        serde_json::to_string(&status).map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Pin a fan at a fixed speed until the override is released.
+    /// If `duration_secs` isn't zero, the fan returns to its
+    /// profile after the given number of seconds.
+    async fn set_speed_override(
+        &mut self,
+        fan_idx: u8,
+        speed: u8,
+        duration_secs: u64,
+    ) -> fdo::Result<()> {
+        if speed > 100 {
+            return Err(fdo::Error::InvalidArgs(format!(
+                "Fan speed can't be larger than 100%: `{speed}`"
+            )));
+        }
+        let duration = (duration_secs != 0).then(|| Duration::from_secs(duration_secs));
+        self.send_speed_override(
+            fan_idx,
+            Some(FanSpeedOverride {
+                speed,
+                duration,
+                cause: Cause::Override,
+            }),
+        )
+        .await
+    }
+
+    /// Release a fan speed override and return to the active profile.
+    async fn clear_speed_override(&mut self, fan_idx: u8) -> fdo::Result<()> {
+        self.send_speed_override(fan_idx, None).await
+    }
+}
+
+impl FanInterface {
+    async fn send_speed_override(
+        &self,
+        fan_idx: u8,
+        speed_override: Option<FanSpeedOverride>,
+    ) -> fdo::Result<()> {
+        if let Some(handle) = self.handles.get(fan_idx as usize) {
+            handle
+                .speed_override_sender
+                .send(speed_override)
+                .await
+                .map_err(|err| fdo::Error::Failed(format!("Internal error: `{err}`")))
+        } else {
+            Err(fdo::Error::InvalidArgs(
+                "No fan found at requested index".to_owned(),
+            ))
+        }
+    }
 }
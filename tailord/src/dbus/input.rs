@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use tailor_api::{HardwareWrite, TailorError};
+use tuxedo_ioctl::hal::{traits::TouchpadControl, IoctlError};
+use tuxedo_sysfs::fn_lock::FnLock;
+use zbus::{dbus_interface, fdo};
+
+use crate::{
+    audit::{self, Cause},
+    fn_lock, inhibit, util,
+};
+
+pub struct InputInterface {
+    /// `None` if the firmware can't turn the touchpad on and off.
+    pub touchpad: Option<Arc<dyn TouchpadControl>>,
+    /// `None` if the driver doesn't expose the Fn-lock.
+    pub fn_lock: Option<FnLock>,
+}
+
+impl InputInterface {
+    fn touchpad(&self) -> fdo::Result<&dyn TouchpadControl> {
+        self.touchpad.as_deref().ok_or_else(|| {
+            util::tailor_error(TailorError::not_supported(
+                "The device can't turn the touchpad on and off",
+            ))
+        })
+    }
+
+    fn fn_lock_attribute(&self) -> fdo::Result<&FnLock> {
+        self.fn_lock.as_ref().ok_or_else(|| {
+            util::tailor_error(TailorError::not_supported(
+                "The driver doesn't expose the Fn-lock",
+            ))
+        })
+    }
+}
+
+// Methods instead of properties, since the hotkey of the
+// keyboard changes the state without a signal.
+#[dbus_interface(name = "com.tux.Tailor.Input")]
+impl InputInterface {
+    /// Whether the touchpad is turned on in the firmware.
+    async fn get_touchpad_enabled(&self) -> fdo::Result<bool> {
+        self.touchpad()?
+            .get_touchpad()
+            .map_err(|err| util::hardware_error("Failed to read the touchpad state", err))
+    }
+
+    /// Turn the touchpad on or off. Nothing is written if it's already in that state.
+    async fn set_touchpad_enabled(&self, enabled: bool) -> fdo::Result<()> {
+        let touchpad = self.touchpad()?;
+        let current = touchpad
+            .get_touchpad()
+            .map_err(|err| util::hardware_error("Failed to read the touchpad state", err))?;
+        if current == enabled {
+            return Ok(());
+        }
+        inhibit::check_write("set the touchpad state")?;
+        touchpad
+            .set_touchpad(enabled)
+            .map_err(|err| util::hardware_error("Failed to set the touchpad state", err))?;
+        tracing::info!("Touchpad enabled: {enabled}");
+        audit::record(
+            HardwareWrite {
+                device: "Touchpad".to_owned(),
+                attribute: "enabled".to_owned(),
+                old: Some(current.to_string()),
+                new: enabled.to_string(),
+            },
+            Cause::Override,
+        );
+        Ok(())
+    }
+
+    /// Whether the function keys send F1-F12 without holding Fn.
+    /// The state is restored after restarts and resume.
+    #[dbus_interface(property)]
+    async fn fn_lock(&self) -> fdo::Result<bool> {
+        self.fn_lock_attribute()?
+            .get()
+            .map_err(|err| util::hardware_error("Failed to read the Fn-lock", IoctlError::IO(err)))
+    }
+
+    #[dbus_interface(property)]
+    async fn set_fn_lock(&mut self, enabled: bool) -> fdo::Result<()> {
+        let fn_lock = self.fn_lock_attribute()?;
+        inhibit::check_write("set the Fn-lock")?;
+        let current = fn_lock.get().ok();
+        fn_lock.set(enabled).map_err(|err| {
+            util::hardware_error("Failed to set the Fn-lock", IoctlError::IO(err))
+        })?;
+        tracing::info!("Fn-lock: {enabled}");
+        fn_lock::store(enabled);
+        audit::record(
+            HardwareWrite {
+                device: "Keyboard".to_owned(),
+                attribute: "Fn-lock".to_owned(),
+                old: current.map(|current| current.to_string()),
+                new: enabled.to_string(),
+            },
+            Cause::Override,
+        );
+        Ok(())
+    }
+}
@@ -1,74 +1,90 @@
-use tailor_api::{Color, ColorProfile, ProfileInfo};
-use zbus::{dbus_interface, fdo};
+use tailor_api::{
+    Color, ColorProfile, EventKind, HardwareWrite, LedDeviceState, ProfileInfo, TailorError,
+};
+use tuxedo_ioctl::hal::IoctlError;
+use tuxedo_sysfs::keyboard::{KeyboardParameters, Parameter};
+use zbus::{dbus_interface, fdo, Connection, MessageHeader, SignalContext};
 
 use crate::{
+    audit::{self, Cause},
+    events, inhibit, keyboard,
     led::LedRuntimeHandle,
-    profiles::{Profile, KEYBOARD_DIR, PROFILE_DIR},
+    profiles::{Profile, KEYBOARD_DIR, KEYBOARD_SUBDIR, PROFILE_DIR},
+    scope::{self, UserScope},
     util,
 };
 
 pub struct LedInterface {
     pub handles: Vec<LedRuntimeHandle>,
+    /// `None` if tuxedo_keyboard isn't loaded.
+    pub keyboard_parameters: Option<KeyboardParameters>,
 }
 
 #[dbus_interface(name = "com.tux.Tailor.Led")]
 impl LedInterface {
     async fn add_profile(&self, name: &str, value: &str) -> fdo::Result<()> {
         // Verify correctness of the file.
-        serde_json::from_str::<ColorProfile>(value)
-            .map_err(|err| fdo::Error::InvalidArgs(err.to_string()))?;
-        util::write_file(KEYBOARD_DIR, name, value.as_bytes()).await?;
+        util::parse_json::<ColorProfile>(name, value)?;
+        util::write_file(&KEYBOARD_DIR, name, value.as_bytes()).await?;
 
         // Reload if the keyboard profile is part of the active global profile
         let info = Profile::get_active_profile_info()?;
         if info.leds.iter().any(|prof| prof.profile == name) {
             let info = Profile::load();
             for handle in &self.handles {
-                let profile = info
-                    .leds
-                    .iter()
-                    .find_map(|(info, profile)| {
-                        if info == &handle.info {
-                            Some(profile.clone())
-                        } else {
-                            None
-                        }
-                    })
-                    .unwrap_or_else(|| ColorProfile::default(handle.info.mode));
-
-                handle.profile_sender.send(profile).await.unwrap();
+                handle
+                    .set_profile(info.led_profile(&handle.info))
+                    .await
+                    .unwrap();
             }
         }
         Ok(())
     }
 
-    async fn get_profile(&self, name: &str) -> fdo::Result<String> {
-        util::read_file(KEYBOARD_DIR, name).await
+    /// Get a profile, preferring the profiles of the calling user.
+    async fn get_profile(
+        &self,
+        name: &str,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(header)] header: MessageHeader<'_>,
+    ) -> fdo::Result<String> {
+        let user = UserScope::of_caller(connection, &header).await?;
+        scope::read_profile_json(user.as_ref(), &KEYBOARD_DIR, KEYBOARD_SUBDIR, name).await
     }
 
-    async fn list_profiles(&self) -> fdo::Result<Vec<String>> {
-        util::get_profiles(KEYBOARD_DIR).await
+    /// List the system profiles along with the profiles of the calling user.
+    async fn list_profiles(
+        &self,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(header)] header: MessageHeader<'_>,
+    ) -> fdo::Result<Vec<String>> {
+        let user = UserScope::of_caller(connection, &header).await?;
+        scope::list_profiles(user.as_ref(), &KEYBOARD_DIR, KEYBOARD_SUBDIR).await
     }
 
     async fn remove_profile(&self, name: &str) -> fdo::Result<()> {
-        util::remove_file(KEYBOARD_DIR, name).await
+        util::remove_file(&KEYBOARD_DIR, name).await
     }
 
     async fn rename_profile(&self, from: &str, to: &str) -> fdo::Result<Vec<String>> {
-        if self.list_profiles().await?.contains(&to.to_string()) {
-            Err(fdo::Error::InvalidArgs(format!(
-                "File `{to}` already exists"
-            )))
+        if util::get_profiles(&KEYBOARD_DIR)
+            .await?
+            .contains(&to.to_string())
+        {
+            Err(util::tailor_error(TailorError::already_exists(format!(
+                "Profile `{to}` already exists"
+            ))))
         } else {
-            let profiles = util::get_profiles(PROFILE_DIR).await?;
+            let profiles = util::get_profiles(&PROFILE_DIR).await?;
 
             for profile in profiles {
-                let mut data =
-                    if let Ok(data) = util::read_json::<ProfileInfo>(PROFILE_DIR, &profile).await {
-                        data
-                    } else {
-                        continue;
-                    };
+                let mut data = if let Ok(data) =
+                    util::read_profile::<ProfileInfo>(&PROFILE_DIR, &profile).await
+                {
+                    data
+                } else {
+                    continue;
+                };
                 let mut changed = false;
 
                 for led in &mut data.leds {
@@ -79,19 +95,18 @@ impl LedInterface {
                 }
 
                 if changed {
-                    util::write_json(PROFILE_DIR, &profile, &data).await?;
+                    util::write_profile(&PROFILE_DIR, &profile, &data).await?;
                 }
             }
 
-            util::move_file(KEYBOARD_DIR, from, to).await?;
+            util::move_file(&KEYBOARD_DIR, from, to).await?;
 
-            self.list_profiles().await
+            util::get_profiles(&KEYBOARD_DIR).await
         }
     }
 
     async fn override_color(&mut self, color: &str) -> fdo::Result<()> {
-        let color: Color =
-            serde_json::from_str(color).map_err(|err| fdo::Error::InvalidArgs(err.to_string()))?;
+        let color: Color = util::parse_json("color", color)?;
         for handle in &self.handles {
             handle
                 .color_sender
@@ -101,4 +116,128 @@ impl LedInterface {
         }
         Ok(())
     }
+
+    /// List all LED devices along with their current state.
+    async fn list_devices(&self) -> fdo::Result<String> {
+        let devices: Vec<LedDeviceState> = self
+            .handles
+            .iter()
+            .map(|handle| handle.state_receiver.borrow().clone())
+            .collect();
+        serde_json::to_string(&devices).map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Replace the colors of a LED device until the override is cleared
+    /// or a profile is (re)loaded. An empty device id selects all devices.
+    async fn set_color_override(&mut self, device: &str, color: &str) -> fdo::Result<()> {
+        let color: Color = util::parse_json("color", color)?;
+        self.send_color_override(device, Some(color)).await
+    }
+
+    /// Clear a color override and continue with the active profile.
+    /// An empty device id selects all devices.
+    async fn clear_color_override(&mut self, device: &str) -> fdo::Result<()> {
+        self.send_color_override(device, None).await
+    }
+
+    /// List the parameters of tuxedo_keyboard with their values, for keyboards
+    /// that the driver doesn't register as LED devices.
+    async fn list_keyboard_parameters(&self) -> fdo::Result<Vec<(String, String)>> {
+        let parameters = self.keyboard_parameters()?;
+        parameters
+            .available()
+            .into_iter()
+            .map(|parameter| {
+                let value = parameters.get(parameter).map_err(|err| {
+                    util::hardware_error(
+                        &format!("Failed to read keyboard parameter `{}`", parameter.name()),
+                        IoctlError::IO(err),
+                    )
+                })?;
+                Ok((parameter.name().to_owned(), value))
+            })
+            .collect()
+    }
+
+    /// Set a parameter of tuxedo_keyboard, e.g. `color_left` to `ff0000`.
+    /// The value is restored when tailord starts.
+    async fn set_keyboard_parameter(&self, name: &str, value: &str) -> fdo::Result<()> {
+        let parameters = self.keyboard_parameters()?;
+        let parameter = Parameter::from_name(name)
+            .filter(|parameter| parameters.available().contains(parameter))
+            .ok_or_else(|| {
+                fdo::Error::InvalidArgs(format!("Unknown keyboard parameter `{name}`"))
+            })?;
+        let driver_value = parameter.parse(value).map_err(fdo::Error::InvalidArgs)?;
+        inhibit::check_write("set keyboard parameters")?;
+        let old = parameters.get(parameter).ok();
+        parameters.set(parameter, &driver_value).map_err(|err| {
+            util::hardware_error(
+                &format!("Failed to set keyboard parameter `{name}`"),
+                IoctlError::IO(err),
+            )
+        })?;
+        tracing::info!("Keyboard parameter `{name}`: {value}");
+        keyboard::store(parameter, &driver_value);
+        audit::record(
+            HardwareWrite {
+                device: "tuxedo_keyboard".to_owned(),
+                attribute: name.to_owned(),
+                old,
+                new: parameters
+                    .get(parameter)
+                    .unwrap_or_else(|_| value.to_owned()),
+            },
+            Cause::Override,
+        );
+        Ok(())
+    }
+
+    /// Emitted when the brightness of a LED device was changed
+    /// with the brightness keys of the keyboard.
+    #[dbus_interface(signal)]
+    pub async fn brightness_changed(
+        ctxt: &SignalContext<'_>,
+        device: &str,
+        brightness: u8,
+    ) -> zbus::Result<()>;
+}
+
+impl LedInterface {
+    fn keyboard_parameters(&self) -> fdo::Result<&KeyboardParameters> {
+        self.keyboard_parameters.as_ref().ok_or_else(|| {
+            util::tailor_error(TailorError::not_supported("tuxedo_keyboard isn't loaded"))
+        })
+    }
+
+    /// Get the handles matching the device id, or all handles if the id is empty.
+    fn select_handles(&self, device: &str) -> fdo::Result<Vec<&LedRuntimeHandle>> {
+        let handles: Vec<&LedRuntimeHandle> = self
+            .handles
+            .iter()
+            .filter(|handle| device.is_empty() || handle.info.device_id() == device)
+            .collect();
+        if handles.is_empty() && !device.is_empty() {
+            Err(fdo::Error::InvalidArgs(format!(
+                "No LED device called `{device}`"
+            )))
+        } else {
+            Ok(handles)
+        }
+    }
+
+    async fn send_color_override(&self, device: &str, color: Option<Color>) -> fdo::Result<()> {
+        for handle in self.select_handles(device)? {
+            handle
+                .color_override_sender
+                .send(color.clone())
+                .await
+                .map_err(|err| fdo::Error::Failed(format!("Internal error: `{err}`")))?;
+        }
+        events::record(EventKind::LedColorOverride {
+            device: (!device.is_empty()).then(|| device.to_owned()),
+            color,
+        });
+        Ok(())
+    }
 }
@@ -1,9 +1,21 @@
+mod battery;
+#[cfg(feature = "debug-ioctl")]
+mod debug;
+mod events;
 mod fan;
+mod input;
 mod led;
 mod performance;
 mod profiles;
+mod webcam;
 
+pub use battery::BatteryInterface;
+#[cfg(feature = "debug-ioctl")]
+pub use debug::DebugInterface;
+pub use events::EventsInterface;
 pub use fan::FanInterface;
+pub use input::InputInterface;
 pub use led::LedInterface;
 pub use performance::PerformanceInterface;
-pub use profiles::ProfileInterface;
+pub use profiles::{ProfileInterface, Reloads};
+pub use webcam::WebcamInterface;
@@ -1,6 +1,11 @@
+use tailor_api::TailorError;
 use zbus::{dbus_interface, fdo};
 
-use crate::performance::PerformanceProfileRuntimeHandle;
+use crate::{
+    audit::{self, Cause},
+    performance::{self, PerformanceProfileRuntimeHandle},
+    util,
+};
 
 pub struct PerformanceInterface {
     pub handler: Option<PerformanceProfileRuntimeHandle>,
@@ -8,37 +13,33 @@ pub struct PerformanceInterface {
 
 impl PerformanceInterface {
     fn handler(&self) -> fdo::Result<&PerformanceProfileRuntimeHandle> {
-        self.handler.as_ref().ok_or(fdo::Error::Failed(
-            "No performance profile handler available".to_string(),
-        ))
+        self.handler.as_ref().ok_or_else(no_handler)
     }
+}
 
-    fn handler_mut(&mut self) -> fdo::Result<&mut PerformanceProfileRuntimeHandle> {
-        self.handler.as_mut().ok_or(fdo::Error::Failed(
-            "No performance profile handler available".to_string(),
-        ))
-    }
+fn no_handler() -> fdo::Error {
+    util::tailor_error(TailorError::not_supported(
+        "No performance profile handler available",
+    ))
 }
 
 #[dbus_interface(name = "com.tux.Tailor.Performance")]
 impl PerformanceInterface {
     /// Temporarily override the performance profile. Please note that this will not survive a
     /// restart as the performance profile is handled by the overall profile configuration.
-    async fn set_profile(&mut self, name: &str) -> fdo::Result<()> {
-        self.handler()?
-            .profile_sender
-            .send(name.to_string())
-            .await
-            .map_err(|err| {
-                fdo::Error::IOError(format!("unable to set performance profile {name}: {err}"))
-            })?;
-        self.handler_mut()?.set_active_performance_profile(name);
+    async fn set_profile(&self, name: &str) -> fdo::Result<()> {
+        let handler = self.handler()?;
+        let old = handler.get_active_performance_profile();
+        handler.apply(name).await.map_err(|err| {
+            util::hardware_error(&format!("unable to set performance profile {name}"), err)
+        })?;
+        audit::record(performance::audit_write(old, name), Cause::Override);
         Ok(())
     }
 
     /// Read the current performance profile.
     async fn get_profile(&self) -> fdo::Result<String> {
-        Ok(self.handler()?.get_active_performance_profile().to_string())
+        Ok(self.handler()?.get_active_performance_profile())
     }
 
     /// Read the list of supported performance profiles.
@@ -46,9 +47,7 @@ impl PerformanceInterface {
         self.handler()?
             .get_availables_performance_profiles()
             .map_err(|err| {
-                fdo::Error::IOError(format!(
-                    "unable to list available performance profiles: {err}"
-                ))
+                util::hardware_error("unable to list available performance profiles", err)
             })
     }
 }
@@ -1,67 +1,258 @@
-use tailor_api::{ColorProfile, LedDeviceInfo, ProfileInfo};
-use zbus::{dbus_interface, fdo};
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use tailor_api::{
+    ColorProfile, DeviceInfo, HardwareCapabilities, HardwareWrite, LedDeviceInfo, ProfileInfo,
+    ProfileOrder, ProfileOwner, ProfileScope, TailorError,
+};
+use tokio::sync::{watch, Mutex};
+use zbus::{dbus_interface, fdo, Connection, MessageHeader, SignalContext};
 
 use crate::{
+    aspm,
+    audit::{self, Cause},
+    cpufreq,
     fancontrol::FanRuntimeHandle,
     led::LedRuntimeHandle,
     performance::PerformanceProfileRuntimeHandle,
-    profiles::{Profile, PROFILE_DIR},
-    util,
+    platform_profile::PlatformProfile,
+    power_tuning,
+    profiles::{Profile, SupportedFeatures, ACTIVE_PROFILE_PATH, PROFILE_DIR, PROFILE_SUBDIR},
+    scope::{self, UserScope},
+    usb, util,
 };
 
+/// How long reloads wait for newer reloads before applying the profile.
+const RELOAD_SETTLE_TIME: Duration = Duration::from_millis(200);
+
+/// The parts of a profile, in the order they are applied.
+const FAN_PROFILES: &str = "fan profiles";
+const KEYBOARD_PROFILES: &str = "keyboard profiles";
+const PERFORMANCE_PROFILE: &str = "performance profile";
+const CPU_FREQUENCY_LIMIT: &str = "CPU frequency limit";
+const USB_AUTOSUSPEND: &str = "USB autosuspend";
+const PCIE_ASPM_POLICY: &str = "PCIe ASPM policy";
+const POWER_TUNING: &str = "power tuning";
+
+/// Coalesces reloads that are requested in quick succession.
+#[derive(Debug)]
+pub struct Reloads {
+    /// Incremented for every reload that waits to be applied.
+    requests: AtomicU64,
+    /// The request of the last coalesced reload that was applied and
+    /// its result, awaited by the reloads it superseded.
+    finished: watch::Sender<(u64, fdo::Result<()>)>,
+    /// Held while a profile is applied, contains the
+    /// last profile that was applied completely.
+    applied: Mutex<Option<AppliedProfile>>,
+}
+
+impl Default for Reloads {
+    fn default() -> Self {
+        Self {
+            requests: AtomicU64::default(),
+            finished: watch::channel((0, Ok(()))).0,
+            applied: Mutex::default(),
+        }
+    }
+}
+
+/// A profile that was applied completely, restored if the next one fails.
+#[derive(Debug)]
+struct AppliedProfile {
+    name: String,
+    profile: Profile,
+    /// The target of the active profile link.
+    link: Option<PathBuf>,
+    owner: Option<ProfileOwner>,
+}
+
 pub struct ProfileInterface {
     pub fan_handles: Vec<FanRuntimeHandle>,
     pub led_handles: Vec<LedRuntimeHandle>,
     pub performance_profile_handle: Option<PerformanceProfileRuntimeHandle>,
+    /// The capabilities that don't change at runtime.
+    pub hardware: HardwareCapabilities,
+    pub model_id: Option<String>,
+    /// Collected at startup.
+    pub device: DeviceInfo,
+    pub reloads: Reloads,
 }
 
 #[dbus_interface(name = "com.tux.Tailor.Profiles")]
 impl ProfileInterface {
     async fn add_profile(&self, name: &str, value: &str) -> fdo::Result<()> {
         // Verify correctness of the file.
-        serde_json::from_str::<ProfileInfo>(value)
-            .map_err(|err| fdo::Error::InvalidArgs(err.to_string()))?;
+        let info = util::parse_json::<ProfileInfo>(name, value)?;
+
+        if let Some(max_freq_mhz) = info.max_freq_mhz {
+            let limits = cpufreq::frequency_limits()
+                .map_err(|err| fdo::Error::IOError(err.to_string()))?
+                .ok_or_else(|| {
+                    util::tailor_error(TailorError::not_supported(
+                        "CPU frequency scaling isn't available",
+                    ))
+                })?;
+            if !limits.contains(max_freq_mhz) {
+                return Err(util::tailor_error(TailorError::invalid_profile(format!(
+                    "CPU frequency cap of {max_freq_mhz} MHz is outside of the supported range of {} to {} MHz",
+                    limits.min_mhz, limits.max_mhz
+                ))));
+            }
+        }
+
+        if info.pcie_aspm.is_some() && !self.hardware.pcie_aspm {
+            return Err(util::tailor_error(TailorError::not_supported(
+                "The PCIe ASPM policy can't be changed on this kernel",
+            )));
+        }
 
-        util::write_file(PROFILE_DIR, name, value.as_bytes()).await
+        util::write_file(&PROFILE_DIR, name, value.as_bytes()).await
     }
 
-    async fn get_profile(&self, name: &str) -> fdo::Result<String> {
-        util::read_file(PROFILE_DIR, name).await
+    /// Get a profile, preferring the profiles of the calling user.
+    async fn get_profile(
+        &self,
+        name: &str,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(header)] header: MessageHeader<'_>,
+    ) -> fdo::Result<String> {
+        let user = UserScope::of_caller(connection, &header).await?;
+        scope::read_profile_json(user.as_ref(), &PROFILE_DIR, PROFILE_SUBDIR, name).await
     }
 
-    async fn list_profiles(&self) -> fdo::Result<Vec<String>> {
-        util::get_profiles(PROFILE_DIR).await
+    /// List the system profiles along with the profiles of the calling user.
+    async fn list_profiles(
+        &self,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(header)] header: MessageHeader<'_>,
+    ) -> fdo::Result<Vec<String>> {
+        let user = UserScope::of_caller(connection, &header).await?;
+        let mut profiles =
+            scope::list_profiles(user.as_ref(), &PROFILE_DIR, PROFILE_SUBDIR).await?;
+        Profile::load_order().await.sort(&mut profiles);
+        Ok(profiles)
     }
 
     async fn remove_profile(&self, name: &str) -> fdo::Result<()> {
-        util::remove_file(PROFILE_DIR, name).await
+        util::remove_file(&PROFILE_DIR, name).await?;
+        let mut order = Profile::load_order().await;
+        if order.remove(name) {
+            Profile::save_order(&order).await?;
+        }
+        Ok(())
+    }
+
+    /// The order in which clients list the profiles, favorites first.
+    async fn get_profile_order(&self) -> fdo::Result<String> {
+        serde_json::to_string(&Profile::load_order().await)
+            .map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    async fn set_profile_order(&self, value: &str) -> fdo::Result<()> {
+        let order = util::parse_json::<ProfileOrder>("profile order", value)?;
+        Profile::save_order(&order).await
     }
 
-    async fn rename_profile(&mut self, from: &str, to: &str) -> fdo::Result<Vec<String>> {
-        if self.list_profiles().await?.contains(&to.to_string()) {
-            Err(fdo::Error::InvalidArgs(format!(
-                "File `{to}` already exists"
-            )))
+    async fn rename_profile(
+        &mut self,
+        from: &str,
+        to: &str,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> fdo::Result<Vec<String>> {
+        if util::get_profiles(&PROFILE_DIR)
+            .await?
+            .contains(&to.to_string())
+        {
+            Err(util::tailor_error(TailorError::already_exists(format!(
+                "Profile `{to}` already exists"
+            ))))
         } else {
-            util::move_file(PROFILE_DIR, from, to).await?;
+            util::move_file(&PROFILE_DIR, from, to).await?;
 
-            if self.get_active_profile_name().await? == from {
-                self.set_active_profile_name(to).await?;
+            let mut order = Profile::load_order().await;
+            if order.rename(from, to) {
+                Profile::save_order(&order).await?;
+            }
+
+            if Profile::get_active_user_scope().is_none()
+                && Profile::get_active_profile_name().await? == from
+            {
+                Profile::set_active_profile_name(to, None, Profile::get_active_profile_owner())
+                    .await?;
                 self.reload().await?;
+                Self::emit_active_profile_changed(&ctxt).await?;
             }
 
-            self.list_profiles().await
+            let mut profiles = util::get_profiles(&PROFILE_DIR).await?;
+            order.sort(&mut profiles);
+            Ok(profiles)
         }
     }
 
-    async fn set_active_profile_name(&self, name: &str) -> fdo::Result<()> {
-        Profile::set_active_profile_name(name).await
+    /// Activate a profile, preferring the profiles of the calling user.
+    /// The last caller wins and becomes the owner of the active profile.
+    /// Other sessions are informed by the `ActiveProfileChanged` signal.
+    async fn set_active_profile_name(
+        &self,
+        name: &str,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(header)] header: MessageHeader<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> fdo::Result<()> {
+        let uid = scope::caller_uid(connection, &header).await?;
+        let user = uid.and_then(UserScope::of_uid);
+        let owner = uid.map(scope::profile_owner);
+        Profile::set_active_profile_name(name, user.as_ref(), owner).await?;
+        Self::emit_active_profile_changed(&ctxt).await
+    }
+
+    /// List the values that activating a profile would change, without
+    /// changing them, preferring the profiles of the calling user.
+    async fn preview_profile(
+        &self,
+        name: &str,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(header)] header: MessageHeader<'_>,
+    ) -> fdo::Result<String> {
+        let user = UserScope::of_caller(connection, &header).await?;
+        let profile = Profile::load_named(name, user.as_ref())?;
+        let writes = self.preview(&profile).await?;
+        serde_json::to_string(&writes).map_err(|err| fdo::Error::Failed(err.to_string()))
     }
 
     async fn get_active_profile_name(&self) -> fdo::Result<String> {
         Profile::get_active_profile_name().await
     }
 
+    /// Get the user that activated the current profile.
+    /// Returns `null` if the daemon activated the profile.
+    async fn get_active_profile_owner(&self) -> fdo::Result<String> {
+        serde_json::to_string(&Profile::get_active_profile_owner())
+            .map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Emitted with the name and the owner of the profile
+    /// whenever another profile is activated.
+    #[dbus_interface(signal)]
+    async fn active_profile_changed(
+        ctxt: &SignalContext<'_>,
+        name: &str,
+        owner: &str,
+    ) -> zbus::Result<()>;
+
+    /// Get whether the active profile is a system or a user profile.
+    async fn get_active_profile_scope(&self) -> fdo::Result<String> {
+        let scope = match Profile::get_active_user_scope() {
+            Some(_) => ProfileScope::User,
+            None => ProfileScope::System,
+        };
+        serde_json::to_string(&scope).map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
     async fn get_number_of_fans(&self) -> fdo::Result<u8> {
         Ok(self.fan_handles.len() as u8)
     }
@@ -75,45 +266,391 @@ impl ProfileInterface {
         Ok(serde_json::to_string(&devices).unwrap())
     }
 
-    async fn reload(&mut self) -> fdo::Result<()> {
+    async fn get_hardware_capabilities(&self) -> fdo::Result<String> {
+        let capabilities = HardwareCapabilities {
+            num_of_fans: self.fan_handles.len() as u8,
+            led_devices: self
+                .led_handles
+                .iter()
+                .map(|handle| handle.info.clone())
+                .collect(),
+            ..self.hardware.clone()
+        };
+        Ok(serde_json::to_string(&capabilities).unwrap())
+    }
+
+    async fn get_device_info(&self) -> fdo::Result<String> {
+        Ok(serde_json::to_string(&self.device).unwrap())
+    }
+
+    /// Restore the default profile and the presets, discarding changes to them.
+    async fn reset_default_profiles(&mut self) -> fdo::Result<()> {
+        let supported_features = SupportedFeatures {
+            leds: self
+                .led_handles
+                .iter()
+                .map(|handle| handle.info.clone())
+                .collect(),
+            num_of_fans: self.fan_handles.len() as u8,
+            performance_profiles: self.hardware.performance_profiles.clone(),
+            model_id: self.model_id.clone(),
+        };
+        Profile::reset_defaults(&supported_features)?;
+        // The active profile might be one of them.
+        if Profile::get_active_user_scope().is_none() {
+            self.reload_active_profile(None).await?;
+        }
+        Ok(())
+    }
+
+    async fn reload(&self) -> fdo::Result<()> {
+        self.reload_latest_profile(None).await
+    }
+}
+
+impl ProfileInterface {
+    /// Inform all sessions about the active profile and its owner.
+    pub async fn emit_active_profile_changed(ctxt: &SignalContext<'_>) -> fdo::Result<()> {
+        let name = Profile::get_active_profile_name().await?;
+        let owner = serde_json::to_string(&Profile::get_active_profile_owner())
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        Ok(Self::active_profile_changed(ctxt, &name, &owner).await?)
+    }
+
+    /// Apply the active profile once no other reload was requested for
+    /// [`RELOAD_SETTLE_TIME`], so that quickly switching between profiles
+    /// only applies the last one instead of every profile in between.
+    /// Superseded reloads return the result of the reload that replaced them.
+    pub async fn reload_latest_profile(&self, rule: Option<&'static str>) -> fdo::Result<()> {
+        let request = self.reloads.requests.fetch_add(1, Ordering::SeqCst) + 1;
+        tokio::time::sleep(RELOAD_SETTLE_TIME).await;
+        let latest = self.reloads.requests.load(Ordering::SeqCst);
+        if latest != request {
+            tracing::debug!("Waiting for a newer reload to apply the active profile");
+            let mut finished = self.reloads.finished.subscribe();
+            let finished = finished
+                .wait_for(|(finished, _)| *finished >= latest)
+                .await
+                .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+            return finished.1.clone();
+        }
+        let result = self.reload_active_profile(rule).await;
+        self.reloads.finished.send_if_modified(|finished| {
+            // Keep the result of the newest reload if they finish out of order.
+            if finished.0 < request {
+                *finished = (request, result.clone());
+                true
+            } else {
+                false
+            }
+        });
+        result
+    }
+
+    /// Load the active profile and send it to all runtimes. If a part
+    /// of it fails, the previous profile is applied again, so the
+    /// hardware doesn't end up with a mix of both profiles.
+    /// `rule` is recorded in the audit log as the reason for the reload.
+    pub async fn reload_active_profile(&self, rule: Option<&'static str>) -> fdo::Result<()> {
+        let mut applied = self.reloads.applied.lock().await;
+        let name = Profile::get_active_profile_name().await.unwrap_or_default();
+        let profile = Profile::load();
+        let previous = applied.as_ref().map(|previous| &previous.profile);
+        let planned = self.audited_writes(previous, &profile);
+        let result = self.apply(&profile).await;
+        record_writes(
+            planned,
+            &result,
+            Cause::Profile {
+                name: name.clone(),
+                rule,
+            },
+        );
+        let Err(failure) = result else {
+            *applied = Some(AppliedProfile {
+                name,
+                profile,
+                link: std::fs::read_link(&*ACTIVE_PROFILE_PATH).ok(),
+                owner: Profile::get_active_profile_owner(),
+            });
+            return Ok(());
+        };
+
+        let rolled_back = match applied.as_ref() {
+            Some(previous) => {
+                let planned = self.audited_writes(Some(&profile), &previous.profile);
+                let result = self.apply(&previous.profile).await;
+                record_writes(
+                    planned,
+                    &result,
+                    Cause::Rollback {
+                        name: previous.name.clone(),
+                    },
+                );
+                // The link points to the previous profile again, also if
+                // restoring failed, so the next reload retries it.
+                if let Some(link) = &previous.link {
+                    if let Err(err) = Profile::restore_active_profile(
+                        &previous.name,
+                        link,
+                        previous.owner.clone(),
+                    ) {
+                        tracing::error!("Failed to restore the active profile link: `{err}`");
+                    }
+                }
+                match result {
+                    Ok(()) => {
+                        tracing::warn!(
+                            "Restored the previous profile, failed to apply the {}: {}",
+                            failure.failed,
+                            failure.message
+                        );
+                        true
+                    }
+                    Err(err) => {
+                        tracing::error!(
+                            "Failed to restore the previous profile, failed to apply the {}: {}",
+                            err.failed,
+                            err.message
+                        );
+                        false
+                    }
+                }
+            }
+            // Nothing was applied since the start.
+            None => false,
+        };
+        Err(util::tailor_error(TailorError::PartiallyApplied {
+            applied: failure
+                .applied
+                .iter()
+                .map(|part| part.to_string())
+                .collect(),
+            failed: failure.failed.to_owned(),
+            rolled_back,
+            message: format!(
+                "Failed to apply the {}: {}",
+                failure.failed, failure.message
+            ),
+        }))
+    }
+
+    /// The writes of [`Self::apply`] that change a value. Fan curves and
+    /// colors are compared with the last profile that was applied.
+    async fn preview(&self, profile: &Profile) -> fdo::Result<Vec<HardwareWrite>> {
+        let applied = self.reloads.applied.lock().await;
+        let previous = applied.as_ref().map(|previous| &previous.profile);
+        let writes = self.planned_writes(previous, profile)?;
+        Ok(writes.into_iter().flat_map(|(_, writes)| writes).collect())
+    }
+
+    /// The planned writes for the audit log, empty if it's disabled.
+    fn audited_writes(
+        &self,
+        previous: Option<&Profile>,
+        profile: &Profile,
+    ) -> Vec<(&'static str, Vec<HardwareWrite>)> {
+        if !audit::enabled() {
+            return Vec::new();
+        }
+        self.planned_writes(previous, profile)
+            .unwrap_or_else(|err| {
+                tracing::warn!("Failed to read the values for the audit log: `{err}`");
+                Vec::new()
+            })
+    }
+
+    /// The writes that change a value, grouped by the parts of [`Self::apply`].
+    fn planned_writes(
+        &self,
+        previous: Option<&Profile>,
+        profile: &Profile,
+    ) -> fdo::Result<Vec<(&'static str, Vec<HardwareWrite>)>> {
+        let changed = |device: String, attribute: &str, old: Option<String>, new: String| {
+            (old.as_ref() != Some(&new)).then(|| HardwareWrite {
+                device,
+                attribute: attribute.to_owned(),
+                old,
+                new,
+            })
+        };
+
+        let fans = (0..self.fan_handles.len())
+            .filter_map(|idx| {
+                let fan = |profile: &Profile| profile.fans.get(idx).cloned().unwrap_or_default();
+                changed(
+                    format!("Fan {}", idx + 1),
+                    "fan curve",
+                    previous.map(|previous| fan(previous).describe()),
+                    fan(profile).describe(),
+                )
+            })
+            .collect();
+        let leds = self
+            .led_handles
+            .iter()
+            .filter_map(|led_handle| {
+                let info = &led_handle.info;
+                changed(
+                    info.device_id(),
+                    "colors",
+                    previous.map(|previous| describe_colors(&previous.led_profile(info))),
+                    describe_colors(&profile.led_profile(info)),
+                )
+            })
+            .collect();
+        let mut writes = vec![(FAN_PROFILES, fans), (KEYBOARD_PROFILES, leds)];
+
+        if let (Some(perf_handle), Some(performance_profile)) = (
+            &self.performance_profile_handle,
+            &profile.performance_profile,
+        ) {
+            let performance_profile = performance_profile.to_string();
+            let platform_write = PlatformProfile::new()
+                .and_then(|platform| platform.preview_odm(&performance_profile));
+            let mut performance: Vec<_> = changed(
+                "Embedded controller".to_owned(),
+                "performance profile",
+                Some(perf_handle.get_active_performance_profile()),
+                performance_profile,
+            )
+            .into_iter()
+            .collect();
+            performance.extend(platform_write);
+            writes.push((PERFORMANCE_PROFILE, performance));
+        }
+
+        let io_error = |err: std::io::Error| fdo::Error::IOError(err.to_string());
+        writes.extend([
+            (
+                CPU_FREQUENCY_LIMIT,
+                cpufreq::preview_max_freq(profile.max_freq_mhz).map_err(io_error)?,
+            ),
+            (
+                USB_AUTOSUSPEND,
+                usb::preview_autosuspend(profile.usb_autosuspend.as_ref()).map_err(io_error)?,
+            ),
+            (
+                PCIE_ASPM_POLICY,
+                aspm::preview_policy(profile.pcie_aspm)
+                    .map_err(io_error)?
+                    .into_iter()
+                    .collect(),
+            ),
+            (
+                POWER_TUNING,
+                power_tuning::preview(profile.power_tuning.as_ref()).map_err(io_error)?,
+            ),
+        ]);
+        Ok(writes)
+    }
+
+    /// Apply the parts of a profile in order and stop at the first failure.
+    async fn apply(&self, profile: &Profile) -> Result<(), ApplyFailure> {
         let Profile {
             fans,
-            leds,
             performance_profile,
-        } = Profile::load();
+            max_freq_mhz,
+            usb_autosuspend,
+            pcie_aspm,
+            power_tuning,
+            ..
+        } = profile;
+        let mut applied = Vec::new();
+        let fail = |applied: &Vec<_>, failed, message: String| ApplyFailure {
+            applied: applied.clone(),
+            failed,
+            message,
+        };
 
         for (idx, fan_handle) in self.fan_handles.iter().enumerate() {
             let profile = fans.get(idx).cloned().unwrap_or_default();
-            fan_handle
-                .profile_sender
-                .send(profile)
-                .await
-                .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+            if let Err(err) = fan_handle.apply_profile(profile).await {
+                return Err(fail(&applied, FAN_PROFILES, err));
+            }
         }
+        applied.push(FAN_PROFILES);
 
         for led_handle in &self.led_handles {
-            let profile = match leds.get(&led_handle.info).cloned() {
-                Some(color_profile) => color_profile,
-                None => ColorProfile::default(led_handle.info.mode),
-            };
-            led_handle
-                .profile_sender
-                .send(profile)
-                .await
-                .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+            let colors = profile.led_profile(&led_handle.info);
+            if let Err(err) = led_handle.apply_profile(colors).await {
+                return Err(fail(&applied, KEYBOARD_PROFILES, err));
+            }
         }
+        applied.push(KEYBOARD_PROFILES);
 
-        if let Some(perf_handle) = self.performance_profile_handle.as_mut() {
-            if let Some(performance_profile) = performance_profile {
-                perf_handle
-                    .profile_sender
-                    .send(performance_profile.to_string())
-                    .await
-                    .map_err(|err| fdo::Error::Failed(err.to_string()))?;
-                perf_handle.set_active_performance_profile(&performance_profile.to_string());
+        if let (Some(perf_handle), Some(performance_profile)) =
+            (&self.performance_profile_handle, performance_profile)
+        {
+            if let Err(err) = perf_handle.apply(&performance_profile.to_string()).await {
+                return Err(fail(&applied, PERFORMANCE_PROFILE, err.to_string()));
             }
+            applied.push(PERFORMANCE_PROFILE);
         }
 
+        let settings: [(&'static str, &dyn Fn() -> std::io::Result<()>); 4] = [
+            (CPU_FREQUENCY_LIMIT, &|| {
+                cpufreq::apply_max_freq(*max_freq_mhz)
+            }),
+            (USB_AUTOSUSPEND, &|| {
+                usb::apply_autosuspend(usb_autosuspend.as_ref())
+            }),
+            (PCIE_ASPM_POLICY, &|| aspm::apply_policy(*pcie_aspm)),
+            (POWER_TUNING, &|| power_tuning::apply(power_tuning.as_ref())),
+        ];
+        for (name, apply) in settings {
+            if let Err(err) = apply() {
+                return Err(fail(&applied, name, err.to_string()));
+            }
+            applied.push(name);
+        }
         Ok(())
     }
 }
+
+/// Record the planned writes of the parts that were applied.
+fn record_writes(
+    planned: Vec<(&'static str, Vec<HardwareWrite>)>,
+    result: &Result<(), ApplyFailure>,
+    cause: Cause,
+) {
+    for (part, writes) in planned {
+        if result
+            .as_ref()
+            .err()
+            .map_or(true, |failure| failure.applied.contains(&part))
+        {
+            for write in writes {
+                audit::record(write, cause.clone());
+            }
+        }
+    }
+}
+
+/// The part of a profile that couldn't be applied.
+struct ApplyFailure {
+    applied: Vec<&'static str>,
+    failed: &'static str,
+    message: String,
+}
+
+fn describe_colors(profile: &ColorProfile) -> String {
+    match profile {
+        ColorProfile::None => "unchanged".to_owned(),
+        ColorProfile::Single(color) => color.to_string(),
+        ColorProfile::Multiple(points) => {
+            let colors: Vec<String> = points.iter().map(|point| point.color.to_string()).collect();
+            format!("animation of {}", colors.join(", "))
+        }
+        ColorProfile::Typing(effect) => {
+            format!("typing effect from {} to {}", effect.idle, effect.pressed)
+        }
+        ColorProfile::Regions(regions) => {
+            let regions: Vec<String> = regions
+                .iter()
+                .map(|region| describe_colors(&region.profile))
+                .collect();
+            format!("regions with {}", regions.join("; "))
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use tailor_api::{HardwareWrite, TailorError};
+use tuxedo_ioctl::hal::traits::WebcamControl;
+use zbus::{dbus_interface, fdo};
+
+use crate::{
+    audit::{self, Cause},
+    inhibit, util,
+};
+
+pub struct WebcamInterface {
+    /// `None` if the device can't turn the webcam on and off.
+    pub webcam: Option<Arc<dyn WebcamControl>>,
+}
+
+impl WebcamInterface {
+    fn webcam(&self) -> fdo::Result<&dyn WebcamControl> {
+        self.webcam.as_deref().ok_or_else(|| {
+            util::tailor_error(TailorError::not_supported(
+                "The device can't turn the webcam on and off",
+            ))
+        })
+    }
+}
+
+// Methods instead of properties, since the hotkey of the
+// keyboard changes the state without a signal.
+#[dbus_interface(name = "com.tux.Tailor.Webcam")]
+impl WebcamInterface {
+    /// Whether the webcam is turned on in the hardware.
+    async fn get_enabled(&self) -> fdo::Result<bool> {
+        self.webcam()?
+            .get_webcam()
+            .map_err(|err| util::hardware_error("Failed to read the webcam state", err))
+    }
+
+    /// Turn the webcam on or off. Nothing is written if it's already in that state.
+    async fn set_enabled(&self, enabled: bool) -> fdo::Result<()> {
+        let webcam = self.webcam()?;
+        let current = webcam
+            .get_webcam()
+            .map_err(|err| util::hardware_error("Failed to read the webcam state", err))?;
+        if current == enabled {
+            return Ok(());
+        }
+        inhibit::check_write("set the webcam state")?;
+        webcam
+            .set_webcam(enabled)
+            .map_err(|err| util::hardware_error("Failed to set the webcam state", err))?;
+        tracing::info!("Webcam enabled: {enabled}");
+        audit::record(
+            HardwareWrite {
+                device: "Webcam".to_owned(),
+                attribute: "enabled".to_owned(),
+                old: Some(current.to_string()),
+                new: enabled.to_string(),
+            },
+            Cause::Override,
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+//! Identifiers and versions of the device and its drivers.
+
+use std::{fs, path::Path};
+
+use tailor_api::{DeviceInfo, DriverInfo, HardwareCapabilities};
+use tuxedo_ioctl::hal::{traits::HardwareDevice, Capabilities};
+
+const DMI_PATH: &str = "/sys/class/dmi/id";
+const SYSFS_MODULE_PATH: &str = "/sys/module";
+const KERNEL_VERSION_PATH: &str = "/proc/sys/kernel/osrelease";
+/// Kernel modules that belong to the tuxedo drivers.
+const MODULE_PREFIXES: [&str; 3] = ["tuxedo", "clevo", "uniwill"];
+
+/// How tailord controls the hardware.
+pub struct Interface<'a> {
+    pub name: String,
+    pub device: &'a dyn HardwareDevice,
+    /// Only known for the ioctl interface.
+    pub io_module_version: Option<String>,
+}
+
+pub fn collect(
+    interface: Option<Interface<'_>>,
+    capabilities: &Capabilities,
+    hardware: &HardwareCapabilities,
+) -> DeviceInfo {
+    let mut detected: Vec<String> = capabilities
+        .list()
+        .into_iter()
+        .map(|capability| format!("{capability:?}"))
+        .collect();
+    for (name, available) in [
+        ("BatteryConservation", hardware.battery_conservation),
+        ("BatteryCalibration", hardware.battery_calibration),
+        ("PcieAspm", hardware.pcie_aspm),
+    ] {
+        if available {
+            detected.push(name.to_owned());
+        }
+    }
+
+    let (interface, odm_interface, io_module_version, model_id) = match interface {
+        Some(interface) => (
+            Some(interface.name),
+            interface.device.device_interface_id_str().ok(),
+            interface.io_module_version,
+            interface.device.device_model_id_str().ok(),
+        ),
+        None => (None, None, None, None),
+    };
+
+    DeviceInfo {
+        vendor: dmi("sys_vendor"),
+        product_name: dmi("product_name"),
+        board_name: dmi("board_name"),
+        bios_version: dmi("bios_version"),
+        bios_date: dmi("bios_date"),
+        ec_version: dmi("ec_firmware_release"),
+        interface,
+        odm_interface: odm_interface.map(|id| id.trim().to_owned()),
+        io_module_version: io_module_version.map(|version| version.trim().to_owned()),
+        model_id: model_id.map(|id| id.trim().to_owned()),
+        kernel_version: read_trimmed(KERNEL_VERSION_PATH),
+        drivers: drivers(),
+        tailord_version: env!("CARGO_PKG_VERSION").to_owned(),
+        capabilities: detected,
+    }
+}
+
+fn dmi(name: &str) -> Option<String> {
+    read_trimmed(Path::new(DMI_PATH).join(name))
+}
+
+/// The content of a file, or `None` if it's missing or empty.
+fn read_trimmed(path: impl AsRef<Path>) -> Option<String> {
+    let value = fs::read_to_string(path).ok()?;
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_owned())
+}
+
+fn drivers() -> Vec<DriverInfo> {
+    let Ok(entries) = fs::read_dir(SYSFS_MODULE_PATH) else {
+        return Vec::new();
+    };
+    let mut drivers: Vec<DriverInfo> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            MODULE_PREFIXES
+                .iter()
+                .any(|prefix| name.starts_with(prefix))
+                .then(|| DriverInfo {
+                    version: read_trimmed(entry.path().join("version")),
+                    name,
+                })
+        })
+        .collect();
+    drivers.sort_by(|a, b| a.name.cmp(&b.name));
+    drivers
+}
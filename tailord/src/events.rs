@@ -0,0 +1,179 @@
+use std::{
+    io,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use tailor_api::{Event, EventKind};
+use tokio::{io::AsyncWriteExt, sync::mpsc};
+
+use crate::config;
+
+const EVENT_LOG: &str = "events.jsonl";
+/// The previous log file is kept after rotation.
+const ROTATED_EVENT_LOG: &str = "events.jsonl.1";
+/// Rotate the log file once it's larger than 1 MiB.
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+
+static EVENT_SENDER: OnceCell<mpsc::UnboundedSender<Event>> = OnceCell::new();
+
+/// Add an event to the event log.
+/// Events are dropped if the event log wasn't set up.
+pub fn record(kind: EventKind) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    tracing::debug!("Recording event: {kind}");
+    if let Some(sender) = EVENT_SENDER.get() {
+        sender.send(Event { timestamp, kind }).ok();
+    }
+}
+
+/// Writes recorded events to the event log.
+pub struct EventLogWriter {
+    receiver: mpsc::UnboundedReceiver<Event>,
+}
+
+impl EventLogWriter {
+    /// Set up the event log. Events that are recorded
+    /// before this is called will be dropped.
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        if EVENT_SENDER.set(sender).is_err() {
+            tracing::warn!("The event log was already set up");
+        }
+        Self { receiver }
+    }
+
+    pub async fn run(mut self) {
+        while let Some(event) = self.receiver.recv().await {
+            if let Err(err) = append_line(EVENT_LOG, ROTATED_EVENT_LOG, &event).await {
+                tracing::warn!(
+                    "Failed to write event to `{:?}`: `{err}`",
+                    config::state_dir()
+                );
+            }
+        }
+    }
+}
+
+/// Append a JSON line to a log file in the [`config::state_dir`] and move it
+/// to `rotated` first if it grew too large.
+pub async fn append_line(
+    name: &str,
+    rotated: &str,
+    value: &impl Serialize,
+) -> Result<(), io::Error> {
+    append_line_in(config::state_dir(), name, rotated, value).await
+}
+
+async fn append_line_in(
+    dir: &Path,
+    name: &str,
+    rotated: &str,
+    value: &impl Serialize,
+) -> Result<(), io::Error> {
+    let path = dir.join(name);
+    tokio::fs::create_dir_all(dir).await?;
+
+    if tokio::fs::metadata(&path)
+        .await
+        .is_ok_and(|metadata| metadata.len() > MAX_LOG_SIZE)
+    {
+        tokio::fs::rename(&path, dir.join(rotated)).await?;
+    }
+
+    let mut line = serde_json::to_string(value)?;
+    line.push('\n');
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .await?;
+    file.write_all(line.as_bytes()).await?;
+    // Tokio finishes writes in the background, which might
+    // otherwise still be pending when the log is read.
+    file.flush().await
+}
+
+/// Read the latest events from the event log, oldest first.
+pub async fn read_events(limit: usize) -> Result<Vec<Event>, io::Error> {
+    read_events_in(config::state_dir(), limit).await
+}
+
+async fn read_events_in(dir: &Path, limit: usize) -> Result<Vec<Event>, io::Error> {
+    let mut events = Vec::new();
+    for name in [ROTATED_EVENT_LOG, EVENT_LOG] {
+        let content = match tokio::fs::read_to_string(dir.join(name)).await {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        // Skip lines that were written by incompatible versions or are incomplete.
+        events.extend(
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str::<Event>(line).ok()),
+        );
+    }
+
+    let skip = events.len().saturating_sub(limit);
+    Ok(events.split_off(skip))
+}
+
+#[cfg(test)]
+mod test {
+    use tailor_api::{Event, EventKind};
+
+    use super::{append_line_in, read_events_in, EVENT_LOG, MAX_LOG_SIZE, ROTATED_EVENT_LOG};
+
+    fn event(timestamp: u64) -> Event {
+        Event {
+            timestamp,
+            kind: EventKind::CriticalTemperatureRecovered { temp: 70 },
+        }
+    }
+
+    #[test]
+    fn event_log() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        tokio_uring::start(async {
+            assert!(read_events_in(dir, 10).await.unwrap().is_empty());
+            for timestamp in 0..3 {
+                append_line_in(dir, EVENT_LOG, ROTATED_EVENT_LOG, &event(timestamp))
+                    .await
+                    .unwrap();
+            }
+            let timestamps = |events: Vec<Event>| -> Vec<u64> {
+                events.into_iter().map(|event| event.timestamp).collect()
+            };
+            assert_eq!(
+                timestamps(read_events_in(dir, 10).await.unwrap()),
+                [0, 1, 2]
+            );
+            // The latest events are kept.
+            assert_eq!(timestamps(read_events_in(dir, 2).await.unwrap()), [1, 2]);
+
+            // A large log is rotated and still read, incomplete lines are skipped.
+            let path = dir.join(EVENT_LOG);
+            let mut content = std::fs::read_to_string(&path).unwrap();
+            content.push_str(&" ".repeat(MAX_LOG_SIZE as usize));
+            content.push_str("\n{\"timestamp\":");
+            std::fs::write(&path, content).unwrap();
+            append_line_in(dir, EVENT_LOG, ROTATED_EVENT_LOG, &event(3))
+                .await
+                .unwrap();
+            assert!(dir.join(ROTATED_EVENT_LOG).exists());
+            assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 1);
+            assert_eq!(
+                timestamps(read_events_in(dir, 10).await.unwrap()),
+                [0, 1, 2, 3]
+            );
+        });
+    }
+}
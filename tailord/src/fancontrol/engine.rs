@@ -0,0 +1,291 @@
+use std::time::Duration;
+
+use tailor_api::PidControl;
+
+use crate::config::FanControlConfig;
+
+use super::{buffer::TemperatureBuffer, profile::FanProfile, smoothing::TemperatureFilter};
+
+/// Number of idle iterations before the delay is increased.
+const IDLE_ITERATIONS_THRESHOLD: u32 = 5;
+
+/// The hardware independent part of the fan control loop.
+/// It calculates new fan speeds and delays from temperatures,
+/// so it can also be driven by recorded temperatures.
+#[derive(Debug)]
+pub struct FanEngine {
+    /// Stores the temperature history.
+    temp_history: TemperatureBuffer,
+    /// Smooths the temperature as configured in the profile.
+    filter: TemperatureFilter,
+    /// Percentage of the current fan speed.
+    /// This is used to avoid unnecessary updates.
+    fan_speed: u8,
+    /// The configuration.
+    profile: FanProfile,
+    /// Timing of the control loop.
+    config: FanControlConfig,
+    /// Number of consecutive iterations without any
+    /// temperature or fan speed changes.
+    idle_iterations: u32,
+    /// State of the PID controller, if the profile has one.
+    pid: Option<PidState>,
+    /// The delay of the last iteration, the time step of the PID controller.
+    last_delay: Duration,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct PidState {
+    /// The integral of the error in °C·s.
+    integral: f32,
+    /// The error of the last iteration, `None` after a reset.
+    last_error: Option<f32>,
+}
+
+/// The result of a single iteration of the fan control loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FanEngineStep {
+    pub temp: u8,
+    pub target_fan_speed: u8,
+    pub fan_speed: u8,
+    pub fan_diff: u8,
+    pub fan_increment: u8,
+    /// Delay until the next iteration (without jitter).
+    pub delay: Duration,
+}
+
+impl FanEngine {
+    pub fn new(temp: u8, fan_speed: u8, profile: FanProfile, config: FanControlConfig) -> Self {
+        Self {
+            temp_history: TemperatureBuffer::new(temp),
+            filter: TemperatureFilter::new(profile.smoothing(), temp),
+            fan_speed,
+            pid: profile.pid().map(|_| PidState::default()),
+            profile,
+            config,
+            idle_iterations: 0,
+            last_delay: config.tick(),
+        }
+    }
+
+    pub fn config(&self) -> &FanControlConfig {
+        &self.config
+    }
+
+    pub fn fan_speed(&self) -> u8 {
+        self.fan_speed
+    }
+
+    pub fn is_synchronized(&self) -> bool {
+        self.profile.is_synchronized()
+    }
+
+    pub fn set_profile(&mut self, profile: FanProfile) {
+        if profile.smoothing() != self.profile.smoothing() {
+            self.filter =
+                TemperatureFilter::new(profile.smoothing(), self.temp_history.get_latest());
+        }
+        if profile.pid() != self.profile.pid() {
+            self.pid = profile.pid().map(|_| PidState::default());
+        }
+        self.profile = profile;
+        self.idle_iterations = 0;
+    }
+
+    /// Synchronize the fan speed with the hardware, e.g. after resume.
+    pub fn reset_fan_speed(&mut self, fan_speed: u8) {
+        self.fan_speed = fan_speed;
+        self.idle_iterations = 0;
+        if let Some(pid) = &mut self.pid {
+            *pid = PidState::default();
+        }
+    }
+
+    /// Calculate the next fan speed.
+    /// If no temperature is available, the latest temperature is used.
+    pub fn step(&mut self, temp: Option<u8>) -> FanEngineStep {
+        // Add the current temperature to history
+        let current_temp = match temp {
+            Some(temp) => {
+                let temp = self.filter.update(temp);
+                self.temp_history.update(temp);
+                temp
+            }
+            None => self.temp_history.get_latest(),
+        };
+
+        let target_fan_speed = match (self.profile.pid(), &mut self.pid) {
+            (Some(control), Some(state)) => {
+                let output = state.update(control, current_temp, self.last_delay);
+                self.profile.clamp_pid_output(output, current_temp)
+            }
+            _ => self.profile.calc_target_fan_speed(current_temp),
+        };
+        let fan_diff = self.fan_speed.abs_diff(target_fan_speed);
+
+        // Make small steps to decrease or increase fan speed.
+        // If the target fan speed is below 50%, don't increase the speed at all
+        // unless the difference is higher than 3% to avoid frequent speed changes
+        // at low temperatures. The PID controller already changes the speed
+        // gradually, so its output is applied directly.
+        let fan_increment = if self.pid.is_some() {
+            fan_diff
+        } else {
+            fan_diff / 4 + (target_fan_speed / 50)
+        };
+
+        self.fan_speed = if target_fan_speed > self.fan_speed {
+            self.fan_speed.saturating_add(fan_increment).min(100)
+        } else {
+            self.fan_speed.saturating_sub(fan_increment)
+        };
+
+        let tick = self.config.tick();
+        let mut delay = suitable_delay(&self.temp_history, fan_diff, tick);
+        if self.profile.is_near_breakpoint(current_temp) {
+            // Small temperature changes have a larger effect close to
+            // breakpoints of the fan curve, so react faster there.
+            delay = delay.min(tick / 2);
+            self.idle_iterations = 0;
+        } else if is_stable(&self.temp_history, fan_diff) {
+            self.idle_iterations = self.idle_iterations.saturating_add(1);
+        } else {
+            self.idle_iterations = 0;
+        }
+
+        let delay = idle_delay(delay, self.idle_iterations, tick);
+        self.last_delay = delay;
+        FanEngineStep {
+            temp: current_temp,
+            target_fan_speed,
+            fan_speed: self.fan_speed,
+            fan_diff,
+            fan_increment,
+            delay,
+        }
+    }
+}
+
+impl PidState {
+    /// The fan speed in percent before clamping.
+    fn update(&mut self, control: PidControl, temp: u8, time_step: Duration) -> f32 {
+        let dt = time_step.as_secs_f32().max(0.001);
+        let error = temp as f32 - control.target_temp as f32;
+        let derivative = self
+            .last_error
+            .map_or(0.0, |last_error| (error - last_error) / dt);
+        self.last_error = Some(error);
+
+        self.integral += error * dt;
+        // Limit the integral to what can change the output, so it
+        // doesn't wind up while the fan is off or at full speed.
+        if control.ki > 0.0 {
+            self.integral = self.integral.clamp(0.0, 100.0 / control.ki);
+        } else {
+            self.integral = 0.0;
+        }
+
+        control.kp * error + control.ki * self.integral + control.kd * derivative
+    }
+}
+
+/// Calculate a suitable delay to reduce CPU usage.
+fn suitable_delay(temp_buffer: &TemperatureBuffer, fan_diff: u8, tick: Duration) -> Duration {
+    // How much is the temperature changing?
+    let temperature_pressure = temp_buffer.diff_to_min_in_history();
+
+    // How much is the fan speed off from the ideal value?
+    let fan_diff_pressure = fan_diff / 2;
+
+    // Calculate an overall pressure value from 0 to 15.
+    let pressure = temperature_pressure
+        .saturating_add(fan_diff_pressure)
+        .min(15);
+
+    // Define a falling exponential function with time constant -1/7.
+    // This should yield decent results but the formula might be tuned
+    // to perform better.
+    // 0  -> tick (2000ms by default)
+    // 15 -> ~12% of tick (~230ms by default)
+    const TAU: f64 = -1.0 / 7.0;
+    tick.mul_f64((pressure as f64 * TAU).exp())
+}
+
+/// Whether neither the temperature nor the fan speed is changing.
+fn is_stable(temp_buffer: &TemperatureBuffer, fan_diff: u8) -> bool {
+    temp_buffer.diff_to_min_in_history() == 0 && fan_diff <= 1
+}
+
+/// Gradually increase the delay up to four ticks once the system
+/// has been idle for a while to reduce wakeups and power consumption.
+fn idle_delay(delay: Duration, idle_iterations: u32, tick: Duration) -> Duration {
+    if idle_iterations <= IDLE_ITERATIONS_THRESHOLD {
+        delay
+    } else {
+        let factor = 2u32.saturating_pow(idle_iterations - IDLE_ITERATIONS_THRESHOLD);
+        delay.saturating_mul(factor).min(tick * 4).max(delay)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use tailor_api::PidControl;
+
+    use crate::fancontrol::buffer::TemperatureBuffer;
+
+    use super::{idle_delay, suitable_delay, PidState};
+
+    #[test]
+    fn pid_control() {
+        let control = PidControl {
+            target_temp: 70,
+            kp: 5.0,
+            ki: 0.5,
+            kd: 0.0,
+        };
+        let mut state = PidState::default();
+        let second = Duration::from_secs(1);
+        assert_eq!(state.update(control, 70, second), 0.0);
+        assert_eq!(state.update(control, 74, second), 22.0);
+        assert_eq!(state.update(control, 74, second), 24.0);
+        // The integral doesn't become negative below the target.
+        assert_eq!(state.update(control, 50, second), -100.0);
+        assert_eq!(state.integral, 0.0);
+    }
+
+    #[test]
+    fn test_suitable_delay() {
+        let mut temp_buffer = TemperatureBuffer::new(20);
+        let tick = Duration::from_millis(2000);
+
+        // Test with no pressure.
+        assert_eq!(suitable_delay(&temp_buffer, 0, tick).as_millis(), 2000);
+
+        // Test with max pressure.
+        assert_eq!(suitable_delay(&temp_buffer, 255, tick).as_millis(), 234);
+
+        // Test with pressure 1.
+        assert_eq!(suitable_delay(&temp_buffer, 2, tick).as_millis(), 1733);
+
+        // Test with pressure 1 but this time through temperature diff.
+        temp_buffer.update(21);
+        assert_eq!(suitable_delay(&temp_buffer, 0, tick).as_millis(), 1733);
+    }
+
+    #[test]
+    fn test_idle_delay() {
+        let tick = Duration::from_millis(2000);
+        let delay = tick;
+        assert_eq!(idle_delay(delay, 0, tick), delay);
+        assert_eq!(idle_delay(delay, 5, tick), delay);
+        assert_eq!(idle_delay(delay, 6, tick).as_millis(), 4000);
+        assert_eq!(idle_delay(delay, 7, tick).as_millis(), 8000);
+        assert_eq!(idle_delay(delay, 100, tick).as_millis(), 8000);
+
+        // Never decrease the delay.
+        let delay = Duration::from_millis(10000);
+        assert_eq!(idle_delay(delay, 100, tick), delay);
+    }
+}
@@ -1,74 +1,221 @@
-use std::{sync::Arc, time::Duration};
+use std::{future::pending, sync::Arc, time::Duration};
 
-use tokio::sync::{broadcast, mpsc};
-use tuxedo_ioctl::hal::traits::HardwareDevice;
+use tailor_api::{EventKind, FanStatus};
+use tokio::sync::{broadcast, mpsc, oneshot, watch};
+use tuxedo_ioctl::hal::traits::{FanControl, FanSensors};
 
-use crate::suspend::get_suspend_receiver;
+use crate::{
+    audit::{self, Cause},
+    config::FanControlConfig,
+    events,
+    inhibit::{self, get_inhibit_receiver},
+    suspend::get_suspend_receiver,
+    util::{self, WriteAck},
+};
 
-use self::{buffer::TemperatureBuffer, profile::FanProfile};
+use self::{
+    engine::FanEngine,
+    profile::{FanLimits, FanProfile},
+};
+
+/// Difference in percent between the commanded duty and the reported
+/// fan speed from which a fan is considered to not follow its duty.
+const SPEED_MISMATCH_TOLERANCE: u8 = 10;
+/// Number of consecutive iterations with a speed mismatch before a fault is recorded.
+/// This gives the firmware enough time to ramp the fan up or down.
+const SPEED_MISMATCH_ITERATIONS: u32 = 10;
 
 mod buffer;
+pub mod engine;
+pub mod models;
 pub mod profile;
 mod runtime;
+mod smoothing;
 
 #[derive(Clone)]
 pub struct FanRuntimeHandle {
     pub fan_speed_sender: mpsc::Sender<u8>,
-    pub profile_sender: mpsc::Sender<FanProfile>,
+    pub speed_override_sender: mpsc::Sender<Option<FanSpeedOverride>>,
+    profile_sender: mpsc::Sender<ProfileUpdate>,
+    pub status_receiver: watch::Receiver<FanStatus>,
+}
+
+struct ProfileUpdate {
+    profile: FanProfile,
+    written: Option<WriteAck>,
+}
+
+impl FanRuntimeHandle {
+    /// Switch the fan to a profile.
+    pub async fn set_profile(&self, profile: FanProfile) -> Result<(), String> {
+        self.send_profile(profile, None).await
+    }
+
+    /// Switch the fan to a profile and wait for the first write of the
+    /// fan speed with it, so a failing EC can be noticed. Succeeds without
+    /// a write if the speed stays the same or the fan doesn't follow the
+    /// profile right now, e.g. while it's pinned or left to the firmware.
+    pub async fn apply_profile(&self, profile: FanProfile) -> Result<(), String> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_profile(profile, Some(sender)).await?;
+        util::first_write(receiver).await
+    }
+
+    async fn send_profile(
+        &self,
+        profile: FanProfile,
+        written: Option<WriteAck>,
+    ) -> Result<(), String> {
+        self.profile_sender
+            .send(ProfileUpdate { profile, written })
+            .await
+            .map_err(|err| err.to_string())
+    }
+}
+
+/// Pins a fan at a fixed speed instead of following the profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FanSpeedOverride {
+    pub speed: u8,
+    /// Return to the profile after this duration.
+    /// If `None`, the override stays active until it's released.
+    pub duration: Option<Duration>,
+    /// Recorded in the audit log.
+    pub cause: Cause,
 }
 
 #[derive(Debug)]
 pub struct FanRuntimeData {
     fan_idx: u8,
-    /// Stores the temperature history.
-    temp_history: TemperatureBuffer,
-    /// Percentage of the current fan speed.
-    /// This is used to avoid unnecessary updates.
-    fan_speed: u8,
+    /// Calculates the fan speeds.
+    engine: FanEngine,
     /// Device i/o interface.
-    io: Arc<dyn HardwareDevice>,
-    /// The configuration.
-    profile: FanProfile,
+    io: Arc<dyn FanControl>,
+    /// Calibration offset in °C for the temperature sensor.
+    temp_offset: i8,
+    /// Publishes the current state of the fan.
+    status_sender: watch::Sender<FanStatus>,
+    /// Whether the last attempt to access the fan failed.
+    faulted: bool,
+    /// The fan speeds that the firmware allows.
+    limits: FanLimits,
+    /// Number of consecutive iterations in which the fan didn't follow its duty.
+    speed_mismatch_iterations: u32,
+    /// Receives the result of the next write of the fan speed
+    /// after a profile was applied.
+    written: Option<WriteAck>,
+    /// The status of all other fans, ordered by index.
+    /// Used to keep fans with synchronized profiles in sync.
+    peers: Vec<watch::Receiver<FanStatus>>,
     suspend_receiver: broadcast::Receiver<bool>,
 }
 
 pub struct FanRuntime {
-    profile_receiver: mpsc::Receiver<FanProfile>,
+    profile_receiver: mpsc::Receiver<ProfileUpdate>,
     fan_speed_receiver: mpsc::Receiver<u8>,
+    speed_override_receiver: mpsc::Receiver<Option<FanSpeedOverride>>,
+    /// The number of tools that took over the fans.
+    inhibit_receiver: watch::Receiver<usize>,
     data: FanRuntimeData,
 }
 
+/// Run each fan runtime as a separate task on a dedicated thread,
+/// so fan control can't be delayed by D-Bus requests or LED animations.
+pub fn spawn_runtimes(mut runtimes: Vec<FanRuntime>) {
+    if runtimes.is_empty() {
+        return;
+    }
+
+    let statuses: Vec<_> = runtimes
+        .iter()
+        .map(|runtime| runtime.data.status_sender.subscribe())
+        .collect();
+    for runtime in &mut runtimes {
+        runtime.data.peers = statuses
+            .iter()
+            .filter(|status| status.borrow().fan_idx != runtime.data.fan_idx)
+            .cloned()
+            .collect();
+    }
+
+    std::thread::Builder::new()
+        .name("fan-control".to_owned())
+        .spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_time()
+                .build()
+                .expect("Failed to create fan control runtime");
+            runtime.block_on(async move {
+                let tasks: Vec<_> = runtimes
+                    .into_iter()
+                    .map(|fan_runtime| tokio::spawn(fan_runtime.run()))
+                    .collect();
+                for task in tasks {
+                    if let Err(err) = task.await {
+                        tracing::error!("Fan runtime failed: `{err}`");
+                    }
+                }
+            });
+        })
+        .expect("Failed to spawn fan control thread");
+}
+
 impl FanRuntime {
     // initialize global instance at startup
     pub fn new(
         fan_idx: u8,
-        io: Arc<dyn HardwareDevice>,
-        profile: FanProfile,
+        io: Arc<dyn FanControl>,
+        mut profile: FanProfile,
+        config: FanControlConfig,
+        temp_offset: i8,
     ) -> (FanRuntimeHandle, FanRuntime) {
         let fan_speed = io.get_fan_speed_percent(fan_idx).unwrap();
-        let temp = io.get_fan_temperature(fan_idx).unwrap();
-        let temp_history = TemperatureBuffer::new(temp);
+        let raw_temp = io.get_fan_temperature(fan_idx).unwrap();
+        let temp = runtime::calibrate(raw_temp, temp_offset);
+        let limits = FanLimits::of(io.as_ref());
+        profile.apply_limits(limits, fan_idx);
+        let synchronized = profile.is_synchronized();
+        let engine = FanEngine::new(temp, fan_speed, profile, config);
+        let (status_sender, status_receiver) = watch::channel(FanStatus {
+            fan_idx,
+            raw_temp,
+            temp,
+            fan_speed,
+            reported_fan_speed: Some(fan_speed),
+            synchronized,
+            ..Default::default()
+        });
 
         let (profile_sender, profile_receiver) = mpsc::channel(1);
         let (fan_speed_sender, fan_speed_receiver) = mpsc::channel(1);
+        let (speed_override_sender, speed_override_receiver) = mpsc::channel(1);
         let suspend_receiver = get_suspend_receiver();
 
         (
             FanRuntimeHandle {
                 fan_speed_sender,
+                speed_override_sender,
                 profile_sender,
+                status_receiver,
             },
             FanRuntime {
                 data: FanRuntimeData {
-                    temp_history,
-                    fan_speed,
+                    engine,
                     io,
-                    profile,
+                    temp_offset,
+                    status_sender,
+                    faulted: false,
+                    limits,
+                    speed_mismatch_iterations: 0,
+                    written: None,
+                    peers: Vec::new(),
                     fan_idx,
                     suspend_receiver,
                 },
                 profile_receiver,
                 fan_speed_receiver,
+                speed_override_receiver,
+                inhibit_receiver: get_inhibit_receiver(),
             },
         )
     }
@@ -78,7 +225,7 @@ impl FanRuntime {
             tokio::select! {
                 new_config = self.profile_receiver.recv() => {
                     if let Some(config) = new_config {
-                        self.data.profile = config;
+                        self.data.set_profile(config);
                     } else {
                         break;
                     }
@@ -104,6 +251,15 @@ impl FanRuntime {
                         break;
                     }
                 }
+                speed_override = self.speed_override_receiver.recv() => {
+                    match speed_override {
+                        Some(Some(speed_override)) => self.pin_speed(speed_override).await,
+                        // Nothing to release
+                        Some(None) => {}
+                        None => break,
+                    }
+                }
+                _ = inhibit::inhibited(&mut self.inhibit_receiver) => self.pause().await,
                 _ = self.data.fan_control_loop() => {},
             }
         }
@@ -114,31 +270,288 @@ impl FanRuntime {
         // Set fans to automatic mode again
         self.data.io.set_fans_auto().ok();
     }
+
+    /// Keep the fan at a fixed speed until the override
+    /// expires or is released.
+    async fn pin_speed(&mut self, mut speed_override: FanSpeedOverride) {
+        let fan_idx = self.data.fan_idx;
+        loop {
+            let FanSpeedOverride {
+                speed,
+                duration,
+                cause,
+            } = speed_override;
+            let old_speed = self.data.status_sender.borrow().fan_speed;
+            if let Err(err) = self.data.io.set_fan_speed_percent(fan_idx, speed) {
+                tracing::error!("Failed to override fan speed: `{err}`");
+                return;
+            }
+            audit::record_fan_speed(fan_idx, Some(old_speed), speed, cause);
+            // Continue from the pinned speed once the override ends.
+            self.data.engine.reset_fan_speed(speed);
+            self.data.status_sender.send_modify(|status| {
+                status.fan_speed = speed;
+                status.speed_override = Some(speed);
+            });
+            tracing::info!("Fan {fan_idx}: Pinned fan speed at {speed}% for {duration:?}");
+            events::record(EventKind::FanSpeedOverride {
+                fan_idx,
+                speed: Some(speed),
+            });
+
+            let expiry = async {
+                match duration {
+                    Some(duration) => tokio::time::sleep(duration).await,
+                    None => pending().await,
+                }
+            };
+            tokio::pin!(expiry);
+
+            let next_override = loop {
+                tokio::select! {
+                    new_override = self.speed_override_receiver.recv() => {
+                        if let Some(Some(new_override)) = new_override {
+                            break Some(new_override);
+                        } else {
+                            tracing::info!("Fan {fan_idx}: Released fan speed override");
+                            break None;
+                        }
+                    }
+                    // Apply the new profile once the override ends.
+                    new_config = self.profile_receiver.recv() => {
+                        if let Some(config) = new_config {
+                            self.data.set_profile(config);
+                            self.data.acknowledge(Ok(()));
+                        }
+                    }
+                    _ = &mut expiry => {
+                        tracing::info!("Fan {fan_idx}: Fan speed override expired");
+                        break None;
+                    }
+                    _ = inhibit::inhibited(&mut self.inhibit_receiver) => {
+                        tracing::info!("Fan {fan_idx}: Released fan speed override for an inhibitor");
+                        break None;
+                    }
+                }
+            };
+
+            if let Some(next_override) = next_override {
+                speed_override = next_override;
+            } else {
+                break;
+            }
+        }
+        self.data
+            .status_sender
+            .send_modify(|status| status.speed_override = None);
+        events::record(EventKind::FanSpeedOverride {
+            fan_idx,
+            speed: None,
+        });
+    }
+}
+
+impl FanRuntime {
+    /// Leave the fans to the firmware until all inhibitors are released.
+    async fn pause(&mut self) {
+        let fan_idx = self.data.fan_idx;
+        tracing::info!("Fan {fan_idx}: Returning control to the firmware");
+        if let Err(err) = self.data.io.set_fans_auto() {
+            tracing::error!("Failed to set the fans to automatic: `{err}`");
+        }
+
+        loop {
+            tokio::select! {
+                _ = inhibit::released(&mut self.inhibit_receiver) => break,
+                Some(config) = self.profile_receiver.recv() => {
+                    self.data.set_profile(config);
+                    self.data.acknowledge(Ok(()));
+                }
+                // Overrides would write to the EC.
+                Some(_) = self.fan_speed_receiver.recv() => {}
+                Some(_) = self.speed_override_receiver.recv() => {}
+            }
+        }
+
+        // Continue from the speed that the firmware chose.
+        if let Ok(speed) = self.data.io.get_fan_speed_percent(fan_idx) {
+            self.data.engine.reset_fan_speed(speed);
+        }
+        tracing::info!("Fan {fan_idx}: Resuming fan control");
+    }
 }
 
 impl FanRuntimeData {
+    fn set_profile(&mut self, update: ProfileUpdate) {
+        let ProfileUpdate {
+            mut profile,
+            written,
+        } = update;
+        profile.apply_limits(self.limits, self.fan_idx);
+        self.engine.set_profile(profile);
+        if let Some(written) = written {
+            // The previous profile was replaced before it was written.
+            if let Some(previous) = self.written.replace(written) {
+                previous.send(Ok(())).ok();
+            }
+        }
+    }
+
+    /// Report the first write after a profile was applied.
+    fn acknowledge(&mut self, result: Result<(), String>) {
+        if let Some(written) = self.written.take() {
+            written.send(result).ok();
+        }
+    }
+
+    /// The temperature and the reported speed are read together every tick.
     #[tracing::instrument(level = "trace", skip(self))]
-    /// Adds entries to history ring buffer.
-    fn update_temp(&mut self) -> u8 {
-        match self.io.get_fan_temperature(self.fan_idx) {
-            Ok(temp) => {
-                self.temp_history.update(temp);
-                temp
+    fn read_sensors(&mut self) -> Option<FanSensors> {
+        match self.io.read_fan_sensors(self.fan_idx) {
+            Ok(sensors) => {
+                self.faulted = false;
+                Some(sensors)
             }
             Err(err) => {
                 tracing::error!("Failed reading the current temperature: `{err}`");
-                self.temp_history.get_latest()
+                self.record_fault(format!("Failed reading the temperature: {err}"));
+                None
             }
         }
     }
 
+    /// Follow the fan curve, `temp` is recorded in the audit log.
     #[tracing::instrument(level = "trace", skip(self))]
-    fn set_speed(&mut self, new_speed: u8) {
-        if self.fan_speed != new_speed {
-            self.fan_speed = new_speed;
-            if let Err(err) = self.io.set_fan_speed_percent(self.fan_idx, new_speed) {
+    fn set_speed(&mut self, old_speed: u8, new_speed: u8, temp: u8) -> Result<(), String> {
+        match self.io.set_fan_speed_percent(self.fan_idx, new_speed) {
+            Ok(()) => {
+                audit::record_fan_speed(
+                    self.fan_idx,
+                    Some(old_speed),
+                    new_speed,
+                    Cause::FanCurve { temp },
+                );
+                Ok(())
+            }
+            Err(err) => {
                 tracing::error!("Failed setting new fan speed: `{err}`");
+                let message = format!("Failed setting the fan speed: {err}");
+                self.record_fault(message.clone());
+                Err(message)
+            }
+        }
+    }
+
+    /// Record a fault if the fan doesn't follow the commanded duty for a while.
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn check_reported_speed(&mut self, reported: u8) {
+        match self.io.get_fan_duty(self.fan_idx) {
+            // The firmware doesn't go below the minimum speed.
+            Some(duty)
+                if duty >= self.limits.min_speed
+                    && reported.abs_diff(duty) > SPEED_MISMATCH_TOLERANCE =>
+            {
+                self.speed_mismatch_iterations += 1;
+                if self.speed_mismatch_iterations == SPEED_MISMATCH_ITERATIONS {
+                    tracing::warn!(
+                        "Fan {}: Runs at {reported}% instead of {duty}%",
+                        self.fan_idx
+                    );
+                    events::record(EventKind::FanFault {
+                        fan_idx: self.fan_idx,
+                        error: format!("The fan runs at {reported}% instead of {duty}%"),
+                    });
+                }
             }
+            _ => self.speed_mismatch_iterations = 0,
+        }
+    }
+
+    /// Only record the first of consecutive faults to avoid flooding the event log.
+    fn record_fault(&mut self, error: String) {
+        if !self.faulted {
+            self.faulted = true;
+            events::record(EventKind::FanFault {
+                fan_idx: self.fan_idx,
+                error,
+            });
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use tuxedo_ioctl::hal::{traits::FanControl, IoctlError, IoctlResult};
+
+    use super::{profile::FanProfile, FanRuntime};
+    use crate::config::FanControlConfig;
+
+    /// A hot fan at standstill whose speed can only be written if `writable` is set.
+    #[derive(Debug)]
+    struct Fan {
+        writable: bool,
+    }
+
+    impl FanControl for Fan {
+        fn get_number_fans(&self) -> u8 {
+            1
+        }
+        fn set_fans_auto(&self) -> IoctlResult<()> {
+            Ok(())
+        }
+        fn get_fan_speed_raw_max(&self) -> u8 {
+            100
+        }
+        fn set_fan_speed_raw(&self, _fan: u8, _fan_speed_raw: u8) -> IoctlResult<()> {
+            if self.writable {
+                Ok(())
+            } else {
+                Err(IoctlError::DevNotAvailable)
+            }
+        }
+        fn get_fan_speed_raw(&self, _fan: u8) -> IoctlResult<u8> {
+            Ok(0)
+        }
+        fn get_fan_duty_raw(&self, _fan: u8) -> Option<u8> {
+            None
+        }
+        fn get_fan_temperature(&self, _fan: u8) -> IoctlResult<u8> {
+            Ok(80)
+        }
+        fn get_fans_min_speed(&self) -> IoctlResult<u8> {
+            Ok(0)
+        }
+        fn get_fans_off_available(&self) -> IoctlResult<bool> {
+            Ok(true)
+        }
+    }
+
+    fn apply_profile(writable: bool) -> Result<(), String> {
+        let (handle, runtime) = FanRuntime::new(
+            0,
+            Arc::new(Fan { writable }),
+            FanProfile::default(),
+            FanControlConfig::default(),
+            0,
+        );
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+            .block_on(async move {
+                tokio::select! {
+                    result = handle.apply_profile(FanProfile::default()) => result,
+                    _ = runtime.run() => unreachable!("The handle is alive"),
+                }
+            })
+    }
+
+    #[test]
+    fn first_write() {
+        assert_eq!(apply_profile(true), Ok(()));
+        let err = apply_profile(false).unwrap_err();
+        assert!(err.contains("Failed setting the fan speed"), "{err}");
+    }
+}
@@ -0,0 +1,76 @@
+//! Default fan curves for chassis with acoustics and thermals
+//! that differ a lot from the generic default curve.
+
+use tailor_api::{FanProfileInfo, FanProfilePoint};
+
+use super::profile::FanProfile;
+
+/// Thin devices get loud quickly, but have little thermal headroom.
+const THIN_AND_LIGHT: &[(u8, u8)] = &[(40, 0), (50, 15), (60, 25), (70, 40), (80, 65), (88, 100)];
+
+/// Large fans that are quiet at low speeds and have to
+/// start early to keep up with the heat of dedicated GPUs.
+const GAMING: &[(u8, u8)] = &[
+    (25, 15),
+    (40, 25),
+    (50, 35),
+    (60, 50),
+    (70, 65),
+    (80, 85),
+    (85, 100),
+];
+
+/// Model ids are DMI board names on platform devices and
+/// numeric ids on uniwill devices. A trailing `*` matches any suffix.
+const MODEL_CURVES: &[(&str, &[(u8, u8)])] = &[
+    // InfinityBook Pro 14 and 16
+    ("PH4*", THIN_AND_LIGHT),
+    ("PH6*", THIN_AND_LIGHT),
+    // Pulse 14 and 15
+    ("PF4*", THIN_AND_LIGHT),
+    ("PF5*", THIN_AND_LIGHT),
+    // Stellaris and Polaris
+    ("GM*", GAMING),
+    ("GX*", GAMING),
+];
+
+fn matches(pattern: &str, model_id: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => model_id.starts_with(prefix),
+        None => model_id == pattern,
+    }
+}
+
+/// The default fan profile for a model, `None` if
+/// the generic default fits the model.
+pub fn default_profile(model_id: &str) -> Option<FanProfile> {
+    let model_id = model_id.trim();
+    let (_, points) = MODEL_CURVES
+        .iter()
+        .find(|(pattern, _)| matches(pattern, model_id))?;
+    let points: Vec<FanProfilePoint> = points
+        .iter()
+        .map(|&(temp, fan)| FanProfilePoint { temp, fan })
+        .collect();
+    Some(FanProfileInfo::from(points).into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{default_profile, MODEL_CURVES};
+
+    #[test]
+    fn model_lookup() {
+        assert!(default_profile("PH4TRX1\n").is_some());
+        assert!(default_profile("GMxRGxx").is_some());
+        assert!(default_profile("19").is_none());
+
+        // Curves must be valid profiles.
+        for (_, points) in MODEL_CURVES {
+            assert!(points
+                .windows(2)
+                .all(|w| w[0].0 < w[1].0 && w[0].1 <= w[1].1));
+            assert_eq!(points.last().unwrap().1, 100);
+        }
+    }
+}
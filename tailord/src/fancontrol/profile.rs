@@ -1,24 +1,95 @@
 use std::path::Path;
 
-use tailor_api::FanProfilePoint;
+use tailor_api::{FanProfileInfo, FanProfilePoint, PidControl, TailorError, TemperatureSmoothing};
+use tuxedo_ioctl::hal::traits::FanControl;
 use zbus::fdo;
 
-#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
-#[serde(transparent)]
+use crate::util;
+
+/// Temperature difference in °C from which a
+/// temperature is considered close to a profile point.
+const BREAKPOINT_MARGIN: u8 = 2;
+
+/// Largest window for moving averages, in samples.
+const MAX_SMOOTHING_WINDOW: u8 = 30;
+
+/// The fan speeds that the firmware actually applies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FanLimits {
+    /// The lowest fan speed in percent, apart from turning the fans off.
+    pub min_speed: u8,
+    pub fans_off_available: bool,
+}
+
+impl FanLimits {
+    const UNRESTRICTED: Self = Self {
+        min_speed: 0,
+        fans_off_available: true,
+    };
+
+    /// Unknown limits don't restrict the profiles.
+    pub fn of(io: &dyn FanControl) -> Self {
+        Self {
+            min_speed: io.get_fans_min_speed().unwrap_or_default(),
+            fans_off_available: io.get_fans_off_available().unwrap_or(true),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(from = "FanProfileInfo", into = "FanProfileInfo")]
 pub struct FanProfile {
     inner: Vec<FanProfilePoint>,
+    smoothing: TemperatureSmoothing,
+    synchronized: bool,
+    pid: Option<PidControl>,
+    /// Set by [`FanProfile::apply_limits`], the PID output is clamped to them.
+    limits: FanLimits,
+}
+
+impl From<FanProfileInfo> for FanProfile {
+    fn from(info: FanProfileInfo) -> Self {
+        Self {
+            inner: info.points,
+            smoothing: info.smoothing,
+            synchronized: info.synchronized,
+            pid: info.pid,
+            limits: FanLimits::UNRESTRICTED,
+        }
+    }
+}
+
+impl From<FanProfile> for FanProfileInfo {
+    fn from(profile: FanProfile) -> Self {
+        Self {
+            points: profile.inner,
+            smoothing: profile.smoothing,
+            synchronized: profile.synchronized,
+            pid: profile.pid,
+        }
+    }
 }
 
 impl FanProfile {
     pub fn load_config(file_name: impl AsRef<Path>) -> fdo::Result<Self> {
         let file_name = file_name.as_ref();
-        let content =
-            std::fs::read(file_name).map_err(|err| fdo::Error::IOError(err.to_string()))?;
-        let mut inner: Vec<FanProfilePoint> = serde_json::from_slice(&content)
-            .map_err(|err| fdo::Error::InvalidFileContent(err.to_string()))?;
+        Self::from_config(util::read_profile_file(file_name)?, file_name)
+    }
 
-        if inner.is_empty() {
-            return Err(fdo::Error::FileNotFound("Empty configuration".to_string()));
+    /// Check a profile that was read from `file_name` and repair what can be repaired.
+    pub fn from_config(info: FanProfileInfo, file_name: &Path) -> fdo::Result<Self> {
+        let FanProfileInfo {
+            points: mut inner,
+            smoothing,
+            synchronized,
+            pid,
+        } = info;
+
+        // The points are ignored while a PID controller holds the temperature.
+        if inner.is_empty() && pid.is_none() {
+            return Err(util::tailor_error(TailorError::invalid_profile(
+                "Empty configuration",
+            )));
         }
 
         // Make sure the temperature is increasing with each point.
@@ -37,7 +108,7 @@ impl FanProfile {
 
         if !is_sorted {
             tracing::warn!("Temperature in temperature profile isn't increasing: `{file_name:?}`");
-            inner.sort_by(|first, second| first.temp.cmp(&second.temp));
+            inner.sort_by_key(|point| point.temp);
         }
 
         // Make sure that the fan speed is increasing along with the temperature.
@@ -57,11 +128,9 @@ impl FanProfile {
             }
         }
 
-        // Make sure some minimum fan speed is kept:
-        // From 75°C the fan speed should ramp up to
-        // 100% at 95°C.
+        // Make sure some minimum fan speed is kept.
         for value in &mut inner {
-            let min_speed = value.temp.saturating_sub(75).saturating_mul(5).min(100);
+            let min_speed = min_safe_speed(value.temp);
 
             if min_speed > value.fan {
                 let invalid_fan_value = value.fan;
@@ -75,7 +144,7 @@ impl FanProfile {
         }
 
         // Make sure that 100% fan speed will be reached
-        if inner.last().unwrap().fan < 100 {
+        if inner.last().is_some_and(|point| point.fan < 100) {
             tracing::warn!(
                 "Fan speed 100% is never reached. Set speed to 100% at 100°C: `{file_name:?}`"
             );
@@ -85,37 +154,146 @@ impl FanProfile {
             })
         }
 
-        Ok(Self { inner })
+        let smoothing = match smoothing {
+            TemperatureSmoothing::MovingAverage { window } => {
+                let valid_window = window.clamp(1, MAX_SMOOTHING_WINDOW);
+                if valid_window != window {
+                    tracing::warn!(
+                        "Smoothing window must be between 1 and {MAX_SMOOTHING_WINDOW}: `{file_name:?}`"
+                    );
+                }
+                TemperatureSmoothing::MovingAverage {
+                    window: valid_window,
+                }
+            }
+            TemperatureSmoothing::Exponential { factor } => {
+                let valid_factor = factor.clamp(1, 100);
+                if valid_factor != factor {
+                    tracing::warn!("Smoothing factor must be between 1 and 100: `{file_name:?}`");
+                }
+                TemperatureSmoothing::Exponential {
+                    factor: valid_factor,
+                }
+            }
+            TemperatureSmoothing::None => TemperatureSmoothing::None,
+        };
+
+        let pid = match pid {
+            Some(mut pid) => {
+                for gain in [&mut pid.kp, &mut pid.ki, &mut pid.kd] {
+                    // Infinite gains turn the output into NaN.
+                    if !gain.is_finite() {
+                        return Err(util::tailor_error(TailorError::invalid_profile(
+                            "PID gains must be finite numbers",
+                        )));
+                    }
+                    if *gain < 0.0 {
+                        tracing::warn!("PID gains can't be negative: `{file_name:?}`");
+                        *gain = 0.0;
+                    }
+                }
+                Some(pid)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            inner,
+            smoothing,
+            synchronized,
+            pid,
+            limits: FanLimits::UNRESTRICTED,
+        })
     }
 
-    // Use the temp profile in the configuration to calculate the
-    // corresponding fan speed.
-    pub fn calc_target_fan_speed(&self, current_temp: u8) -> u8 {
-        // Find the first item that has a greater or equal temperature.
-        let position = self.inner.iter().position(|p| p.temp >= current_temp);
+    /// Reject profiles that turn the fans off, if the firmware can't do that.
+    pub fn validate_limits(&self, limits: FanLimits) -> Result<(), TailorError> {
+        match self.inner.iter().find(|point| point.fan == 0) {
+            Some(point) if !limits.fans_off_available => {
+                Err(TailorError::invalid_profile(format!(
+                    "The fans can't be turned off on this device (0% at {}°C)",
+                    point.temp
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
 
-        if let Some(position) = position {
-            let profile_point = &self.inner[position];
+    /// Raise fan speeds below the minimum speed of the firmware,
+    /// which would otherwise be ignored silently.
+    pub fn apply_limits(&mut self, limits: FanLimits, fan_idx: u8) {
+        self.limits = limits;
+        for point in &mut self.inner {
+            let fans_off = point.fan == 0 && limits.fans_off_available;
+            if point.fan < limits.min_speed && !fans_off {
+                tracing::warn!(
+                    "Fan {fan_idx}: Fan speed {}% at {}°C is below the hardware minimum. Using {}%",
+                    point.fan,
+                    point.temp,
+                    limits.min_speed
+                );
+                point.fan = limits.min_speed;
+            }
+        }
+    }
 
-            // If the profile point fits exact or it's the first element,
-            // directly use its temperature.
-            if profile_point.temp == current_temp || position == 0 {
-                profile_point.fan
-            } else {
-                let prev_point = &self.inner[position - 1];
+    pub fn smoothing(&self) -> TemperatureSmoothing {
+        self.smoothing
+    }
 
-                // Interpolate with a linear slope between those two points.
-                // Use u16 to make sure the multiplication doesn't overflow.
-                let temp_diff = (profile_point.temp - prev_point.temp) as u16;
-                let curr_temp_diff = (current_temp - prev_point.temp) as u16;
-                let fan_diff = (profile_point.fan - prev_point.fan) as u16;
+    pub fn is_synchronized(&self) -> bool {
+        self.synchronized
+    }
 
-                prev_point.fan + (fan_diff * curr_temp_diff / temp_diff) as u8
+    pub fn pid(&self) -> Option<PidControl> {
+        self.pid
+    }
+
+    /// Clamp the output of the PID controller to the speeds
+    /// that the firmware applies and that keep the device safe.
+    pub fn clamp_pid_output(&self, speed: f32, current_temp: u8) -> u8 {
+        let speed = speed.clamp(0.0, 100.0).round() as u8;
+        let speed = if speed < self.limits.min_speed {
+            // Turn the fans off only if the controller asks for less than half the minimum.
+            if self.limits.fans_off_available && speed < self.limits.min_speed / 2 {
+                0
+            } else {
+                self.limits.min_speed
             }
         } else {
-            // The temperature is higher than anything in the list.
-            100
+            speed
+        };
+        speed.max(min_safe_speed(current_temp))
+    }
+
+    /// The points of the curve, e.g. `40°C 20%, 60°C 50%`.
+    pub fn describe(&self) -> String {
+        if let Some(pid) = self.pid {
+            return format!("PID control at {}°C", pid.target_temp);
         }
+        let points: Vec<String> = self
+            .inner
+            .iter()
+            .map(|point| format!("{}°C {}%", point.temp, point.fan))
+            .collect();
+        points.join(", ")
+    }
+
+    /// Whether the temperature is close to a point of the profile
+    /// where the slope of the fan curve changes.
+    pub fn is_near_breakpoint(&self, current_temp: u8) -> bool {
+        if let Some(pid) = self.pid {
+            return pid.target_temp.abs_diff(current_temp) <= BREAKPOINT_MARGIN;
+        }
+        self.inner
+            .iter()
+            .any(|point| point.temp.abs_diff(current_temp) <= BREAKPOINT_MARGIN)
+    }
+
+    // Use the temp profile in the configuration to calculate the
+    // corresponding fan speed.
+    pub fn calc_target_fan_speed(&self, current_temp: u8) -> u8 {
+        tailor_api::fan_speed_at(&self.inner, current_temp)
     }
 }
 
@@ -132,6 +310,71 @@ impl Default for FanProfile {
                 FanProfilePoint { temp: 80, fan: 75 },
                 FanProfilePoint { temp: 90, fan: 100 },
             ],
+            smoothing: TemperatureSmoothing::None,
+            synchronized: false,
+            pid: None,
+            limits: FanLimits::UNRESTRICTED,
         }
     }
 }
+
+/// From 75°C the fan speed should ramp up to 100% at 95°C.
+fn min_safe_speed(temp: u8) -> u8 {
+    temp.saturating_sub(75).saturating_mul(5).min(100)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use tailor_api::{FanProfileInfo, FanProfilePoint, PidControl};
+
+    use super::{FanLimits, FanProfile};
+
+    #[test]
+    fn pid_profiles() {
+        let pid = PidControl {
+            target_temp: 70,
+            kp: 4.0,
+            ki: -0.1,
+            kd: 2.0,
+        };
+        let info = |pid| FanProfileInfo {
+            pid,
+            ..FanProfileInfo::from(Vec::new())
+        };
+        let file_name = Path::new("pid.json");
+
+        let profile = FanProfile::from_config(info(Some(pid)), file_name).unwrap();
+        assert!(profile.inner.is_empty());
+        assert_eq!(profile.pid.unwrap().ki, 0.0);
+
+        assert!(FanProfile::from_config(info(None), file_name).is_err());
+        for gain in [f32::INFINITY, f32::NEG_INFINITY, f32::NAN] {
+            let pid = PidControl { kp: gain, ..pid };
+            assert!(FanProfile::from_config(info(Some(pid)), file_name).is_err());
+        }
+    }
+
+    #[test]
+    fn hardware_limits() {
+        let mut profile = FanProfile::default();
+        let limits = FanLimits {
+            min_speed: 20,
+            fans_off_available: true,
+        };
+        assert!(profile.validate_limits(limits).is_ok());
+        profile.apply_limits(limits, 0);
+        assert_eq!(profile.inner[0], FanProfilePoint { temp: 25, fan: 0 });
+        assert_eq!(profile.inner[1], FanProfilePoint { temp: 30, fan: 20 });
+        assert_eq!(profile.inner[2], FanProfilePoint { temp: 40, fan: 22 });
+
+        let limits = FanLimits {
+            fans_off_available: false,
+            ..limits
+        };
+        assert!(profile.validate_limits(limits).is_err());
+        profile.apply_limits(limits, 0);
+        assert_eq!(profile.inner[0], FanProfilePoint { temp: 25, fan: 20 });
+    }
+}
@@ -1,93 +1,166 @@
-use crate::suspend::process_suspend;
+use tailor_api::FanStatus;
+use tokio::sync::watch;
 
-use super::{buffer::TemperatureBuffer, FanRuntimeData};
+use crate::suspend::process_suspend;
 
-use std::time::Duration;
+use super::FanRuntimeData;
 
 impl FanRuntimeData {
     #[tracing::instrument(level = "debug", skip(self))]
     pub async fn fan_control_loop(&mut self) {
         loop {
-            // Add the current temperature to history
-            let current_temp = self.update_temp();
-
-            let target_fan_speed = self.profile.calc_target_fan_speed(current_temp);
-            let fan_diff = self.fan_speed.abs_diff(target_fan_speed);
-
-            // Make small steps to decrease or increase fan speed.
-            // If the target fan speed is below 50%, don't increase the speed at all
-            // unless the difference is higher than 3% to avoid frequent speed changes
-            // at low temperatures.
-            let fan_increment = fan_diff / 4 + (target_fan_speed / 50);
+            let sensors = self.read_sensors();
+            let raw_temp = sensors.map(|sensors| sensors.temp);
+            let temp = raw_temp.map(|temp| calibrate(temp, self.temp_offset));
+            let synchronized = self.engine.is_synchronized();
+            let leader = if synchronized {
+                sync_leader(&self.peers, self.fan_idx)
+            } else {
+                None
+            };
+
+            let prev_fan_speed = self.engine.fan_speed();
+            let step = match leader {
+                // Follow the speed of the leader, the own sensor
+                // only contributes to the temperature of the group.
+                Some(leader) => {
+                    let mut step = self.engine.step(temp);
+                    let leader_status = self.peers[leader].borrow_and_update();
+                    self.engine.reset_fan_speed(leader_status.fan_speed);
+                    step.fan_speed = leader_status.fan_speed;
+                    step.target_fan_speed = leader_status.target_fan_speed;
+                    step
+                }
+                None if synchronized => {
+                    let temp = temp.map(|temp| temp.max(synchronized_peer_temp(&self.peers)));
+                    self.engine.step(temp)
+                }
+                None => self.engine.step(temp),
+            };
 
             // Update fan speed
-            self.set_speed(if target_fan_speed > self.fan_speed {
-                self.fan_speed.saturating_add(fan_increment).min(100)
+            let written = if step.fan_speed != prev_fan_speed {
+                self.set_speed(prev_fan_speed, step.fan_speed, step.temp)
             } else {
-                self.fan_speed.saturating_sub(fan_increment)
+                Ok(())
+            };
+            self.acknowledge(written);
+            // Read before the new speed is set, the fan needs a while to follow anyway.
+            let reported_fan_speed = sensors.map(|sensors| sensors.speed);
+            if let Some(reported) = reported_fan_speed {
+                self.check_reported_speed(reported);
+            }
+
+            self.status_sender.send_modify(|status| {
+                if let Some(raw_temp) = raw_temp {
+                    status.raw_temp = raw_temp;
+                }
+                status.temp = step.temp;
+                status.fan_speed = step.fan_speed;
+                status.reported_fan_speed = reported_fan_speed;
+                status.target_fan_speed = step.target_fan_speed;
+                status.synchronized = synchronized;
             });
 
-            let delay = suitable_delay(&self.temp_history, fan_diff);
+            let delay = step.delay + self.engine.config().jitter();
 
             tracing::debug!(
-                "Fan {}: Current temperature is {current_temp}°C, fan speed: {}%, target fan speed: {target_fan_speed} \
-                fan diff: {fan_diff}, fan increment {fan_increment}, delay: {delay:?}", self.fan_idx, self.fan_speed
+                "Fan {}: Current temperature is {}°C, fan speed: {}%, target fan speed: {} \
+                fan diff: {}, fan increment {}, delay: {delay:?}, synchronized: {synchronized}",
+                self.fan_idx,
+                step.temp,
+                step.fan_speed,
+                step.target_fan_speed,
+                step.fan_diff,
+                step.fan_increment
             );
 
             tokio::select! {
                 _ = tokio::time::sleep(delay) => {},
+                // Followers apply each change of the leader right away.
+                _ = leader_changed(leader.map(|leader| &mut self.peers[leader])) => {},
                 _ = process_suspend(&mut self.suspend_receiver) => {
-                    self.fan_speed = self.io.get_fan_speed_percent(0).unwrap();
+                    self.engine.reset_fan_speed(self.io.get_fan_speed_percent(0).unwrap());
                 }
             }
         }
     }
 }
 
-/// Calculate a suitable delay to reduce CPU usage.
-fn suitable_delay(temp_buffer: &TemperatureBuffer, fan_diff: u8) -> Duration {
-    // How much is the temperature changing?
-    let temperature_pressure = temp_buffer.diff_to_min_in_history();
-
-    // How much is the fan speed off from the ideal value?
-    let fan_diff_pressure = fan_diff / 2;
-
-    // Calculate an overall pressure value from 0 to 15.
-    let pressure = temperature_pressure
-        .saturating_add(fan_diff_pressure)
-        .min(15);
-
-    // Define a falling exponential function with time constant -1/7.
-    // This should yield decent results but the formula might be tuned
-    // to perform better.
-    // 0  -> 2000ms
-    // 15 -> ~230ms
-    const TAU: f64 = -1.0 / 7.0;
-    let delay = 2000.0 * (pressure as f64 * TAU).exp();
-    Duration::from_millis(delay as u64)
+/// The position in `peers` of the fan with the lowest index that is
+/// synchronized, if it comes before the fan `fan_idx`. The leader controls
+/// the speed of all synchronized fans.
+fn sync_leader(peers: &[watch::Receiver<FanStatus>], fan_idx: u8) -> Option<usize> {
+    peers.iter().position(|peer| {
+        let status = peer.borrow();
+        status.synchronized && status.fan_idx < fan_idx
+    })
 }
 
-#[cfg(test)]
-mod test {
-    use crate::fancontrol::buffer::TemperatureBuffer;
-
-    use super::suitable_delay;
-
-    #[test]
-    fn test_suitable_delay() {
-        let mut temp_buffer = TemperatureBuffer::new(20);
+/// The highest temperature of the other synchronized fans.
+fn synchronized_peer_temp(peers: &[watch::Receiver<FanStatus>]) -> u8 {
+    peers
+        .iter()
+        .map(|peer| peer.borrow())
+        .filter(|status| status.synchronized)
+        .map(|status| status.temp)
+        .max()
+        .unwrap_or_default()
+}
 
-        // Test with no pressure.
-        assert_eq!(suitable_delay(&temp_buffer, 0).as_millis(), 2000);
+async fn leader_changed(leader: Option<&mut watch::Receiver<FanStatus>>) {
+    if let Some(leader) = leader {
+        if leader.changed().await.is_ok() {
+            return;
+        }
+    }
+    std::future::pending().await
+}
 
-        // Test with max pressure.
-        assert_eq!(suitable_delay(&temp_buffer, 255).as_millis(), 234);
+/// Apply a calibration offset to a temperature.
+pub(super) fn calibrate(raw_temp: u8, offset: i8) -> u8 {
+    raw_temp.saturating_add_signed(offset)
+}
 
-        // Test with pressure 1.
-        assert_eq!(suitable_delay(&temp_buffer, 2).as_millis(), 1733);
+#[cfg(test)]
+mod test {
+    use tailor_api::FanStatus;
+    use tokio::sync::watch;
+
+    use super::{sync_leader, synchronized_peer_temp};
+
+    fn peer(fan_idx: u8, temp: u8, synchronized: bool) -> watch::Receiver<FanStatus> {
+        // The receiver keeps the last value after the sender is dropped.
+        watch::channel(FanStatus {
+            fan_idx,
+            temp,
+            synchronized,
+            ..Default::default()
+        })
+        .1
+    }
 
-        // Test with pressure 1 but this time through temperature diff.
-        temp_buffer.update(21);
-        assert_eq!(suitable_delay(&temp_buffer, 0).as_millis(), 1733);
+    #[test]
+    fn synchronized_fans() {
+        // Fan 1 sees fans 0 and 2, fan 0 isn't synchronized.
+        let peers = [peer(0, 70, false), peer(2, 60, true)];
+        assert_eq!(sync_leader(&peers, 1), None);
+        assert_eq!(synchronized_peer_temp(&peers), 60);
+
+        // Fan 2 sees fans 0 and 1 and follows fan 1.
+        let peers = [peer(0, 70, false), peer(1, 55, true)];
+        assert_eq!(sync_leader(&peers, 2), Some(1));
+        assert_eq!(synchronized_peer_temp(&peers), 55);
+
+        // The synchronized fan with the lowest index leads.
+        let peers = [peer(0, 50, true), peer(1, 80, true), peer(3, 65, true)];
+        assert_eq!(sync_leader(&peers, 2), Some(0));
+        assert_eq!(synchronized_peer_temp(&peers), 80);
+
+        // Without synchronized peers, the fan leads and only uses its own temperature.
+        let peers = [peer(1, 90, false)];
+        assert_eq!(sync_leader(&peers, 0), None);
+        assert_eq!(synchronized_peer_temp(&peers), 0);
+        assert_eq!(sync_leader(&[], 0), None);
     }
 }
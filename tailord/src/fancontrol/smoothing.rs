@@ -0,0 +1,69 @@
+use std::collections::VecDeque;
+
+use tailor_api::TemperatureSmoothing;
+
+/// Smooths temperature samples as configured in the fan profile.
+#[derive(Debug)]
+pub struct TemperatureFilter {
+    smoothing: TemperatureSmoothing,
+    /// Latest samples for moving averages.
+    samples: VecDeque<u8>,
+    /// Current value of an exponential moving average.
+    average: f64,
+}
+
+impl TemperatureFilter {
+    pub(super) fn new(smoothing: TemperatureSmoothing, temp: u8) -> Self {
+        Self {
+            smoothing,
+            samples: VecDeque::from([temp]),
+            average: temp as f64,
+        }
+    }
+
+    /// Add a new sample and return the smoothed temperature.
+    pub(super) fn update(&mut self, temp: u8) -> u8 {
+        match self.smoothing {
+            TemperatureSmoothing::None => temp,
+            TemperatureSmoothing::MovingAverage { window } => {
+                let window = (window as usize).max(1);
+                self.samples.push_back(temp);
+                while self.samples.len() > window {
+                    self.samples.pop_front();
+                }
+                let sum: u32 = self.samples.iter().map(|temp| *temp as u32).sum();
+                (sum as f64 / self.samples.len() as f64).round() as u8
+            }
+            TemperatureSmoothing::Exponential { factor } => {
+                let factor = factor.clamp(1, 100) as f64 / 100.0;
+                self.average = factor * temp as f64 + (1.0 - factor) * self.average;
+                self.average.round() as u8
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tailor_api::TemperatureSmoothing;
+
+    use super::TemperatureFilter;
+
+    #[test]
+    fn smoothing() {
+        let mut filter = TemperatureFilter::new(TemperatureSmoothing::None, 40);
+        assert_eq!(filter.update(60), 60);
+
+        let mut filter =
+            TemperatureFilter::new(TemperatureSmoothing::MovingAverage { window: 3 }, 40);
+        assert_eq!(filter.update(46), 43);
+        assert_eq!(filter.update(46), 44);
+        // The first sample left the window.
+        assert_eq!(filter.update(46), 46);
+
+        let mut filter =
+            TemperatureFilter::new(TemperatureSmoothing::Exponential { factor: 50 }, 40);
+        assert_eq!(filter.update(60), 50);
+        assert_eq!(filter.update(60), 55);
+    }
+}
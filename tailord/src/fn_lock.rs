@@ -0,0 +1,50 @@
+//! Keeps the Fn-lock across restarts and suspend,
+//! since the firmware resets it on both.
+
+use std::path::PathBuf;
+
+use tuxedo_sysfs::fn_lock::FnLock;
+
+use crate::{
+    config,
+    suspend::{get_suspend_receiver, process_suspend},
+};
+
+const FN_LOCK_STATE: &str = "fn-lock";
+
+fn state_path() -> PathBuf {
+    config::state_dir().join(FN_LOCK_STATE)
+}
+
+/// Apply the state that was set last, if any.
+pub fn restore(fn_lock: &FnLock) {
+    let Ok(state) = std::fs::read_to_string(state_path()) else {
+        return;
+    };
+    match state.trim().parse::<bool>() {
+        Ok(enabled) => {
+            if let Err(err) = fn_lock.set(enabled) {
+                tracing::error!("Failed to restore the Fn-lock: `{err}`");
+            }
+        }
+        Err(err) => tracing::warn!("Invalid Fn-lock state `{state}`: `{err}`"),
+    }
+}
+
+pub fn store(enabled: bool) {
+    let result = std::fs::create_dir_all(config::state_dir())
+        .and_then(|()| std::fs::write(state_path(), enabled.to_string()));
+    if let Err(err) = result {
+        tracing::warn!("Failed to store the Fn-lock: `{err}`");
+    }
+}
+
+/// Apply the stored state again after each wake up.
+pub async fn restore_after_resume(fn_lock: FnLock) {
+    let mut suspend_receiver = get_suspend_receiver();
+    loop {
+        process_suspend(&mut suspend_receiver).await;
+        tracing::debug!("Restoring the Fn-lock after resume");
+        restore(&fn_lock);
+    }
+}
@@ -0,0 +1,113 @@
+//! Detects idle sessions with the `IdleHint` of logind,
+//! so it doesn't depend on X11 or the desktop.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use once_cell::sync::Lazy;
+use tokio::sync::watch;
+use zbus::{dbus_proxy, CacheProperties, Connection};
+
+use crate::{config::IdleConfig, profile_switch};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// The reason of profile switches in the audit log.
+const RULE: &str = "idle";
+
+static IDLE: Lazy<watch::Sender<bool>> = Lazy::new(|| watch::channel(false).0);
+
+/// Receives `true` while all sessions are idle for longer than the configured delay.
+pub fn get_idle_receiver() -> watch::Receiver<bool> {
+    IDLE.subscribe()
+}
+
+#[dbus_proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait IdleHint {
+    /// Whether all sessions are idle.
+    #[dbus_proxy(property)]
+    fn idle_hint(&self) -> zbus::Result<bool>;
+
+    /// Microseconds since the epoch at which the idle hint last changed.
+    #[dbus_proxy(property)]
+    fn idle_since_hint(&self) -> zbus::Result<u64>;
+}
+
+pub struct IdleWatcher {
+    config: IdleConfig,
+    connection: Connection,
+}
+
+impl IdleWatcher {
+    pub fn new(config: IdleConfig, connection: Connection) -> Self {
+        Self { config, connection }
+    }
+
+    pub async fn run(self) {
+        let logind = match Connection::system().await {
+            Ok(connection) => connection,
+            Err(err) => {
+                tracing::error!("Failed to connect to logind: `{err}`");
+                return;
+            }
+        };
+        let delay = Duration::from_secs(self.config.delay_secs);
+        let mut previous_profile = None;
+
+        loop {
+            let idle = match idle_duration(&logind).await {
+                Ok(duration) => duration.is_some_and(|duration| duration >= delay),
+                Err(err) => {
+                    tracing::debug!("Failed to read the idle state of the sessions: `{err}`");
+                    false
+                }
+            };
+
+            let changed = IDLE.send_if_modified(|current| {
+                let changed = *current != idle;
+                *current = idle;
+                changed
+            });
+            if changed {
+                tracing::info!("Sessions are {}", if idle { "idle" } else { "active" });
+                if let Some(profile) = &self.config.profile {
+                    let result = if idle {
+                        profile_switch::activate(&self.connection, profile, RULE)
+                            .await
+                            .map(|previous| previous_profile = previous)
+                    } else {
+                        profile_switch::restore(
+                            &self.connection,
+                            profile,
+                            previous_profile.take(),
+                            RULE,
+                        )
+                        .await
+                    };
+                    if let Err(err) = result {
+                        tracing::error!("Failed to switch the idle profile: `{err}`");
+                    }
+                }
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// How long all sessions have been idle, `None` if they aren't.
+async fn idle_duration(connection: &Connection) -> zbus::Result<Option<Duration>> {
+    let logind = IdleHintProxy::builder(connection)
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+    if !logind.idle_hint().await? {
+        return Ok(None);
+    }
+    let since = Duration::from_micros(logind.idle_since_hint().await?);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    Ok(Some(now.saturating_sub(since)))
+}
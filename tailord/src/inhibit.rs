@@ -0,0 +1,115 @@
+//! Lets other tools, like firmware updaters, take over the embedded
+//! controller temporarily. While at least one inhibitor is active, the fans
+//! return to the firmware and tailord doesn't write to the EC: LED colors
+//! and performance profiles are applied once all inhibitors are released,
+//! other writes are refused.
+
+use std::{io::Read, os::unix::net::UnixStream};
+
+use nix::libc;
+use once_cell::sync::Lazy;
+use tailor_api::TailorError;
+use tokio::sync::watch;
+use zbus::fdo;
+
+use crate::util;
+
+/// Number of active inhibitors.
+static INHIBITORS: Lazy<watch::Sender<usize>> = Lazy::new(|| watch::channel(0).0);
+
+/// Receives the number of active inhibitors.
+pub fn get_inhibit_receiver() -> watch::Receiver<usize> {
+    INHIBITORS.subscribe()
+}
+
+/// Whether at least one inhibitor is active.
+pub fn is_inhibited() -> bool {
+    *INHIBITORS.borrow() > 0
+}
+
+/// Refuse a write to the EC while it's inhibited.
+pub fn check_write(what: &str) -> fdo::Result<()> {
+    if is_inhibited() {
+        Err(util::tailor_error(TailorError::hardware_error(
+            Some(libc::EBUSY),
+            format!(
+                "Can't {what} while another tool has exclusive access to the embedded controller"
+            ),
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// Wait until fan control is inhibited.
+pub async fn inhibited(receiver: &mut watch::Receiver<usize>) {
+    if receiver
+        .wait_for(|inhibitors| *inhibitors > 0)
+        .await
+        .is_err()
+    {
+        std::future::pending().await
+    }
+}
+
+/// Wait until all inhibitors are released.
+pub async fn released(receiver: &mut watch::Receiver<usize>) {
+    if receiver
+        .wait_for(|inhibitors| *inhibitors == 0)
+        .await
+        .is_err()
+    {
+        std::future::pending().await
+    }
+}
+
+/// Add an inhibitor that lasts until the returned socket is closed,
+/// so it's also released if the inhibiting process dies.
+pub fn inhibit(who: String, reason: String) -> std::io::Result<UnixStream> {
+    let (mut stream, client) = UnixStream::pair()?;
+
+    INHIBITORS.send_modify(|inhibitors| *inhibitors += 1);
+    tracing::info!("Fan control inhibited by `{who}`: {reason}");
+
+    let release = |who: &str| {
+        INHIBITORS.send_modify(|inhibitors| *inhibitors -= 1);
+        tracing::info!("Fan control inhibitor of `{who}` released");
+    };
+    let thread_who = who.clone();
+    let result = std::thread::Builder::new()
+        .name("fan-inhibitor".to_owned())
+        .spawn(move || {
+            // Returns once the other side is closed.
+            let mut buf = [0; 64];
+            while matches!(stream.read(&mut buf), Ok(len) if len > 0) {}
+            release(&thread_who);
+        });
+    if let Err(err) = result {
+        release(&who);
+        return Err(err);
+    }
+    Ok(client)
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::{check_write, get_inhibit_receiver, inhibit};
+
+    #[test]
+    fn release_on_close() {
+        let receiver = get_inhibit_receiver();
+        let inhibitor = inhibit("test".to_owned(), "testing".to_owned()).unwrap();
+        assert_eq!(*receiver.borrow(), 1);
+        assert!(check_write("test").is_err());
+
+        drop(inhibitor);
+        let start = Instant::now();
+        while *receiver.borrow() != 0 {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(check_write("test").is_ok());
+    }
+}
@@ -0,0 +1,82 @@
+//! Makes sure that only one instance of tailord runs,
+//! since two of them would fight over the fans.
+
+use std::{
+    fs::File,
+    io::Write,
+    os::fd::AsRawFd,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use nix::{
+    errno::Errno,
+    fcntl::{flock, FlockArg},
+};
+
+use crate::config;
+
+/// Locked while tailord runs and contains its pid.
+const LOCK_FILE: &str = "tailord.lock";
+
+/// The locked file, held open until the process exits.
+static LOCK: OnceLock<File> = OnceLock::new();
+
+fn lock_path() -> PathBuf {
+    config::state_dir().join(LOCK_FILE)
+}
+
+/// Lock the instance, or exit if another instance holds the lock.
+/// Failing to create the lock file isn't fatal, it only means
+/// that other instances aren't detected.
+pub fn lock() {
+    let path = lock_path();
+    let file = std::fs::create_dir_all(config::state_dir()).and_then(|_| {
+        File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+    });
+    let file = match file {
+        Ok(file) => file,
+        Err(err) => {
+            tracing::warn!("Failed to open the lock file `{path:?}`: `{err}`");
+            return;
+        }
+    };
+
+    match flock(file.as_raw_fd(), FlockArg::LockExclusiveNonblock) {
+        Ok(()) => {
+            let mut lock = file;
+            let result = lock
+                .set_len(0)
+                .and_then(|_| write!(lock, "{}", std::process::id()));
+            if let Err(err) = result {
+                tracing::warn!("Failed to write the pid to `{path:?}`: `{err}`");
+            }
+            LOCK.set(lock).ok();
+        }
+        Err(Errno::EWOULDBLOCK) => {
+            match read_pid(&path) {
+                Some(pid) => tracing::error!("tailord is already running with pid {pid}, exiting"),
+                None => tracing::error!("tailord is already running, exiting"),
+            }
+            std::process::exit(1);
+        }
+        Err(err) => tracing::warn!("Failed to lock `{path:?}`: `{err}`"),
+    }
+}
+
+/// The pid of the running instance, without taking the lock
+/// so that it doesn't keep a starting instance from running.
+pub fn running() -> Option<u32> {
+    let pid = read_pid(&lock_path())?;
+    let comm = std::fs::read_to_string(format!("/proc/{pid}/comm")).ok()?;
+    (comm.trim() == "tailord").then_some(pid)
+}
+
+fn read_pid(path: &Path) -> Option<u32> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
@@ -0,0 +1,52 @@
+//! Keeps the tuxedo_keyboard parameters that were set over D-Bus across
+//! restarts, so they don't have to be set as modprobe options.
+
+use std::{collections::BTreeMap, io, path::PathBuf};
+
+use tuxedo_sysfs::keyboard::{KeyboardParameters, Parameter};
+
+use crate::config;
+
+const PARAMETERS_STATE: &str = "keyboard-parameters.json";
+
+fn state_path() -> PathBuf {
+    config::state_dir().join(PARAMETERS_STATE)
+}
+
+/// The stored values by parameter name, in the format of the driver.
+fn stored() -> io::Result<BTreeMap<String, String>> {
+    let data = std::fs::read(state_path())?;
+    serde_json::from_slice(&data).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Apply the values that were set last, if any.
+pub fn restore(parameters: &KeyboardParameters) {
+    let Ok(stored) = stored() else {
+        return;
+    };
+    for (name, value) in stored {
+        let Some(parameter) = Parameter::from_name(&name) else {
+            tracing::warn!("Unknown keyboard parameter `{name}`");
+            continue;
+        };
+        if let Err(err) = parameters.set(parameter, &value) {
+            tracing::error!("Failed to restore keyboard parameter `{name}`: `{err}`");
+        }
+    }
+}
+
+pub fn store(parameter: Parameter, value: &str) {
+    let result = std::fs::create_dir_all(config::state_dir()).and_then(|()| {
+        let mut stored = stored().unwrap_or_default();
+        stored.insert(parameter.name().to_owned(), value.to_owned());
+        let data = serde_json::to_vec(&stored)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        std::fs::write(state_path(), data)
+    });
+    if let Err(err) = result {
+        tracing::warn!(
+            "Failed to store keyboard parameter `{}`: `{err}`",
+            parameter.name()
+        );
+    }
+}
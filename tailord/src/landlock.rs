@@ -0,0 +1,219 @@
+//! Landlock rules that restrict tailord to the files it manages.
+//!
+//! The rules only apply to the calling thread and the threads it starts
+//! afterwards, so they're applied before the runtime starts.
+//! Paths that don't exist are skipped.
+
+use std::{
+    fs::OpenOptions,
+    io,
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd},
+        unix::fs::OpenOptionsExt,
+    },
+    path::PathBuf,
+};
+
+use nix::libc;
+
+use crate::config;
+
+const CREATE_RULESET_VERSION: u32 = 1 << 0;
+const RULE_PATH_BENEATH: u32 = 1;
+
+const ACCESS_EXECUTE: u64 = 1 << 0;
+const ACCESS_WRITE_FILE: u64 = 1 << 1;
+const ACCESS_READ_FILE: u64 = 1 << 2;
+const ACCESS_READ_DIR: u64 = 1 << 3;
+const ACCESS_REMOVE_DIR: u64 = 1 << 4;
+const ACCESS_REMOVE_FILE: u64 = 1 << 5;
+const ACCESS_MAKE_DIR: u64 = 1 << 7;
+const ACCESS_MAKE_REG: u64 = 1 << 8;
+const ACCESS_MAKE_SYM: u64 = 1 << 12;
+/// Moving files between directories, since ABI version 2.
+const ACCESS_REFER: u64 = 1 << 13;
+/// Truncating files, since ABI version 3.
+const ACCESS_TRUNCATE: u64 = 1 << 14;
+/// Ioctls on device files, since ABI version 5.
+const ACCESS_IOCTL_DEV: u64 = 1 << 15;
+
+/// All access rights of ABI version 1.
+const ACCESS_V1: u64 = (1 << 13) - 1;
+
+const READ: u64 = ACCESS_READ_FILE | ACCESS_READ_DIR;
+const WRITE: u64 = ACCESS_WRITE_FILE
+    | ACCESS_REMOVE_DIR
+    | ACCESS_REMOVE_FILE
+    | ACCESS_MAKE_DIR
+    | ACCESS_MAKE_REG
+    | ACCESS_MAKE_SYM
+    | ACCESS_REFER
+    | ACCESS_TRUNCATE;
+
+#[repr(C)]
+struct RulesetAttr {
+    handled_access_fs: u64,
+}
+
+#[repr(C, packed)]
+struct PathBeneathAttr {
+    allowed_access: u64,
+    parent_fd: i32,
+}
+
+/// Restrict the daemon to the paths it needs.
+/// Returns `false` if the kernel doesn't support Landlock.
+pub fn apply() -> Result<bool, io::Error> {
+    // SAFETY: Querying the version takes no attributes.
+    let abi = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            std::ptr::null::<RulesetAttr>(),
+            0usize,
+            CREATE_RULESET_VERSION,
+        )
+    };
+    if abi < 0 {
+        let err = io::Error::last_os_error();
+        return match err.raw_os_error() {
+            Some(libc::ENOSYS | libc::EOPNOTSUPP) => Ok(false),
+            _ => Err(err),
+        };
+    }
+
+    let handled = handled_access(abi);
+    let attr = RulesetAttr {
+        handled_access_fs: handled,
+    };
+    // SAFETY: `attr` is valid for the given size.
+    let fd = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            &attr as *const RulesetAttr,
+            std::mem::size_of::<RulesetAttr>(),
+            0u32,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: The kernel returned a new file descriptor.
+    let ruleset = unsafe { OwnedFd::from_raw_fd(fd as i32) };
+
+    // The state directory has to exist to be allowed.
+    std::fs::create_dir_all(config::state_dir()).ok();
+    for (path, access) in rules() {
+        add_rule(&ruleset, path, access & handled)?;
+    }
+
+    // SAFETY: The arguments are plain integers.
+    if unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `ruleset` is a valid Landlock ruleset.
+    if unsafe { libc::syscall(libc::SYS_landlock_restrict_self, ruleset.as_raw_fd(), 0u32) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(true)
+}
+
+/// The access rights the kernel knows, everything else can't be restricted.
+fn handled_access(abi: i64) -> u64 {
+    let mut access = ACCESS_V1;
+    if abi >= 2 {
+        access |= ACCESS_REFER;
+    }
+    if abi >= 3 {
+        access |= ACCESS_TRUNCATE;
+    }
+    if abi >= 5 {
+        access |= ACCESS_IOCTL_DEV;
+    }
+    access
+}
+
+fn rules() -> Vec<(PathBuf, u64)> {
+    let mut rules = vec![
+        (config::config_dir().to_owned(), READ | WRITE),
+        (config::state_dir().to_owned(), READ | WRITE),
+        // Sysfs attributes and the EC.
+        ("/sys".into(), READ | ACCESS_WRITE_FILE),
+        (
+            "/dev/tuxedo_io".into(),
+            ACCESS_READ_FILE | ACCESS_WRITE_FILE | ACCESS_IOCTL_DEV,
+        ),
+        // Keyboards for the typing effect.
+        ("/dev/input".into(), READ | ACCESS_IOCTL_DEV),
+        // Profiles of users, the user database and the time zone.
+        ("/home".into(), READ),
+        ("/root".into(), READ),
+        ("/etc".into(), READ),
+        ("/proc".into(), READ),
+        // Libraries and the programs of critical temperature commands.
+        ("/usr".into(), READ | ACCESS_EXECUTE),
+        ("/bin".into(), READ | ACCESS_EXECUTE),
+        ("/lib".into(), READ | ACCESS_EXECUTE),
+        ("/lib64".into(), READ | ACCESS_EXECUTE),
+        ("/nix/store".into(), READ | ACCESS_EXECUTE),
+    ];
+    // Per-key controllers without a kernel driver.
+    if let Ok(entries) = std::fs::read_dir("/dev") {
+        rules.extend(
+            entries
+                .flatten()
+                .filter(|entry| entry.file_name().to_string_lossy().starts_with("hidraw"))
+                .map(|entry| {
+                    (
+                        entry.path(),
+                        ACCESS_READ_FILE | ACCESS_WRITE_FILE | ACCESS_IOCTL_DEV,
+                    )
+                }),
+        );
+    }
+    rules
+}
+
+fn add_rule(ruleset: &OwnedFd, path: PathBuf, access: u64) -> Result<(), io::Error> {
+    let file = match OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_PATH | libc::O_CLOEXEC)
+        .open(&path)
+    {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    // Directory rights are invalid for files.
+    let access = if file.metadata()?.is_dir() {
+        access
+    } else {
+        access
+            & (ACCESS_EXECUTE
+                | ACCESS_WRITE_FILE
+                | ACCESS_READ_FILE
+                | ACCESS_TRUNCATE
+                | ACCESS_IOCTL_DEV)
+    };
+    let attr = PathBeneathAttr {
+        allowed_access: access,
+        parent_fd: file.as_raw_fd(),
+    };
+    // SAFETY: `attr` outlives the call and `file` is open.
+    let res = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_add_rule,
+            ruleset.as_raw_fd(),
+            RULE_PATH_BENEATH,
+            &attr as *const PathBeneathAttr,
+            0u32,
+        )
+    };
+    if res != 0 {
+        let err = io::Error::last_os_error();
+        return Err(io::Error::new(
+            err.kind(),
+            format!("Failed to allow {path:?}: {err}"),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,159 @@
+//! Follows the brightness keys of the keyboard. The firmware changes the
+//! brightness of the LED device without telling tailord, so the attribute
+//! is polled to keep the published state of the device in sync.
+
+use std::{path::PathBuf, time::Duration};
+
+use tailor_api::{Color, LedControllerMode, LedDeviceInfo, LedDeviceState};
+use tokio::sync::watch;
+use tuxedo_sysfs::attribute::Attribute;
+use zbus::Connection;
+
+use crate::{dbus::LedInterface, DBUS_PATH};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct BrightnessWatcher {
+    pub(super) info: LedDeviceInfo,
+    pub(super) path: PathBuf,
+    pub(super) max_brightness: u32,
+    pub(super) state_sender: watch::Sender<LedDeviceState>,
+}
+
+impl BrightnessWatcher {
+    pub async fn run(self, connection: Connection) {
+        let mut attribute = None;
+        loop {
+            let value = match self.read(&mut attribute).await {
+                Ok(value) => value,
+                Err(err) => {
+                    // The device might be gone until it's plugged in again.
+                    tracing::debug!("Failed to read the brightness of {:?}: `{err}`", self.path);
+                    attribute = None;
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                    continue;
+                }
+            };
+            if let Some(brightness) = self.update_state(value) {
+                tracing::info!(
+                    "Brightness of `{}` changed to {brightness}%",
+                    self.info.device_id()
+                );
+                if let Err(err) = self.emit_brightness_changed(&connection, brightness).await {
+                    tracing::warn!("Failed to emit the brightness change: `{err}`");
+                }
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    async fn read(&self, attribute: &mut Option<Attribute>) -> std::io::Result<u32> {
+        if attribute.is_none() {
+            *attribute = Some(Attribute::open(&self.path).await?);
+        }
+        attribute.as_ref().unwrap().read_value().await
+    }
+
+    /// Publish the brightness that was read. Returns the brightness in
+    /// percent if it was changed by the keys and not by tailord.
+    fn update_state(&self, value: u32) -> Option<u8> {
+        let brightness = brightness_percent(value, self.max_brightness);
+        let mut changed_by_keys = None;
+        self.state_sender.send_if_modified(|state| {
+            if state.brightness == Some(brightness) {
+                return false;
+            }
+            let initial = state.brightness.is_none();
+            state.brightness = Some(brightness);
+            let by_keys = match self.info.mode {
+                // The colors of monochrome devices are their brightness,
+                // which tailord writes itself.
+                LedControllerMode::Monochrome => {
+                    let written = state
+                        .color
+                        .as_ref()
+                        .map(|color| color.sysfs_monochrome_string(self.max_brightness));
+                    let by_keys = written != Some(value.to_string());
+                    if by_keys {
+                        state.color =
+                            Some(Color::from_sysfs_rgb_value([value; 3], self.max_brightness));
+                    }
+                    by_keys
+                }
+                _ => true,
+            };
+            if by_keys && !initial {
+                changed_by_keys = Some(brightness);
+            }
+            true
+        });
+        changed_by_keys
+    }
+
+    async fn emit_brightness_changed(
+        &self,
+        connection: &Connection,
+        brightness: u8,
+    ) -> zbus::Result<()> {
+        let iface = connection
+            .object_server()
+            .interface::<_, LedInterface>(DBUS_PATH)
+            .await?;
+        LedInterface::brightness_changed(iface.signal_context(), &self.info.device_id(), brightness)
+            .await
+    }
+}
+
+fn brightness_percent(value: u32, max_brightness: u32) -> u8 {
+    (value as f64 * 100.0 / max_brightness as f64)
+        .round()
+        .min(100.0) as u8
+}
+
+#[cfg(test)]
+mod test {
+    use tailor_api::{Color, LedControllerMode, LedDeviceInfo, LedDeviceState};
+    use tokio::sync::watch;
+
+    use super::BrightnessWatcher;
+
+    #[test]
+    fn brightness_keys() {
+        let info = LedDeviceInfo {
+            device_name: "white".to_owned(),
+            function: "kbd_backlight".to_owned(),
+            mode: LedControllerMode::Monochrome,
+        };
+        let (state_sender, state_receiver) = watch::channel(LedDeviceState {
+            info: info.clone(),
+            color: Some(Color {
+                r: 255,
+                g: 255,
+                b: 255,
+            }),
+            color_override: None,
+            brightness: None,
+        });
+        let watcher = BrightnessWatcher {
+            info,
+            path: Default::default(),
+            max_brightness: 4,
+            state_sender,
+        };
+
+        // Written by tailord.
+        assert_eq!(watcher.update_state(4), None);
+        assert_eq!(state_receiver.borrow().brightness, Some(100));
+        // Changed with the keys.
+        assert_eq!(watcher.update_state(2), Some(50));
+        assert_eq!(
+            state_receiver.borrow().color,
+            Some(Color {
+                r: 127,
+                g: 127,
+                b: 127
+            })
+        );
+        assert_eq!(watcher.update_state(2), None);
+    }
+}
@@ -0,0 +1,147 @@
+//! Detection of LED devices that reappear while the daemon is running,
+//! for example after a USB reset of an external keyboard or after resume.
+//! The kernel announces such devices via uevents on a netlink socket.
+
+use std::{
+    io,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+    path::{Path, PathBuf},
+};
+
+use nix::sys::socket::{
+    bind, recv, socket, AddressFamily, MsgFlags, NetlinkAddr, SockFlag, SockProtocol, SockType,
+};
+use tailor_api::LedDeviceInfo;
+use tokio::sync::mpsc;
+use tuxedo_sysfs::led::Controller;
+
+const SYSFS_PATH: &str = "/sys";
+/// Multicast group of kernel uevents.
+const KERNEL_UEVENT_GROUP: u32 = 1;
+const UEVENT_BUFFER_SIZE: usize = 8192;
+
+/// Used to hand a newly detected controller to its LED runtime.
+pub struct LedHotplugHandle {
+    pub info: LedDeviceInfo,
+    pub controller_sender: mpsc::Sender<Controller>,
+}
+
+/// Wait for LED devices to be added and pass them to the
+/// runtime of the matching device, so it can reapply its profile.
+pub async fn watch_led_devices(handles: Vec<LedHotplugHandle>) {
+    if handles.is_empty() {
+        return;
+    }
+
+    let (path_sender, mut path_receiver) = mpsc::channel(8);
+    std::thread::spawn(move || {
+        if let Err(err) = listen_for_uevents(&path_sender) {
+            tracing::warn!("Stopped listening for LED hotplug events: `{err}`");
+        }
+    });
+
+    while let Some(path) = path_receiver.recv().await {
+        let controller = match Controller::from_path(&path).await {
+            Ok(Some(controller)) => controller,
+            Ok(None) => continue,
+            Err(err) => {
+                tracing::warn!("Failed to load hotplugged LED device {path:?}: `{err}`");
+                continue;
+            }
+        };
+
+        if let Some(handle) = handles.iter().find(|handle| {
+            handle.info.device_name == controller.device_name
+                && handle.info.function == controller.function
+        }) {
+            tracing::info!("LED device `{}` reappeared", handle.info.device_id());
+            if handle.controller_sender.send(controller).await.is_err() {
+                tracing::warn!("LED runtime of `{}` stopped", handle.info.device_id());
+            }
+        } else {
+            tracing::debug!("Ignoring unknown LED device {path:?}");
+        }
+    }
+}
+
+/// Blocking loop that forwards the sysfs paths of added LED devices.
+fn listen_for_uevents(path_sender: &mpsc::Sender<PathBuf>) -> io::Result<()> {
+    let fd = socket(
+        AddressFamily::Netlink,
+        SockType::Datagram,
+        SockFlag::SOCK_CLOEXEC,
+        SockProtocol::NetlinkKObjectUEvent,
+    )?;
+    // SAFETY: The socket was just created and isn't owned by anything else.
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+    // With port id 0, the kernel assigns a free one. The pid
+    // may already be taken by another netlink socket of tailord.
+    bind(fd.as_raw_fd(), &NetlinkAddr::new(0, KERNEL_UEVENT_GROUP))?;
+
+    let mut buffer = vec![0; UEVENT_BUFFER_SIZE];
+    loop {
+        let len = recv(fd.as_raw_fd(), &mut buffer, MsgFlags::empty())?;
+        if let Some(path) = parse_led_add_event(&buffer[..len]) {
+            if path_sender.blocking_send(path).is_err() {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Returns the sysfs path of the device if the uevent
+/// announces a new device of the `leds` subsystem.
+fn parse_led_add_event(message: &[u8]) -> Option<PathBuf> {
+    let mut action = None;
+    let mut subsystem = None;
+    let mut devpath = None;
+
+    // The message starts with a `ACTION@DEVPATH` header
+    // followed by null-terminated `KEY=VALUE` pairs.
+    for field in message.split(|byte| *byte == 0).skip(1) {
+        let field = std::str::from_utf8(field).ok()?;
+        if let Some((key, value)) = field.split_once('=') {
+            match key {
+                "ACTION" => action = Some(value),
+                "SUBSYSTEM" => subsystem = Some(value),
+                "DEVPATH" => devpath = Some(value),
+                _ => {}
+            }
+        }
+    }
+
+    if action? == "add" && subsystem? == "leds" {
+        Some(Path::new(SYSFS_PATH).join(devpath?.trim_start_matches('/')))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::parse_led_add_event;
+
+    #[test]
+    fn led_add_event() {
+        let message = b"add@/devices/platform/tuxedo_keyboard/leds/rgb:kbd_backlight\0\
+            ACTION=add\0DEVPATH=/devices/platform/tuxedo_keyboard/leds/rgb:kbd_backlight\0\
+            SUBSYSTEM=leds\0SEQNUM=4242\0";
+        assert_eq!(
+            parse_led_add_event(message),
+            Some(PathBuf::from(
+                "/sys/devices/platform/tuxedo_keyboard/leds/rgb:kbd_backlight"
+            ))
+        );
+
+        let message = b"remove@/devices/platform/tuxedo_keyboard/leds/rgb:kbd_backlight\0\
+            ACTION=remove\0DEVPATH=/devices/platform/tuxedo_keyboard/leds/rgb:kbd_backlight\0\
+            SUBSYSTEM=leds\0SEQNUM=4243\0";
+        assert_eq!(parse_led_add_event(message), None);
+
+        let message = b"add@/devices/virtual/input/input42\0\
+            ACTION=add\0DEVPATH=/devices/virtual/input/input42\0SUBSYSTEM=input\0";
+        assert_eq!(parse_led_add_event(message), None);
+    }
+}
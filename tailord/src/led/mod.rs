@@ -1,47 +1,176 @@
-use tailor_api::{Color, ColorProfile, LedDeviceInfo};
-use tokio::sync::mpsc;
+use tailor_api::{Color, ColorProfile, LedDeviceInfo, LedDeviceState};
+use tokio::sync::{mpsc, oneshot, watch};
 use tuxedo_sysfs::led::Controller;
 
+use crate::{
+    config::ColorCorrection,
+    inhibit::get_inhibit_receiver,
+    lighting::get_lights_off_receiver,
+    night_shift::get_temperature_receiver,
+    util::{self, WriteAck},
+};
+
+mod brightness;
+mod hotplug;
 pub mod runtime;
+pub mod typing;
+
+pub use brightness::BrightnessWatcher;
+pub use hotplug::{watch_led_devices, LedHotplugHandle};
 
 pub struct LedRuntime {
     data: LedRuntimeData,
-    profile_receiver: mpsc::Receiver<ColorProfile>,
+    profile_receiver: mpsc::Receiver<ProfileUpdate>,
     color_receiver: mpsc::Receiver<Color>,
+    color_override_receiver: mpsc::Receiver<Option<Color>>,
+    /// Receives a new controller if the device was added again.
+    controller_receiver: mpsc::Receiver<Controller>,
+    controller_sender: mpsc::Sender<Controller>,
+    /// Whether the lighting schedule turned the LEDs off.
+    lights_off_receiver: watch::Receiver<bool>,
+    /// Notifies about new night shift temperatures.
+    temperature_receiver: watch::Receiver<u32>,
+    /// The number of tools that took over the EC.
+    inhibit_receiver: watch::Receiver<usize>,
 }
 
 pub struct LedRuntimeData {
-    pub controller: Controller,
-    pub profile: ColorProfile,
+    controller: Controller,
+    profile: ColorProfile,
+    /// A color that replaces the profile until the next
+    /// profile is loaded or the override is cleared.
+    color_override: Option<Color>,
+    /// Applied to every color before it's written.
+    correction: Option<ColorCorrection>,
+    /// The color temperature of the night shift.
+    temperature_receiver: watch::Receiver<u32>,
+    /// Publishes the current state of the device.
+    state_sender: watch::Sender<LedDeviceState>,
+    /// Receives the result of the next write after a profile was applied.
+    written: Option<WriteAck>,
 }
 
 #[derive(Clone)]
 pub struct LedRuntimeHandle {
     pub info: LedDeviceInfo,
-    pub profile_sender: mpsc::Sender<ColorProfile>,
+    profile_sender: mpsc::Sender<ProfileUpdate>,
     pub color_sender: mpsc::Sender<Color>,
+    pub color_override_sender: mpsc::Sender<Option<Color>>,
+    pub state_receiver: watch::Receiver<LedDeviceState>,
+}
+
+struct ProfileUpdate {
+    profile: ColorProfile,
+    written: Option<WriteAck>,
+}
+
+impl LedRuntimeHandle {
+    /// Switch the device to a color profile.
+    pub async fn set_profile(&self, profile: ColorProfile) -> Result<(), String> {
+        self.send_profile(profile, None).await
+    }
+
+    /// Switch the device to a color profile and wait for the first write
+    /// of its colors. Succeeds without a write if the profile leaves the
+    /// colors unchanged or the EC is inhibited.
+    pub async fn apply_profile(&self, profile: ColorProfile) -> Result<(), String> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_profile(profile, Some(sender)).await?;
+        util::first_write(receiver).await
+    }
+
+    async fn send_profile(
+        &self,
+        profile: ColorProfile,
+        written: Option<WriteAck>,
+    ) -> Result<(), String> {
+        self.profile_sender
+            .send(ProfileUpdate { profile, written })
+            .await
+            .map_err(|err| err.to_string())
+    }
 }
 
 impl LedRuntime {
-    pub fn new(data: LedRuntimeData) -> (LedRuntimeHandle, Self) {
+    pub fn new(
+        controller: Controller,
+        profile: ColorProfile,
+        correction: Option<ColorCorrection>,
+    ) -> (LedRuntimeHandle, Self) {
         let (profile_sender, profile_receiver) = mpsc::channel(1);
         let (color_sender, color_receiver) = mpsc::channel(1);
+        let (color_override_sender, color_override_receiver) = mpsc::channel(1);
+        let (controller_sender, controller_receiver) = mpsc::channel(1);
+
+        let info = LedDeviceInfo {
+            device_name: controller.device_name.clone(),
+            function: controller.function.clone(),
+            mode: controller.mode(),
+        };
+        let (state_sender, state_receiver) = watch::channel(LedDeviceState {
+            info: info.clone(),
+            color: None,
+            color_override: None,
+            brightness: None,
+        });
 
         (
             LedRuntimeHandle {
-                info: LedDeviceInfo {
-                    device_name: data.controller.device_name.clone(),
-                    function: data.controller.function.clone(),
-                    mode: data.controller.mode(),
-                },
+                info,
                 profile_sender,
                 color_sender,
+                color_override_sender,
+                state_receiver,
             },
             Self {
-                data,
+                data: LedRuntimeData {
+                    controller,
+                    profile,
+                    color_override: None,
+                    correction,
+                    temperature_receiver: get_temperature_receiver(),
+                    state_sender,
+                    written: None,
+                },
                 profile_receiver,
                 color_receiver,
+                color_override_receiver,
+                controller_receiver,
+                controller_sender,
+                lights_off_receiver: get_lights_off_receiver(),
+                temperature_receiver: get_temperature_receiver(),
+                inhibit_receiver: get_inhibit_receiver(),
             },
         )
     }
+
+    /// Get a handle to replace the controller of this runtime
+    /// once the LED device reappears.
+    pub fn hotplug_handle(&self) -> LedHotplugHandle {
+        let controller = &self.data.controller;
+        LedHotplugHandle {
+            info: LedDeviceInfo {
+                device_name: controller.device_name.clone(),
+                function: controller.function.clone(),
+                mode: controller.mode(),
+            },
+            controller_sender: self.controller_sender.clone(),
+        }
+    }
+
+    /// Get a watcher that keeps the state in sync with the brightness keys
+    /// of the keyboard. `None` if the device isn't driven via sysfs.
+    pub fn brightness_watcher(&self) -> Option<BrightnessWatcher> {
+        let controller = &self.data.controller;
+        Some(BrightnessWatcher {
+            info: LedDeviceInfo {
+                device_name: controller.device_name.clone(),
+                function: controller.function.clone(),
+                mode: controller.mode(),
+            },
+            path: controller.brightness_path()?.to_owned(),
+            max_brightness: controller.max_brightness(),
+            state_sender: self.data.state_sender.clone(),
+        })
+    }
 }
@@ -1,28 +1,61 @@
-use std::{future::pending, time::Duration};
+use std::{
+    future::pending,
+    io,
+    time::{Duration, Instant},
+};
 
-use tailor_api::{Color, ColorPoint, ColorProfile, ColorTransition};
-use tokio::sync::broadcast;
+use tailor_api::{Color, ColorPoint, ColorProfile, ColorRegion, ColorTransition, TypingEffect};
+use tokio::sync::{broadcast, watch};
 
-use crate::suspend::{get_suspend_receiver, process_suspend};
+use crate::{
+    night_shift,
+    suspend::{get_suspend_receiver, process_suspend},
+};
 
-use super::{LedRuntime, LedRuntimeData};
+use super::{typing, LedRuntime, LedRuntimeData, ProfileUpdate};
+
+const OFF: Color = Color { r: 0, g: 0, b: 0 };
+/// The frame time of animated regions, the same as the
+/// maximum step size of single color animations.
+const FRAME_TIME: Duration = Duration::from_millis(80);
 
 impl LedRuntime {
     pub async fn run(mut self) {
         let mut suspend_receiver = get_suspend_receiver();
 
         loop {
+            let lights_off = *self.lights_off_receiver.borrow_and_update();
+            let temperature = *self.temperature_receiver.borrow_and_update();
+            // The colors are written again once the EC is released.
+            let inhibited = *self.inhibit_receiver.borrow_and_update() > 0;
+            // Animations pick up new temperatures with their next color.
+            let animated = self.data.is_animated();
             tokio::select! {
-                new_colors = self.profile_receiver.recv() => {
-                    if let Some(colors) = new_colors {
-                        self.data.profile = colors;
+                update = self.profile_receiver.recv() => {
+                    if let Some(update) = update {
+                        self.data.set_profile(update);
+                        if inhibited {
+                            self.data.acknowledge(Ok(()));
+                        }
+                    }
+                }
+                // The device reappeared, the colors are reapplied
+                // once the loop starts updating the colors again.
+                controller = self.controller_receiver.recv() => {
+                    if let Some(controller) = controller {
+                        self.data.controller = controller;
+                    }
+                }
+                color_override = self.color_override_receiver.recv() => {
+                    if let Some(color) = color_override {
+                        self.data.set_color_override(color);
                     }
                 }
                 // Override the current color value for 1s
                 override_color = self.color_receiver.recv() => {
-                    if let Some(mut color) = override_color {
+                    if let Some(mut color) = override_color.filter(|_| !inhibited) {
                         loop {
-                            if let Err(err) = self.data.controller.set_color(&color).await {
+                            if let Err(err) = self.data.set_color(&color).await {
                                 tracing::error!("Failed to update keyboard color: `{}`", err.to_string());
                                 break;
                             }
@@ -37,28 +70,167 @@ impl LedRuntime {
                         }
                     }
                 }
-                _ = self.data.update_colors(&mut suspend_receiver) => {}
+                // Restart the colors with the new schedule state.
+                changed = self.lights_off_receiver.changed() => {
+                    if changed.is_err() {
+                        // The schedule can't change anymore.
+                        self.lights_off_receiver = watch::channel(lights_off).1;
+                    }
+                }
+                // Rewrite static colors with the new night shift temperature.
+                changed = self.temperature_receiver.changed(), if !animated => {
+                    if changed.is_err() {
+                        self.temperature_receiver = watch::channel(temperature).1;
+                    }
+                }
+                // The inhibitors are counted by a static sender that never closes.
+                _ = self.inhibit_receiver.changed() => {}
+                _ = self.data.update_colors(&mut suspend_receiver, lights_off), if !inhibited => {}
             }
         }
     }
 }
 
 impl LedRuntimeData {
-    pub async fn update_colors(&mut self, suspend_receiver: &mut broadcast::Receiver<bool>) {
-        match &self.profile {
-            ColorProfile::None => pending().await,
-            ColorProfile::Single(color) => {
-                self.controller.set_color(color).await.unwrap();
+    pub async fn update_colors(
+        &mut self,
+        suspend_receiver: &mut broadcast::Receiver<bool>,
+        lights_off: bool,
+    ) {
+        if lights_off {
+            let off = Color { r: 0, g: 0, b: 0 };
+            if let Err(err) = self.set_color(&off).await {
+                tracing::error!("Failed turning the LEDs off: `{err}`")
+            }
+            return process_suspend(suspend_receiver).await;
+        }
+
+        if let Some(color) = self.color_override.clone() {
+            if let Err(err) = self.set_color(&color).await {
+                tracing::error!("Failed setting keyboard colors: `{err}`")
+            }
+            return pending().await;
+        }
+
+        // Only per-key controllers show all regions at once, other
+        // devices show the region that contains their LED.
+        let profile = match &self.profile {
+            ColorProfile::Regions(regions) if self.controller.key_count().is_some() => {
+                let regions = regions.clone();
+                return self.run_regions(&regions, suspend_receiver).await;
+            }
+            profile => profile
+                .region(typing::led_index(self.controller.function()))
+                .cloned()
+                .unwrap_or(ColorProfile::Single(OFF)),
+        };
+
+        match profile {
+            ColorProfile::None | ColorProfile::Regions(_) => {
+                // Nothing is written.
+                self.acknowledge(Ok(()));
                 pending().await
             }
+            ColorProfile::Single(color) => {
+                if let Err(err) = self.set_color(&color).await {
+                    tracing::error!("Failed setting keyboard colors: `{err}`")
+                }
+                // Some devices are reset during suspend,
+                // so reapply the color after waking up.
+                process_suspend(suspend_receiver).await
+            }
             ColorProfile::Multiple(colors) => {
-                let color_steps = calculate_color_animation_steps(colors);
+                let color_steps = calculate_color_animation_steps(&colors);
                 self.run_color_animation(&color_steps, suspend_receiver)
                     .await;
             }
+            ColorProfile::Typing(effect) => {
+                self.run_typing_effect(&effect, suspend_receiver).await;
+            }
         }
     }
 
+    /// Write a color to the device and publish it as the current state.
+    /// Brightness-only devices show colors of RGB profiles as gray.
+    async fn set_color(&mut self, color: &Color) -> Result<(), io::Error> {
+        let color = if self.controller.mode().supports_color() {
+            color.clone()
+        } else {
+            color.to_monochrome()
+        };
+        let color = self.correct(&color);
+        let written = self.controller.set_color(&color).await;
+        self.acknowledge(written.as_ref().map(|_| ()).map_err(|err| err.to_string()));
+        written?;
+        self.state_sender
+            .send_modify(|state| state.color = Some(color));
+        Ok(())
+    }
+
+    /// Write the colors of all keys and publish the first one as the current state.
+    async fn set_key_colors(&mut self, colors: &[Color]) -> Result<(), io::Error> {
+        let colors: Vec<Color> = colors.iter().map(|color| self.correct(color)).collect();
+        let written = self.controller.set_key_colors(&colors).await;
+        self.acknowledge(written.as_ref().map(|_| ()).map_err(|err| err.to_string()));
+        written?;
+        self.state_sender
+            .send_modify(|state| state.color = colors.first().cloned());
+        Ok(())
+    }
+
+    /// Warm the colors of RGB devices at night and apply the color correction.
+    fn correct(&self, color: &Color) -> Color {
+        let color = if self.controller.mode().supports_color() {
+            night_shift::shift(color, *self.temperature_receiver.borrow())
+        } else {
+            color.clone()
+        };
+        match &self.correction {
+            Some(correction) => correction.apply(&color),
+            None => color,
+        }
+    }
+
+    /// Whether the colors change by themselves.
+    fn is_animated(&self) -> bool {
+        let animated = |profile: &ColorProfile| matches!(profile, ColorProfile::Multiple(_));
+        if self.color_override.is_some() {
+            return false;
+        }
+        match &self.profile {
+            ColorProfile::Regions(regions) if self.controller.key_count().is_some() => {
+                regions.iter().any(|region| animated(&region.profile))
+            }
+            profile => profile
+                .region(typing::led_index(self.controller.function()))
+                .is_some_and(animated),
+        }
+    }
+
+    fn set_profile(&mut self, update: ProfileUpdate) {
+        self.profile = update.profile;
+        self.set_color_override(None);
+        if let Some(written) = update.written {
+            // The previous profile was replaced before it was written.
+            if let Some(previous) = self.written.replace(written) {
+                previous.send(Ok(())).ok();
+            }
+        }
+    }
+
+    /// Report the first write after a profile was applied.
+    fn acknowledge(&mut self, result: Result<(), String>) {
+        if let Some(written) = self.written.take() {
+            written.send(result).ok();
+        }
+    }
+
+    fn set_color_override(&mut self, color: Option<Color>) {
+        self.state_sender
+            .send_modify(|state| state.color_override = color.clone());
+        self.color_override = color;
+    }
+
     /// Infinitely run a color animation and
     /// stop the animation while suspended.
     async fn run_color_animation(
@@ -67,7 +239,7 @@ impl LedRuntimeData {
         suspend_receiver: &mut broadcast::Receiver<bool>,
     ) {
         for step in color_steps.iter().cycle() {
-            if let Err(err) = self.controller.set_color(&step.0).await {
+            if let Err(err) = self.set_color(&step.0).await {
                 tracing::error!("Failed setting keyboard colors: `{err}`")
             }
 
@@ -77,6 +249,213 @@ impl LedRuntimeData {
             }
         }
     }
+
+    /// Composite the regions of a per-key controller into frames. Frames are
+    /// only written while something is animated and stop while suspended.
+    async fn run_regions(
+        &mut self,
+        regions: &[ColorRegion],
+        suspend_receiver: &mut broadcast::Receiver<bool>,
+    ) {
+        let layers: Vec<Layer> = regions
+            .iter()
+            .map(|region| Layer::new(&region.profile))
+            .collect();
+        let key_count = self.controller.key_count().unwrap_or_default();
+        let key_layers: Vec<Option<&Layer>> = (0..key_count as u32)
+            .map(|led| {
+                regions
+                    .iter()
+                    .position(|region| region.leds.is_empty() || region.leds.contains(&led))
+                    .map(|idx| &layers[idx])
+            })
+            .collect();
+        let animated = layers
+            .iter()
+            .any(|layer| matches!(layer, Layer::Animation { .. }));
+        let fade_time = layers
+            .iter()
+            .filter_map(|layer| match layer {
+                Layer::Typing(effect) => Some(Duration::from_millis(effect.fade_time as u64)),
+                _ => None,
+            })
+            .max();
+        let mut key_presses = fade_time.map(|_| typing::key_presses());
+
+        let start = Instant::now();
+        let mut pressed: Vec<Option<Instant>> = vec![None; key_count];
+        let mut fading_until = None;
+        let mut shown = None;
+        loop {
+            let now = Instant::now();
+            let colors: Vec<Color> = key_layers
+                .iter()
+                .zip(&pressed)
+                .map(|(layer, pressed)| match layer {
+                    Some(layer) => layer.color(now - start, pressed.map(|time| now - time)),
+                    None => OFF,
+                })
+                .collect();
+            if shown.as_ref() != Some(&colors) {
+                if let Err(err) = self.set_key_colors(&colors).await {
+                    tracing::error!("Failed setting keyboard colors: `{err}`")
+                }
+                shown = Some(colors);
+            }
+
+            let fading = fading_until.is_some_and(|until| now < until);
+            let next_frame = async {
+                if animated || fading {
+                    tokio::time::sleep(FRAME_TIME).await
+                } else {
+                    pending().await
+                }
+            };
+            let key_press = async {
+                match &mut key_presses {
+                    Some(key_presses) => key_presses.recv().await,
+                    None => pending().await,
+                }
+            };
+            tokio::select! {
+                _ = next_frame => {}
+                key = key_press => {
+                    let key = match key {
+                        Ok(key) => Some(key),
+                        Err(broadcast::error::RecvError::Lagged(_)) => None,
+                        Err(broadcast::error::RecvError::Closed) => {
+                            key_presses = None;
+                            continue;
+                        }
+                    };
+                    let now = Instant::now();
+                    for (led, time) in pressed.iter_mut().enumerate() {
+                        // Light up every key if presses were missed.
+                        if key.map_or(true, |key| typing::lights_up(key, led as u32)) {
+                            *time = Some(now);
+                        }
+                    }
+                    fading_until = fade_time.map(|fade_time| now + fade_time);
+                }
+                _ = process_suspend(suspend_receiver) => shown = None,
+            }
+        }
+    }
+
+    /// Light up on key presses and fade back to the idle color.
+    async fn run_typing_effect(
+        &mut self,
+        effect: &TypingEffect,
+        suspend_receiver: &mut broadcast::Receiver<bool>,
+    ) {
+        let led = typing::led_index(self.controller.function());
+        let mut key_presses = typing::key_presses();
+
+        let mut fade_steps = Vec::new();
+        linear_color_transition(
+            &mut fade_steps,
+            effect.idle.clone(),
+            &effect.pressed,
+            effect.fade_time,
+        );
+        if fade_steps.len() == 1 {
+            // Too short for a transition.
+            fade_steps = vec![(effect.pressed.clone(), effect.fade_time)];
+        }
+        fade_steps.push((effect.idle.clone(), 0));
+
+        let idle_step = fade_steps.len() - 1;
+        let mut step = idle_step;
+        let mut shown_step = None;
+        loop {
+            if shown_step != Some(step) {
+                if let Err(err) = self.set_color(&fade_steps[step].0).await {
+                    tracing::error!("Failed setting keyboard colors: `{err}`")
+                }
+                shown_step = Some(step);
+            }
+
+            let next_step = async {
+                if step == idle_step {
+                    pending().await
+                } else {
+                    tokio::time::sleep(Duration::from_millis(fade_steps[step].1 as u64)).await
+                }
+            };
+            tokio::select! {
+                key = key_presses.recv() => match key {
+                    Ok(key) if typing::lights_up(key, led) => step = 0,
+                    Ok(_) => {}
+                    Err(broadcast::error::RecvError::Lagged(_)) => step = 0,
+                    Err(broadcast::error::RecvError::Closed) => return pending().await,
+                },
+                _ = next_step => step += 1,
+                _ = process_suspend(suspend_receiver) => shown_step = None,
+            }
+        }
+    }
+}
+
+/// The colors of a region over time.
+enum Layer {
+    Static(Color),
+    Animation {
+        steps: Vec<(Color, u32)>,
+        /// The sum of the step times in ms.
+        duration: u32,
+    },
+    Typing(TypingEffect),
+}
+
+impl Layer {
+    fn new(profile: &ColorProfile) -> Self {
+        match profile {
+            ColorProfile::Single(color) => Self::Static(color.clone()),
+            ColorProfile::Multiple(colors) if !colors.is_empty() => {
+                let steps = calculate_color_animation_steps(colors);
+                let duration = steps.iter().map(|(_, time)| time).sum();
+                Self::Animation { steps, duration }
+            }
+            ColorProfile::Typing(effect) => Self::Typing(effect.clone()),
+            ColorProfile::None | ColorProfile::Multiple(_) | ColorProfile::Regions(_) => {
+                Self::Static(OFF)
+            }
+        }
+    }
+
+    /// The color after the time since the regions started
+    /// and since the key of the color was last pressed.
+    fn color(&self, elapsed: Duration, since_pressed: Option<Duration>) -> Color {
+        match self {
+            Self::Static(color) => color.clone(),
+            Self::Animation { steps, duration } => {
+                let mut time = (elapsed.as_millis() % (*duration).max(1) as u128) as u32;
+                for (color, step_time) in steps {
+                    if time < *step_time {
+                        return color.clone();
+                    }
+                    time -= step_time;
+                }
+                steps.last().map(|(color, _)| color.clone()).unwrap_or(OFF)
+            }
+            Self::Typing(effect) => match since_pressed {
+                Some(time) if time.as_millis() < effect.fade_time as u128 => {
+                    let fraction = time.as_millis() as f64 / effect.fade_time as f64;
+                    blend(&effect.pressed, &effect.idle, fraction)
+                }
+                _ => effect.idle.clone(),
+            },
+        }
+    }
+}
+
+fn blend(from: &Color, to: &Color, fraction: f64) -> Color {
+    let channel = |from: u8, to: u8| f64_to_u8(from as f64 + (to as f64 - from as f64) * fraction);
+    Color {
+        r: channel(from.r, to.r),
+        g: channel(from.g, to.g),
+        b: channel(from.b, to.b),
+    }
 }
 
 fn calculate_color_animation_steps(colors: &[ColorPoint]) -> Vec<(Color, u32)> {
@@ -168,7 +547,31 @@ fn decent_linear_steps(transition_time: u32, diffs: &[f64]) -> u32 {
 
 #[cfg(test)]
 mod test {
-    use crate::led::runtime::decent_linear_steps;
+    use std::time::Duration;
+
+    use tailor_api::{Color, ColorPoint, ColorProfile, ColorTransition};
+
+    use crate::led::runtime::{decent_linear_steps, Layer};
+
+    #[test]
+    fn region_layers() {
+        let point = |r, transition_time| ColorPoint {
+            color: Color { r, g: 0, b: 0 },
+            transition: ColorTransition::None,
+            transition_time,
+        };
+        let layer = Layer::new(&ColorProfile::Multiple(vec![point(1, 100), point(2, 300)]));
+        let color_at = |ms| layer.color(Duration::from_millis(ms), None).r;
+        assert_eq!(color_at(50), 1);
+        assert_eq!(color_at(399), 2);
+        assert_eq!(color_at(400), 1);
+
+        let layer = Layer::new(&ColorProfile::Multiple(Vec::new()));
+        assert_eq!(
+            layer.color(Duration::ZERO, None),
+            Color { r: 0, g: 0, b: 0 }
+        );
+    }
 
     #[test]
     fn decent_linear_step() {
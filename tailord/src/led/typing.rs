@@ -0,0 +1,132 @@
+//! Key presses for the typing effect of LED profiles.
+//! The keyboards are only opened once a profile with the typing
+//! effect is active and only the codes of pressed keys are forwarded.
+
+use std::{fs::File, io::Read, mem::size_of, path::Path};
+
+use once_cell::sync::{Lazy, OnceCell};
+use tokio::sync::broadcast;
+
+use crate::config::KeyLed;
+
+/// Contains a `*-event-kbd` link for each keyboard.
+const INPUT_BY_PATH: &str = "/dev/input/by-path";
+const KEYBOARD_SUFFIX: &str = "-event-kbd";
+const EV_KEY: u16 = 0x01;
+const KEY_PRESSED: i32 = 1;
+/// Size of the `input_event` struct of the kernel. It starts with
+/// a timestamp whose size depends on the platform, followed by
+/// the type, code and value of the event.
+const INPUT_EVENT_SIZE: usize = size_of::<nix::libc::input_event>();
+
+static KEY_LEDS: OnceCell<Vec<KeyLed>> = OnceCell::new();
+static KEY_PRESSES: Lazy<broadcast::Sender<u16>> = Lazy::new(|| {
+    let (sender, _) = broadcast::channel(32);
+    spawn_readers(&sender);
+    sender
+});
+
+/// Set the LED of each key. This must be called before the LED runtimes start.
+pub fn init(key_leds: Vec<KeyLed>) {
+    KEY_LEDS.set(key_leds).ok();
+}
+
+/// Receives the codes of pressed keys.
+pub fn key_presses() -> broadcast::Receiver<u16> {
+    KEY_PRESSES.subscribe()
+}
+
+/// The number of a per-key LED, taken from the end of
+/// its function, e.g. `12` for `kbd_backlight_12`.
+pub fn led_index(function: &str) -> u32 {
+    function
+        .rsplit_once('_')
+        .and_then(|(_, index)| index.parse().ok())
+        .unwrap_or_default()
+}
+
+/// Whether a key press lights up the LED. Without a key
+/// map, every key lights up all LEDs.
+pub fn lights_up(key: u16, led: u32) -> bool {
+    match KEY_LEDS.get() {
+        Some(key_leds) if !key_leds.is_empty() => key_leds
+            .iter()
+            .any(|key_led| key_led.key == key && key_led.led == led),
+        _ => true,
+    }
+}
+
+fn spawn_readers(sender: &broadcast::Sender<u16>) {
+    let entries = match std::fs::read_dir(INPUT_BY_PATH) {
+        Ok(entries) => entries,
+        Err(err) => {
+            tracing::warn!("Failed to find keyboards in `{INPUT_BY_PATH}`: `{err}`");
+            return;
+        }
+    };
+
+    for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+        let is_keyboard = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.ends_with(KEYBOARD_SUFFIX));
+        if is_keyboard {
+            let sender = sender.clone();
+            std::thread::spawn(move || {
+                if let Err(err) = read_key_presses(&path, &sender) {
+                    tracing::warn!("Stopped reading key presses of {path:?}: `{err}`");
+                }
+            });
+        }
+    }
+}
+
+/// Blocking loop that forwards the codes of pressed keys.
+fn read_key_presses(path: &Path, sender: &broadcast::Sender<u16>) -> std::io::Result<()> {
+    tracing::debug!("Reading key presses of {path:?}");
+    let mut file = File::open(path)?;
+    let mut event = [0; INPUT_EVENT_SIZE];
+    loop {
+        file.read_exact(&mut event)?;
+        if let Some(key) = parse_key_press(&event) {
+            // Fails if no runtime uses the typing effect.
+            sender.send(key).ok();
+        }
+    }
+}
+
+/// The code of the key if the event is a key press.
+fn parse_key_press(event: &[u8; INPUT_EVENT_SIZE]) -> Option<u16> {
+    let fields = &event[INPUT_EVENT_SIZE - 8..];
+    let ty = u16::from_ne_bytes([fields[0], fields[1]]);
+    let code = u16::from_ne_bytes([fields[2], fields[3]]);
+    let value = i32::from_ne_bytes([fields[4], fields[5], fields[6], fields[7]]);
+    (ty == EV_KEY && value == KEY_PRESSED).then_some(code)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{led_index, parse_key_press, INPUT_EVENT_SIZE};
+
+    fn event(ty: u16, code: u16, value: i32) -> [u8; INPUT_EVENT_SIZE] {
+        let mut event = [0; INPUT_EVENT_SIZE];
+        let fields = &mut event[INPUT_EVENT_SIZE - 8..];
+        fields[..2].copy_from_slice(&ty.to_ne_bytes());
+        fields[2..4].copy_from_slice(&code.to_ne_bytes());
+        fields[4..].copy_from_slice(&value.to_ne_bytes());
+        event
+    }
+
+    #[test]
+    fn key_presses() {
+        assert_eq!(parse_key_press(&event(1, 30, 1)), Some(30));
+        // Released and repeated keys
+        assert_eq!(parse_key_press(&event(1, 30, 0)), None);
+        assert_eq!(parse_key_press(&event(1, 30, 2)), None);
+        // Sync events
+        assert_eq!(parse_key_press(&event(0, 0, 1)), None);
+
+        assert_eq!(led_index("kbd_backlight_12"), 12);
+        assert_eq!(led_index("kbd_backlight"), 0);
+    }
+}
@@ -0,0 +1,93 @@
+//! Turns the LEDs off during the configured off hours or while
+//! the screen is locked or idle, independently of the active profile.
+
+use std::time::Duration;
+
+use chrono::Timelike;
+use once_cell::sync::Lazy;
+use tokio::sync::watch;
+use zbus::Connection;
+
+use crate::{
+    config::{LightingConfig, TimeOfDay},
+    idle::get_idle_receiver,
+    logind,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Session types with a screen that can be locked.
+const GRAPHICAL_SESSIONS: [&str; 3] = ["x11", "wayland", "mir"];
+
+static LIGHTS_OFF: Lazy<watch::Sender<bool>> = Lazy::new(|| watch::channel(false).0);
+
+/// Receives `true` while all LEDs should be off.
+pub fn get_lights_off_receiver() -> watch::Receiver<bool> {
+    LIGHTS_OFF.subscribe()
+}
+
+pub async fn run(config: LightingConfig) {
+    if !config.is_enabled() {
+        return;
+    }
+
+    let connection = if config.off_when_locked {
+        match Connection::system().await {
+            Ok(connection) => Some(connection),
+            Err(err) => {
+                tracing::error!("Failed to connect to logind: `{err}`");
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let idle_receiver = get_idle_receiver();
+    loop {
+        let off_hours = config.off_hours.is_some_and(|off_hours| {
+            let now = chrono::Local::now();
+            off_hours.contains(TimeOfDay {
+                hour: now.hour() as u8,
+                minute: now.minute() as u8,
+            })
+        });
+        let locked = match &connection {
+            Some(connection) => sessions_locked(connection).await.unwrap_or_else(|err| {
+                tracing::debug!("Failed to read the lock state of the sessions: `{err}`");
+                false
+            }),
+            None => false,
+        };
+
+        let idle = config.off_when_idle && *idle_receiver.borrow();
+
+        let off = off_hours || locked || idle;
+        LIGHTS_OFF.send_if_modified(|lights_off| {
+            if *lights_off == off {
+                return false;
+            }
+            if off {
+                tracing::info!("Turning the LEDs off");
+            } else {
+                tracing::info!("Turning the LEDs on again");
+            }
+            *lights_off = off;
+            true
+        });
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Whether there are graphical sessions and all of them are locked.
+async fn sessions_locked(connection: &Connection) -> zbus::Result<bool> {
+    let mut graphical_sessions = 0;
+    for (_, session) in logind::sessions(connection).await? {
+        if GRAPHICAL_SESSIONS.contains(&session.session_type().await?.as_str()) {
+            if !session.locked_hint().await? {
+                return Ok(false);
+            }
+            graphical_sessions += 1;
+        }
+    }
+    Ok(graphical_sessions > 0)
+}
@@ -0,0 +1,62 @@
+//! Sessions of logind, to tell which users are in front of the device.
+
+use zbus::{dbus_proxy, zvariant::OwnedObjectPath, CacheProperties, Connection};
+
+/// The id, uid, user name, seat and path of a session.
+pub type SessionEntry = (String, u32, String, String, OwnedObjectPath);
+
+#[dbus_proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait Sessions {
+    fn list_sessions(&self) -> zbus::Result<Vec<SessionEntry>>;
+}
+
+#[dbus_proxy(
+    interface = "org.freedesktop.login1.Session",
+    default_service = "org.freedesktop.login1"
+)]
+trait Session {
+    #[dbus_proxy(property)]
+    fn active(&self) -> zbus::Result<bool>;
+
+    #[dbus_proxy(property)]
+    fn remote(&self) -> zbus::Result<bool>;
+
+    #[dbus_proxy(property)]
+    fn locked_hint(&self) -> zbus::Result<bool>;
+
+    #[dbus_proxy(property, name = "Type")]
+    fn session_type(&self) -> zbus::Result<String>;
+}
+
+/// All sessions with their current properties.
+pub async fn sessions(connection: &Connection) -> zbus::Result<Vec<(u32, SessionProxy<'_>)>> {
+    let entries = SessionsProxy::new(connection)
+        .await?
+        .list_sessions()
+        .await?;
+    let mut sessions = Vec::with_capacity(entries.len());
+    for (_, uid, _, _, path) in entries {
+        let session = SessionProxy::builder(connection)
+            .path(path)?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?;
+        sessions.push((uid, session));
+    }
+    Ok(sessions)
+}
+
+/// Whether the user has an active local session, the same
+/// condition as `allow_active` of polkit.
+pub async fn has_active_session(connection: &Connection, uid: u32) -> zbus::Result<bool> {
+    for (session_uid, session) in sessions(connection).await? {
+        if session_uid == uid && session.active().await? && !session.remote().await? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
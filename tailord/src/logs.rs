@@ -0,0 +1,221 @@
+//! Keeps the latest messages of the log in memory, so clients can show
+//! them without access to the journal. Only messages of the info level
+//! and more severe levels are kept, independent of `RUST_LOG`.
+//!
+//! The messages can name users, e.g. the owners of profiles, so they're
+//! only sent to root and users of an active local session that subscribed.
+
+use std::{
+    collections::{BTreeSet, VecDeque},
+    fmt::{Debug, Write},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use futures::StreamExt;
+use once_cell::sync::OnceCell;
+use tailor_api::{LogLevel, LogRecord};
+use tokio::sync::mpsc;
+use tracing::{field::Field, Level, Metadata, Subscriber};
+use tracing_subscriber::{field::Visit, layer::Context, Layer};
+use zbus::{
+    fdo::DBusProxy,
+    names::{BusName, OwnedUniqueName},
+    Connection, SignalContext,
+};
+
+use crate::{dbus::EventsInterface, DBUS_PATH};
+
+const MAX_RECORDS: usize = 500;
+
+static RECORDS: Mutex<VecDeque<LogRecord>> = Mutex::new(VecDeque::new());
+static RECORD_SENDER: OnceCell<mpsc::UnboundedSender<LogRecord>> = OnceCell::new();
+/// The bus names of the clients that receive the `LogRecorded` signal.
+static SUBSCRIBERS: Mutex<BTreeSet<OwnedUniqueName>> = Mutex::new(BTreeSet::new());
+
+/// A layer of the tracing subscriber that records the messages.
+pub struct LogBuffer;
+
+impl<S: Subscriber> Layer<S> for LogBuffer {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let Some(level) = recorded_level(metadata) else {
+            return;
+        };
+
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let record = LogRecord {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+            level,
+            target: metadata.target().to_owned(),
+            message: visitor.message,
+        };
+
+        if let Some(sender) = RECORD_SENDER.get() {
+            sender.send(record.clone()).ok();
+        }
+        let mut records = RECORDS.lock().unwrap();
+        if records.len() == MAX_RECORDS {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+}
+
+/// The level of a message that is kept, if it's kept.
+fn recorded_level(metadata: &Metadata<'_>) -> Option<LogLevel> {
+    // Emitting the signals would log messages of zbus again.
+    if metadata.target().starts_with("zbus") {
+        return None;
+    }
+    match *metadata.level() {
+        Level::ERROR => Some(LogLevel::Error),
+        Level::WARN => Some(LogLevel::Warn),
+        Level::INFO => Some(LogLevel::Info),
+        _ => None,
+    }
+}
+
+/// The message followed by the other fields, e.g. `Failed to read fan err=...`.
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            let fields = std::mem::take(&mut self.message);
+            write!(self.message, "{value:?}{fields}").ok();
+        } else {
+            write!(self.message, " {}={value:?}", field.name()).ok();
+        }
+    }
+}
+
+/// Get up to `limit` of the latest messages, oldest first.
+pub fn records(limit: usize) -> Vec<LogRecord> {
+    let records = RECORDS.lock().unwrap();
+    let skip = records.len().saturating_sub(limit);
+    records.iter().skip(skip).cloned().collect()
+}
+
+/// Send the `LogRecorded` signal for new messages to a client until it
+/// disconnects. The caller must be allowed to read the log.
+pub fn subscribe(name: OwnedUniqueName) {
+    SUBSCRIBERS.lock().unwrap().insert(name);
+}
+
+fn unsubscribe(name: &str) {
+    SUBSCRIBERS
+        .lock()
+        .unwrap()
+        .retain(|subscriber| subscriber.as_str() != name);
+}
+
+fn subscribers() -> Vec<OwnedUniqueName> {
+    SUBSCRIBERS.lock().unwrap().iter().cloned().collect()
+}
+
+/// Emit the `LogRecorded` signal for new messages to the subscribers.
+pub async fn forward(connection: Connection) {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    if RECORD_SENDER.set(sender).is_err() {
+        return;
+    }
+    let mut disconnects = match DBusProxy::new(&connection).await {
+        Ok(proxy) => proxy.receive_name_owner_changed().await.ok(),
+        Err(_) => None,
+    };
+    loop {
+        let record = tokio::select! {
+            record = receiver.recv() => record,
+            Some(signal) = async {
+                match &mut disconnects {
+                    Some(disconnects) => disconnects.next().await,
+                    None => std::future::pending().await,
+                }
+            } => {
+                if let Ok(args) = signal.args() {
+                    if args.new_owner().is_none() {
+                        unsubscribe(args.name());
+                    }
+                }
+                continue;
+            }
+        };
+        let Some(record) = record else {
+            break;
+        };
+        let data = serde_json::to_string(&record).unwrap();
+        for subscriber in subscribers() {
+            if let Err(err) = emit(&connection, &subscriber, &data).await {
+                // Not logged as an error, it would be forwarded again.
+                tracing::debug!("Failed to emit log signal: `{err}`");
+            }
+        }
+    }
+}
+
+async fn emit(
+    connection: &Connection,
+    subscriber: &OwnedUniqueName,
+    data: &str,
+) -> zbus::Result<()> {
+    let ctxt = SignalContext::new(connection, DBUS_PATH)?
+        .set_destination(BusName::Unique(subscriber.as_ref()));
+    EventsInterface::log_recorded(&ctxt, data).await
+}
+
+#[cfg(test)]
+mod test {
+    use tailor_api::LogLevel;
+    use tracing_subscriber::prelude::*;
+    use zbus::names::OwnedUniqueName;
+
+    use super::{records, subscribe, subscribers, unsubscribe, LogBuffer};
+
+    #[test]
+    fn levels() {
+        let subscriber = tracing_subscriber::registry().with(LogBuffer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::trace!("Read fan speed");
+            tracing::debug!("Recording event");
+            tracing::info!(fan = 1, "Switched profile");
+            tracing::warn!(target: "zbus::connection", "Emitting signal");
+            tracing::error!("Failed to write");
+        });
+        let kept: Vec<_> = records(10)
+            .into_iter()
+            .map(|record| (record.level, record.message))
+            .collect();
+        assert_eq!(
+            kept,
+            [
+                (LogLevel::Info, "Switched profile fan=1".to_owned()),
+                (LogLevel::Error, "Failed to write".to_owned()),
+            ]
+        );
+        assert_eq!(records(1).len(), 1);
+    }
+
+    #[test]
+    fn subscribers_only() {
+        let name = |name: &str| OwnedUniqueName::try_from(name).unwrap();
+        assert!(subscribers().is_empty());
+        subscribe(name(":1.42"));
+        subscribe(name(":1.42"));
+        subscribe(name(":1.7"));
+        assert_eq!(subscribers(), [name(":1.42"), name(":1.7")]);
+
+        // Clients that disconnect don't receive messages anymore.
+        unsubscribe(":1.42");
+        assert_eq!(subscribers(), [name(":1.7")]);
+        unsubscribe(":1.7");
+        assert!(subscribers().is_empty());
+    }
+}
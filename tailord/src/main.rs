@@ -1,94 +1,301 @@
+mod aspm;
+mod audit;
+mod battery;
+mod calibration;
+mod check;
+mod config;
+mod conflicts;
+mod cpufreq;
+mod critical;
 mod dbus;
+mod device;
+mod events;
 mod fancontrol;
+mod fn_lock;
+mod idle;
+mod inhibit;
+mod instance;
+mod keyboard;
+mod landlock;
 pub mod led;
+mod lighting;
+mod logind;
+mod logs;
+mod migration;
+mod night_shift;
+#[cfg(feature = "openrgb")]
+mod openrgb;
 mod performance;
+mod platform_profile;
+mod power_tuning;
+mod profile_switch;
 mod profiles;
+mod safe_mode;
+mod scope;
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+mod seccomp;
 pub mod shutdown;
+#[cfg(feature = "simulator")]
+mod simulator;
 mod suspend;
+mod throttle;
+mod usb;
 pub mod util;
 
-use std::future::pending;
+use std::{future::pending, sync::Arc};
 
-use dbus::{FanInterface, PerformanceInterface, ProfileInterface};
+use dbus::{
+    BatteryInterface, EventsInterface, FanInterface, InputInterface, PerformanceInterface,
+    ProfileInterface, Reloads, WebcamInterface,
+};
 use profiles::Profile;
-use tailor_api::{ColorProfile, LedControllerMode};
-use tuxedo_ioctl::hal::IoInterface;
+use tailor_api::{HardwareCapabilities, LedDeviceInfo};
+use tracing_subscriber::{filter::LevelFilter, prelude::*, EnvFilter};
+use tuxedo_ioctl::hal::{cache, traits::HardwareDevice, Capabilities, IoInterface};
+use tuxedo_sysfs::{
+    charging::BatteryConservation, fn_lock::FnLock, keyboard::KeyboardParameters,
+    pcie_aspm::PcieAspm, platform::PlatformHardware, power_supply::Battery,
+};
 use zbus::ConnectionBuilder;
 
 use crate::{
+    audit::AuditLogWriter,
+    calibration::CalibrationRuntime,
+    config::DaemonConfig,
+    critical::CriticalTempWatcher,
     dbus::LedInterface,
-    fancontrol::FanRuntime,
-    led::{LedRuntime, LedRuntimeData},
+    events::EventLogWriter,
+    fancontrol::{profile::FanLimits, FanRuntime},
+    idle::IdleWatcher,
+    led::LedRuntime,
     performance::PerformanceProfileRuntime,
     profiles::SupportedFeatures,
+    throttle::ThrottleWatcher,
 };
 
 const DBUS_NAME: &str = "com.tux.Tailor";
-const DBUS_PATH: &str = "/com/tux/Tailor";
+pub(crate) const DBUS_PATH: &str = "/com/tux/Tailor";
 
 fn main() {
     if std::env::var_os("RUST_LOG").is_none() {
         std::env::set_var("RUST_LOG", "info");
     }
 
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .with_writer(std::io::stderr)
-        .without_time()
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(std::io::stderr)
+                .without_time()
+                .with_filter(EnvFilter::from_default_env()),
+        )
+        .with(logs::LogBuffer.with_filter(LevelFilter::INFO))
         .init();
 
-    tokio_uring::start(start_runtime());
+    let args = match config::init_dirs(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            tracing::error!("{err}");
+            std::process::exit(2);
+        }
+    };
+
+    if let Some(status) = check::run_from_args(&args) {
+        std::process::exit(status);
+    }
+
+    #[cfg(feature = "simulator")]
+    if simulator::run_from_args(&args) {
+        return;
+    }
+
+    // Before a failed start is recorded for the safe mode.
+    instance::lock();
+
+    let (safe_mode, args) = safe_mode::init(args);
+    for arg in args {
+        tracing::warn!("Ignoring unknown argument `{arg}`");
+    }
+
+    // Safe mode ignores the configuration.
+    let config = if safe_mode {
+        DaemonConfig::default()
+    } else {
+        DaemonConfig::load()
+    };
+    // Before Landlock blocks reading `/proc` and before the hardware is touched.
+    if conflicts::warn(&conflicts::detect()) && config.refuse_on_conflict {
+        tracing::error!("Refusing to start because another program controls the fans");
+        std::process::exit(1);
+    }
+    if config.landlock {
+        match landlock::apply() {
+            Ok(true) => tracing::info!("Restricted file access with Landlock"),
+            Ok(false) => tracing::warn!("Landlock isn't supported by the kernel"),
+            Err(err) => tracing::warn!("Failed to apply Landlock rules: {err}"),
+        }
+    }
+
+    tokio_uring::start(start_runtime(safe_mode, config));
 }
 
-#[tracing::instrument]
-async fn start_runtime() {
+#[tracing::instrument(skip(config))]
+async fn start_runtime(safe_mode: bool, config: DaemonConfig) {
     tracing::info!("Starting tailord");
 
     // Setup shutdown
     let mut shutdown_receiver = shutdown::setup();
 
+    let event_log_writer = EventLogWriter::new();
+    tokio_uring::spawn(event_log_writer.run());
+    if config.audit_log {
+        tokio_uring::spawn(AuditLogWriter::new().run());
+    }
+
     let led_devices = tuxedo_sysfs::led::Collection::new()
         .await
         .map(|c| c.into_inner())
         .unwrap_or_default();
 
-    let mut mode = LedControllerMode::Rgb;
-    for device in &led_devices {
-        if device.mode() == LedControllerMode::Monochrome {
-            mode = LedControllerMode::Monochrome;
-        }
-    }
-    Profile::init_if_necessary(SupportedFeatures { mode });
-    let profile = Profile::load();
+    let leds: Vec<LedDeviceInfo> = led_devices
+        .iter()
+        .map(|device| LedDeviceInfo {
+            device_name: device.device_name.clone(),
+            function: device.function.clone(),
+            mode: device.mode(),
+        })
+        .collect();
 
-    let (device, _webcam, _tdp) = match IoInterface::new() {
+    let (mut capabilities, interface) = match IoInterface::new() {
         Ok(interface) => {
             let IoInterface {
-                device,
-                webcam,
-                tdp,
                 module_version,
+                device,
+                capabilities,
             } = interface;
             tracing::info!("Connected to Tuxedo ioctl interface with version {module_version}");
-            (Some(device), webcam, tdp)
+            let interface = ("ioctl".to_owned(), device, Some(module_version));
+            (capabilities, Some(interface))
         }
         Err(err) => {
             tracing::warn!("No tuxedo ioctl interface available: {err}");
-            (None, None, None)
+            match PlatformHardware::new() {
+                Ok(platform) => {
+                    tracing::info!(
+                        "Connected to Tuxedo platform interface {:?}",
+                        platform.interface()
+                    );
+                    let name = format!("{:?}", platform.interface());
+                    let platform = Arc::new(platform);
+                    let device: Arc<dyn HardwareDevice> = platform.clone();
+                    (platform.capabilities(), Some((name, device, None)))
+                }
+                Err(err) => {
+                    tracing::warn!("No tuxedo platform interface available: {err}");
+                    (Capabilities::default(), None)
+                }
+            }
         }
     };
+    let interface = interface.map(|(name, device, version)| (name, cache::device(device), version));
+    let model_id = interface
+        .as_ref()
+        .and_then(|(_, device, _)| device.device_model_id_str().ok());
+    tracing::info!("Supported capabilities: {:?}", capabilities.list());
+    if let Some(model_id) = &model_id {
+        tracing::info!("Device model: {}", model_id.trim());
+    }
+
+    if safe_mode {
+        safe_mode::apply(&capabilities);
+        safe_mode::mark_started();
+        tracing::info!("Tailord started in safe mode");
+        shutdown_receiver.recv().await.ok();
+        tracing::info!("Shutting down, bye!");
+        return;
+    }
+
+    cache::apply(&mut capabilities, config.fan_control.sensor_cache());
+    let battery_conservation = BatteryConservation::new();
+    if let Some(conservation) = &battery_conservation {
+        battery::restore(conservation);
+    }
+    let fn_lock = FnLock::new();
+    if let Some(fn_lock) = &fn_lock {
+        fn_lock::restore(fn_lock);
+    }
+    let battery = Battery::new();
+    let (calibration_runtime, calibration) = battery
+        .clone()
+        .map(|battery| {
+            CalibrationRuntime::new(
+                battery,
+                battery_conservation.clone(),
+                config.battery_calibration.clone(),
+            )
+        })
+        .unzip();
+    let hardware = HardwareCapabilities {
+        battery_conservation: battery_conservation.is_some(),
+        fn_lock: fn_lock.is_some(),
+        battery_calibration: calibration.is_some(),
+        pcie_aspm: PcieAspm::new().is_some_and(|aspm| aspm.is_writable()),
+        ..hardware_capabilities(&capabilities)
+    };
+    let device = device::collect(
+        interface
+            .as_ref()
+            .map(|(name, device, io_module_version)| device::Interface {
+                name: name.clone(),
+                device: device.as_ref(),
+                io_module_version: io_module_version.clone(),
+            }),
+        &capabilities,
+        &hardware,
+    );
+
+    Profile::init_if_necessary(SupportedFeatures {
+        leds: leds.clone(),
+        num_of_fans: capabilities
+            .fans
+            .as_ref()
+            .map(|fans| fans.get_number_fans())
+            .unwrap_or_default(),
+        performance_profiles: hardware.performance_profiles.clone(),
+        model_id: model_id.clone(),
+    });
+    let profile = Profile::load();
+    if let Err(err) = cpufreq::apply_max_freq(profile.max_freq_mhz) {
+        tracing::warn!("Failed to apply CPU frequency cap: `{err}`");
+    }
+    if let Err(err) = usb::apply_autosuspend(profile.usb_autosuspend.as_ref()) {
+        tracing::warn!("Failed to apply USB autosuspend policy: `{err}`");
+    }
+    if let Err(err) = aspm::apply_policy(profile.pcie_aspm) {
+        tracing::warn!("Failed to apply PCIe ASPM policy: `{err}`");
+    }
+    if let Err(err) = power_tuning::apply(profile.power_tuning.as_ref()) {
+        tracing::warn!("Failed to apply device power tuning: `{err}`");
+    }
+
+    led::typing::init(config.lighting.key_leds.clone());
 
     let mut fan_handles = Vec::new();
     let mut fan_runtimes = Vec::new();
-    if let Some(device) = &device {
-        let available_fans = device.get_number_fans();
+    if let Some(fans) = &capabilities.fans {
+        let available_fans = fans.get_number_fans();
         for fan_idx in 0..available_fans {
             let profile = profile
                 .fans
                 .get(fan_idx as usize)
                 .cloned()
                 .unwrap_or_default();
-            let (handle, runtime) = FanRuntime::new(fan_idx, device.clone(), profile);
+            let (handle, runtime) = FanRuntime::new(
+                fan_idx,
+                fans.clone(),
+                profile,
+                config.fan_control,
+                config.calibration.temp_offset(fan_idx),
+            );
 
             fan_handles.push(handle);
             fan_runtimes.push(runtime);
@@ -97,35 +304,34 @@ async fn start_runtime() {
 
     let mut led_handles = Vec::new();
     let mut led_runtimes = Vec::new();
-    for led_device in led_devices {
-        let profile = profile
-            .leds
-            .iter()
-            .find_map(|(info, profile)| {
-                if info.device_name == led_device.device_name
-                    && info.function == led_device.function
-                {
-                    Some(profile.clone())
-                } else {
-                    None
-                }
-            })
-            .unwrap_or_else(|| ColorProfile::default(led_device.mode()));
+    let mut led_hotplug_handles = Vec::new();
+    let mut brightness_watchers = Vec::new();
+    for (led_device, info) in led_devices.into_iter().zip(leds) {
+        let profile = profile.led_profile(&info);
 
-        let (handle, runtime) = LedRuntime::new(LedRuntimeData {
-            controller: led_device,
-            profile,
-        });
+        let correction = config.calibration.color_correction(&info);
 
+        let (handle, runtime) = LedRuntime::new(led_device, profile, correction);
+
+        led_hotplug_handles.push(runtime.hotplug_handle());
+        brightness_watchers.extend(runtime.brightness_watcher());
         led_handles.push(handle);
         led_runtimes.push(runtime);
     }
 
-    let (performance_profile_handle, performance_profile_runtime) = match device {
-        Some(device) => {
-            if let Ok(default_performance_profile) = device.get_default_odm_performance_profile() {
+    let critical_temp_watcher = match (&capabilities.fans, &config.critical_temp) {
+        (Some(fans), Some(critical_temp)) => Some((fans.clone(), critical_temp.clone())),
+        _ => None,
+    };
+
+    let (performance_profile_handle, performance_profile_runtime) = match capabilities
+        .performance_profiles
+    {
+        Some(profiles) => {
+            if let Ok(default_performance_profile) = profiles.get_default_odm_performance_profile()
+            {
                 let (handle, runtime) = PerformanceProfileRuntime::new(
-                    device,
+                    profiles,
                     profile.performance_profile,
                     default_performance_profile,
                 );
@@ -141,14 +347,44 @@ async fn start_runtime() {
         led_handles: led_handles.clone(),
         fan_handles: fan_handles.clone(),
         performance_profile_handle: performance_profile_handle.clone(),
+        hardware,
+        model_id,
+        device,
+        reloads: Reloads::default(),
     };
 
+    match config.openrgb {
+        #[cfg(feature = "openrgb")]
+        Some(openrgb) => {
+            tracing::debug!("Starting OpenRGB server");
+            tokio_uring::spawn(openrgb::serve(openrgb.address, led_handles.clone()));
+        }
+        #[cfg(not(feature = "openrgb"))]
+        Some(_) => tracing::warn!("The OpenRGB server requires the `openrgb` feature"),
+        None => {}
+    }
+
+    let keyboard_parameters = KeyboardParameters::new();
+    if let Some(parameters) = &keyboard_parameters {
+        keyboard::restore(parameters);
+    }
     let led_interface = LedInterface {
         handles: led_handles,
+        keyboard_parameters,
     };
 
+    let (throttle_watcher, throttle_status) = ThrottleWatcher::new(
+        config.throttle,
+        fan_handles
+            .iter()
+            .map(|handle| handle.status_receiver.clone())
+            .collect(),
+    );
+
     let fan_interface = FanInterface {
-        handles: fan_handles,
+        handles: fan_handles.clone(),
+        throttle_status,
+        limits: capabilities.fans.as_deref().map(FanLimits::of),
     };
 
     let performance_profile_interface = PerformanceInterface {
@@ -156,7 +392,7 @@ async fn start_runtime() {
     };
 
     tracing::debug!("Connecting to DBUS as {DBUS_NAME}");
-    let _conn = ConnectionBuilder::system()
+    let builder = ConnectionBuilder::system()
         .unwrap()
         .name(DBUS_NAME)
         .unwrap()
@@ -168,9 +404,38 @@ async fn start_runtime() {
         .unwrap()
         .serve_at(DBUS_PATH, performance_profile_interface)
         .unwrap()
-        .build()
-        .await
+        .serve_at(DBUS_PATH, EventsInterface)
+        .unwrap()
+        .serve_at(
+            DBUS_PATH,
+            WebcamInterface {
+                webcam: capabilities.webcam.clone(),
+            },
+        )
+        .unwrap()
+        .serve_at(
+            DBUS_PATH,
+            InputInterface {
+                touchpad: capabilities.touchpad.clone(),
+                fn_lock: fn_lock.clone(),
+            },
+        )
+        .unwrap()
+        .serve_at(
+            DBUS_PATH,
+            BatteryInterface {
+                conservation: battery_conservation,
+                battery: battery.clone(),
+                calibration,
+            },
+        )
         .unwrap();
+    #[cfg(feature = "debug-ioctl")]
+    let builder = builder.serve_at(DBUS_PATH, dbus::DebugInterface).unwrap();
+    let conn = builder.build().await.unwrap();
+
+    tracing::debug!("Forwarding log messages");
+    tokio_uring::spawn(logs::forward(conn.clone()));
 
     tracing::debug!("Starting suspend watcher runtime");
     tokio_uring::spawn(suspend::wait_for_suspend());
@@ -180,9 +445,64 @@ async fn start_runtime() {
         tokio_uring::spawn(runtime.run());
     }
 
+    tracing::debug!("Starting lighting schedule");
+    tokio_uring::spawn(lighting::run(config.lighting.clone()));
+
+    if let Some(night_shift) = config.lighting.night_shift {
+        tracing::debug!("Starting night shift");
+        tokio_uring::spawn(night_shift::run(night_shift));
+    }
+
+    tracing::debug!(
+        "Starting {} brightness watcher(s)",
+        brightness_watchers.len()
+    );
+    for watcher in brightness_watchers {
+        tokio_uring::spawn(watcher.run(conn.clone()));
+    }
+
+    tracing::debug!("Starting LED hotplug watcher");
+    tokio_uring::spawn(led::watch_led_devices(led_hotplug_handles));
+
+    tracing::debug!("Starting USB hotplug watcher");
+    tokio_uring::spawn(usb::watch_usb_devices());
+
     tracing::debug!("Starting {} fans runtime(s)", fan_runtimes.len());
-    for runtime in fan_runtimes {
-        tokio_uring::spawn(runtime.run());
+    fancontrol::spawn_runtimes(fan_runtimes);
+
+    tracing::debug!("Starting thermal throttling watcher");
+    tokio_uring::spawn(throttle_watcher.run(conn.clone()));
+
+    if let Some((fans, critical_temp)) = critical_temp_watcher {
+        tracing::debug!("Starting critical temperature watcher");
+        let watcher = CriticalTempWatcher::new(
+            critical_temp,
+            config.calibration.clone(),
+            fans,
+            fan_handles,
+            conn.clone(),
+        );
+        tokio_uring::spawn(watcher.run());
+    }
+
+    if config.lighting.off_when_idle || config.idle.profile.is_some() {
+        tracing::debug!("Starting idle watcher");
+        tokio_uring::spawn(IdleWatcher::new(config.idle.clone(), conn.clone()).run());
+    }
+
+    if let Some(fn_lock) = fn_lock {
+        tracing::debug!("Starting Fn-lock resume watcher");
+        tokio_uring::spawn(fn_lock::restore_after_resume(fn_lock));
+    }
+
+    if let Some(battery) = battery {
+        tracing::debug!("Starting battery health recorder");
+        tokio_uring::spawn(battery::record_health(battery));
+    }
+
+    if let Some(runtime) = calibration_runtime {
+        tracing::debug!("Starting battery calibration runtime");
+        tokio_uring::spawn(runtime.run(conn.clone()));
     }
 
     if let Some(performance_profile_runtime) = performance_profile_runtime {
@@ -190,6 +510,19 @@ async fn start_runtime() {
         tokio_uring::spawn(performance_profile_runtime.run());
     }
 
+    if config.seccomp && config.runs_commands() {
+        tracing::warn!("Seccomp filter disabled, critical temperature commands run programs");
+    } else if config.seccomp {
+        #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+        match seccomp::apply() {
+            Ok(()) => tracing::info!("Applied seccomp filter"),
+            Err(err) => tracing::warn!("Failed to apply seccomp filter: {err}"),
+        }
+        #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+        tracing::warn!("Seccomp isn't supported on this architecture");
+    }
+
+    safe_mode::mark_started();
     tracing::info!("Tailord started");
     tokio::select! {
         _ = pending() => {
@@ -201,3 +534,30 @@ async fn start_runtime() {
         }
     }
 }
+
+/// The capabilities that are known at startup. Fans and LEDs
+/// are filled in from the runtimes when clients ask for them.
+fn hardware_capabilities(capabilities: &Capabilities) -> HardwareCapabilities {
+    let fans = capabilities.fans.as_deref();
+    HardwareCapabilities {
+        fans_min_speed: fans
+            .and_then(|fans| fans.get_fans_min_speed().ok())
+            .unwrap_or_default(),
+        fans_off_available: fans
+            .and_then(|fans| fans.get_fans_off_available().ok())
+            .unwrap_or_default(),
+        performance_profiles: capabilities
+            .performance_profiles
+            .as_deref()
+            .and_then(|profiles| profiles.get_available_odm_performance_profiles().ok())
+            .unwrap_or_default(),
+        tdp_descriptors: capabilities
+            .tdp
+            .as_deref()
+            .and_then(|tdp| tdp.get_tdp_descriptors().ok())
+            .unwrap_or_default(),
+        webcam: capabilities.webcam.is_some(),
+        touchpad: capabilities.touchpad.is_some(),
+        ..Default::default()
+    }
+}
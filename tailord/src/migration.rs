@@ -0,0 +1,214 @@
+//! Upgrades of stored profiles to the latest schema version.
+//!
+//! Each migration converts the JSON of a profile from one schema version
+//! to the next. Files are upgraded in place at startup and the original
+//! is kept in [`BACKUP_DIR`], so profiles aren't lost if a newer schema
+//! can't be read by an older version of tailord.
+
+use std::{fs, io, path::Path};
+
+use serde_json::Value;
+use tailor_api::{ConfigFormat, SCHEMA_VERSION};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    config,
+    profiles::{FAN_DIR, KEYBOARD_DIR, PROFILE_DIR},
+};
+
+pub static BACKUP_DIR: Lazy<String> = Lazy::new(|| config::config_path("backup/"));
+
+/// Upgrades the JSON of a profile by one schema version.
+/// The new version number is set afterwards.
+type Migration = fn(Value) -> Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProfileKind {
+    Profile,
+    Fan,
+    Keyboard,
+}
+
+impl ProfileKind {
+    fn dir(&self) -> &'static str {
+        match self {
+            Self::Profile => &PROFILE_DIR,
+            Self::Fan => &FAN_DIR,
+            Self::Keyboard => &KEYBOARD_DIR,
+        }
+    }
+
+    /// Migrations to the next version, starting at version 0.
+    fn migrations(&self) -> &'static [Migration] {
+        match self {
+            Self::Profile => &[|value| value],
+            Self::Fan => &[wrap_fan_points],
+            Self::Keyboard => &[wrap_keyboard_profile],
+        }
+    }
+}
+
+/// Fan profiles without settings were stored as plain list of points.
+fn wrap_fan_points(value: Value) -> Value {
+    if value.is_array() {
+        serde_json::json!({ "points": value })
+    } else {
+        value
+    }
+}
+
+/// Keyboard profiles were stored without any surrounding object.
+fn wrap_keyboard_profile(value: Value) -> Value {
+    serde_json::json!({ "profile": value })
+}
+
+fn schema_version(value: &Value) -> u64 {
+    value
+        .get("version")
+        .and_then(Value::as_u64)
+        .unwrap_or_default()
+}
+
+/// Upgrade the JSON of a profile to the latest schema version.
+/// Returns `None` if the profile is already up to date.
+fn migrate_value(kind: ProfileKind, mut value: Value) -> Result<Option<Value>, String> {
+    let version = schema_version(&value);
+    if version == u64::from(SCHEMA_VERSION) {
+        return Ok(None);
+    }
+
+    let migrations = kind.migrations();
+    for (idx, migration) in migrations.iter().enumerate().skip(version as usize) {
+        value = migration(value);
+        let Some(object) = value.as_object_mut() else {
+            return Err(format!(
+                "Migration to version {} returned no object",
+                idx + 1
+            ));
+        };
+        object.insert("version".to_owned(), Value::from(idx + 1));
+    }
+
+    if version > migrations.len() as u64 {
+        Err(format!(
+            "Schema version {version} is newer than the supported version {SCHEMA_VERSION}"
+        ))
+    } else {
+        Ok(Some(value))
+    }
+}
+
+/// Upgrade all stored profiles to the latest schema version.
+pub fn migrate_profiles() {
+    for kind in [
+        ProfileKind::Profile,
+        ProfileKind::Fan,
+        ProfileKind::Keyboard,
+    ] {
+        let dir = match fs::read_dir(kind.dir()) {
+            Ok(dir) => dir,
+            Err(err) => {
+                tracing::warn!("Failed to read profiles at `{}`: `{err}`", kind.dir());
+                continue;
+            }
+        };
+
+        for entry in dir.flatten() {
+            let path = entry.path();
+            let Some(format) = ConfigFormat::from_path(&path) else {
+                continue;
+            };
+            if path.is_file() {
+                if let Err(err) = migrate_file(kind, &path, format) {
+                    tracing::warn!("Failed to migrate profile at `{path:?}`: `{err}`");
+                }
+            }
+        }
+    }
+}
+
+fn migrate_file(kind: ProfileKind, path: &Path, format: ConfigFormat) -> Result<(), io::Error> {
+    let invalid_data = |err: String| io::Error::new(io::ErrorKind::InvalidData, err);
+
+    let content = fs::read_to_string(path)?;
+    let value: Value = format
+        .deserialize(&content)
+        .map_err(|err| invalid_data(err.to_string()))?;
+    let version = schema_version(&value);
+    let Some(value) = migrate_value(kind, value).map_err(invalid_data)? else {
+        return Ok(());
+    };
+
+    // Keep the original, e.g. as `backup/fan/default.v0.json`.
+    let (Some(dir_name), Some(file_stem)) = (
+        Path::new(kind.dir()).file_name(),
+        path.file_stem().and_then(|stem| stem.to_str()),
+    ) else {
+        return Err(invalid_data(format!("Invalid profile path {path:?}")));
+    };
+    let backup_dir = Path::new(&*BACKUP_DIR).join(dir_name);
+    fs::create_dir_all(&backup_dir)?;
+    fs::write(
+        backup_dir.join(format!("{file_stem}.v{version}.{}", format.extension())),
+        &content,
+    )?;
+
+    let data = format
+        .serialize(&value)
+        .map_err(|err| invalid_data(err.to_string()))?;
+    fs::write(path, data)?;
+    tracing::info!(
+        "Migrated profile at `{path:?}` from schema version {version} to {SCHEMA_VERSION}"
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use tailor_api::{ColorProfile, FanProfileInfo, ProfileInfo, SCHEMA_VERSION};
+
+    use super::{migrate_value, ProfileKind};
+
+    #[test]
+    fn migrate_legacy_profiles() {
+        for kind in [
+            ProfileKind::Profile,
+            ProfileKind::Fan,
+            ProfileKind::Keyboard,
+        ] {
+            assert_eq!(kind.migrations().len(), SCHEMA_VERSION as usize);
+        }
+
+        let migrate = |kind, data: &str| {
+            migrate_value(kind, serde_json::from_str(data).unwrap())
+                .unwrap()
+                .unwrap()
+        };
+
+        let profile: ProfileInfo = serde_json::from_value(migrate(
+            ProfileKind::Profile,
+            r#"{"fans":["default"],"leds":[],"performance_profile":null}"#,
+        ))
+        .unwrap();
+        assert_eq!(profile, ProfileInfo::default());
+
+        let fan = migrate(ProfileKind::Fan, r#"[{"temp":30,"fan":20}]"#);
+        assert_eq!(fan["version"], SCHEMA_VERSION);
+        let fan: FanProfileInfo = serde_json::from_value(fan).unwrap();
+        assert_eq!(fan.points.len(), 1);
+
+        let keyboard: ColorProfile = serde_json::from_value(migrate(
+            ProfileKind::Keyboard,
+            r#"{"Single":{"r":255,"g":0,"b":0}}"#,
+        ))
+        .unwrap();
+        assert!(matches!(keyboard, ColorProfile::Single(_)));
+
+        let current = serde_json::to_value(ProfileInfo::default()).unwrap();
+        assert_eq!(migrate_value(ProfileKind::Profile, current).unwrap(), None);
+
+        let newer = serde_json::json!({ "version": SCHEMA_VERSION + 1, "profile": "None" });
+        migrate_value(ProfileKind::Keyboard, newer).unwrap_err();
+    }
+}
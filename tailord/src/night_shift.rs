@@ -0,0 +1,215 @@
+//! Warms the colors of RGB LEDs at night. The LED runtimes shift every
+//! color they write towards the color temperature of [`get_temperature_receiver`].
+
+use std::{f64::consts::PI, time::Duration};
+
+use chrono::{Datelike, Offset, Timelike};
+use once_cell::sync::Lazy;
+use tailor_api::Color;
+use tokio::sync::watch;
+
+use crate::config::{Location, NightShiftConfig};
+
+/// The color temperature that keeps the colors.
+pub const NEUTRAL_TEMPERATURE: u32 = 6500;
+const MIN_TEMPERATURE: u32 = 1000;
+/// Updates are rounded to this step, so colors
+/// aren't rewritten for changes nobody can see.
+const TEMPERATURE_STEP: u32 = 100;
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+const MINUTES_PER_DAY: i32 = 24 * 60;
+
+static TEMPERATURE: Lazy<watch::Sender<u32>> = Lazy::new(|| watch::channel(NEUTRAL_TEMPERATURE).0);
+
+/// Receives the current color temperature of the LEDs in Kelvin.
+pub fn get_temperature_receiver() -> watch::Receiver<u32> {
+    TEMPERATURE.subscribe()
+}
+
+/// When the night starts and ends, in minutes after midnight of local time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Night {
+    Between {
+        start: i32,
+        end: i32,
+    },
+    /// The sun doesn't rise, e.g. during polar nights.
+    Always,
+    /// The sun doesn't set.
+    Never,
+}
+
+pub async fn run(config: NightShiftConfig) {
+    if config.hours.is_none() && config.location.is_none() {
+        tracing::warn!("The night shift needs either `hours` or a `location`");
+        return;
+    }
+    let night_temperature = config
+        .temperature
+        .clamp(MIN_TEMPERATURE, NEUTRAL_TEMPERATURE);
+
+    loop {
+        let now = chrono::Local::now();
+        let minute = (now.hour() * 60 + now.minute()) as i32;
+        let night = match (config.hours, config.location) {
+            (Some(hours), _) => Night::Between {
+                start: (hours.from.hour as i32) * 60 + hours.from.minute as i32,
+                end: (hours.to.hour as i32) * 60 + hours.to.minute as i32,
+            },
+            (None, Some(location)) => {
+                let utc_offset_mins = now.offset().fix().local_minus_utc() / 60;
+                sun_night(location, now.ordinal(), utc_offset_mins)
+            }
+            (None, None) => Night::Never,
+        };
+
+        let factor = night_factor(night, minute, config.transition_mins);
+        let shift = (NEUTRAL_TEMPERATURE - night_temperature) as f64 * factor;
+        let temperature = NEUTRAL_TEMPERATURE
+            - (shift / TEMPERATURE_STEP as f64).round() as u32 * TEMPERATURE_STEP;
+        TEMPERATURE.send_if_modified(|current| {
+            if *current == temperature {
+                return false;
+            }
+            tracing::debug!("Shifting the LED colors to {temperature} K");
+            *current = temperature;
+            true
+        });
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Shift a color to a color temperature in Kelvin.
+pub fn shift(color: &Color, temperature: u32) -> Color {
+    if temperature >= NEUTRAL_TEMPERATURE {
+        return color.clone();
+    }
+    let [r, g, b] = white_point(temperature);
+    let [neutral_r, neutral_g, neutral_b] = white_point(NEUTRAL_TEMPERATURE);
+    let channel = |value: u8, factor: f64| (value as f64 * factor).round().clamp(0.0, 255.0) as u8;
+    Color {
+        r: channel(color.r, r / neutral_r),
+        g: channel(color.g, g / neutral_g),
+        b: channel(color.b, b / neutral_b),
+    }
+}
+
+/// The color of a black body at a temperature from 1000 to 6500 K, each
+/// channel from 0.0 to 1.0. An approximation of the CIE color matching
+/// functions that is close enough for LEDs.
+fn white_point(temperature: u32) -> [f64; 3] {
+    let temperature = temperature.clamp(MIN_TEMPERATURE, NEUTRAL_TEMPERATURE) as f64 / 100.0;
+    let green = 99.470_802_586_1 * temperature.ln() - 161.119_568_166_1;
+    let blue = if temperature <= 19.0 {
+        0.0
+    } else {
+        138.517_731_223_1 * (temperature - 10.0).ln() - 305.044_792_730_7
+    };
+    [
+        1.0,
+        (green / 255.0).clamp(0.0, 1.0),
+        (blue / 255.0).clamp(0.0, 1.0),
+    ]
+}
+
+/// How far the night has progressed, from 0.0 at day to 1.0 at night.
+/// The factor rises after the start and falls before the end of the night.
+fn night_factor(night: Night, minute: i32, transition_mins: u32) -> f64 {
+    let (start, end) = match night {
+        Night::Between { start, end } => (start, end),
+        Night::Always => return 1.0,
+        Night::Never => return 0.0,
+    };
+    let since_start = (minute - start).rem_euclid(MINUTES_PER_DAY);
+    let length = (end - start).rem_euclid(MINUTES_PER_DAY);
+    if since_start >= length {
+        return 0.0;
+    }
+    let until_end = length - since_start;
+    let transition = transition_mins.max(1) as f64;
+    (since_start as f64 / transition)
+        .min(until_end as f64 / transition)
+        .min(1.0)
+}
+
+/// The night from sunset to sunrise, after the equations of the NOAA.
+fn sun_night(location: Location, day_of_year: u32, utc_offset_mins: i32) -> Night {
+    let year_angle = 2.0 * PI / 365.0 * (day_of_year as f64 - 1.0);
+    let equation_of_time = 229.18
+        * (0.000_075 + 0.001_868 * year_angle.cos()
+            - 0.032_077 * year_angle.sin()
+            - 0.014_615 * (2.0 * year_angle).cos()
+            - 0.040_849 * (2.0 * year_angle).sin());
+    let declination = 0.006_918 - 0.399_912 * year_angle.cos() + 0.070_257 * year_angle.sin()
+        - 0.006_758 * (2.0 * year_angle).cos()
+        + 0.000_907 * (2.0 * year_angle).sin()
+        - 0.002_697 * (3.0 * year_angle).cos()
+        + 0.001_48 * (3.0 * year_angle).sin();
+
+    let latitude = location.latitude.to_radians();
+    // The zenith of the sun at sunrise and sunset, including refraction.
+    let zenith = 90.833_f64.to_radians();
+    let cos_hour_angle =
+        zenith.cos() / (latitude.cos() * declination.cos()) - latitude.tan() * declination.tan();
+    if cos_hour_angle > 1.0 {
+        return Night::Always;
+    }
+    if cos_hour_angle < -1.0 {
+        return Night::Never;
+    }
+    let hour_angle = cos_hour_angle.acos().to_degrees();
+
+    let local = |minutes: f64| minutes.round() as i32 + utc_offset_mins;
+    let sunrise = local(720.0 - 4.0 * (location.longitude + hour_angle) - equation_of_time);
+    let sunset = local(720.0 - 4.0 * (location.longitude - hour_angle) - equation_of_time);
+    Night::Between {
+        start: sunset.rem_euclid(MINUTES_PER_DAY),
+        end: sunrise.rem_euclid(MINUTES_PER_DAY),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tailor_api::Color;
+
+    use super::{night_factor, shift, sun_night, Night, NEUTRAL_TEMPERATURE};
+    use crate::config::Location;
+
+    #[test]
+    fn night_shift() {
+        let night = Night::Between {
+            start: 20 * 60,
+            end: 7 * 60,
+        };
+        assert_eq!(night_factor(night, 12 * 60, 60), 0.0);
+        assert_eq!(night_factor(night, 20 * 60 + 30, 60), 0.5);
+        assert_eq!(night_factor(night, 2 * 60, 60), 1.0);
+        assert_eq!(night_factor(night, 6 * 60 + 45, 60), 0.25);
+
+        let white = Color {
+            r: 255,
+            g: 255,
+            b: 255,
+        };
+        assert_eq!(shift(&white, NEUTRAL_TEMPERATURE), white);
+        let warm = shift(&white, 3400);
+        assert_eq!(warm.r, 255);
+        assert!(warm.g < 255 && warm.b < warm.g);
+
+        // Berlin in the middle of June, in CEST.
+        let berlin = Location {
+            latitude: 52.52,
+            longitude: 13.40,
+        };
+        let Night::Between { start, end } = sun_night(berlin, 172, 120) else {
+            panic!("The sun must set in Berlin");
+        };
+        assert!((21 * 60 + 25..21 * 60 + 40).contains(&start), "{start}");
+        assert!((4 * 60 + 35..4 * 60 + 50).contains(&end), "{end}");
+        let svalbard = Location {
+            latitude: 78.2,
+            longitude: 15.6,
+        };
+        assert_eq!(sun_night(svalbard, 172, 120), Night::Never);
+    }
+}
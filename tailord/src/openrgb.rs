@@ -0,0 +1,327 @@
+//! A server for the network SDK of OpenRGB, so applications that
+//! integrate with OpenRGB can set the colors of the LED devices.
+//!
+//! Each LED device is a controller with a single zone and LED.
+//! New colors are sent as color overrides, so they last until
+//! the override is cleared or a profile is (re)loaded.
+
+use std::net::SocketAddr;
+
+use tailor_api::{Color, LedDeviceState};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::led::LedRuntimeHandle;
+
+const MAGIC: &[u8; 4] = b"ORGB";
+const HEADER_SIZE: usize = 16;
+/// Version 1 adds the vendor to the controller data,
+/// later versions add features that don't apply to these devices.
+const PROTOCOL_VERSION: u32 = 1;
+/// Larger packets are rejected, so clients can't exhaust the memory.
+const MAX_PACKET_SIZE: usize = 1 << 16;
+
+const REQUEST_CONTROLLER_COUNT: u32 = 0;
+const REQUEST_CONTROLLER_DATA: u32 = 1;
+const REQUEST_PROTOCOL_VERSION: u32 = 40;
+const SET_CLIENT_NAME: u32 = 50;
+const UPDATE_LEDS: u32 = 1050;
+const UPDATE_ZONE_LEDS: u32 = 1051;
+const UPDATE_SINGLE_LED: u32 = 1052;
+
+const DEVICE_TYPE_KEYBOARD: i32 = 5;
+const DEVICE_TYPE_LIGHT: i32 = 11;
+const ZONE_TYPE_SINGLE: i32 = 0;
+const MODE_FLAG_HAS_PER_LED_COLOR: u32 = 1 << 5;
+const MODE_COLORS_PER_LED: u32 = 1;
+
+/// Accept clients until the listener fails.
+pub async fn serve(address: SocketAddr, handles: Vec<LedRuntimeHandle>) {
+    let listener = match TcpListener::bind(address).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            tracing::error!("Failed to start the OpenRGB server at {address}: `{err}`");
+            return;
+        }
+    };
+    tracing::info!("OpenRGB server listening at {address}");
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                tracing::debug!("OpenRGB client connected from {peer}");
+                let client = Client {
+                    stream,
+                    handles: handles.clone(),
+                    protocol_version: 0,
+                };
+                tokio_uring::spawn(async move {
+                    if let Err(err) = client.run().await {
+                        tracing::debug!("OpenRGB client {peer} disconnected: `{err}`");
+                    }
+                });
+            }
+            Err(err) => {
+                tracing::error!("Stopping the OpenRGB server: `{err}`");
+                return;
+            }
+        }
+    }
+}
+
+struct Client {
+    stream: TcpStream,
+    handles: Vec<LedRuntimeHandle>,
+    /// The version that both sides support.
+    protocol_version: u32,
+}
+
+impl Client {
+    async fn run(mut self) -> std::io::Result<()> {
+        let mut header = [0; HEADER_SIZE];
+        loop {
+            self.stream.read_exact(&mut header).await?;
+            if &header[..4] != MAGIC {
+                return Err(invalid_data("Invalid magic"));
+            }
+            let device_idx = read_u32(&header[4..]) as usize;
+            let packet_id = read_u32(&header[8..]);
+            let size = read_u32(&header[12..]) as usize;
+            if size > MAX_PACKET_SIZE {
+                return Err(invalid_data("Packet too large"));
+            }
+            let mut data = vec![0; size];
+            self.stream.read_exact(&mut data).await?;
+
+            self.handle_packet(device_idx, packet_id, &data).await?;
+        }
+    }
+
+    async fn handle_packet(
+        &mut self,
+        device_idx: usize,
+        packet_id: u32,
+        data: &[u8],
+    ) -> std::io::Result<()> {
+        match packet_id {
+            REQUEST_CONTROLLER_COUNT => {
+                let count = self.handles.len() as u32;
+                self.send(0, packet_id, &count.to_le_bytes()).await
+            }
+            REQUEST_CONTROLLER_DATA => {
+                let version = data
+                    .get(..4)
+                    .map(read_u32)
+                    .unwrap_or_default()
+                    .min(self.protocol_version);
+                let Some(handle) = self.handles.get(device_idx) else {
+                    return Ok(());
+                };
+                let state = handle.state_receiver.borrow().clone();
+                let data = controller_data(&state, version);
+                self.send(device_idx as u32, packet_id, &data).await
+            }
+            REQUEST_PROTOCOL_VERSION => {
+                let client_version = data.get(..4).map(read_u32).unwrap_or_default();
+                self.protocol_version = client_version.min(PROTOCOL_VERSION);
+                self.send(0, packet_id, &PROTOCOL_VERSION.to_le_bytes())
+                    .await
+            }
+            SET_CLIENT_NAME => {
+                let name = String::from_utf8_lossy(data);
+                tracing::debug!("OpenRGB client `{}`", name.trim_end_matches('\0'));
+                Ok(())
+            }
+            UPDATE_LEDS | UPDATE_ZONE_LEDS | UPDATE_SINGLE_LED => {
+                if let (Some(handle), Some(color)) =
+                    (self.handles.get(device_idx), update_color(packet_id, data))
+                {
+                    handle
+                        .color_override_sender
+                        .send(Some(color))
+                        .await
+                        .map_err(|err| {
+                            std::io::Error::new(std::io::ErrorKind::Other, err.to_string())
+                        })?;
+                }
+                Ok(())
+            }
+            // Modes and zones can't be changed, there's only the direct mode.
+            _ => Ok(()),
+        }
+    }
+
+    async fn send(&mut self, device_idx: u32, packet_id: u32, data: &[u8]) -> std::io::Result<()> {
+        let mut packet = Vec::with_capacity(HEADER_SIZE + data.len());
+        packet.extend_from_slice(MAGIC);
+        packet.extend_from_slice(&device_idx.to_le_bytes());
+        packet.extend_from_slice(&packet_id.to_le_bytes());
+        packet.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        packet.extend_from_slice(data);
+        self.stream.write_all(&packet).await
+    }
+}
+
+fn invalid_data(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+/// Callers make sure that there are at least 4 bytes.
+fn read_u32(data: &[u8]) -> u32 {
+    u32::from_le_bytes([data[0], data[1], data[2], data[3]])
+}
+
+/// The first color of an update packet.
+/// The devices only have a single LED, so the other colors are ignored.
+fn update_color(packet_id: u32, data: &[u8]) -> Option<Color> {
+    let color = match packet_id {
+        // Data size and number of colors.
+        UPDATE_LEDS => data.get(6..10)?,
+        // Data size, zone index and number of colors.
+        UPDATE_ZONE_LEDS => data.get(10..14)?,
+        // LED index.
+        UPDATE_SINGLE_LED => data.get(4..8)?,
+        _ => return None,
+    };
+    Some(Color {
+        r: color[0],
+        g: color[1],
+        b: color[2],
+    })
+}
+
+/// Serializes the description of a LED device.
+fn controller_data(state: &LedDeviceState, protocol_version: u32) -> Vec<u8> {
+    let info = &state.info;
+    let color = state
+        .color_override
+        .clone()
+        .or_else(|| state.color.clone())
+        .unwrap_or(Color { r: 0, g: 0, b: 0 });
+    let device_type = if info.function.starts_with("kbd_backlight") {
+        DEVICE_TYPE_KEYBOARD
+    } else {
+        DEVICE_TYPE_LIGHT
+    };
+
+    let mut data = Writer::default();
+    data.i32(device_type);
+    data.string(&info.device_id());
+    if protocol_version >= 1 {
+        data.string("TUXEDO");
+    }
+    data.string("LED device managed by tailord");
+    data.string(env!("CARGO_PKG_VERSION"));
+    // Serial number.
+    data.string("");
+    data.string(&format!("/sys/class/leds/{}", info.device_name));
+
+    // A single direct mode that is always active.
+    data.u16(1);
+    data.i32(0);
+    data.string("Direct");
+    data.i32(0);
+    data.u32(MODE_FLAG_HAS_PER_LED_COLOR);
+    // Minimum and maximum speed and number of colors.
+    data.u32(0);
+    data.u32(0);
+    data.u32(0);
+    data.u32(0);
+    // Speed and direction.
+    data.u32(0);
+    data.u32(0);
+    data.u32(MODE_COLORS_PER_LED);
+    data.u16(0);
+
+    data.u16(1);
+    data.string(&info.function);
+    data.i32(ZONE_TYPE_SINGLE);
+    // Minimum, maximum and actual number of LEDs.
+    data.u32(1);
+    data.u32(1);
+    data.u32(1);
+    // No matrix.
+    data.u16(0);
+
+    data.u16(1);
+    data.string(&info.function);
+    data.u32(0);
+
+    data.u16(1);
+    data.color(&color);
+
+    data.finish()
+}
+
+#[derive(Default)]
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u16(&mut self, value: u16) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn i32(&mut self, value: i32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Strings are prefixed with their length and null terminated.
+    fn string(&mut self, value: &str) {
+        self.u16(value.len() as u16 + 1);
+        self.0.extend_from_slice(value.as_bytes());
+        self.0.push(0);
+    }
+
+    fn color(&mut self, color: &Color) {
+        self.0.extend_from_slice(&[color.r, color.g, color.b, 0]);
+    }
+
+    /// Prefix the data with its size, including the size itself.
+    fn finish(self) -> Vec<u8> {
+        let size = self.0.len() as u32 + 4;
+        let mut data = size.to_le_bytes().to_vec();
+        data.extend(self.0);
+        data
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tailor_api::{Color, LedDeviceInfo, LedDeviceState};
+
+    use super::{controller_data, read_u32, update_color, UPDATE_LEDS, UPDATE_SINGLE_LED};
+
+    #[test]
+    fn packets() {
+        let red = Color { r: 255, g: 0, b: 0 };
+        let update_leds = [10, 0, 0, 0, 1, 0, 255, 0, 0, 0];
+        assert_eq!(update_color(UPDATE_LEDS, &update_leds), Some(red.clone()));
+        let single_led = [0, 0, 0, 0, 255, 0, 0, 0];
+        assert_eq!(update_color(UPDATE_SINGLE_LED, &single_led), Some(red));
+        assert_eq!(update_color(UPDATE_LEDS, &update_leds[..8]), None);
+
+        let state = LedDeviceState {
+            info: LedDeviceInfo {
+                device_name: "rgb".to_owned(),
+                function: "kbd_backlight".to_owned(),
+                mode: Default::default(),
+            },
+            color: Some(Color { r: 1, g: 2, b: 3 }),
+            color_override: None,
+            brightness: None,
+        };
+        let data = controller_data(&state, 0);
+        assert_eq!(read_u32(&data) as usize, data.len());
+        // Keyboard type and the name `rgb::kbd_backlight` with its null terminator.
+        assert_eq!(read_u32(&data[4..]), 5);
+        assert_eq!(&data[8..10], &[19, 0]);
+        assert!(data.ends_with(&[1, 0, 1, 2, 3, 0]));
+        assert_eq!(controller_data(&state, 1).len(), data.len() + 9);
+    }
+}
@@ -1,14 +1,28 @@
-use std::sync::Arc;
+use std::{
+    fmt::Display,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
-use tokio::sync::mpsc;
-use tuxedo_ioctl::hal::{traits::HardwareDevice, IoctlResult};
+use tailor_api::HardwareWrite;
+use tokio::sync::{mpsc, watch};
+use tuxedo_ioctl::hal::{traits::PerformanceProfiles, IoctlResult};
+
+use crate::{
+    audit::{self, Cause},
+    inhibit::{self, get_inhibit_receiver},
+    platform_profile::PlatformProfile,
+};
+
+/// Interval in which external changes of the platform profile are detected.
+const PLATFORM_PROFILE_POLL_INTERVAL: Duration = Duration::from_secs(2);
 
 #[derive(Debug)]
 pub struct PerformanceProfile(String);
 
-impl ToString for PerformanceProfile {
-    fn to_string(&self) -> String {
-        self.0.to_string()
+impl Display for PerformanceProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
     }
 }
 
@@ -18,25 +32,53 @@ impl PerformanceProfile {
     }
 }
 
-#[allow(unused)]
 #[derive(Clone)]
 pub struct PerformanceProfileRuntimeHandle {
     pub profile_sender: mpsc::Sender<String>,
     /// Device i/o interface.
-    io: Arc<dyn HardwareDevice>,
-    /// Current profile.
-    performance_profile: String,
+    io: Arc<dyn PerformanceProfiles>,
+    /// Current profile, shared with the runtime.
+    performance_profile: Arc<Mutex<String>>,
 }
 
 impl PerformanceProfileRuntimeHandle {
     pub fn get_availables_performance_profiles(&self) -> IoctlResult<Vec<String>> {
         self.io.get_available_odm_performance_profiles()
     }
-    pub fn set_active_performance_profile(&mut self, name: &str) {
-        self.performance_profile = name.to_string();
+    pub fn set_active_performance_profile(&self, name: &str) {
+        *self.performance_profile.lock().unwrap() = name.to_string();
+    }
+    /// Write the performance profile to the EC and let the
+    /// runtime update the platform profile. While the EC is
+    /// inhibited, the runtime writes it once it's released.
+    pub async fn apply(&self, name: &str) -> IoctlResult<()> {
+        if inhibit::is_inhibited() {
+            tracing::info!("Loading performance profile {name} once the EC is released");
+            self.set_active_performance_profile(name);
+            return Ok(());
+        }
+        tracing::info!("Loading performance profile {name}");
+        self.io.set_odm_performance_profile(name)?;
+        self.set_active_performance_profile(name);
+        if self.profile_sender.send(name.to_owned()).await.is_err() {
+            tracing::warn!(
+                "The performance profile runtime stopped, the platform profile isn't updated"
+            );
+        }
+        Ok(())
+    }
+    pub fn get_active_performance_profile(&self) -> String {
+        self.performance_profile.lock().unwrap().clone()
     }
-    pub fn get_active_performance_profile(&self) -> &str {
-        &self.performance_profile
+}
+
+/// A write of the performance profile for the audit log.
+pub fn audit_write(old: String, new: &str) -> HardwareWrite {
+    HardwareWrite {
+        device: "Embedded controller".to_owned(),
+        attribute: "performance profile".to_owned(),
+        old: Some(old),
+        new: new.to_owned(),
     }
 }
 
@@ -44,14 +86,19 @@ impl PerformanceProfileRuntimeHandle {
 pub struct PerformanceProfileRuntime {
     profile_receiver: mpsc::Receiver<String>,
     /// Device i/o interface.
-    io: Arc<dyn HardwareDevice>,
+    io: Arc<dyn PerformanceProfiles>,
+    performance_profile: Arc<Mutex<String>>,
+    /// The ACPI platform profile, if the kernel exposes it.
+    platform_profile: Option<PlatformProfile>,
+    /// The number of tools that took over the EC.
+    inhibit_receiver: watch::Receiver<usize>,
 }
 
 impl PerformanceProfileRuntime {
     // initialize global instance at startup
     #[tracing::instrument(skip(io))]
     pub fn new(
-        io: Arc<dyn HardwareDevice>,
+        io: Arc<dyn PerformanceProfiles>,
         performance_profile: Option<PerformanceProfile>,
         default_performance_profile: String,
     ) -> (PerformanceProfileRuntimeHandle, PerformanceProfileRuntime) {
@@ -62,30 +109,125 @@ impl PerformanceProfileRuntime {
         };
         io.set_odm_performance_profile(&performance_profile)
             .unwrap();
+
+        let mut platform_profile = PlatformProfile::new();
+        if let Some(platform_profile) = &mut platform_profile {
+            if let Err(err) = platform_profile.apply_odm(&performance_profile) {
+                tracing::warn!("Failed to set platform profile: `{err}`");
+            }
+        }
+
+        let performance_profile = Arc::new(Mutex::new(performance_profile));
         (
             PerformanceProfileRuntimeHandle {
                 profile_sender,
                 io: io.clone(),
-                performance_profile,
+                performance_profile: performance_profile.clone(),
             },
             PerformanceProfileRuntime {
                 profile_receiver,
                 io,
+                performance_profile,
+                platform_profile,
+                inhibit_receiver: get_inhibit_receiver(),
             },
         )
     }
 
     #[tracing::instrument(skip(self))]
     pub async fn run(mut self) {
+        let mut poll_interval = tokio::time::interval(PLATFORM_PROFILE_POLL_INTERVAL);
+        loop {
+            tokio::select! {
+                profile = self.profile_receiver.recv() => {
+                    // The handle already wrote the profile to the EC.
+                    if let Some(profile) = profile {
+                        if let Some(platform_profile) = &mut self.platform_profile {
+                            if let Err(err) = platform_profile.apply_odm(&profile) {
+                                tracing::warn!("Failed to set platform profile: `{err}`");
+                            }
+                        }
+                    } else {
+                        tracing::warn!(
+                            "Stopping runtime, the performance profile channel sender has probably dropped"
+                        );
+                        break;
+                    }
+                }
+                _ = poll_interval.tick(), if self.platform_profile.is_some() => {
+                    self.sync_platform_profile();
+                }
+                _ = inhibit::inhibited(&mut self.inhibit_receiver) => self.pause().await,
+            }
+        }
+    }
+
+    /// Don't write to the EC until all inhibitors are released,
+    /// then apply the profile that is active by now.
+    async fn pause(&mut self) {
         loop {
-            if let Some(profile) = self.profile_receiver.recv().await {
-                tracing::info!("Loading performance profile {profile}");
-                self.io.set_odm_performance_profile(&profile).unwrap();
-            } else {
-                tracing::warn!(
-                    "Stopping runtime, the performance profile channel sender has probably dropped"
-                );
-                break;
+            tokio::select! {
+                _ = inhibit::released(&mut self.inhibit_receiver) => break,
+                // Profiles are only stored as active during the inhibit.
+                Some(_) = self.profile_receiver.recv() => {}
+            }
+        }
+
+        let profile = self.performance_profile.lock().unwrap().clone();
+        tracing::info!("Loading performance profile {profile} after the EC was released");
+        if let Err(err) = self.io.set_odm_performance_profile(&profile) {
+            tracing::warn!("Failed to set performance profile: `{err:?}`");
+        }
+        if let Some(platform_profile) = &mut self.platform_profile {
+            if let Err(err) = platform_profile.apply_odm(&profile) {
+                tracing::warn!("Failed to set platform profile: `{err}`");
+            }
+        }
+    }
+
+    /// Switch to the matching ODM profile if the
+    /// platform profile was changed by another tool.
+    fn sync_platform_profile(&mut self) {
+        let Some(platform_profile) = &mut self.platform_profile else {
+            return;
+        };
+
+        let value = match platform_profile.poll_change() {
+            Ok(Some(value)) => value,
+            Ok(None) => return,
+            Err(err) => {
+                tracing::warn!("Failed to read platform profile: `{err}`");
+                return;
+            }
+        };
+
+        let odm_profiles = match self.io.get_available_odm_performance_profiles() {
+            Ok(profiles) => profiles,
+            Err(err) => {
+                tracing::warn!("Failed to read available performance profiles: `{err:?}`");
+                return;
+            }
+        };
+
+        let Some(odm_profile) = platform_profile.odm_for(&value, &odm_profiles) else {
+            tracing::warn!("No performance profile matches the platform profile `{value}`");
+            return;
+        };
+
+        let mut active = self.performance_profile.lock().unwrap();
+        if *active != odm_profile {
+            tracing::info!(
+                "Platform profile was changed to `{value}`, loading performance profile {odm_profile}"
+            );
+            match self.io.set_odm_performance_profile(odm_profile) {
+                Ok(()) => {
+                    audit::record(
+                        audit_write(active.clone(), odm_profile),
+                        Cause::Rule("platform profile change"),
+                    );
+                    *active = odm_profile.to_owned();
+                }
+                Err(err) => tracing::warn!("Failed to set performance profile: `{err:?}`"),
             }
         }
     }
@@ -0,0 +1,129 @@
+//! Synchronization with the ACPI platform profile of the kernel,
+//! so other tools like power-profiles-daemon see the same profile
+//! as tailord and changes made by them are picked up.
+
+use std::{fs, io, path::Path};
+
+use tailor_api::HardwareWrite;
+use tuxedo_sysfs::attribute::planned_write;
+
+const PLATFORM_PROFILE_PATH: &str = "/sys/firmware/acpi/platform_profile";
+const PLATFORM_PROFILE_CHOICES_PATH: &str = "/sys/firmware/acpi/platform_profile_choices";
+
+/// Platform profiles that fit an ODM performance profile, best match first.
+fn candidates(odm_profile: &str) -> &'static [&'static str] {
+    match odm_profile {
+        "quiet" => &["quiet", "low-power", "cool"],
+        "power_saving" => &["low-power", "quiet", "cool"],
+        "power_save" | "entertainment" => &["balanced"],
+        "enthusiast" => &["balanced-performance", "performance"],
+        "overboost" | "performance" => &["performance"],
+        _ => &[],
+    }
+}
+
+#[derive(Debug)]
+pub struct PlatformProfile {
+    choices: Vec<String>,
+    /// The value that was last written or read.
+    current: Option<String>,
+}
+
+impl PlatformProfile {
+    /// Returns `None` if the kernel doesn't expose a platform profile.
+    pub fn new() -> Option<Self> {
+        let choices = fs::read_to_string(PLATFORM_PROFILE_CHOICES_PATH).ok()?;
+        let choices = choices.split_whitespace().map(ToOwned::to_owned).collect();
+        Some(Self {
+            choices,
+            current: None,
+        })
+    }
+
+    /// The platform profile that matches an ODM performance profile.
+    pub fn for_odm(&self, odm_profile: &str) -> Option<&str> {
+        if let Some(choice) = self.choices.iter().find(|choice| *choice == odm_profile) {
+            return Some(choice);
+        }
+        candidates(odm_profile)
+            .iter()
+            .copied()
+            .find(|candidate| self.choices.iter().any(|choice| choice == candidate))
+    }
+
+    /// The ODM performance profile that maps to a platform profile.
+    pub fn odm_for<'a>(
+        &self,
+        platform_profile: &str,
+        odm_profiles: &'a [String],
+    ) -> Option<&'a str> {
+        odm_profiles
+            .iter()
+            .find(|odm_profile| self.for_odm(odm_profile) == Some(platform_profile))
+            .map(String::as_str)
+    }
+
+    /// Set the platform profile that matches an ODM performance profile.
+    pub fn apply_odm(&mut self, odm_profile: &str) -> Result<(), io::Error> {
+        if let Some(platform_profile) = self.for_odm(odm_profile).map(ToOwned::to_owned) {
+            if self.current.as_deref() != Some(platform_profile.as_str()) {
+                fs::write(PLATFORM_PROFILE_PATH, &platform_profile)?;
+                tracing::debug!("Set platform profile to `{platform_profile}`");
+                self.current = Some(platform_profile);
+            }
+        }
+        Ok(())
+    }
+
+    /// The write of [`Self::apply_odm`], `None` if it keeps the platform profile.
+    pub fn preview_odm(&self, odm_profile: &str) -> Option<HardwareWrite> {
+        let path = Path::new(PLATFORM_PROFILE_PATH);
+        let name = path.file_name()?.to_string_lossy();
+        planned_write(path.parent()?, &name, self.for_odm(odm_profile)?)
+    }
+
+    /// Returns the new value if the platform profile
+    /// was changed by someone else since the last call.
+    pub fn poll_change(&mut self) -> Result<Option<String>, io::Error> {
+        let value = fs::read_to_string(PLATFORM_PROFILE_PATH)?.trim().to_owned();
+        if self.current.as_deref() == Some(value.as_str()) {
+            Ok(None)
+        } else {
+            self.current = Some(value.clone());
+            Ok(Some(value))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PlatformProfile;
+
+    #[test]
+    fn profile_mapping() {
+        let platform_profile = PlatformProfile {
+            choices: vec![
+                "low-power".to_owned(),
+                "balanced".to_owned(),
+                "performance".to_owned(),
+            ],
+            current: None,
+        };
+
+        assert_eq!(platform_profile.for_odm("quiet"), Some("low-power"));
+        assert_eq!(platform_profile.for_odm("enthusiast"), Some("performance"));
+        assert_eq!(platform_profile.for_odm("balanced"), Some("balanced"));
+        assert_eq!(platform_profile.for_odm("unknown"), None);
+
+        let odm_profiles = ["power_save", "enthusiast", "overboost"].map(ToOwned::to_owned);
+        assert_eq!(
+            platform_profile.odm_for("balanced", &odm_profiles),
+            Some("power_save")
+        );
+        assert_eq!(
+            platform_profile.odm_for("performance", &odm_profiles),
+            Some("enthusiast")
+        );
+        assert_eq!(platform_profile.odm_for("low-power", &odm_profiles), None);
+    }
+}
@@ -0,0 +1,51 @@
+//! The device power management settings of the active profile.
+
+use std::{io, sync::Mutex};
+
+use tailor_api::{HardwareWrite, PowerTuning};
+use tuxedo_sysfs::power_tuning::{PowerTunables, SavedPowerSettings};
+
+/// The raw settings before a profile changed them, so they can be
+/// restored when switching to a profile that sets fewer of them.
+static ORIGINAL: Mutex<Option<SavedPowerSettings>> = Mutex::new(None);
+
+/// Apply the settings of a profile. Settings the profile
+/// leaves out, or all of them for `None`, are restored.
+pub fn apply(tuning: Option<&PowerTuning>) -> Result<(), io::Error> {
+    let mut original = ORIGINAL.lock().unwrap();
+    let tunables = PowerTunables::new();
+    match tuning {
+        Some(tuning) => {
+            let original = match original.as_ref() {
+                Some(original) => original,
+                None => original.insert(tunables.save()?),
+            };
+            original.restore(tuning)?;
+            tunables.set(tuning)?;
+            tracing::info!("Applied device power tuning: {tuning:?}");
+        }
+        None => {
+            if let Some(original) = original.take() {
+                original.restore(&PowerTuning::default())?;
+                tracing::info!("Restored device power settings");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The writes of [`apply`] that change a value, without applying them.
+pub fn preview(tuning: Option<&PowerTuning>) -> Result<Vec<HardwareWrite>, io::Error> {
+    let original = ORIGINAL.lock().unwrap();
+    let tunables = PowerTunables::new();
+    match (tuning, original.as_ref()) {
+        (Some(tuning), Some(original)) => {
+            let mut writes = original.preview(tuning);
+            writes.extend(tunables.preview(tuning));
+            Ok(writes)
+        }
+        (Some(tuning), None) => Ok(tunables.preview(tuning)),
+        (None, Some(original)) => Ok(original.preview(&PowerTuning::default())),
+        (None, None) => Ok(Vec::new()),
+    }
+}
@@ -0,0 +1,73 @@
+//! Temporarily activate a profile and restore the previous one afterwards.
+
+use tailor_api::ProfileOwner;
+use zbus::Connection;
+
+use crate::{dbus::ProfileInterface, profiles::Profile, scope::UserScope, DBUS_PATH};
+
+/// The profile that was active before the temporary profile.
+pub struct PreviousProfile {
+    name: String,
+    user: Option<UserScope>,
+    owner: Option<ProfileOwner>,
+}
+
+/// Returns `None` if the profile is already active.
+/// `rule` names the reason in the audit log.
+pub async fn activate(
+    connection: &Connection,
+    name: &str,
+    rule: &'static str,
+) -> Result<Option<PreviousProfile>, String> {
+    let current = Profile::get_active_profile_name()
+        .await
+        .map_err(|err| err.to_string())?;
+    if current == name {
+        return Ok(None);
+    }
+    let previous = PreviousProfile {
+        name: current,
+        user: Profile::get_active_user_scope(),
+        owner: Profile::get_active_profile_owner(),
+    };
+    Profile::set_active_profile_name(name, None, None)
+        .await
+        .map_err(|err| err.to_string())?;
+    reload(connection, rule).await?;
+    Ok(Some(previous))
+}
+
+/// Restore the previous profile, unless another profile was activated in the meantime.
+pub async fn restore(
+    connection: &Connection,
+    temporary: &str,
+    previous: Option<PreviousProfile>,
+    rule: &'static str,
+) -> Result<(), String> {
+    let Some(previous) = previous else {
+        return Ok(());
+    };
+    let current = Profile::get_active_profile_name()
+        .await
+        .map_err(|err| err.to_string())?;
+    if current != temporary {
+        return Ok(());
+    }
+    Profile::set_active_profile_name(&previous.name, previous.user.as_ref(), previous.owner)
+        .await
+        .map_err(|err| err.to_string())?;
+    reload(connection, rule).await
+}
+
+async fn reload(connection: &Connection, rule: &'static str) -> Result<(), String> {
+    let iface = connection
+        .object_server()
+        .interface::<_, ProfileInterface>(DBUS_PATH)
+        .await
+        .map_err(|err| err.to_string())?;
+    let result = iface.get().await.reload_latest_profile(Some(rule)).await;
+    result.map_err(|err| err.to_string())?;
+    ProfileInterface::emit_active_profile_changed(iface.signal_context())
+        .await
+        .map_err(|err| err.to_string())
+}
@@ -1,83 +1,181 @@
-use std::{collections::HashMap, path::Component, path::Path};
+use std::{collections::HashMap, io, path::Path, sync::Mutex};
 
-use crate::{fancontrol::profile::FanProfile, performance::PerformanceProfile};
-use tailor_api::{ColorProfile, LedControllerMode, LedDeviceInfo, LedProfile, ProfileInfo};
+use crate::{
+    config, events,
+    fancontrol::{models, profile::FanProfile},
+    migration,
+    performance::PerformanceProfile,
+    scope::{self, UserScope},
+};
+use once_cell::sync::Lazy;
+use tailor_api::{
+    AspmPolicy, ColorProfile, EventKind, LedControllerMode, LedDeviceInfo, LedProfile, PowerTuning,
+    Preset, ProfileInfo, ProfileOrder, ProfileOwner, TailorError, UsbAutosuspend,
+};
 use zbus::fdo;
 
 use super::util;
 
 const DEFAULT_PROFILE_NAME: &str = "default";
-pub const PROFILE_DIR: &str = "/etc/tailord/profiles/";
-pub const KEYBOARD_DIR: &str = "/etc/tailord/keyboard/";
-pub const FAN_DIR: &str = "/etc/tailord/fan/";
-pub const ACTIVE_PROFILE_PATH: &str = "/etc/tailord/active_profile.json";
+pub const PROFILE_SUBDIR: &str = "profiles/";
+pub const KEYBOARD_SUBDIR: &str = "keyboard/";
+pub const FAN_SUBDIR: &str = "fan/";
+pub static PROFILE_DIR: Lazy<String> = Lazy::new(|| config::config_path(PROFILE_SUBDIR));
+pub static KEYBOARD_DIR: Lazy<String> = Lazy::new(|| config::config_path(KEYBOARD_SUBDIR));
+pub static FAN_DIR: Lazy<String> = Lazy::new(|| config::config_path(FAN_SUBDIR));
+pub static ACTIVE_PROFILE_PATH: Lazy<String> =
+    Lazy::new(|| config::config_path("active_profile.json"));
+/// The order of the profiles in the lists of all clients, stored as `profile_order.json`.
+const PROFILE_ORDER_NAME: &str = "profile_order";
+/// The user that activated the current profile, `None` if it was
+/// activated by the daemon, e.g. at startup.
+static ACTIVE_PROFILE_OWNER: Mutex<Option<ProfileOwner>> = Mutex::new(None);
 
 fn init_paths() {
-    [PROFILE_DIR, KEYBOARD_DIR, FAN_DIR]
+    [&*PROFILE_DIR, &*KEYBOARD_DIR, &*FAN_DIR]
         .into_iter()
         .for_each(|dir| {
             std::fs::create_dir_all(dir).ok();
         })
 }
 
-fn init_profiles(supported_features: SupportedFeatures) {
+/// Prefer RGB for the keyboard profiles because monochrome
+/// devices can still display RGB colors as brightness values.
+fn keyboard_mode(supported_features: &SupportedFeatures) -> LedControllerMode {
+    if supported_features
+        .leds
+        .iter()
+        .any(|info| info.mode == LedControllerMode::Rgb)
+    {
+        LedControllerMode::Rgb
+    } else {
+        LedControllerMode::Monochrome
+    }
+}
+
+/// Assign a keyboard profile to every device it was made for,
+/// other devices fall back to the default of their mode.
+fn led_profiles(
+    supported_features: &SupportedFeatures,
+    mode: LedControllerMode,
+    profile: &str,
+) -> Vec<LedProfile> {
+    supported_features
+        .leds
+        .iter()
+        .filter(|info| info.mode == mode)
+        .map(|info| LedProfile {
+            device_name: info.device_name.clone(),
+            function: info.function.clone(),
+            profile: profile.to_owned(),
+            mode: info.mode,
+        })
+        .collect()
+}
+
+fn write_default_profiles(
+    supported_features: &SupportedFeatures,
+    overwrite: bool,
+) -> fdo::Result<()> {
     fn default_profile_exists(base_path: &str) -> bool {
         Path::new(base_path).join(DEFAULT_PROFILE_NAME).exists()
     }
 
-    tracing::debug!("Initialising profiles.");
-    if !default_profile_exists(KEYBOARD_DIR) {
-        let profile = ColorProfile::default(supported_features.mode);
-        util::write_json_sync(KEYBOARD_DIR, DEFAULT_PROFILE_NAME, &profile).ok();
+    let mode = keyboard_mode(supported_features);
+    if overwrite || !default_profile_exists(&KEYBOARD_DIR) {
+        let profile = ColorProfile::default(mode);
+        util::write_profile_sync(&KEYBOARD_DIR, DEFAULT_PROFILE_NAME, &profile)?;
+    }
+    if overwrite || !default_profile_exists(&FAN_DIR) {
+        let profile = supported_features
+            .model_id
+            .as_deref()
+            .and_then(models::default_profile)
+            .unwrap_or_default();
+        util::write_profile_sync(&FAN_DIR, DEFAULT_PROFILE_NAME, &profile)?;
     }
-    if !default_profile_exists(FAN_DIR) {
-        let profile = FanProfile::default();
-        util::write_json_sync(FAN_DIR, DEFAULT_PROFILE_NAME, &profile).ok();
+    if overwrite || !default_profile_exists(&PROFILE_DIR) {
+        let profile = ProfileInfo {
+            leds: led_profiles(supported_features, mode, DEFAULT_PROFILE_NAME),
+            ..Default::default()
+        };
+        util::write_profile_sync(&PROFILE_DIR, DEFAULT_PROFILE_NAME, &profile)?;
     }
-    if !default_profile_exists(PROFILE_DIR) {
-        let profile = ProfileInfo::default();
-        util::write_json_sync(PROFILE_DIR, DEFAULT_PROFILE_NAME, &profile).ok();
+    Ok(())
+}
+
+fn write_presets(supported_features: &SupportedFeatures) -> fdo::Result<()> {
+    let mode = keyboard_mode(supported_features);
+    for preset in Preset::ALL {
+        let name = preset.name();
+        util::write_profile_sync(&KEYBOARD_DIR, name, &preset.keyboard_profile(mode))?;
+        util::write_profile_sync(&FAN_DIR, name, &preset.fan_profile())?;
+        let profile = ProfileInfo {
+            fans: vec![name.to_owned(); supported_features.num_of_fans.max(1) as usize],
+            leds: led_profiles(supported_features, mode, name),
+            performance_profile: preset
+                .odm_performance_profile(&supported_features.performance_profiles),
+            max_freq_mhz: None,
+            usb_autosuspend: None,
+            pcie_aspm: None,
+            power_tuning: preset.power_tuning(),
+        };
+        util::write_profile_sync(&PROFILE_DIR, name, &profile)?;
+    }
+    Ok(())
+}
+
+fn init_profiles(supported_features: SupportedFeatures) {
+    tracing::debug!("Initialising profiles.");
+    let first_start = std::fs::read_dir(&*PROFILE_DIR)
+        .map(|mut entries| entries.next().is_none())
+        .unwrap_or(true);
+    if let Err(err) = write_default_profiles(&supported_features, false) {
+        tracing::warn!("Failed to write the default profiles: `{err}`");
+    }
+    if first_start {
+        if let Err(err) = write_presets(&supported_features) {
+            tracing::warn!("Failed to write the preset profiles: `{err}`");
+        }
     }
 
     // Delete broken symlink
-    if std::fs::remove_file(ACTIVE_PROFILE_PATH).is_ok() {
-        tracing::warn!("Broken symlink at {ACTIVE_PROFILE_PATH} was removed");
+    if std::fs::remove_file(&*ACTIVE_PROFILE_PATH).is_ok() {
+        tracing::warn!("Broken symlink at {} was removed", *ACTIVE_PROFILE_PATH);
     }
 
     // Create new symlink
-    let mut default_profile = Path::new(PROFILE_DIR).join(DEFAULT_PROFILE_NAME);
-    default_profile.set_extension("json");
-    std::os::unix::fs::symlink(default_profile, ACTIVE_PROFILE_PATH).ok();
+    if let Ok((default_profile, _)) = util::find_profile(&PROFILE_DIR, DEFAULT_PROFILE_NAME) {
+        std::os::unix::fs::symlink(default_profile, &*ACTIVE_PROFILE_PATH).ok();
+    }
 }
 
 fn init_profiles_if_necessary(supported_features: SupportedFeatures) {
-    if !Path::new(ACTIVE_PROFILE_PATH).exists() {
+    if !Path::new(&*ACTIVE_PROFILE_PATH).exists() {
         init_profiles(supported_features);
     }
 }
 
-fn led_profile_path(name: &str) -> fdo::Result<String> {
-    util::normalize_json_path(KEYBOARD_DIR, name)
-}
-
-fn fan_path(name: &str) -> fdo::Result<String> {
-    util::normalize_json_path(FAN_DIR, name)
-}
-
-fn load_led_profile(name: &str) -> fdo::Result<ColorProfile> {
-    let color_profile_data = std::fs::read(led_profile_path(name)?)
-        .map_err(|err| fdo::Error::IOError(err.to_string()))?;
-    serde_json::from_slice(&color_profile_data)
-        .map_err(|err| fdo::Error::InvalidFileContent(err.to_string()))
+fn load_led_profile(user: Option<&UserScope>, name: &str) -> fdo::Result<ColorProfile> {
+    scope::read_profile(user, &KEYBOARD_DIR, KEYBOARD_SUBDIR, name)
 }
 
-fn load_fan_profile(name: &str) -> fdo::Result<FanProfile> {
-    FanProfile::load_config(fan_path(name)?)
+fn load_fan_profile(user: Option<&UserScope>, name: &str) -> fdo::Result<FanProfile> {
+    match scope::find_user_profile(user, FAN_SUBDIR, name) {
+        Some((user, path)) => {
+            FanProfile::from_config(user.read_profile_file(&path)?, path.as_ref())
+        }
+        None => FanProfile::load_config(util::find_profile(&FAN_DIR, name)?.0),
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct SupportedFeatures {
-    pub mode: LedControllerMode,
+    pub leds: Vec<LedDeviceInfo>,
+    pub num_of_fans: u8,
+    pub performance_profiles: Vec<String>,
+    /// Selects the default fan curve for the chassis.
+    pub model_id: Option<String>,
 }
 
 #[derive(Debug)]
@@ -85,21 +183,55 @@ pub struct Profile {
     pub fans: Vec<FanProfile>,
     pub leds: HashMap<LedDeviceInfo, ColorProfile>,
     pub performance_profile: Option<PerformanceProfile>,
+    pub max_freq_mhz: Option<u32>,
+    pub usb_autosuspend: Option<UsbAutosuspend>,
+    pub pcie_aspm: Option<AspmPolicy>,
+    pub power_tuning: Option<PowerTuning>,
 }
 
 impl Profile {
     pub fn init_if_necessary(supported_features: SupportedFeatures) {
         init_paths();
+        migration::migrate_profiles();
         init_profiles_if_necessary(supported_features);
     }
 
+    /// Restore the default profile and the presets, discarding changes to them.
+    pub fn reset_defaults(supported_features: &SupportedFeatures) -> fdo::Result<()> {
+        write_default_profiles(supported_features, true)?;
+        write_presets(supported_features)
+    }
+
     pub fn load() -> Self {
         let profile_info = Self::get_active_profile_info().unwrap_or_else(|err| {
-            tracing::warn!("Failed to load active profile at `{ACTIVE_PROFILE_PATH}`: {err:?}");
+            tracing::warn!(
+                "Failed to load active profile at `{}`: {err:?}",
+                *ACTIVE_PROFILE_PATH
+            );
             ProfileInfo::default()
         });
-        tracing::info!("Loaded profile at `{ACTIVE_PROFILE_PATH}`: {profile_info:?}");
+        tracing::info!(
+            "Loaded profile at `{}`: {profile_info:?}",
+            *ACTIVE_PROFILE_PATH
+        );
 
+        // Profiles of a user may use the fan and keyboard profiles of the user.
+        let user = Self::get_active_user_scope();
+        Self::from_info(profile_info, user.as_ref())
+    }
+
+    /// Load a profile without activating it, preferring the profiles of the user.
+    pub fn load_named(name: &str, user: Option<&UserScope>) -> fdo::Result<Self> {
+        match scope::find_user_profile(user, PROFILE_SUBDIR, name) {
+            Some((user, path)) => Ok(Self::from_info(user.read_profile_file(path)?, Some(user))),
+            None => {
+                let (path, _) = util::find_profile(&PROFILE_DIR, name)?;
+                Ok(Self::from_info(util::read_profile_file(path)?, None))
+            }
+        }
+    }
+
+    fn from_info(profile_info: ProfileInfo, user: Option<&UserScope>) -> Self {
         let mut led = HashMap::new();
         for data in profile_info.leds {
             let LedProfile {
@@ -113,7 +245,7 @@ impl Profile {
                 function,
                 mode,
             };
-            let profile = match load_led_profile(&profile) {
+            let profile = match load_led_profile(user, &profile) {
                 Ok(keyboard) => keyboard,
                 Err(err) => {
                     tracing::warn!(
@@ -130,7 +262,7 @@ impl Profile {
         let fan = profile_info
             .fans
             .iter()
-            .map(|fan_profile| match load_fan_profile(fan_profile) {
+            .map(|fan_profile| match load_fan_profile(user, fan_profile) {
                 Ok(fan) => fan,
                 Err(err) => {
                     tracing::error!(
@@ -151,42 +283,179 @@ impl Profile {
             fans: fan,
             leds: led,
             performance_profile,
+            max_freq_mhz: profile_info.max_freq_mhz,
+            usb_autosuspend: profile_info.usb_autosuspend,
+            pcie_aspm: profile_info.pcie_aspm,
+            power_tuning: profile_info.power_tuning,
         }
     }
 
-    pub async fn set_active_profile_name(name: &str) -> fdo::Result<()> {
-        std::fs::metadata(util::normalize_json_path(PROFILE_DIR, name)?)
-            .map_err(|_| fdo::Error::FileNotFound(format!("Couldn't find profile `{name}`")))?;
+    /// Get the color profile of a LED device.
+    /// Devices are identified by name and function only, so profiles
+    /// still apply if the mode of a device was stored incorrectly.
+    pub fn led_profile(&self, info: &LedDeviceInfo) -> ColorProfile {
+        self.leds
+            .iter()
+            .find_map(|(led_info, profile)| {
+                if led_info.device_name == info.device_name && led_info.function == info.function {
+                    Some(profile.clone())
+                } else {
+                    None
+                }
+            })
+            .unwrap_or_else(|| ColorProfile::default(info.mode))
+    }
 
-        std::fs::remove_file(ACTIVE_PROFILE_PATH)
+    /// Activate a profile. Profiles of the user take precedence over
+    /// system profiles and are linked with their absolute path.
+    /// The `owner` is `None` if the daemon itself activates the profile.
+    pub async fn set_active_profile_name(
+        name: &str,
+        user: Option<&UserScope>,
+        owner: Option<ProfileOwner>,
+    ) -> fdo::Result<()> {
+        let target = match user.and_then(|user| user.find_profile(PROFILE_SUBDIR, name)) {
+            Some(path) => path,
+            None => {
+                let (path, format) = util::find_profile(&PROFILE_DIR, name)?;
+                std::fs::metadata(path).map_err(|_| {
+                    util::tailor_error(TailorError::invalid_profile(format!(
+                        "Couldn't find profile `{name}`"
+                    )))
+                })?;
+                util::normalize_path("profiles", name, format)?
+            }
+        };
+
+        std::fs::remove_file(&*ACTIVE_PROFILE_PATH)
+            .map_err(|err| fdo::Error::IOError(err.to_string()))?;
+        std::os::unix::fs::symlink(target, &*ACTIVE_PROFILE_PATH)
             .map_err(|err| fdo::Error::IOError(err.to_string()))?;
-        std::os::unix::fs::symlink(
-            util::normalize_json_path("profiles", name)?,
-            ACTIVE_PROFILE_PATH,
-        )
-        .map_err(|err| fdo::Error::IOError(err.to_string()))
+
+        events::record(EventKind::ProfileSwitch {
+            profile: name.to_owned(),
+            user: owner.as_ref().map(|owner| owner.user_name.clone()),
+        });
+        *ACTIVE_PROFILE_OWNER.lock().unwrap() = owner;
+        Ok(())
+    }
+
+    /// Point the active profile back to `target` after a switch
+    /// to another profile failed and the previous profile was restored.
+    pub fn restore_active_profile(
+        name: &str,
+        target: &Path,
+        owner: Option<ProfileOwner>,
+    ) -> io::Result<()> {
+        replace_link(Path::new(&*ACTIVE_PROFILE_PATH), target)?;
+        events::record(EventKind::ProfileSwitch {
+            profile: name.to_owned(),
+            user: owner.as_ref().map(|owner| owner.user_name.clone()),
+        });
+        *ACTIVE_PROFILE_OWNER.lock().unwrap() = owner;
+        Ok(())
+    }
+
+    /// The user that activated the current profile.
+    pub fn get_active_profile_owner() -> Option<ProfileOwner> {
+        ACTIVE_PROFILE_OWNER.lock().unwrap().clone()
+    }
+
+    /// The order of the profiles, empty if none was set.
+    pub async fn load_order() -> ProfileOrder {
+        let dir = config::config_path("");
+        let exists = util::find_profile(&dir, PROFILE_ORDER_NAME)
+            .is_ok_and(|(path, _)| Path::new(&path).exists());
+        if !exists {
+            return ProfileOrder::default();
+        }
+        util::read_profile(&dir, PROFILE_ORDER_NAME)
+            .await
+            .unwrap_or_else(|err| {
+                tracing::warn!("Failed to load the profile order: {err}");
+                ProfileOrder::default()
+            })
+    }
+
+    pub async fn save_order(order: &ProfileOrder) -> fdo::Result<()> {
+        util::write_profile(&config::config_path(""), PROFILE_ORDER_NAME, order).await
     }
 
     pub async fn get_active_profile_name() -> fdo::Result<String> {
-        let link = std::fs::read_link(ACTIVE_PROFILE_PATH)
+        let link = std::fs::read_link(&*ACTIVE_PROFILE_PATH)
             .map_err(|err| fdo::Error::IOError(err.to_string()))?;
-        let components: Vec<Component> = link.components().collect();
-        if !components.is_empty() {
-            if let Component::Normal(name) = components.last().unwrap() {
-                if let Some(name) = name.to_str() {
-                    return Ok(name.trim_end_matches(".json").to_string());
-                }
-            }
+        if let Some(name) = link.file_stem().and_then(|name| name.to_str()) {
+            return Ok(name.to_string());
         }
 
-        Err(fdo::Error::InvalidFileContent(
-            "The active profile isn't set correctly".to_string(),
-        ))
+        Err(util::tailor_error(TailorError::invalid_profile(
+            "The active profile isn't set correctly",
+        )))
     }
 
     pub fn get_active_profile_info() -> fdo::Result<ProfileInfo> {
-        let data = std::fs::read(ACTIVE_PROFILE_PATH)
-            .map_err(|err| fdo::Error::IOError(err.to_string()))?;
-        serde_json::from_slice(&data).map_err(|err| fdo::Error::InvalidFileContent(err.to_string()))
+        // Profiles of users are read from the target of the link, which
+        // must be a file of the user and not another link.
+        if let Some(user) = Self::get_active_user_scope() {
+            let target = std::fs::read_link(&*ACTIVE_PROFILE_PATH)
+                .map_err(|err| fdo::Error::IOError(err.to_string()))?;
+            return user.read_profile_file(target);
+        }
+        util::read_profile_file(&*ACTIVE_PROFILE_PATH)
+    }
+
+    /// The user that the active profile belongs to, or `None` for system profiles.
+    pub fn get_active_user_scope() -> Option<UserScope> {
+        let link = std::fs::read_link(&*ACTIVE_PROFILE_PATH).ok()?;
+        // System profiles are linked with a relative path.
+        if link.is_absolute() && !link.starts_with(config::config_dir()) {
+            UserScope::of_profile_path(&link)
+        } else {
+            None
+        }
+    }
+}
+
+/// Replace the symlink at `path` with one to `target`. The new link is
+/// renamed over the old one, so `path` always points to a profile.
+fn replace_link(path: &Path, target: &Path) -> io::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".new");
+    let temp = Path::new(&temp);
+    match std::fs::remove_file(temp) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+        _ => {}
+    }
+    std::os::unix::fs::symlink(target, temp)?;
+    std::fs::rename(temp, path)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::replace_link;
+
+    #[test]
+    fn replaced_link() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let link = dir.join("active_profile.json");
+        std::os::unix::fs::symlink("profiles/performance.json", &link).unwrap();
+
+        replace_link(&link, Path::new("profiles/default.json")).unwrap();
+        assert_eq!(
+            std::fs::read_link(&link).unwrap(),
+            Path::new("profiles/default.json")
+        );
+        // A leftover of an interrupted replacement is replaced as well.
+        std::os::unix::fs::symlink("profiles/quiet.json", dir.join("active_profile.json.new"))
+            .unwrap();
+        replace_link(&link, Path::new("profiles/quiet.json")).unwrap();
+        assert_eq!(
+            std::fs::read_link(&link).unwrap(),
+            Path::new("profiles/quiet.json")
+        );
+        assert_eq!(std::fs::read_dir(dir).unwrap().count(), 1);
     }
 }
@@ -0,0 +1,131 @@
+//! A minimal mode that leaves the fans and the performance
+//! profile to the firmware and ignores all configurations.
+//! It's used if a broken configuration keeps crashing tailord.
+
+use std::path::{Path, PathBuf};
+
+use tuxedo_ioctl::hal::Capabilities;
+
+use crate::config;
+
+const SAFE_MODE_FLAG: &str = "--safe-mode";
+/// Stores the number of consecutive starts that didn't finish.
+const FAILED_STARTS: &str = "failed-starts";
+/// Start in safe mode after this many failed starts in a row.
+const MAX_FAILED_STARTS: u32 = 3;
+
+fn failed_starts_path() -> PathBuf {
+    config::state_dir().join(FAILED_STARTS)
+}
+
+/// Whether to start in safe mode, either because of
+/// the `--safe-mode` flag or repeated failed starts.
+/// Also returns the remaining arguments.
+pub fn init(args: Vec<String>) -> (bool, Vec<String>) {
+    init_at(&failed_starts_path(), args)
+}
+
+fn init_at(path: &Path, args: Vec<String>) -> (bool, Vec<String>) {
+    let (flags, remaining): (Vec<_>, Vec<_>) =
+        args.into_iter().partition(|arg| arg == SAFE_MODE_FLAG);
+
+    let failed_starts: u32 = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|data| data.trim().parse().ok())
+        .unwrap_or_default();
+    // Reset by `mark_started` once this start finished, so stops after
+    // a successful start, e.g. restarts and reboots, don't count.
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(path, (failed_starts + 1).to_string()));
+    if let Err(err) = result {
+        tracing::warn!("Failed to record the start in `{path:?}`: `{err}`");
+    }
+
+    if !flags.is_empty() {
+        tracing::warn!("Starting in safe mode");
+        (true, remaining)
+    } else if failed_starts >= MAX_FAILED_STARTS {
+        tracing::warn!(
+            "Tailord didn't start successfully {failed_starts} times in a row. Starting in safe mode"
+        );
+        (true, remaining)
+    } else {
+        (false, remaining)
+    }
+}
+
+/// Reset the failed starts once tailord finished starting.
+/// After a start in safe mode, the next start uses the
+/// configuration again.
+pub fn mark_started() {
+    mark_started_at(&failed_starts_path());
+}
+
+fn mark_started_at(path: &Path) {
+    match std::fs::remove_file(path) {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => tracing::warn!("Failed to reset the failed starts in `{path:?}`: `{err}`"),
+    }
+}
+
+/// Hand the fans back to the firmware and use
+/// the default performance profile.
+pub fn apply(capabilities: &Capabilities) {
+    if let Some(fans) = &capabilities.fans {
+        match fans.set_fans_auto() {
+            Ok(()) => tracing::info!("Safe mode: The firmware controls the fans"),
+            Err(err) => tracing::error!("Safe mode: Failed to set the fans to automatic: `{err}`"),
+        }
+    }
+
+    if let Some(profiles) = &capabilities.performance_profiles {
+        let result = profiles
+            .get_default_odm_performance_profile()
+            .and_then(|profile| {
+                profiles.set_odm_performance_profile(&profile)?;
+                Ok(profile)
+            });
+        match result {
+            Ok(profile) => tracing::info!("Safe mode: Using performance profile `{profile}`"),
+            Err(err) => {
+                tracing::error!("Safe mode: Failed to set the default performance profile: `{err}`")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{init_at, mark_started_at, MAX_FAILED_STARTS, SAFE_MODE_FLAG};
+
+    #[test]
+    fn failed_starts() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let path = dir.join("state").join("failed-starts");
+        let args = || vec!["--other".to_owned()];
+
+        // Starts that finished don't count, no matter how quickly the next start follows.
+        for _ in 0..MAX_FAILED_STARTS + 2 {
+            assert_eq!(init_at(&path, args()), (false, args()));
+            mark_started_at(&path);
+        }
+        assert!(!path.exists());
+
+        // Starts that never finished.
+        for _ in 0..MAX_FAILED_STARTS {
+            assert_eq!(init_at(&path, args()), (false, args()));
+        }
+        assert_eq!(init_at(&path, args()), (true, args()));
+        // The start in safe mode finished, the next start uses the configuration.
+        mark_started_at(&path);
+        assert_eq!(init_at(&path, args()), (false, args()));
+
+        let mut flagged = args();
+        flagged.push(SAFE_MODE_FLAG.to_owned());
+        assert_eq!(init_at(&path, flagged), (true, args()));
+    }
+}
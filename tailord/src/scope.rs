@@ -0,0 +1,289 @@
+//! Profiles of users that overlay the system profiles.
+//!
+//! Users can store profiles in `~/.config/tailor` with the same layout
+//! as the system configuration directory, without root permissions.
+//! Profiles of a user take precedence over system profiles with the same name
+//! when they are requested by that user. tailord never writes to these
+//! directories and only reads files that are owned by the user.
+
+use std::{
+    fs::File,
+    io::Read,
+    os::unix::fs::{MetadataExt, OpenOptionsExt},
+    path::{Path, PathBuf},
+};
+
+use nix::{
+    libc,
+    unistd::{Uid, User},
+};
+use serde::de::DeserializeOwned;
+use tailor_api::{ConfigFormat, ProfileOwner, TailorError};
+use zbus::{fdo, Connection, MessageHeader};
+
+use crate::{logind, util};
+
+/// Configuration directory of a user, relative to their home.
+pub const USER_CONFIG_DIR: &str = ".config/tailor";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserScope {
+    pub uid: u32,
+    pub config_dir: PathBuf,
+}
+
+impl UserScope {
+    pub fn of_uid(uid: u32) -> Option<Self> {
+        // Root uses the system profiles.
+        if uid == 0 {
+            return None;
+        }
+        let user = User::from_uid(Uid::from_raw(uid)).ok()??;
+        Some(Self {
+            uid,
+            config_dir: user.dir.join(USER_CONFIG_DIR),
+        })
+    }
+
+    /// The scope of the user that sent a D-Bus message.
+    pub async fn of_caller(
+        connection: &Connection,
+        header: &MessageHeader<'_>,
+    ) -> fdo::Result<Option<Self>> {
+        Ok(caller_uid(connection, header).await?.and_then(Self::of_uid))
+    }
+
+    /// The scope of a profile path below the configuration
+    /// directory of a user, e.g. `~/.config/tailor/profiles/quiet.json`.
+    pub fn of_profile_path(path: &Path) -> Option<Self> {
+        let config_dir = path.parent()?.parent()?.to_owned();
+        let uid = std::fs::metadata(path).ok()?.uid();
+        let this = Self::of_uid(uid)?;
+        (this.config_dir == config_dir).then_some(this)
+    }
+
+    /// The directory of a kind of profiles, e.g. `fan/`.
+    pub fn profile_dir(&self, sub_dir: &str) -> String {
+        self.config_dir.join(sub_dir).to_string_lossy().into_owned()
+    }
+
+    /// Open a file on behalf of the user. Only regular files of the user
+    /// may be read, otherwise symlinks could expose files of other users.
+    /// The owner is checked on the open file, so the file can't be replaced
+    /// between the check and reading it. A symlink at the end of the path
+    /// isn't followed and FIFOs don't block the open.
+    fn open(&self, path: &Path) -> Option<File> {
+        let file = File::options()
+            .read(true)
+            .custom_flags(libc::O_NOFOLLOW | libc::O_NONBLOCK)
+            .open(path)
+            .ok()?;
+        let metadata = file.metadata().ok()?;
+        (metadata.is_file() && metadata.uid() == self.uid).then_some(file)
+    }
+
+    pub fn owns(&self, path: impl AsRef<Path>) -> bool {
+        self.open(path.as_ref()).is_some()
+    }
+
+    /// Read a file of the user and the format given by its extension.
+    fn read(&self, path: &Path) -> fdo::Result<(String, ConfigFormat)> {
+        let mut file = self.open(path).ok_or_else(|| {
+            util::tailor_error(TailorError::permission_denied(
+                "The profile isn't a file of the user",
+            ))
+        })?;
+        let mut data = String::new();
+        file.read_to_string(&mut data)
+            .map_err(|err| fdo::Error::IOError(err.to_string()))?;
+        Ok((data, util::file_format(path)))
+    }
+
+    /// Read a profile file of the user. Errors only tell where the
+    /// file is invalid, since their messages can quote the content.
+    pub fn read_profile_file<T: DeserializeOwned>(&self, path: impl AsRef<Path>) -> fdo::Result<T> {
+        let path = path.as_ref();
+        let (data, format) = self.read(path)?;
+        format
+            .deserialize(&data)
+            .map_err(|err| util::format_error_without_content(path, &err))
+    }
+
+    /// Read a profile file of the user as JSON, regardless of its format.
+    pub fn read_profile_json(&self, path: impl AsRef<Path>) -> fdo::Result<String> {
+        let path = path.as_ref();
+        let (data, format) = self.read(path)?;
+        if format == ConfigFormat::Json {
+            Ok(data)
+        } else {
+            format
+                .convert(&data, ConfigFormat::Json)
+                .map_err(|err| util::format_error_without_content(path, &err))
+        }
+    }
+
+    /// The path of a profile of the user, if it exists and may be read.
+    pub fn find_profile(&self, sub_dir: &str, name: &str) -> Option<String> {
+        let (path, _) = util::find_profile(&self.profile_dir(sub_dir), name).ok()?;
+        self.owns(&path).then_some(path)
+    }
+}
+
+/// The uid of the user that sent a D-Bus message.
+pub async fn caller_uid(
+    connection: &Connection,
+    header: &MessageHeader<'_>,
+) -> fdo::Result<Option<u32>> {
+    let Some(sender) = header.sender()? else {
+        return Ok(None);
+    };
+    let uid = fdo::DBusProxy::new(connection)
+        .await?
+        .get_connection_unix_user(sender.to_owned().into())
+        .await?;
+    Ok(Some(uid))
+}
+
+/// Only allow root and users with an active local session, so remote
+/// users and system services can't take over the hardware.
+pub async fn require_active_user(
+    connection: &Connection,
+    header: &MessageHeader<'_>,
+    action: &str,
+) -> fdo::Result<()> {
+    let allowed = match caller_uid(connection, header).await? {
+        Some(0) => true,
+        Some(uid) => logind::has_active_session(connection, uid)
+            .await
+            .unwrap_or_else(|err| {
+                tracing::warn!("Failed to read the sessions of uid {uid}: `{err}`");
+                false
+            }),
+        None => false,
+    };
+    if allowed {
+        Ok(())
+    } else {
+        Err(util::tailor_error(TailorError::permission_denied(format!(
+            "Only root and users of an active local session can {action}"
+        ))))
+    }
+}
+
+/// The owner of a profile that was activated by the user with the given uid.
+pub fn profile_owner(uid: u32) -> ProfileOwner {
+    let user_name = User::from_uid(Uid::from_raw(uid))
+        .ok()
+        .flatten()
+        .map_or_else(|| uid.to_string(), |user| user.name);
+    ProfileOwner { uid, user_name }
+}
+
+/// The user and the path of a profile, if the user has a profile with that name.
+pub fn find_user_profile<'a>(
+    user: Option<&'a UserScope>,
+    sub_dir: &str,
+    name: &str,
+) -> Option<(&'a UserScope, String)> {
+    let user = user?;
+    Some((user, user.find_profile(sub_dir, name)?))
+}
+
+/// Read a profile, preferring the profiles of the user.
+pub fn read_profile<T: DeserializeOwned>(
+    user: Option<&UserScope>,
+    system_dir: &str,
+    sub_dir: &str,
+    name: &str,
+) -> fdo::Result<T> {
+    match find_user_profile(user, sub_dir, name) {
+        Some((user, path)) => user.read_profile_file(path),
+        None => util::read_profile_file(util::find_profile(system_dir, name)?.0),
+    }
+}
+
+/// Read a profile as JSON, preferring the profiles of the user.
+pub async fn read_profile_json(
+    user: Option<&UserScope>,
+    system_dir: &str,
+    sub_dir: &str,
+    name: &str,
+) -> fdo::Result<String> {
+    match find_user_profile(user, sub_dir, name) {
+        Some((user, path)) => user.read_profile_json(path),
+        None => util::read_file(system_dir, name).await,
+    }
+}
+
+/// Names of the system profiles along with the profiles of the user.
+pub async fn list_profiles(
+    user: Option<&UserScope>,
+    system_dir: &str,
+    sub_dir: &str,
+) -> fdo::Result<Vec<String>> {
+    let mut profiles = util::get_profiles(system_dir).await?;
+    if let Some(user) = user {
+        if let Ok(user_profiles) = util::get_profiles(&user.profile_dir(sub_dir)).await {
+            for name in user_profiles {
+                if !profiles.contains(&name) && user.find_profile(sub_dir, &name).is_some() {
+                    profiles.push(name);
+                }
+            }
+        }
+    }
+    Ok(profiles)
+}
+
+#[cfg(test)]
+mod test {
+    use nix::{sys::stat::Mode, unistd::Uid};
+    use serde_json::Value;
+
+    use super::UserScope;
+
+    #[test]
+    fn user_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("profiles")).unwrap();
+        let user = UserScope {
+            uid: Uid::current().as_raw(),
+            config_dir: dir.to_owned(),
+        };
+        assert_eq!(UserScope::of_uid(0), None);
+
+        let quiet = dir.join("profiles/quiet.toml");
+        std::fs::write(&quiet, "fans = [\"quiet\"]\n").unwrap();
+        assert!(user.owns(&quiet));
+        assert_eq!(
+            user.find_profile("profiles/", "quiet").as_deref(),
+            quiet.to_str()
+        );
+        let json: Value = serde_json::from_str(&user.read_profile_json(&quiet).unwrap()).unwrap();
+        assert_eq!(json, serde_json::json!({"fans": ["quiet"]}));
+
+        // Files of other users can't be read, not even through a symlink.
+        let other = UserScope {
+            uid: user.uid + 1,
+            config_dir: dir.to_owned(),
+        };
+        assert!(!other.owns(&quiet));
+        let link = dir.join("profiles/link.toml");
+        std::os::unix::fs::symlink(&quiet, &link).unwrap();
+        assert!(!user.owns(&link));
+        assert!(user.read_profile_file::<Value>(&link).is_err());
+        assert_eq!(user.find_profile("profiles/", "link"), None);
+        // Opening a FIFO doesn't block.
+        let fifo = dir.join("profiles/fifo.json");
+        nix::unistd::mkfifo(&fifo, Mode::S_IRWXU).unwrap();
+        assert!(!user.owns(&fifo));
+
+        // Errors don't quote the file.
+        let broken = dir.join("profiles/broken.json");
+        std::fs::write(&broken, "{\"fans\": \"secret\"}").unwrap();
+        let err = user
+            .read_profile_file::<tailor_api::ProfileInfo>(&broken)
+            .unwrap_err();
+        assert!(!err.to_string().contains("secret"), "{err}");
+    }
+}
@@ -0,0 +1,287 @@
+//! An optional seccomp filter that restricts tailord to the system calls
+//! it needs once it's started.
+//!
+//! Seccomp can't look at paths or tell file descriptors apart, so the
+//! systemd sandbox of the service limits which files can be accessed.
+//! Only x86_64 and aarch64 are supported.
+
+use std::io;
+
+use nix::libc::{self, c_long, sock_filter};
+
+/// Calls that aren't allowed fail with `EPERM` instead of killing the
+/// daemon, so a missing entry shows up as an error in the log.
+const DENIED: u32 = libc::SECCOMP_RET_ERRNO | libc::EPERM as u32;
+
+/// Offsets of the fields of `struct seccomp_data`.
+const SYSCALL_NR_OFFSET: u32 = 0;
+const ARCH_OFFSET: u32 = 4;
+
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: u32 = 0xc000_003e;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: u32 = 0xc000_00b7;
+
+/// File IO, D-Bus and the tokio and io_uring runtimes.
+const SYSCALLS: &[c_long] = &[
+    // Files and sysfs attributes.
+    libc::SYS_read,
+    libc::SYS_write,
+    libc::SYS_readv,
+    libc::SYS_writev,
+    libc::SYS_pread64,
+    libc::SYS_pwrite64,
+    libc::SYS_openat,
+    libc::SYS_close,
+    libc::SYS_fstat,
+    libc::SYS_newfstatat,
+    libc::SYS_statx,
+    libc::SYS_fstatfs,
+    libc::SYS_lseek,
+    libc::SYS_getdents64,
+    libc::SYS_readlinkat,
+    libc::SYS_faccessat,
+    libc::SYS_faccessat2,
+    libc::SYS_mkdirat,
+    libc::SYS_unlinkat,
+    libc::SYS_symlinkat,
+    libc::SYS_renameat,
+    libc::SYS_renameat2,
+    libc::SYS_ftruncate,
+    libc::SYS_fsync,
+    libc::SYS_fdatasync,
+    libc::SYS_fcntl,
+    libc::SYS_dup,
+    libc::SYS_dup3,
+    libc::SYS_pipe2,
+    libc::SYS_getcwd,
+    // Any `ioctl`, e.g. on `/dev/tuxedo_io` and the input devices.
+    libc::SYS_ioctl,
+    // The D-Bus socket and the OpenRGB server.
+    libc::SYS_socket,
+    libc::SYS_socketpair,
+    libc::SYS_connect,
+    libc::SYS_bind,
+    libc::SYS_listen,
+    libc::SYS_accept4,
+    libc::SYS_getsockname,
+    libc::SYS_getpeername,
+    libc::SYS_getsockopt,
+    libc::SYS_setsockopt,
+    libc::SYS_sendto,
+    libc::SYS_recvfrom,
+    libc::SYS_sendmsg,
+    libc::SYS_recvmsg,
+    libc::SYS_shutdown,
+    // Event loops of tokio and io_uring.
+    libc::SYS_ppoll,
+    libc::SYS_pselect6,
+    libc::SYS_epoll_create1,
+    libc::SYS_epoll_ctl,
+    libc::SYS_epoll_pwait,
+    libc::SYS_epoll_pwait2,
+    libc::SYS_eventfd2,
+    libc::SYS_timerfd_create,
+    libc::SYS_timerfd_settime,
+    libc::SYS_io_uring_setup,
+    libc::SYS_io_uring_enter,
+    libc::SYS_io_uring_register,
+    // Memory, threads and signals.
+    libc::SYS_brk,
+    libc::SYS_mmap,
+    libc::SYS_munmap,
+    libc::SYS_mremap,
+    libc::SYS_mprotect,
+    libc::SYS_madvise,
+    libc::SYS_futex,
+    libc::SYS_clone,
+    libc::SYS_clone3,
+    libc::SYS_set_robust_list,
+    libc::SYS_rseq,
+    libc::SYS_sched_yield,
+    libc::SYS_sched_getaffinity,
+    libc::SYS_rt_sigaction,
+    libc::SYS_rt_sigprocmask,
+    libc::SYS_rt_sigreturn,
+    libc::SYS_sigaltstack,
+    libc::SYS_tgkill,
+    libc::SYS_restart_syscall,
+    libc::SYS_exit,
+    libc::SYS_exit_group,
+    // Time and process information.
+    libc::SYS_clock_gettime,
+    libc::SYS_clock_nanosleep,
+    libc::SYS_nanosleep,
+    libc::SYS_gettimeofday,
+    libc::SYS_getrandom,
+    libc::SYS_getpid,
+    libc::SYS_gettid,
+    libc::SYS_getuid,
+    libc::SYS_geteuid,
+    libc::SYS_getgid,
+    libc::SYS_getegid,
+    libc::SYS_uname,
+    libc::SYS_sysinfo,
+    libc::SYS_prlimit64,
+    libc::SYS_prctl,
+];
+
+/// Calls that only exist on x86_64 and are still used by the standard library.
+#[cfg(target_arch = "x86_64")]
+const LEGACY_SYSCALLS: &[c_long] = &[
+    libc::SYS_open,
+    libc::SYS_stat,
+    libc::SYS_lstat,
+    libc::SYS_access,
+    libc::SYS_readlink,
+    libc::SYS_rename,
+    libc::SYS_mkdir,
+    libc::SYS_unlink,
+    libc::SYS_symlink,
+    libc::SYS_poll,
+    libc::SYS_epoll_wait,
+    libc::SYS_pipe,
+    libc::SYS_dup2,
+];
+#[cfg(not(target_arch = "x86_64"))]
+const LEGACY_SYSCALLS: &[c_long] = &[];
+
+/// Install the filter for all threads of the daemon. Programs can't be run
+/// afterwards, they would inherit the filter.
+pub fn apply() -> Result<(), io::Error> {
+    let syscalls: Vec<c_long> = SYSCALLS.iter().chain(LEGACY_SYSCALLS).copied().collect();
+    let filter = filter(&syscalls);
+    let program = libc::sock_fprog {
+        len: filter.len() as u16,
+        filter: filter.as_ptr() as *mut sock_filter,
+    };
+
+    // Required to install a filter without `CAP_SYS_ADMIN`.
+    // SAFETY: The arguments are plain integers.
+    if unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `program` points to `filter`, which outlives the call.
+    // The kernel copies the program.
+    let res = unsafe {
+        libc::syscall(
+            libc::SYS_seccomp,
+            libc::SECCOMP_SET_MODE_FILTER,
+            libc::SECCOMP_FILTER_FLAG_TSYNC,
+            &program as *const libc::sock_fprog,
+        )
+    };
+    match res {
+        0 => Ok(()),
+        -1 => Err(io::Error::last_os_error()),
+        // With `TSYNC`, the id of a thread that couldn't be synchronized.
+        thread => Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("Failed to apply the filter to thread {thread}"),
+        )),
+    }
+}
+
+/// A BPF program that allows `syscalls` and denies everything else.
+/// Calls of other architectures, e.g. the x32 ABI, kill the process.
+fn filter(syscalls: &[c_long]) -> Vec<sock_filter> {
+    let mut filter = vec![
+        statement(libc::BPF_LD | libc::BPF_W | libc::BPF_ABS, ARCH_OFFSET),
+        jump(
+            libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K,
+            AUDIT_ARCH,
+            1,
+            0,
+        ),
+        statement(libc::BPF_RET | libc::BPF_K, libc::SECCOMP_RET_KILL_PROCESS),
+        statement(
+            libc::BPF_LD | libc::BPF_W | libc::BPF_ABS,
+            SYSCALL_NR_OFFSET,
+        ),
+    ];
+    for syscall in syscalls {
+        filter.push(jump(
+            libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K,
+            *syscall as u32,
+            0,
+            1,
+        ));
+        filter.push(statement(
+            libc::BPF_RET | libc::BPF_K,
+            libc::SECCOMP_RET_ALLOW,
+        ));
+    }
+    filter.push(statement(libc::BPF_RET | libc::BPF_K, DENIED));
+    filter
+}
+
+fn statement(code: u32, k: u32) -> sock_filter {
+    jump(code, k, 0, 0)
+}
+
+fn jump(code: u32, k: u32, jt: u8, jf: u8) -> sock_filter {
+    sock_filter {
+        code: code as u16,
+        jt,
+        jf,
+        k,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nix::libc::{self, c_long};
+
+    use super::{LEGACY_SYSCALLS, SYSCALLS};
+
+    /// The calls behind the file operations of the daemon. Each of them
+    /// must be allowed, or the operation fails with `EPERM` under the filter.
+    const FILE_OPERATIONS: &[(&str, &[c_long])] = &[
+        // The link to the active profile.
+        ("symlink", &[libc::SYS_symlinkat]),
+        ("readlink", &[libc::SYS_readlinkat]),
+        // Files that are replaced atomically, e.g. the event log.
+        ("rename", &[libc::SYS_renameat, libc::SYS_renameat2]),
+        ("unlink", &[libc::SYS_unlinkat]),
+        ("mkdir", &[libc::SYS_mkdirat]),
+        ("read dir", &[libc::SYS_openat, libc::SYS_getdents64]),
+        ("metadata", &[libc::SYS_newfstatat, libc::SYS_statx]),
+        // The uevent socket of the LED hotplug.
+        (
+            "netlink socket",
+            &[libc::SYS_socket, libc::SYS_bind, libc::SYS_recvfrom],
+        ),
+    ];
+
+    #[cfg(target_arch = "x86_64")]
+    const LEGACY_FILE_OPERATIONS: &[(&str, &[c_long])] = &[
+        ("symlink", &[libc::SYS_symlink]),
+        ("readlink", &[libc::SYS_readlink]),
+        ("rename", &[libc::SYS_rename]),
+        ("unlink", &[libc::SYS_unlink]),
+        ("mkdir", &[libc::SYS_mkdir]),
+        ("metadata", &[libc::SYS_stat, libc::SYS_lstat]),
+    ];
+    #[cfg(not(target_arch = "x86_64"))]
+    const LEGACY_FILE_OPERATIONS: &[(&str, &[c_long])] = &[];
+
+    #[test]
+    fn file_operations() {
+        for (operation, syscalls) in FILE_OPERATIONS {
+            for syscall in *syscalls {
+                assert!(
+                    SYSCALLS.contains(syscall),
+                    "`{operation}` needs the system call {syscall}"
+                );
+            }
+        }
+        for (operation, syscalls) in LEGACY_FILE_OPERATIONS {
+            for syscall in *syscalls {
+                assert!(
+                    LEGACY_SYSCALLS.contains(syscall),
+                    "`{operation}` needs the legacy system call {syscall}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! Replays a recorded temperature trace through the fan engine
+//! and prints the resulting fan speeds, so changes to fan profiles
+//! or the control loop can be evaluated without real hardware.
+//!
+//! Usage: `tailord [--config-dir <dir>] simulate <fan profile> <trace>`
+//!
+//! The trace contains one `time_ms,temperature` sample per line.
+//! Empty lines and lines starting with `#` are ignored.
+//! The output is a CSV timeline of `time_ms,temp,target_fan_speed,fan_speed`.
+
+use std::{io, path::Path, time::Duration};
+
+use crate::{
+    config::DaemonConfig,
+    fancontrol::{engine::FanEngine, profile::FanProfile},
+};
+
+/// A single sample of a temperature trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceSample {
+    pub time: Duration,
+    pub temp: u8,
+}
+
+/// A single iteration of the simulated fan control loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimulationStep {
+    pub time: Duration,
+    pub temp: u8,
+    pub target_fan_speed: u8,
+    pub fan_speed: u8,
+}
+
+/// Run the simulation if the daemon was started with the `simulate` subcommand.
+/// Returns `false` if the daemon should start normally.
+pub fn run_from_args(args: &[String]) -> bool {
+    let mut args = args.iter();
+    if args.next().map(String::as_str) != Some("simulate") {
+        return false;
+    }
+
+    let (Some(profile_path), Some(trace_path)) = (args.next(), args.next()) else {
+        eprintln!("Usage: tailord simulate <fan profile> <trace>");
+        std::process::exit(2);
+    };
+
+    if let Err(err) = run(Path::new(&profile_path), Path::new(&trace_path)) {
+        eprintln!("Simulation failed: {err}");
+        std::process::exit(1);
+    }
+    true
+}
+
+fn run(profile_path: &Path, trace_path: &Path) -> Result<(), io::Error> {
+    let profile = FanProfile::load_config(profile_path)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+    let trace = parse_trace(&std::fs::read_to_string(trace_path)?)?;
+    let config = DaemonConfig::load();
+
+    println!("time_ms,temp,target_fan_speed,fan_speed");
+    for step in simulate(profile, config, &trace) {
+        println!(
+            "{},{},{},{}",
+            step.time.as_millis(),
+            step.temp,
+            step.target_fan_speed,
+            step.fan_speed
+        );
+    }
+    Ok(())
+}
+
+pub fn parse_trace(content: &str) -> Result<Vec<TraceSample>, io::Error> {
+    let invalid_line = |idx: usize| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid trace sample in line {}", idx + 1),
+        )
+    };
+
+    let mut samples: Vec<TraceSample> = Vec::new();
+    for (idx, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (time, temp) = line.split_once(',').ok_or_else(|| invalid_line(idx))?;
+        let time: u64 = time.trim().parse().map_err(|_| invalid_line(idx))?;
+        let temp: u8 = temp.trim().parse().map_err(|_| invalid_line(idx))?;
+        let time = Duration::from_millis(time);
+
+        if samples.last().is_some_and(|sample| sample.time > time) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Time isn't increasing in line {}", idx + 1),
+            ));
+        }
+        samples.push(TraceSample { time, temp });
+    }
+    Ok(samples)
+}
+
+/// Feed the trace into the fan engine. At each iteration, the engine
+/// sees the latest sample that was recorded before the current time.
+/// Jitter is ignored to keep the results deterministic.
+pub fn simulate(
+    profile: FanProfile,
+    config: DaemonConfig,
+    trace: &[TraceSample],
+) -> Vec<SimulationStep> {
+    let (Some(first), Some(last)) = (trace.first(), trace.last()) else {
+        return Vec::new();
+    };
+
+    let mut engine = FanEngine::new(first.temp, 0, profile, config.fan_control);
+    let mut steps = Vec::new();
+    let mut time = first.time;
+    let mut sample_idx = 0;
+
+    while time <= last.time {
+        while trace
+            .get(sample_idx + 1)
+            .is_some_and(|sample| sample.time <= time)
+        {
+            sample_idx += 1;
+        }
+
+        let step = engine.step(Some(trace[sample_idx].temp));
+        steps.push(SimulationStep {
+            time,
+            temp: step.temp,
+            target_fan_speed: step.target_fan_speed,
+            fan_speed: step.fan_speed,
+        });
+        time += step.delay;
+    }
+    steps
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{config::DaemonConfig, fancontrol::profile::FanProfile};
+
+    use super::{parse_trace, simulate};
+
+    #[test]
+    fn replay_trace() {
+        let trace = parse_trace("# time_ms,temp\n0,40\n10000,80\n\n30000,80\n").unwrap();
+        assert_eq!(trace.len(), 3);
+
+        let steps = simulate(FanProfile::default(), DaemonConfig::default(), &trace);
+        assert_eq!(
+            steps,
+            simulate(FanProfile::default(), DaemonConfig::default(), &trace)
+        );
+        assert_eq!(steps[0].temp, 40);
+
+        // The fan speed should approach the target of the curve.
+        let last = steps.last().unwrap();
+        assert_eq!(last.temp, 80);
+        assert_eq!(last.target_fan_speed, 75);
+        assert!(last.fan_speed.abs_diff(75) <= 5);
+
+        parse_trace("0,40\n-1,40").unwrap_err();
+        parse_trace("100,40\n0,40").unwrap_err();
+    }
+}
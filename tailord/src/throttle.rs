@@ -0,0 +1,166 @@
+//! Detection of thermal throttling of the CPU.
+//! If the CPU keeps throttling while all fans already run at full speed,
+//! the cooling can't keep up, e.g. because of dried out thermal paste.
+
+use std::{fs, io, path::Path, time::Duration};
+
+use tailor_api::{EventKind, FanStatus, ThrottleStatus};
+use tokio::sync::watch;
+use zbus::Connection;
+
+use crate::{config::ThrottleConfig, dbus::FanInterface, events, DBUS_PATH};
+
+const SYSFS_CPU_PATH: &str = "/sys/devices/system/cpu";
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Periodically reads the throttle counters of the CPU.
+pub struct ThrottleWatcher {
+    config: ThrottleConfig,
+    fan_status: Vec<watch::Receiver<FanStatus>>,
+    status_sender: watch::Sender<ThrottleStatus>,
+}
+
+impl ThrottleWatcher {
+    pub fn new(
+        config: ThrottleConfig,
+        fan_status: Vec<watch::Receiver<FanStatus>>,
+    ) -> (Self, watch::Receiver<ThrottleStatus>) {
+        let (status_sender, status_receiver) = watch::channel(ThrottleStatus::default());
+        (
+            Self {
+                config,
+                fan_status,
+                status_sender,
+            },
+            status_receiver,
+        )
+    }
+
+    pub async fn run(self, connection: Connection) {
+        let mut last_count = match read_throttle_count(Path::new(SYSFS_CPU_PATH)) {
+            Ok(Some(count)) => count,
+            Ok(None) => {
+                tracing::info!("No CPU throttle counters available");
+                return;
+            }
+            Err(err) => {
+                tracing::warn!("Failed to read CPU throttle counters: `{err}`");
+                return;
+            }
+        };
+
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+
+            let count = match read_throttle_count(Path::new(SYSFS_CPU_PATH)) {
+                Ok(Some(count)) => count,
+                Ok(None) => continue,
+                Err(err) => {
+                    tracing::warn!("Failed to read CPU throttle counters: `{err}`");
+                    continue;
+                }
+            };
+
+            let status = ThrottleStatus {
+                throttling: count > last_count,
+                fans_at_max: !self.fan_status.is_empty()
+                    && self
+                        .fan_status
+                        .iter()
+                        .all(|status| status.borrow().fan_speed >= 100),
+                throttle_count: count,
+            };
+            last_count = count;
+
+            let was_critical = self.status_sender.borrow().is_critical();
+            let is_critical = status.is_critical();
+            self.status_sender.send_replace(status);
+
+            if is_critical != was_critical {
+                if is_critical {
+                    tracing::warn!("The CPU is thermally throttling although all fans run at 100%");
+                }
+                events::record(EventKind::ThermalThrottling {
+                    active: is_critical,
+                });
+                if self.config.notify {
+                    if let Err(err) = notify(&connection, is_critical).await {
+                        tracing::error!("Failed to emit thermal throttling signal: `{err}`");
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn notify(connection: &Connection, active: bool) -> zbus::Result<()> {
+    let iface = connection
+        .object_server()
+        .interface::<_, FanInterface>(DBUS_PATH)
+        .await?;
+    FanInterface::thermal_throttling(iface.signal_context(), active).await
+}
+
+/// Sum of the core and package throttle counters of all CPUs,
+/// or `None` if the kernel doesn't expose them.
+fn read_throttle_count(cpu_path: &Path) -> Result<Option<u64>, io::Error> {
+    let mut total = None;
+    for dir in fs::read_dir(cpu_path)? {
+        let dir = dir?;
+        let is_cpu = dir.file_name().to_str().is_some_and(|name| {
+            name.strip_prefix("cpu")
+                .is_some_and(|id| !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()))
+        });
+        if !is_cpu {
+            continue;
+        }
+
+        let throttle_path = dir.path().join("thermal_throttle");
+        for counter in ["core_throttle_count", "package_throttle_count"] {
+            match fs::read_to_string(throttle_path.join(counter)) {
+                Ok(value) => {
+                    let value: u64 = value
+                        .trim()
+                        .parse()
+                        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                    *total.get_or_insert(0) += value;
+                }
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err),
+            }
+        }
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod test {
+    use super::read_throttle_count;
+
+    #[test]
+    fn throttle_count() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        // Not a CPU directory, but contains counters.
+        std::fs::create_dir_all(dir.join("cpufreq/thermal_throttle")).unwrap();
+        std::fs::write(
+            dir.join("cpufreq/thermal_throttle/core_throttle_count"),
+            "7\n",
+        )
+        .unwrap();
+        assert_eq!(read_throttle_count(dir).unwrap(), None);
+
+        for (cpu, core, package) in [("cpu0", "3\n", "10\n"), ("cpu1", "2\n", "10\n")] {
+            let throttle = dir.join(cpu).join("thermal_throttle");
+            std::fs::create_dir_all(&throttle).unwrap();
+            std::fs::write(throttle.join("core_throttle_count"), core).unwrap();
+            std::fs::write(throttle.join("package_throttle_count"), package).unwrap();
+        }
+        // Offline CPUs have no counters.
+        std::fs::create_dir_all(dir.join("cpu2")).unwrap();
+        assert_eq!(read_throttle_count(dir).unwrap(), Some(25));
+
+        std::fs::write(dir.join("cpu1/thermal_throttle/core_throttle_count"), "x\n").unwrap();
+        assert!(read_throttle_count(dir).is_err());
+    }
+}
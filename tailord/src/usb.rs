@@ -0,0 +1,176 @@
+//! Runtime power management of USB devices via the `power` attributes in sysfs.
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use tailor_api::{HardwareWrite, UsbAutosuspend};
+use tuxedo_sysfs::attribute::planned_write;
+
+const USB_DEVICES_PATH: &str = "/sys/bus/usb/devices";
+/// Hotplugged devices get the policy of the active profile after at most this long.
+const HOTPLUG_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The `power/control` and `power/autosuspend_delay_ms` values of a device
+/// before a profile changed them.
+#[derive(Debug, Clone)]
+struct OriginalPower {
+    control: String,
+    delay_ms: Option<String>,
+}
+
+#[derive(Debug)]
+struct State {
+    policy: Option<UsbAutosuspend>,
+    /// The original settings of every device that a profile changed,
+    /// so they can be restored when switching to a profile without policy.
+    original: BTreeMap<PathBuf, OriginalPower>,
+}
+
+static STATE: Mutex<State> = Mutex::new(State {
+    policy: None,
+    original: BTreeMap::new(),
+});
+
+/// Apply the autosuspend policy of a profile to all USB devices.
+/// `None` restores the settings the devices had before.
+pub fn apply_autosuspend(policy: Option<&UsbAutosuspend>) -> Result<(), io::Error> {
+    let mut state = STATE.lock().unwrap();
+    state.policy = policy.cloned();
+    apply(&mut state)?;
+    match policy {
+        Some(policy) if policy.enabled => tracing::info!("Enabled USB autosuspend"),
+        Some(_) => tracing::info!("Disabled USB autosuspend"),
+        None => {}
+    }
+    Ok(())
+}
+
+/// Apply the policy of the active profile to hotplugged devices.
+pub async fn watch_usb_devices() {
+    loop {
+        tokio::time::sleep(HOTPLUG_POLL_INTERVAL).await;
+        let mut state = STATE.lock().unwrap();
+        if state.policy.is_none() {
+            continue;
+        }
+        if let Err(err) = apply(&mut state) {
+            tracing::warn!("Failed to apply USB autosuspend policy: `{err}`");
+        }
+    }
+}
+
+fn apply(state: &mut State) -> Result<(), io::Error> {
+    let Some(policy) = state.policy.clone() else {
+        for (dir, original) in std::mem::take(&mut state.original) {
+            // Unplugged devices can't be restored.
+            if let Err(err) = restore(&dir, &original) {
+                tracing::debug!("Failed to restore power settings of {dir:?}: `{err}`");
+            }
+        }
+        return Ok(());
+    };
+
+    // Unplugged devices are saved again when they're plugged in.
+    state.original.retain(|dir, _| dir.exists());
+    for dir in devices()? {
+        // A failing device must not keep the others at the old policy.
+        if let Err(err) = apply_device(state, &dir, &policy) {
+            tracing::warn!("Failed to apply USB autosuspend policy to {dir:?}: `{err}`");
+        }
+    }
+    Ok(())
+}
+
+fn apply_device(state: &mut State, dir: &Path, policy: &UsbAutosuspend) -> Result<(), io::Error> {
+    let (Ok(vendor), Ok(product)) = (read(dir, "idVendor"), read(dir, "idProduct")) else {
+        return Ok(());
+    };
+    if !state.original.contains_key(dir) {
+        let original = OriginalPower {
+            control: read(dir, "power/control")?,
+            delay_ms: read(dir, "power/autosuspend_delay_ms").ok(),
+        };
+        state.original.insert(dir.to_owned(), original);
+    }
+
+    let control = control(policy, &vendor, &product);
+    if let Some(delay_ms) = policy.delay_ms {
+        fs::write(dir.join("power/autosuspend_delay_ms"), delay_ms.to_string())?;
+    }
+    if read(dir, "power/control")? != control {
+        fs::write(dir.join("power/control"), control)?;
+    }
+    Ok(())
+}
+
+/// The writes of [`apply_autosuspend`] that change a value, without applying them.
+pub fn preview_autosuspend(
+    policy: Option<&UsbAutosuspend>,
+) -> Result<Vec<HardwareWrite>, io::Error> {
+    let state = STATE.lock().unwrap();
+    let mut writes = Vec::new();
+    let Some(policy) = policy else {
+        for (dir, original) in &state.original {
+            if let Some(delay_ms) = &original.delay_ms {
+                writes.extend(planned_write(dir, "power/autosuspend_delay_ms", delay_ms));
+            }
+            writes.extend(planned_write(dir, "power/control", &original.control));
+        }
+        return Ok(writes);
+    };
+
+    for dir in devices()? {
+        let (Ok(vendor), Ok(product)) = (read(&dir, "idVendor"), read(&dir, "idProduct")) else {
+            continue;
+        };
+        if let Some(delay_ms) = policy.delay_ms {
+            writes.extend(planned_write(&dir, "power/autosuspend_delay_ms", delay_ms));
+        }
+        let control = control(policy, &vendor, &product);
+        writes.extend(planned_write(&dir, "power/control", control));
+    }
+    Ok(writes)
+}
+
+/// The `power/control` value of a device under a policy.
+fn control(policy: &UsbAutosuspend, vendor: &str, product: &str) -> &'static str {
+    if policy.allows(vendor, product) {
+        "auto"
+    } else {
+        "on"
+    }
+}
+
+fn restore(dir: &Path, original: &OriginalPower) -> Result<(), io::Error> {
+    if let Some(delay_ms) = &original.delay_ms {
+        fs::write(dir.join("power/autosuspend_delay_ms"), delay_ms)?;
+    }
+    fs::write(dir.join("power/control"), &original.control)
+}
+
+fn devices() -> Result<Vec<PathBuf>, io::Error> {
+    let dirs = match fs::read_dir(USB_DEVICES_PATH) {
+        Ok(dirs) => dirs,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+
+    let mut devices = Vec::new();
+    for dir in dirs {
+        let dir = dir?;
+        // Interfaces like `1-1:1.0` have no power settings of their own.
+        if !dir.file_name().to_string_lossy().contains(':') {
+            devices.push(dir.path());
+        }
+    }
+    Ok(devices)
+}
+
+fn read(dir: &Path, name: &str) -> Result<String, io::Error> {
+    Ok(fs::read_to_string(dir.join(name))?.trim().to_owned())
+}
@@ -1,78 +1,246 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
 use serde::{de::DeserializeOwned, Serialize};
+use tailor_api::{ConfigFormat, FormatError, TailorError};
+use tokio::sync::oneshot;
+use tuxedo_ioctl::hal::IoctlError;
 use zbus::fdo;
 
-pub fn normalize_json_path(base_path: &str, name: &str) -> fdo::Result<String> {
+use crate::config;
+
+/// A new profile counts as applied if a runtime didn't write it
+/// within this time, e.g. because the EC is inhibited.
+const FIRST_WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Receives the result of the first write of a runtime after a new profile.
+pub type WriteAck = oneshot::Sender<Result<(), String>>;
+
+/// Wait for the result of the first write of a runtime after a new profile.
+pub async fn first_write(receiver: oneshot::Receiver<Result<(), String>>) -> Result<(), String> {
+    match tokio::time::timeout(FIRST_WRITE_TIMEOUT, receiver).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(_)) => Err("The runtime stopped before writing the profile".to_owned()),
+        Err(_) => Ok(()),
+    }
+}
+
+pub fn normalize_path(base_path: &str, name: &str, format: ConfigFormat) -> fdo::Result<String> {
+    let extension = format.extension();
     // Make sure the name doesn't contain any illegal characters.
     if name.contains('/') {
-        Err(fdo::Error::InvalidArgs(format!(
+        Err(tailor_error(TailorError::invalid_profile(format!(
             "Can't use '/' in profile names: `{name}`"
-        )))
+        ))))
     } else if name.contains('.') {
-        Err(fdo::Error::InvalidArgs(format!(
+        Err(tailor_error(TailorError::invalid_profile(format!(
             "Can't use '.' in profile names: `{name}`"
-        )))
+        ))))
     } else if base_path.is_empty() {
-        Ok(format!("{name}.json"))
+        Ok(format!("{name}.{extension}"))
     } else {
         let base_path = base_path.trim().trim_end_matches('/');
-        Ok(format!("{base_path}/{name}.json"))
+        Ok(format!("{base_path}/{name}.{extension}"))
+    }
+}
+
+/// Find the path and format of an existing profile.
+/// Profiles that don't exist yet are stored as JSON.
+pub fn find_profile(base_path: &str, name: &str) -> fdo::Result<(String, ConfigFormat)> {
+    for format in ConfigFormat::ALL {
+        let path = normalize_path(base_path, name, format)?;
+        if Path::new(&path).exists() {
+            return Ok((path, format));
+        }
+    }
+    Ok((
+        normalize_path(base_path, name, ConfigFormat::Json)?,
+        ConfigFormat::Json,
+    ))
+}
+
+/// The format of a profile file, following symlinks.
+pub fn file_format(path: impl AsRef<Path>) -> ConfigFormat {
+    let path = path.as_ref();
+    std::fs::read_link(path)
+        .ok()
+        .and_then(ConfigFormat::from_path)
+        .or_else(|| ConfigFormat::from_path(path))
+        .unwrap_or(ConfigFormat::Json)
+}
+
+/// Send a [`TailorError`] over D-Bus. The name of the D-Bus error
+/// matches the kind, for clients that don't parse the message.
+pub fn tailor_error(err: TailorError) -> fdo::Error {
+    let message = err.to_json();
+    match err {
+        TailorError::NotSupported { .. } => fdo::Error::NotSupported(message),
+        TailorError::InvalidProfile { .. } => fdo::Error::InvalidArgs(message),
+        TailorError::HardwareError { .. } => fdo::Error::IOError(message),
+        TailorError::PermissionDenied { .. } => fdo::Error::AccessDenied(message),
+        TailorError::AlreadyExists { .. } => fdo::Error::FileExists(message),
+        TailorError::PartiallyApplied { .. } => fdo::Error::Failed(message),
     }
 }
 
+/// Report a failed hardware access, including the error code of the kernel.
+pub fn hardware_error(context: &str, err: IoctlError) -> fdo::Error {
+    let errno = match &err {
+        IoctlError::DevNotAvailable | IoctlError::NotAvailable => {
+            return tailor_error(TailorError::not_supported(format!("{context}: {err}")));
+        }
+        IoctlError::Read(errno) => Some(*errno as i32),
+        IoctlError::IO(err) => err.raw_os_error(),
+        IoctlError::Utf8(_) | IoctlError::InvalidArgs => None,
+    };
+    tailor_error(TailorError::hardware_error(
+        errno,
+        format!("{context}: {err}"),
+    ))
+}
+
+/// The name of a profile file in messages. Files in the configuration
+/// directory are named relative to it, others by their directory and file
+/// name, so the messages don't tell bus clients where the files are.
+fn display_name(path: &Path) -> String {
+    if let Ok(relative) = path.strip_prefix(config::config_dir()) {
+        return relative.display().to_string();
+    }
+    let components: Vec<_> = path.components().rev().take(2).collect();
+    components
+        .into_iter()
+        .rev()
+        .collect::<PathBuf>()
+        .display()
+        .to_string()
+}
+
+/// An error of reading a file, with the location of the problem.
+fn format_error(path: impl AsRef<Path>, err: FormatError) -> fdo::Error {
+    let name = display_name(path.as_ref());
+    tailor_error(TailorError::invalid_profile(format!("`{name}`: {err}")))
+}
+
+/// An error of reading a file on behalf of a user that only tells
+/// where the file is invalid, since the messages can quote the content.
+pub fn format_error_without_content(path: impl AsRef<Path>, err: &FormatError) -> fdo::Error {
+    let name = display_name(path.as_ref());
+    let message = match (err.line(), err.column()) {
+        (Some(line), Some(column)) => {
+            format!("`{name}`: Invalid profile at line {line} column {column}")
+        }
+        _ => format!("`{name}`: Invalid profile"),
+    };
+    tailor_error(TailorError::invalid_profile(message))
+}
+
+/// Parse JSON that a client sent, e.g. a profile. Like errors of profile
+/// files, the error tells which field is invalid, and it's logged.
+pub fn parse_json<T: DeserializeOwned>(name: &str, value: &str) -> fdo::Result<T> {
+    ConfigFormat::Json.deserialize(value).map_err(|err| {
+        tracing::warn!("Rejected invalid `{name}` from a client: {err}");
+        tailor_error(TailorError::invalid_profile(format!("`{name}`: {err}")))
+    })
+}
+
+/// Write JSON data to a profile. The data is converted
+/// if the profile already exists in a different format.
 pub async fn write_file(base_path: &str, name: &str, data: &[u8]) -> Result<(), fdo::Error> {
-    tokio::fs::write(normalize_json_path(base_path, name)?, data)
+    let (path, format) = find_profile(base_path, name)?;
+    let data = if format == ConfigFormat::Json {
+        data.to_vec()
+    } else {
+        let data =
+            std::str::from_utf8(data).map_err(|err| fdo::Error::InvalidArgs(err.to_string()))?;
+        ConfigFormat::Json
+            .convert(data, format)
+            .map_err(|err| fdo::Error::InvalidArgs(err.to_string()))?
+            .into_bytes()
+    };
+    tokio::fs::write(path, data)
         .await
         .map_err(|err| fdo::Error::IOError(err.to_string()))
 }
 
-pub async fn write_json<T: Serialize>(
+pub async fn write_profile<T: Serialize>(
     base_path: &str,
     name: &str,
     data: &T,
 ) -> Result<(), fdo::Error> {
-    write_file(base_path, name, to_pretty_json(data)?.as_bytes()).await
+    let (path, format) = find_profile(base_path, name)?;
+    tokio::fs::write(path, serialize(format, data)?)
+        .await
+        .map_err(|err| fdo::Error::IOError(err.to_string()))
 }
 
-pub fn write_json_sync<T: Serialize>(
+pub fn write_profile_sync<T: Serialize>(
     base_path: &str,
     name: &str,
     data: &T,
 ) -> Result<(), fdo::Error> {
-    std::fs::write(
-        normalize_json_path(base_path, name)?,
-        to_pretty_json(data)?.as_bytes(),
-    )
-    .map_err(|err| fdo::Error::IOError(err.to_string()))
+    let (path, format) = find_profile(base_path, name)?;
+    std::fs::write(path, serialize(format, data)?)
+        .map_err(|err| fdo::Error::IOError(err.to_string()))
 }
 
-fn to_pretty_json<T: Serialize>(data: &T) -> Result<String, fdo::Error> {
-    serde_json::to_string_pretty(data).map_err(|err| fdo::Error::Failed(err.to_string()))
+fn serialize<T: Serialize>(format: ConfigFormat, data: &T) -> Result<String, fdo::Error> {
+    format
+        .serialize(data)
+        .map_err(|err| fdo::Error::Failed(err.to_string()))
 }
 
+/// Read a profile as JSON, regardless of the format it's stored in.
 pub async fn read_file(base_path: &str, name: &str) -> Result<String, fdo::Error> {
-    tokio::fs::read_to_string(normalize_json_path(base_path, name)?)
+    let (path, format) = find_profile(base_path, name)?;
+    let data = tokio::fs::read_to_string(&path)
         .await
-        .map_err(|err| fdo::Error::IOError(err.to_string()))
+        .map_err(|err| fdo::Error::IOError(err.to_string()))?;
+    if format == ConfigFormat::Json {
+        Ok(data)
+    } else {
+        format
+            .convert(&data, ConfigFormat::Json)
+            .map_err(|err| format_error(&path, err))
+    }
+}
+
+pub async fn read_profile<T: DeserializeOwned>(
+    base_path: &str,
+    name: &str,
+) -> Result<T, fdo::Error> {
+    let (path, format) = find_profile(base_path, name)?;
+    let data = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|err| fdo::Error::IOError(err.to_string()))?;
+    format
+        .deserialize(&data)
+        .map_err(|err| format_error(&path, err))
 }
 
-pub async fn read_json<T: DeserializeOwned>(base_path: &str, name: &str) -> Result<T, fdo::Error> {
-    let data = read_file(base_path, name).await?;
-    serde_json::from_str(&data).map_err(|err| fdo::Error::Failed(err.to_string()))
+/// Read a profile file in the format given by its extension.
+pub fn read_profile_file<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T, fdo::Error> {
+    let path = path.as_ref();
+    let data = std::fs::read_to_string(path).map_err(|err| fdo::Error::IOError(err.to_string()))?;
+    file_format(path)
+        .deserialize(&data)
+        .map_err(|err| format_error(path, err))
 }
 
 pub async fn remove_file(base_path: &str, name: &str) -> Result<(), fdo::Error> {
-    tokio::fs::remove_file(normalize_json_path(base_path, name)?)
+    let (path, _) = find_profile(base_path, name)?;
+    tokio::fs::remove_file(path)
         .await
         .map_err(|err| fdo::Error::IOError(err.to_string()))
 }
 
+/// Rename a profile, keeping its format.
 pub async fn move_file(base_path: &str, from: &str, to: &str) -> Result<(), fdo::Error> {
-    tokio::fs::rename(
-        normalize_json_path(base_path, from)?,
-        normalize_json_path(base_path, to)?,
-    )
-    .await
-    .map_err(|err| fdo::Error::IOError(err.to_string()))
+    let (from_path, format) = find_profile(base_path, from)?;
+    tokio::fs::rename(from_path, normalize_path(base_path, to, format)?)
+        .await
+        .map_err(|err| fdo::Error::IOError(err.to_string()))
 }
 
 pub async fn get_profiles(base_path: &str) -> fdo::Result<Vec<String>> {
@@ -88,17 +256,21 @@ pub async fn get_profiles(base_path: &str) -> fdo::Result<Vec<String>> {
             .map(|f| f.is_file())
             .unwrap_or_default()
         {
-            match entry.file_name().into_string() {
-                Ok(file_name) => {
-                    if file_name.contains(".json") {
-                        if !file_name.contains("active_profile") {
-                            entries.push(file_name.replace(".json", ""))
+            let path = entry.path();
+            match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(name) => {
+                    if ConfigFormat::from_path(&path).is_some() {
+                        if !name.contains("active_profile") && !entries.iter().any(|n| n == name) {
+                            entries.push(name.to_owned())
                         }
                     } else {
-                        tracing::warn!("Unknown file type (expected JSON): `{:?}`", entry.path());
+                        tracing::warn!(
+                            "Unknown file type (expected JSON or TOML): `{:?}`",
+                            entry.path()
+                        );
                     }
                 }
-                Err(_) => {
+                None => {
                     tracing::warn!("Couldn't convert file name to UTF8: `{:?}`", entry.path());
                 }
             }
@@ -107,3 +279,118 @@ pub async fn get_profiles(base_path: &str) -> fdo::Result<Vec<String>> {
 
     Ok(entries)
 }
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use serde_json::{json, Value};
+    use tailor_api::{ConfigFormat, FanProfileInfo, TailorError};
+    use zbus::fdo;
+
+    use super::{
+        display_name, file_format, find_profile, get_profiles, move_file, normalize_path,
+        parse_json, read_file, read_profile, read_profile_file, remove_file, write_file,
+        write_profile,
+    };
+    use crate::config;
+
+    #[test]
+    fn profile_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let base = dir.to_str().unwrap().to_owned();
+
+        assert_eq!(
+            normalize_path("/etc/tailord/fan/ ", "quiet", ConfigFormat::Toml).unwrap(),
+            "/etc/tailord/fan/quiet.toml"
+        );
+        assert!(matches!(
+            normalize_path(&base, "../quiet", ConfigFormat::Json),
+            Err(fdo::Error::InvalidArgs(_))
+        ));
+        assert!(normalize_path(&base, "quiet.json", ConfigFormat::Json).is_err());
+
+        // New profiles are stored as JSON, existing ones keep their format.
+        let (path, format) = find_profile(&base, "quiet").unwrap();
+        assert_eq!(
+            (path, format),
+            (format!("{base}/quiet.json"), ConfigFormat::Json)
+        );
+        std::fs::write(dir.join("quiet.toml"), "speed = 20\n").unwrap();
+        assert_eq!(find_profile(&base, "quiet").unwrap().1, ConfigFormat::Toml);
+
+        tokio_uring::start(async {
+            write_file(&base, "quiet", br#"{"speed": 30}"#)
+                .await
+                .unwrap();
+            assert_eq!(
+                std::fs::read_to_string(dir.join("quiet.toml"))
+                    .unwrap()
+                    .trim(),
+                "speed = 30"
+            );
+            assert_eq!(
+                serde_json::from_str::<Value>(&read_file(&base, "quiet").await.unwrap()).unwrap(),
+                json!({"speed": 30})
+            );
+
+            write_profile(&base, "loud", &json!({"speed": 90}))
+                .await
+                .unwrap();
+            assert!(dir.join("loud.json").exists());
+            let loud: Value = read_profile(&base, "loud").await.unwrap();
+            assert_eq!(loud, json!({"speed": 90}));
+
+            let mut profiles = get_profiles(&base).await.unwrap();
+            profiles.sort();
+            assert_eq!(profiles, ["loud", "quiet"]);
+
+            move_file(&base, "quiet", "silent").await.unwrap();
+            assert!(dir.join("silent.toml").exists());
+            remove_file(&base, "loud").await.unwrap();
+            assert_eq!(get_profiles(&base).await.unwrap(), ["silent"]);
+        });
+
+        // The format of a link is the format of its target.
+        std::os::unix::fs::symlink(dir.join("silent.toml"), dir.join("active_profile")).unwrap();
+        assert_eq!(file_format(dir.join("active_profile")), ConfigFormat::Toml);
+        let active: Value = read_profile_file(dir.join("active_profile")).unwrap();
+        assert_eq!(active, json!({"speed": 30}));
+
+        std::fs::write(dir.join("broken.json"), "{\"speed\": ").unwrap();
+        assert!(matches!(
+            read_profile_file::<Value>(dir.join("broken.json")),
+            Err(fdo::Error::InvalidArgs(_))
+        ));
+        assert!(matches!(
+            read_profile_file::<Value>(dir.join("missing.json")),
+            Err(fdo::Error::IOError(_))
+        ));
+    }
+
+    #[test]
+    fn error_messages() {
+        let message = |err: fdo::Error| match err {
+            fdo::Error::InvalidArgs(message) => TailorError::from_json(&message)
+                .unwrap()
+                .message()
+                .to_owned(),
+            err => panic!("Unexpected error {err:?}"),
+        };
+        let err =
+            parse_json::<FanProfileInfo>("quiet", r#"{"points": [{"temp": 20, "fan": "x"}]}"#)
+                .unwrap_err();
+        assert_eq!(
+            message(err),
+            "`quiet`: invalid type: string \"x\", expected u8 in `points[0].fan` at line 1 column 35"
+        );
+
+        let path = config::config_dir().join("fan/quiet.json");
+        assert_eq!(display_name(&path), "fan/quiet.json");
+        assert_eq!(
+            display_name(Path::new("/home/tux/.config/tailor/fan/quiet.json")),
+            "fan/quiet.json"
+        );
+    }
+}
@@ -0,0 +1,130 @@
+//! Raw access to the attributes of the tuxedo_io driver by name, for
+//! development and triaging driver bugs. Nothing checks whether the
+//! device uses an attribute or whether a written value is safe.
+//!
+//! Writes are only available with the `debug-writes` feature.
+
+use std::fs::File;
+
+use crate::{config::open_device_file, error::IoctlError, read};
+
+enum Reader {
+    Int(fn(&File) -> Result<i32, IoctlError>),
+    String(fn(&File) -> Result<String, IoctlError>),
+}
+
+/// The attributes that can be read, prefixed with their interface.
+const READABLE: &[(&str, Reader)] = &[
+    ("mod_version", Reader::String(read::mod_version)),
+    ("cl.hw_check", Reader::Int(read::cl::hw_check)),
+    (
+        "cl.hw_interface_id",
+        Reader::String(read::cl::hw_interface_id),
+    ),
+    ("cl.fan_info_0", Reader::Int(read::cl::fan_info_0)),
+    ("cl.fan_info_1", Reader::Int(read::cl::fan_info_1)),
+    ("cl.fan_info_2", Reader::Int(read::cl::fan_info_2)),
+    ("cl.webcam_sw", Reader::Int(read::cl::webcam_sw)),
+    ("cl.touchpad_sw", Reader::Int(read::cl::touchpad_sw)),
+    ("uw.hw_check", Reader::Int(read::uw::hw_check)),
+    (
+        "uw.hw_interface_id",
+        Reader::String(read::uw::hw_interface_id),
+    ),
+    ("uw.model_id", Reader::Int(read::uw::model_id)),
+    ("uw.fan_speed_0", Reader::Int(read::uw::fan_speed_0)),
+    ("uw.fan_speed_1", Reader::Int(read::uw::fan_speed_1)),
+    ("uw.fan_temp_0", Reader::Int(read::uw::fan_temp_0)),
+    ("uw.fan_temp_1", Reader::Int(read::uw::fan_temp_1)),
+    (
+        "uw.fans_off_available",
+        Reader::Int(read::uw::fans_off_available),
+    ),
+    ("uw.fans_min_speed", Reader::Int(read::uw::fans_min_speed)),
+    ("uw.tdp_0", Reader::Int(read::uw::tdp_0)),
+    ("uw.tdp_1", Reader::Int(read::uw::tdp_1)),
+    ("uw.tdp_2", Reader::Int(read::uw::tdp_2)),
+    ("uw.tdp_min_0", Reader::Int(read::uw::tdp_min_0)),
+    ("uw.tdp_min_1", Reader::Int(read::uw::tdp_min_1)),
+    ("uw.tdp_min_2", Reader::Int(read::uw::tdp_min_2)),
+    ("uw.tdp_max_0", Reader::Int(read::uw::tdp_max_0)),
+    ("uw.tdp_max_1", Reader::Int(read::uw::tdp_max_1)),
+    ("uw.tdp_max_2", Reader::Int(read::uw::tdp_max_2)),
+    ("uw.profs_available", Reader::Int(read::uw::profs_available)),
+];
+
+#[cfg(feature = "debug-writes")]
+type Writer = fn(&File, i32) -> Result<(), IoctlError>;
+
+/// The attributes that can be written, prefixed with their interface.
+#[cfg(feature = "debug-writes")]
+const WRITABLE: &[(&str, Writer)] = {
+    use crate::write::{cl, uw};
+    &[
+        ("cl.fan_speed", cl::fan_speed),
+        ("cl.fan_auto", cl::fan_auto),
+        ("cl.webcam_sw", cl::webcam_sw),
+        ("cl.touchpad_sw", cl::touchpad_sw),
+        ("cl.perf_profile", cl::perf_profile),
+        ("uw.fan_speed_0", uw::fan_speed_0),
+        ("uw.fan_speed_1", uw::fan_speed_1),
+        ("uw.mode_enable", uw::mode_enable),
+        ("uw.fan_auto", uw::fan_auto),
+        ("uw.tdp_0", uw::tdp_0),
+        ("uw.tdp_1", uw::tdp_1),
+        ("uw.tdp_2", uw::tdp_2),
+        ("uw.perf_profile", uw::perf_profile),
+    ]
+};
+
+/// The names of the attributes that [`read_attribute`] accepts.
+pub fn readable_attributes() -> Vec<&'static str> {
+    READABLE.iter().map(|(name, _)| *name).collect()
+}
+
+/// The names of the attributes that [`write_attribute`] accepts.
+/// Empty without the `debug-writes` feature.
+pub fn writable_attributes() -> Vec<&'static str> {
+    #[cfg(feature = "debug-writes")]
+    return WRITABLE.iter().map(|(name, _)| *name).collect();
+    #[cfg(not(feature = "debug-writes"))]
+    Vec::new()
+}
+
+/// Read an attribute, returns `None` if there's no attribute with that name.
+pub fn read_attribute(name: &str) -> Option<Result<String, IoctlError>> {
+    let (_, reader) = READABLE.iter().find(|(attribute, _)| *attribute == name)?;
+    Some(
+        open_device_file()
+            .map_err(IoctlError::from)
+            .and_then(|file| match reader {
+                Reader::Int(read) => read(&file).map(|value| value.to_string()),
+                // The driver doesn't fill the whole buffer.
+                Reader::String(read) => {
+                    read(&file).map(|value| value.trim_end_matches('\0').to_owned())
+                }
+            }),
+    )
+}
+
+/// Write an attribute, returns `None` if there's no attribute with that name.
+#[cfg(feature = "debug-writes")]
+pub fn write_attribute(name: &str, value: i32) -> Option<Result<(), IoctlError>> {
+    let (_, write) = WRITABLE.iter().find(|(attribute, _)| *attribute == name)?;
+    Some(
+        open_device_file()
+            .map_err(IoctlError::from)
+            .and_then(|file| write(&file, value)),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{read_attribute, readable_attributes};
+
+    #[test]
+    fn unknown_attribute() {
+        assert!(read_attribute("cl.fan_info_3").is_none());
+        assert!(readable_attributes().contains(&"uw.fan_temp_0"));
+    }
+}
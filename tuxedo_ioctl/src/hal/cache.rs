@@ -0,0 +1,384 @@
+//! Caching of values that are read from the EC.
+//!
+//! Limits, descriptors, the available profiles and the identity of the
+//! device don't change at runtime, so they are only read once. Sensor readings can be reused for a short
+//! time, so several readers don't multiply the traffic to the EC.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use super::{
+    traits::{FanControl, FanSensors, HardwareDevice, PerformanceProfiles, TdpControl},
+    Capabilities, IoctlError, IoctlResult,
+};
+
+/// Wrap the capabilities of a device with caches. Sensor readings are
+/// reused for `sensor_ttl`, or read every time if it's `None`.
+pub fn apply(capabilities: &mut Capabilities, sensor_ttl: Option<Duration>) {
+    if let Some(fans) = capabilities.fans.take() {
+        capabilities.fans = Some(Arc::new(CachedFans::new(fans, sensor_ttl)));
+    }
+    if let Some(profiles) = capabilities.performance_profiles.take() {
+        capabilities.performance_profiles = Some(Arc::new(CachedProfiles::new(profiles)));
+    }
+    if let Some(tdp) = capabilities.tdp.take() {
+        capabilities.tdp = Some(Arc::new(CachedTdp::new(tdp)));
+    }
+}
+
+/// Wrap a device so its interface and model id are only read once.
+pub fn device(device: Arc<dyn HardwareDevice>) -> Arc<dyn HardwareDevice> {
+    Arc::new(CachedDevice::new(device))
+}
+
+/// A value that is read once. Errors aren't cached, so a failed read is retried.
+#[derive(Debug)]
+struct Cached<T> {
+    value: Mutex<Option<T>>,
+}
+
+impl<T: Clone> Cached<T> {
+    fn new() -> Self {
+        Self {
+            value: Mutex::new(None),
+        }
+    }
+
+    fn get_or_read(&self, read: impl FnOnce() -> IoctlResult<T>) -> IoctlResult<T> {
+        let mut value = self.value.lock().unwrap();
+        if let Some(value) = value.as_ref() {
+            return Ok(value.clone());
+        }
+        let new_value = read()?;
+        *value = Some(new_value.clone());
+        Ok(new_value)
+    }
+}
+
+/// A value per index that is read once, e.g. the limits of each power limit.
+#[derive(Debug)]
+struct CachedMap<T> {
+    values: Mutex<BTreeMap<u8, T>>,
+}
+
+impl<T: Clone> CachedMap<T> {
+    fn new() -> Self {
+        Self {
+            values: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn get_or_read(&self, index: u8, read: impl FnOnce() -> IoctlResult<T>) -> IoctlResult<T> {
+        let mut values = self.values.lock().unwrap();
+        if let Some(value) = values.get(&index) {
+            return Ok(value.clone());
+        }
+        let value = read()?;
+        values.insert(index, value.clone());
+        Ok(value)
+    }
+}
+
+#[derive(Debug)]
+pub struct CachedFans {
+    inner: Arc<dyn FanControl>,
+    min_speed: Cached<u8>,
+    off_available: Cached<bool>,
+    sensor_ttl: Option<Duration>,
+    /// The last reading of each fan and when it was taken. They're cached
+    /// per fan, so a fan that fails to read doesn't fail the others.
+    sensors: Mutex<BTreeMap<u8, (Instant, FanSensors)>>,
+}
+
+impl CachedFans {
+    pub fn new(inner: Arc<dyn FanControl>, sensor_ttl: Option<Duration>) -> Self {
+        Self {
+            inner,
+            min_speed: Cached::new(),
+            off_available: Cached::new(),
+            sensor_ttl,
+            sensors: Mutex::new(BTreeMap::new()),
+        }
+    }
+}
+
+impl FanControl for CachedFans {
+    fn get_number_fans(&self) -> u8 {
+        self.inner.get_number_fans()
+    }
+
+    fn set_fans_auto(&self) -> IoctlResult<()> {
+        self.inner.set_fans_auto()
+    }
+
+    fn get_fan_speed_raw_max(&self) -> u8 {
+        self.inner.get_fan_speed_raw_max()
+    }
+
+    fn set_fan_speed_raw(&self, fan: u8, fan_speed_raw: u8) -> IoctlResult<()> {
+        self.inner.set_fan_speed_raw(fan, fan_speed_raw)
+    }
+
+    fn get_fan_speed_raw(&self, fan: u8) -> IoctlResult<u8> {
+        self.inner.get_fan_speed_raw(fan)
+    }
+
+    fn get_fan_duty_raw(&self, fan: u8) -> Option<u8> {
+        self.inner.get_fan_duty_raw(fan)
+    }
+
+    fn get_fan_temperature(&self, fan: u8) -> IoctlResult<u8> {
+        match self.sensor_ttl {
+            Some(_) => self.read_fan_sensors(fan).map(|sensors| sensors.temp),
+            None => self.inner.get_fan_temperature(fan),
+        }
+    }
+
+    /// With a TTL, the readings are shared until they expire.
+    fn read_fan_sensors(&self, fan: u8) -> IoctlResult<FanSensors> {
+        let Some(ttl) = self.sensor_ttl else {
+            return self.inner.read_fan_sensors(fan);
+        };
+        if fan >= self.inner.get_number_fans() {
+            return Err(IoctlError::InvalidArgs);
+        }
+        let mut cached = self.sensors.lock().unwrap();
+        if let Some((read_at, sensors)) = cached.get(&fan) {
+            if read_at.elapsed() < ttl {
+                return Ok(*sensors);
+            }
+        }
+        // Errors aren't cached, so a failed read is retried.
+        let sensors = self.inner.read_fan_sensors(fan)?;
+        cached.insert(fan, (Instant::now(), sensors));
+        Ok(sensors)
+    }
+
+    fn read_sensors(&self) -> IoctlResult<Vec<FanSensors>> {
+        if self.sensor_ttl.is_none() {
+            return self.inner.read_sensors();
+        }
+        (0..self.inner.get_number_fans())
+            .map(|fan| self.read_fan_sensors(fan))
+            .collect()
+    }
+
+    fn get_fans_min_speed(&self) -> IoctlResult<u8> {
+        self.min_speed
+            .get_or_read(|| self.inner.get_fans_min_speed())
+    }
+
+    fn get_fans_off_available(&self) -> IoctlResult<bool> {
+        self.off_available
+            .get_or_read(|| self.inner.get_fans_off_available())
+    }
+}
+
+#[derive(Debug)]
+pub struct CachedDevice {
+    inner: Arc<dyn HardwareDevice>,
+    interface_id: Cached<String>,
+    model_id: Cached<String>,
+}
+
+impl CachedDevice {
+    pub fn new(inner: Arc<dyn HardwareDevice>) -> Self {
+        Self {
+            inner,
+            interface_id: Cached::new(),
+            model_id: Cached::new(),
+        }
+    }
+}
+
+impl HardwareDevice for CachedDevice {
+    fn device_interface_id_str(&self) -> IoctlResult<String> {
+        self.interface_id
+            .get_or_read(|| self.inner.device_interface_id_str())
+    }
+
+    fn device_model_id_str(&self) -> IoctlResult<String> {
+        self.model_id
+            .get_or_read(|| self.inner.device_model_id_str())
+    }
+
+    fn set_enable_mode_set(&self, enabled: bool) -> IoctlResult<()> {
+        self.inner.set_enable_mode_set(enabled)
+    }
+}
+
+#[derive(Debug)]
+pub struct CachedProfiles {
+    inner: Arc<dyn PerformanceProfiles>,
+    available: Cached<Vec<String>>,
+    default: Cached<String>,
+}
+
+impl CachedProfiles {
+    pub fn new(inner: Arc<dyn PerformanceProfiles>) -> Self {
+        Self {
+            inner,
+            available: Cached::new(),
+            default: Cached::new(),
+        }
+    }
+}
+
+impl PerformanceProfiles for CachedProfiles {
+    fn get_available_odm_performance_profiles(&self) -> IoctlResult<Vec<String>> {
+        self.available
+            .get_or_read(|| self.inner.get_available_odm_performance_profiles())
+    }
+
+    fn set_odm_performance_profile(&self, performance_profile: &str) -> IoctlResult<()> {
+        self.inner.set_odm_performance_profile(performance_profile)
+    }
+
+    fn get_default_odm_performance_profile(&self) -> IoctlResult<String> {
+        self.default
+            .get_or_read(|| self.inner.get_default_odm_performance_profile())
+    }
+}
+
+#[derive(Debug)]
+pub struct CachedTdp {
+    inner: Arc<dyn TdpControl>,
+    count: Cached<u8>,
+    descriptors: Cached<Vec<String>>,
+    min: CachedMap<i32>,
+    max: CachedMap<i32>,
+}
+
+impl CachedTdp {
+    pub fn new(inner: Arc<dyn TdpControl>) -> Self {
+        Self {
+            inner,
+            count: Cached::new(),
+            descriptors: Cached::new(),
+            min: CachedMap::new(),
+            max: CachedMap::new(),
+        }
+    }
+}
+
+impl TdpControl for CachedTdp {
+    fn get_number_tdps(&self) -> IoctlResult<u8> {
+        self.count.get_or_read(|| self.inner.get_number_tdps())
+    }
+
+    fn get_tdp_descriptors(&self) -> IoctlResult<Vec<String>> {
+        self.descriptors
+            .get_or_read(|| self.inner.get_tdp_descriptors())
+    }
+
+    fn get_tdp_min(&self, tdp_index: u8) -> IoctlResult<i32> {
+        self.min
+            .get_or_read(tdp_index, || self.inner.get_tdp_min(tdp_index))
+    }
+
+    fn get_tdp_max(&self, tdp_index: u8) -> IoctlResult<i32> {
+        self.max
+            .get_or_read(tdp_index, || self.inner.get_tdp_max(tdp_index))
+    }
+
+    fn set_tdp(&self, tdp_index: u8, tdp_value: i32) -> IoctlResult<()> {
+        self.inner.set_tdp(tdp_index, tdp_value)
+    }
+
+    fn get_tdp(&self, tdp_index: u8) -> IoctlResult<i32> {
+        self.inner.get_tdp(tdp_index)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use super::CachedFans;
+    use crate::hal::{traits::FanControl, IoctlError, IoctlResult};
+
+    /// Counts how often the sensors are read.
+    #[derive(Debug, Default)]
+    struct Fans {
+        reads: AtomicU32,
+        /// A fan whose temperature can't be read.
+        failing: Option<u8>,
+    }
+
+    impl FanControl for Fans {
+        fn get_number_fans(&self) -> u8 {
+            2
+        }
+        fn set_fans_auto(&self) -> IoctlResult<()> {
+            Ok(())
+        }
+        fn get_fan_speed_raw_max(&self) -> u8 {
+            100
+        }
+        fn set_fan_speed_raw(&self, _fan: u8, _fan_speed_raw: u8) -> IoctlResult<()> {
+            Ok(())
+        }
+        fn get_fan_speed_raw(&self, fan: u8) -> IoctlResult<u8> {
+            Ok(fan)
+        }
+        fn get_fan_duty_raw(&self, _fan: u8) -> Option<u8> {
+            None
+        }
+        fn get_fan_temperature(&self, fan: u8) -> IoctlResult<u8> {
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            if self.failing == Some(fan) {
+                return Err(IoctlError::DevNotAvailable);
+            }
+            Ok(40 + fan)
+        }
+        fn get_fans_min_speed(&self) -> IoctlResult<u8> {
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            Ok(25)
+        }
+        fn get_fans_off_available(&self) -> IoctlResult<bool> {
+            Ok(true)
+        }
+    }
+
+    #[test]
+    fn cached_fans() {
+        let inner = Arc::new(Fans::default());
+        let fans = CachedFans::new(inner.clone(), Some(Duration::from_secs(60)));
+        assert_eq!(fans.get_fans_min_speed().unwrap(), 25);
+        assert_eq!(fans.get_fans_min_speed().unwrap(), 25);
+        assert_eq!(inner.reads.load(Ordering::Relaxed), 1);
+
+        // One pass over both fans serves all readers until the TTL expires.
+        assert_eq!(fans.get_fan_temperature(1).unwrap(), 41);
+        assert_eq!(fans.read_fan_sensors(0).unwrap().temp, 40);
+        assert_eq!(fans.read_sensors().unwrap().len(), 2);
+        assert_eq!(inner.reads.load(Ordering::Relaxed), 3);
+
+        let fans = CachedFans::new(inner.clone(), None);
+        fans.get_fan_temperature(0).unwrap();
+        fans.get_fan_temperature(0).unwrap();
+        assert_eq!(inner.reads.load(Ordering::Relaxed), 5);
+
+        // A failing fan doesn't fail the readings of the other fan.
+        let inner = Arc::new(Fans {
+            failing: Some(0),
+            ..Default::default()
+        });
+        let fans = CachedFans::new(inner.clone(), Some(Duration::from_secs(60)));
+        assert!(fans.read_sensors().is_err());
+        assert!(fans.get_fan_temperature(0).is_err());
+        assert_eq!(fans.get_fan_temperature(1).unwrap(), 41);
+        assert_eq!(fans.get_fan_temperature(1).unwrap(), 41);
+        // The failed reads are retried, the successful one is cached.
+        assert_eq!(inner.reads.load(Ordering::Relaxed), 3);
+    }
+}
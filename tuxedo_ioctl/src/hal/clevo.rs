@@ -1,7 +1,10 @@
 use crate::{error::IoctlError, read, write};
 
-use super::traits::{HardwareDevice, WebcamDevice};
-use super::IoctlResult;
+use super::traits::{
+    raw_to_percent, FanControl, FanSensors, HardwareDevice, PerformanceProfiles, TouchpadControl,
+    WebcamControl,
+};
+use super::{FanDuties, IoctlResult};
 
 pub const MAX_FAN_SPEED: u8 = 0xff;
 
@@ -21,6 +24,7 @@ const PERF_PROFILE_MAP: [(&str, u8); 4] = [
 pub struct ClevoHardware {
     file: std::fs::File,
     num_of_fans: u8,
+    duties: FanDuties,
 }
 
 impl ClevoHardware {
@@ -29,6 +33,7 @@ impl ClevoHardware {
             let mut this = Self {
                 file,
                 num_of_fans: 0,
+                duties: FanDuties::default(),
             };
 
             // Only show actually available fans
@@ -56,54 +61,62 @@ impl HardwareDevice for ClevoHardware {
         // Not implemented for clevo for now...
         Ok(())
     }
+}
 
+impl FanControl for ClevoHardware {
     fn get_number_fans(&self) -> u8 {
         self.num_of_fans
     }
 
     fn set_fans_auto(&self) -> IoctlResult<()> {
-        write::cl::fan_auto(&self.file, 0xF)
+        write::cl::fan_auto(&self.file, 0xF)?;
+        self.duties.clear();
+        Ok(())
     }
 
-    fn set_fan_speed_percent(&self, fan: u8, fan_speed_percent: u8) -> IoctlResult<()> {
-        let mut fan_speed_raw: [u8; 3] = [0; 3];
+    fn get_fan_speed_raw_max(&self) -> u8 {
+        MAX_FAN_SPEED
+    }
 
-        let fan_speed_percent = fan_speed_percent.clamp(0, 100);
+    fn set_fan_speed_raw(&self, fan: u8, fan_speed_raw: u8) -> IoctlResult<()> {
+        let mut fan_speeds_raw: [u8; 3] = [0; 3];
 
-        for (i, fan_speed) in fan_speed_raw.iter_mut().enumerate() {
+        for (i, fan_speed) in fan_speeds_raw.iter_mut().enumerate() {
             let selected_fan = i as u8;
             if selected_fan == fan {
-                *fan_speed = (fan_speed_percent as f64 * 0xFF as f64 / 100.0).round() as u8;
+                *fan_speed = fan_speed_raw;
             } else {
                 *fan_speed = self.read_fanspeed_raw(selected_fan)?;
             }
         }
 
-        let mut argument: i32 = fan_speed_raw[0] as i32;
-        argument |= (fan_speed_raw[1] as i32) << 0x08;
-        argument |= (fan_speed_raw[2] as i32) << 0x10;
+        let mut argument: i32 = fan_speeds_raw[0] as i32;
+        argument |= (fan_speeds_raw[1] as i32) << 0x08;
+        argument |= (fan_speeds_raw[2] as i32) << 0x10;
         write::cl::fan_speed(&self.file, argument)?;
+        self.duties.set(fan, fan_speed_raw);
         Ok(())
     }
 
-    fn get_fan_speed_percent(&self, fan: u8) -> IoctlResult<u8> {
-        let fan_speed_raw = self.read_fanspeed_raw(fan)?;
-        Ok(((fan_speed_raw as f64 / MAX_FAN_SPEED as f64) * 100.0).round() as u8)
+    fn get_fan_speed_raw(&self, fan: u8) -> IoctlResult<u8> {
+        self.read_fanspeed_raw(fan)
     }
 
-    fn get_fan_temperature(&self, fan: u8) -> IoctlResult<u8> {
-        let fan_info_raw = self.read_faninfo_raw(fan)?;
+    fn get_fan_duty_raw(&self, fan: u8) -> Option<u8> {
+        self.duties.get(fan)
+    }
 
-        // Explicitly use temp2 since it's more consistently implemented
-        // int fanTemp1 = (int8_t) ((fanInfo >> 0x08) & 0xff);
-        let fan_temp_2: u8 = ((fan_info_raw >> 0x10) & 0xff).try_into().unwrap();
+    fn get_fan_temperature(&self, fan: u8) -> IoctlResult<u8> {
+        fan_temp(self.read_faninfo_raw(fan)?)
+    }
 
-        // If a fan is not available a low value is read out
-        if fan_temp_2 <= 1 {
-            Err(IoctlError::DevNotAvailable)
-        } else {
-            Ok(fan_temp_2)
-        }
+    /// The fan info contains both the speed and the temperature.
+    fn read_fan_sensors(&self, fan: u8) -> IoctlResult<FanSensors> {
+        let fan_info_raw = self.read_faninfo_raw(fan)?;
+        Ok(FanSensors {
+            temp: fan_temp(fan_info_raw)?,
+            speed: raw_to_percent(fan_speed_raw(fan_info_raw), MAX_FAN_SPEED),
+        })
     }
 
     fn get_fans_min_speed(&self) -> IoctlResult<u8> {
@@ -113,7 +126,9 @@ impl HardwareDevice for ClevoHardware {
     fn get_fans_off_available(&self) -> IoctlResult<bool> {
         Ok(true)
     }
+}
 
+impl PerformanceProfiles for ClevoHardware {
     fn get_available_odm_performance_profiles(&self) -> IoctlResult<Vec<String>> {
         Ok(vec![
             PERF_PROF_QUIET.into(),
@@ -139,7 +154,7 @@ impl HardwareDevice for ClevoHardware {
     }
 }
 
-impl WebcamDevice for ClevoHardware {
+impl WebcamControl for ClevoHardware {
     fn set_webcam(&self, status: bool) -> IoctlResult<()> {
         write::cl::webcam_sw(&self.file, i32::from(status))
     }
@@ -149,11 +164,20 @@ impl WebcamDevice for ClevoHardware {
     }
 }
 
+impl TouchpadControl for ClevoHardware {
+    fn set_touchpad(&self, status: bool) -> IoctlResult<()> {
+        write::cl::touchpad_sw(&self.file, i32::from(status))
+    }
+
+    fn get_touchpad(&self) -> IoctlResult<bool> {
+        read::cl::touchpad_sw(&self.file).map(|val| val != 0)
+    }
+}
+
 // Helper methods
 impl ClevoHardware {
     fn read_fanspeed_raw(&self, fan: u8) -> Result<u8, IoctlError> {
-        self.read_faninfo_raw(fan)
-            .map(|value| (value & 0xFF).try_into().unwrap())
+        self.read_faninfo_raw(fan).map(fan_speed_raw)
     }
 
     fn read_faninfo_raw(&self, fan: u8) -> Result<i32, IoctlError> {
@@ -165,3 +189,20 @@ impl ClevoHardware {
         }
     }
 }
+
+fn fan_speed_raw(fan_info_raw: i32) -> u8 {
+    (fan_info_raw & 0xFF).try_into().unwrap()
+}
+
+fn fan_temp(fan_info_raw: i32) -> IoctlResult<u8> {
+    // Explicitly use temp2 since it's more consistently implemented
+    // int fanTemp1 = (int8_t) ((fanInfo >> 0x08) & 0xff);
+    let fan_temp_2: u8 = ((fan_info_raw >> 0x10) & 0xff).try_into().unwrap();
+
+    // If a fan is not available a low value is read out
+    if fan_temp_2 <= 1 {
+        Err(IoctlError::DevNotAvailable)
+    } else {
+        Ok(fan_temp_2)
+    }
+}
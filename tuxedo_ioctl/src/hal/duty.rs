@@ -0,0 +1,25 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+/// Remembers the raw fan duties that were commanded last,
+/// because the hardware only reports the current fan speed.
+#[derive(Debug, Default)]
+pub struct FanDuties {
+    duties: Mutex<BTreeMap<u8, u8>>,
+}
+
+impl FanDuties {
+    pub fn set(&self, fan: u8, duty_raw: u8) {
+        self.duties.lock().unwrap().insert(fan, duty_raw);
+    }
+
+    /// Get the raw duty of a fan or `None` if the
+    /// fan is controlled by the firmware.
+    pub fn get(&self, fan: u8) -> Option<u8> {
+        self.duties.lock().unwrap().get(&fan).copied()
+    }
+
+    /// Hand control of all fans back to the firmware.
+    pub fn clear(&self) {
+        self.duties.lock().unwrap().clear();
+    }
+}
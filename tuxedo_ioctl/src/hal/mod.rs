@@ -1,25 +1,78 @@
 use std::sync::Arc;
 
-use crate::{config::open_device_file, error::IoctlError, read};
+use crate::{config::open_device_file, read};
 
 use self::{
     clevo::ClevoHardware,
-    traits::{HardwareDevice, TdpDevice, WebcamDevice},
+    traits::{
+        FanControl, HardwareDevice, PerformanceProfiles, TdpControl, TouchpadControl, WebcamControl,
+    },
     uniwill::UniwillHardware,
 };
 
+pub mod cache;
 mod clevo;
+mod duty;
 pub mod traits;
 mod uniwill;
 
+pub use self::duty::FanDuties;
+pub use crate::error::IoctlError;
+
 pub type IoctlResult<T> = Result<T, IoctlError>;
 
+/// A feature that a device might support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    FanControl,
+    PerformanceProfiles,
+    WebcamControl,
+    TouchpadControl,
+    TdpControl,
+}
+
+/// The features of a device. Each feature is `None`
+/// if the hardware doesn't support it.
+#[derive(Debug, Clone, Default)]
+pub struct Capabilities {
+    pub fans: Option<Arc<dyn FanControl>>,
+    pub performance_profiles: Option<Arc<dyn PerformanceProfiles>>,
+    pub webcam: Option<Arc<dyn WebcamControl>>,
+    pub touchpad: Option<Arc<dyn TouchpadControl>>,
+    pub tdp: Option<Arc<dyn TdpControl>>,
+}
+
+impl Capabilities {
+    pub fn has(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::FanControl => self.fans.is_some(),
+            Capability::PerformanceProfiles => self.performance_profiles.is_some(),
+            Capability::WebcamControl => self.webcam.is_some(),
+            Capability::TouchpadControl => self.touchpad.is_some(),
+            Capability::TdpControl => self.tdp.is_some(),
+        }
+    }
+
+    /// All supported capabilities.
+    pub fn list(&self) -> Vec<Capability> {
+        [
+            Capability::FanControl,
+            Capability::PerformanceProfiles,
+            Capability::WebcamControl,
+            Capability::TouchpadControl,
+            Capability::TdpControl,
+        ]
+        .into_iter()
+        .filter(|capability| self.has(*capability))
+        .collect()
+    }
+}
+
 #[derive(Debug)]
 pub struct IoInterface {
     pub module_version: String,
     pub device: Arc<dyn HardwareDevice>,
-    pub webcam: Option<Arc<dyn WebcamDevice>>,
-    pub tdp: Option<Arc<dyn TdpDevice>>,
+    pub capabilities: Capabilities,
 }
 
 impl IoInterface {
@@ -33,8 +86,13 @@ impl IoInterface {
             Ok(Self {
                 module_version,
                 device: interface.clone(),
-                webcam: Some(interface),
-                tdp: None,
+                capabilities: Capabilities {
+                    fans: Some(interface.clone()),
+                    performance_profiles: Some(interface.clone()),
+                    webcam: Some(interface.clone()),
+                    touchpad: Some(interface),
+                    tdp: None,
+                },
             })
         } else if matches!(read::uw::hw_check(&file), Ok(1)) {
             let uniwill_hardware = UniwillHardware::init(file)?;
@@ -42,8 +100,13 @@ impl IoInterface {
             Ok(Self {
                 module_version,
                 device: interface.clone(),
-                webcam: None,
-                tdp: Some(interface),
+                capabilities: Capabilities {
+                    fans: Some(interface.clone()),
+                    performance_profiles: Some(interface.clone()),
+                    webcam: None,
+                    touchpad: None,
+                    tdp: Some(interface),
+                },
             })
         } else {
             Err(IoctlError::DevNotAvailable)
@@ -52,6 +115,7 @@ impl IoInterface {
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod test {
     use super::*;
 
@@ -61,7 +125,7 @@ mod test {
 
         let io = IoInterface::new().unwrap();
 
-        if let Some(webcam) = &io.webcam {
+        if let Some(webcam) = &io.capabilities.webcam {
             // Check webcam
             webcam.set_webcam(false).unwrap();
             assert_eq!(webcam.get_webcam().unwrap(), false);
@@ -71,14 +135,17 @@ mod test {
         }
 
         let device = &io.device;
+        let profiles = io.capabilities.performance_profiles.as_ref().unwrap();
         // Set performance profile
         if device.device_interface_id_str().unwrap() == "uniwill_wmi" {
-            device.set_odm_performance_profile("power_save").unwrap();
+            profiles.set_odm_performance_profile("power_save").unwrap();
         }
         if device.device_interface_id_str().unwrap() == "clevo_acpi" {
-            device.set_odm_performance_profile("quiet").unwrap();
+            profiles.set_odm_performance_profile("quiet").unwrap();
         }
 
+        let device = io.capabilities.fans.as_ref().unwrap();
+
         // Get temperatures
         assert!(20 < device.get_fan_temperature(0).unwrap());
         assert!(matches!(
@@ -2,40 +2,123 @@ use std::fmt::Debug;
 
 use super::IoctlResult;
 
+/// The identity of a device. Everything else the device can do is exposed
+/// through the capability traits in [`super::Capabilities`], so a backend
+/// only implements what the hardware actually has.
 pub trait HardwareDevice: Send + Sync + Debug {
     fn device_interface_id_str(&self) -> IoctlResult<String>;
     fn device_model_id_str(&self) -> IoctlResult<String>;
     fn set_enable_mode_set(&self, enabled: bool) -> IoctlResult<()>;
+}
+
+/// The temperature and speed of a fan, read in one pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FanSensors {
+    /// In °C.
+    pub temp: u8,
+    /// The speed that the hardware reports in percent.
+    pub speed: u8,
+}
 
+/// Reading the temperatures and controlling the fans.
+pub trait FanControl: Send + Sync + Debug {
     // Get the amount of available fans
     fn get_number_fans(&self) -> u8;
 
     fn set_fans_auto(&self) -> IoctlResult<()>;
 
+    /// Get the raw fan speed value of the hardware that
+    /// corresponds to 100%, e.g. 0xc8 on uniwill devices.
+    fn get_fan_speed_raw_max(&self) -> u8;
+
+    /// Set the fan speed in raw hardware units from 0 to
+    /// [`FanControl::get_fan_speed_raw_max`].
+    /// Larger values will be clamped to the maximum.
+    fn set_fan_speed_raw(&self, fan: u8, fan_speed_raw: u8) -> IoctlResult<()>;
+
+    /// Get the fan speed that the hardware reports in raw units from 0 to
+    /// [`FanControl::get_fan_speed_raw_max`].
+    fn get_fan_speed_raw(&self, fan: u8) -> IoctlResult<u8>;
+
+    /// Get the fan duty that was set last in raw units or `None`
+    /// if the fan is controlled by the firmware.
+    fn get_fan_duty_raw(&self, fan: u8) -> Option<u8>;
+
     /// Set the fan speed in percent from 0 to 100.
     /// Values above 100 will be clamped to 100.
-    fn set_fan_speed_percent(&self, fan: u8, fan_speed_percent: u8) -> IoctlResult<()>;
+    fn set_fan_speed_percent(&self, fan: u8, fan_speed_percent: u8) -> IoctlResult<()> {
+        let fan_speed_percent = fan_speed_percent.min(100);
+        let fan_speed_raw =
+            (self.get_fan_speed_raw_max() as f64 * fan_speed_percent as f64 / 100.0).round() as u8;
+        self.set_fan_speed_raw(fan, fan_speed_raw)?;
+        tracing::trace!(
+            "Set fan {fan} speed percentage to {fan_speed_percent}, fan speed raw: {fan_speed_raw}"
+        );
+        Ok(())
+    }
+
+    /// Get the fan duty that was set last in percent or `None`
+    /// if the fan is controlled by the firmware.
+    /// Compare with [`FanControl::get_fan_speed_percent`] to check
+    /// whether a fan follows the commanded duty.
+    fn get_fan_duty(&self, fan: u8) -> Option<u8> {
+        self.get_fan_duty_raw(fan)
+            .map(|fan_duty_raw| raw_to_percent(fan_duty_raw, self.get_fan_speed_raw_max()))
+    }
 
-    /// Get the fan speed value in percent from 0 to 100.
-    fn get_fan_speed_percent(&self, fan: u8) -> IoctlResult<u8>;
+    /// Get the fan speed value that the hardware reports in percent from 0 to 100.
+    fn get_fan_speed_percent(&self, fan: u8) -> IoctlResult<u8> {
+        let fan_speed_raw = self.get_fan_speed_raw(fan)?;
+        let speed = raw_to_percent(fan_speed_raw, self.get_fan_speed_raw_max());
+        tracing::trace!("Fan {fan} speed percentage is {speed}, fan speed raw: {fan_speed_raw}");
+        Ok(speed)
+    }
 
     /// Get the fan temperature in °C
     fn get_fan_temperature(&self, fan: u8) -> IoctlResult<u8>;
 
+    /// Read the temperature and speed of a fan. Backends override
+    /// this if the hardware reports both with a single request.
+    fn read_fan_sensors(&self, fan: u8) -> IoctlResult<FanSensors> {
+        Ok(FanSensors {
+            temp: self.get_fan_temperature(fan)?,
+            speed: self.get_fan_speed_percent(fan)?,
+        })
+    }
+
+    /// Read the temperatures and speeds of all fans in one pass.
+    fn read_sensors(&self) -> IoctlResult<Vec<FanSensors>> {
+        (0..self.get_number_fans())
+            .map(|fan| self.read_fan_sensors(fan))
+            .collect()
+    }
+
     /// Get the minimum supported speed of the fans
     fn get_fans_min_speed(&self) -> IoctlResult<u8>;
     fn get_fans_off_available(&self) -> IoctlResult<bool>;
+}
+
+/// Switching between the performance profiles of the firmware.
+pub trait PerformanceProfiles: Send + Sync + Debug {
     fn get_available_odm_performance_profiles(&self) -> IoctlResult<Vec<String>>;
     fn set_odm_performance_profile(&self, performance_profile: &str) -> IoctlResult<()>;
     fn get_default_odm_performance_profile(&self) -> IoctlResult<String>;
 }
 
-pub trait WebcamDevice: Send + Sync + Debug {
+/// Turning the webcam on and off.
+pub trait WebcamControl: Send + Sync + Debug {
     fn set_webcam(&self, status: bool) -> IoctlResult<()>;
     fn get_webcam(&self) -> IoctlResult<bool>;
 }
 
-pub trait TdpDevice: Send + Sync + Debug {
+/// Turning the touchpad on and off in the firmware.
+pub trait TouchpadControl: Send + Sync + Debug {
+    fn set_touchpad(&self, status: bool) -> IoctlResult<()>;
+    fn get_touchpad(&self) -> IoctlResult<bool>;
+}
+
+/// Reading and setting the power limits of the CPU.
+pub trait TdpControl: Send + Sync + Debug {
     fn get_number_tdps(&self) -> IoctlResult<u8>;
     fn get_tdp_descriptors(&self) -> IoctlResult<Vec<String>>;
     fn get_tdp_min(&self, tdp_index: u8) -> IoctlResult<i32>;
@@ -43,3 +126,7 @@ pub trait TdpDevice: Send + Sync + Debug {
     fn set_tdp(&self, tdp_index: u8, tdp_value: i32) -> IoctlResult<()>;
     fn get_tdp(&self, tdp_index: u8) -> IoctlResult<i32>;
 }
+
+pub(crate) fn raw_to_percent(raw: u8, max: u8) -> u8 {
+    (raw as f64 * 100.0 / max as f64).round().min(100.0) as u8
+}
@@ -1,7 +1,9 @@
+use std::sync::Mutex;
+
 use crate::{error::IoctlError, read, write};
 
-use super::traits::{HardwareDevice, TdpDevice};
-use super::IoctlResult;
+use super::traits::{FanControl, FanSensors, HardwareDevice, PerformanceProfiles, TdpControl};
+use super::{FanDuties, IoctlResult};
 
 const MAX_FAN_SPEED: u8 = 0xc8;
 
@@ -19,6 +21,10 @@ const PERF_PROFILE_MAP: [(&str, u8); 3] = [
 pub struct UniwillHardware {
     file: std::fs::File,
     num_of_fans: u8,
+    duties: FanDuties,
+    /// Held while the sensors are read, so the readings of a fan are taken
+    /// back to back instead of interleaving with the requests of other fans.
+    sensor_lock: Mutex<()>,
 }
 
 impl UniwillHardware {
@@ -27,6 +33,8 @@ impl UniwillHardware {
             let mut this = Self {
                 file,
                 num_of_fans: 0,
+                duties: FanDuties::default(),
+                sensor_lock: Mutex::default(),
             };
 
             // Only show actually available fans
@@ -62,7 +70,9 @@ impl HardwareDevice for UniwillHardware {
         tracing::trace!("Set enable mode to {enabled}");
         Ok(())
     }
+}
 
+impl FanControl for UniwillHardware {
     #[tracing::instrument(level = "trace", skip(self))]
     fn get_number_fans(&self) -> u8 {
         tracing::trace!("Available number of fans: {}", self.num_of_fans);
@@ -72,37 +82,43 @@ impl HardwareDevice for UniwillHardware {
     #[tracing::instrument(level = "trace", skip(self))]
     fn set_fans_auto(&self) -> IoctlResult<()> {
         write::uw::fan_auto(&self.file, 0)?;
+        self.duties.clear();
         tracing::trace!("Set fan mode to auto");
         Ok(())
     }
 
+    fn get_fan_speed_raw_max(&self) -> u8 {
+        MAX_FAN_SPEED
+    }
+
     #[tracing::instrument(level = "trace", skip(self))]
-    fn set_fan_speed_percent(&self, fan: u8, fan_speed_percent: u8) -> IoctlResult<()> {
-        let fan_speed_raw =
-            (MAX_FAN_SPEED as f64 * fan_speed_percent as f64 / 100.0).round() as i32;
+    fn set_fan_speed_raw(&self, fan: u8, fan_speed_raw: u8) -> IoctlResult<()> {
+        let fan_speed_raw = fan_speed_raw.min(MAX_FAN_SPEED);
 
         match fan {
-            0 => write::uw::fan_speed_0(&self.file, fan_speed_raw)?,
-            1 => write::uw::fan_speed_1(&self.file, fan_speed_raw)?,
+            0 => write::uw::fan_speed_0(&self.file, i32::from(fan_speed_raw))?,
+            1 => write::uw::fan_speed_1(&self.file, i32::from(fan_speed_raw))?,
             _ => return Err(IoctlError::DevNotAvailable),
         }
-        tracing::trace!(
-            "Set fan {fan} speed percentage to {fan_speed_percent}, fan speed raw: {fan_speed_raw}"
-        );
+        self.duties.set(fan, fan_speed_raw);
+        tracing::trace!("Set fan {fan} speed raw to {fan_speed_raw}");
         Ok(())
     }
 
     #[tracing::instrument(level = "trace", skip(self))]
-    fn get_fan_speed_percent(&self, fan: u8) -> IoctlResult<u8> {
+    fn get_fan_speed_raw(&self, fan: u8) -> IoctlResult<u8> {
         let fan_speed_raw = match fan {
             0 => read::uw::fan_speed_0(&self.file),
             1 => read::uw::fan_speed_1(&self.file),
             _ => Err(IoctlError::DevNotAvailable),
         }?;
 
-        let speed = (fan_speed_raw as f64 * 100.0 / MAX_FAN_SPEED as f64).round() as u8;
-        tracing::trace!("Fan {fan} speed percentage is {speed}, fan speed raw: {fan_speed_raw}");
-        Ok(speed)
+        tracing::trace!("Fan {fan} speed raw is {fan_speed_raw}");
+        Ok(fan_speed_raw.clamp(0, i32::from(MAX_FAN_SPEED)) as u8)
+    }
+
+    fn get_fan_duty_raw(&self, fan: u8) -> Option<u8> {
+        self.duties.get(fan)
     }
 
     #[tracing::instrument(level = "trace", skip(self))]
@@ -122,6 +138,18 @@ impl HardwareDevice for UniwillHardware {
         }
     }
 
+    fn read_fan_sensors(&self, fan: u8) -> IoctlResult<FanSensors> {
+        let _lock = self.sensor_lock.lock().unwrap();
+        self.fan_sensors(fan)
+    }
+
+    fn read_sensors(&self) -> IoctlResult<Vec<FanSensors>> {
+        let _lock = self.sensor_lock.lock().unwrap();
+        (0..self.num_of_fans)
+            .map(|fan| self.fan_sensors(fan))
+            .collect()
+    }
+
     #[tracing::instrument(level = "trace", skip(self))]
     fn get_fans_min_speed(&self) -> IoctlResult<u8> {
         let speed = read::uw::fans_min_speed(&self.file)?;
@@ -135,7 +163,9 @@ impl HardwareDevice for UniwillHardware {
         tracing::trace!("Fan off switch available: {is_off}");
         Ok(is_off)
     }
+}
 
+impl PerformanceProfiles for UniwillHardware {
     #[tracing::instrument(level = "trace", skip(self))]
     fn get_available_odm_performance_profiles(&self) -> IoctlResult<Vec<String>> {
         let available_profs = read::uw::profs_available(&self.file)?;
@@ -194,7 +224,7 @@ impl HardwareDevice for UniwillHardware {
     }
 }
 
-impl TdpDevice for UniwillHardware {
+impl TdpControl for UniwillHardware {
     fn get_number_tdps(&self) -> IoctlResult<u8> {
         // Check return status of getters to figure out how many
         // TDPs are configurable
@@ -262,3 +292,13 @@ impl TdpDevice for UniwillHardware {
         }
     }
 }
+
+// Helper methods
+impl UniwillHardware {
+    fn fan_sensors(&self, fan: u8) -> IoctlResult<FanSensors> {
+        Ok(FanSensors {
+            temp: self.get_fan_temperature(fan)?,
+            speed: self.get_fan_speed_percent(fan)?,
+        })
+    }
+}
@@ -1,4 +1,5 @@
 mod config;
+pub mod debug;
 mod error;
 pub mod hal;
 mod read;
@@ -89,7 +89,7 @@ pub mod cl {
 
     ioctl_read_int!(webcam_sw, MAGIC_READ_CL, 0x13);
     //ioctl_read_int!(flightmode_sw, MAGIC_READ_CL, 0x14);
-    //ioctl_read_int!(touchpad_sw, MAGIC_READ_CL, 0x15);
+    ioctl_read_int!(touchpad_sw, MAGIC_READ_CL, 0x15);
 }
 
 // Read uniwill
@@ -145,6 +145,7 @@ mod test {
             cl::fan_info_2(&file).unwrap();
 
             cl::webcam_sw(&file).unwrap();
+            cl::touchpad_sw(&file).unwrap();
         } else {
             assert!(uw::hw_check(&file).unwrap() == 1);
         }
@@ -40,7 +40,7 @@ pub mod cl {
 
     ioctl_write_int!(webcam_sw, MAGIC_WRITE_CL, 0x12);
     // ioctl_write_int!(flightmode_sw, MAGIC_WRITE_CL, 0x13);
-    // ioctl_write_int!(touchpad_sw, MAGIC_WRITE_CL, 0x14);
+    ioctl_write_int!(touchpad_sw, MAGIC_WRITE_CL, 0x14);
     ioctl_write_int!(perf_profile, MAGIC_WRITE_CL, 0x15);
 }
 
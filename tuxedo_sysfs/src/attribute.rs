@@ -0,0 +1,187 @@
+//! Shared access to sysfs attributes.
+//!
+//! Opening a sysfs file is much more expensive than reading it, so the files
+//! are opened once and read from offset 0 again, which makes the kernel
+//! regenerate their content. [`Attribute`] and [`read_batch`] use io_uring on
+//! the tailord runtime, [`CachedAttributes`] serves the synchronous hardware
+//! traits that are shared between threads.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io,
+    os::unix::fs::FileExt,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Mutex,
+};
+
+use tailor_api::HardwareWrite;
+
+/// Sysfs attributes fit into a single page, most are a few bytes long.
+const ATTRIBUTE_CAPACITY: usize = 256;
+
+/// An open sysfs attribute for use on a `tokio_uring` runtime.
+#[derive(Debug)]
+pub struct Attribute {
+    file: tokio_uring::fs::File,
+}
+
+impl Attribute {
+    pub async fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = tokio_uring::fs::File::open(path).await?;
+        Ok(Self { file })
+    }
+
+    pub async fn open_rw(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = tokio_uring::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)
+            .await?;
+        Ok(Self { file })
+    }
+
+    /// The content without the trailing newline.
+    pub async fn read_string(&self) -> io::Result<String> {
+        let buffer = Vec::with_capacity(ATTRIBUTE_CAPACITY);
+        let (res, buffer) = self.file.read_at(buffer, 0).await;
+        res?;
+        let content = String::from_utf8(buffer).map_err(invalid_data)?;
+        Ok(content.trim().to_owned())
+    }
+
+    pub async fn read_value<T>(&self) -> io::Result<T>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        self.read_string().await?.parse().map_err(invalid_data)
+    }
+
+    /// Space separated numbers, e.g. the intensities of multicolor LEDs.
+    pub async fn read_int_list(&self) -> io::Result<Vec<u32>> {
+        let content = self.read_string().await?;
+        let values = content
+            .split_whitespace()
+            .map(|value| value.parse().map_err(invalid_data))
+            .collect::<io::Result<Vec<u32>>>()?;
+        if values.is_empty() {
+            Err(io::Error::new(io::ErrorKind::InvalidData, "Empty file"))
+        } else {
+            Ok(values)
+        }
+    }
+
+    pub async fn write(&self, value: String) -> io::Result<()> {
+        self.file.write_at(value.into_bytes(), 0).await.0?;
+        Ok(())
+    }
+}
+
+/// Read several attributes at once. The reads are submitted to io_uring
+/// together instead of waiting for each file in turn.
+pub async fn read_batch<P: AsRef<Path>>(paths: &[P]) -> Vec<io::Result<String>> {
+    futures::future::join_all(
+        paths
+            .iter()
+            .map(|path| async move { Attribute::open(path).await?.read_string().await }),
+    )
+    .await
+}
+
+/// Attributes that stay open after the first access, for synchronous code.
+/// A handle is dropped after a failed access, so the next one opens
+/// the file again, e.g. after the device was unbound.
+#[derive(Debug, Default)]
+pub struct CachedAttributes {
+    files: Mutex<HashMap<PathBuf, File>>,
+}
+
+impl CachedAttributes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The content without the trailing newline.
+    pub fn read_string(&self, path: impl AsRef<Path>) -> io::Result<String> {
+        let mut buffer = [0; ATTRIBUTE_CAPACITY];
+        let len = self.with_file(path.as_ref(), |file| file.read_at(&mut buffer, 0))?;
+        let content = std::str::from_utf8(&buffer[..len]).map_err(invalid_data)?;
+        Ok(content.trim().to_owned())
+    }
+
+    pub fn read_value<T>(&self, path: impl AsRef<Path>) -> io::Result<T>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        self.read_string(path)?.parse().map_err(invalid_data)
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>, value: impl AsRef<[u8]>) -> io::Result<()> {
+        self.with_file(path.as_ref(), |file| file.write_at(value.as_ref(), 0))
+            .map(|_| ())
+    }
+
+    fn with_file<T>(&self, path: &Path, f: impl FnOnce(&File) -> io::Result<T>) -> io::Result<T> {
+        let mut files = self.files.lock().unwrap();
+        if !files.contains_key(path) {
+            // Read-only attributes can't be opened for writing.
+            let file = File::options()
+                .read(true)
+                .write(true)
+                .open(path)
+                .or_else(|_| File::open(path))?;
+            files.insert(path.to_owned(), file);
+        }
+        let result = f(&files[path]);
+        if result.is_err() {
+            files.remove(path);
+        }
+        result
+    }
+}
+
+/// The write of `new` to the attribute `name` of the device in `dir`,
+/// for dry runs. `None` if the attribute already has this value.
+pub fn planned_write(dir: &Path, name: &str, new: impl ToString) -> Option<HardwareWrite> {
+    let new = new.to_string();
+    let old = std::fs::read_to_string(dir.join(name))
+        .ok()
+        .map(|value| value.trim().to_owned());
+    (old.as_deref() != Some(new.as_str())).then(|| HardwareWrite {
+        device: dir.display().to_string(),
+        attribute: name.to_owned(),
+        old,
+        new,
+    })
+}
+
+fn invalid_data(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{read_batch, CachedAttributes};
+
+    #[test]
+    fn cached_attributes() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let path = dir.join("temp1_input");
+        std::fs::write(&path, "42000\n").unwrap();
+
+        let attributes = CachedAttributes::new();
+        assert_eq!(attributes.read_value::<u32>(&path).unwrap(), 42000);
+        // The cached handle sees new content, like sysfs files do.
+        attributes.write(&path, "43000").unwrap();
+        assert_eq!(attributes.read_value::<u32>(&path).unwrap(), 43000);
+        assert!(attributes.read_string(dir.join("missing")).is_err());
+
+        let contents = tokio_uring::start(read_batch(&[path, dir.join("missing")]));
+        assert_eq!(contents[0].as_deref().unwrap(), "43000");
+        assert!(contents[1].is_err());
+    }
+}
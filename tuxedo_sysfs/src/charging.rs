@@ -0,0 +1,79 @@
+//! The charging profiles of tuxedo_keyboard. Besides charging to full
+//! capacity, the firmware can limit the charge to extend the lifespan
+//! of batteries that are mostly plugged in.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+const CHARGING_PROFILE_PATH: &str = "/sys/devices/platform/tuxedo_keyboard/charging_profile";
+const CHARGING_PROFILE: &str = "charging_profile";
+const CHARGING_PROFILES_AVAILABLE: &str = "charging_profiles_available";
+
+/// Charges to full capacity, the default of the firmware.
+const HIGH_CAPACITY_PROFILE: &str = "high_capacity";
+/// Keeps the charge low for the maximum lifespan.
+const CONSERVATION_PROFILE: &str = "stationary";
+
+/// Switches between the full capacity and the conservation profile.
+#[derive(Debug, Clone)]
+pub struct BatteryConservation {
+    dir: PathBuf,
+}
+
+impl BatteryConservation {
+    /// Returns `None` if the firmware doesn't have a conservation profile.
+    pub fn new() -> Option<Self> {
+        Self::with_dir(CHARGING_PROFILE_PATH)
+    }
+
+    fn with_dir(dir: impl AsRef<Path>) -> Option<Self> {
+        let dir = dir.as_ref();
+        let available = fs::read_to_string(dir.join(CHARGING_PROFILES_AVAILABLE)).ok()?;
+        let profiles: Vec<&str> = available.split_whitespace().collect();
+        (profiles.contains(&HIGH_CAPACITY_PROFILE) && profiles.contains(&CONSERVATION_PROFILE))
+            .then(|| Self {
+                dir: dir.to_owned(),
+            })
+    }
+
+    pub fn get(&self) -> io::Result<bool> {
+        let profile = fs::read_to_string(self.dir.join(CHARGING_PROFILE))?;
+        Ok(profile.trim() == CONSERVATION_PROFILE)
+    }
+
+    pub fn set(&self, enabled: bool) -> io::Result<()> {
+        let profile = if enabled {
+            CONSERVATION_PROFILE
+        } else {
+            HIGH_CAPACITY_PROFILE
+        };
+        fs::write(self.dir.join(CHARGING_PROFILE), profile)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::BatteryConservation;
+
+    #[test]
+    fn conservation_profile() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::write(
+            dir.join("charging_profiles_available"),
+            "high_capacity balanced stationary\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("charging_profile"), "high_capacity\n").unwrap();
+
+        let conservation = BatteryConservation::with_dir(dir).unwrap();
+        assert!(!conservation.get().unwrap());
+        conservation.set(true).unwrap();
+        assert!(conservation.get().unwrap());
+
+        std::fs::write(dir.join("charging_profiles_available"), "high_capacity\n").unwrap();
+        assert!(BatteryConservation::with_dir(dir).is_none());
+    }
+}
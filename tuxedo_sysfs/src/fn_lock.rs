@@ -0,0 +1,55 @@
+//! The Fn-lock of tuxedo_keyboard, which makes the function keys send
+//! F1-F12 without holding Fn. Only some Uniwill models expose it.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+const FN_LOCK_PATH: &str = "/sys/devices/platform/tuxedo_keyboard/fn_lock";
+
+#[derive(Debug, Clone)]
+pub struct FnLock {
+    path: PathBuf,
+}
+
+impl FnLock {
+    /// Returns `None` if the driver doesn't expose the Fn-lock.
+    pub fn new() -> Option<Self> {
+        Self::with_path(FN_LOCK_PATH)
+    }
+
+    fn with_path(path: impl AsRef<Path>) -> Option<Self> {
+        let path = path.as_ref();
+        path.exists().then(|| Self {
+            path: path.to_owned(),
+        })
+    }
+
+    pub fn get(&self) -> io::Result<bool> {
+        let state = fs::read_to_string(&self.path)?;
+        Ok(state.trim() != "0")
+    }
+
+    pub fn set(&self, enabled: bool) -> io::Result<()> {
+        fs::write(&self.path, if enabled { "1" } else { "0" })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FnLock;
+
+    #[test]
+    fn fn_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fn_lock");
+        assert!(FnLock::with_path(&path).is_none());
+
+        std::fs::write(&path, "0\n").unwrap();
+        let fn_lock = FnLock::with_path(&path).unwrap();
+        assert!(!fn_lock.get().unwrap());
+        fn_lock.set(true).unwrap();
+        assert!(fn_lock.get().unwrap());
+    }
+}
@@ -0,0 +1,172 @@
+//! The module parameters of tuxedo_keyboard, which control the backlight
+//! of keyboards that the driver doesn't register as LED devices. They can
+//! be changed at runtime instead of setting modprobe options.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use tailor_api::Color;
+
+const PARAMETERS_PATH: &str = "/sys/module/tuxedo_keyboard/parameters";
+
+/// The highest mode of the driver, the modes above 0 are animations.
+const MAX_MODE: u8 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zone {
+    Left,
+    Center,
+    Right,
+    Extra,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parameter {
+    /// Whether the backlight is on.
+    State,
+    Mode,
+    Brightness,
+    Color(Zone),
+}
+
+impl Parameter {
+    pub const ALL: [Self; 7] = [
+        Self::State,
+        Self::Mode,
+        Self::Brightness,
+        Self::Color(Zone::Left),
+        Self::Color(Zone::Center),
+        Self::Color(Zone::Right),
+        Self::Color(Zone::Extra),
+    ];
+
+    /// The name of the parameter in sysfs.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::State => "state",
+            Self::Mode => "mode",
+            Self::Brightness => "brightness",
+            Self::Color(Zone::Left) => "color_left",
+            Self::Color(Zone::Center) => "color_center",
+            Self::Color(Zone::Right) => "color_right",
+            Self::Color(Zone::Extra) => "color_extra",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|parameter| parameter.name() == name)
+    }
+
+    /// Convert a value to the format of the driver. States are `on` or
+    /// `off`, colors are hexadecimal RGB values like `ff0000`.
+    pub fn parse(&self, value: &str) -> Result<String, String> {
+        let value = value.trim();
+        match self {
+            Self::State => match value {
+                "on" | "1" => Ok("1".to_owned()),
+                "off" | "0" => Ok("0".to_owned()),
+                _ => Err(format!("Invalid state `{value}`, expected `on` or `off`")),
+            },
+            Self::Mode => match value.parse::<u8>() {
+                Ok(mode) if mode <= MAX_MODE => Ok(mode.to_string()),
+                _ => Err(format!("Invalid mode `{value}`, expected 0 to {MAX_MODE}")),
+            },
+            Self::Brightness => value
+                .parse::<u8>()
+                .map(|brightness| brightness.to_string())
+                .map_err(|_| format!("Invalid brightness `{value}`, expected 0 to 255")),
+            Self::Color(_) => value
+                .trim_start_matches('#')
+                .parse::<Color>()
+                .map(|Color { r, g, b }| format!("0x{r:02x}{g:02x}{b:02x}"))
+                .map_err(|_| format!("Invalid color `{value}`, expected e.g. `ff0000`")),
+        }
+    }
+
+    /// Convert a value of the driver to the format of [`Parameter::parse`].
+    fn format(&self, value: &str) -> io::Result<String> {
+        let value = value.trim();
+        let number = value.parse::<u32>().map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid value `{value}` of `{}`: {err}", self.name()),
+            )
+        })?;
+        Ok(match self {
+            Self::State if number == 0 => "off".to_owned(),
+            Self::State => "on".to_owned(),
+            Self::Mode | Self::Brightness => number.to_string(),
+            Self::Color(_) => format!("{:06x}", number & 0xff_ffff),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct KeyboardParameters {
+    dir: PathBuf,
+}
+
+impl KeyboardParameters {
+    /// Returns `None` if tuxedo_keyboard isn't loaded.
+    pub fn new() -> Option<Self> {
+        Self::with_dir(PARAMETERS_PATH)
+    }
+
+    fn with_dir(dir: impl AsRef<Path>) -> Option<Self> {
+        let dir = dir.as_ref();
+        dir.is_dir().then(|| Self {
+            dir: dir.to_owned(),
+        })
+    }
+
+    /// The parameters that the loaded driver has, which depends on the keyboard.
+    pub fn available(&self) -> Vec<Parameter> {
+        Parameter::ALL
+            .into_iter()
+            .filter(|parameter| self.dir.join(parameter.name()).exists())
+            .collect()
+    }
+
+    pub fn get(&self, parameter: Parameter) -> io::Result<String> {
+        let value = fs::read_to_string(self.dir.join(parameter.name()))?;
+        parameter.format(&value)
+    }
+
+    /// Write a value in the format of the driver, see [`Parameter::parse`].
+    pub fn set(&self, parameter: Parameter, value: &str) -> io::Result<()> {
+        fs::write(self.dir.join(parameter.name()), value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{KeyboardParameters, Parameter, Zone};
+
+    #[test]
+    fn parameters() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::write(dir.join("state"), "1\n").unwrap();
+        std::fs::write(dir.join("color_left"), "16711680\n").unwrap();
+
+        let parameters = KeyboardParameters::with_dir(dir).unwrap();
+        let left = Parameter::Color(Zone::Left);
+        assert_eq!(parameters.available(), [Parameter::State, left]);
+        assert_eq!(parameters.get(Parameter::State).unwrap(), "on");
+        assert_eq!(parameters.get(left).unwrap(), "ff0000");
+
+        assert_eq!(left.parse("#00ff00").unwrap(), "0x00ff00");
+        assert_eq!(
+            Parameter::Mode.parse("8").unwrap_err(),
+            "Invalid mode `8`, expected 0 to 7"
+        );
+        assert_eq!(
+            Parameter::from_name("color_extra"),
+            Some(Parameter::Color(Zone::Extra))
+        );
+    }
+}
@@ -1,13 +1,14 @@
 use std::{
     io,
     ops::{Index, IndexMut},
+    path::Path,
 };
 
 use tailor_api::Color;
 
-use crate::sysfs_util::{r_file, read_int_list, read_path_to_string, rw_file};
+use crate::attribute::{read_batch, Attribute};
 
-use super::{Collection, Controller};
+use super::{ite8291::Ite8291, Collection, Controller};
 
 const SYSFS_LED_PATH: &str = "/sys/class/leds";
 const BRIGHTNESS: &str = "brightness";
@@ -23,120 +24,16 @@ impl Collection {
 
         let mut dirs = tokio::fs::read_dir(SYSFS_LED_PATH).await?;
         while let Some(dir) = dirs.next_entry().await? {
-            let path = dir.path();
-            let file_name = path
-                .file_name()
-                .expect("The sysfs path must have a last segment");
-            let file_name_str = file_name.to_str().unwrap_or_default();
-
-            if file_name_str.contains("mmc") {
-                // Not a useful device, skip it.
-                continue;
-            }
-
-            let function = if let Some(function) = file_name_str.split(':').last() {
-                function.trim().to_owned()
-            } else {
-                tracing::warn!("Badly formatted led device: {:?}", file_name);
-                continue;
-            };
-
-            let device_name_path = path.join(DEVICE_NAME);
-            let device_name = if let Ok(name) = read_path_to_string(device_name_path).await {
-                name.trim().to_owned()
-            } else {
-                let device_modalias_path = path.join(DEVICE_MODALIAS);
-                if let Ok(name) = read_path_to_string(device_modalias_path).await {
-                    name.trim().to_owned()
-                } else {
-                    tracing::warn!("Could not find LED device name: {:?}", file_name);
-                    continue;
-                }
-            };
-
-            // Check for brightness file
-            let brightness_path = path.join(BRIGHTNESS);
-            let brightness_file = if let Ok(file) = rw_file(brightness_path).await {
-                file
-            } else {
-                // Not even basic support available -> skip device.
-                continue;
-            };
-
-            // Get maximum brightness
-            let max_brightness_path = path.join(MAX_BRIGHTNESS);
-            let max_brightness = if let Ok(mut file) = r_file(max_brightness_path).await {
-                if let Ok(values) = read_int_list(&mut file).await {
-                    values[0]
-                } else {
-                    tracing::warn!("Brightness file can't be read: {:?}", file_name);
-                    continue;
-                }
-            } else {
-                // Not even basic support available -> skip device.
-                continue;
-            };
-
-            if max_brightness < 2 {
-                // Not even basic support available -> skip device.
-                continue;
+            if let Some(controller) = Controller::from_path(&dir.path()).await? {
+                controllers.push(controller);
             }
+        }
 
-            let multi_index_path = path.join(MULTI_INDEX);
-            let rgb_support = tokio::fs::read_to_string(multi_index_path)
-                .await
-                .map(|content| content.trim().to_lowercase() == "red green blue")
-                .unwrap_or_default();
-
-            if rgb_support {
-                // Get intensities
-                let intensities_path = path.join(MULTI_INTENSITIES);
-                let (intensities_file, intensities) =
-                    if let Ok(mut file) = rw_file(intensities_path).await {
-                        if let Ok(values) = read_int_list(&mut file).await {
-                            (file, values)
-                        } else {
-                            tracing::warn!("Intensities file can't be read: {:?}", file_name);
-                            continue;
-                        }
-                    } else {
-                        // Should be there for an RGB device
-                        tracing::warn!(
-                            "RGB device should have multiple intensities: {:?}",
-                            file_name
-                        );
-                        continue;
-                    };
-
-                if intensities.len() == 3 {
-                    // Push controller with RGB capabilities
-                    controllers.push(
-                        Controller::new_rgb(
-                            max_brightness,
-                            device_name,
-                            function,
-                            brightness_file,
-                            intensities_file,
-                        )
-                        .await?,
-                    );
-                } else {
-                    // Should be 3 for an RGB device
-                    tracing::warn!("RGB device should have 3 intensities: {:?}", file_name);
-                    continue;
-                }
-            } else {
-                // Push controller with monochrome capabilities
-                controllers.push(
-                    Controller::new_monochrome(
-                        max_brightness,
-                        device_name,
-                        function,
-                        brightness_file,
-                    )
-                    .await?,
-                );
-            }
+        // Only used if no kernel driver handles the controller.
+        match Ite8291::find() {
+            Ok(Some(device)) => controllers.push(Controller::new_ite8291(device)),
+            Ok(None) => {}
+            Err(err) => tracing::warn!("Failed to open the ITE 8291 controller: `{err}`"),
         }
 
         Ok(Self { controllers })
@@ -184,6 +81,128 @@ impl IndexMut<usize> for Collection {
     }
 }
 
+impl Controller {
+    /// Load the LED device at the given sysfs path.
+    /// Returns `None` if the device isn't supported.
+    pub async fn from_path(path: &Path) -> Result<Option<Self>, io::Error> {
+        let file_name = path
+            .file_name()
+            .expect("The sysfs path must have a last segment");
+        let file_name_str = file_name.to_str().unwrap_or_default();
+
+        if file_name_str.contains("mmc") {
+            // Not a useful device, skip it.
+            return Ok(None);
+        }
+
+        let function = if let Some(function) = file_name_str.split(':').next_back() {
+            function.trim().to_owned()
+        } else {
+            tracing::warn!("Badly formatted led device: {:?}", file_name);
+            return Ok(None);
+        };
+
+        // The attributes that describe the device are read together.
+        let [device_name, device_modalias, max_brightness, multi_index]: [io::Result<String>; 4] =
+            read_batch(
+                &[DEVICE_NAME, DEVICE_MODALIAS, MAX_BRIGHTNESS, MULTI_INDEX]
+                    .map(|name| path.join(name)),
+            )
+            .await
+            .try_into()
+            .expect("One result per path");
+
+        let device_name = if let Ok(name) = device_name.or(device_modalias) {
+            name
+        } else {
+            tracing::warn!("Could not find LED device name: {:?}", file_name);
+            return Ok(None);
+        };
+
+        // Check for brightness file
+        let brightness_path = path.join(BRIGHTNESS);
+        let brightness_file = if let Ok(file) = Attribute::open_rw(&brightness_path).await {
+            file
+        } else {
+            // Not even basic support available -> skip device.
+            return Ok(None);
+        };
+
+        // Get maximum brightness
+        let max_brightness = match max_brightness {
+            Ok(content) => {
+                if let Some(Ok(value)) = content.split_whitespace().next().map(str::parse::<u32>) {
+                    value
+                } else {
+                    tracing::warn!("Brightness file can't be read: {:?}", file_name);
+                    return Ok(None);
+                }
+            }
+            // Not even basic support available -> skip device.
+            Err(_) => return Ok(None),
+        };
+
+        if max_brightness < 2 {
+            // Not even basic support available -> skip device.
+            return Ok(None);
+        }
+
+        let rgb_support = multi_index
+            .map(|content| content.to_lowercase() == "red green blue")
+            .unwrap_or_default();
+
+        if rgb_support {
+            // Get intensities
+            let intensities_path = path.join(MULTI_INTENSITIES);
+            let (intensities_file, intensities) =
+                if let Ok(file) = Attribute::open_rw(intensities_path).await {
+                    if let Ok(values) = file.read_int_list().await {
+                        (file, values)
+                    } else {
+                        tracing::warn!("Intensities file can't be read: {:?}", file_name);
+                        return Ok(None);
+                    }
+                } else {
+                    // Should be there for an RGB device
+                    tracing::warn!(
+                        "RGB device should have multiple intensities: {:?}",
+                        file_name
+                    );
+                    return Ok(None);
+                };
+
+            if intensities.len() == 3 {
+                // Controller with RGB capabilities
+                Controller::new_rgb(
+                    max_brightness,
+                    device_name,
+                    function,
+                    brightness_path,
+                    brightness_file,
+                    intensities_file,
+                )
+                .await
+                .map(Some)
+            } else {
+                // Should be 3 for an RGB device
+                tracing::warn!("RGB device should have 3 intensities: {:?}", file_name);
+                Ok(None)
+            }
+        } else {
+            // Controller with monochrome capabilities
+            Controller::new_monochrome(
+                max_brightness,
+                device_name,
+                function,
+                brightness_path,
+                brightness_file,
+            )
+            .await
+            .map(Some)
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use tailor_api::Color;
@@ -1,29 +1,39 @@
-use std::io;
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
 
 use tailor_api::Color;
 use tailor_api::LedControllerMode;
 
-use crate::sysfs_util::{read_int_list, write_string};
+use crate::attribute::Attribute;
 
-use super::Controller;
+use super::{
+    ite8291::{self, Ite8291},
+    Backend, Controller,
+};
 
 impl Controller {
     pub async fn new_rgb(
         max_brightness: u32,
         device_name: String,
         function: String,
-        mut brightness_file: tokio_uring::fs::File,
-        intensities_file: tokio_uring::fs::File,
+        brightness_path: PathBuf,
+        brightness_file: Attribute,
+        intensities_file: Attribute,
     ) -> Result<Self, io::Error> {
         // Set brightness to 100% so the individual intensities represent their colors without additional scaling.
-        write_string(&mut brightness_file, max_brightness.to_string()).await?;
+        brightness_file.write(max_brightness.to_string()).await?;
 
         Ok(Self {
             max_brightness,
             device_name,
             function,
-            brightness_file,
-            intensities_file: Some(intensities_file),
+            backend: Backend::Sysfs {
+                brightness_path,
+                brightness_file,
+                intensities_file: Some(intensities_file),
+            },
         })
     }
 
@@ -31,56 +41,125 @@ impl Controller {
         max_brightness: u32,
         device_name: String,
         function: String,
-        brightness_file: tokio_uring::fs::File,
+        brightness_path: PathBuf,
+        brightness_file: Attribute,
     ) -> Result<Self, io::Error> {
         Ok(Self {
             max_brightness,
             device_name,
             function,
-            brightness_file,
-            intensities_file: None,
+            backend: Backend::Sysfs {
+                brightness_path,
+                brightness_file,
+                intensities_file: None,
+            },
         })
     }
 
+    /// A per-key controller that is driven via hidraw. All keys show the same color.
+    pub fn new_ite8291(device: Ite8291) -> Self {
+        Self {
+            max_brightness: 255,
+            device_name: format!("ite_{:04x}", device.product_id()),
+            function: "kbd_backlight".to_owned(),
+            backend: Backend::Ite8291 {
+                device,
+                color: None,
+            },
+        }
+    }
+
     pub async fn set_color(&mut self, color: &Color) -> Result<(), io::Error> {
-        let Self {
-            max_brightness,
-            brightness_file,
-            intensities_file,
-            ..
-        } = self;
-
-        if let Some(intensities) = intensities_file {
-            write_string(intensities, color.sysfs_rgb_string(*max_brightness)).await
-        } else {
-            write_string(
-                brightness_file,
-                color.sysfs_monochrome_string(*max_brightness),
-            )
-            .await
+        match &mut self.backend {
+            Backend::Sysfs {
+                intensities_file: Some(intensities),
+                ..
+            } => {
+                intensities
+                    .write(color.sysfs_rgb_string(self.max_brightness))
+                    .await
+            }
+            Backend::Sysfs {
+                brightness_file, ..
+            } => {
+                brightness_file
+                    .write(color.sysfs_monochrome_string(self.max_brightness))
+                    .await
+            }
+            Backend::Ite8291 {
+                device,
+                color: current,
+            } => {
+                device.set_color(color)?;
+                *current = Some(color.clone());
+                Ok(())
+            }
+        }
+    }
+
+    /// The number of keys whose colors [`Controller::set_key_colors`]
+    /// sets at once. `None` if the device only has a single color.
+    pub fn key_count(&self) -> Option<usize> {
+        match &self.backend {
+            Backend::Sysfs { .. } => None,
+            Backend::Ite8291 { .. } => Some(ite8291::ROWS * ite8291::COLUMNS),
+        }
+    }
+
+    /// Set the color of each key, see [`Controller::key_count`].
+    pub async fn set_key_colors(&mut self, colors: &[Color]) -> Result<(), io::Error> {
+        match &mut self.backend {
+            Backend::Sysfs { .. } => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "The device only has a single color",
+            )),
+            Backend::Ite8291 { device, color } => {
+                device.set_keys(colors)?;
+                *color = colors.first().cloned();
+                Ok(())
+            }
         }
     }
 
     pub async fn get_color(&mut self) -> Result<Color, io::Error> {
-        let Self {
-            max_brightness,
-            brightness_file,
-            intensities_file,
-            ..
-        } = self;
-
-        if let Some(intensities) = intensities_file {
-            let values = read_int_list(intensities).await?;
-            let values: [u32; 3] = values.try_into().map_err(|_| {
-                io::Error::new(io::ErrorKind::InvalidData, "Invalid number of values")
-            })?;
-            Ok(Color::from_sysfs_rgb_value(values, *max_brightness))
-        } else {
-            let value = read_int_list(brightness_file).await?[0];
-            Ok(Color::from_sysfs_rgb_value(
-                [value, value, value],
-                *max_brightness,
-            ))
+        match &self.backend {
+            Backend::Sysfs {
+                intensities_file: Some(intensities),
+                ..
+            } => {
+                let values = intensities.read_int_list().await?;
+                let values: [u32; 3] = values.try_into().map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidData, "Invalid number of values")
+                })?;
+                Ok(Color::from_sysfs_rgb_value(values, self.max_brightness))
+            }
+            Backend::Sysfs {
+                brightness_file, ..
+            } => {
+                let value = brightness_file.read_int_list().await?[0];
+                Ok(Color::from_sysfs_rgb_value(
+                    [value, value, value],
+                    self.max_brightness,
+                ))
+            }
+            Backend::Ite8291 { color, .. } => color
+                .clone()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "No color was set yet")),
+        }
+    }
+
+    pub fn max_brightness(&self) -> u32 {
+        self.max_brightness
+    }
+
+    /// The brightness attribute, which the brightness keys of the keyboard
+    /// change on some devices. `None` if the device isn't driven via sysfs.
+    pub fn brightness_path(&self) -> Option<&Path> {
+        match &self.backend {
+            Backend::Sysfs {
+                brightness_path, ..
+            } => Some(brightness_path),
+            Backend::Ite8291 { .. } => None,
         }
     }
 
@@ -93,10 +172,12 @@ impl Controller {
     }
 
     pub fn mode(&self) -> LedControllerMode {
-        if self.intensities_file.is_some() {
-            LedControllerMode::Rgb
-        } else {
-            LedControllerMode::Monochrome
+        match &self.backend {
+            Backend::Sysfs {
+                intensities_file: None,
+                ..
+            } => LedControllerMode::Monochrome,
+            _ => LedControllerMode::Rgb,
         }
     }
 }
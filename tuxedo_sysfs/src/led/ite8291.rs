@@ -0,0 +1,182 @@
+//! A userspace driver for the ITE 8291 per-key RGB controllers of Stellaris
+//! models, for systems without the ite_8291 kernel driver. The controller is
+//! accessed via hidraw: commands are 8 byte feature reports and the colors
+//! are uploaded row by row as output reports.
+
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    os::fd::AsRawFd,
+    path::Path,
+};
+
+use tailor_api::Color;
+
+const SYSFS_HIDRAW_PATH: &str = "/sys/class/hidraw";
+const DEV_PATH: &str = "/dev";
+/// The kernel driver for HID devices without a specific driver.
+const GENERIC_DRIVER: &str = "hid-generic";
+
+const VENDOR_ID: u32 = 0x048d;
+/// The ITE 8291 revisions with the per-key protocol. The ITE 8297
+/// has a single zone and a different protocol, it's not supported.
+const PRODUCT_IDS: &[u32] = &[0x6004, 0x6006, 0xce00];
+
+pub const ROWS: usize = 6;
+pub const COLUMNS: usize = 21;
+/// The highest brightness of the firmware.
+const MAX_BRIGHTNESS: u8 = 0x32;
+
+const SET_EFFECT: u8 = 0x08;
+const SET_ROW_INDEX: u8 = 0x16;
+const POWER_ON: u8 = 0x02;
+const POWER_OFF: u8 = 0x01;
+/// The effect that shows the uploaded colors.
+const USER_MODE: u8 = 0x33;
+
+/// The report id, a padding byte, the blue, green and
+/// red values of each column and another padding byte.
+const ROW_BUFFER_LEN: usize = 3 + 3 * COLUMNS;
+const BLUE_OFFSET: usize = 2;
+const GREEN_OFFSET: usize = BLUE_OFFSET + COLUMNS;
+const RED_OFFSET: usize = GREEN_OFFSET + COLUMNS;
+
+nix::ioctl_readwrite_buf!(hid_set_feature, b'H', 0x06, u8);
+
+/// The colors of all keys, row by row from the bottom left.
+pub type Frame = [[Color; COLUMNS]; ROWS];
+
+#[derive(Debug)]
+pub struct Ite8291 {
+    file: File,
+    product_id: u32,
+}
+
+impl Ite8291 {
+    /// Find a controller that isn't bound to a kernel driver
+    /// other than `hid-generic`. Returns `None` if there's none.
+    pub fn find() -> io::Result<Option<Self>> {
+        let dirs = match fs::read_dir(SYSFS_HIDRAW_PATH) {
+            Ok(dirs) => dirs,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        for dir in dirs {
+            let path = dir?.path();
+            let Some(product_id) = supported_product_id(&path) else {
+                continue;
+            };
+            if !is_generic(&path) {
+                tracing::debug!("ITE 8291 {path:?} is handled by a kernel driver");
+                continue;
+            }
+            let Some(name) = path.file_name() else {
+                continue;
+            };
+            let file = File::options()
+                .read(true)
+                .write(true)
+                .open(Path::new(DEV_PATH).join(name))?;
+            tracing::info!("Found ITE controller {product_id:04x} at {path:?}");
+            return Ok(Some(Self { file, product_id }));
+        }
+        Ok(None)
+    }
+
+    pub fn product_id(&self) -> u32 {
+        self.product_id
+    }
+
+    /// Show the colors of a frame.
+    pub fn set_frame(&mut self, frame: &Frame) -> io::Result<()> {
+        self.send_command([SET_EFFECT, POWER_ON, USER_MODE, 0, MAX_BRIGHTNESS, 0, 0, 0])?;
+        for (index, row) in frame.iter().enumerate() {
+            self.send_command([SET_ROW_INDEX, 0, index as u8, 0, 0, 0, 0, 0])?;
+            self.file.write_all(&row_buffer(row))?;
+        }
+        Ok(())
+    }
+
+    /// Show a color for each key, numbered row by row. Missing keys are off.
+    pub fn set_keys(&mut self, colors: &[Color]) -> io::Result<()> {
+        let off = Color { r: 0, g: 0, b: 0 };
+        let frame = std::array::from_fn(|row| {
+            std::array::from_fn(|column| colors.get(row * COLUMNS + column).unwrap_or(&off).clone())
+        });
+        self.set_frame(&frame)
+    }
+
+    /// Show the same color on all keys.
+    pub fn set_color(&mut self, color: &Color) -> io::Result<()> {
+        if *color == (Color { r: 0, g: 0, b: 0 }) {
+            self.send_command([SET_EFFECT, POWER_OFF, 0, 0, 0, 0, 0, 0])
+        } else {
+            self.set_frame(&std::array::from_fn(|_| {
+                std::array::from_fn(|_| color.clone())
+            }))
+        }
+    }
+
+    fn send_command(&self, mut command: [u8; 8]) -> io::Result<()> {
+        // SAFETY: The buffer is valid for its whole length.
+        unsafe { hid_set_feature(self.file.as_raw_fd(), &mut command) }?;
+        Ok(())
+    }
+}
+
+fn row_buffer(row: &[Color; COLUMNS]) -> [u8; ROW_BUFFER_LEN] {
+    let mut buffer = [0; ROW_BUFFER_LEN];
+    for (column, color) in row.iter().enumerate() {
+        buffer[BLUE_OFFSET + column] = color.b;
+        buffer[GREEN_OFFSET + column] = color.g;
+        buffer[RED_OFFSET + column] = color.r;
+    }
+    buffer
+}
+
+fn supported_product_id(hidraw: &Path) -> Option<u32> {
+    let uevent = fs::read_to_string(hidraw.join("device/uevent")).ok()?;
+    let (vendor_id, product_id) = parse_hid_id(&uevent)?;
+    (vendor_id == VENDOR_ID && PRODUCT_IDS.contains(&product_id)).then_some(product_id)
+}
+
+fn is_generic(hidraw: &Path) -> bool {
+    fs::read_link(hidraw.join("device/driver"))
+        .ok()
+        .as_deref()
+        .and_then(Path::file_name)
+        .is_some_and(|driver| driver == GENERIC_DRIVER)
+}
+
+/// The vendor and product id of a `HID_ID=0003:0000048D:00006004` line.
+fn parse_hid_id(uevent: &str) -> Option<(u32, u32)> {
+    let id = uevent
+        .lines()
+        .find_map(|line| line.strip_prefix("HID_ID="))?;
+    let mut parts = id.split(':').skip(1);
+    let vendor_id = u32::from_str_radix(parts.next()?, 16).ok()?;
+    let product_id = u32::from_str_radix(parts.next()?, 16).ok()?;
+    Some((vendor_id, product_id))
+}
+
+#[cfg(test)]
+mod test {
+    use tailor_api::Color;
+
+    use super::{parse_hid_id, row_buffer, COLUMNS, ROW_BUFFER_LEN};
+
+    #[test]
+    fn protocol() {
+        let uevent = "DRIVER=hid-generic\nHID_ID=0003:0000048D:0000CE00\nHID_NAME=ITE Tech. Inc. ITE Device(8291)\n";
+        assert_eq!(parse_hid_id(uevent), Some((0x048d, 0xce00)));
+        assert_eq!(parse_hid_id("DRIVER=hid-generic\n"), None);
+
+        let mut row: [Color; COLUMNS] = std::array::from_fn(|_| Color { r: 0, g: 0, b: 0 });
+        row[1] = Color { r: 1, g: 2, b: 3 };
+        let buffer = row_buffer(&row);
+        assert_eq!(buffer.len(), ROW_BUFFER_LEN);
+        assert_eq!(buffer[3], 3);
+        assert_eq!(buffer[3 + COLUMNS], 2);
+        assert_eq!(buffer[3 + 2 * COLUMNS], 1);
+    }
+}
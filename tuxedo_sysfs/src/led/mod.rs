@@ -1,5 +1,10 @@
+use std::path::PathBuf;
+
+use crate::attribute::Attribute;
+
 mod collection;
 mod controller;
+pub mod ite8291;
 
 /// A collection of controllers for LED devices.
 /// Stores a [`Vec`] of [`Controller`] and initializes by
@@ -16,6 +21,20 @@ pub struct Controller {
     pub device_name: String,
     pub function: String,
     max_brightness: u32,
-    brightness_file: tokio_uring::fs::File,
-    intensities_file: Option<tokio_uring::fs::File>,
+    backend: Backend,
+}
+
+#[derive(Debug)]
+enum Backend {
+    Sysfs {
+        brightness_path: PathBuf,
+        brightness_file: Attribute,
+        intensities_file: Option<Attribute>,
+    },
+    /// Controllers without a kernel driver. They don't report
+    /// their colors, so the color that was set last is kept.
+    Ite8291 {
+        device: ite8291::Ite8291,
+        color: Option<tailor_api::Color>,
+    },
 }
@@ -1,2 +1,9 @@
+pub mod attribute;
+pub mod charging;
+pub mod fn_lock;
+pub mod keyboard;
 pub mod led;
-pub(crate) mod sysfs_util;
+pub mod pcie_aspm;
+pub mod platform;
+pub mod power_supply;
+pub mod power_tuning;
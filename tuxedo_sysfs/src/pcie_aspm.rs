@@ -0,0 +1,96 @@
+//! The PCIe Active State Power Management (ASPM) policy of the kernel.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use tailor_api::{AspmPolicy, HardwareWrite};
+
+const POLICY_PATH: &str = "/sys/module/pcie_aspm/parameters/policy";
+
+#[derive(Debug, Clone)]
+pub struct PcieAspm {
+    path: PathBuf,
+}
+
+impl PcieAspm {
+    /// Returns `None` if the kernel was built without ASPM support.
+    pub fn new() -> Option<Self> {
+        Self::with_path(POLICY_PATH)
+    }
+
+    fn with_path(path: impl AsRef<Path>) -> Option<Self> {
+        let path = path.as_ref();
+        path.exists().then(|| Self {
+            path: path.to_owned(),
+        })
+    }
+
+    /// The kernel lists all policies and marks the active one,
+    /// e.g. `[default] performance powersave powersupersave`.
+    pub fn get(&self) -> io::Result<AspmPolicy> {
+        let policies = fs::read_to_string(&self.path)?;
+        policies
+            .split_whitespace()
+            .find_map(|policy| policy.strip_prefix('[')?.strip_suffix(']'))
+            .and_then(AspmPolicy::from_name)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Unknown ASPM policy `{}`", policies.trim()),
+                )
+            })
+    }
+
+    pub fn set(&self, policy: AspmPolicy) -> io::Result<()> {
+        fs::write(&self.path, policy.name())
+    }
+
+    /// The write of [`Self::set`], `None` if the policy is already active.
+    pub fn preview(&self, policy: AspmPolicy) -> Option<HardwareWrite> {
+        // The file lists all policies, only the active one is interesting.
+        let active = self.get().ok();
+        (active != Some(policy)).then(|| HardwareWrite {
+            device: self
+                .path
+                .parent()
+                .unwrap_or(&self.path)
+                .display()
+                .to_string(),
+            attribute: "policy".to_owned(),
+            old: active.map(|active| active.name().to_owned()),
+            new: policy.name().to_owned(),
+        })
+    }
+
+    /// Whether the policy can be changed. The kernel refuses changes if ASPM
+    /// is disabled by the firmware or with `pcie_aspm=off`, so this writes
+    /// the active policy again.
+    pub fn is_writable(&self) -> bool {
+        self.get().and_then(|policy| self.set(policy)).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tailor_api::AspmPolicy;
+
+    use super::PcieAspm;
+
+    #[test]
+    fn policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("policy");
+        assert!(PcieAspm::with_path(&path).is_none());
+
+        std::fs::write(&path, "[default] performance powersave powersupersave\n").unwrap();
+        let aspm = PcieAspm::with_path(&path).unwrap();
+        assert_eq!(aspm.get().unwrap(), AspmPolicy::Default);
+        assert!(aspm.is_writable());
+
+        // Sysfs shows the brackets on read, a plain file keeps what was written.
+        aspm.set(AspmPolicy::Powersupersave).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "powersupersave");
+    }
+}
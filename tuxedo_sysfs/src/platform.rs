@@ -0,0 +1,427 @@
+//! Hardware support for newer TUXEDO platforms (nb04 and nb05)
+//! that are controlled by dedicated tuxedo_drivers sysfs interfaces
+//! instead of the tuxedo_io ioctl interface.
+//!
+//! Lighting on these platforms is exposed through the regular LED
+//! class and is therefore handled by [`crate::led::Collection`].
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use tuxedo_ioctl::hal::{
+    traits::{FanControl, HardwareDevice, PerformanceProfiles},
+    Capabilities, FanDuties, IoctlError, IoctlResult,
+};
+
+use crate::attribute::CachedAttributes;
+
+const SYSFS_PLATFORM_PATH: &str = "/sys/bus/platform/devices";
+const SYSFS_HWMON_PATH: &str = "/sys/class/hwmon";
+const DMI_BOARD_NAME_PATH: &str = "/sys/class/dmi/id/board_name";
+
+const NB05_FAN_CONTROL: &str = "tuxedo_fan_control";
+
+const PLATFORM_PROFILE: &str = "platform_profile";
+const PLATFORM_PROFILE_CHOICES: &str = "platform_profile_choices";
+const DEFAULT_PLATFORM_PROFILE: &str = "balanced";
+
+const MAX_FAN_PWM: u8 = 0xff;
+/// Value of `fan*_pwm_enable` for manual fan control.
+const PWM_ENABLE_MANUAL: &str = "1";
+/// Value of `fan*_pwm_enable` for automatic (firmware) fan control.
+const PWM_ENABLE_AUTO: &str = "2";
+
+/// The tuxedo_drivers platform interfaces that are supported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlatformInterface {
+    Nb04,
+    Nb05,
+}
+
+impl PlatformInterface {
+    const ALL: [Self; 2] = [Self::Nb04, Self::Nb05];
+
+    fn id(&self) -> &'static str {
+        match self {
+            Self::Nb04 => "tuxedo_nb04",
+            Self::Nb05 => "tuxedo_nb05",
+        }
+    }
+
+    fn sensors_name(&self) -> String {
+        format!("{}_sensors", self.id())
+    }
+
+    fn power_profiles_path(&self) -> PathBuf {
+        Path::new(SYSFS_PLATFORM_PATH).join(format!("{}_power_profiles", self.id()))
+    }
+
+    fn fan_control_path(&self) -> Option<PathBuf> {
+        match self {
+            // Fans follow the power profile on nb04 devices.
+            Self::Nb04 => None,
+            Self::Nb05 => Some(Path::new(SYSFS_PLATFORM_PATH).join(NB05_FAN_CONTROL)),
+        }
+    }
+}
+
+/// A [`HardwareDevice`] implementation for platforms
+/// that are controlled via tuxedo_drivers sysfs interfaces.
+#[derive(Debug)]
+pub struct PlatformHardware {
+    interface: PlatformInterface,
+    /// The hwmon directory of the platform sensors.
+    sensors: PathBuf,
+    /// The directory with PWM controls, if the fans can be controlled.
+    fan_control: Option<PathBuf>,
+    /// The directory that contains the platform profile attributes.
+    power_profiles: Option<PathBuf>,
+    num_of_fans: u8,
+    duties: FanDuties,
+    /// The fan runtimes read the sensors every tick.
+    attributes: CachedAttributes,
+}
+
+impl PlatformHardware {
+    /// Detect a supported platform interface.
+    pub fn new() -> IoctlResult<Self> {
+        for interface in PlatformInterface::ALL {
+            if let Some(sensors) = find_hwmon(&interface.sensors_name())? {
+                let this = Self::with_paths(
+                    interface,
+                    sensors,
+                    interface.fan_control_path(),
+                    Some(interface.power_profiles_path()),
+                );
+                tracing::debug!("Detected platform interface: {this:?}");
+                return Ok(this);
+            }
+        }
+
+        Err(IoctlError::DevNotAvailable)
+    }
+
+    /// Use the given directories, controls that don't exist are left out.
+    fn with_paths(
+        interface: PlatformInterface,
+        sensors: PathBuf,
+        fan_control: Option<PathBuf>,
+        power_profiles: Option<PathBuf>,
+    ) -> Self {
+        let fan_control = fan_control.filter(|path| path.join(fan_attribute(0, "pwm")).exists());
+        let power_profiles = power_profiles.filter(|path| path.join(PLATFORM_PROFILE).exists());
+
+        let mut this = Self {
+            interface,
+            sensors,
+            fan_control,
+            power_profiles,
+            num_of_fans: 0,
+            duties: FanDuties::default(),
+            attributes: CachedAttributes::new(),
+        };
+
+        // Only show fans that are actually available and can be controlled
+        if this.fan_control.is_some() {
+            while this
+                .sensors
+                .join(fan_attribute(this.num_of_fans, "input"))
+                .exists()
+            {
+                this.num_of_fans += 1;
+            }
+        }
+        this
+    }
+
+    pub fn interface(&self) -> PlatformInterface {
+        self.interface
+    }
+
+    /// Fan control is only available on nb05 and
+    /// performance profiles depend on the firmware.
+    pub fn capabilities(self: &Arc<Self>) -> Capabilities {
+        Capabilities {
+            fans: self
+                .fan_control
+                .is_some()
+                .then(|| self.clone() as Arc<dyn FanControl>),
+            performance_profiles: self
+                .power_profiles
+                .is_some()
+                .then(|| self.clone() as Arc<dyn PerformanceProfiles>),
+            webcam: None,
+            touchpad: None,
+            tdp: None,
+        }
+    }
+
+    fn fan_control(&self) -> IoctlResult<&Path> {
+        self.fan_control.as_deref().ok_or(IoctlError::NotAvailable)
+    }
+
+    fn power_profiles(&self) -> IoctlResult<&Path> {
+        self.power_profiles
+            .as_deref()
+            .ok_or(IoctlError::NotAvailable)
+    }
+
+    fn check_fan(&self, fan: u8) -> IoctlResult<()> {
+        if fan < self.num_of_fans {
+            Ok(())
+        } else {
+            Err(IoctlError::DevNotAvailable)
+        }
+    }
+}
+
+impl HardwareDevice for PlatformHardware {
+    fn device_interface_id_str(&self) -> IoctlResult<String> {
+        Ok(self.interface.id().to_owned())
+    }
+
+    fn device_model_id_str(&self) -> IoctlResult<String> {
+        Ok(self.attributes.read_string(DMI_BOARD_NAME_PATH)?)
+    }
+
+    fn set_enable_mode_set(&self, _enabled: bool) -> IoctlResult<()> {
+        // Not required for sysfs based platforms.
+        Ok(())
+    }
+}
+
+impl FanControl for PlatformHardware {
+    fn get_number_fans(&self) -> u8 {
+        self.num_of_fans
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn set_fans_auto(&self) -> IoctlResult<()> {
+        // Without fan control, the firmware is always in charge.
+        if let Some(fan_control) = &self.fan_control {
+            for fan in 0..self.num_of_fans {
+                self.attributes.write(
+                    fan_control.join(fan_attribute(fan, "pwm_enable")),
+                    PWM_ENABLE_AUTO,
+                )?;
+            }
+        }
+        self.duties.clear();
+        Ok(())
+    }
+
+    fn get_fan_speed_raw_max(&self) -> u8 {
+        MAX_FAN_PWM
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn set_fan_speed_raw(&self, fan: u8, fan_speed_raw: u8) -> IoctlResult<()> {
+        self.check_fan(fan)?;
+        let fan_control = self.fan_control()?;
+
+        self.attributes.write(
+            fan_control.join(fan_attribute(fan, "pwm_enable")),
+            PWM_ENABLE_MANUAL,
+        )?;
+        self.attributes.write(
+            fan_control.join(fan_attribute(fan, "pwm")),
+            fan_speed_raw.to_string(),
+        )?;
+        self.duties.set(fan, fan_speed_raw);
+        tracing::trace!("Set fan {fan} speed raw to {fan_speed_raw}");
+        Ok(())
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn get_fan_speed_raw(&self, fan: u8) -> IoctlResult<u8> {
+        self.check_fan(fan)?;
+        let fan_control = self.fan_control()?;
+
+        let fan_speed_raw: u32 = self
+            .attributes
+            .read_value(fan_control.join(fan_attribute(fan, "pwm")))?;
+        tracing::trace!("Fan {fan} speed raw is {fan_speed_raw}");
+        Ok(fan_speed_raw.min(u32::from(MAX_FAN_PWM)) as u8)
+    }
+
+    fn get_fan_duty_raw(&self, fan: u8) -> Option<u8> {
+        self.duties.get(fan)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn get_fan_temperature(&self, fan: u8) -> IoctlResult<u8> {
+        self.check_fan(fan)?;
+
+        // Some platforms only have a single sensor for all fans.
+        let mut path = self.sensors.join(format!("temp{}_input", fan + 1));
+        if !path.exists() {
+            path = self.sensors.join("temp1_input");
+        }
+
+        // hwmon reports temperatures in millidegree Celsius
+        let temp: i32 = self.attributes.read_value(path)?;
+        let temp = (temp / 1000).clamp(0, u8::MAX as i32) as u8;
+        tracing::trace!("Fan {fan} temperature is {temp} C");
+        Ok(temp)
+    }
+
+    fn get_fans_min_speed(&self) -> IoctlResult<u8> {
+        Ok(20)
+    }
+
+    fn get_fans_off_available(&self) -> IoctlResult<bool> {
+        Ok(false)
+    }
+}
+
+impl PerformanceProfiles for PlatformHardware {
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn get_available_odm_performance_profiles(&self) -> IoctlResult<Vec<String>> {
+        let choices = self
+            .attributes
+            .read_string(self.power_profiles()?.join(PLATFORM_PROFILE_CHOICES))?;
+        let profiles = choices.split_whitespace().map(ToOwned::to_owned).collect();
+        tracing::trace!("Available performance profiles: {profiles:?}");
+        Ok(profiles)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn set_odm_performance_profile(&self, performance_profile: &str) -> IoctlResult<()> {
+        if self
+            .get_available_odm_performance_profiles()?
+            .iter()
+            .any(|profile| profile == performance_profile)
+        {
+            self.attributes.write(
+                self.power_profiles()?.join(PLATFORM_PROFILE),
+                performance_profile,
+            )?;
+            tracing::trace!("Set performance profiles: {performance_profile}");
+            Ok(())
+        } else {
+            Err(IoctlError::InvalidArgs)
+        }
+    }
+
+    fn get_default_odm_performance_profile(&self) -> IoctlResult<String> {
+        let profiles = self.get_available_odm_performance_profiles()?;
+        if profiles.iter().any(|name| name == DEFAULT_PLATFORM_PROFILE) {
+            Ok(DEFAULT_PLATFORM_PROFILE.to_owned())
+        } else {
+            profiles.into_iter().next().ok_or(IoctlError::NotAvailable)
+        }
+    }
+}
+
+/// Name of a fan attribute, e.g. `fan1_pwm` for the first fan.
+fn fan_attribute(fan: u8, attribute: &str) -> String {
+    format!("fan{}_{attribute}", fan + 1)
+}
+
+/// Find the hwmon directory with the given name.
+fn find_hwmon(name: &str) -> Result<Option<PathBuf>, io::Error> {
+    let dirs = match fs::read_dir(SYSFS_HWMON_PATH) {
+        Ok(dirs) => dirs,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+
+    for dir in dirs {
+        let path = dir?.path();
+        if read_string(path.join("name")).is_ok_and(|hwmon_name| hwmon_name == name) {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
+fn read_string(path: impl AsRef<Path>) -> Result<String, io::Error> {
+    fs::read_to_string(path).map(|content| content.trim().to_owned())
+}
+
+#[cfg(test)]
+mod test {
+    use tuxedo_ioctl::hal::{
+        traits::{FanControl, PerformanceProfiles},
+        IoctlError,
+    };
+
+    use super::{PlatformHardware, PlatformInterface};
+
+    #[test]
+    fn platform_hardware() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let sensors = dir.join("hwmon3");
+        let fan_control = dir.join("tuxedo_fan_control");
+        let power_profiles = dir.join("tuxedo_nb05_power_profiles");
+        for path in [&sensors, &fan_control, &power_profiles] {
+            std::fs::create_dir_all(path).unwrap();
+        }
+        let write =
+            |path: std::path::PathBuf, content: &str| std::fs::write(path, content).unwrap();
+        write(sensors.join("fan1_input"), "2100\n");
+        write(sensors.join("fan2_input"), "2300\n");
+        // A single sensor for both fans.
+        write(sensors.join("temp1_input"), "64500\n");
+        for fan in ["fan1", "fan2"] {
+            write(fan_control.join(format!("{fan}_pwm")), "100\n");
+            write(fan_control.join(format!("{fan}_pwm_enable")), "2\n");
+        }
+        write(
+            power_profiles.join("platform_profile_choices"),
+            "low-power balanced performance\n",
+        );
+        write(power_profiles.join("platform_profile"), "balanced\n");
+
+        let platform = PlatformHardware::with_paths(
+            PlatformInterface::Nb05,
+            sensors.clone(),
+            Some(fan_control.clone()),
+            Some(power_profiles.clone()),
+        );
+        assert_eq!(platform.get_number_fans(), 2);
+        assert_eq!(platform.get_fan_temperature(1).unwrap(), 64);
+        assert_eq!(platform.get_fan_speed_raw(0).unwrap(), 100);
+        assert!(matches!(
+            platform.get_fan_temperature(2),
+            Err(IoctlError::DevNotAvailable)
+        ));
+
+        platform.set_fan_speed_raw(1, 200).unwrap();
+        let read = |path: std::path::PathBuf| std::fs::read_to_string(path).unwrap();
+        assert_eq!(read(fan_control.join("fan2_pwm_enable")).trim(), "1");
+        assert_eq!(read(fan_control.join("fan2_pwm")).trim(), "200");
+        assert_eq!(platform.get_fan_duty_raw(1), Some(200));
+        platform.set_fans_auto().unwrap();
+        assert_eq!(read(fan_control.join("fan2_pwm_enable")).trim(), "2");
+        assert_eq!(platform.get_fan_duty_raw(1), None);
+
+        assert_eq!(
+            platform.get_default_odm_performance_profile().unwrap(),
+            "balanced"
+        );
+        platform.set_odm_performance_profile("performance").unwrap();
+        assert_eq!(
+            read(power_profiles.join("platform_profile")).trim(),
+            "performance"
+        );
+        assert!(matches!(
+            platform.set_odm_performance_profile("turbo"),
+            Err(IoctlError::InvalidArgs)
+        ));
+
+        // Without PWM controls, the fans are left to the firmware.
+        let platform = PlatformHardware::with_paths(
+            PlatformInterface::Nb04,
+            sensors,
+            Some(dir.join("missing")),
+            Some(power_profiles),
+        );
+        assert_eq!(platform.get_number_fans(), 0);
+        assert!(std::sync::Arc::new(platform).capabilities().fans.is_none());
+    }
+}
@@ -0,0 +1,290 @@
+//! The charge and status of the internal battery, as reported by the
+//! power supply class of the kernel.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use tailor_api::{BatteryHealth, BatteryState, BatteryStatus, CapacityUnit};
+
+use crate::attribute::CachedAttributes;
+
+const POWER_SUPPLY_PATH: &str = "/sys/class/power_supply";
+const START_THRESHOLD: &str = "charge_control_start_threshold";
+const END_THRESHOLD: &str = "charge_control_end_threshold";
+const CHARGE_BEHAVIOUR: &str = "charge_behaviour";
+
+/// The charge thresholds in percent, `None` if the battery doesn't have the threshold.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChargeThresholds {
+    /// Charging starts below this charge.
+    pub start: Option<u8>,
+    /// Charging stops at this charge.
+    pub end: Option<u8>,
+}
+
+/// What the firmware does while the charger is plugged in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChargeBehaviour {
+    /// Charge as usual.
+    Auto,
+    /// Power the device from the charger without charging.
+    InhibitCharge,
+    /// Power the device from the battery although the charger is plugged in.
+    ForceDischarge,
+}
+
+impl ChargeBehaviour {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::InhibitCharge => "inhibit-charge",
+            Self::ForceDischarge => "force-discharge",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Battery {
+    dir: PathBuf,
+    /// Shared by the clones, the daemon samples the battery periodically.
+    attributes: Arc<CachedAttributes>,
+}
+
+impl Battery {
+    /// Returns `None` if there's no internal battery.
+    pub fn new() -> Option<Self> {
+        Self::find(POWER_SUPPLY_PATH)
+    }
+
+    /// Peripherals like mice show up as batteries with the `Device` scope.
+    fn find(dir: impl AsRef<Path>) -> Option<Self> {
+        let mut supplies: Vec<PathBuf> = fs::read_dir(dir)
+            .ok()?
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .collect();
+        supplies.sort();
+        supplies
+            .into_iter()
+            .find(|dir| {
+                let read = |name: &str| fs::read_to_string(dir.join(name)).unwrap_or_default();
+                read("type").trim() == "Battery" && read("scope").trim() != "Device"
+            })
+            .map(|dir| Self {
+                dir,
+                attributes: Arc::default(),
+            })
+    }
+
+    /// The charge in percent.
+    pub fn capacity(&self) -> io::Result<u8> {
+        let capacity = self.read_u32("capacity")?;
+        Ok(capacity.min(100) as u8)
+    }
+
+    pub fn status(&self) -> io::Result<BatteryStatus> {
+        let status = self.attributes.read_string(self.dir.join("status"))?;
+        Ok(match status.as_str() {
+            "Charging" => BatteryStatus::Charging,
+            "Discharging" => BatteryStatus::Discharging,
+            "Not charging" => BatteryStatus::NotCharging,
+            "Full" => BatteryStatus::Full,
+            _ => BatteryStatus::Unknown,
+        })
+    }
+
+    /// The charge, charging rate and charge thresholds.
+    pub fn state(&self) -> io::Result<BatteryState> {
+        let threshold = |name: &str| {
+            self.read_u32(name)
+                .ok()
+                .map(|threshold| threshold.min(100) as u8)
+        };
+        Ok(BatteryState {
+            capacity: self.capacity()?,
+            status: self.status()?,
+            power_mw: self.power().map(|power| (power / 1000) as u32),
+            start_threshold: threshold(START_THRESHOLD),
+            end_threshold: threshold(END_THRESHOLD),
+        })
+    }
+
+    pub fn charge_thresholds(&self) -> ChargeThresholds {
+        let threshold = |name: &str| {
+            self.read_u32(name)
+                .ok()
+                .map(|threshold| threshold.min(100) as u8)
+        };
+        ChargeThresholds {
+            start: threshold(START_THRESHOLD),
+            end: threshold(END_THRESHOLD),
+        }
+    }
+
+    /// Write the thresholds that are set. The kernel rejects a start threshold
+    /// above the end threshold, so a higher end threshold is written first
+    /// and a lower one last.
+    pub fn set_charge_thresholds(&self, thresholds: ChargeThresholds) -> io::Result<()> {
+        let write = |name: &str, threshold: Option<u8>| match threshold {
+            Some(threshold) => fs::write(self.dir.join(name), threshold.to_string()),
+            None => Ok(()),
+        };
+        if thresholds.end >= self.charge_thresholds().end {
+            write(END_THRESHOLD, thresholds.end)?;
+            write(START_THRESHOLD, thresholds.start)
+        } else {
+            write(START_THRESHOLD, thresholds.start)?;
+            write(END_THRESHOLD, thresholds.end)
+        }
+    }
+
+    /// Whether the firmware supports a charge behaviour. The kernel
+    /// lists the supported behaviours with the active one in brackets.
+    pub fn supports_charge_behaviour(&self, behaviour: ChargeBehaviour) -> bool {
+        self.attributes
+            .read_string(self.dir.join(CHARGE_BEHAVIOUR))
+            .is_ok_and(|behaviours| {
+                behaviours
+                    .split_whitespace()
+                    .any(|name| name.trim_matches(['[', ']']) == behaviour.name())
+            })
+    }
+
+    pub fn set_charge_behaviour(&self, behaviour: ChargeBehaviour) -> io::Result<()> {
+        fs::write(self.dir.join(CHARGE_BEHAVIOUR), behaviour.name())
+    }
+
+    /// The charging or discharging rate in µW. Some batteries only report
+    /// their current and voltage, some report negative values while discharging.
+    fn power(&self) -> Option<u64> {
+        let read =
+            |name: &str| -> Option<i64> { self.attributes.read_value(self.dir.join(name)).ok() };
+        let power = read("power_now").or_else(|| {
+            let current = read("current_now")?;
+            let voltage = read("voltage_now")?;
+            Some(current * voltage / 1_000_000)
+        })?;
+        Some(power.unsigned_abs())
+    }
+
+    /// The kernel reports capacities in µWh or µAh, depending on the battery.
+    pub fn health(&self) -> io::Result<BatteryHealth> {
+        let (prefix, unit) = if self.dir.join("energy_full_design").exists() {
+            ("energy", CapacityUnit::MilliWattHours)
+        } else {
+            ("charge", CapacityUnit::MilliAmpHours)
+        };
+        let design_capacity = self.read_u32(&format!("{prefix}_full_design"))? / 1000;
+        let full_capacity = self.read_u32(&format!("{prefix}_full"))? / 1000;
+        // Batteries without a cycle counter report 0.
+        let cycle_count = self
+            .read_u32("cycle_count")
+            .ok()
+            .filter(|cycles| *cycles > 0);
+        Ok(BatteryHealth {
+            design_capacity,
+            full_capacity,
+            unit,
+            cycle_count,
+        })
+    }
+
+    fn read_u32(&self, name: &str) -> io::Result<u32> {
+        self.attributes.read_value(self.dir.join(name))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tailor_api::{BatteryStatus, CapacityUnit};
+
+    use super::{Battery, ChargeBehaviour, ChargeThresholds};
+
+    #[test]
+    fn internal_battery() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let supply = |name: &str, files: &[(&str, &str)]| {
+            let supply = dir.join(name);
+            std::fs::create_dir_all(&supply).unwrap();
+            for (file, content) in files {
+                std::fs::write(supply.join(file), content).unwrap();
+            }
+        };
+        supply("AC", &[("type", "Mains\n")]);
+        supply(
+            "hidpp_battery_0",
+            &[("type", "Battery\n"), ("scope", "Device\n")],
+        );
+        assert!(Battery::find(dir).is_none());
+
+        supply(
+            "BAT0",
+            &[
+                ("type", "Battery\n"),
+                ("capacity", "42\n"),
+                ("status", "Not charging\n"),
+            ],
+        );
+        let battery = Battery::find(dir).unwrap();
+        assert_eq!(battery.capacity().unwrap(), 42);
+        assert_eq!(battery.status().unwrap(), BatteryStatus::NotCharging);
+        assert!(battery.status().unwrap().is_plugged_in());
+
+        supply(
+            "BAT0",
+            &[
+                ("energy_full_design", "50000000\n"),
+                ("energy_full", "45000000\n"),
+                ("cycle_count", "0\n"),
+            ],
+        );
+        let health = battery.health().unwrap();
+        assert_eq!(health.design_capacity, 50000);
+        assert_eq!(health.full_capacity, 45000);
+        assert_eq!(health.unit, CapacityUnit::MilliWattHours);
+        assert_eq!(health.cycle_count, None);
+
+        supply(
+            "BAT0",
+            &[
+                ("current_now", "-1500000\n"),
+                ("voltage_now", "12000000\n"),
+                ("charge_control_end_threshold", "80\n"),
+            ],
+        );
+        let state = battery.state().unwrap();
+        assert_eq!(state.power_mw, Some(18000));
+        assert_eq!(state.start_threshold, None);
+        assert_eq!(state.end_threshold, Some(80));
+
+        supply(
+            "BAT0",
+            &[
+                ("charge_control_start_threshold", "40\n"),
+                ("charge_behaviour", "[auto] force-discharge\n"),
+            ],
+        );
+        let lifted = ChargeThresholds {
+            start: Some(99),
+            end: Some(100),
+        };
+        battery.set_charge_thresholds(lifted).unwrap();
+        assert_eq!(battery.charge_thresholds(), lifted);
+        let original = ChargeThresholds {
+            start: Some(40),
+            end: Some(80),
+        };
+        battery.set_charge_thresholds(original).unwrap();
+        assert_eq!(battery.charge_thresholds(), original);
+
+        assert!(battery.supports_charge_behaviour(ChargeBehaviour::Auto));
+        assert!(battery.supports_charge_behaviour(ChargeBehaviour::ForceDischarge));
+        assert!(!battery.supports_charge_behaviour(ChargeBehaviour::InhibitCharge));
+        battery
+            .set_charge_behaviour(ChargeBehaviour::ForceDischarge)
+            .unwrap();
+    }
+}
@@ -0,0 +1,288 @@
+//! Runtime power management of NVMe drives, SATA links and the audio codec.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use tailor_api::{HardwareWrite, PowerTuning, SataLinkPower};
+
+use crate::attribute::planned_write;
+
+const NVME_PATH: &str = "/sys/class/nvme";
+const SCSI_HOST_PATH: &str = "/sys/class/scsi_host";
+const AUDIO_POWER_SAVE_PATH: &str = "/sys/module/snd_hda_intel/parameters/power_save";
+
+/// Changing the latency tolerance of an NVMe controller updates its APST table.
+const NVME_LATENCY: &str = "power/pm_qos_latency_tolerance_us";
+const SATA_LINK_POWER: &str = "link_power_management_policy";
+
+/// The devices whose power management can be tuned.
+#[derive(Debug, Clone)]
+pub struct PowerTunables {
+    nvme_controllers: Vec<PathBuf>,
+    sata_hosts: Vec<PathBuf>,
+    audio_power_save: Option<PathBuf>,
+}
+
+impl PowerTunables {
+    pub fn new() -> Self {
+        Self::with_paths(NVME_PATH, SCSI_HOST_PATH, AUDIO_POWER_SAVE_PATH)
+    }
+
+    fn with_paths(
+        nvme_dir: impl AsRef<Path>,
+        scsi_host_dir: impl AsRef<Path>,
+        audio_power_save: impl AsRef<Path>,
+    ) -> Self {
+        let audio_power_save = audio_power_save.as_ref();
+        Self {
+            nvme_controllers: devices(nvme_dir.as_ref(), NVME_LATENCY),
+            // Hosts without link power management, e.g. USB storage, lack the file.
+            sata_hosts: devices(scsi_host_dir.as_ref(), SATA_LINK_POWER),
+            audio_power_save: audio_power_save
+                .exists()
+                .then(|| audio_power_save.to_owned()),
+        }
+    }
+
+    /// The current settings. Devices of the same kind are assumed to share
+    /// their settings, so the first one is read.
+    pub fn get(&self) -> io::Result<PowerTuning> {
+        let nvme_max_latency_us = match self.nvme_controllers.first() {
+            // `auto` and `any` mean there's no explicit tolerance.
+            Some(dir) => read(&dir.join(NVME_LATENCY))?.parse().ok(),
+            None => None,
+        };
+        let sata_link_power = match self.sata_hosts.first() {
+            Some(dir) => SataLinkPower::from_name(&read(&dir.join(SATA_LINK_POWER))?),
+            None => None,
+        };
+        let audio_power_save_secs = match &self.audio_power_save {
+            Some(path) => read(path)?.parse().ok(),
+            None => None,
+        };
+        Ok(PowerTuning {
+            nvme_max_latency_us,
+            sata_link_power,
+            audio_power_save_secs,
+        })
+    }
+
+    /// The raw values of all attributes, including values that
+    /// [`Self::get`] can't represent, like `auto` NVMe latencies.
+    pub fn save(&self) -> io::Result<SavedPowerSettings> {
+        let save = |dirs: &[PathBuf], name: &str| {
+            dirs.iter()
+                .map(|dir| Ok((dir.join(name), read(&dir.join(name))?)))
+                .collect::<io::Result<Vec<_>>>()
+        };
+        Ok(SavedPowerSettings {
+            nvme: save(&self.nvme_controllers, NVME_LATENCY)?,
+            sata: save(&self.sata_hosts, SATA_LINK_POWER)?,
+            audio: match &self.audio_power_save {
+                Some(path) => vec![(path.clone(), read(path)?)],
+                None => Vec::new(),
+            },
+        })
+    }
+
+    /// Apply the settings that are `Some` to all devices of their kind.
+    pub fn set(&self, tuning: &PowerTuning) -> io::Result<()> {
+        if let Some(latency_us) = tuning.nvme_max_latency_us {
+            for dir in &self.nvme_controllers {
+                fs::write(dir.join(NVME_LATENCY), latency_us.to_string())?;
+            }
+        }
+        if let Some(policy) = tuning.sata_link_power {
+            for dir in &self.sata_hosts {
+                fs::write(dir.join(SATA_LINK_POWER), policy.name())?;
+            }
+        }
+        if let (Some(secs), Some(path)) = (tuning.audio_power_save_secs, &self.audio_power_save) {
+            fs::write(path, secs.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+impl PowerTunables {
+    /// The writes of [`Self::set`] that change a value, without applying them.
+    pub fn preview(&self, tuning: &PowerTuning) -> Vec<HardwareWrite> {
+        let mut writes = Vec::new();
+        if let Some(latency_us) = tuning.nvme_max_latency_us {
+            for dir in &self.nvme_controllers {
+                writes.extend(planned_write(dir, NVME_LATENCY, latency_us));
+            }
+        }
+        if let Some(policy) = tuning.sata_link_power {
+            for dir in &self.sata_hosts {
+                writes.extend(planned_write(dir, SATA_LINK_POWER, policy.name()));
+            }
+        }
+        if let (Some(secs), Some(path)) = (tuning.audio_power_save_secs, &self.audio_power_save) {
+            if let (Some(dir), Some(name)) = (path.parent(), path.file_name()) {
+                writes.extend(planned_write(dir, &name.to_string_lossy(), secs));
+            }
+        }
+        writes
+    }
+}
+
+/// The raw values of the attributes before a profile changed them.
+#[derive(Debug, Clone)]
+pub struct SavedPowerSettings {
+    nvme: Vec<(PathBuf, String)>,
+    sata: Vec<(PathBuf, String)>,
+    audio: Vec<(PathBuf, String)>,
+}
+
+impl SavedPowerSettings {
+    /// Write back the values of the settings that `tuning` leaves out, verbatim.
+    pub fn restore(&self, tuning: &PowerTuning) -> io::Result<()> {
+        for (path, value) in self.restored(tuning) {
+            fs::write(path, value)?;
+        }
+        Ok(())
+    }
+
+    /// The writes of [`Self::restore`] that change a value, without applying them.
+    pub fn preview(&self, tuning: &PowerTuning) -> Vec<HardwareWrite> {
+        self.restored(tuning)
+            .filter_map(|(path, value)| {
+                let (dir, name) = (path.parent()?, path.file_name()?);
+                planned_write(dir, &name.to_string_lossy(), value)
+            })
+            .collect()
+    }
+
+    fn restored<'a>(
+        &'a self,
+        tuning: &PowerTuning,
+    ) -> impl Iterator<Item = &'a (PathBuf, String)> + 'a {
+        let restore = |values: &'a [(PathBuf, String)], set: bool| {
+            if set {
+                &[][..]
+            } else {
+                values
+            }
+        };
+        restore(&self.nvme, tuning.nvme_max_latency_us.is_some())
+            .iter()
+            .chain(restore(&self.sata, tuning.sata_link_power.is_some()))
+            .chain(restore(&self.audio, tuning.audio_power_save_secs.is_some()))
+    }
+}
+
+impl Default for PowerTunables {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The devices in `dir` that have the attribute `file`.
+fn devices(dir: &Path, file: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut devices: Vec<PathBuf> = entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|dir| dir.join(file).exists())
+        .collect();
+    devices.sort();
+    devices
+}
+
+fn read(path: &Path) -> io::Result<String> {
+    Ok(fs::read_to_string(path)?.trim().to_owned())
+}
+
+#[cfg(test)]
+mod test {
+    use tailor_api::{PowerTuning, SataLinkPower};
+
+    use super::PowerTunables;
+
+    fn read(path: &std::path::Path) -> String {
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn tunables() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let write = |path: &str, content: &str| {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write("nvme/nvme0/power/pm_qos_latency_tolerance_us", "auto\n");
+        write(
+            "scsi_host/host0/link_power_management_policy",
+            "max_performance\n",
+        );
+        write("scsi_host/host1/proc_name", "usb-storage\n");
+        write("power_save", "0\n");
+
+        let tunables = PowerTunables::with_paths(
+            dir.join("nvme"),
+            dir.join("scsi_host"),
+            dir.join("power_save"),
+        );
+        assert_eq!(
+            tunables.get().unwrap(),
+            PowerTuning {
+                nvme_max_latency_us: None,
+                sata_link_power: Some(SataLinkPower::MaxPerformance),
+                audio_power_save_secs: Some(0),
+            }
+        );
+
+        let saved = tunables.save().unwrap();
+
+        let tuning = PowerTuning {
+            nvme_max_latency_us: Some(5500),
+            sata_link_power: Some(SataLinkPower::MedPowerWithDipm),
+            audio_power_save_secs: None,
+        };
+        let writes = tunables.preview(&tuning);
+        assert_eq!(writes.len(), 2);
+        assert_eq!(writes[0].old.as_deref(), Some("auto"));
+        assert_eq!(writes[0].new, "5500");
+        assert_eq!(writes[1].attribute, "link_power_management_policy");
+        assert_eq!(writes[1].new, "med_power_with_dipm");
+
+        tunables.set(&tuning).unwrap();
+        assert!(tunables.preview(&tuning).is_empty());
+        assert_eq!(
+            tunables.get().unwrap(),
+            PowerTuning {
+                audio_power_save_secs: Some(0),
+                ..tuning
+            }
+        );
+
+        // Values that `get` can't represent are restored verbatim.
+        assert_eq!(saved.preview(&PowerTuning::default()).len(), 2);
+        saved
+            .restore(&PowerTuning {
+                sata_link_power: Some(SataLinkPower::MinPower),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(
+            read(&dir.join("nvme/nvme0/power/pm_qos_latency_tolerance_us")),
+            "auto"
+        );
+        assert_eq!(
+            read(&dir.join("scsi_host/host0/link_power_management_policy")),
+            "med_power_with_dipm"
+        );
+        saved.restore(&PowerTuning::default()).unwrap();
+        assert!(saved.preview(&PowerTuning::default()).is_empty());
+        assert_eq!(
+            read(&dir.join("scsi_host/host0/link_power_management_policy")),
+            "max_performance"
+        );
+    }
+}